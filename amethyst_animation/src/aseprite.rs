@@ -0,0 +1,241 @@
+use std::fmt;
+
+use minterpolate::InterpolationFunction;
+use serde::{
+    de::{Deserializer, MapAccess, Visitor},
+    Deserialize,
+};
+
+use amethyst_error::{format_err, Error, ResultExt};
+use amethyst_renderer::SpriteRender;
+
+use crate::{
+    prefab::{AnimationPrefab, AnimationSetPrefab},
+    resources::Sampler,
+    sprite::{SpriteRenderChannel, SpriteRenderPrimitive},
+};
+
+#[derive(Deserialize)]
+struct AsepriteFrame {
+    /// Duration of the frame in milliseconds
+    duration: u32,
+}
+
+/// Frames of the hash variant, in the order of the file.
+struct AsepriteFrameMap(Vec<AsepriteFrame>);
+
+impl<'de> Deserialize<'de> for AsepriteFrameMap {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct FrameMapVisitor;
+
+        impl<'de> Visitor<'de> for FrameMapVisitor {
+            type Value = AsepriteFrameMap;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("a map of frames")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<AsepriteFrameMap, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut frames = Vec::with_capacity(map.size_hint().unwrap_or(0));
+                while let Some((_, frame)) = map.next_entry::<String, AsepriteFrame>()? {
+                    frames.push(frame);
+                }
+                Ok(AsepriteFrameMap(frames))
+            }
+        }
+
+        deserializer.deserialize_map(FrameMapVisitor)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AsepriteFrames {
+    Array(Vec<AsepriteFrame>),
+    Hash(AsepriteFrameMap),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum AsepriteDirection {
+    Forward,
+    Reverse,
+    Pingpong,
+}
+
+#[derive(Deserialize)]
+struct AsepriteTag {
+    name: String,
+    from: usize,
+    to: usize,
+    direction: AsepriteDirection,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AsepriteMeta {
+    #[serde(default)]
+    frame_tags: Vec<AsepriteTag>,
+}
+
+#[derive(Deserialize)]
+struct AsepriteSheet {
+    frames: AsepriteFrames,
+    meta: AsepriteMeta,
+}
+
+/// Sprite animations of an Aseprite JSON export, one for each frame tag.
+///
+/// The sprite sheet of the export is loaded with the `TexturePackerFormat` of
+/// `amethyst_renderer`, the JSON export of Aseprite being compatible with the one of
+/// TexturePacker. The sprite indices of the animations are the indices of the frames in the
+/// export, and each frame is shown for its duration. The forward, reverse and ping-pong
+/// directions of the tags are supported.
+///
+/// ```rust,no_run
+/// # use amethyst_animation::AsepriteAnimations;
+/// # fn load() -> Result<(), amethyst_error::Error> {
+/// let json = std::fs::read("assets/texture/hero.json")?;
+/// let animation_set = AsepriteAnimations::from_json(&json)?.animation_set_prefab();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct AsepriteAnimations {
+    /// Name of the frame tag and sampler of the `SpriteIndex` channel of each animation
+    pub animations: Vec<(String, Sampler<SpriteRenderPrimitive>)>,
+}
+
+impl AsepriteAnimations {
+    /// Parses the animations from an Aseprite JSON export, in the hash or array variant.
+    pub fn from_json(bytes: &[u8]) -> Result<Self, Error> {
+        let sheet: AsepriteSheet = serde_json::from_slice(bytes)
+            .with_context(|_| format_err!("Failed to parse Aseprite JSON"))?;
+        let frames = match sheet.frames {
+            AsepriteFrames::Array(frames) => frames,
+            AsepriteFrames::Hash(AsepriteFrameMap(frames)) => frames,
+        };
+
+        let animations = sheet
+            .meta
+            .frame_tags
+            .into_iter()
+            .map(|tag| {
+                if tag.from > tag.to || tag.to >= frames.len() {
+                    return Err(format_err!(
+                        "Frame tag {} uses frames {} to {} of {} frames",
+                        tag.name,
+                        tag.from,
+                        tag.to,
+                        frames.len()
+                    ));
+                }
+                let indices: Vec<usize> = match tag.direction {
+                    AsepriteDirection::Forward => (tag.from..=tag.to).collect(),
+                    AsepriteDirection::Reverse => (tag.from..=tag.to).rev().collect(),
+                    AsepriteDirection::Pingpong => (tag.from..=tag.to)
+                        .chain((tag.from + 1..tag.to).rev())
+                        .collect(),
+                };
+                Ok((tag.name, sampler(&indices, &frames)))
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(AsepriteAnimations { animations })
+    }
+
+    /// Creates the `AnimationSetPrefab` of the animations, with the names of the frame tags as
+    /// ids.
+    pub fn animation_set_prefab(&self) -> AnimationSetPrefab<String, SpriteRender> {
+        AnimationSetPrefab {
+            animations: self
+                .animations
+                .iter()
+                .map(|(name, sampler)| {
+                    let mut animation = AnimationPrefab::default();
+                    animation.samplers =
+                        vec![(0, SpriteRenderChannel::SpriteIndex, sampler.clone())];
+                    (name.clone(), animation)
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Creates the sampler showing each frame for its duration.
+///
+/// The last frame is repeated at the end of the animation, so it is shown for its duration too.
+fn sampler(indices: &[usize], frames: &[AsepriteFrame]) -> Sampler<SpriteRenderPrimitive> {
+    let mut input = Vec::with_capacity(indices.len() + 1);
+    let mut output = Vec::with_capacity(indices.len() + 1);
+    let mut time_ms = 0;
+    for &index in indices {
+        input.push(time_ms as f32 / 1000.0);
+        output.push(SpriteRenderPrimitive::SpriteIndex(index));
+        time_ms += frames[index].duration;
+    }
+    input.push(time_ms as f32 / 1000.0);
+    output.push(SpriteRenderPrimitive::SpriteIndex(
+        indices[indices.len() - 1],
+    ));
+    Sampler {
+        input,
+        output,
+        function: InterpolationFunction::Step,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JSON: &str = r#"{
+        "frames": {
+            "hero 0.aseprite": {"frame": {"x": 0, "y": 0, "w": 8, "h": 8}, "duration": 100},
+            "hero 1.aseprite": {"frame": {"x": 8, "y": 0, "w": 8, "h": 8}, "duration": 200},
+            "hero 2.aseprite": {"frame": {"x": 16, "y": 0, "w": 8, "h": 8}, "duration": 100}
+        },
+        "meta": {
+            "size": {"w": 24, "h": 8},
+            "frameTags": [
+                {"name": "walk", "from": 0, "to": 2, "direction": "forward"},
+                {"name": "bounce", "from": 0, "to": 2, "direction": "pingpong"}
+            ]
+        }
+    }"#;
+
+    fn indices(sampler: &Sampler<SpriteRenderPrimitive>) -> Vec<usize> {
+        sampler
+            .output
+            .iter()
+            .map(|primitive| match primitive {
+                SpriteRenderPrimitive::SpriteIndex(index) => *index,
+                SpriteRenderPrimitive::SpriteSheet(_) => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn frame_tags_become_animations() {
+        let animations = AsepriteAnimations::from_json(JSON.as_bytes()).unwrap();
+        let (ref name, ref walk) = animations.animations[0];
+        assert_eq!(name, "walk");
+        assert_eq!(walk.input, vec![0.0, 0.1, 0.3, 0.4]);
+        assert_eq!(indices(walk), vec![0, 1, 2, 2]);
+
+        let (ref name, ref bounce) = animations.animations[1];
+        assert_eq!(name, "bounce");
+        assert_eq!(indices(bounce), vec![0, 1, 2, 1, 1]);
+    }
+
+    #[test]
+    fn tag_out_of_frames_fails() {
+        let json = JSON.replace(r#""from": 0, "to": 2"#, r#""from": 0, "to": 3"#);
+        assert!(AsepriteAnimations::from_json(json.as_bytes()).is_err());
+    }
+}
@@ -1,10 +1,13 @@
 use std::{hash::Hash, marker};
 
+use serde::{de::DeserializeOwned, Serialize};
+
 use crate::{
     resources::AnimationSampling,
     skinning::VertexSkinningSystem,
     systems::{
-        AnimationControlSystem, AnimationProcessor, SamplerInterpolationSystem, SamplerProcessor,
+        AnimationClipProcessor, AnimationClipSystem, AnimationControlSystem, AnimationProcessor,
+        SamplerInterpolationSystem, SamplerProcessor,
     },
 };
 use amethyst_error::Error;
@@ -154,3 +157,55 @@ where
             .build(builder)
     }
 }
+
+/// Bundle for loading animations from `AnimationClip` assets.
+///
+/// Will add `AnimationClipProcessor<T>` and `AnimationClipSystem<I, T>` with the given name.
+/// Should be added before `AnimationBundle`, and the `AnimationControlSystem` should depend on the
+/// `AnimationClipSystem`.
+///
+/// ### Type parameters:
+///
+/// - `I`: identifier type of the animations
+/// - `T`: the component type that the animation should be applied to
+#[derive(Default)]
+pub struct AnimationClipBundle<'a, I, T> {
+    name: &'a str,
+    dep: &'a [&'a str],
+    m: marker::PhantomData<(I, T)>,
+}
+
+impl<'a, I, T> AnimationClipBundle<'a, I, T> {
+    /// Create a new animation clip bundle
+    ///
+    /// ### Parameters:
+    ///
+    /// - `name`: name of the `AnimationClipSystem`
+    pub fn new(name: &'a str) -> Self {
+        Self {
+            name,
+            dep: &[],
+            m: marker::PhantomData,
+        }
+    }
+
+    /// Set dependencies for the `AnimationClipSystem`
+    pub fn with_dep(mut self, dep: &'a [&'a str]) -> Self {
+        self.dep = dep;
+        self
+    }
+}
+
+impl<'a, 'b, 'c, I, T> SystemBundle<'a, 'b> for AnimationClipBundle<'c, I, T>
+where
+    I: Clone + Eq + Hash + Send + Sync + 'static,
+    T: AnimationSampling,
+    T::Channel: DeserializeOwned + Serialize,
+    T::Primitive: DeserializeOwned + Serialize,
+{
+    fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<(), Error> {
+        builder.add(AnimationClipProcessor::<T>::new(), "", &[]);
+        builder.add(AnimationClipSystem::<I, T>::new(), self.name, self.dep);
+        Ok(())
+    }
+}
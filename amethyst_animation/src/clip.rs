@@ -0,0 +1,306 @@
+use std::hash::Hash;
+
+use derivative::Derivative;
+use fnv::FnvHashMap;
+use minterpolate::InterpolationFunction;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use amethyst_assets::{Asset, Handle};
+use amethyst_core::specs::prelude::{Component, DenseVecStorage, VecStorage};
+use amethyst_error::{format_err, Error};
+
+use crate::resources::{Animation, AnimationSampling, Sampler};
+
+/// The node a channel of an `AnimationClip` is applied to.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ClipTarget {
+    /// Node index in the `AnimationHierarchy`, `0` is the entity the animation is played on.
+    Node(usize),
+    /// Name of the joint or entity, as given by its `Named` component.
+    Name(String),
+}
+
+/// Interpolation used between the keyframes of a clip channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClipInterpolation {
+    /// Linear interpolation
+    Linear,
+    /// Spherical linear interpolation, use for rotations
+    SphericalLinear,
+    /// No interpolation, the value of the previous keyframe is used
+    Step,
+}
+
+impl Default for ClipInterpolation {
+    fn default() -> Self {
+        ClipInterpolation::Linear
+    }
+}
+
+/// A single keyframe of a clip channel.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe<P> {
+    /// Time of the keyframe in seconds
+    pub time: f32,
+    /// Value at the keyframe
+    pub value: P,
+}
+
+/// A single channel of an `AnimationClip`.
+///
+/// ### Type parameters
+///
+/// - `T`: The animatable `Component`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ClipChannel<T>
+where
+    T: AnimationSampling,
+    T::Channel: DeserializeOwned + Serialize,
+    T::Primitive: DeserializeOwned + Serialize,
+{
+    /// The node that is animated
+    pub target: ClipTarget,
+    /// The channel of the component that is animated
+    pub channel: T::Channel,
+    /// How to interpolate between keyframes
+    #[serde(default)]
+    pub interpolation: ClipInterpolation,
+    /// Keyframes, ordered by time
+    pub keyframes: Vec<Keyframe<T::Primitive>>,
+}
+
+impl<T> ClipChannel<T>
+where
+    T: AnimationSampling,
+    T::Channel: DeserializeOwned + Serialize,
+    T::Primitive: DeserializeOwned + Serialize,
+{
+    /// Create a `Sampler` from the keyframes of this channel.
+    pub fn sampler(&self) -> Result<Sampler<T::Primitive>, Error> {
+        if self.keyframes.is_empty() {
+            return Err(format_err!(
+                "Animation clip channel {:?} for {:?} has no keyframes",
+                self.channel,
+                self.target
+            ));
+        }
+        if self
+            .keyframes
+            .windows(2)
+            .any(|pair| pair[0].time > pair[1].time)
+        {
+            return Err(format_err!(
+                "Keyframes of animation clip channel {:?} for {:?} are not ordered by time",
+                self.channel,
+                self.target
+            ));
+        }
+        Ok(Sampler {
+            input: self.keyframes.iter().map(|k| k.time).collect(),
+            output: self.keyframes.iter().map(|k| k.value.clone()).collect(),
+            function: match self.interpolation {
+                ClipInterpolation::Linear => InterpolationFunction::Linear,
+                ClipInterpolation::SphericalLinear => InterpolationFunction::SphericalLinear,
+                ClipInterpolation::Step => InterpolationFunction::Step,
+            },
+        })
+    }
+}
+
+/// Data of an `AnimationClip`, as authored in an asset file.
+///
+/// Can be loaded with `RonFormat`, for example:
+///
+/// ```text,ignore
+/// (
+///     channels: [
+///         (
+///             target: Name("head"),
+///             channel: Translation,
+///             interpolation: Linear,
+///             keyframes: [
+///                 (time: 0.0, value: Vec3((0.0, 1.0, 0.0))),
+///                 (time: 0.5, value: Vec3((0.0, 1.2, 0.0))),
+///                 (time: 1.0, value: Vec3((0.0, 1.0, 0.0))),
+///             ],
+///         ),
+///     ],
+/// )
+/// ```
+///
+/// ### Type parameters
+///
+/// - `T`: The animatable `Component`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AnimationClipData<T>
+where
+    T: AnimationSampling,
+    T::Channel: DeserializeOwned + Serialize,
+    T::Primitive: DeserializeOwned + Serialize,
+{
+    /// All channels of the clip
+    pub channels: Vec<ClipChannel<T>>,
+}
+
+/// An animation clip loaded from an asset file.
+///
+/// In contrast to `Animation`, channels target nodes by name, so a clip is resolved to an
+/// `Animation` for a specific entity by the `AnimationClipSystem`. Clips are processed by the
+/// `AnimationClipProcessor`, which also handles hot reloading.
+///
+/// ### Type parameters
+///
+/// - `T`: The animatable `Component`
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""), Debug(bound = ""))]
+pub struct AnimationClip<T>
+where
+    T: AnimationSampling,
+{
+    /// Target, channel and sampler of all channels in the clip
+    pub channels: Vec<(ClipTarget, T::Channel, Handle<Sampler<T::Primitive>>)>,
+    pub(crate) version: u64,
+}
+
+impl<T> AnimationClip<T>
+where
+    T: AnimationSampling,
+{
+    /// Version of the clip, changes every time the clip is reloaded.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Create an `Animation` from the clip.
+    ///
+    /// ### Parameters:
+    ///
+    /// - `names`: mapping from node name to node index in the `AnimationHierarchy`
+    pub fn to_animation(&self, names: &FnvHashMap<String, usize>) -> Result<Animation<T>, Error> {
+        let mut animation = Animation::new();
+        for (target, channel, sampler) in &self.channels {
+            let node_index = match target {
+                ClipTarget::Node(index) => *index,
+                ClipTarget::Name(name) => *names
+                    .get(name)
+                    .ok_or_else(|| format_err!("Animation clip targets unknown node {:?}", name))?,
+            };
+            animation.add(node_index, channel.clone(), sampler.clone());
+        }
+        Ok(animation)
+    }
+}
+
+impl<T> Asset for AnimationClip<T>
+where
+    T: AnimationSampling,
+    T::Channel: DeserializeOwned + Serialize,
+    T::Primitive: DeserializeOwned + Serialize,
+{
+    const NAME: &'static str = "animation::AnimationClip";
+    type Data = AnimationClipData<T>;
+    type HandleStorage = VecStorage<Handle<Self>>;
+}
+
+/// Attaches to an entity, with links to the animation clips that should be available on it.
+///
+/// The `AnimationClipSystem` resolves each loaded clip into an `Animation` and inserts it into
+/// the `AnimationSet` of the entity, using the same id. When a clip is reloaded, the animation is
+/// rebuilt.
+///
+/// ### Type parameters:
+///
+/// - `I`: identifier type of the animations
+/// - `T`: the component type that the animation should be applied to
+pub struct AnimationClipSet<I, T>
+where
+    I: Eq + Hash,
+    T: AnimationSampling,
+{
+    /// The mapping between `I` and the clip handles.
+    pub clips: FnvHashMap<I, Handle<AnimationClip<T>>>,
+    pub(crate) resolved: FnvHashMap<I, (u32, u64)>,
+}
+
+impl<I, T> Default for AnimationClipSet<I, T>
+where
+    I: Eq + Hash,
+    T: AnimationSampling,
+{
+    fn default() -> Self {
+        AnimationClipSet {
+            clips: FnvHashMap::default(),
+            resolved: FnvHashMap::default(),
+        }
+    }
+}
+
+impl<I, T> AnimationClipSet<I, T>
+where
+    I: Eq + Hash,
+    T: AnimationSampling,
+{
+    /// Create
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert a clip in the set
+    pub fn insert(&mut self, id: I, handle: Handle<AnimationClip<T>>) {
+        self.clips.insert(id, handle);
+    }
+
+    /// Retrieve a clip handle from the set
+    pub fn get(&self, id: &I) -> Option<&Handle<AnimationClip<T>>> {
+        self.clips.get(id)
+    }
+}
+
+impl<I, T> Component for AnimationClipSet<I, T>
+where
+    I: Eq + Hash + Send + Sync + 'static,
+    T: AnimationSampling,
+{
+    type Storage = DenseVecStorage<Self>;
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::Transform;
+
+    use crate::{transform::TransformChannel, SamplerPrimitive};
+
+    use super::*;
+
+    fn channel(keyframes: Vec<f32>) -> ClipChannel<Transform> {
+        ClipChannel {
+            target: ClipTarget::Node(0),
+            channel: TransformChannel::Translation,
+            interpolation: ClipInterpolation::Step,
+            keyframes: keyframes
+                .into_iter()
+                .map(|time| Keyframe {
+                    time,
+                    value: SamplerPrimitive::Vec3([time; 3]),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn clip_channel_creates_sampler() {
+        let sampler = channel(vec![0.0, 0.5, 1.0]).sampler().unwrap();
+        assert_eq!(sampler.input, vec![0.0, 0.5, 1.0]);
+        assert_eq!(sampler.output.len(), 3);
+        match sampler.function {
+            InterpolationFunction::Step => {}
+            _ => panic!("Expected step interpolation"),
+        }
+    }
+
+    #[test]
+    fn clip_channel_rejects_invalid_keyframes() {
+        assert!(channel(vec![]).sampler().is_err());
+        assert!(channel(vec![1.0, 0.5]).sampler().is_err());
+    }
+}
@@ -50,7 +50,12 @@
 pub use minterpolate::{InterpolationFunction, InterpolationPrimitive};
 
 pub use self::{
-    bundle::{AnimationBundle, SamplingBundle, VertexSkinningBundle},
+    aseprite::AsepriteAnimations,
+    bundle::{AnimationBundle, AnimationClipBundle, SamplingBundle, VertexSkinningBundle},
+    clip::{
+        AnimationClip, AnimationClipData, AnimationClipSet, ClipChannel, ClipInterpolation,
+        ClipTarget, Keyframe,
+    },
     material::{MaterialChannel, MaterialPrimitive},
     prefab::{AnimatablePrefab, AnimationHierarchyPrefab, AnimationPrefab, AnimationSetPrefab},
     resources::{
@@ -61,13 +66,16 @@ pub use self::{
     skinning::{Joint, JointPrefab, Skin, SkinPrefab, SkinnablePrefab, VertexSkinningSystem},
     sprite::{SpriteRenderChannel, SpriteRenderPrimitive},
     systems::{
-        AnimationControlSystem, AnimationProcessor, SamplerInterpolationSystem, SamplerProcessor,
+        AnimationClipProcessor, AnimationClipSystem, AnimationControlSystem, AnimationProcessor,
+        SamplerInterpolationSystem, SamplerProcessor,
     },
     transform::TransformChannel,
     util::{get_animation_set, SamplerPrimitive},
 };
 
+mod aseprite;
 mod bundle;
+mod clip;
 mod material;
 mod prefab;
 mod resources;
@@ -0,0 +1,194 @@
+use std::{hash::Hash, marker};
+
+use fnv::FnvHashMap;
+use log::error;
+use serde::{de::DeserializeOwned, Serialize};
+
+use amethyst_assets::{AssetStorage, HotReloadStrategy, Loader, ProcessingState};
+use amethyst_core::{
+    specs::prelude::{Entities, Join, Read, ReadExpect, ReadStorage, System, Write, WriteStorage},
+    timing::Time,
+    ArcThreadPool, Named,
+};
+
+use crate::{
+    clip::{AnimationClip, AnimationClipData, AnimationClipSet},
+    resources::{Animation, AnimationHierarchy, AnimationSampling, AnimationSet, Sampler},
+};
+
+/// Asset storage processor for `AnimationClip`.
+///
+/// Creates the `Sampler`s of each clip channel, and bumps the version of the clip every time it
+/// is (re)loaded, so that `AnimationClipSystem` can rebuild the animations.
+///
+/// ### Type parameters:
+///
+/// - `T`: the component type that the animation should be applied to
+pub struct AnimationClipProcessor<T> {
+    m: marker::PhantomData<T>,
+    next_version: u64,
+}
+
+impl<T> AnimationClipProcessor<T> {
+    /// Creates a new `AnimationClipProcessor`
+    pub fn new() -> Self {
+        AnimationClipProcessor {
+            m: marker::PhantomData,
+            next_version: 1,
+        }
+    }
+}
+
+impl<T> Default for AnimationClipProcessor<T> {
+    fn default() -> Self {
+        AnimationClipProcessor::new()
+    }
+}
+
+impl<'a, T> System<'a> for AnimationClipProcessor<T>
+where
+    T: AnimationSampling,
+    T::Channel: DeserializeOwned + Serialize,
+    T::Primitive: DeserializeOwned + Serialize,
+{
+    type SystemData = (
+        Write<'a, AssetStorage<AnimationClip<T>>>,
+        ReadExpect<'a, Loader>,
+        Read<'a, AssetStorage<Sampler<T::Primitive>>>,
+        ReadExpect<'a, ArcThreadPool>,
+        Read<'a, Time>,
+        Option<Read<'a, HotReloadStrategy>>,
+    );
+
+    fn run(
+        &mut self,
+        (mut clip_storage, loader, sampler_storage, pool, time, strategy): Self::SystemData,
+    ) {
+        use std::ops::Deref;
+
+        let next_version = &mut self.next_version;
+        clip_storage.process(
+            |data: AnimationClipData<T>| {
+                let mut channels = Vec::with_capacity(data.channels.len());
+                for channel in &data.channels {
+                    let sampler = loader.load_from_data(channel.sampler()?, (), &sampler_storage);
+                    channels.push((channel.target.clone(), channel.channel.clone(), sampler));
+                }
+                let version = *next_version;
+                *next_version += 1;
+                Ok(ProcessingState::Loaded(AnimationClip { channels, version }))
+            },
+            time.frame_number(),
+            &**pool,
+            strategy.as_ref().map(Deref::deref),
+        );
+    }
+}
+
+/// Resolves the clips of all `AnimationClipSet`s into `Animation`s, and inserts them into the
+/// `AnimationSet` of the same entity.
+///
+/// Clip channels that target a node by name are matched against the `Named` component of the
+/// entities in the `AnimationHierarchy`. The entity itself is node `0` if it is not part of the
+/// hierarchy. Animations are rebuilt when a clip is reloaded.
+///
+/// ### Type parameters:
+///
+/// - `I`: identifier type of the animations
+/// - `T`: the component type that the animation should be applied to
+#[derive(Default)]
+pub struct AnimationClipSystem<I, T> {
+    m: marker::PhantomData<(I, T)>,
+}
+
+impl<I, T> AnimationClipSystem<I, T> {
+    /// Creates a new `AnimationClipSystem`
+    pub fn new() -> Self {
+        AnimationClipSystem {
+            m: marker::PhantomData,
+        }
+    }
+}
+
+impl<'a, I, T> System<'a> for AnimationClipSystem<I, T>
+where
+    I: Clone + Eq + Hash + Send + Sync + 'static,
+    T: AnimationSampling,
+    T::Channel: DeserializeOwned + Serialize,
+    T::Primitive: DeserializeOwned + Serialize,
+{
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, AnimationClipSet<I, T>>,
+        Read<'a, AssetStorage<AnimationClip<T>>>,
+        ReadExpect<'a, Loader>,
+        Read<'a, AssetStorage<Animation<T>>>,
+        WriteStorage<'a, AnimationSet<I, T>>,
+        ReadStorage<'a, AnimationHierarchy<T>>,
+        ReadStorage<'a, Named>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut clip_sets,
+            clip_storage,
+            loader,
+            animation_storage,
+            mut animation_sets,
+            hierarchies,
+            names,
+        ): Self::SystemData,
+    ) {
+        for (entity, clip_set) in (&*entities, &mut clip_sets).join() {
+            let AnimationClipSet { clips, resolved } = clip_set;
+            let outdated = clips
+                .iter()
+                .filter_map(|(id, handle)| {
+                    clip_storage
+                        .get(handle)
+                        .map(|clip| (id, (handle.id(), clip.version())))
+                })
+                .filter(|(id, version)| resolved.get(*id) != Some(version))
+                .map(|(id, version)| (id.clone(), version))
+                .collect::<Vec<_>>();
+            if outdated.is_empty() {
+                continue;
+            }
+
+            let mut node_names = FnvHashMap::default();
+            if let Some(name) = names.get(entity) {
+                node_names.insert(name.name.to_string(), 0);
+            }
+            if let Some(hierarchy) = hierarchies.get(entity) {
+                for (node_index, node_entity) in &hierarchy.nodes {
+                    if let Some(name) = names.get(*node_entity) {
+                        node_names.insert(name.name.to_string(), *node_index);
+                    }
+                }
+            }
+
+            let animation_set = match animation_sets.entry(entity) {
+                Ok(entry) => entry.or_insert_with(AnimationSet::default),
+                Err(err) => {
+                    error!("Failed to insert `AnimationSet`: {}", err);
+                    continue;
+                }
+            };
+            for (id, version) in outdated {
+                let clip = clip_storage
+                    .get(&clips[&id])
+                    .expect("Unreachable: Existence of clip checked above");
+                match clip.to_animation(&node_names) {
+                    Ok(animation) => {
+                        let handle = loader.load_from_data(animation, (), &animation_storage);
+                        animation_set.insert(id.clone(), handle);
+                    }
+                    Err(err) => error!("Failed to resolve animation clip: {}", err),
+                }
+                resolved.insert(id, version);
+            }
+        }
+    }
+}
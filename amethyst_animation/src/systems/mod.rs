@@ -2,8 +2,13 @@ use amethyst_assets::Processor;
 
 use crate::resources::{Animation, Sampler};
 
-pub use self::{control::AnimationControlSystem, sampling::SamplerInterpolationSystem};
+pub use self::{
+    clip::{AnimationClipProcessor, AnimationClipSystem},
+    control::AnimationControlSystem,
+    sampling::SamplerInterpolationSystem,
+};
 
+mod clip;
 mod control;
 mod sampling;
 
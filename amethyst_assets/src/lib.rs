@@ -18,7 +18,7 @@ pub use crate::{
     helper::AssetLoaderSystemData,
     loader::Loader,
     prefab::{AssetPrefab, Prefab, PrefabData, PrefabLoader, PrefabLoaderSystem},
-    progress::{Completion, Progress, ProgressCounter, Tracker},
+    progress::{AssetErrorMeta, Completion, Progress, ProgressCounter, Tracker},
     reload::{HotReloadBundle, HotReloadStrategy, HotReloadSystem, Reload, SingleFile},
     source::{Directory, Source},
     storage::{AssetStorage, Handle, ProcessingState, Processor, WeakHandle},
@@ -2,6 +2,7 @@ use std::{hash::Hash, marker::PhantomData};
 
 use amethyst_core::{bundle::SystemBundle, specs::prelude::DispatcherBuilder};
 use amethyst_error::Error;
+use winit::MouseButton;
 
 use super::*;
 
@@ -34,7 +35,10 @@ pub struct FlyControlBundle<A, B> {
     right_input_axis: Option<A>,
     up_input_axis: Option<A>,
     forward_input_axis: Option<A>,
-    _marker: PhantomData<B>,
+    acceleration: Option<(f32, f32)>,
+    sprint: Option<(B, f32)>,
+    smoothing: f32,
+    curve: SensitivityCurve,
 }
 
 impl<A, B> FlyControlBundle<A, B> {
@@ -51,7 +55,10 @@ impl<A, B> FlyControlBundle<A, B> {
             right_input_axis,
             up_input_axis,
             forward_input_axis,
-            _marker: PhantomData,
+            acceleration: None,
+            sprint: None,
+            smoothing: 0.0,
+            curve: SensitivityCurve::default(),
         }
     }
 
@@ -67,6 +74,31 @@ impl<A, B> FlyControlBundle<A, B> {
         self.speed = speed;
         self
     }
+
+    /// Makes the movement speed up and slow down progressively, see
+    /// `FlyMovementSystem::with_acceleration`.
+    pub fn with_acceleration(mut self, acceleration: f32, deceleration: f32) -> Self {
+        self.acceleration = Some((acceleration, deceleration));
+        self
+    }
+
+    /// Multiplies the speed by `multiplier` while the given action is down.
+    pub fn with_sprint(mut self, action: B, multiplier: f32) -> Self {
+        self.sprint = Some((action, multiplier));
+        self
+    }
+
+    /// Smooths the movement, see `FlyMovementSystem::with_smoothing`.
+    pub fn with_smoothing(mut self, time_constant: f32) -> Self {
+        self.smoothing = time_constant;
+        self
+    }
+
+    /// Applies the curve to the mouse movement before the sensitivity.
+    pub fn with_sensitivity_curve(mut self, curve: SensitivityCurve) -> Self {
+        self.curve = curve;
+        self
+    }
 }
 
 impl<'a, 'b, A, B> SystemBundle<'a, 'b> for FlyControlBundle<A, B>
@@ -75,18 +107,23 @@ where
     B: Send + Sync + Hash + Eq + Clone + 'static,
 {
     fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<(), Error> {
+        let mut movement = FlyMovementSystem::<A, B>::new(
+            self.speed,
+            self.right_input_axis,
+            self.up_input_axis,
+            self.forward_input_axis,
+        )
+        .with_smoothing(self.smoothing);
+        if let Some((acceleration, deceleration)) = self.acceleration {
+            movement = movement.with_acceleration(acceleration, deceleration);
+        }
+        if let Some((action, multiplier)) = self.sprint {
+            movement = movement.with_sprint(action, multiplier);
+        }
+        builder.add(movement, "fly_movement", &[]);
         builder.add(
-            FlyMovementSystem::<A, B>::new(
-                self.speed,
-                self.right_input_axis,
-                self.up_input_axis,
-                self.forward_input_axis,
-            ),
-            "fly_movement",
-            &[],
-        );
-        builder.add(
-            FreeRotationSystem::<A, B>::new(self.sensitivity_x, self.sensitivity_y),
+            FreeRotationSystem::<A, B>::new(self.sensitivity_x, self.sensitivity_y)
+                .with_curve(self.curve),
             "free_rotation",
             &[],
         );
@@ -106,10 +143,17 @@ where
 /// You might want to add "fly_movement" and "free_rotation" as dependencies of the TransformSystem.
 /// Adding this bundle will grab the mouse, hide it and keep it centered.
 ///
+/// Zooming with the mouse wheel, panning with the middle mouse button, pitch limits and smoothing
+/// are disabled by default.
+///
 /// See the `arc_ball_camera` example to see how to use the arc ball camera.
 pub struct ArcBallControlBundle<A, B> {
     sensitivity_x: f32,
     sensitivity_y: f32,
+    zoom: Option<(f32, f32, f32)>,
+    pan_speed: Option<f32>,
+    pitch_limits: Option<(f32, f32)>,
+    smoothing: f32,
     _marker: PhantomData<(A, B)>,
 }
 
@@ -119,6 +163,10 @@ impl<A, B> ArcBallControlBundle<A, B> {
         ArcBallControlBundle {
             sensitivity_x: 1.0,
             sensitivity_y: 1.0,
+            zoom: None,
+            pan_speed: None,
+            pitch_limits: None,
+            smoothing: 0.0,
             _marker: PhantomData,
         }
     }
@@ -129,6 +177,30 @@ impl<A, B> ArcBallControlBundle<A, B> {
         self.sensitivity_y = y;
         self
     }
+
+    /// Zooms by `factor` on each scroll step, keeping the distance between `min` and `max`.
+    pub fn with_zoom(mut self, factor: f32, min: f32, max: f32) -> Self {
+        self.zoom = Some((factor, min, max));
+        self
+    }
+
+    /// Pans the orbited point with the middle mouse button, see `ArcBallInputSystem::with_pan`.
+    pub fn with_pan(mut self, speed: f32) -> Self {
+        self.pan_speed = Some(speed);
+        self
+    }
+
+    /// Keeps the pitch of the camera between the given angles in degrees.
+    pub fn with_pitch_limits(mut self, min: f32, max: f32) -> Self {
+        self.pitch_limits = Some((min, max));
+        self
+    }
+
+    /// Smooths the movement of the camera, see `ArcBallRotationSystem::with_smoothing`.
+    pub fn with_smoothing(mut self, time_constant: f32) -> Self {
+        self.smoothing = time_constant;
+        self
+    }
 }
 
 impl<'a, 'b, A, B> SystemBundle<'a, 'b> for ArcBallControlBundle<A, B>
@@ -137,12 +209,26 @@ where
     B: Send + Sync + Hash + Eq + Clone + 'static,
 {
     fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<(), Error> {
-        builder.add(ArcBallRotationSystem::default(), "arc_ball_rotation", &[]);
         builder.add(
-            FreeRotationSystem::<A, B>::new(self.sensitivity_x, self.sensitivity_y),
+            FreeRotationSystem::<A, B>::new(self.sensitivity_x, self.sensitivity_y)
+                .with_pause_button(MouseButton::Middle),
             "free_rotation",
             &[],
         );
+        let (factor, min, max) = self.zoom.unwrap_or((1.0, 0.0, std::f32::MAX));
+        let input = ArcBallInputSystem::<A, B>::new()
+            .with_zoom(factor, min, max)
+            .with_pan(self.pan_speed.unwrap_or(0.0), MouseButton::Middle);
+        builder.add(input, "arc_ball_input", &[]);
+        let mut rotation = ArcBallRotationSystem::default().with_smoothing(self.smoothing);
+        if let Some((min, max)) = self.pitch_limits {
+            rotation = rotation.with_pitch_limits(min, max);
+        }
+        builder.add(
+            rotation,
+            "arc_ball_rotation",
+            &["free_rotation", "arc_ball_input"],
+        );
         builder.add(
             MouseFocusUpdateSystem::new(),
             "mouse_focus",
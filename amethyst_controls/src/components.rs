@@ -1,5 +1,8 @@
 use amethyst_assets::PrefabData;
-use amethyst_core::specs::prelude::{Component, Entity, HashMapStorage, NullStorage, WriteStorage};
+use amethyst_core::{
+    nalgebra::Vector3,
+    specs::prelude::{Component, Entity, HashMapStorage, NullStorage, WriteStorage},
+};
 use amethyst_error::Error;
 
 use serde::{Deserialize, Serialize};
@@ -20,6 +23,19 @@ pub struct ArcBallControlTag {
     pub target: Entity,
     /// The distance from the target entity that the camera should orbit at.
     pub distance: f32,
+    /// Offset of the orbited point from the target entity, changed by panning.
+    pub pan: Vector3<f32>,
+}
+
+impl ArcBallControlTag {
+    /// Orbits around the target entity at the given distance.
+    pub fn new(target: Entity, distance: f32) -> Self {
+        ArcBallControlTag {
+            target,
+            distance,
+            pan: Vector3::zeros(),
+        }
+    }
 }
 
 impl Component for ArcBallControlTag {
@@ -53,13 +69,9 @@ impl<'a> PrefabData<'a> for ControlTagPrefab {
     ) -> Result<(), Error> {
         system_data.0.insert(entity, FlyControlTag)?;
         if let Some((index, distance)) = self.arc_ball {
-            system_data.1.insert(
-                entity,
-                ArcBallControlTag {
-                    target: entities[index],
-                    distance,
-                },
-            )?;
+            system_data
+                .1
+                .insert(entity, ArcBallControlTag::new(entities[index], distance))?;
         }
         Ok(())
     }
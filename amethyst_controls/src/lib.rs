@@ -7,8 +7,8 @@ pub use self::{
     components::{ArcBallControlTag, ControlTagPrefab, FlyControlTag},
     resources::{HideCursor, WindowFocus},
     systems::{
-        ArcBallRotationSystem, CursorHideSystem, FlyMovementSystem, FreeRotationSystem,
-        MouseFocusUpdateSystem,
+        ArcBallInputSystem, ArcBallRotationSystem, CursorHideSystem, FlyMovementSystem,
+        FreeRotationSystem, MouseFocusUpdateSystem, SensitivityCurve,
     },
 };
 
@@ -1,16 +1,17 @@
 use std::{hash::Hash, marker::PhantomData};
 
-use winit::{DeviceEvent, Event, WindowEvent};
+use winit::{DeviceEvent, Event, MouseButton, WindowEvent};
 
 use amethyst_core::{
-    nalgebra::{Unit, Vector3},
+    nalgebra::{Unit, UnitQuaternion, Vector3},
     shrev::{EventChannel, ReaderId},
-    specs::prelude::{Join, Read, ReadStorage, Resources, System, Write, WriteStorage},
+    specs::prelude::{Entities, Join, Read, ReadStorage, Resources, System, Write, WriteStorage},
     timing::Time,
     transform::Transform,
 };
-use amethyst_input::{get_input_axis_simple, InputHandler};
+use amethyst_input::{get_input_axis_simple, InputEvent, InputHandler, ScrollDirection};
 use amethyst_renderer::WindowMessages;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     components::{ArcBallControlTag, FlyControlTag},
@@ -19,6 +20,9 @@ use crate::{
 
 /// The system that manages the fly movement.
 ///
+/// By default the movement starts and stops instantly, `with_acceleration` makes it speed up and
+/// slow down progressively.
+///
 /// # Type parameters
 ///
 /// * `A`: This is the key the `InputHandler` is using for axes. Often, this is a `String`.
@@ -32,7 +36,16 @@ pub struct FlyMovementSystem<A, B> {
     up_input_axis: Option<A>,
     /// The name of the input axis to locally move in the z coordinates.
     forward_input_axis: Option<A>,
-    _marker: PhantomData<B>,
+    /// The acceleration and deceleration in units per second squared, instant if `None`.
+    acceleration: Option<(f32, f32)>,
+    /// The action multiplying the speed while it is down, and the multiplier.
+    sprint: Option<(B, f32)>,
+    /// The time constant in seconds of the smoothing of the movement.
+    smoothing: f32,
+    /// The local velocity the movement reaches with the acceleration.
+    velocity: Vector3<f32>,
+    /// The local velocity after smoothing.
+    smoothed_velocity: Vector3<f32>,
 }
 
 impl<A, B> FlyMovementSystem<A, B>
@@ -52,9 +65,44 @@ where
             right_input_axis,
             up_input_axis,
             forward_input_axis,
-            _marker: PhantomData,
+            acceleration: None,
+            sprint: None,
+            smoothing: 0.0,
+            velocity: Vector3::zeros(),
+            smoothed_velocity: Vector3::zeros(),
         }
     }
+
+    /// Makes the movement speed up and slow down progressively, with the given acceleration and
+    /// deceleration in units per second squared.
+    pub fn with_acceleration(mut self, acceleration: f32, deceleration: f32) -> Self {
+        self.acceleration = Some((acceleration, deceleration));
+        self
+    }
+
+    /// Multiplies the speed by `multiplier` while the given action is down.
+    pub fn with_sprint(mut self, action: B, multiplier: f32) -> Self {
+        self.sprint = Some((action, multiplier));
+        self
+    }
+
+    /// Smooths the movement, the time constant being the time in seconds the movement takes to
+    /// reach about two thirds of a change in velocity. Disabled with 0, the default.
+    pub fn with_smoothing(mut self, time_constant: f32) -> Self {
+        self.smoothing = time_constant;
+        self
+    }
+}
+
+/// Moves `current` towards `target` by at most `max_delta`.
+fn approach(current: Vector3<f32>, target: Vector3<f32>, max_delta: f32) -> Vector3<f32> {
+    let difference = target - current;
+    let distance = difference.norm();
+    if distance <= max_delta {
+        target
+    } else {
+        current + difference * (max_delta / distance)
+    }
 }
 
 impl<'a, A, B> System<'a> for FlyMovementSystem<A, B>
@@ -73,10 +121,39 @@ where
         let x = get_input_axis_simple(&self.right_input_axis, &input);
         let y = get_input_axis_simple(&self.up_input_axis, &input);
         let z = get_input_axis_simple(&self.forward_input_axis, &input);
+        let delta_seconds = time.delta_seconds();
+
+        let mut speed = self.speed;
+        if let Some((ref action, multiplier)) = self.sprint {
+            if input.action_is_down(action).unwrap_or(false) {
+                speed *= multiplier;
+            }
+        }
+        let target = Unit::try_new(Vector3::new(x, y, z), 1.0e-6)
+            .map_or_else(Vector3::zeros, |dir| dir.into_inner() * speed);
+
+        self.velocity = match self.acceleration {
+            Some((acceleration, deceleration)) => {
+                let rate = if target.norm() >= self.velocity.norm() {
+                    acceleration
+                } else {
+                    deceleration
+                };
+                approach(self.velocity, target, rate * delta_seconds)
+            }
+            None => target,
+        };
+        self.smoothed_velocity = if self.smoothing > 0.0 {
+            let factor = 1.0 - (-delta_seconds / self.smoothing).exp();
+            self.smoothed_velocity + (self.velocity - self.smoothed_velocity) * factor
+        } else {
+            self.velocity
+        };
 
-        if let Some(dir) = Unit::try_new(Vector3::new(x, y, z), 1.0e-6) {
+        let velocity = self.smoothed_velocity;
+        if let Some(dir) = Unit::try_new(velocity, 1.0e-6) {
             for (transform, _) in (&mut transform, &tag).join() {
-                transform.move_along_local(dir, time.delta_seconds() * self.speed);
+                transform.move_along_local(dir, velocity.norm() * delta_seconds);
             }
         }
     }
@@ -87,29 +164,240 @@ where
 /// and while keeping the orientation of the camera.
 ///
 /// To modify the orientation of the camera in accordance with the mouse input, please use the
-/// `FreeRotationSystem`.
+/// `FreeRotationSystem`, and the `ArcBallInputSystem` to zoom and pan.
 #[derive(Default)]
-pub struct ArcBallRotationSystem;
+pub struct ArcBallRotationSystem {
+    /// The minimum and maximum pitch in degrees.
+    pitch_limits: Option<(f32, f32)>,
+    /// The time constant in seconds of the smoothing of the movement.
+    smoothing: f32,
+}
+
+impl ArcBallRotationSystem {
+    /// Keeps the pitch of the camera between the given angles in degrees, 0 being horizontal
+    /// and positive angles looking up.
+    pub fn with_pitch_limits(mut self, min: f32, max: f32) -> Self {
+        self.pitch_limits = Some((min, max));
+        self
+    }
+
+    /// Smooths the movement of the camera, the time constant being the time in seconds it takes
+    /// to cover about two thirds of the distance to its position. Disabled with 0, the default.
+    pub fn with_smoothing(mut self, time_constant: f32) -> Self {
+        self.smoothing = time_constant;
+        self
+    }
+}
+
+/// Limits the pitch of the rotation, removing its roll if it had to be limited.
+fn clamp_pitch(rotation: &UnitQuaternion<f32>, min: f32, max: f32) -> Option<UnitQuaternion<f32>> {
+    let forward = rotation * -Vector3::z();
+    let pitch = forward.y.max(-1.0).min(1.0).asin();
+    let clamped = pitch.max(min.to_radians()).min(max.to_radians());
+    if (clamped - pitch).abs() <= std::f32::EPSILON {
+        return None;
+    }
+    let yaw = (-forward.x).atan2(-forward.z);
+    Some(
+        UnitQuaternion::from_axis_angle(&Vector3::y_axis(), yaw)
+            * UnitQuaternion::from_axis_angle(&Vector3::x_axis(), clamped),
+    )
+}
 
 impl<'a> System<'a> for ArcBallRotationSystem {
     type SystemData = (
+        Entities<'a>,
+        Read<'a, Time>,
         WriteStorage<'a, Transform>,
         ReadStorage<'a, ArcBallControlTag>,
     );
 
-    fn run(&mut self, (mut transforms, tags): Self::SystemData) {
-        let mut position = None;
-        for (transform, arc_ball_camera_tag) in (&transforms, &tags).join() {
-            let pos_vec = transform.rotation() * -Vector3::z() * arc_ball_camera_tag.distance;
-            if let Some(target_transform) = transforms.get(arc_ball_camera_tag.target) {
-                position = Some(target_transform.translation() - pos_vec);
+    fn run(&mut self, (entities, time, mut transforms, tags): Self::SystemData) {
+        let focuses = (&entities, &tags)
+            .join()
+            .filter_map(|(entity, tag)| {
+                transforms
+                    .get(tag.target)
+                    .map(|target| (entity, target.translation() + tag.pan, tag.distance))
+            })
+            .collect::<Vec<_>>();
+        for (entity, focus, distance) in focuses {
+            let transform = match transforms.get_mut(entity) {
+                Some(transform) => transform,
+                None => continue,
+            };
+            if let Some((min, max)) = self.pitch_limits {
+                if let Some(rotation) = clamp_pitch(transform.rotation(), min, max) {
+                    transform.set_rotation(rotation);
+                }
             }
+            let position = focus - transform.rotation() * -Vector3::z() * distance;
+            let position = if self.smoothing > 0.0 {
+                let current = *transform.translation();
+                let factor = 1.0 - (-time.delta_seconds() / self.smoothing).exp();
+                current + (position - current) * factor
+            } else {
+                position
+            };
+            *transform.translation_mut() = position;
+        }
+    }
+}
+
+/// The system zooming arc ball cameras with the mouse wheel, and panning the orbited point while
+/// the middle mouse button is down.
+///
+/// # Type parameters
+///
+/// * `A`: This is the key the `InputHandler` is using for axes. Often, this is a `String`.
+/// * `B`: This is the key the `InputHandler` is using for actions. Often, this is a `String`.
+pub struct ArcBallInputSystem<A, B> {
+    /// The distance is multiplied or divided by this factor on each scroll step.
+    zoom_factor: f32,
+    min_distance: f32,
+    max_distance: f32,
+    /// The pan in units per pixel of mouse movement, per unit of distance.
+    pan_speed: f32,
+    pan_button: MouseButton,
+    input_reader: Option<ReaderId<InputEvent<B>>>,
+    event_reader: Option<ReaderId<Event>>,
+    _marker: PhantomData<A>,
+}
+
+impl<A, B> ArcBallInputSystem<A, B> {
+    /// Builds a new `ArcBallInputSystem` zooming by a factor of 1.1 per scroll step, and panning
+    /// with the middle mouse button.
+    pub fn new() -> Self {
+        ArcBallInputSystem {
+            zoom_factor: 1.1,
+            min_distance: 0.1,
+            max_distance: std::f32::MAX,
+            pan_speed: 0.001,
+            pan_button: MouseButton::Middle,
+            input_reader: None,
+            event_reader: None,
+            _marker: PhantomData,
         }
-        if let Some(new_pos) = position {
-            for (transform, _) in (&mut transforms, &tags).join() {
-                *transform.translation_mut() = new_pos;
+    }
+
+    /// Zooms by `factor` on each scroll step, keeping the distance between `min` and `max`.
+    /// A factor of 1 disables zooming.
+    pub fn with_zoom(mut self, factor: f32, min: f32, max: f32) -> Self {
+        self.zoom_factor = factor;
+        self.min_distance = min;
+        self.max_distance = max;
+        self
+    }
+
+    /// Pans by `speed` units per pixel of mouse movement and unit of distance, while the button
+    /// is down. A speed of 0 disables panning.
+    pub fn with_pan(mut self, speed: f32, button: MouseButton) -> Self {
+        self.pan_speed = speed;
+        self.pan_button = button;
+        self
+    }
+}
+
+impl<A, B> Default for ArcBallInputSystem<A, B> {
+    fn default() -> Self {
+        ArcBallInputSystem::new()
+    }
+}
+
+impl<'a, A, B> System<'a> for ArcBallInputSystem<A, B>
+where
+    A: Send + Sync + Hash + Eq + Clone + 'static,
+    B: Send + Sync + Hash + Eq + Clone + 'static,
+{
+    type SystemData = (
+        Read<'a, EventChannel<InputEvent<B>>>,
+        Read<'a, EventChannel<Event>>,
+        Read<'a, InputHandler<A, B>>,
+        ReadStorage<'a, Transform>,
+        WriteStorage<'a, ArcBallControlTag>,
+    );
+
+    fn run(&mut self, (input_events, events, input, transforms, mut tags): Self::SystemData) {
+        let mut zoom = 0;
+        for event in
+            input_events.read(self.input_reader.as_mut().expect(
+                "`ArcBallInputSystem::setup` was not called before `ArcBallInputSystem::run`",
+            ))
+        {
+            match *event {
+                InputEvent::MouseWheelMoved(ScrollDirection::ScrollUp) => zoom -= 1,
+                InputEvent::MouseWheelMoved(ScrollDirection::ScrollDown) => zoom += 1,
+                _ => {}
+            }
+        }
+        let panning = input.mouse_button_is_down(self.pan_button);
+        let (mut pan_x, mut pan_y) = (0.0, 0.0);
+        for event in
+            events.read(self.event_reader.as_mut().expect(
+                "`ArcBallInputSystem::setup` was not called before `ArcBallInputSystem::run`",
+            ))
+        {
+            if let Event::DeviceEvent {
+                event: DeviceEvent::MouseMotion { delta: (x, y) },
+                ..
+            } = *event
+            {
+                if panning {
+                    pan_x += x as f32;
+                    pan_y += y as f32;
+                }
             }
         }
+
+        for (transform, tag) in (&transforms, &mut tags).join() {
+            if zoom != 0 {
+                tag.distance = (tag.distance * self.zoom_factor.powi(zoom))
+                    .max(self.min_distance)
+                    .min(self.max_distance);
+            }
+            if self.pan_speed > 0.0 && (pan_x != 0.0 || pan_y != 0.0) {
+                let right = transform.rotation() * Vector3::x();
+                let up = transform.rotation() * Vector3::y();
+                tag.pan += (right * -pan_x + up * pan_y) * self.pan_speed * tag.distance;
+            }
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        use amethyst_core::specs::prelude::SystemData;
+
+        Self::SystemData::setup(res);
+        self.input_reader = Some(
+            res.fetch_mut::<EventChannel<InputEvent<B>>>()
+                .register_reader(),
+        );
+        self.event_reader = Some(res.fetch_mut::<EventChannel<Event>>().register_reader());
+    }
+}
+
+/// Curve applied to the mouse movement by the `FreeRotationSystem`, before the sensitivity.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SensitivityCurve {
+    /// The rotation is proportional to the mouse movement.
+    Linear,
+    /// The mouse movement is raised to the power, keeping its sign. Powers above 1 make small
+    /// movements precise and large movements fast.
+    Power(f32),
+}
+
+impl SensitivityCurve {
+    /// Applies the curve to a mouse movement.
+    pub fn apply(self, delta: f32) -> f32 {
+        match self {
+            SensitivityCurve::Linear => delta,
+            SensitivityCurve::Power(power) => delta.signum() * delta.abs().powf(power),
+        }
+    }
+}
+
+impl Default for SensitivityCurve {
+    fn default() -> Self {
+        SensitivityCurve::Linear
     }
 }
 
@@ -127,6 +415,9 @@ impl<'a> System<'a> for ArcBallRotationSystem {
 pub struct FreeRotationSystem<A, B> {
     sensitivity_x: f32,
     sensitivity_y: f32,
+    curve: SensitivityCurve,
+    /// The rotation stops while this button is down.
+    pause_button: Option<MouseButton>,
     _marker1: PhantomData<A>,
     _marker2: PhantomData<B>,
     event_reader: Option<ReaderId<Event>>,
@@ -138,11 +429,25 @@ impl<A, B> FreeRotationSystem<A, B> {
         FreeRotationSystem {
             sensitivity_x,
             sensitivity_y,
+            curve: SensitivityCurve::default(),
+            pause_button: None,
             _marker1: PhantomData,
             _marker2: PhantomData,
             event_reader: None,
         }
     }
+
+    /// Applies the curve to the mouse movement.
+    pub fn with_curve(mut self, curve: SensitivityCurve) -> Self {
+        self.curve = curve;
+        self
+    }
+
+    /// Stops the rotation while the mouse button is down, for instance while it pans the camera.
+    pub fn with_pause_button(mut self, button: MouseButton) -> Self {
+        self.pause_button = Some(button);
+        self
+    }
 }
 
 impl<'a, A, B> System<'a> for FreeRotationSystem<A, B>
@@ -156,10 +461,14 @@ where
         ReadStorage<'a, FlyControlTag>,
         Read<'a, WindowFocus>,
         Read<'a, HideCursor>,
+        Read<'a, InputHandler<A, B>>,
     );
 
-    fn run(&mut self, (events, mut transform, tag, focus, hide): Self::SystemData) {
-        let focused = focus.is_focused;
+    fn run(&mut self, (events, mut transform, tag, focus, hide, input): Self::SystemData) {
+        let paused = self
+            .pause_button
+            .map_or(false, |button| input.mouse_button_is_down(button));
+        let focused = focus.is_focused && !paused;
         for event in
             events.read(&mut self.event_reader.as_mut().expect(
                 "`FreeRotationSystem::setup` was not called before `FreeRotationSystem::run`",
@@ -169,8 +478,9 @@ where
                 if let Event::DeviceEvent { ref event, .. } = *event {
                     if let DeviceEvent::MouseMotion { delta: (x, y) } = *event {
                         for (transform, _) in (&mut transform, &tag).join() {
-                            transform.pitch_local((-y as f32 * self.sensitivity_y).to_radians());
-                            transform.yaw_global((-x as f32 * self.sensitivity_x).to_radians());
+                            let (x, y) = (self.curve.apply(x as f32), self.curve.apply(y as f32));
+                            transform.pitch_local((-y * self.sensitivity_y).to_radians());
+                            transform.yaw_global((-x * self.sensitivity_x).to_radians());
                         }
                     }
                 }
@@ -18,7 +18,7 @@ use std::sync::Arc;
 pub use crate::{
     bundle::SystemBundle,
     event::EventReader,
-    system_ext::{Pausable, SystemExt},
+    system_ext::{GameplayStatus, Pausable, SystemExt, SystemTimings, Timed},
     timing::*,
     transform::*,
 };
@@ -3,7 +3,13 @@
 //! This modules contains an extension trait for the System trait which adds useful transformation
 //! functions.
 
-use shred::{RunningTime, SystemData};
+use std::{
+    collections::BTreeMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use shred::{Resources, RunningTime, SystemData};
 use specs::prelude::{Read, System};
 
 /// Extension functionality associated systems.
@@ -75,6 +81,54 @@ pub trait SystemExt {
     where
         Self: Sized,
         V: Send + Sync + Default + PartialEq;
+
+    /// Make a system part of the gameplay, paused while the `GameplayStatus` is `Paused`.
+    ///
+    /// The state machine of the application pauses the gameplay while a state pausing it is on
+    /// the state stack, see `State::pauses_gameplay`.
+    ///
+    /// The notes of `pausable` apply.
+    fn gameplay(self) -> Pausable<Self, GameplayStatus>
+    where
+        Self: Sized;
+
+    /// Measure the time taken by each run of the system, recording it under `name` in the
+    /// `SystemTimings` resource.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use amethyst::{
+    ///     core::SystemTimings,
+    ///     ecs::{System, Write},
+    ///     shred::DispatcherBuilder,
+    ///     prelude::*,
+    /// };
+    ///
+    /// struct AddNumber(u32);
+    ///
+    /// impl<'s> System<'s> for AddNumber {
+    ///     type SystemData = Write<'s, u32>;
+    ///
+    ///     fn run(&mut self, mut number: Self::SystemData) {
+    ///         *number += self.0;
+    ///     }
+    /// }
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let mut dispatcher = DispatcherBuilder::default()
+    ///     .with(AddNumber(1).timed("add_number"), "add_number", &[])
+    ///     .build();
+    ///
+    /// dispatcher.setup(&mut world.res);
+    /// dispatcher.dispatch(&mut world.res);
+    ///
+    /// assert!(world.read_resource::<SystemTimings>().get("add_number").is_some());
+    /// ```
+    fn timed<N: Into<String>>(self, name: N) -> Timed<Self>
+    where
+        Self: Sized;
 }
 
 impl<'s, S> SystemExt for S
@@ -91,6 +145,38 @@ where
             value,
         }
     }
+
+    fn gameplay(self) -> Pausable<Self, GameplayStatus>
+    where
+        Self: Sized,
+    {
+        self.pausable(GameplayStatus::Running)
+    }
+
+    fn timed<N: Into<String>>(self, name: N) -> Timed<Self>
+    where
+        Self: Sized,
+    {
+        Timed {
+            system: self,
+            name: name.into(),
+        }
+    }
+}
+
+/// Resource pausing the systems made part of the gameplay with `SystemExt::gameplay`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GameplayStatus {
+    /// The gameplay systems run.
+    Running,
+    /// The gameplay systems are paused.
+    Paused,
+}
+
+impl Default for GameplayStatus {
+    fn default() -> Self {
+        GameplayStatus::Running
+    }
 }
 
 /// A system that is enabled when `V` has a specific value.
@@ -123,3 +209,80 @@ where
         self.system.running_time()
     }
 }
+
+/// Resource holding the duration of the last run of the systems made timed with
+/// `SystemExt::timed`.
+///
+/// Timed systems only need to read this resource, so they can still run in parallel.
+#[derive(Debug, Default)]
+pub struct SystemTimings {
+    timings: Mutex<BTreeMap<String, Duration>>,
+}
+
+impl SystemTimings {
+    /// Records the duration of the last run of a system.
+    pub fn record(&self, name: &str, duration: Duration) {
+        let mut timings = self
+            .timings
+            .lock()
+            .expect("SystemTimings mutex was poisoned");
+        match timings.get_mut(name) {
+            Some(timing) => *timing = duration,
+            None => {
+                timings.insert(name.to_string(), duration);
+            }
+        }
+    }
+
+    /// The duration of the last run of a system.
+    pub fn get(&self, name: &str) -> Option<Duration> {
+        self.timings
+            .lock()
+            .expect("SystemTimings mutex was poisoned")
+            .get(name)
+            .cloned()
+    }
+
+    /// The duration of the last run of every timed system, sorted by name.
+    pub fn timings(&self) -> Vec<(String, Duration)> {
+        self.timings
+            .lock()
+            .expect("SystemTimings mutex was poisoned")
+            .iter()
+            .map(|(name, duration)| (name.clone(), *duration))
+            .collect()
+    }
+}
+
+/// A system recording the duration of its runs in the `SystemTimings` resource.
+///
+/// This is created using the [`SystemExt::timed`] method.
+///
+/// [`SystemExt::timed`]: trait.SystemExt.html#tymethod.timed
+pub struct Timed<S> {
+    system: S,
+    name: String,
+}
+
+impl<'s, S> System<'s> for Timed<S>
+where
+    S::SystemData: SystemData<'s>,
+    S: System<'s>,
+{
+    type SystemData = (Read<'s, SystemTimings>, S::SystemData);
+
+    fn run(&mut self, (timings, data): Self::SystemData) {
+        let start = Instant::now();
+        self.system.run(data);
+        timings.record(&self.name, start.elapsed());
+    }
+
+    fn running_time(&self) -> RunningTime {
+        self.system.running_time()
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        <Read<'_, SystemTimings> as SystemData<'_>>::setup(res);
+        self.system.setup(res);
+    }
+}
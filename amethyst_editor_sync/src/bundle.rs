@@ -0,0 +1,95 @@
+//! ECS editor sync bundle
+
+use std::net::SocketAddr;
+
+use amethyst_core::{
+    bundle::SystemBundle,
+    specs::{prelude::DispatcherBuilder, Component},
+    Named, Transform,
+};
+use amethyst_error::Error;
+use serde::{de::DeserializeOwned, Serialize};
+use shred::Resource;
+
+use crate::{
+    registry::{ComponentAccess, ResourceAccess},
+    server::EditorServer,
+    system::EditorSyncSystem,
+};
+
+/// Editor sync bundle
+///
+/// Listens for editors on a local socket, `127.0.0.1:8000` by default, and adds the
+/// `EditorSyncSystem` as a thread local system answering their requests. The `Transform` and
+/// `Named` components are always exposed, other components and resources are registered with
+/// `with_component` and `with_resource`.
+///
+/// The socket gives full access to the registered types, so it should only be bound to local
+/// addresses, in development builds.
+///
+/// ## Errors
+///
+/// Returns an error if the socket can't be bound.
+pub struct EditorSyncBundle {
+    addr: SocketAddr,
+    components: Vec<ComponentAccess>,
+    resources: Vec<ResourceAccess>,
+}
+
+impl Default for EditorSyncBundle {
+    fn default() -> Self {
+        EditorSyncBundle {
+            addr: ([127, 0, 0, 1], 8000).into(),
+            components: vec![
+                ComponentAccess::new::<Named>("Named".to_string()),
+                ComponentAccess::new::<Transform>("Transform".to_string()),
+            ],
+            resources: Vec::new(),
+        }
+    }
+}
+
+impl EditorSyncBundle {
+    /// Create a new editor sync bundle.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set the address the editors connect to.
+    pub fn with_address(mut self, addr: SocketAddr) -> Self {
+        self.addr = addr;
+        self
+    }
+
+    /// Expose a component to the editors, under the given name.
+    pub fn with_component<C, N>(mut self, name: N) -> Self
+    where
+        C: Component + Serialize + DeserializeOwned + Send + Sync,
+        N: Into<String>,
+    {
+        self.components.push(ComponentAccess::new::<C>(name.into()));
+        self
+    }
+
+    /// Expose a resource to the editors, under the given name.
+    pub fn with_resource<R, N>(mut self, name: N) -> Self
+    where
+        R: Resource + Serialize + DeserializeOwned,
+        N: Into<String>,
+    {
+        self.resources.push(ResourceAccess::new::<R>(name.into()));
+        self
+    }
+}
+
+impl<'a, 'b> SystemBundle<'a, 'b> for EditorSyncBundle {
+    fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<(), Error> {
+        let server = EditorServer::bind(self.addr)?;
+        builder.add_thread_local(EditorSyncSystem::new(
+            server,
+            self.components,
+            self.resources,
+        ));
+        Ok(())
+    }
+}
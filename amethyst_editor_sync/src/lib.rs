@@ -0,0 +1,51 @@
+//! Amethyst editor sync crate, exposing the world of a running game to external editors and
+//! inspectors over a local socket.
+//!
+//! The `EditorSyncBundle` listens for editors, and adds the `EditorSyncSystem` answering their
+//! requests between frames. Only the components and resources registered with the bundle are
+//! exposed, serialized with `serde`.
+//!
+//! # Protocol
+//!
+//! Editors connect with TCP and send requests as JSON objects, each on its own line. Every
+//! request gets a response on its own line, in the same order. Requests may have an `id`, echoed
+//! in the response, and have a `type` with the following fields:
+//!
+//! * `list_types`: answered with `types`, the `components` and `resources` names.
+//! * `list_entities`: answered with `entities`, the `id` and `name` of the living entities.
+//! * `get_entity`, with the `entity` id: answered with `entity`, the `components` of the entity
+//!   by name.
+//! * `create_entity`, with optional `components` by name: answered with `created` and the
+//!   `entity` id.
+//! * `destroy_entity`, with the `entity` id: answered with `done`.
+//! * `set_component`, with the `entity` id, the `component` name and its `value`: inserts or
+//!   replaces the component, answered with `done`.
+//! * `remove_component`, with the `entity` id and the `component` name: answered with `done`.
+//! * `get_resource`, with the `resource` name: answered with `resource` and its `value`.
+//! * `set_resource`, with the `resource` name and its `value`: answered with `done`.
+//!
+//! Failed requests are answered with `error` and a `message`. For example:
+//!
+//! ```text
+//! > {"id": 1, "type": "set_component", "entity": 3, "component": "Named", "value": {"name": "player"}}
+//! < {"id": 1, "type": "done"}
+//! > {"id": 2, "type": "get_entity", "entity": 9}
+//! < {"id": 2, "type": "error", "message": "Entity 9 does not exist"}
+//! ```
+//!
+//! The messages are also available as the `RequestMessage` and `ResponseMessage` types, for
+//! editors written in Rust.
+
+#![warn(missing_docs, rust_2018_idioms, rust_2018_compatibility)]
+
+pub use self::{
+    bundle::EditorSyncBundle,
+    protocol::{EntityInfo, Request, RequestMessage, Response, ResponseMessage},
+    system::EditorSyncSystem,
+};
+
+mod bundle;
+mod protocol;
+mod registry;
+mod server;
+mod system;
@@ -0,0 +1,181 @@
+//! The messages exchanged with the editors.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A request sent by an editor, with the id echoed in the response.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RequestMessage {
+    /// Id chosen by the editor to match the response to the request.
+    #[serde(default)]
+    pub id: u64,
+    /// The request.
+    #[serde(flatten)]
+    pub request: Request,
+}
+
+/// A response sent to an editor.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ResponseMessage {
+    /// Id of the request, 0 if the request could not be parsed.
+    pub id: u64,
+    /// The response.
+    #[serde(flatten)]
+    pub response: Response,
+}
+
+/// Requests of the protocol. Entities are identified by their id.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    /// Lists the names of the registered components and resources.
+    ListTypes,
+    /// Lists the living entities.
+    ListEntities,
+    /// Gets the registered components of an entity.
+    GetEntity {
+        /// Id of the entity.
+        entity: u32,
+    },
+    /// Creates an entity with the given components.
+    CreateEntity {
+        /// Values of the components, by name.
+        #[serde(default)]
+        components: BTreeMap<String, Value>,
+    },
+    /// Deletes an entity.
+    DestroyEntity {
+        /// Id of the entity.
+        entity: u32,
+    },
+    /// Inserts or replaces a component of an entity.
+    SetComponent {
+        /// Id of the entity.
+        entity: u32,
+        /// Name of the component.
+        component: String,
+        /// Value of the component.
+        value: Value,
+    },
+    /// Removes a component of an entity.
+    RemoveComponent {
+        /// Id of the entity.
+        entity: u32,
+        /// Name of the component.
+        component: String,
+    },
+    /// Gets the value of a resource.
+    GetResource {
+        /// Name of the resource.
+        resource: String,
+    },
+    /// Replaces the value of a resource.
+    SetResource {
+        /// Name of the resource.
+        resource: String,
+        /// Value of the resource.
+        value: Value,
+    },
+}
+
+/// An entity, as listed by `Request::ListEntities`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EntityInfo {
+    /// Id of the entity.
+    pub id: u32,
+    /// The `Named` component of the entity, if any.
+    pub name: Option<String>,
+}
+
+/// Responses of the protocol.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Response {
+    /// The registered components and resources.
+    Types {
+        /// Names of the components.
+        components: Vec<String>,
+        /// Names of the resources.
+        resources: Vec<String>,
+    },
+    /// The living entities.
+    Entities {
+        /// The entities.
+        entities: Vec<EntityInfo>,
+    },
+    /// The registered components of an entity.
+    Entity {
+        /// Id of the entity.
+        entity: u32,
+        /// Values of the components, by name.
+        components: BTreeMap<String, Value>,
+    },
+    /// An entity was created.
+    Created {
+        /// Id of the entity.
+        entity: u32,
+    },
+    /// The value of a resource.
+    Resource {
+        /// Name of the resource.
+        resource: String,
+        /// Value of the resource.
+        value: Value,
+    },
+    /// The request succeeded.
+    Done,
+    /// The request failed.
+    Error {
+        /// Description of the error.
+        message: String,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn parse_request() {
+        let message: RequestMessage = serde_json::from_str(
+            r#"{"id": 4, "type": "set_component", "entity": 2, "component": "Named", "value": {"name": "player"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            message,
+            RequestMessage {
+                id: 4,
+                request: Request::SetComponent {
+                    entity: 2,
+                    component: "Named".to_string(),
+                    value: json!({ "name": "player" }),
+                },
+            }
+        );
+
+        let message: RequestMessage = serde_json::from_str(r#"{"type": "create_entity"}"#).unwrap();
+        assert_eq!(message.id, 0);
+        assert_eq!(
+            message.request,
+            Request::CreateEntity {
+                components: BTreeMap::new()
+            }
+        );
+    }
+
+    #[test]
+    fn serialize_response() {
+        let message = ResponseMessage {
+            id: 7,
+            response: Response::Created { entity: 3 },
+        };
+        assert_eq!(
+            serde_json::to_value(&message).unwrap(),
+            json!({ "id": 7, "type": "created", "entity": 3 })
+        );
+    }
+}
@@ -0,0 +1,133 @@
+//! Components and resources exposed to the editors.
+
+use amethyst_core::specs::{
+    prelude::SystemData, Component, Entity, ReadStorage, Resources, WriteStorage,
+};
+use amethyst_error::{format_err, Error};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use shred::Resource;
+
+/// Access to the storage of a registered component, without knowing its type.
+pub(crate) struct ComponentAccess {
+    pub(crate) name: String,
+    setup: fn(&mut Resources),
+    get: fn(&Resources, Entity) -> Result<Option<Value>, Error>,
+    set: fn(&Resources, Entity, Value) -> Result<(), Error>,
+    remove: fn(&Resources, Entity) -> bool,
+}
+
+impl ComponentAccess {
+    pub(crate) fn new<C>(name: String) -> Self
+    where
+        C: Component + Serialize + DeserializeOwned + Send + Sync,
+    {
+        ComponentAccess {
+            name,
+            setup: setup_component::<C>,
+            get: get_component::<C>,
+            set: set_component::<C>,
+            remove: remove_component::<C>,
+        }
+    }
+
+    pub(crate) fn setup(&self, res: &mut Resources) {
+        (self.setup)(res)
+    }
+
+    /// The serialized component of the entity, if it has one.
+    pub(crate) fn get(&self, res: &Resources, entity: Entity) -> Result<Option<Value>, Error> {
+        (self.get)(res, entity)
+    }
+
+    /// Inserts or replaces the component of the entity.
+    pub(crate) fn set(&self, res: &Resources, entity: Entity, value: Value) -> Result<(), Error> {
+        (self.set)(res, entity, value)
+    }
+
+    /// Removes the component of the entity, returns `false` if it had none.
+    pub(crate) fn remove(&self, res: &Resources, entity: Entity) -> bool {
+        (self.remove)(res, entity)
+    }
+}
+
+fn setup_component<C>(res: &mut Resources)
+where
+    C: Component + Serialize + DeserializeOwned + Send + Sync,
+{
+    WriteStorage::<'_, C>::setup(res);
+}
+
+fn get_component<C>(res: &Resources, entity: Entity) -> Result<Option<Value>, Error>
+where
+    C: Component + Serialize + DeserializeOwned + Send + Sync,
+{
+    match ReadStorage::<'_, C>::fetch(res).get(entity) {
+        Some(component) => Ok(Some(serde_json::to_value(component)?)),
+        None => Ok(None),
+    }
+}
+
+fn set_component<C>(res: &Resources, entity: Entity, value: Value) -> Result<(), Error>
+where
+    C: Component + Serialize + DeserializeOwned + Send + Sync,
+{
+    let component = serde_json::from_value::<C>(value)?;
+    WriteStorage::<'_, C>::fetch(res).insert(entity, component)?;
+    Ok(())
+}
+
+fn remove_component<C>(res: &Resources, entity: Entity) -> bool
+where
+    C: Component + Serialize + DeserializeOwned + Send + Sync,
+{
+    WriteStorage::<'_, C>::fetch(res).remove(entity).is_some()
+}
+
+/// Access to a registered resource, without knowing its type.
+pub(crate) struct ResourceAccess {
+    pub(crate) name: String,
+    get: fn(&Resources) -> Result<Value, Error>,
+    set: fn(&Resources, Value) -> Result<(), Error>,
+}
+
+impl ResourceAccess {
+    pub(crate) fn new<R: Resource + Serialize + DeserializeOwned>(name: String) -> Self {
+        ResourceAccess {
+            name,
+            get: get_resource::<R>,
+            set: set_resource::<R>,
+        }
+    }
+
+    /// The serialized resource.
+    pub(crate) fn get(&self, res: &Resources) -> Result<Value, Error> {
+        (self.get)(res)
+    }
+
+    /// Replaces the resource.
+    pub(crate) fn set(&self, res: &Resources, value: Value) -> Result<(), Error> {
+        (self.set)(res, value)
+    }
+}
+
+fn get_resource<R: Resource + Serialize + DeserializeOwned>(
+    res: &Resources,
+) -> Result<Value, Error> {
+    let resource = res
+        .try_fetch::<R>()
+        .ok_or_else(|| format_err!("The resource does not exist"))?;
+    Ok(serde_json::to_value(&*resource)?)
+}
+
+fn set_resource<R: Resource + Serialize + DeserializeOwned>(
+    res: &Resources,
+    value: Value,
+) -> Result<(), Error> {
+    let value = serde_json::from_value::<R>(value)?;
+    let mut resource = res
+        .try_fetch_mut::<R>()
+        .ok_or_else(|| format_err!("The resource does not exist"))?;
+    *resource = value;
+    Ok(())
+}
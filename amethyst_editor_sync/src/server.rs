@@ -0,0 +1,123 @@
+//! The socket the editors connect to.
+//!
+//! Messages are JSON objects, each on its own line.
+
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use amethyst_error::Error;
+use log::{error, info, warn};
+
+/// Event received from the connected editors.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum ServerEvent {
+    /// A line was received.
+    Line { client: usize, line: String },
+    /// The editor disconnected.
+    Disconnected { client: usize },
+}
+
+type Clients = Arc<Mutex<HashMap<usize, TcpStream>>>;
+
+/// Accepts the editors on a background thread, and reads their lines on a thread per editor.
+pub(crate) struct EditorServer {
+    clients: Clients,
+    events: Receiver<ServerEvent>,
+}
+
+impl EditorServer {
+    pub(crate) fn bind(addr: SocketAddr) -> Result<Self, Error> {
+        let listener = TcpListener::bind(addr)?;
+        info!("Editor server listening on {}", listener.local_addr()?);
+        let clients = Clients::default();
+        let (tx, rx) = mpsc::channel();
+
+        let accept_clients = clients.clone();
+        thread::spawn(move || {
+            for (client, stream) in listener.incoming().enumerate() {
+                let result =
+                    stream.and_then(|stream| add_client(client, stream, &accept_clients, &tx));
+                if let Err(e) = result {
+                    error!("Failed to accept editor connection. Reason: {:?}", e);
+                }
+            }
+        });
+
+        Ok(EditorServer {
+            clients,
+            events: rx,
+        })
+    }
+
+    /// Returns all events that occurred since the last call.
+    pub(crate) fn events(&self) -> Vec<ServerEvent> {
+        self.events.try_iter().collect()
+    }
+
+    /// Sends a line to the editor, dropping it if the line can't be written.
+    pub(crate) fn send(&self, client: usize, line: &str) {
+        let mut clients = self.clients.lock().expect("Editor clients mutex poisoned");
+        let result = match clients.get_mut(&client) {
+            Some(stream) => stream
+                .write_all(line.as_bytes())
+                .and_then(|_| stream.write_all(b"\n")),
+            None => return,
+        };
+        if let Err(e) = result {
+            warn!("Failed to write to editor {}. Reason: {:?}", client, e);
+            if let Some(stream) = clients.remove(&client) {
+                let _ = stream.shutdown(Shutdown::Both);
+            }
+        }
+    }
+
+    /// Forgets a disconnected editor.
+    pub(crate) fn remove(&self, client: usize) {
+        self.clients
+            .lock()
+            .expect("Editor clients mutex poisoned")
+            .remove(&client);
+    }
+}
+
+// Registers the editor for sending, and starts reading lines from it.
+fn add_client(
+    client: usize,
+    stream: TcpStream,
+    clients: &Clients,
+    sender: &Sender<ServerEvent>,
+) -> io::Result<()> {
+    stream.set_nodelay(true)?;
+    let reader = BufReader::new(stream.try_clone()?);
+    clients
+        .lock()
+        .expect("Editor clients mutex poisoned")
+        .insert(client, stream);
+
+    let sender = sender.clone();
+    thread::spawn(move || {
+        for line in reader.lines() {
+            match line {
+                Ok(line) => {
+                    if sender.send(ServerEvent::Line { client, line }).is_err() {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    warn!("Editor {} disconnected. Reason: {:?}", client, e);
+                    break;
+                }
+            }
+        }
+        let _ = sender.send(ServerEvent::Disconnected { client });
+    });
+    Ok(())
+}
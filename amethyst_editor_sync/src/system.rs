@@ -0,0 +1,289 @@
+//! The system answering the requests of the editors.
+
+use std::collections::BTreeMap;
+
+use amethyst_core::{
+    specs::{prelude::SystemData, Entities, Entity, Join, ReadStorage, Resources, RunNow},
+    Named,
+};
+use amethyst_error::{format_err, Error};
+use log::error;
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{
+    protocol::{EntityInfo, Request, RequestMessage, Response, ResponseMessage},
+    registry::{ComponentAccess, ResourceAccess},
+    server::{EditorServer, ServerEvent},
+};
+
+/// Answers the requests of the editors connected to the socket of the `EditorSyncBundle`.
+///
+/// The system is thread local, as it needs access to the storages of all the registered
+/// components and to the registered resources. Requests are handled in the order they are
+/// received, at most once per frame.
+pub struct EditorSyncSystem {
+    server: EditorServer,
+    components: Vec<ComponentAccess>,
+    resources: Vec<ResourceAccess>,
+}
+
+impl EditorSyncSystem {
+    pub(crate) fn new(
+        server: EditorServer,
+        components: Vec<ComponentAccess>,
+        resources: Vec<ResourceAccess>,
+    ) -> Self {
+        EditorSyncSystem {
+            server,
+            components,
+            resources,
+        }
+    }
+}
+
+impl<'a> RunNow<'a> for EditorSyncSystem {
+    fn run_now(&mut self, res: &'a Resources) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("editor_sync_system");
+
+        for event in self.server.events() {
+            match event {
+                ServerEvent::Line { client, line } => {
+                    let response = handle_line(res, &self.components, &self.resources, &line);
+                    match serde_json::to_string(&response) {
+                        Ok(line) => self.server.send(client, &line),
+                        Err(e) => error!("Failed to serialize editor response: {}", e),
+                    }
+                }
+                ServerEvent::Disconnected { client } => self.server.remove(client),
+            }
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        ReadStorage::<'_, Named>::setup(res);
+        for component in &self.components {
+            component.setup(res);
+        }
+    }
+}
+
+fn handle_line(
+    res: &Resources,
+    components: &[ComponentAccess],
+    resources: &[ResourceAccess],
+    line: &str,
+) -> ResponseMessage {
+    let (id, result) = match serde_json::from_str::<RequestMessage>(line) {
+        Ok(message) => (
+            message.id,
+            handle(res, components, resources, message.request),
+        ),
+        Err(e) => (0, Err(format_err!("Invalid request: {}", e))),
+    };
+    ResponseMessage {
+        id,
+        response: result.unwrap_or_else(|e| Response::Error {
+            message: e.to_string(),
+        }),
+    }
+}
+
+fn handle(
+    res: &Resources,
+    components: &[ComponentAccess],
+    resources: &[ResourceAccess],
+    request: Request,
+) -> Result<Response, Error> {
+    let component = |name: &str| {
+        components
+            .iter()
+            .find(|component| component.name == name)
+            .ok_or_else(|| format_err!("Unknown component `{}`", name))
+    };
+    let resource = |name: &str| {
+        resources
+            .iter()
+            .find(|resource| resource.name == name)
+            .ok_or_else(|| format_err!("Unknown resource `{}`", name))
+    };
+
+    Ok(match request {
+        Request::ListTypes => Response::Types {
+            components: components.iter().map(|c| c.name.clone()).collect(),
+            resources: resources.iter().map(|r| r.name.clone()).collect(),
+        },
+        Request::ListEntities => {
+            let (entities, names) = <(Entities<'_>, ReadStorage<'_, Named>)>::fetch(res);
+            Response::Entities {
+                entities: (&*entities, (&names).maybe())
+                    .join()
+                    .map(|(entity, name)| EntityInfo {
+                        id: entity.id(),
+                        name: name.map(|name| name.name.to_string()),
+                    })
+                    .collect(),
+            }
+        }
+        Request::GetEntity { entity } => {
+            let entity = find_entity(res, entity)?;
+            let mut values = BTreeMap::new();
+            for component in components {
+                if let Some(value) = component.get(res, entity)? {
+                    values.insert(component.name.clone(), value);
+                }
+            }
+            Response::Entity {
+                entity: entity.id(),
+                components: values,
+            }
+        }
+        Request::CreateEntity { components: values } => {
+            let entity = Entities::fetch(res).create();
+            let result = values
+                .into_iter()
+                .try_for_each(|(name, value)| component(&name)?.set(res, entity, value));
+            if let Err(e) = result {
+                Entities::fetch(res).delete(entity)?;
+                return Err(e);
+            }
+            Response::Created {
+                entity: entity.id(),
+            }
+        }
+        Request::DestroyEntity { entity } => {
+            let entity = find_entity(res, entity)?;
+            Entities::fetch(res).delete(entity)?;
+            Response::Done
+        }
+        Request::SetComponent {
+            entity,
+            component: name,
+            value,
+        } => {
+            let entity = find_entity(res, entity)?;
+            component(&name)?.set(res, entity, value)?;
+            Response::Done
+        }
+        Request::RemoveComponent {
+            entity,
+            component: name,
+        } => {
+            let entity = find_entity(res, entity)?;
+            if !component(&name)?.remove(res, entity) {
+                return Err(format_err!("The entity has no `{}` component", name));
+            }
+            Response::Done
+        }
+        Request::GetResource { resource: name } => Response::Resource {
+            value: resource(&name)?.get(res)?,
+            resource: name,
+        },
+        Request::SetResource {
+            resource: name,
+            value,
+        } => {
+            resource(&name)?.set(res, value)?;
+            Response::Done
+        }
+    })
+}
+
+/// The living entity with the given id.
+fn find_entity(res: &Resources, id: u32) -> Result<Entity, Error> {
+    let entities = Entities::fetch(res);
+    let entity = entities.entity(id);
+    if entities.is_alive(entity) {
+        Ok(entity)
+    } else {
+        Err(format_err!("Entity {} does not exist", id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::{specs::world::EntitiesRes, Transform};
+    use serde_json::json;
+
+    use super::*;
+
+    fn setup() -> (Resources, Vec<ComponentAccess>) {
+        let mut res = Resources::new();
+        res.insert(EntitiesRes::default());
+        let components = vec![
+            ComponentAccess::new::<Named>("Named".to_string()),
+            ComponentAccess::new::<Transform>("Transform".to_string()),
+        ];
+        for component in &components {
+            component.setup(&mut res);
+        }
+        (res, components)
+    }
+
+    fn request(res: &Resources, components: &[ComponentAccess], line: &str) -> Response {
+        handle_line(res, components, &[], line).response
+    }
+
+    #[test]
+    fn create_and_edit_entity() {
+        let (res, components) = setup();
+        let entity = match request(
+            &res,
+            &components,
+            r#"{"type": "create_entity", "components": {"Named": {"name": "player"}}}"#,
+        ) {
+            Response::Created { entity } => entity,
+            response => panic!("Unexpected response {:?}", response),
+        };
+
+        assert_eq!(
+            request(&res, &components, r#"{"type": "list_entities"}"#),
+            Response::Entities {
+                entities: vec![EntityInfo {
+                    id: entity,
+                    name: Some("player".to_string()),
+                }],
+            }
+        );
+
+        let line = json!({
+            "type": "set_component",
+            "entity": entity,
+            "component": "Named",
+            "value": { "name": "enemy" },
+        })
+        .to_string();
+        assert_eq!(request(&res, &components, &line), Response::Done);
+
+        let line = json!({ "type": "get_entity", "entity": entity }).to_string();
+        match request(&res, &components, &line) {
+            Response::Entity { components, .. } => {
+                assert_eq!(components["Named"], json!({ "name": "enemy" }));
+                assert!(!components.contains_key("Transform"));
+            }
+            response => panic!("Unexpected response {:?}", response),
+        }
+    }
+
+    #[test]
+    fn report_errors() {
+        let (res, components) = setup();
+        let response = handle_line(&res, &components, &[], "not json");
+        assert_eq!(response.id, 0);
+        match response.response {
+            Response::Error { .. } => {}
+            response => panic!("Unexpected response {:?}", response),
+        }
+
+        match request(
+            &res,
+            &components,
+            r#"{"id": 1, "type": "get_entity", "entity": 42}"#,
+        ) {
+            Response::Error { message } => assert_eq!(message, "Entity 42 does not exist"),
+            response => panic!("Unexpected response {:?}", response),
+        }
+    }
+}
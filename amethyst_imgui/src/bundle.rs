@@ -0,0 +1,86 @@
+//! ECS inspector bundle
+
+use amethyst_core::{
+    bundle::SystemBundle,
+    specs::{prelude::DispatcherBuilder, Component},
+    Named, Time, Transform,
+};
+use amethyst_error::Error;
+use shred::Resource;
+use winit::VirtualKeyCode;
+
+use crate::{
+    inspector::{ComponentAccess, InspectorSystem, ResourceAccess},
+    reflect::Reflect,
+};
+
+/// Inspector bundle
+///
+/// Adds the `InspectorSystem` as a thread local system, opening and closing the inspector with
+/// the toggle key, `F12` by default. The `Transform` and `Named` components and the `Time`
+/// resource are always shown, other components and resources are registered with
+/// `with_component` and `with_resource`.
+///
+/// The inspector is drawn by the `DrawImgui` pass, which must be added to the pipeline.
+///
+/// ## Errors
+///
+/// No errors will be returned by this bundle.
+pub struct InspectorBundle {
+    toggle_key: VirtualKeyCode,
+    components: Vec<ComponentAccess>,
+    resources: Vec<ResourceAccess>,
+}
+
+impl Default for InspectorBundle {
+    fn default() -> Self {
+        InspectorBundle {
+            toggle_key: VirtualKeyCode::F12,
+            components: vec![
+                ComponentAccess::new::<Named>("Named".to_string()),
+                ComponentAccess::new::<Transform>("Transform".to_string()),
+            ],
+            resources: vec![ResourceAccess::new::<Time>("Time".to_string())],
+        }
+    }
+}
+
+impl InspectorBundle {
+    /// Create a new inspector bundle.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set the key opening and closing the inspector.
+    pub fn with_toggle_key(mut self, key: VirtualKeyCode) -> Self {
+        self.toggle_key = key;
+        self
+    }
+
+    /// Show a component in the inspector, under the given name.
+    pub fn with_component<C, N>(mut self, name: N) -> Self
+    where
+        C: Component + Reflect + Send + Sync,
+        N: Into<String>,
+    {
+        self.components.push(ComponentAccess::new::<C>(name.into()));
+        self
+    }
+
+    /// Show a resource in the inspector, under the given name.
+    pub fn with_resource<R: Resource + Reflect, N: Into<String>>(mut self, name: N) -> Self {
+        self.resources.push(ResourceAccess::new::<R>(name.into()));
+        self
+    }
+}
+
+impl<'a, 'b> SystemBundle<'a, 'b> for InspectorBundle {
+    fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<(), Error> {
+        builder.add_thread_local(InspectorSystem::new(
+            self.toggle_key,
+            self.components,
+            self.resources,
+        ));
+        Ok(())
+    }
+}
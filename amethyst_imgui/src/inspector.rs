@@ -0,0 +1,348 @@
+//! The inspector resource, and the system updating it.
+
+use amethyst_core::{
+    shrev::{EventChannel, ReaderId},
+    specs::{
+        prelude::SystemData, Component, Entities, Entity, Join, ReadStorage, Resources, RunNow,
+        WriteStorage,
+    },
+    Named,
+};
+use shred::Resource;
+use winit::{
+    ElementState, Event, KeyboardInput, MouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent,
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::reflect::{Field, Reflect, Value};
+
+/// Resource holding the state of the inspector, drawn by the `DrawImgui` pass.
+///
+/// The inspector lists the living entities, and shows the components of the selected entity and
+/// the resources registered with the `InspectorBundle`. Games should ignore gameplay input while
+/// it `is_open`.
+#[derive(Default)]
+pub struct Inspector {
+    pub(crate) open: bool,
+    pub(crate) selected: Option<Entity>,
+    /// The entities, with their label
+    pub(crate) entities: Vec<(Entity, String)>,
+    /// The index in the registry, the name and the fields of the components of the selected entity
+    pub(crate) components: Vec<(usize, String, Vec<Field>)>,
+    /// The index in the registry, the name and the fields of the resources
+    pub(crate) resources: Vec<(usize, String, Vec<Field>)>,
+    pub(crate) edits: Vec<Edit>,
+    pub(crate) input: InputState,
+}
+
+impl Inspector {
+    /// Whether the inspector is shown and capturing the input.
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Shows or hides the inspector.
+    pub fn set_open(&mut self, open: bool) {
+        self.open = open;
+    }
+
+    /// The entity whose components are shown.
+    pub fn selected(&self) -> Option<Entity> {
+        self.selected
+    }
+
+    /// Selects the entity whose components are shown.
+    pub fn select(&mut self, entity: Option<Entity>) {
+        self.selected = entity;
+    }
+}
+
+/// A change made in the inspector, applied by the `InspectorSystem` on its next run.
+#[derive(Clone, Debug)]
+pub(crate) enum Edit {
+    Component {
+        entity: Entity,
+        index: usize,
+        field: &'static str,
+        value: Value,
+    },
+    Resource {
+        index: usize,
+        field: &'static str,
+        value: Value,
+    },
+}
+
+/// The input collected from the window events, given to imgui by the pass.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct InputState {
+    pub(crate) mouse_position: (f32, f32),
+    pub(crate) mouse_down: [bool; 3],
+    pub(crate) mouse_wheel: f32,
+    pub(crate) characters: Vec<char>,
+    pub(crate) keys: Vec<(VirtualKeyCode, bool)>,
+    pub(crate) ctrl: bool,
+    pub(crate) shift: bool,
+    pub(crate) alt: bool,
+}
+
+/// Access to the storage of a registered component, without knowing its type.
+pub(crate) struct ComponentAccess {
+    name: String,
+    setup: fn(&mut Resources),
+    fields: fn(&Resources, Entity) -> Option<Vec<Field>>,
+    set_field: fn(&Resources, Entity, &str, Value) -> bool,
+}
+
+impl ComponentAccess {
+    pub(crate) fn new<C>(name: String) -> Self
+    where
+        C: Component + Reflect + Send + Sync,
+    {
+        ComponentAccess {
+            name,
+            setup: setup_component::<C>,
+            fields: component_fields::<C>,
+            set_field: set_component_field::<C>,
+        }
+    }
+}
+
+/// Access to a registered resource, without knowing its type.
+pub(crate) struct ResourceAccess {
+    name: String,
+    fields: fn(&Resources) -> Option<Vec<Field>>,
+    set_field: fn(&Resources, &str, Value) -> bool,
+}
+
+impl ResourceAccess {
+    pub(crate) fn new<R: Resource + Reflect>(name: String) -> Self {
+        ResourceAccess {
+            name,
+            fields: resource_fields::<R>,
+            set_field: set_resource_field::<R>,
+        }
+    }
+}
+
+fn setup_component<C: Component + Reflect + Send + Sync>(res: &mut Resources) {
+    WriteStorage::<'_, C>::setup(res);
+}
+
+fn component_fields<C: Component + Reflect + Send + Sync>(
+    res: &Resources,
+    entity: Entity,
+) -> Option<Vec<Field>> {
+    ReadStorage::<'_, C>::fetch(res)
+        .get(entity)
+        .map(Reflect::fields)
+}
+
+fn set_component_field<C: Component + Reflect + Send + Sync>(
+    res: &Resources,
+    entity: Entity,
+    field: &str,
+    value: Value,
+) -> bool {
+    WriteStorage::<'_, C>::fetch(res)
+        .get_mut(entity)
+        .map_or(false, |component| component.set_field(field, value))
+}
+
+fn resource_fields<R: Resource + Reflect>(res: &Resources) -> Option<Vec<Field>> {
+    res.try_fetch::<R>().map(|resource| resource.fields())
+}
+
+fn set_resource_field<R: Resource + Reflect>(res: &Resources, field: &str, value: Value) -> bool {
+    res.try_fetch_mut::<R>()
+        .map_or(false, |mut resource| resource.set_field(field, value))
+}
+
+/// Applies the edits made in the inspector, refreshes the entities and fields it shows, and
+/// collects the input of the window for imgui.
+///
+/// The system is thread local, as it needs access to the storages of all the registered
+/// components and to the registered resources.
+pub struct InspectorSystem {
+    toggle_key: VirtualKeyCode,
+    components: Vec<ComponentAccess>,
+    resources: Vec<ResourceAccess>,
+    reader: Option<ReaderId<Event>>,
+}
+
+impl InspectorSystem {
+    pub(crate) fn new(
+        toggle_key: VirtualKeyCode,
+        components: Vec<ComponentAccess>,
+        resources: Vec<ResourceAccess>,
+    ) -> Self {
+        InspectorSystem {
+            toggle_key,
+            components,
+            resources,
+            reader: None,
+        }
+    }
+
+    fn handle_input(&mut self, res: &Resources) {
+        let events = res.fetch::<EventChannel<Event>>();
+        let mut inspector = res.fetch_mut::<Inspector>();
+        let reader = self
+            .reader
+            .as_mut()
+            .expect("`InspectorSystem::setup` was not called before `InspectorSystem::run_now`");
+        let input = &mut inspector.input;
+        input.mouse_wheel = 0.0;
+        input.characters.clear();
+        input.keys.clear();
+        let mut toggle = false;
+        for event in events.read(reader) {
+            let event = match *event {
+                Event::WindowEvent { ref event, .. } => event,
+                _ => continue,
+            };
+            match *event {
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            state,
+                            virtual_keycode: Some(key),
+                            modifiers,
+                            ..
+                        },
+                    ..
+                } => {
+                    let pressed = state == ElementState::Pressed;
+                    if key == self.toggle_key {
+                        toggle ^= pressed;
+                        continue;
+                    }
+                    input.keys.push((key, pressed));
+                    input.ctrl = modifiers.ctrl;
+                    input.shift = modifiers.shift;
+                    input.alt = modifiers.alt;
+                }
+                WindowEvent::ReceivedCharacter(c) if !c.is_control() => {
+                    input.characters.push(c);
+                }
+                WindowEvent::CursorMoved { position, .. } => {
+                    input.mouse_position = (position.x as f32, position.y as f32);
+                }
+                WindowEvent::MouseInput { state, button, .. } => {
+                    let index = match button {
+                        MouseButton::Left => 0,
+                        MouseButton::Right => 1,
+                        MouseButton::Middle => 2,
+                        MouseButton::Other(_) => continue,
+                    };
+                    input.mouse_down[index] = state == ElementState::Pressed;
+                }
+                WindowEvent::MouseWheel { delta, .. } => {
+                    input.mouse_wheel += match delta {
+                        MouseScrollDelta::LineDelta(_, y) => y,
+                        MouseScrollDelta::PixelDelta(position) => position.y as f32 / 20.0,
+                    };
+                }
+                _ => {}
+            }
+        }
+        if toggle {
+            inspector.open = !inspector.open;
+        }
+    }
+
+    fn apply_edits(&self, res: &Resources) {
+        let edits = std::mem::replace(&mut res.fetch_mut::<Inspector>().edits, Vec::new());
+        for edit in edits {
+            match edit {
+                Edit::Component {
+                    entity,
+                    index,
+                    field,
+                    value,
+                } => {
+                    if let Some(component) = self.components.get(index) {
+                        (component.set_field)(res, entity, field, value);
+                    }
+                }
+                Edit::Resource {
+                    index,
+                    field,
+                    value,
+                } => {
+                    if let Some(resource) = self.resources.get(index) {
+                        (resource.set_field)(res, field, value);
+                    }
+                }
+            }
+        }
+    }
+
+    fn refresh(&self, res: &Resources) {
+        let (entities, names) = <(Entities<'_>, ReadStorage<'_, Named>)>::fetch(res);
+        let mut inspector = res.fetch_mut::<Inspector>();
+
+        inspector.entities.clear();
+        for (entity, name) in (&*entities, (&names).maybe()).join() {
+            let label = match name {
+                Some(name) => format!("{} ({})", name.name, entity.id()),
+                None => format!("Entity {}", entity.id()),
+            };
+            inspector.entities.push((entity, label));
+        }
+
+        if let Some(selected) = inspector.selected {
+            if !entities.is_alive(selected) {
+                inspector.selected = None;
+            }
+        }
+        inspector.components = match inspector.selected {
+            Some(selected) => self
+                .components
+                .iter()
+                .enumerate()
+                .filter_map(|(index, component)| {
+                    (component.fields)(res, selected)
+                        .map(|fields| (index, component.name.clone(), fields))
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+        inspector.resources = self
+            .resources
+            .iter()
+            .enumerate()
+            .filter_map(|(index, resource)| {
+                (resource.fields)(res).map(|fields| (index, resource.name.clone(), fields))
+            })
+            .collect();
+    }
+}
+
+impl<'a> RunNow<'a> for InspectorSystem {
+    fn run_now(&mut self, res: &'a Resources) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("inspector_system");
+
+        self.handle_input(res);
+        self.apply_edits(res);
+        if res.fetch::<Inspector>().is_open() {
+            self.refresh(res);
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        ReadStorage::<'_, Named>::setup(res);
+        res.entry::<Inspector>().or_insert_with(Inspector::default);
+        for component in &self.components {
+            (component.setup)(res);
+        }
+        self.reader = Some(
+            res.entry::<EventChannel<Event>>()
+                .or_insert_with(EventChannel::default)
+                .register_reader(),
+        );
+    }
+}
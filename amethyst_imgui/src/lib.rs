@@ -0,0 +1,22 @@
+//! Amethyst imgui crate, drawing an inspector of the entities, components and resources of the
+//! world over the game, to debug and tweak it during play.
+//!
+//! The `InspectorBundle` adds the `InspectorSystem`, which keeps the `Inspector` resource up to
+//! date and applies the edits made in it, and the `DrawImgui` pass draws it. Components and
+//! resources are shown by implementing `Reflect`, and registering them with the bundle.
+
+#![warn(missing_docs, rust_2018_idioms, rust_2018_compatibility)]
+
+pub use imgui;
+
+pub use self::{
+    bundle::InspectorBundle,
+    inspector::{Inspector, InspectorSystem},
+    pass::DrawImgui,
+    reflect::{Field, Reflect, Value},
+};
+
+mod bundle;
+mod inspector;
+mod pass;
+mod reflect;
@@ -0,0 +1,303 @@
+//! The pass drawing the inspector with imgui.
+
+use amethyst_core::{
+    specs::{Entity, Read, ReadExpect, Write},
+    Time,
+};
+use amethyst_error::Error;
+use amethyst_renderer::{
+    pipe::{
+        pass::{Pass, PassData},
+        Effect, NewEffect,
+    },
+    Encoder, Factory, Resources, ScreenDimensions,
+};
+use gfx::memory::Typed;
+use imgui::{im_str, FrameSize, ImGui, ImGuiCond, ImGuiKey, ImGuiSelectableFlags, ImString, Ui};
+use imgui_gfx_renderer::{Renderer, Shaders};
+use log::error;
+use winit::VirtualKeyCode;
+
+use crate::{
+    inspector::{Edit, InputState, Inspector},
+    reflect::{Field, Value},
+};
+
+/// The pass only draws through the imgui renderer, the effect is compiled to get the render
+/// targets of the stage.
+const VERT_SRC: &[u8] = b"
+#version 150 core
+void main() {
+    gl_Position = vec4(0.0);
+}
+";
+
+const FRAG_SRC: &[u8] = b"
+#version 150 core
+out vec4 color;
+void main() {
+    color = vec4(0.0);
+}
+";
+
+/// Keys given to imgui, indexed by their position.
+const KEYS: [(ImGuiKey, VirtualKeyCode); 19] = [
+    (ImGuiKey::Tab, VirtualKeyCode::Tab),
+    (ImGuiKey::LeftArrow, VirtualKeyCode::Left),
+    (ImGuiKey::RightArrow, VirtualKeyCode::Right),
+    (ImGuiKey::UpArrow, VirtualKeyCode::Up),
+    (ImGuiKey::DownArrow, VirtualKeyCode::Down),
+    (ImGuiKey::PageUp, VirtualKeyCode::PageUp),
+    (ImGuiKey::PageDown, VirtualKeyCode::PageDown),
+    (ImGuiKey::Home, VirtualKeyCode::Home),
+    (ImGuiKey::End, VirtualKeyCode::End),
+    (ImGuiKey::Delete, VirtualKeyCode::Delete),
+    (ImGuiKey::Backspace, VirtualKeyCode::Back),
+    (ImGuiKey::Enter, VirtualKeyCode::Return),
+    (ImGuiKey::Escape, VirtualKeyCode::Escape),
+    (ImGuiKey::A, VirtualKeyCode::A),
+    (ImGuiKey::C, VirtualKeyCode::C),
+    (ImGuiKey::V, VirtualKeyCode::V),
+    (ImGuiKey::X, VirtualKeyCode::X),
+    (ImGuiKey::Y, VirtualKeyCode::Y),
+    (ImGuiKey::Z, VirtualKeyCode::Z),
+];
+
+/// Draws the `Inspector` with imgui, when it is open.
+///
+/// Should be the last pass of the stage drawing to the screen, so the inspector is drawn over the
+/// rest of the frame.
+pub struct DrawImgui {
+    imgui: ImGui,
+    renderer: Option<Renderer<Resources>>,
+}
+
+impl DrawImgui {
+    /// Create instance of `DrawImgui` pass
+    pub fn new() -> Self {
+        let mut imgui = ImGui::init();
+        imgui.set_ini_filename(None);
+        for (index, &(key, _)) in KEYS.iter().enumerate() {
+            imgui.set_imgui_key(key, index as u8);
+        }
+        DrawImgui {
+            imgui,
+            renderer: None,
+        }
+    }
+}
+
+impl Default for DrawImgui {
+    fn default() -> Self {
+        DrawImgui::new()
+    }
+}
+
+impl<'a> PassData<'a> for DrawImgui {
+    type Data = (
+        Write<'a, Inspector>,
+        ReadExpect<'a, ScreenDimensions>,
+        Read<'a, Time>,
+    );
+}
+
+impl Pass for DrawImgui {
+    fn compile(&mut self, mut effect: NewEffect<'_>) -> Result<Effect, Error> {
+        effect
+            .simple(VERT_SRC, FRAG_SRC)
+            .with_output("color", None)
+            .build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        mut factory: Factory,
+        (mut inspector, dimensions, time): <Self as PassData<'a>>::Data,
+    ) {
+        if !inspector.is_open() {
+            return;
+        }
+        let target = match effect.data.out_colors.first() {
+            Some(target) => Typed::new(target.clone()),
+            None => return,
+        };
+        match self.renderer {
+            Some(ref mut renderer) => renderer.update_render_target(target),
+            None => match Renderer::init(&mut self.imgui, &mut factory, Shaders::GlSl150, target) {
+                Ok(renderer) => self.renderer = Some(renderer),
+                Err(e) => {
+                    error!("Failed to initialize the imgui renderer: {:?}", e);
+                    inspector.set_open(false);
+                    return;
+                }
+            },
+        }
+
+        feed_input(&mut self.imgui, &inspector.input);
+        let hidpi = dimensions.hidpi_factor();
+        let size = FrameSize::new(
+            f64::from(dimensions.width()) / hidpi,
+            f64::from(dimensions.height()) / hidpi,
+            hidpi,
+        );
+        let ui = self.imgui.frame(size, time.delta_real_seconds());
+        let (selected, edits) = draw_inspector(&ui, &inspector);
+        inspector.selected = selected;
+        inspector.edits.extend(edits);
+
+        let renderer = self.renderer.as_mut().expect("Unreachable");
+        if let Err(e) = renderer.render(ui, &mut factory, encoder) {
+            error!("Failed to draw the inspector: {:?}", e);
+        }
+    }
+}
+
+fn feed_input(imgui: &mut ImGui, input: &InputState) {
+    imgui.set_mouse_pos(input.mouse_position.0, input.mouse_position.1);
+    imgui.set_mouse_down([
+        input.mouse_down[0],
+        input.mouse_down[1],
+        input.mouse_down[2],
+        false,
+        false,
+    ]);
+    imgui.set_mouse_wheel(input.mouse_wheel);
+    imgui.set_key_ctrl(input.ctrl);
+    imgui.set_key_shift(input.shift);
+    imgui.set_key_alt(input.alt);
+    for &(key, pressed) in &input.keys {
+        if let Some(index) = KEYS.iter().position(|&(_, code)| code == key) {
+            imgui.set_key(index as u8, pressed);
+        }
+    }
+    for &c in &input.characters {
+        imgui.add_input_character(c);
+    }
+}
+
+/// Draws the windows of the inspector, returns the selected entity and the edits.
+fn draw_inspector(ui: &Ui<'_>, inspector: &Inspector) -> (Option<Entity>, Vec<Edit>) {
+    let mut selected = inspector.selected;
+    let mut edits = Vec::new();
+
+    ui.window(im_str!("Entities"))
+        .position((10.0, 10.0), ImGuiCond::FirstUseEver)
+        .size((250.0, 400.0), ImGuiCond::FirstUseEver)
+        .build(|| {
+            for &(entity, ref label) in &inspector.entities {
+                let label = ImString::new(label.clone());
+                if ui.selectable(
+                    &label,
+                    selected == Some(entity),
+                    ImGuiSelectableFlags::empty(),
+                    (0.0, 0.0),
+                ) {
+                    selected = Some(entity);
+                }
+            }
+        });
+
+    ui.window(im_str!("Components"))
+        .position((270.0, 10.0), ImGuiCond::FirstUseEver)
+        .size((350.0, 400.0), ImGuiCond::FirstUseEver)
+        .build(|| {
+            let entity = match inspector.selected {
+                Some(entity) => entity,
+                None => {
+                    ui.text("No entity selected");
+                    return;
+                }
+            };
+            for &(index, ref name, ref fields) in &inspector.components {
+                if ui
+                    .collapsing_header(&ImString::new(name.clone()))
+                    .default_open(true)
+                    .build()
+                {
+                    for field in fields {
+                        if let Some(value) = draw_field(ui, name, field) {
+                            edits.push(Edit::Component {
+                                entity,
+                                index,
+                                field: field.name,
+                                value,
+                            });
+                        }
+                    }
+                }
+            }
+        });
+
+    ui.window(im_str!("Resources"))
+        .position((630.0, 10.0), ImGuiCond::FirstUseEver)
+        .size((350.0, 400.0), ImGuiCond::FirstUseEver)
+        .build(|| {
+            for &(index, ref name, ref fields) in &inspector.resources {
+                if ui
+                    .collapsing_header(&ImString::new(name.clone()))
+                    .default_open(true)
+                    .build()
+                {
+                    for field in fields {
+                        if let Some(value) = draw_field(ui, name, field) {
+                            edits.push(Edit::Resource {
+                                index,
+                                field: field.name,
+                                value,
+                            });
+                        }
+                    }
+                }
+            }
+        });
+
+    (selected, edits)
+}
+
+/// Draws a field, returns its new value if it was edited.
+fn draw_field(ui: &Ui<'_>, owner: &str, field: &Field) -> Option<Value> {
+    if !field.editable {
+        ui.text(format!("{}: {}", field.name, field.value));
+        return None;
+    }
+    // The part after `##` is only used by imgui to tell the widgets apart.
+    let label = ImString::new(format!("{}##{}.{}", field.name, owner, field.name));
+    match field.value {
+        Value::Bool(mut value) => {
+            if ui.checkbox(&label, &mut value) {
+                return Some(Value::Bool(value));
+            }
+        }
+        Value::Int(value) => {
+            let mut value = value as i32;
+            if ui.input_int(&label, &mut value).build() {
+                return Some(Value::Int(i64::from(value)));
+            }
+        }
+        Value::Float(mut value) => {
+            if ui.input_float(&label, &mut value).build() {
+                return Some(Value::Float(value));
+            }
+        }
+        Value::Text(ref value) => {
+            let mut text = ImString::with_capacity(value.len() + 64);
+            text.push_str(value);
+            if ui.input_text(&label, &mut text).build() {
+                return Some(Value::Text(text.to_str().to_string()));
+            }
+        }
+        Value::Vector3(mut value) => {
+            if ui.input_float3(&label, &mut value).build() {
+                return Some(Value::Vector3(value));
+            }
+        }
+        Value::Color(mut value) => {
+            if ui.input_float4(&label, &mut value).build() {
+                return Some(Value::Color(value));
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,180 @@
+//! Fields of the components and resources shown by the inspector.
+
+use std::fmt::{self, Display};
+
+use amethyst_core::{nalgebra::Vector3, Named, Time, Transform};
+
+/// Value of a field shown by the inspector.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// A boolean, edited with a checkbox.
+    Bool(bool),
+    /// An integer.
+    Int(i64),
+    /// A floating point number.
+    Float(f32),
+    /// A string.
+    Text(String),
+    /// A vector of three numbers.
+    Vector3([f32; 3]),
+    /// A color, as RGBA.
+    Color([f32; 4]),
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{:.3}", value),
+            Value::Text(ref value) => write!(f, "{}", value),
+            Value::Vector3([x, y, z]) => write!(f, "[{:.3}, {:.3}, {:.3}]", x, y, z),
+            Value::Color([r, g, b, a]) => write!(f, "[{:.3}, {:.3}, {:.3}, {:.3}]", r, g, b, a),
+        }
+    }
+}
+
+/// A field of a component or resource.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field {
+    /// Name of the field.
+    pub name: &'static str,
+    /// Current value of the field.
+    pub value: Value,
+    /// Whether the field can be edited from the inspector.
+    pub editable: bool,
+}
+
+impl Field {
+    /// Creates an editable field.
+    pub fn new(name: &'static str, value: Value) -> Self {
+        Field {
+            name,
+            value,
+            editable: true,
+        }
+    }
+
+    /// Creates a field that is only shown.
+    pub fn read_only(name: &'static str, value: Value) -> Self {
+        Field {
+            name,
+            value,
+            editable: false,
+        }
+    }
+}
+
+/// A component or resource whose fields can be shown and edited by the inspector.
+pub trait Reflect {
+    /// The fields of the value, in the order they are shown.
+    fn fields(&self) -> Vec<Field>;
+
+    /// Sets a field, returns `false` if the field is unknown or read-only, or if the value has the
+    /// wrong type, in which case the value must be left unchanged.
+    fn set_field(&mut self, _name: &str, _value: Value) -> bool {
+        false
+    }
+}
+
+/// Fields: the translation, the rotation as euler angles in degrees, and the scale.
+impl Reflect for Transform {
+    fn fields(&self) -> Vec<Field> {
+        let (x, y, z) = self.rotation().euler_angles();
+        vec![
+            Field::new("translation", Value::Vector3((*self.translation()).into())),
+            Field::new(
+                "rotation",
+                Value::Vector3([x.to_degrees(), y.to_degrees(), z.to_degrees()]),
+            ),
+            Field::new("scale", Value::Vector3((*self.scale()).into())),
+        ]
+    }
+
+    fn set_field(&mut self, name: &str, value: Value) -> bool {
+        let [x, y, z] = match value {
+            Value::Vector3(value) if value.iter().all(|v| v.is_finite()) => value,
+            _ => return false,
+        };
+        match name {
+            "translation" => *self.translation_mut() = Vector3::new(x, y, z),
+            "rotation" => {
+                self.set_rotation_euler(x.to_radians(), y.to_radians(), z.to_radians());
+            }
+            "scale" => *self.scale_mut() = Vector3::new(x, y, z),
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl Reflect for Named {
+    fn fields(&self) -> Vec<Field> {
+        vec![Field::new("name", Value::Text(self.name.to_string()))]
+    }
+
+    fn set_field(&mut self, name: &str, value: Value) -> bool {
+        match (name, value) {
+            ("name", Value::Text(text)) => {
+                self.name = text.into();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Only the time scale can be edited.
+impl Reflect for Time {
+    fn fields(&self) -> Vec<Field> {
+        vec![
+            Field::read_only("frame_number", Value::Int(self.frame_number() as i64)),
+            Field::read_only("delta_seconds", Value::Float(self.delta_seconds())),
+            Field::read_only(
+                "absolute_time_seconds",
+                Value::Float(self.absolute_time_seconds() as f32),
+            ),
+            Field::new("time_scale", Value::Float(self.time_scale())),
+        ]
+    }
+
+    fn set_field(&mut self, name: &str, value: Value) -> bool {
+        match (name, value) {
+            ("time_scale", Value::Float(scale)) if scale.is_finite() && scale >= 0.0 => {
+                self.set_time_scale(scale);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transform_fields() {
+        let mut transform = Transform::default();
+        transform.set_xyz(1.0, 2.0, 3.0);
+        assert_eq!(transform.fields()[0].value, Value::Vector3([1.0, 2.0, 3.0]));
+
+        assert!(transform.set_field("rotation", Value::Vector3([0.0, 90.0, 0.0])));
+        match transform.fields()[1].value {
+            Value::Vector3([_, y, _]) => assert!((y - 90.0).abs() < 1e-3),
+            ref value => panic!("Unexpected rotation {:?}", value),
+        }
+
+        assert!(!transform.set_field("scale", Value::Float(2.0)));
+        assert!(!transform.set_field("scale", Value::Vector3([std::f32::NAN, 1.0, 1.0])));
+        assert_eq!(transform.scale(), &Vector3::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn read_only_time_fields() {
+        let mut time = Time::default();
+        assert!(!time.set_field("delta_seconds", Value::Float(1.0)));
+        assert!(time.set_field("time_scale", Value::Float(0.5)));
+        assert_eq!(time.time_scale(), 0.5);
+    }
+}
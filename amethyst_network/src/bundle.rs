@@ -1,11 +1,21 @@
-use std::net::SocketAddr;
+use std::{marker::PhantomData, net::SocketAddr};
 
 use serde::{de::DeserializeOwned, Serialize};
 
 use amethyst_core::{bundle::SystemBundle, shred::DispatcherBuilder};
 use amethyst_error::{Error, ResultExt};
 
-use crate::{filter::NetFilter, server::ServerConfig, NetSocketSystem};
+use crate::{
+    codec::NetCodec,
+    filter::NetFilter,
+    replication::{
+        ComponentApplySystem, ComponentDiffSystem, NetworkIdSystem, ReplicatedComponent,
+        ReplicationClientSystem, ReplicationServerSystem,
+    },
+    server::ServerConfig,
+    session::{SessionClientSystem, SessionServerSystem},
+    NetSocketSystem,
+};
 
 /// A convenience bundle to create the infrastructure needed to send and receive network messages.
 pub struct NetworkBundle<T> {
@@ -14,6 +24,9 @@ pub struct NetworkBundle<T> {
 
     /// The filters applied on received network events.
     filters: Vec<Box<dyn NetFilter<T>>>,
+
+    /// The codec used to serialize the network events, if not the default.
+    codec: Option<Box<dyn NetCodec<T>>>,
 }
 
 impl<T> NetworkBundle<T> {
@@ -29,10 +42,37 @@ impl<T> NetworkBundle<T> {
         let config = ServerConfig {
             udp_recv_addr: receive_addr,
             udp_send_addr: send_addr,
+            tcp_addr: None,
+            websocket_addr: None,
             max_throughput: 5000,
         };
 
-        NetworkBundle { config, filters }
+        NetworkBundle {
+            config,
+            filters,
+            codec: None,
+        }
+    }
+
+    /// Sets the codec used to serialize and deserialize the network events.
+    pub fn with_codec<C>(mut self, codec: C) -> Self
+    where
+        C: NetCodec<T> + 'static,
+    {
+        self.codec = Some(Box::new(codec));
+        self
+    }
+
+    /// Enables the TCP transport, listening for incoming streams on `tcp_addr`.
+    pub fn with_tcp(mut self, tcp_addr: SocketAddr) -> Self {
+        self.config.tcp_addr = Some(tcp_addr);
+        self
+    }
+
+    /// Enables the WebSocket transport, listening for incoming connections on `websocket_addr`.
+    pub fn with_websocket(mut self, websocket_addr: SocketAddr) -> Self {
+        self.config.websocket_addr = Some(websocket_addr);
+        self
     }
 }
 
@@ -42,11 +82,255 @@ where
 {
     /// Build the networking bundle by adding the networking system to the application.
     fn build(self, builder: &mut DispatcherBuilder<'_, '_>) -> Result<(), Error> {
-        let socket_system = NetSocketSystem::<T>::new(self.config, self.filters)
+        let mut socket_system = NetSocketSystem::<T>::new(self.config, self.filters)
             .with_context(|_| Error::from_string("Failed to open network system."))?;
+        if let Some(codec) = self.codec {
+            socket_system.codec = codec;
+        }
 
         builder.add(socket_system, "net_socket", &[]);
 
         Ok(())
     }
 }
+
+type AddComponentSystem = fn(&mut DispatcherBuilder<'_, '_>, &str, &[&str]);
+
+fn add_diff_system<C>(builder: &mut DispatcherBuilder<'_, '_>, name: &str, dep: &[&str])
+where
+    C: ReplicatedComponent,
+{
+    builder.add(ComponentDiffSystem::<C>::new(), name, dep);
+}
+
+fn add_apply_system<C>(builder: &mut DispatcherBuilder<'_, '_>, name: &str, dep: &[&str])
+where
+    C: ReplicatedComponent,
+{
+    builder.add(ComponentApplySystem::<C>::new(), name, dep);
+}
+
+/// Bundle replicating entities from the server to the clients.
+///
+/// Adds the `NetworkIdSystem`, a `ComponentDiffSystem` for every replicated component, and the
+/// `ReplicationServerSystem`. The systems should run after the game logic of the server.
+///
+/// ### Type parameters:
+///
+/// - `E`: the custom event type of the `NetConnection`s
+pub struct ReplicationServerBundle<'a, E> {
+    dep: &'a [&'a str],
+    components: Vec<(&'static str, AddComponentSystem)>,
+    _marker: PhantomData<E>,
+}
+
+impl<'a, E> ReplicationServerBundle<'a, E> {
+    /// Creates a new `ReplicationServerBundle`.
+    pub fn new() -> Self {
+        ReplicationServerBundle {
+            dep: &[],
+            components: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Set dependencies for the replication systems.
+    pub fn with_dep(mut self, dep: &'a [&'a str]) -> Self {
+        self.dep = dep;
+        self
+    }
+
+    /// Replicate the component `C` of all replicated entities.
+    pub fn with_component<C>(mut self) -> Self
+    where
+        C: ReplicatedComponent,
+    {
+        self.components
+            .push((C::NAME, add_diff_system::<C> as AddComponentSystem));
+        self
+    }
+}
+
+impl<'a, E> Default for ReplicationServerBundle<'a, E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, 'b, 'c, E> SystemBundle<'a, 'b> for ReplicationServerBundle<'c, E>
+where
+    E: Send + Sync + 'static,
+{
+    fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<(), Error> {
+        builder.add(NetworkIdSystem::new(), "replication_network_id", self.dep);
+
+        let mut diff_systems = vec!["replication_network_id".to_string()];
+        for (component, add_system) in self.components {
+            let name = format!("replication_diff_{}", component);
+            add_system(builder, &name, &["replication_network_id"]);
+            diff_systems.push(name);
+        }
+
+        let dep = diff_systems.iter().map(String::as_str).collect::<Vec<_>>();
+        builder.add(
+            ReplicationServerSystem::<E>::new(),
+            "replication_server",
+            &dep,
+        );
+
+        Ok(())
+    }
+}
+
+/// Bundle applying the entities replicated by the server.
+///
+/// Adds the `ReplicationClientSystem`, and a `ComponentApplySystem` for every replicated
+/// component. The systems should run after the `NetSocketSystem`.
+///
+/// ### Type parameters:
+///
+/// - `E`: the custom event type of the `NetConnection`s
+pub struct ReplicationClientBundle<'a, E> {
+    dep: &'a [&'a str],
+    components: Vec<(&'static str, AddComponentSystem)>,
+    _marker: PhantomData<E>,
+}
+
+impl<'a, E> ReplicationClientBundle<'a, E> {
+    /// Creates a new `ReplicationClientBundle`.
+    pub fn new() -> Self {
+        ReplicationClientBundle {
+            dep: &[],
+            components: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Set dependencies for the replication systems.
+    pub fn with_dep(mut self, dep: &'a [&'a str]) -> Self {
+        self.dep = dep;
+        self
+    }
+
+    /// Apply the replicated component `C`.
+    pub fn with_component<C>(mut self) -> Self
+    where
+        C: ReplicatedComponent,
+    {
+        self.components
+            .push((C::NAME, add_apply_system::<C> as AddComponentSystem));
+        self
+    }
+}
+
+impl<'a, E> Default for ReplicationClientBundle<'a, E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, 'b, 'c, E> SystemBundle<'a, 'b> for ReplicationClientBundle<'c, E>
+where
+    E: Send + Sync + 'static,
+{
+    fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<(), Error> {
+        builder.add(
+            ReplicationClientSystem::<E>::new(),
+            "replication_client",
+            self.dep,
+        );
+
+        for (component, add_system) in self.components {
+            let name = format!("replication_apply_{}", component);
+            add_system(builder, &name, &["replication_client"]);
+        }
+
+        Ok(())
+    }
+}
+
+/// Adds the `SessionServerSystem`, which accepts clients joining with the given protocol version.
+pub struct SessionServerBundle<'a, E> {
+    protocol_version: u32,
+    max_clients: Option<usize>,
+    dep: &'a [&'a str],
+    _marker: PhantomData<E>,
+}
+
+impl<'a, E> SessionServerBundle<'a, E> {
+    /// Creates a new `SessionServerBundle`.
+    pub fn new(protocol_version: u32) -> Self {
+        SessionServerBundle {
+            protocol_version,
+            max_clients: None,
+            dep: &[],
+            _marker: PhantomData,
+        }
+    }
+
+    /// Set dependencies for the session system.
+    pub fn with_dep(mut self, dep: &'a [&'a str]) -> Self {
+        self.dep = dep;
+        self
+    }
+
+    /// Limits the number of joined clients.
+    pub fn with_max_clients(mut self, max_clients: usize) -> Self {
+        self.max_clients = Some(max_clients);
+        self
+    }
+}
+
+impl<'a, 'b, 'c, E> SystemBundle<'a, 'b> for SessionServerBundle<'c, E>
+where
+    E: Clone + Send + Sync + 'static,
+{
+    fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<(), Error> {
+        let mut system = SessionServerSystem::<E>::new(self.protocol_version);
+        if let Some(max_clients) = self.max_clients {
+            system = system.with_max_clients(max_clients);
+        }
+        builder.add(system, "session_server", self.dep);
+        Ok(())
+    }
+}
+
+/// Adds the `SessionClientSystem`, which joins the server on every new connection.
+pub struct SessionClientBundle<'a, E> {
+    protocol_version: u32,
+    name: String,
+    dep: &'a [&'a str],
+    _marker: PhantomData<E>,
+}
+
+impl<'a, E> SessionClientBundle<'a, E> {
+    /// Creates a new `SessionClientBundle`, joining with the given protocol version and name.
+    pub fn new<S: Into<String>>(protocol_version: u32, name: S) -> Self {
+        SessionClientBundle {
+            protocol_version,
+            name: name.into(),
+            dep: &[],
+            _marker: PhantomData,
+        }
+    }
+
+    /// Set dependencies for the session system.
+    pub fn with_dep(mut self, dep: &'a [&'a str]) -> Self {
+        self.dep = dep;
+        self
+    }
+}
+
+impl<'a, 'b, 'c, E> SystemBundle<'a, 'b> for SessionClientBundle<'c, E>
+where
+    E: Clone + Send + Sync + 'static,
+{
+    fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<(), Error> {
+        builder.add(
+            SessionClientSystem::<E>::new(self.protocol_version, self.name),
+            "session_client",
+            self.dep,
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+//! Serialization formats of the events sent over the network.
+
+use std::io::{Read, Write};
+
+use bincode::{deserialize, serialize};
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    error::{Error, Result},
+    NetEvent,
+};
+
+/// The maximal size of a decompressed message, larger messages are rejected.
+pub const MAX_DECOMPRESSED_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Serializes and deserializes the `NetEvent`s sent over the network.
+///
+/// Both ends of a connection have to use the same codec.
+pub trait NetCodec<E>: Send + Sync {
+    /// Serializes an event.
+    fn encode(&self, event: &NetEvent<E>) -> Result<Vec<u8>>;
+
+    /// Deserializes an event.
+    fn decode(&self, data: &[u8]) -> Result<NetEvent<E>>;
+}
+
+/// Compact binary format using `bincode`, this is the default codec.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BincodeCodec;
+
+impl<E> NetCodec<E> for BincodeCodec
+where
+    E: Serialize + DeserializeOwned,
+{
+    fn encode(&self, event: &NetEvent<E>) -> Result<Vec<u8>> {
+        Ok(serialize(event)?)
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<NetEvent<E>> {
+        Ok(deserialize(data)?)
+    }
+}
+
+/// Human readable format using JSON, for servers written in other languages and for debugging.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct JsonCodec;
+
+impl<E> NetCodec<E> for JsonCodec
+where
+    E: Serialize + DeserializeOwned,
+{
+    fn encode(&self, event: &NetEvent<E>) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(event)?)
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<NetEvent<E>> {
+        Ok(serde_json::from_slice(data)?)
+    }
+}
+
+/// Compresses the output of another codec with deflate.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CompressedCodec<C> {
+    codec: C,
+    level: u32,
+}
+
+impl<C> CompressedCodec<C> {
+    /// Wraps the codec, using the default compression level.
+    pub fn new(codec: C) -> Self {
+        Self::with_level(codec, 6)
+    }
+
+    /// Wraps the codec, using a compression level between 0 (fastest) and 9 (smallest).
+    pub fn with_level(codec: C, level: u32) -> Self {
+        CompressedCodec {
+            codec,
+            level: level.min(9),
+        }
+    }
+}
+
+impl<C, E> NetCodec<E> for CompressedCodec<C>
+where
+    C: NetCodec<E>,
+{
+    fn encode(&self, event: &NetEvent<E>) -> Result<Vec<u8>> {
+        let data = self.codec.encode(event)?;
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::new(self.level));
+        encoder.write_all(&data)?;
+        Ok(encoder.finish()?)
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<NetEvent<E>> {
+        let mut decompressed = Vec::new();
+        DeflateDecoder::new(data)
+            .take(MAX_DECOMPRESSED_SIZE + 1)
+            .read_to_end(&mut decompressed)?;
+        if decompressed.len() as u64 > MAX_DECOMPRESSED_SIZE {
+            return Err(Error::MessageTooLarge(decompressed.len()));
+        }
+        self.codec.decode(&decompressed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<C: NetCodec<String>>(codec: C) {
+        let event = NetEvent::Custom("hello".to_string());
+        let data = codec.encode(&event).unwrap();
+        assert_eq!(codec.decode(&data).unwrap(), event);
+    }
+
+    #[test]
+    fn codecs_round_trip() {
+        round_trip(BincodeCodec);
+        round_trip(JsonCodec);
+        round_trip(CompressedCodec::new(JsonCodec));
+    }
+
+    #[test]
+    fn compressed_codec_rejects_garbage() {
+        let codec = CompressedCodec::new(BincodeCodec);
+        assert!(NetCodec::<String>::decode(&codec, &[1, 2, 3]).is_err());
+    }
+}
@@ -1,13 +1,18 @@
 //! Network Connection and states.
 
+use log::error;
 use serde::{Deserialize, Serialize};
 use shrev::{EventChannel, EventIterator, ReaderId};
-use std::net::SocketAddr;
+use std::{net::SocketAddr, time::Instant};
 use uuid::Uuid;
 
 use amethyst_core::specs::{Component, VecStorage};
 
-use crate::NetEvent;
+use crate::{
+    codec::NetCodec,
+    reliability::{DeliveryRequirement, ReliabilityConfig, ReliableEndpoint},
+    NetEvent,
+};
 
 // TODO: Think about relationship between NetConnection and NetIdentity.
 
@@ -21,7 +26,11 @@ pub struct NetConnection<E: 'static> {
     pub target_sender: SocketAddr,
     /// The state of the connection.
     pub state: ConnectionState,
+    /// The transport used to exchange events with the remote end.
+    pub transport: Transport,
     /// The buffer of events to be sent.
+    ///
+    /// Events written here are sent unreliably, use `send_with` to choose the delivery guarantee.
     #[serde(skip)]
     pub send_buffer: EventChannel<NetEvent<E>>,
     /// The buffer of events that have been received.
@@ -30,6 +39,12 @@ pub struct NetConnection<E: 'static> {
     /// Private. Used by `NetSocketSystem` to be able to immediately send events upon receiving a new NetConnection.
     #[serde(skip)]
     send_reader: ReaderId<NetEvent<E>>,
+    /// Events queued with an explicit delivery guarantee.
+    #[serde(skip)]
+    send_queue: Vec<(NetEvent<E>, DeliveryRequirement)>,
+    /// Sequencing, acknowledgement and fragmentation state of the connection.
+    #[serde(skip)]
+    endpoint: ReliableEndpoint,
 }
 
 impl<E: Send + Sync + 'static> NetConnection<E> {
@@ -42,12 +57,45 @@ impl<E: Send + Sync + 'static> NetConnection<E> {
             target_receiver,
             target_sender,
             state: ConnectionState::Connecting,
+            transport: Transport::Udp,
             send_buffer,
             receive_buffer: EventChannel::<NetEvent<E>>::new(),
             send_reader,
+            send_queue: Vec::new(),
+            endpoint: ReliableEndpoint::new(ReliabilityConfig::default(), Instant::now()),
         }
     }
 
+    /// Sets the transport used by this connection.
+    ///
+    /// TCP and WebSocket connections are always reliable and ordered, the reliability layer is not
+    /// used for them.
+    pub fn with_transport(mut self, transport: Transport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Sets the configuration of the reliability layer, like heartbeat interval and timeout.
+    pub fn with_reliability_config(mut self, config: ReliabilityConfig) -> Self {
+        self.endpoint = ReliableEndpoint::new(config, Instant::now());
+        self
+    }
+
+    /// Returns the configuration of the reliability layer.
+    pub fn reliability_config(&self) -> &ReliabilityConfig {
+        self.endpoint.config()
+    }
+
+    /// Queues an event to be sent with the given delivery guarantee.
+    pub fn send_with(&mut self, event: NetEvent<E>, delivery: DeliveryRequirement) {
+        self.send_queue.push((event, delivery));
+    }
+
+    /// Number of reliable messages sent on this connection that were not acknowledged yet.
+    pub fn unacknowledged_messages(&self) -> usize {
+        self.endpoint.unacknowledged()
+    }
+
     /// Function used ONLY by NetSocketSystem.
     /// Since most users will want to both create the connection and send messages on the same frame,
     /// we need a way to read those. Since the NetSocketSystem runs after the creation of the NetConnection,
@@ -58,6 +106,63 @@ impl<E: Send + Sync + 'static> NetConnection<E> {
     pub fn send_buffer_early_read(&mut self) -> EventIterator<'_, NetEvent<E>> {
         self.send_buffer.read(&mut self.send_reader)
     }
+
+    /// Returns true if nothing has been received on this connection for longer than the timeout.
+    pub(crate) fn is_timed_out(&self, now: Instant) -> bool {
+        match self.transport {
+            Transport::Udp => self.endpoint.is_timed_out(now),
+            // A closed stream is reported by the transport itself.
+            Transport::Tcp | Transport::WebSocket => false,
+        }
+    }
+
+    /// Serializes all queued events, and returns the datagrams to send to the remote end.
+    pub(crate) fn outgoing_datagrams(
+        &mut self,
+        now: Instant,
+        codec: &dyn NetCodec<E>,
+    ) -> Vec<Vec<u8>>
+    where
+        E: Clone,
+    {
+        let mut events = self
+            .send_buffer_early_read()
+            .cloned()
+            .map(|event| (event, DeliveryRequirement::Unreliable))
+            .collect::<Vec<_>>();
+        events.append(&mut self.send_queue);
+
+        let mut datagrams = Vec::new();
+        for (event, delivery) in events {
+            let payload = match codec.encode(&event) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    error!("Failed to serialize the event: {}", e);
+                    continue;
+                }
+            };
+            if self.transport != Transport::Udp {
+                datagrams.push(payload);
+                continue;
+            }
+            match self.endpoint.send(&payload, delivery, now) {
+                Ok(mut event_datagrams) => datagrams.append(&mut event_datagrams),
+                Err(e) => error!("Failed to send the event: {}", e),
+            }
+        }
+        if self.transport == Transport::Udp {
+            datagrams.extend(self.endpoint.update(now));
+        }
+        datagrams
+    }
+
+    /// Processes a received datagram, and returns the payloads of the messages ready to be read.
+    pub(crate) fn incoming_datagram(&mut self, datagram: &[u8], now: Instant) -> Vec<Vec<u8>> {
+        match self.transport {
+            Transport::Udp => self.endpoint.receive(datagram, now),
+            Transport::Tcp | Transport::WebSocket => vec![datagram.to_vec()],
+        }
+    }
 }
 
 impl<E> PartialEq for NetConnection<E> {
@@ -86,6 +191,25 @@ pub enum ConnectionState {
     Disconnected,
 }
 
+/// The transport used by a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Transport {
+    /// Datagrams over UDP, with the delivery guarantees of the reliability layer.
+    Udp,
+    /// Length prefixed frames over a TCP stream, always reliable and ordered.
+    Tcp,
+    /// Binary messages over a WebSocket connection, always reliable and ordered.
+    ///
+    /// Use this to talk to browser clients and debug tools.
+    WebSocket,
+}
+
+impl Default for Transport {
+    fn default() -> Self {
+        Transport::Udp
+    }
+}
+
 /// A network identity. It can represent either a client or a server.
 /// It represents anything that can own an entity or a component.
 /// Think of it as an identity card.
@@ -25,6 +25,26 @@ pub enum Error {
     /// Error that could occur when sending an `ServerSocketEvent` to some channel.
     #[error(display = "Channel send error occurred")]
     ChannelSendError(#[cause] mpsc::SendError<ServerSocketEvent>),
+    /// Error that could occur when serializing whit `serde_json`
+    #[error(display = "JSON serialization error occurred")]
+    JsonError(#[cause] serde_json::Error),
+    /// Error that could occur in a user provided codec.
+    #[error(display = "Codec error occurred: {}", _0)]
+    CodecError(String),
+    /// Error that occurs when a message is too large to be split into fragments.
+    #[error(display = "Message of {} bytes is too large to be sent", _0)]
+    MessageTooLarge(usize),
+    /// Error that occurs when a TCP connection is used, but TCP is not enabled in the
+    /// `ServerConfig`.
+    #[error(display = "TCP is not enabled in the server config")]
+    TcpDisabled,
+    /// Error that could occur on a WebSocket connection.
+    #[error(display = "WebSocket-error occurred")]
+    WebSocketError(#[cause] tungstenite::Error),
+    /// Error that occurs when a WebSocket connection is used, but WebSocket is not enabled in the
+    /// `ServerConfig`.
+    #[error(display = "WebSocket is not enabled in the server config")]
+    WebSocketDisabled,
     #[error(display = "Some error has occurred")]
     #[doc(hidden)]
     __Nonexhaustive,
@@ -48,6 +68,18 @@ impl From<laminar::error::NetworkError> for Error {
     }
 }
 
+impl From<tungstenite::Error> for Error {
+    fn from(e: tungstenite::Error) -> Error {
+        Error::WebSocketError(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Error {
+        Error::JsonError(e)
+    }
+}
+
 impl From<bincode::Error> for Error {
     fn from(e: bincode::Error) -> Error {
         Error::SerializeError(e)
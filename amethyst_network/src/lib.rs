@@ -3,33 +3,58 @@
 #![warn(missing_docs, rust_2018_idioms, rust_2018_compatibility)]
 
 pub use crate::{
-    bundle::NetworkBundle,
-    connection::{ConnectionState, NetConnection, NetIdentity},
-    error::Result,
+    bundle::{
+        NetworkBundle, ReplicationClientBundle, ReplicationServerBundle, SessionClientBundle,
+        SessionServerBundle,
+    },
+    codec::{BincodeCodec, CompressedCodec, JsonCodec, NetCodec},
+    connection::{ConnectionState, NetConnection, NetIdentity, Transport},
+    error::{Error, Result},
     filter::{FilterConnected, NetFilter},
     net_event::NetEvent,
     network_socket::NetSocketSystem,
+    prediction::{InputQueue, InterpolationBuffer, Prediction, SequencedInput},
+    reliability::{DeliveryRequirement, ReliabilityConfig},
+    replication::{
+        ComponentApplySystem, ComponentDiffSystem, ComponentUpdate, EntitySnapshot, NetworkId,
+        NetworkIdSystem, Replicated, ReplicatedComponent, ReplicationClientState,
+        ReplicationClientSystem, ReplicationInterest, ReplicationServerState,
+        ReplicationServerSystem, ReplicationSnapshot,
+    },
     server::{Host, ServerConfig, ServerSocketEvent},
+    session::{
+        ClientId, SessionClientSystem, SessionEvent, SessionMessage, SessionServerSystem, Sessions,
+    },
+    simulation::NetworkSimulation,
 };
 
 use std::{net::SocketAddr, sync::mpsc::SyncSender};
 
-use bincode::{deserialize, serialize};
+use bincode::serialize;
 use laminar::Packet;
 use log::error;
-use serde::{de::DeserializeOwned, Serialize};
+use serde::Serialize;
 
 mod bundle;
+mod codec;
 mod connection;
 mod error;
 mod filter;
 mod net_event;
 mod network_socket;
+mod prediction;
+mod reliability;
+mod replication;
 mod server;
+mod session;
+mod simulation;
 mod test;
 
 /// Sends an event to the target NetConnection using the provided network Socket.
 /// The socket has to be bound.
+///
+/// The event is serialized with the `BincodeCodec`, and sent unreliably, without going through the
+/// reliability layer of a `NetConnection`.
 pub fn send_event<T>(event: NetEvent<T>, addr: SocketAddr, sender: &SyncSender<ServerSocketEvent>)
 where
     T: Serialize,
@@ -37,11 +62,10 @@ where
     let ser = serialize(&event);
     match ser {
         Ok(s) => {
-            let slice = s.as_slice();
+            let datagram = reliability::encode_unreliable(&s);
             // send an unreliable `Packet` from laminar which is basically just a bare UDP packet.
             match sender.send(ServerSocketEvent::Packet(Packet::unreliable(
-                addr,
-                slice.to_owned(),
+                addr, datagram,
             ))) {
                 Ok(_qty) => {}
                 Err(e) => error!("Failed to send data to network socket: {}", e),
@@ -50,11 +74,3 @@ where
         Err(e) => error!("Failed to serialize the event: {}", e),
     }
 }
-
-// Attempts to deserialize an event from the raw byte data.
-fn deserialize_event<T>(data: &[u8]) -> Result<NetEvent<T>>
-where
-    T: DeserializeOwned,
-{
-    Ok(deserialize::<NetEvent<T>>(data)?)
-}
@@ -5,8 +5,9 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::{replication::ReplicationSnapshot, session::SessionMessage};
+
 /// The basic network events shipped with amethyst.
-// TODO: Add CreateEntity,RemoveEntity,UpdateEntity once specs 0.11 is released
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum NetEvent<T> {
     /// Ask to connect to the server.
@@ -39,6 +40,10 @@ pub enum NetEvent<T> {
         /// The message.
         msg: String,
     },
+    /// The changes of the replicated entities, sent by the server.
+    Replication(ReplicationSnapshot),
+    /// A message of the session layer, handled by the session systems.
+    Session(SessionMessage),
     /// A user-defined type containing more network event types.
     Custom(T),
 }
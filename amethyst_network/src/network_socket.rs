@@ -8,26 +8,31 @@ use std::{
         Arc, Mutex,
     },
     thread,
+    time::Instant,
 };
 
-use amethyst_core::specs::{Join, Resources, System, SystemData, WriteStorage};
+use amethyst_core::specs::{Entities, Join, Read, Resources, System, SystemData, WriteStorage};
 
 use laminar::Packet;
 use log::{error, warn};
 use serde::{de::DeserializeOwned, Serialize};
 
 use super::{
-    deserialize_event,
+    codec::{BincodeCodec, NetCodec},
     error::Result,
-    send_event,
-    server::{Host, ReceiveHandler, SendHandler, ServerConfig, ServerSocketEvent},
-    ConnectionState, NetConnection, NetEvent, NetFilter,
+    server::{
+        Host, ReceiveHandler, SendHandler, ServerConfig, ServerSocketEvent, StreamEvent,
+        TcpHandler, WebSocketHandler,
+    },
+    simulation::{NetworkSimulation, Simulator},
+    ConnectionState, NetConnection, NetEvent, NetFilter, Transport,
 };
 
-enum InternalSocketEvent<E> {
-    SendEvents {
+enum InternalSocketEvent {
+    SendDatagrams {
         target: SocketAddr,
-        events: Vec<NetEvent<E>>,
+        transport: Transport,
+        datagrams: Vec<Vec<u8>>,
     },
     Stop,
 }
@@ -50,16 +55,26 @@ where
 {
     /// The list of filters applied on the events received.
     pub filters: Vec<Box<dyn NetFilter<E>>>,
+    /// The codec used to serialize and deserialize the events, `BincodeCodec` by default.
+    pub codec: Box<dyn NetCodec<E>>,
     // sender on which you can queue packets to send to some endpoint.
-    transport_sender: Sender<InternalSocketEvent<E>>,
+    transport_sender: Sender<InternalSocketEvent>,
     // receiver from which you can read received packets.
     transport_receiver: Receiver<Packet>,
+    // handle to the TCP streams, if TCP is enabled.
+    tcp: Option<Arc<TcpHandler>>,
+    // handle to the WebSocket connections, if WebSocket is enabled.
+    websocket: Option<Arc<WebSocketHandler>>,
     config: ServerConfig,
+    // delays, drops and reorders outgoing UDP datagrams when the network simulation is enabled.
+    outgoing_simulator: Simulator<(SocketAddr, Vec<u8>)>,
+    // delays, drops and reorders incoming UDP packets when the network simulation is enabled.
+    incoming_simulator: Simulator<Packet>,
 }
 
 impl<E> NetSocketSystem<E>
 where
-    E: Serialize + PartialEq + Send + 'static,
+    E: Serialize + DeserializeOwned + PartialEq + Send + 'static,
 {
     /// Creates a `NetSocketSystem` and binds the Socket on the ip and port added in parameters.
     pub fn new(config: ServerConfig, filters: Vec<Box<dyn NetFilter<E>>>) -> Result<Self> {
@@ -72,28 +87,96 @@ where
 
         let udp_send_handle = server.udp_send_handle();
         let udp_receive_handle = server.udp_receive_handle();
+        let tcp = server.tcp_handle();
+        let websocket = server.websocket_handle();
 
-        let server_sender = NetSocketSystem::<E>::start_sending(udp_send_handle);
+        let server_sender =
+            NetSocketSystem::<E>::start_sending(udp_send_handle, tcp.clone(), websocket.clone());
         let server_receiver = NetSocketSystem::<E>::start_receiving(udp_receive_handle);
 
         Ok(NetSocketSystem {
             filters,
+            codec: Box::new(BincodeCodec),
             transport_sender: server_sender,
             transport_receiver: server_receiver,
+            tcp,
+            websocket,
             config,
+            outgoing_simulator: Simulator::new(),
+            incoming_simulator: Simulator::new(),
         })
     }
 
+    /// Sets the codec used to serialize and deserialize the events.
+    ///
+    /// All connections of the application have to use the same codec.
+    pub fn with_codec<C>(mut self, codec: C) -> Self
+    where
+        C: NetCodec<E> + 'static,
+    {
+        self.codec = Box::new(codec);
+        self
+    }
+
     /// Start a thread to send all queued packets.
-    fn start_sending(sender: Arc<SendHandler>) -> Sender<InternalSocketEvent<E>> {
+    fn start_sending(
+        sender: Arc<SendHandler>,
+        tcp: Option<Arc<TcpHandler>>,
+        websocket: Option<Arc<WebSocketHandler>>,
+    ) -> Sender<InternalSocketEvent> {
         let (tx, send_queue) = mpsc::channel();
 
         thread::spawn(move || loop {
             for control_event in send_queue.try_iter() {
                 match control_event {
-                    InternalSocketEvent::SendEvents { target, events } => {
-                        for ev in events {
-                            send_event(ev, target, &sender.get_sender());
+                    InternalSocketEvent::SendDatagrams {
+                        target,
+                        transport: Transport::Tcp,
+                        datagrams,
+                    } => match tcp {
+                        Some(ref tcp) => {
+                            for frame in datagrams {
+                                if let Err(e) = tcp.send(target, &frame) {
+                                    error!("Failed to send data to TCP stream: {}", e);
+                                    break;
+                                }
+                            }
+                        }
+                        None => error!("Failed to send data to {}: TCP is not enabled", target),
+                    },
+                    InternalSocketEvent::SendDatagrams {
+                        target,
+                        transport: Transport::WebSocket,
+                        datagrams,
+                    } => match websocket {
+                        Some(ref websocket) => {
+                            for message in datagrams {
+                                if let Err(e) = websocket.send(target, message) {
+                                    error!("Failed to send data to WebSocket: {}", e);
+                                    break;
+                                }
+                            }
+                        }
+                        None => {
+                            error!(
+                                "Failed to send data to {}: WebSocket is not enabled",
+                                target
+                            )
+                        }
+                    },
+                    InternalSocketEvent::SendDatagrams {
+                        target,
+                        transport: Transport::Udp,
+                        datagrams,
+                    } => {
+                        for datagram in datagrams {
+                            // The reliability layer takes care of the delivery guarantees, so
+                            // the datagrams are sent as bare UDP packets.
+                            if let Err(e) = sender.send(ServerSocketEvent::Packet(
+                                Packet::unreliable(target, datagram),
+                            )) {
+                                error!("Failed to send data to network socket: {}", e);
+                            }
                         }
                     }
                     InternalSocketEvent::Stop => {
@@ -146,19 +229,47 @@ impl<'a, E> System<'a> for NetSocketSystem<E>
 where
     E: Send + Sync + Serialize + Clone + DeserializeOwned + PartialEq + 'static,
 {
-    type SystemData = (WriteStorage<'a, NetConnection<E>>);
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, NetConnection<E>>,
+        Read<'a, NetworkSimulation>,
+    );
+
+    fn run(&mut self, (entities, mut net_connections, simulation): Self::SystemData) {
+        let now = Instant::now();
 
-    fn run(&mut self, mut net_connections: Self::SystemData) {
         for net_connection in (&mut net_connections).join() {
             let target = net_connection.target_receiver;
 
             if net_connection.state == ConnectionState::Connected
                 || net_connection.state == ConnectionState::Connecting
             {
+                if net_connection.is_timed_out(now) {
+                    warn!("Connection to {} timed out", target);
+                    net_connection.state = ConnectionState::Disconnected;
+                    net_connection
+                        .receive_buffer
+                        .single_write(NetEvent::Disconnected {
+                            reason: "Connection timed out".to_string(),
+                        });
+                    continue;
+                }
+
+                let transport = net_connection.transport;
+                let datagrams = net_connection.outgoing_datagrams(now, &*self.codec);
+                if transport == Transport::Udp && self.outgoing_simulator.is_active(&simulation) {
+                    for datagram in datagrams {
+                        self.outgoing_simulator
+                            .push(&simulation, now, (target, datagram));
+                    }
+                    continue;
+                }
+
                 self.transport_sender
-                    .send(InternalSocketEvent::SendEvents {
+                    .send(InternalSocketEvent::SendDatagrams {
                         target,
-                        events: net_connection.send_buffer_early_read().cloned().collect(),
+                        transport,
+                        datagrams,
                     })
                     .expect("Unreachable: Channel will be alive until a stop event is sent");
             } else if net_connection.state == ConnectionState::Disconnected {
@@ -168,32 +279,73 @@ where
             }
         }
 
-        for (counter, raw_event) in self.transport_receiver.try_iter().enumerate() {
+        for (target, datagram) in self.outgoing_simulator.pop_ready(now) {
+            self.transport_sender
+                .send(InternalSocketEvent::SendDatagrams {
+                    target,
+                    transport: Transport::Udp,
+                    datagrams: vec![datagram],
+                })
+                .expect("Unreachable: Channel will be alive until a stop event is sent");
+        }
+
+        // this will prevent our system to be stuck in the iterator.
+        // After `max_throughput` packets we will continue and leave the other packets for the next run.
+        // eventually some congestion prevention should be done.
+        let mut received = self
+            .transport_receiver
+            .try_iter()
+            .take(self.config.max_throughput as usize)
+            .collect::<Vec<_>>();
+        if self.incoming_simulator.is_active(&simulation) {
+            for packet in received {
+                self.incoming_simulator.push(&simulation, now, packet);
+            }
+            received = self.incoming_simulator.pop_ready(now);
+        }
+
+        for raw_event in received {
             // Get the NetConnection from the source
             for net_connection in (&mut net_connections).join() {
-                if net_connection.target_sender == raw_event.addr() {
-                    // Get the event
-                    match deserialize_event::<E>(raw_event.payload()) {
-                        Ok(ev) => {
-                            net_connection.receive_buffer.single_write(ev);
+                if net_connection.transport == Transport::Udp
+                    && net_connection.target_sender == raw_event.addr()
+                {
+                    // Get the events that are ready to be delivered
+                    for payload in net_connection.incoming_datagram(raw_event.payload(), now) {
+                        match self.codec.decode(&payload) {
+                            Ok(ev) => {
+                                net_connection.receive_buffer.single_write(ev);
+                            }
+                            Err(e) => error!(
+                                "Failed to deserialize an incoming network event: {} From source: {:?}",
+                                e,
+                                raw_event.addr()
+                            ),
                         }
-                        Err(e) => error!(
-                            "Failed to deserialize an incoming network event: {} From source: {:?}",
-                            e,
-                            raw_event.addr()
-                        ),
                     }
                 } else {
                     warn!("Received packet from unknown source");
                 }
             }
+        }
 
-            // this will prevent our system to be stuck in the iterator.
-            // After 10000 packets we will continue and leave the other packets for the next run.
-            // eventually some congestion prevention should be done.
-            if counter >= self.config.max_throughput as usize {
-                break;
-            }
+        if let Some(ref tcp) = self.tcp {
+            receive_stream_events(
+                Transport::Tcp,
+                tcp.events(),
+                &*self.codec,
+                &entities,
+                &mut net_connections,
+            );
+        }
+        if let Some(ref websocket) = self.websocket {
+            receive_stream_events(
+                Transport::WebSocket,
+                websocket.events(),
+                &*self.codec,
+                &entities,
+                &mut net_connections,
+            );
         }
     }
 
@@ -201,3 +353,61 @@ where
         Self::SystemData::setup(res);
     }
 }
+
+// Writes the events received on the streams of a stream based transport to their connections.
+fn receive_stream_events<E>(
+    transport: Transport,
+    events: Vec<StreamEvent>,
+    codec: &dyn NetCodec<E>,
+    entities: &Entities<'_>,
+    net_connections: &mut WriteStorage<'_, NetConnection<E>>,
+) where
+    E: Send + Sync + 'static,
+{
+    for stream_event in events {
+        match stream_event {
+            StreamEvent::Frame { addr, payload } => {
+                let event = match codec.decode(&payload) {
+                    Ok(event) => event,
+                    Err(e) => {
+                        error!(
+                            "Failed to deserialize an incoming network event: {} From source: {:?}",
+                            e, addr
+                        );
+                        continue;
+                    }
+                };
+                let net_connection = (&mut *net_connections)
+                    .join()
+                    .find(|c| c.transport == transport && c.target_sender == addr);
+                match net_connection {
+                    Some(net_connection) => net_connection.receive_buffer.single_write(event),
+                    None => {
+                        // Incoming streams come from an unknown port, so a connection is created
+                        // for them.
+                        let mut net_connection =
+                            NetConnection::new(addr, addr).with_transport(transport);
+                        net_connection.receive_buffer.single_write(event);
+                        if let Err(e) = net_connections.insert(entities.create(), net_connection) {
+                            error!("Failed to insert `NetConnection`: {}", e);
+                        }
+                    }
+                }
+            }
+            StreamEvent::Disconnected { addr } => {
+                for net_connection in (&mut *net_connections).join().filter(|c| {
+                    c.transport == transport
+                        && c.target_sender == addr
+                        && c.state != ConnectionState::Disconnected
+                }) {
+                    net_connection.state = ConnectionState::Disconnected;
+                    net_connection
+                        .receive_buffer
+                        .single_write(NetEvent::Disconnected {
+                            reason: "Stream closed".to_string(),
+                        });
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,306 @@
+//! Client-side prediction, server reconciliation and interpolation of remote entities.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use amethyst_core::specs::{Component, DenseVecStorage};
+
+/// An input tagged with the sequence number it was predicted with.
+///
+/// Send these to the server, and echo the last processed sequence number back with the
+/// authoritative state, so the client can reconcile its prediction.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SequencedInput<I> {
+    /// Sequence number of the input, increasing by one for every input
+    pub sequence: u32,
+    /// The input
+    pub input: I,
+}
+
+/// Predicts the local state of a client by applying its inputs immediately, without waiting for
+/// the server.
+///
+/// Inputs stay buffered until the server acknowledges them. When an authoritative state arrives,
+/// the acknowledged inputs are dropped and the remaining ones are simulated again on top of the
+/// authoritative state, using the same deterministic step function as the server.
+///
+/// ### Type parameters:
+///
+/// - `I`: the input type
+/// - `S`: the predicted state
+pub struct Prediction<I, S> {
+    state: S,
+    next_sequence: u32,
+    pending: VecDeque<SequencedInput<I>>,
+    step: fn(&mut S, &I),
+}
+
+impl<I, S> Prediction<I, S>
+where
+    I: Clone,
+{
+    /// Creates a new prediction from the initial state, and the step function that applies an
+    /// input to the state.
+    pub fn new(state: S, step: fn(&mut S, &I)) -> Self {
+        Prediction {
+            state,
+            next_sequence: 0,
+            pending: VecDeque::new(),
+            step,
+        }
+    }
+
+    /// The predicted state.
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    /// Inputs that were not acknowledged by the server yet.
+    pub fn pending(&self) -> impl Iterator<Item = &SequencedInput<I>> {
+        self.pending.iter()
+    }
+
+    /// Applies a local input to the predicted state, and returns it tagged with its sequence
+    /// number, ready to be sent to the server.
+    pub fn push_input(&mut self, input: I) -> SequencedInput<I> {
+        let input = SequencedInput {
+            sequence: self.next_sequence,
+            input,
+        };
+        self.next_sequence = self.next_sequence.wrapping_add(1);
+        (self.step)(&mut self.state, &input.input);
+        self.pending.push_back(input.clone());
+        input
+    }
+
+    /// Resets the predicted state to the authoritative state of the server, which includes all
+    /// inputs up to `last_processed`, and simulates the remaining inputs again.
+    pub fn reconcile(&mut self, authoritative: S, last_processed: u32) {
+        while let Some(input) = self.pending.front() {
+            if sequence_greater_than(input.sequence, last_processed) {
+                break;
+            }
+            self.pending.pop_front();
+        }
+        self.state = authoritative;
+        for input in &self.pending {
+            (self.step)(&mut self.state, &input.input);
+        }
+    }
+}
+
+impl<I, S> Component for Prediction<I, S>
+where
+    I: Send + Sync + 'static,
+    S: Send + Sync + 'static,
+{
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Orders the inputs received by the server, and keeps track of the last processed one.
+///
+/// Duplicated and outdated inputs are dropped.
+///
+/// ### Type parameters:
+///
+/// - `I`: the input type
+#[derive(Clone, Debug)]
+pub struct InputQueue<I> {
+    last_processed: Option<u32>,
+    inputs: BTreeMap<u32, I>,
+}
+
+impl<I> Default for InputQueue<I> {
+    fn default() -> Self {
+        InputQueue {
+            last_processed: None,
+            inputs: BTreeMap::new(),
+        }
+    }
+}
+
+impl<I> InputQueue<I> {
+    /// Creates a new empty queue.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sequence number of the last input returned by `drain`, send it back to the client with the
+    /// authoritative state.
+    pub fn last_processed(&self) -> Option<u32> {
+        self.last_processed
+    }
+
+    /// Queues an input received from the client.
+    pub fn push(&mut self, input: SequencedInput<I>) {
+        if let Some(last) = self.last_processed {
+            if !sequence_greater_than(input.sequence, last) {
+                return;
+            }
+        }
+        self.inputs.insert(input.sequence, input.input);
+    }
+
+    /// Removes all queued inputs, in the order they were predicted by the client.
+    pub fn drain(&mut self) -> Vec<I> {
+        let mut inputs = std::mem::replace(&mut self.inputs, BTreeMap::new())
+            .into_iter()
+            .collect::<Vec<_>>();
+        // The sequence numbers wrap around, so order them relative to the last processed input.
+        if let Some(last) = self.last_processed {
+            inputs.sort_by_key(|(sequence, _)| sequence.wrapping_sub(last));
+        }
+        if let Some((sequence, _)) = inputs.last() {
+            self.last_processed = Some(*sequence);
+        }
+        inputs.into_iter().map(|(_, input)| input).collect()
+    }
+}
+
+impl<I> Component for InputQueue<I>
+where
+    I: Send + Sync + 'static,
+{
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Buffers the states of a remote entity received from the server, and interpolates between them
+/// to hide the latency and jitter of the network.
+///
+/// The state is rendered `delay` seconds in the past, so there usually are two states to
+/// interpolate between.
+///
+/// ### Type parameters:
+///
+/// - `S`: the interpolated state
+pub struct InterpolationBuffer<S> {
+    delay: f64,
+    states: VecDeque<(f64, S)>,
+    interpolate: fn(&S, &S, f32) -> S,
+}
+
+impl<S> InterpolationBuffer<S>
+where
+    S: Clone,
+{
+    /// Creates a new buffer, with the render delay in seconds and the function interpolating
+    /// between two states.
+    pub fn new(delay: f64, interpolate: fn(&S, &S, f32) -> S) -> Self {
+        InterpolationBuffer {
+            delay,
+            states: VecDeque::new(),
+            interpolate,
+        }
+    }
+
+    /// The render delay in seconds.
+    pub fn delay(&self) -> f64 {
+        self.delay
+    }
+
+    /// Adds a state received from the server, with the time it was sent at.
+    ///
+    /// States that are older than the newest buffered state are ignored.
+    pub fn push(&mut self, time: f64, state: S) {
+        if let Some((last, _)) = self.states.back() {
+            if time <= *last {
+                return;
+            }
+        }
+        self.states.push_back((time, state));
+    }
+
+    /// Returns the interpolated state at `time - delay`, and drops the states that are no longer
+    /// needed.
+    ///
+    /// Before the first state is buffered `None` is returned, if the buffer runs out of states the
+    /// newest one is returned.
+    pub fn sample(&mut self, time: f64) -> Option<S> {
+        let render_time = time - self.delay;
+        while self.states.len() > 2 && self.states[1].0 <= render_time {
+            self.states.pop_front();
+        }
+
+        match (self.states.get(0), self.states.get(1)) {
+            (Some((from_time, from)), Some((to_time, to))) => {
+                let t = ((render_time - from_time) / (to_time - from_time))
+                    .max(0.0)
+                    .min(1.0);
+                Some((self.interpolate)(from, to, t as f32))
+            }
+            (Some((_, state)), None) => Some(state.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl<S> Component for InterpolationBuffer<S>
+where
+    S: Send + Sync + 'static,
+{
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Compares two sequence numbers, taking wrap around into account.
+fn sequence_greater_than(s1: u32, s2: u32) -> bool {
+    s1 != s2 && s1.wrapping_sub(s2) < u32::max_value() / 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(state: &mut i32, input: &i32) {
+        *state += *input;
+    }
+
+    #[test]
+    fn reconcile_replays_pending_inputs() {
+        let mut prediction = Prediction::new(0, step);
+        let first = prediction.push_input(1);
+        prediction.push_input(2);
+        prediction.push_input(3);
+        assert_eq!(*prediction.state(), 6);
+
+        // The server processed the first input, but applied a correction.
+        prediction.reconcile(10, first.sequence);
+        assert_eq!(*prediction.state(), 15);
+        assert_eq!(prediction.pending().count(), 2);
+    }
+
+    #[test]
+    fn input_queue_orders_and_drops_old_inputs() {
+        let mut queue = InputQueue::new();
+        queue.push(SequencedInput {
+            sequence: 1,
+            input: 'b',
+        });
+        queue.push(SequencedInput {
+            sequence: 0,
+            input: 'a',
+        });
+        assert_eq!(queue.drain(), vec!['a', 'b']);
+        assert_eq!(queue.last_processed(), Some(1));
+
+        queue.push(SequencedInput {
+            sequence: 1,
+            input: 'x',
+        });
+        queue.push(SequencedInput {
+            sequence: 2,
+            input: 'c',
+        });
+        assert_eq!(queue.drain(), vec!['c']);
+    }
+
+    #[test]
+    fn interpolation_buffer_samples_in_the_past() {
+        let mut buffer = InterpolationBuffer::new(1.0, |a: &f32, b: &f32, t| a + (b - a) * t);
+        assert_eq!(buffer.sample(0.0), None);
+        buffer.push(0.0, 0.0);
+        buffer.push(2.0, 1.0);
+        assert_eq!(buffer.sample(2.0), Some(0.5));
+        assert_eq!(buffer.sample(4.0), Some(1.0));
+    }
+}
@@ -0,0 +1,627 @@
+//! Delivery guarantees, fragmentation and heartbeats on top of bare UDP datagrams.
+//!
+//! Every datagram sent by a `NetConnection` starts with a small header containing the delivery
+//! channel, a per-channel sequence number, fragment information and an acknowledgement of the
+//! reliable messages received from the remote end.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// Size of the header in front of every datagram, in bytes.
+pub(crate) const HEADER_SIZE: usize = 11;
+
+const MAX_FRAGMENTS: usize = 255;
+/// Reliable messages further ahead of the next one to deliver are dropped and resent later, as
+/// only this many are acknowledged by the `ack_bits` of the header.
+const ORDERED_WINDOW: u16 = 32;
+/// Fragmented messages reassembled at once, the fragments of other messages are dropped.
+const MAX_PARTIAL_MESSAGES: usize = 64;
+const CHANNEL_MASK: u8 = 0b0000_0011;
+const FLAG_NO_ACK: u8 = 0b0100_0000;
+const FLAG_HEARTBEAT: u8 = 0b1000_0000;
+
+/// The delivery guarantee of a message sent over a `NetConnection`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DeliveryRequirement {
+    /// Messages may be lost, duplicated or arrive out of order.
+    Unreliable,
+    /// Messages may be lost, but messages arriving after a newer message are dropped.
+    UnreliableSequenced,
+    /// Messages are resent until they are acknowledged, and are delivered in the order they were
+    /// sent.
+    ReliableOrdered,
+}
+
+impl Default for DeliveryRequirement {
+    fn default() -> Self {
+        DeliveryRequirement::Unreliable
+    }
+}
+
+impl DeliveryRequirement {
+    fn id(self) -> u8 {
+        match self {
+            DeliveryRequirement::Unreliable => 0,
+            DeliveryRequirement::UnreliableSequenced => 1,
+            DeliveryRequirement::ReliableOrdered => 2,
+        }
+    }
+
+    fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(DeliveryRequirement::Unreliable),
+            1 => Some(DeliveryRequirement::UnreliableSequenced),
+            2 => Some(DeliveryRequirement::ReliableOrdered),
+            _ => None,
+        }
+    }
+}
+
+/// Configuration of the reliability layer of a `NetConnection`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReliabilityConfig {
+    /// A heartbeat is sent when nothing was sent to the remote end for this long.
+    pub heartbeat_interval: Duration,
+    /// The connection is considered dropped when nothing was received for this long.
+    pub timeout: Duration,
+    /// Unacknowledged reliable messages are resent after this long.
+    pub resend_interval: Duration,
+    /// Maximum payload size of a single datagram, larger messages are fragmented.
+    ///
+    /// A message can be split into at most 255 fragments.
+    pub fragment_size: usize,
+}
+
+impl Default for ReliabilityConfig {
+    fn default() -> Self {
+        ReliabilityConfig {
+            heartbeat_interval: Duration::from_secs(1),
+            timeout: Duration::from_secs(10),
+            resend_interval: Duration::from_millis(200),
+            fragment_size: 1024,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Header {
+    flags: u8,
+    sequence: u16,
+    ack: Option<u16>,
+    ack_bits: u32,
+    fragment: u8,
+    fragment_count: u8,
+}
+
+impl Header {
+    fn write(&self, out: &mut Vec<u8>) {
+        match self.ack {
+            Some(_) => out.push(self.flags),
+            None => out.push(self.flags | FLAG_NO_ACK),
+        }
+        out.extend_from_slice(&self.sequence.to_be_bytes());
+        out.extend_from_slice(&self.ack.unwrap_or(0).to_be_bytes());
+        out.extend_from_slice(&self.ack_bits.to_be_bytes());
+        out.push(self.fragment);
+        out.push(self.fragment_count);
+    }
+
+    fn read(data: &[u8]) -> Option<Header> {
+        if data.len() < HEADER_SIZE {
+            return None;
+        }
+        Some(Header {
+            flags: data[0],
+            sequence: u16::from_be_bytes([data[1], data[2]]),
+            ack: if data[0] & FLAG_NO_ACK == 0 {
+                Some(u16::from_be_bytes([data[3], data[4]]))
+            } else {
+                None
+            },
+            ack_bits: u32::from_be_bytes([data[5], data[6], data[7], data[8]]),
+            fragment: data[9],
+            fragment_count: data[10],
+        })
+    }
+}
+
+/// Returns true if sequence number `a` is more recent than `b`, taking wrap around into account.
+fn sequence_greater_than(a: u16, b: u16) -> bool {
+    (a > b && a - b <= 32768) || (a < b && b - a > 32768)
+}
+
+/// Prefixes `payload` with a header for a single unreliable datagram, without acknowledgement
+/// information.
+pub(crate) fn encode_unreliable(payload: &[u8]) -> Vec<u8> {
+    let mut datagram = Vec::with_capacity(HEADER_SIZE + payload.len());
+    Header {
+        flags: DeliveryRequirement::Unreliable.id(),
+        sequence: 0,
+        ack: None,
+        ack_bits: 0,
+        fragment: 0,
+        fragment_count: 1,
+    }
+    .write(&mut datagram);
+    datagram.extend_from_slice(payload);
+    datagram
+}
+
+struct PendingMessage {
+    sequence: u16,
+    fragments: Vec<Vec<u8>>,
+    last_sent: Instant,
+}
+
+struct PartialMessage {
+    fragments: Vec<Option<Vec<u8>>>,
+    received: usize,
+    first_seen: Instant,
+}
+
+/// The reliability state of one end of a connection.
+pub(crate) struct ReliableEndpoint {
+    config: ReliabilityConfig,
+    next_sequence: [u16; 3],
+    pending: VecDeque<PendingMessage>,
+    last_sequenced: Option<u16>,
+    last_ordered: Option<u16>,
+    ordered_buffer: HashMap<u16, Vec<u8>>,
+    partial: HashMap<(u8, u16), PartialMessage>,
+    ack_pending: bool,
+    last_sent: Instant,
+    last_received: Instant,
+}
+
+impl ReliableEndpoint {
+    /// Creates a new endpoint, `now` counts as the time something was last received.
+    pub fn new(config: ReliabilityConfig, now: Instant) -> Self {
+        ReliableEndpoint {
+            config,
+            next_sequence: [0; 3],
+            pending: VecDeque::new(),
+            last_sequenced: None,
+            last_ordered: None,
+            ordered_buffer: HashMap::new(),
+            partial: HashMap::new(),
+            ack_pending: false,
+            last_sent: now,
+            last_received: now,
+        }
+    }
+
+    /// Returns the configuration of this endpoint.
+    pub fn config(&self) -> &ReliabilityConfig {
+        &self.config
+    }
+
+    /// Returns true if nothing was received from the remote end for longer than the timeout.
+    pub fn is_timed_out(&self, now: Instant) -> bool {
+        now.duration_since(self.last_received) > self.config.timeout
+    }
+
+    /// Number of reliable messages that have not been acknowledged yet.
+    pub fn unacknowledged(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Splits `payload` into datagrams ready to be sent.
+    pub fn send(
+        &mut self,
+        payload: &[u8],
+        delivery: DeliveryRequirement,
+        now: Instant,
+    ) -> Result<Vec<Vec<u8>>> {
+        let fragment_size = self.config.fragment_size.max(1);
+        if payload.len() > fragment_size * MAX_FRAGMENTS {
+            return Err(Error::MessageTooLarge(payload.len()));
+        }
+        let fragments = if payload.is_empty() {
+            vec![Vec::new()]
+        } else {
+            payload
+                .chunks(fragment_size)
+                .map(<[u8]>::to_vec)
+                .collect::<Vec<_>>()
+        };
+
+        let channel = delivery.id();
+        let sequence = self.next_sequence[channel as usize];
+        self.next_sequence[channel as usize] = sequence.wrapping_add(1);
+
+        let datagrams = self.build_datagrams(channel, sequence, &fragments, now);
+        if delivery == DeliveryRequirement::ReliableOrdered {
+            self.pending.push_back(PendingMessage {
+                sequence,
+                fragments,
+                last_sent: now,
+            });
+        }
+        Ok(datagrams)
+    }
+
+    /// Returns the datagrams that need to be sent even if there is nothing new to send: resent
+    /// reliable messages, acknowledgements and heartbeats.
+    pub fn update(&mut self, now: Instant) -> Vec<Vec<u8>> {
+        let resend_interval = self.config.resend_interval;
+        let mut resend = Vec::new();
+        for message in &mut self.pending {
+            if now.duration_since(message.last_sent) >= resend_interval {
+                message.last_sent = now;
+                resend.push((message.sequence, message.fragments.clone()));
+            }
+        }
+
+        let channel = DeliveryRequirement::ReliableOrdered.id();
+        let mut datagrams = Vec::new();
+        for (sequence, fragments) in resend {
+            datagrams.extend(self.build_datagrams(channel, sequence, &fragments, now));
+        }
+
+        if datagrams.is_empty()
+            && (self.ack_pending
+                || now.duration_since(self.last_sent) >= self.config.heartbeat_interval)
+        {
+            let mut datagram = Vec::with_capacity(HEADER_SIZE);
+            self.header(FLAG_HEARTBEAT, 0, 0, 1).write(&mut datagram);
+            self.last_sent = now;
+            datagrams.push(datagram);
+        }
+        datagrams
+    }
+
+    /// Processes a received datagram, and returns the messages that are ready to be delivered.
+    pub fn receive(&mut self, datagram: &[u8], now: Instant) -> Vec<Vec<u8>> {
+        let header = match Header::read(datagram) {
+            Some(header) => header,
+            None => {
+                warn!("Received a datagram that is too small to contain a header");
+                return Vec::new();
+            }
+        };
+        self.last_received = now;
+
+        if let Some(ack) = header.ack {
+            let ack_bits = header.ack_bits;
+            self.pending.retain(|message| {
+                let acked = !sequence_greater_than(message.sequence, ack)
+                    || (message.sequence.wrapping_sub(ack) >= 2
+                        && message.sequence.wrapping_sub(ack) < 34
+                        && ack_bits & (1 << (message.sequence.wrapping_sub(ack) - 2)) != 0);
+                !acked
+            });
+        }
+        if header.flags & FLAG_HEARTBEAT != 0 {
+            return Vec::new();
+        }
+
+        let channel = header.flags & CHANNEL_MASK;
+        let delivery = match DeliveryRequirement::from_id(channel) {
+            Some(delivery) => delivery,
+            None => {
+                warn!(
+                    "Received a datagram with unknown delivery channel {}",
+                    channel
+                );
+                return Vec::new();
+            }
+        };
+
+        let payload = datagram[HEADER_SIZE..].to_vec();
+        let message = if header.fragment_count <= 1 {
+            payload
+        } else {
+            match self.reassemble(channel, &header, payload, now) {
+                Some(message) => message,
+                None => return Vec::new(),
+            }
+        };
+
+        match delivery {
+            DeliveryRequirement::Unreliable => vec![message],
+            DeliveryRequirement::UnreliableSequenced => match self.last_sequenced {
+                Some(last) if !sequence_greater_than(header.sequence, last) => Vec::new(),
+                _ => {
+                    self.last_sequenced = Some(header.sequence);
+                    vec![message]
+                }
+            },
+            DeliveryRequirement::ReliableOrdered => {
+                self.ack_pending = true;
+                if header.sequence == self.next_ordered() {
+                    let mut messages = vec![message];
+                    self.last_ordered = Some(header.sequence);
+                    while let Some(message) = self.ordered_buffer.remove(&self.next_ordered()) {
+                        messages.push(message);
+                        self.last_ordered = Some(self.next_ordered());
+                    }
+                    messages
+                } else {
+                    let ahead = header.sequence.wrapping_sub(self.next_ordered());
+                    if sequence_greater_than(header.sequence, self.next_ordered())
+                        && ahead <= ORDERED_WINDOW
+                    {
+                        self.ordered_buffer.insert(header.sequence, message);
+                    }
+                    Vec::new()
+                }
+            }
+        }
+    }
+
+    fn reassemble(
+        &mut self,
+        channel: u8,
+        header: &Header,
+        payload: Vec<u8>,
+        now: Instant,
+    ) -> Option<Vec<u8>> {
+        let timeout = self.config.timeout;
+        self.partial
+            .retain(|_, partial| now.duration_since(partial.first_seen) <= timeout);
+
+        let key = (channel, header.sequence);
+        let count = header.fragment_count as usize;
+        let index = header.fragment as usize;
+        if index >= count {
+            warn!("Received a fragment with an invalid index");
+            return None;
+        }
+        if !self.partial.contains_key(&key) && self.partial.len() >= MAX_PARTIAL_MESSAGES {
+            warn!("Too many fragmented messages are being received, dropping a fragment");
+            return None;
+        }
+
+        let complete = {
+            let partial = self.partial.entry(key).or_insert_with(|| PartialMessage {
+                fragments: vec![None; count],
+                received: 0,
+                first_seen: now,
+            });
+            if partial.fragments.len() != count {
+                warn!("Received fragments with inconsistent counts");
+                return None;
+            }
+            if partial.fragments[index].is_none() {
+                partial.fragments[index] = Some(payload);
+                partial.received += 1;
+            }
+            partial.received == count
+        };
+
+        if complete {
+            self.partial.remove(&key).map(|partial| {
+                partial
+                    .fragments
+                    .into_iter()
+                    .flat_map(|fragment| fragment.unwrap_or_default())
+                    .collect()
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Sequence number of the next reliable message to deliver.
+    fn next_ordered(&self) -> u16 {
+        self.last_ordered.map_or(0, |last| last.wrapping_add(1))
+    }
+
+    fn header(&mut self, flags: u8, sequence: u16, fragment: u8, fragment_count: u8) -> Header {
+        let ack = self.last_ordered;
+        let mut ack_bits = 0;
+        if let Some(ack) = ack {
+            for bit in 0..32 {
+                if self
+                    .ordered_buffer
+                    .contains_key(&ack.wrapping_add(2 + bit as u16))
+                {
+                    ack_bits |= 1 << bit;
+                }
+            }
+        }
+        self.ack_pending = false;
+        Header {
+            flags,
+            sequence,
+            ack,
+            ack_bits,
+            fragment,
+            fragment_count,
+        }
+    }
+
+    fn build_datagrams(
+        &mut self,
+        channel: u8,
+        sequence: u16,
+        fragments: &[Vec<u8>],
+        now: Instant,
+    ) -> Vec<Vec<u8>> {
+        let count = fragments.len() as u8;
+        let datagrams = fragments
+            .iter()
+            .enumerate()
+            .map(|(index, fragment)| {
+                let mut datagram = Vec::with_capacity(HEADER_SIZE + fragment.len());
+                self.header(channel, sequence, index as u8, count)
+                    .write(&mut datagram);
+                datagram.extend_from_slice(fragment);
+                datagram
+            })
+            .collect();
+        self.last_sent = now;
+        datagrams
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoints() -> (ReliableEndpoint, ReliableEndpoint, Instant) {
+        let now = Instant::now();
+        let config = ReliabilityConfig {
+            fragment_size: 4,
+            ..ReliabilityConfig::default()
+        };
+        (
+            ReliableEndpoint::new(config.clone(), now),
+            ReliableEndpoint::new(config, now),
+            now,
+        )
+    }
+
+    #[test]
+    fn sequence_wraps_around() {
+        assert!(sequence_greater_than(1, 0));
+        assert!(sequence_greater_than(0, 65535));
+        assert!(!sequence_greater_than(65535, 0));
+    }
+
+    #[test]
+    fn fragments_are_reassembled() {
+        let (mut a, mut b, now) = endpoints();
+        let datagrams = a
+            .send(b"hello world", DeliveryRequirement::Unreliable, now)
+            .unwrap();
+        assert_eq!(datagrams.len(), 3);
+        let mut received = Vec::new();
+        for datagram in datagrams.iter().rev() {
+            received.extend(b.receive(datagram, now));
+        }
+        assert_eq!(received, vec![b"hello world".to_vec()]);
+    }
+
+    #[test]
+    fn sequenced_drops_old_messages() {
+        let (mut a, mut b, now) = endpoints();
+        let first = a
+            .send(b"1", DeliveryRequirement::UnreliableSequenced, now)
+            .unwrap();
+        let second = a
+            .send(b"2", DeliveryRequirement::UnreliableSequenced, now)
+            .unwrap();
+        assert_eq!(b.receive(&second[0], now), vec![b"2".to_vec()]);
+        assert!(b.receive(&first[0], now).is_empty());
+    }
+
+    #[test]
+    fn reliable_messages_are_ordered_resent_and_acked() {
+        let (mut a, mut b, now) = endpoints();
+        let first = a
+            .send(b"1", DeliveryRequirement::ReliableOrdered, now)
+            .unwrap();
+        let second = a
+            .send(b"2", DeliveryRequirement::ReliableOrdered, now)
+            .unwrap();
+        assert_eq!(a.unacknowledged(), 2);
+
+        // The first message is lost, so the second is held back.
+        assert!(b.receive(&second[0], now).is_empty());
+
+        let later = now + a.config().resend_interval;
+        let resent = a.update(later);
+        assert_eq!(resent.len(), 2);
+        let mut received = Vec::new();
+        for datagram in &resent {
+            received.extend(b.receive(datagram, later));
+        }
+        assert_eq!(received, vec![b"1".to_vec(), b"2".to_vec()]);
+
+        for datagram in b.update(later) {
+            a.receive(&datagram, later);
+        }
+        assert_eq!(a.unacknowledged(), 0);
+    }
+
+    #[test]
+    fn reliable_messages_too_far_ahead_are_dropped() {
+        let (mut a, mut b, now) = endpoints();
+        let datagrams = (0..40)
+            .map(|i| {
+                a.send(&[i], DeliveryRequirement::ReliableOrdered, now)
+                    .unwrap()
+                    .remove(0)
+            })
+            .collect::<Vec<_>>();
+
+        // The first message is lost, only the next ones in the window are kept.
+        for datagram in &datagrams[1..] {
+            assert!(b.receive(datagram, now).is_empty());
+        }
+        assert_eq!(b.ordered_buffer.len(), ORDERED_WINDOW as usize);
+
+        let later = now + a.config().resend_interval;
+        let mut received = Vec::new();
+        for datagram in a.update(later) {
+            received.extend(b.receive(&datagram, later));
+        }
+        assert_eq!(received, (0..40).map(|i| vec![i]).collect::<Vec<_>>());
+        assert!(b.ordered_buffer.is_empty());
+    }
+
+    #[test]
+    fn fragmented_messages_are_limited() {
+        let (mut a, mut b, now) = endpoints();
+        let messages = (0..=MAX_PARTIAL_MESSAGES)
+            .map(|i| {
+                a.send(&[i as u8; 8], DeliveryRequirement::Unreliable, now)
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        for datagrams in &messages {
+            assert!(b.receive(&datagrams[0], now).is_empty());
+        }
+        assert_eq!(b.partial.len(), MAX_PARTIAL_MESSAGES);
+
+        // The first fragment of the last message was dropped, the first message is completed.
+        assert!(b
+            .receive(&messages[MAX_PARTIAL_MESSAGES][1], now)
+            .is_empty());
+        assert_eq!(b.receive(&messages[0][1], now), vec![vec![0; 8]]);
+        assert_eq!(b.partial.len(), MAX_PARTIAL_MESSAGES - 1);
+    }
+
+    #[test]
+    fn nothing_is_acked_before_the_first_reliable_message() {
+        let (mut a, mut b, now) = endpoints();
+        a.send(b"1", DeliveryRequirement::ReliableOrdered, now)
+            .unwrap();
+        let later = now + b.config().heartbeat_interval;
+        let heartbeats = b.update(later);
+        assert_eq!(Header::read(&heartbeats[0]).unwrap().ack, None);
+        a.receive(&heartbeats[0], later);
+        assert_eq!(a.unacknowledged(), 1);
+    }
+
+    #[test]
+    fn heartbeats_and_timeouts() {
+        let (mut a, mut b, now) = endpoints();
+        assert!(a.update(now).is_empty());
+        let later = now + a.config().heartbeat_interval;
+        let heartbeats = a.update(later);
+        assert_eq!(heartbeats.len(), 1);
+        assert!(b.receive(&heartbeats[0], later).is_empty());
+
+        let timeout = b.config().timeout;
+        assert!(!b.is_timed_out(later + timeout));
+        assert!(b.is_timed_out(later + timeout + Duration::from_millis(1)));
+    }
+
+    #[test]
+    fn too_large_messages_are_rejected() {
+        let (mut a, _, now) = endpoints();
+        let payload = vec![0; 4 * MAX_FRAGMENTS + 1];
+        assert!(a
+            .send(&payload, DeliveryRequirement::Unreliable, now)
+            .is_err());
+    }
+}
@@ -0,0 +1,177 @@
+use std::marker::PhantomData;
+
+use bincode::deserialize;
+use fnv::FnvHashMap;
+use log::error;
+use shrev::ReaderId;
+
+use amethyst_core::specs::{Entities, Entity, Join, System, Write, WriteStorage};
+
+use crate::{
+    replication::{NetworkId, ReplicatedComponent, ReplicationClientState, ReplicationSnapshot},
+    NetConnection, NetEvent,
+};
+
+/// Receives the `ReplicationSnapshot`s sent by the server.
+///
+/// Creates a local entity for every new `NetworkId`, deletes the entities that were removed, and
+/// queues the component updates for the `ComponentApplySystem`s, which have to run after this
+/// system.
+///
+/// ### Type parameters:
+///
+/// - `E`: the custom event type of the `NetConnection`s
+pub struct ReplicationClientSystem<E: 'static> {
+    readers: FnvHashMap<Entity, ReaderId<NetEvent<E>>>,
+}
+
+impl<E: 'static> ReplicationClientSystem<E> {
+    /// Creates a new `ReplicationClientSystem`.
+    pub fn new() -> Self {
+        ReplicationClientSystem {
+            readers: FnvHashMap::default(),
+        }
+    }
+}
+
+impl<E: 'static> Default for ReplicationClientSystem<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, E> System<'a> for ReplicationClientSystem<E>
+where
+    E: Send + Sync + 'static,
+{
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, NetConnection<E>>,
+        WriteStorage<'a, NetworkId>,
+        Write<'a, ReplicationClientState>,
+    );
+
+    fn run(&mut self, (entities, mut connections, mut ids, mut state): Self::SystemData) {
+        let state = &mut *state;
+        state.pending.clear();
+
+        let mut snapshots = Vec::new();
+        for (entity, connection) in (&*entities, &mut connections).join() {
+            let reader = self
+                .readers
+                .entry(entity)
+                .or_insert_with(|| connection.receive_buffer.register_reader());
+            snapshots.extend(connection.receive_buffer.read(reader).filter_map(
+                |event| match event {
+                    NetEvent::Replication(snapshot) => Some(snapshot.clone()),
+                    _ => None,
+                },
+            ));
+        }
+        self.readers
+            .retain(|entity, _| connections.contains(*entity));
+
+        for ReplicationSnapshot {
+            tick,
+            entities: updates,
+            removed,
+        } in snapshots
+        {
+            state.tick = state.tick.max(tick);
+
+            for id in removed {
+                if let Some(entity) = state.entities.remove(&id) {
+                    if let Err(e) = entities.delete(entity) {
+                        error!("Failed to delete replicated entity: {}", e);
+                    }
+                }
+            }
+
+            for update in updates {
+                let entity = match state.entities.get(&update.id) {
+                    Some(entity) => *entity,
+                    None => {
+                        let entity = entities.create();
+                        if let Err(e) = ids.insert(entity, update.id) {
+                            error!("Failed to insert `NetworkId`: {}", e);
+                        }
+                        state.entities.insert(update.id, entity);
+                        entity
+                    }
+                };
+                for component in update.components {
+                    state
+                        .pending
+                        .entry(component.component)
+                        .or_default()
+                        .push((entity, component.data));
+                }
+            }
+        }
+    }
+}
+
+/// Applies the received updates of the `ReplicatedComponent` `C` to the local entities.
+///
+/// ### Type parameters:
+///
+/// - `C`: the replicated component
+pub struct ComponentApplySystem<C> {
+    _marker: PhantomData<C>,
+}
+
+impl<C> ComponentApplySystem<C> {
+    /// Creates a new `ComponentApplySystem`.
+    pub fn new() -> Self {
+        ComponentApplySystem {
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<C> Default for ComponentApplySystem<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, C> System<'a> for ComponentApplySystem<C>
+where
+    C: ReplicatedComponent,
+{
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, C>,
+        Write<'a, ReplicationClientState>,
+    );
+
+    fn run(&mut self, (entities, mut components, mut state): Self::SystemData) {
+        let updates = match state.pending.remove(C::NAME) {
+            Some(updates) => updates,
+            None => return,
+        };
+
+        for (entity, data) in updates {
+            if !entities.is_alive(entity) {
+                continue;
+            }
+            match data {
+                Some(data) => match deserialize::<C>(&data) {
+                    Ok(component) => {
+                        if let Err(e) = components.insert(entity, component) {
+                            error!("Failed to insert replicated component {}: {}", C::NAME, e);
+                        }
+                    }
+                    Err(e) => error!(
+                        "Failed to deserialize replicated component {}: {}",
+                        C::NAME,
+                        e
+                    ),
+                },
+                None => {
+                    components.remove(entity);
+                }
+            }
+        }
+    }
+}
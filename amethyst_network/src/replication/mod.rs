@@ -0,0 +1,180 @@
+//! Replication of entities and their components from a server to its clients.
+//!
+//! On the server, entities marked with `Replicated` get a `NetworkId`. Every tick, the registered
+//! `ReplicatedComponent`s of those entities are diffed against the previous tick, and the changes
+//! are sent to the clients that are interested in the entity. Clients map each `NetworkId` to a
+//! local entity, and apply the received component states to it.
+
+use fnv::FnvHashMap;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use amethyst_core::{
+    nalgebra::Vector3,
+    specs::{Component, DenseVecStorage, Entity, NullStorage},
+    Transform,
+};
+
+pub use self::{
+    client::{ComponentApplySystem, ReplicationClientSystem},
+    server::{ComponentDiffSystem, NetworkIdSystem, ReplicationServerSystem},
+};
+
+mod client;
+mod server;
+
+/// A component that can be replicated from the server to the clients.
+///
+/// Components are identified on the wire by their `NAME`, so it must be unique and the same on
+/// the server and the clients.
+pub trait ReplicatedComponent: Component + Serialize + DeserializeOwned + Send + Sync {
+    /// Unique name of the component
+    const NAME: &'static str;
+}
+
+impl ReplicatedComponent for Transform {
+    const NAME: &'static str = "core::Transform";
+}
+
+/// Marks an entity on the server to be replicated to the clients.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Replicated;
+
+impl Component for Replicated {
+    type Storage = NullStorage<Self>;
+}
+
+/// Identifier of a replicated entity, shared between the server and the clients.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct NetworkId(pub u64);
+
+impl Component for NetworkId {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Limits the replicated entities sent over a `NetConnection` to the ones within `radius` of
+/// `center`.
+///
+/// Attach to the entity of the `NetConnection` on the server, and keep `center` up to date, for
+/// example with the position of the player of that client. Entities without a `GlobalTransform`
+/// are always replicated. If a connection has no `ReplicationInterest`, all entities are
+/// replicated.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplicationInterest {
+    /// Center of the area of interest
+    pub center: Vector3<f32>,
+    /// Radius of the area of interest
+    pub radius: f32,
+}
+
+impl ReplicationInterest {
+    /// Creates a new area of interest.
+    pub fn new(center: Vector3<f32>, radius: f32) -> Self {
+        ReplicationInterest { center, radius }
+    }
+
+    /// Returns true if the given position is within the area of interest.
+    pub fn contains(&self, position: &Vector3<f32>) -> bool {
+        (position - self.center).norm_squared() <= self.radius * self.radius
+    }
+}
+
+impl Component for ReplicationInterest {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// The changes of the replicated entities during one server tick, as seen by one client.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReplicationSnapshot {
+    /// The server tick the snapshot was taken at
+    pub tick: u64,
+    /// Entities that were created or changed, or that became relevant to the client
+    pub entities: Vec<EntitySnapshot>,
+    /// Entities that were deleted, or that are no longer relevant to the client
+    pub removed: Vec<NetworkId>,
+}
+
+impl ReplicationSnapshot {
+    /// Returns true if the snapshot contains no changes.
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// The changed components of a single replicated entity.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EntitySnapshot {
+    /// The replicated entity
+    pub id: NetworkId,
+    /// The changed components
+    pub components: Vec<ComponentUpdate>,
+}
+
+/// The new state of a replicated component.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ComponentUpdate {
+    /// The `ReplicatedComponent::NAME` of the component
+    pub component: String,
+    /// The serialized component, `None` if the component was removed
+    pub data: Option<Vec<u8>>,
+}
+
+/// Resource containing the replication state of the server.
+///
+/// Maintained by the `NetworkIdSystem`, the `ComponentDiffSystem`s and the
+/// `ReplicationServerSystem`.
+#[derive(Debug, Default)]
+pub struct ReplicationServerState {
+    pub(crate) tick: u64,
+    pub(crate) next_id: u64,
+    pub(crate) entities: FnvHashMap<NetworkId, Entity>,
+    pub(crate) components: FnvHashMap<NetworkId, FnvHashMap<&'static str, Vec<u8>>>,
+    pub(crate) changed: FnvHashMap<NetworkId, Vec<ComponentUpdate>>,
+    pub(crate) removed: Vec<NetworkId>,
+}
+
+impl ReplicationServerState {
+    /// The last tick that was sent to the clients.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Returns the entity with the given `NetworkId`.
+    pub fn entity(&self, id: NetworkId) -> Option<Entity> {
+        self.entities.get(&id).cloned()
+    }
+}
+
+/// Resource containing the replication state of a client.
+///
+/// Maintained by the `ReplicationClientSystem` and the `ComponentApplySystem`s.
+#[derive(Debug, Default)]
+pub struct ReplicationClientState {
+    pub(crate) tick: u64,
+    pub(crate) entities: FnvHashMap<NetworkId, Entity>,
+    pub(crate) pending: FnvHashMap<String, Vec<(Entity, Option<Vec<u8>>)>>,
+}
+
+impl ReplicationClientState {
+    /// The last server tick that was received.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Returns the local entity mapped to the given `NetworkId`.
+    pub fn entity(&self, id: NetworkId) -> Option<Entity> {
+        self.entities.get(&id).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interest_contains_positions_within_radius() {
+        let interest = ReplicationInterest::new(Vector3::new(1.0, 0.0, 0.0), 2.0);
+        assert!(interest.contains(&Vector3::new(1.0, 0.0, 0.0)));
+        assert!(interest.contains(&Vector3::new(3.0, 0.0, 0.0)));
+        assert!(!interest.contains(&Vector3::new(1.0, 2.5, 0.0)));
+    }
+}
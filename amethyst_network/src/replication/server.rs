@@ -0,0 +1,272 @@
+use std::{marker::PhantomData, mem};
+
+use bincode::serialize;
+use fnv::{FnvHashMap, FnvHashSet};
+use log::error;
+
+use amethyst_core::{
+    nalgebra::Vector3,
+    specs::{Entities, Entity, Join, ReadStorage, System, Write, WriteStorage},
+    GlobalTransform,
+};
+
+use crate::{
+    replication::{
+        ComponentUpdate, EntitySnapshot, NetworkId, Replicated, ReplicatedComponent,
+        ReplicationInterest, ReplicationServerState, ReplicationSnapshot,
+    },
+    ConnectionState, DeliveryRequirement, NetConnection, NetEvent,
+};
+
+/// Assigns a `NetworkId` to all entities marked `Replicated`, and keeps track of the replicated
+/// entities that were deleted.
+#[derive(Default)]
+pub struct NetworkIdSystem;
+
+impl NetworkIdSystem {
+    /// Creates a new `NetworkIdSystem`.
+    pub fn new() -> Self {
+        NetworkIdSystem
+    }
+}
+
+impl<'a> System<'a> for NetworkIdSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Replicated>,
+        WriteStorage<'a, NetworkId>,
+        Write<'a, ReplicationServerState>,
+    );
+
+    fn run(&mut self, (entities, replicated, mut ids, mut state): Self::SystemData) {
+        let state = &mut *state;
+
+        let removed = state
+            .entities
+            .iter()
+            .filter(|(id, entity)| {
+                !entities.is_alive(**entity)
+                    || !replicated.contains(**entity)
+                    || ids.get(**entity) != Some(*id)
+            })
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        for id in removed {
+            if let Some(entity) = state.entities.remove(&id) {
+                if entities.is_alive(entity) && ids.get(entity) == Some(&id) {
+                    ids.remove(entity);
+                }
+            }
+            state.components.remove(&id);
+            state.changed.remove(&id);
+            state.removed.push(id);
+        }
+
+        let new_entities = (&*entities, &replicated, !&ids)
+            .join()
+            .map(|(entity, _, _)| entity)
+            .collect::<Vec<_>>();
+        for entity in new_entities {
+            let id = NetworkId(state.next_id);
+            state.next_id += 1;
+            if let Err(e) = ids.insert(entity, id) {
+                error!("Failed to insert `NetworkId`: {}", e);
+                continue;
+            }
+            state.entities.insert(id, entity);
+        }
+    }
+}
+
+/// Diffs the `ReplicatedComponent` `C` of all replicated entities against the state sent on the
+/// previous tick.
+///
+/// ### Type parameters:
+///
+/// - `C`: the replicated component
+pub struct ComponentDiffSystem<C> {
+    _marker: PhantomData<C>,
+}
+
+impl<C> ComponentDiffSystem<C> {
+    /// Creates a new `ComponentDiffSystem`.
+    pub fn new() -> Self {
+        ComponentDiffSystem {
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<C> Default for ComponentDiffSystem<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, C> System<'a> for ComponentDiffSystem<C>
+where
+    C: ReplicatedComponent,
+{
+    type SystemData = (
+        ReadStorage<'a, NetworkId>,
+        ReadStorage<'a, C>,
+        Write<'a, ReplicationServerState>,
+    );
+
+    fn run(&mut self, (ids, components, mut state): Self::SystemData) {
+        let state = &mut *state;
+
+        for (id, entity) in &state.entities {
+            if ids.get(*entity) != Some(id) {
+                continue;
+            }
+            let current = match components.get(*entity).map(serialize).transpose() {
+                Ok(current) => current,
+                Err(e) => {
+                    error!(
+                        "Failed to serialize replicated component {}: {}",
+                        C::NAME,
+                        e
+                    );
+                    continue;
+                }
+            };
+            let cache = state.components.entry(*id).or_default();
+            if cache.get(C::NAME) == current.as_ref() {
+                continue;
+            }
+            match current {
+                Some(ref data) => {
+                    cache.insert(C::NAME, data.clone());
+                }
+                None => {
+                    cache.remove(C::NAME);
+                }
+            }
+            state.changed.entry(*id).or_default().push(ComponentUpdate {
+                component: C::NAME.to_string(),
+                data: current,
+            });
+        }
+    }
+}
+
+/// Sends the changes of the replicated entities to all connected clients.
+///
+/// Entities that become relevant to a client are sent in full, later ticks only contain the
+/// changed components. Entities that are deleted or leave the `ReplicationInterest` of the
+/// client are removed on the client.
+///
+/// ### Type parameters:
+///
+/// - `E`: the custom event type of the `NetConnection`s
+pub struct ReplicationServerSystem<E> {
+    known: FnvHashMap<Entity, FnvHashSet<NetworkId>>,
+    _marker: PhantomData<E>,
+}
+
+impl<E> ReplicationServerSystem<E> {
+    /// Creates a new `ReplicationServerSystem`.
+    pub fn new() -> Self {
+        ReplicationServerSystem {
+            known: FnvHashMap::default(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<E> Default for ReplicationServerSystem<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, E> System<'a> for ReplicationServerSystem<E>
+where
+    E: Send + Sync + 'static,
+{
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, NetConnection<E>>,
+        ReadStorage<'a, ReplicationInterest>,
+        ReadStorage<'a, GlobalTransform>,
+        Write<'a, ReplicationServerState>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, mut connections, interests, transforms, mut state): Self::SystemData,
+    ) {
+        state.tick += 1;
+        let tick = state.tick;
+        let removed = mem::replace(&mut state.removed, Vec::new());
+        let changed = mem::replace(&mut state.changed, FnvHashMap::default());
+
+        for (connection_entity, connection, interest) in
+            (&*entities, &mut connections, (&interests).maybe()).join()
+        {
+            if connection.state != ConnectionState::Connected {
+                self.known.remove(&connection_entity);
+                continue;
+            }
+            let known = self.known.entry(connection_entity).or_default();
+
+            let mut snapshot = ReplicationSnapshot {
+                tick,
+                ..Default::default()
+            };
+            snapshot
+                .removed
+                .extend(removed.iter().filter(|id| known.remove(*id)).cloned());
+
+            for (id, entity) in &state.entities {
+                let relevant = match (interest, transforms.get(*entity)) {
+                    (Some(interest), Some(transform)) => {
+                        let m = &transform.0;
+                        interest.contains(&Vector3::new(m[(0, 3)], m[(1, 3)], m[(2, 3)]))
+                    }
+                    _ => true,
+                };
+
+                if !relevant {
+                    if known.remove(id) {
+                        snapshot.removed.push(*id);
+                    }
+                } else if known.insert(*id) {
+                    let components = state
+                        .components
+                        .get(id)
+                        .map(|components| {
+                            components
+                                .iter()
+                                .map(|(name, data)| ComponentUpdate {
+                                    component: name.to_string(),
+                                    data: Some(data.clone()),
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                    snapshot.entities.push(EntitySnapshot {
+                        id: *id,
+                        components,
+                    });
+                } else if let Some(components) = changed.get(id) {
+                    snapshot.entities.push(EntitySnapshot {
+                        id: *id,
+                        components: components.clone(),
+                    });
+                }
+            }
+
+            if !snapshot.is_empty() {
+                // Only changes are sent, so every snapshot has to arrive, in order.
+                connection.send_with(
+                    NetEvent::Replication(snapshot),
+                    DeliveryRequirement::ReliableOrdered,
+                );
+            }
+        }
+
+        self.known.retain(|entity, _| connections.contains(*entity));
+    }
+}
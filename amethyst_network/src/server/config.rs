@@ -7,6 +7,11 @@ pub struct ServerConfig {
     pub udp_recv_addr: SocketAddr,
     /// Address from which the UDP server will be sending packets.
     pub udp_send_addr: SocketAddr,
+    /// Address at which the TCP server will listen for incoming streams, `None` disables TCP.
+    pub tcp_addr: Option<SocketAddr>,
+    /// Address at which the WebSocket server will listen for incoming connections, `None`
+    /// disables WebSocket.
+    pub websocket_addr: Option<SocketAddr>,
     /// Specifies what the maximal packets that could be handled by the server.
     /// This value is meant for preventing some loops to read infinitely long when many packets are send and received.
     /// This value is by default 5000.
@@ -19,6 +24,8 @@ impl Default for ServerConfig {
             // by passing in :0 port the OS will give an available port.
             udp_recv_addr: "0.0.0.0:0".parse().unwrap(),
             udp_send_addr: "0.0.0.0:0".parse().unwrap(),
+            tcp_addr: None,
+            websocket_addr: None,
             max_throughput: 5000,
         }
     }
@@ -3,12 +3,16 @@
 /// 1. Sending Data
 /// 2. Receiving Data
 /// 3. Broadcasting
-use crate::error::Result;
+use crate::error::{Error, Result};
 use crate::server::{
-    ReceiveHandler, SendHandler, ServerConfig, ServerSocketEvent, UdpReceiver, UdpSender,
+    ReceiveHandler, SendHandler, ServerConfig, ServerSocketEvent, TcpHandler, UdpReceiver,
+    UdpSender, WebSocketHandler,
 };
 use laminar::Packet;
-use std::sync::{Arc, Mutex};
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
 
 /// 'Host' abstracts TCP and UDP sockets away.
 pub struct Host {
@@ -16,6 +20,10 @@ pub struct Host {
     udp_receiver: Arc<Mutex<ReceiveHandler>>,
     // Handler to access the internals of the UDP sender thread
     udp_sender: Arc<SendHandler>,
+    // Handler to access the TCP streams, if TCP is enabled
+    tcp: Option<Arc<TcpHandler>>,
+    // Handler to access the WebSocket connections, if WebSocket is enabled
+    websocket: Option<Arc<WebSocketHandler>>,
 }
 
 impl Host {
@@ -32,9 +40,23 @@ impl Host {
         // setup the UDP-sender which will send packets to an certain endpoint.
         let udp_sender = Arc::new(UdpSender::run(config.udp_send_addr)?);
 
+        // setup the TCP-listener if enabled, streams to other endpoints are opened on demand.
+        let tcp = match config.tcp_addr {
+            Some(addr) => Some(Arc::new(TcpHandler::run(addr)?)),
+            None => None,
+        };
+
+        // setup the WebSocket-listener if enabled.
+        let websocket = match config.websocket_addr {
+            Some(addr) => Some(Arc::new(WebSocketHandler::run(addr)?)),
+            None => None,
+        };
+
         Ok(Host {
             udp_sender,
             udp_receiver,
+            tcp,
+            websocket,
         })
     }
 
@@ -48,9 +70,30 @@ impl Host {
         self.udp_sender.clone()
     }
 
-    /// Schedule a TCP-packet for sending.
-    pub fn send_tcp(&mut self, _payload: &[u8]) -> Result<()> {
-        unimplemented!()
+    /// Get the handle to the TCP streams, if TCP is enabled.
+    pub fn tcp_handle(&self) -> Option<Arc<TcpHandler>> {
+        self.tcp.clone()
+    }
+
+    /// Get the handle to the WebSocket connections, if WebSocket is enabled.
+    pub fn websocket_handle(&self) -> Option<Arc<WebSocketHandler>> {
+        self.websocket.clone()
+    }
+
+    /// Send a WebSocket-message to the given address.
+    pub fn send_websocket(&mut self, addr: SocketAddr, payload: Vec<u8>) -> Result<()> {
+        match self.websocket {
+            Some(ref websocket) => websocket.send(addr, payload),
+            None => Err(Error::WebSocketDisabled),
+        }
+    }
+
+    /// Send a TCP-frame to the given address.
+    pub fn send_tcp(&mut self, addr: SocketAddr, payload: &[u8]) -> Result<()> {
+        match self.tcp {
+            Some(ref tcp) => tcp.send(addr, payload),
+            None => Err(Error::TcpDisabled),
+        }
     }
 
     /// Schedule a UDP-packet for sending.
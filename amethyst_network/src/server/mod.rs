@@ -3,17 +3,21 @@ mod host;
 mod receive_handler;
 mod send_handler;
 mod server_socket_event;
+mod tcp;
 mod udp;
+mod websocket;
 
 pub use self::{
     config::ServerConfig,
     host::Host,
     receive_handler::ReceiveHandler,
     send_handler::SendHandler,
+    tcp::{read_frame, write_frame, TcpHandler, MAX_FRAME_SIZE},
     udp::{UdpReceiver, UdpSender},
+    websocket::WebSocketHandler,
 };
 
-pub use self::server_socket_event::{ClientEvent, ServerSocketEvent};
+pub use self::server_socket_event::{ClientEvent, ServerSocketEvent, StreamEvent};
 use std::sync::mpsc::{Receiver, Sender};
 
 /// Can be implemented for the receiving side of a socket.
@@ -1,4 +1,5 @@
 use laminar::{error::NetworkError, Event, Packet};
+use std::net::SocketAddr;
 
 /// Net event which occurred on the network.
 pub enum ServerSocketEvent {
@@ -35,3 +36,20 @@ impl From<Event> for ClientEvent {
         }
     }
 }
+
+/// Event that occurred on one of the streams of a stream based transport, like TCP.
+#[derive(Debug, PartialEq)]
+pub enum StreamEvent {
+    /// A message was received from the given address.
+    Frame {
+        /// The address of the remote end of the stream
+        addr: SocketAddr,
+        /// The received data
+        payload: Vec<u8>,
+    },
+    /// The stream to the given address was closed.
+    Disconnected {
+        /// The address of the remote end of the stream
+        addr: SocketAddr,
+    },
+}
@@ -0,0 +1,227 @@
+//! All TCP related logic for getting and sending data out to the other side.
+//!
+//! Messages are framed with a 4 byte big endian length prefix.
+
+use crate::{
+    error::{Error, Result},
+    server::StreamEvent,
+};
+use fnv::FnvHashMap;
+use log::{error, warn};
+use std::{
+    collections::hash_map::Entry,
+    io::{self, Read, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+/// The maximal size of a single frame, larger frames are rejected.
+pub const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+/// Writes a single length prefixed frame.
+pub fn write_frame<W: Write>(writer: &mut W, payload: &[u8]) -> Result<()> {
+    if payload.len() > MAX_FRAME_SIZE {
+        return Err(Error::MessageTooLarge(payload.len()));
+    }
+    let mut frame = Vec::with_capacity(4 + payload.len());
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    frame.extend_from_slice(payload);
+    writer.write_all(&frame)?;
+    Ok(())
+}
+
+/// Reads a single length prefixed frame, blocking until it is complete.
+pub fn read_frame<R: Read>(reader: &mut R) -> Result<Vec<u8>> {
+    let mut len = [0; 4];
+    reader.read_exact(&mut len)?;
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_FRAME_SIZE {
+        return Err(Error::MessageTooLarge(len));
+    }
+    let mut payload = vec![0; len];
+    reader.read_exact(&mut payload)?;
+    Ok(payload)
+}
+
+type Streams = Arc<Mutex<FnvHashMap<SocketAddr, TcpStream>>>;
+
+/// Handler to access the TCP streams of a host.
+///
+/// Accepts incoming streams on the listening address, and connects to remote hosts the first time
+/// something is sent to them.
+pub struct TcpHandler {
+    local_addr: SocketAddr,
+    streams: Streams,
+    event_sender: Mutex<Sender<StreamEvent>>,
+    events: Mutex<Receiver<StreamEvent>>,
+}
+
+impl TcpHandler {
+    /// This will run the TCP listener on it's own thread.
+    pub fn run(addr: SocketAddr) -> Result<TcpHandler> {
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+        let streams = Streams::default();
+        let (tx, rx) = mpsc::channel();
+
+        let accept_streams = streams.clone();
+        let accept_sender = tx.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        if let Err(e) = add_stream(stream, &accept_streams, &accept_sender) {
+                            error!("Failed to accept TCP stream. Reason: {:?}", e);
+                        }
+                    }
+                    Err(e) => error!("Failed to accept TCP stream. Reason: {:?}", e),
+                }
+            }
+        });
+
+        Ok(TcpHandler {
+            local_addr,
+            streams,
+            event_sender: Mutex::new(tx),
+            events: Mutex::new(rx),
+        })
+    }
+
+    /// The address the listener is bound to.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Sends a frame to the given address, connecting to it if there is no stream yet.
+    pub fn send(&self, addr: SocketAddr, payload: &[u8]) -> Result<()> {
+        let connected = self
+            .streams
+            .lock()
+            .expect("TCP streams mutex poisoned")
+            .contains_key(&addr);
+        // Connecting can take a while, so the streams are not locked in the meantime.
+        let stream = if connected {
+            None
+        } else {
+            Some(TcpStream::connect(addr)?)
+        };
+
+        let mut streams = self.streams.lock().expect("TCP streams mutex poisoned");
+        if let Some(stream) = stream {
+            // Another stream to the address may have been added while connecting.
+            if let Entry::Vacant(entry) = streams.entry(addr) {
+                let sender = self
+                    .event_sender
+                    .lock()
+                    .expect("TCP event sender mutex poisoned")
+                    .clone();
+                spawn_reader(&stream, sender)?;
+                entry.insert(stream);
+            }
+        }
+
+        let result = match streams.get_mut(&addr) {
+            Some(stream) => write_frame(stream, payload),
+            None => Err(io::Error::from(io::ErrorKind::NotConnected).into()),
+        };
+        if result.is_err() {
+            streams.remove(&addr);
+        }
+        result
+    }
+
+    /// Closes the stream to the given address, if any.
+    pub fn disconnect(&self, addr: SocketAddr) {
+        let stream = self
+            .streams
+            .lock()
+            .expect("TCP streams mutex poisoned")
+            .remove(&addr);
+        if let Some(stream) = stream {
+            if let Err(e) = stream.shutdown(Shutdown::Both) {
+                warn!("Failed to shut down TCP stream. Reason: {:?}", e);
+            }
+        }
+    }
+
+    /// Returns all events that occurred since the last call.
+    pub fn events(&self) -> Vec<StreamEvent> {
+        self.events
+            .lock()
+            .expect("TCP events mutex poisoned")
+            .try_iter()
+            .collect()
+    }
+}
+
+// Registers the stream for sending, and starts reading frames from it.
+fn add_stream(stream: TcpStream, streams: &Streams, sender: &Sender<StreamEvent>) -> Result<()> {
+    spawn_reader(&stream, sender.clone())?;
+    streams
+        .lock()
+        .expect("TCP streams mutex poisoned")
+        .insert(stream.peer_addr()?, stream);
+    Ok(())
+}
+
+// Starts a thread reading frames from the stream until it is closed.
+fn spawn_reader(stream: &TcpStream, sender: Sender<StreamEvent>) -> io::Result<()> {
+    stream.set_nodelay(true)?;
+    let addr = stream.peer_addr()?;
+    let mut reader = stream.try_clone()?;
+
+    thread::spawn(move || loop {
+        match read_frame(&mut reader) {
+            Ok(payload) => {
+                if sender.send(StreamEvent::Frame { addr, payload }).is_err() {
+                    break;
+                }
+            }
+            Err(e) => {
+                match e {
+                    Error::IoError(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => {}
+                    e => warn!("TCP stream to {} closed. Reason: {:?}", addr, e),
+                }
+                // Closing the stream makes the sending side drop it on the next send.
+                let _ = reader.shutdown(Shutdown::Both);
+                let _ = sender.send(StreamEvent::Disconnected { addr });
+                break;
+            }
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn frames_round_trip() {
+        let mut buffer = Vec::new();
+        write_frame(&mut buffer, b"hello").unwrap();
+        write_frame(&mut buffer, b"").unwrap();
+        assert_eq!(&buffer[..4], &[0, 0, 0, 5]);
+
+        let mut reader = Cursor::new(buffer);
+        assert_eq!(read_frame(&mut reader).unwrap(), b"hello".to_vec());
+        assert_eq!(read_frame(&mut reader).unwrap(), Vec::<u8>::new());
+        assert!(read_frame(&mut reader).is_err());
+    }
+
+    #[test]
+    fn oversized_frames_are_rejected() {
+        let mut reader = Cursor::new(((MAX_FRAME_SIZE + 1) as u32).to_be_bytes().to_vec());
+        match read_frame(&mut reader) {
+            Err(Error::MessageTooLarge(_)) => {}
+            _ => panic!("Expected the frame to be rejected"),
+        }
+    }
+}
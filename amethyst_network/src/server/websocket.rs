@@ -0,0 +1,332 @@
+//! All WebSocket related logic for getting and sending data out to the other side.
+//!
+//! Events are sent as binary messages, text messages are accepted as well for debug tools.
+
+use crate::{
+    error::{Error, Result},
+    server::StreamEvent,
+};
+use fnv::FnvHashMap;
+use log::{error, warn};
+use std::{
+    collections::hash_map::Entry,
+    io,
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        mpsc::{self, Receiver, Sender, TryRecvError},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+use tungstenite::{handshake::HandshakeError, Message, WebSocket};
+use url::Url;
+
+// How long a socket thread waits for incoming messages before sending the queued ones.
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+// How long the handshake of a connection may block before the connection is dropped.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+type Sockets = Arc<Mutex<FnvHashMap<SocketAddr, Sender<Vec<u8>>>>>;
+
+/// Handler to access the WebSocket connections of a host.
+///
+/// Accepts incoming connections on the listening address, and connects to remote hosts the first
+/// time something is sent to them.
+pub struct WebSocketHandler {
+    local_addr: SocketAddr,
+    sockets: Sockets,
+    event_sender: Mutex<Sender<StreamEvent>>,
+    events: Mutex<Receiver<StreamEvent>>,
+}
+
+impl WebSocketHandler {
+    /// This will run the WebSocket listener on it's own thread.
+    pub fn run(addr: SocketAddr) -> Result<WebSocketHandler> {
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+        let sockets = Sockets::default();
+        let (tx, rx) = mpsc::channel();
+
+        let accept_sockets = sockets.clone();
+        let accept_sender = tx.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        error!("Failed to accept WebSocket connection. Reason: {:?}", e);
+                        continue;
+                    }
+                };
+                // The handshake has its own thread, so a client which never finishes it does not
+                // stop the next ones from connecting.
+                let sockets = accept_sockets.clone();
+                let sender = accept_sender.clone();
+                thread::spawn(move || {
+                    let result = accept(stream).and_then(|(socket, addr)| {
+                        if !spawn_socket(socket, addr, &sockets, sender)? {
+                            warn!("Dropped a second WebSocket connection from {}", addr);
+                        }
+                        Ok(())
+                    });
+                    if let Err(e) = result {
+                        error!("Failed to accept WebSocket connection. Reason: {:?}", e);
+                    }
+                });
+            }
+        });
+
+        Ok(WebSocketHandler {
+            local_addr,
+            sockets,
+            event_sender: Mutex::new(tx),
+            events: Mutex::new(rx),
+        })
+    }
+
+    /// The address the listener is bound to.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Sends a message to the given address, connecting to it if there is no connection yet.
+    pub fn send(&self, addr: SocketAddr, payload: Vec<u8>) -> Result<()> {
+        let connected = self
+            .sockets
+            .lock()
+            .expect("WebSocket sockets mutex poisoned")
+            .contains_key(&addr);
+        if !connected {
+            let url = Url::parse(&format!("ws://{}/", addr)).expect("Unreachable: Valid url");
+            let stream = TcpStream::connect_timeout(&addr, HANDSHAKE_TIMEOUT)?;
+            set_handshake_timeout(&stream)?;
+            let (socket, _) = tungstenite::client(url, stream).map_err(handshake_error)?;
+            let sender = self
+                .event_sender
+                .lock()
+                .expect("WebSocket event sender mutex poisoned")
+                .clone();
+            // Another connection to the address may have been added while connecting, in which
+            // case it is used instead.
+            spawn_socket(socket, addr, &self.sockets, sender)?;
+        }
+
+        let mut sockets = self
+            .sockets
+            .lock()
+            .expect("WebSocket sockets mutex poisoned");
+        let result = match sockets.get(&addr) {
+            Some(socket) => socket.send(payload).map_err(|_| {
+                Error::IoError(io::Error::new(
+                    io::ErrorKind::NotConnected,
+                    "WebSocket connection closed",
+                ))
+            }),
+            None => Ok(()),
+        };
+        if result.is_err() {
+            sockets.remove(&addr);
+        }
+        result
+    }
+
+    /// Closes the connection to the given address, if any.
+    pub fn disconnect(&self, addr: SocketAddr) {
+        // Dropping the sender makes the socket thread close the connection.
+        self.sockets
+            .lock()
+            .expect("WebSocket sockets mutex poisoned")
+            .remove(&addr);
+    }
+
+    /// Returns all events that occurred since the last call.
+    pub fn events(&self) -> Vec<StreamEvent> {
+        self.events
+            .lock()
+            .expect("WebSocket events mutex poisoned")
+            .try_iter()
+            .collect()
+    }
+}
+
+// Accepts the handshake of an incoming connection.
+fn accept(stream: TcpStream) -> Result<(WebSocket<TcpStream>, SocketAddr)> {
+    let addr = stream.peer_addr()?;
+    set_handshake_timeout(&stream)?;
+    let socket = tungstenite::accept(stream).map_err(handshake_error)?;
+    Ok((socket, addr))
+}
+
+fn set_handshake_timeout(stream: &TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    stream.set_write_timeout(Some(HANDSHAKE_TIMEOUT))
+}
+
+fn handshake_error<R>(e: HandshakeError<R>) -> Error
+where
+    R: tungstenite::handshake::HandshakeRole,
+{
+    match e {
+        HandshakeError::Failure(e) => Error::WebSocketError(e),
+        // The streams block during the handshake, so it is only interrupted by their timeouts.
+        HandshakeError::Interrupted(_) => Error::IoError(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "WebSocket handshake timed out",
+        )),
+    }
+}
+
+fn is_would_block(e: &tungstenite::Error) -> bool {
+    match e {
+        tungstenite::Error::Io(e) => {
+            e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut
+        }
+        _ => false,
+    }
+}
+
+// Registers the socket for sending, and starts a thread exchanging messages on it until the
+// connection is closed.
+//
+// Returns `false` and closes the socket if there already is a connection to the address.
+fn spawn_socket(
+    mut socket: WebSocket<TcpStream>,
+    addr: SocketAddr,
+    sockets: &Sockets,
+    events: Sender<StreamEvent>,
+) -> Result<bool> {
+    socket.get_mut().set_nodelay(true)?;
+    socket.get_mut().set_read_timeout(Some(POLL_INTERVAL))?;
+    socket.get_mut().set_write_timeout(None)?;
+
+    let (tx, outgoing) = mpsc::channel();
+    match sockets
+        .lock()
+        .expect("WebSocket sockets mutex poisoned")
+        .entry(addr)
+    {
+        Entry::Vacant(entry) => {
+            entry.insert(tx);
+        }
+        Entry::Occupied(_) => {
+            let _ = socket.close(None);
+            let _ = socket.write_pending();
+            return Ok(false);
+        }
+    }
+
+    let sockets = sockets.clone();
+    thread::spawn(move || {
+        loop {
+            let mut closing = false;
+            loop {
+                match outgoing.try_recv() {
+                    Ok(payload) => match socket.write_message(Message::Binary(payload)) {
+                        Err(ref e) if !is_would_block(e) => {
+                            warn!("Failed to send WebSocket message to {}: {}", addr, e);
+                            break;
+                        }
+                        _ => {}
+                    },
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        closing = true;
+                        break;
+                    }
+                }
+            }
+            if closing {
+                let _ = socket.close(None);
+                let _ = socket.write_pending();
+                break;
+            }
+
+            match socket.read_message() {
+                Ok(Message::Binary(payload)) => {
+                    if events.send(StreamEvent::Frame { addr, payload }).is_err() {
+                        break;
+                    }
+                }
+                Ok(Message::Text(text)) => {
+                    let payload = text.into_bytes();
+                    if events.send(StreamEvent::Frame { addr, payload }).is_err() {
+                        break;
+                    }
+                }
+                // Ping, pong and close messages are answered by tungstenite.
+                Ok(_) => {}
+                Err(ref e) if is_would_block(e) => {}
+                Err(e) => {
+                    match e {
+                        tungstenite::Error::ConnectionClosed
+                        | tungstenite::Error::AlreadyClosed => {}
+                        e => warn!("WebSocket connection to {} closed. Reason: {}", addr, e),
+                    }
+                    break;
+                }
+            }
+        }
+
+        sockets
+            .lock()
+            .expect("WebSocket sockets mutex poisoned")
+            .remove(&addr);
+        let _ = events.send(StreamEvent::Disconnected { addr });
+    });
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    // Waits for the first frame received by the handler.
+    fn next_frame(handler: &WebSocketHandler) -> (SocketAddr, Vec<u8>) {
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_secs(5) {
+            for event in handler.events() {
+                if let StreamEvent::Frame { addr, payload } = event {
+                    return (addr, payload);
+                }
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+        panic!("No WebSocket frame received");
+    }
+
+    fn localhost() -> SocketAddr {
+        "127.0.0.1:0".parse().unwrap()
+    }
+
+    #[test]
+    fn loopback_handshake_sends_and_receives() {
+        let server = WebSocketHandler::run(localhost()).unwrap();
+        let client = WebSocketHandler::run(localhost()).unwrap();
+
+        client.send(server.local_addr(), b"hello".to_vec()).unwrap();
+        let (client_addr, payload) = next_frame(&server);
+        assert_eq!(payload, b"hello".to_vec());
+
+        // The server answers on the accepted connection.
+        server.send(client_addr, b"world".to_vec()).unwrap();
+        let (server_addr, payload) = next_frame(&client);
+        assert_eq!(server_addr, server.local_addr());
+        assert_eq!(payload, b"world".to_vec());
+    }
+
+    #[test]
+    fn stalled_handshake_does_not_block_other_clients() {
+        let server = WebSocketHandler::run(localhost()).unwrap();
+        // Connects without ever sending the handshake.
+        let _stalled = TcpStream::connect(server.local_addr()).unwrap();
+
+        let client = WebSocketHandler::run(localhost()).unwrap();
+        client.send(server.local_addr(), b"hello".to_vec()).unwrap();
+        assert_eq!(next_frame(&server).1, b"hello".to_vec());
+    }
+}
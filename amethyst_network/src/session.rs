@@ -0,0 +1,562 @@
+//! Join and leave handshakes, client ids and rooms on top of `NetConnection`s.
+//!
+//! Clients send a `SessionMessage::Join` with their protocol version when connecting. The server
+//! checks the version, assigns a `ClientId` to the connection and answers with a
+//! `SessionMessage::Welcome`. Clients can then join and leave rooms, which the server keeps track
+//! of in the `Sessions` resource. Both sides emit `SessionEvent`s.
+
+use fnv::{FnvHashMap, FnvHashSet};
+use log::error;
+use serde::{Deserialize, Serialize};
+use shrev::{EventChannel, ReaderId};
+use uuid::Uuid;
+
+use amethyst_core::specs::{
+    Component, DenseVecStorage, Entities, Entity, Join, Read, System, Write, WriteStorage,
+};
+
+use crate::{ConnectionState, DeliveryRequirement, NetConnection, NetEvent, NetIdentity};
+
+/// Identifier of a client, assigned by the server when the client joins.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ClientId(pub u32);
+
+impl Component for ClientId {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// The messages of the session protocol.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SessionMessage {
+    /// Sent by a client to join the server.
+    Join {
+        /// The uuid of the client
+        client_uuid: Uuid,
+        /// The protocol version of the client, has to match the one of the server
+        protocol_version: u32,
+        /// The name of the client
+        name: String,
+    },
+    /// Sent by the server when a client joined.
+    Welcome {
+        /// The id assigned to the client
+        client: ClientId,
+    },
+    /// Sent by the server when a client was not allowed to join.
+    Rejected {
+        /// The reason of the refusal
+        reason: String,
+    },
+    /// Sent by a client to leave the server.
+    Leave,
+    /// Sent by a client to join a room.
+    JoinRoom(String),
+    /// Sent by a client to leave a room.
+    LeaveRoom(String),
+    /// Sent by the server to the members of a room when a client joined it.
+    RoomJoined {
+        /// The room
+        room: String,
+        /// The client that joined
+        client: ClientId,
+    },
+    /// Sent by the server to the members of a room when a client left it.
+    RoomLeft {
+        /// The room
+        room: String,
+        /// The client that left
+        client: ClientId,
+    },
+}
+
+/// Events emitted by the session systems into the `EventChannel<SessionEvent>` resource.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SessionEvent {
+    /// A client joined the server.
+    ClientJoined {
+        /// The id of the client
+        client: ClientId,
+        /// The entity of the `NetConnection` to the client
+        connection: Entity,
+        /// The name of the client
+        name: String,
+    },
+    /// A client was not allowed to join the server.
+    ClientRejected {
+        /// The entity of the `NetConnection` to the client
+        connection: Entity,
+        /// The reason of the refusal
+        reason: String,
+    },
+    /// A client left the server, or was disconnected.
+    ClientLeft {
+        /// The id of the client
+        client: ClientId,
+    },
+    /// The local client joined the server.
+    Joined {
+        /// The id assigned to the local client
+        client: ClientId,
+    },
+    /// The local client was not allowed to join the server.
+    Rejected {
+        /// The reason of the refusal
+        reason: String,
+    },
+    /// A client joined a room.
+    RoomJoined {
+        /// The room
+        room: String,
+        /// The client that joined
+        client: ClientId,
+    },
+    /// A client left a room.
+    RoomLeft {
+        /// The room
+        room: String,
+        /// The client that left
+        client: ClientId,
+    },
+}
+
+/// Resource containing the clients and rooms of the server.
+#[derive(Debug, Default)]
+pub struct Sessions {
+    next_id: u32,
+    clients: FnvHashMap<ClientId, Entity>,
+    rooms: FnvHashMap<String, FnvHashSet<ClientId>>,
+}
+
+impl Sessions {
+    /// Returns the entity of the `NetConnection` to the client.
+    pub fn connection(&self, client: ClientId) -> Option<Entity> {
+        self.clients.get(&client).cloned()
+    }
+
+    /// Iterates over all joined clients.
+    pub fn clients(&self) -> impl Iterator<Item = ClientId> + '_ {
+        self.clients.keys().cloned()
+    }
+
+    /// Iterates over the members of a room.
+    pub fn room_members<'a>(&'a self, room: &str) -> impl Iterator<Item = ClientId> + 'a {
+        self.rooms.get(room).into_iter().flatten().cloned()
+    }
+
+    /// Iterates over the rooms the client is a member of.
+    pub fn rooms_of(&self, client: ClientId) -> impl Iterator<Item = &str> {
+        self.rooms
+            .iter()
+            .filter(move |(_, members)| members.contains(&client))
+            .map(|(room, _)| room.as_str())
+    }
+
+    /// Queues an event to be sent to all joined clients.
+    pub fn broadcast<E>(
+        &self,
+        connections: &mut WriteStorage<'_, NetConnection<E>>,
+        event: NetEvent<E>,
+        delivery: DeliveryRequirement,
+    ) where
+        E: Clone + Send + Sync + 'static,
+    {
+        self.send_to(self.clients(), connections, event, delivery);
+    }
+
+    /// Queues an event to be sent to all members of a room.
+    pub fn broadcast_to_room<E>(
+        &self,
+        room: &str,
+        connections: &mut WriteStorage<'_, NetConnection<E>>,
+        event: NetEvent<E>,
+        delivery: DeliveryRequirement,
+    ) where
+        E: Clone + Send + Sync + 'static,
+    {
+        self.send_to(self.room_members(room), connections, event, delivery);
+    }
+
+    /// Queues an event to be sent to the given clients.
+    pub fn send_to<E, I>(
+        &self,
+        clients: I,
+        connections: &mut WriteStorage<'_, NetConnection<E>>,
+        event: NetEvent<E>,
+        delivery: DeliveryRequirement,
+    ) where
+        E: Clone + Send + Sync + 'static,
+        I: IntoIterator<Item = ClientId>,
+    {
+        for client in clients {
+            if let Some(connection) = self
+                .connection(client)
+                .and_then(|entity| connections.get_mut(entity))
+            {
+                connection.send_with(event.clone(), delivery);
+            }
+        }
+    }
+
+    fn join_room(&mut self, room: String, client: ClientId) -> bool {
+        self.rooms.entry(room).or_default().insert(client)
+    }
+
+    fn leave_room(&mut self, room: &str, client: ClientId) -> bool {
+        let left = match self.rooms.get_mut(room) {
+            Some(members) => members.remove(&client),
+            None => false,
+        };
+        if self.rooms.get(room).map_or(false, FnvHashSet::is_empty) {
+            self.rooms.remove(room);
+        }
+        left
+    }
+}
+
+/// Handles the session messages of the clients on the server.
+///
+/// ### Type parameters:
+///
+/// - `E`: the custom event type of the `NetConnection`s
+pub struct SessionServerSystem<E: 'static> {
+    protocol_version: u32,
+    max_clients: Option<usize>,
+    readers: FnvHashMap<Entity, ReaderId<NetEvent<E>>>,
+}
+
+impl<E: 'static> SessionServerSystem<E> {
+    /// Creates a new `SessionServerSystem`, accepting clients with the given protocol version.
+    pub fn new(protocol_version: u32) -> Self {
+        SessionServerSystem {
+            protocol_version,
+            max_clients: None,
+            readers: FnvHashMap::default(),
+        }
+    }
+
+    /// Limits the number of joined clients.
+    pub fn with_max_clients(mut self, max_clients: usize) -> Self {
+        self.max_clients = Some(max_clients);
+        self
+    }
+}
+
+impl<'a, E> System<'a> for SessionServerSystem<E>
+where
+    E: Clone + Send + Sync + 'static,
+{
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, NetConnection<E>>,
+        WriteStorage<'a, ClientId>,
+        Write<'a, Sessions>,
+        Write<'a, EventChannel<SessionEvent>>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, mut connections, mut client_ids, mut sessions, mut events): Self::SystemData,
+    ) {
+        let mut messages = Vec::new();
+        for (entity, connection) in (&*entities, &mut connections).join() {
+            let reader = self
+                .readers
+                .entry(entity)
+                .or_insert_with(|| connection.receive_buffer.register_reader());
+            for event in connection.receive_buffer.read(reader) {
+                match event {
+                    NetEvent::Session(message) => messages.push((entity, message.clone())),
+                    NetEvent::Disconnect { .. } => {
+                        messages.push((entity, SessionMessage::Leave));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        self.readers
+            .retain(|entity, _| connections.contains(*entity));
+
+        for (entity, message) in messages {
+            let client = client_ids.get(entity).cloned();
+            match (message, client) {
+                (
+                    SessionMessage::Join {
+                        protocol_version,
+                        name,
+                        ..
+                    },
+                    None,
+                ) => {
+                    let rejection = if protocol_version != self.protocol_version {
+                        Some(format!(
+                            "Protocol version {} does not match the server version {}",
+                            protocol_version, self.protocol_version
+                        ))
+                    } else if self
+                        .max_clients
+                        .map_or(false, |max| sessions.clients.len() >= max)
+                    {
+                        Some("The server is full".to_string())
+                    } else {
+                        None
+                    };
+
+                    let connection = connections
+                        .get_mut(entity)
+                        .expect("Unreachable: Messages are read from existing connections");
+                    match rejection {
+                        Some(reason) => {
+                            connection.send_with(
+                                NetEvent::Session(SessionMessage::Rejected {
+                                    reason: reason.clone(),
+                                }),
+                                DeliveryRequirement::ReliableOrdered,
+                            );
+                            events.single_write(SessionEvent::ClientRejected {
+                                connection: entity,
+                                reason,
+                            });
+                        }
+                        None => {
+                            let client = ClientId(sessions.next_id);
+                            sessions.next_id += 1;
+                            if let Err(e) = client_ids.insert(entity, client) {
+                                error!("Failed to insert `ClientId`: {}", e);
+                                continue;
+                            }
+                            sessions.clients.insert(client, entity);
+                            connection.state = ConnectionState::Connected;
+                            connection.send_with(
+                                NetEvent::Session(SessionMessage::Welcome { client }),
+                                DeliveryRequirement::ReliableOrdered,
+                            );
+                            events.single_write(SessionEvent::ClientJoined {
+                                client,
+                                connection: entity,
+                                name,
+                            });
+                        }
+                    }
+                }
+                (SessionMessage::Leave, Some(client)) => {
+                    client_ids.remove(entity);
+                    remove_client(client, &mut sessions, &mut connections, &mut events);
+                }
+                (SessionMessage::JoinRoom(room), Some(client)) => {
+                    if sessions.join_room(room.clone(), client) {
+                        let message = SessionMessage::RoomJoined { room, client };
+                        notify_room(&message, &sessions, &mut connections, &mut events);
+                    }
+                }
+                (SessionMessage::LeaveRoom(room), Some(client)) => {
+                    if sessions.leave_room(&room, client) {
+                        let message = SessionMessage::RoomLeft { room, client };
+                        // The client that left has to be notified as well.
+                        sessions.send_to(
+                            Some(client),
+                            &mut connections,
+                            NetEvent::Session(message.clone()),
+                            DeliveryRequirement::ReliableOrdered,
+                        );
+                        notify_room(&message, &sessions, &mut connections, &mut events);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Clients whose connection was dropped or deleted leave as well.
+        let gone = sessions
+            .clients
+            .iter()
+            .filter(|(_, entity)| {
+                connections
+                    .get(**entity)
+                    .map_or(true, |c| c.state == ConnectionState::Disconnected)
+            })
+            .map(|(client, entity)| (*client, *entity))
+            .collect::<Vec<_>>();
+        for (client, entity) in gone {
+            client_ids.remove(entity);
+            remove_client(client, &mut sessions, &mut connections, &mut events);
+        }
+    }
+}
+
+fn remove_client<E>(
+    client: ClientId,
+    sessions: &mut Sessions,
+    connections: &mut WriteStorage<'_, NetConnection<E>>,
+    events: &mut EventChannel<SessionEvent>,
+) where
+    E: Clone + Send + Sync + 'static,
+{
+    let rooms = sessions
+        .rooms_of(client)
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    for room in rooms {
+        sessions.leave_room(&room, client);
+        let message = SessionMessage::RoomLeft { room, client };
+        notify_room(&message, sessions, connections, events);
+    }
+    sessions.clients.remove(&client);
+    events.single_write(SessionEvent::ClientLeft { client });
+}
+
+// Sends a room message to all members of the room, and emits it as an event.
+fn notify_room<E>(
+    message: &SessionMessage,
+    sessions: &Sessions,
+    connections: &mut WriteStorage<'_, NetConnection<E>>,
+    events: &mut EventChannel<SessionEvent>,
+) where
+    E: Clone + Send + Sync + 'static,
+{
+    let (room, event) = match message {
+        SessionMessage::RoomJoined { room, client } => (
+            room,
+            SessionEvent::RoomJoined {
+                room: room.clone(),
+                client: *client,
+            },
+        ),
+        SessionMessage::RoomLeft { room, client } => (
+            room,
+            SessionEvent::RoomLeft {
+                room: room.clone(),
+                client: *client,
+            },
+        ),
+        _ => return,
+    };
+    sessions.broadcast_to_room(
+        room,
+        connections,
+        NetEvent::Session(message.clone()),
+        DeliveryRequirement::ReliableOrdered,
+    );
+    events.single_write(event);
+}
+
+/// Joins the server on all new connections of a client, and handles the answers of the server.
+///
+/// ### Type parameters:
+///
+/// - `E`: the custom event type of the `NetConnection`s
+pub struct SessionClientSystem<E: 'static> {
+    protocol_version: u32,
+    name: String,
+    readers: FnvHashMap<Entity, ReaderId<NetEvent<E>>>,
+}
+
+impl<E: 'static> SessionClientSystem<E> {
+    /// Creates a new `SessionClientSystem`, joining with the given protocol version and name.
+    pub fn new<S: Into<String>>(protocol_version: u32, name: S) -> Self {
+        SessionClientSystem {
+            protocol_version,
+            name: name.into(),
+            readers: FnvHashMap::default(),
+        }
+    }
+}
+
+impl<'a, E> System<'a> for SessionClientSystem<E>
+where
+    E: Clone + Send + Sync + 'static,
+{
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, NetConnection<E>>,
+        WriteStorage<'a, ClientId>,
+        Read<'a, NetIdentity>,
+        Write<'a, EventChannel<SessionEvent>>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, mut connections, mut client_ids, identity, mut events): Self::SystemData,
+    ) {
+        for (entity, connection) in (&*entities, &mut connections).join() {
+            let protocol_version = self.protocol_version;
+            let name = &self.name;
+            let reader = self.readers.entry(entity).or_insert_with(|| {
+                // The first time the connection is seen, join the server.
+                connection.send_with(
+                    NetEvent::Session(SessionMessage::Join {
+                        client_uuid: identity.uuid,
+                        protocol_version,
+                        name: name.clone(),
+                    }),
+                    DeliveryRequirement::ReliableOrdered,
+                );
+                connection.receive_buffer.register_reader()
+            });
+
+            let mut state = None;
+            for event in connection.receive_buffer.read(reader) {
+                match event {
+                    NetEvent::Session(SessionMessage::Welcome { client }) => {
+                        if let Err(e) = client_ids.insert(entity, *client) {
+                            error!("Failed to insert `ClientId`: {}", e);
+                        }
+                        state = Some(ConnectionState::Connected);
+                        events.single_write(SessionEvent::Joined { client: *client });
+                    }
+                    NetEvent::Session(SessionMessage::Rejected { reason }) => {
+                        state = Some(ConnectionState::Disconnected);
+                        events.single_write(SessionEvent::Rejected {
+                            reason: reason.clone(),
+                        });
+                    }
+                    NetEvent::Session(SessionMessage::RoomJoined { room, client }) => {
+                        events.single_write(SessionEvent::RoomJoined {
+                            room: room.clone(),
+                            client: *client,
+                        });
+                    }
+                    NetEvent::Session(SessionMessage::RoomLeft { room, client }) => {
+                        events.single_write(SessionEvent::RoomLeft {
+                            room: room.clone(),
+                            client: *client,
+                        });
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(state) = state {
+                connection.state = state;
+            }
+        }
+        self.readers
+            .retain(|entity, _| connections.contains(*entity));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use amethyst_core::specs::{Builder, World};
+
+    #[test]
+    fn rooms_track_members() {
+        let mut world = World::new();
+        let entity = world.create_entity().build();
+        let mut sessions = Sessions::default();
+        let client = ClientId(0);
+        sessions.clients.insert(client, entity);
+
+        assert!(sessions.join_room("lobby".to_string(), client));
+        assert!(!sessions.join_room("lobby".to_string(), client));
+        assert_eq!(
+            sessions.room_members("lobby").collect::<Vec<_>>(),
+            vec![client]
+        );
+        assert_eq!(sessions.rooms_of(client).collect::<Vec<_>>(), vec!["lobby"]);
+
+        assert!(sessions.leave_room("lobby", client));
+        assert_eq!(sessions.room_members("lobby").count(), 0);
+        assert!(sessions.rooms.is_empty());
+    }
+}
@@ -0,0 +1,163 @@
+//! Simulation of bad network conditions, to test netcode on loopback connections.
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+/// Resource configuring the network simulator of the `NetSocketSystem`.
+///
+/// When enabled, UDP datagrams sent and received by the `NetSocketSystem` are delayed, dropped
+/// and reordered. TCP and WebSocket connections are not affected. The simulator can be toggled
+/// at runtime by changing this resource.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkSimulation {
+    /// Whether the simulator is enabled
+    pub enabled: bool,
+    /// Delay added to every datagram, in both directions
+    pub latency: Duration,
+    /// Maximal random variation of the latency
+    pub jitter: Duration,
+    /// Chance between 0 and 1 that a datagram is dropped
+    pub packet_loss: f32,
+    /// Chance between 0 and 1 that a datagram is delayed to arrive after the next ones
+    pub reordering: f32,
+}
+
+impl Default for NetworkSimulation {
+    fn default() -> Self {
+        NetworkSimulation {
+            enabled: false,
+            latency: Duration::from_millis(0),
+            jitter: Duration::from_millis(0),
+            packet_loss: 0.0,
+            reordering: 0.0,
+        }
+    }
+}
+
+impl NetworkSimulation {
+    /// Creates an enabled simulation with the given latency, jitter, packet loss and reordering.
+    pub fn new(latency: Duration, jitter: Duration, packet_loss: f32, reordering: f32) -> Self {
+        NetworkSimulation {
+            enabled: true,
+            latency,
+            jitter,
+            packet_loss,
+            reordering,
+        }
+    }
+}
+
+/// Delays, drops and reorders items according to a `NetworkSimulation`.
+pub(crate) struct Simulator<T> {
+    rng: u64,
+    next_order: u64,
+    queue: Vec<(Instant, u64, T)>,
+}
+
+impl<T> Simulator<T> {
+    pub(crate) fn new() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() ^ (u64::from(d.subsec_nanos()) << 32))
+            .unwrap_or(0);
+        Self::with_seed(seed)
+    }
+
+    pub(crate) fn with_seed(seed: u64) -> Self {
+        Simulator {
+            // xorshift must not be seeded with 0
+            rng: seed | 1,
+            next_order: 0,
+            queue: Vec::new(),
+        }
+    }
+
+    /// Returns true if the simulator has to process the items, either because it is enabled or
+    /// because there still are delayed items.
+    pub(crate) fn is_active(&self, simulation: &NetworkSimulation) -> bool {
+        simulation.enabled || !self.queue.is_empty()
+    }
+
+    /// Schedules the item, or drops it.
+    pub(crate) fn push(&mut self, simulation: &NetworkSimulation, now: Instant, item: T) {
+        let mut release = now;
+        if simulation.enabled {
+            if self.random() < simulation.packet_loss {
+                return;
+            }
+            let jitter = simulation.jitter.as_secs() as f32 * 1_000_000.0
+                + simulation.jitter.subsec_micros() as f32;
+            release += simulation.latency + Duration::from_micros((jitter * self.random()) as u64);
+            if self.random() < simulation.reordering {
+                release += simulation.latency + simulation.jitter + Duration::from_millis(1);
+            }
+        }
+        self.queue.push((release, self.next_order, item));
+        self.next_order += 1;
+    }
+
+    /// Removes the items that are due, in the order they are released.
+    pub(crate) fn pop_ready(&mut self, now: Instant) -> Vec<T> {
+        let (mut ready, delayed) = self
+            .queue
+            .drain(..)
+            .partition::<Vec<_>, _>(|(release, _, _)| *release <= now);
+        self.queue = delayed;
+        ready.sort_by_key(|(release, order, _)| (*release, *order));
+        ready.into_iter().map(|(_, _, item)| item).collect()
+    }
+
+    // Random number between 0 and 1, using xorshift.
+    fn random(&mut self) -> f32 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        (self.rng >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_simulation_passes_through() {
+        let mut simulator = Simulator::with_seed(1);
+        let simulation = NetworkSimulation::default();
+        let now = Instant::now();
+        simulator.push(&simulation, now, 1);
+        simulator.push(&simulation, now, 2);
+        assert_eq!(simulator.pop_ready(now), vec![1, 2]);
+        assert!(!simulator.is_active(&simulation));
+    }
+
+    #[test]
+    fn latency_delays_items() {
+        let mut simulator = Simulator::with_seed(1);
+        let simulation = NetworkSimulation::new(
+            Duration::from_millis(100),
+            Duration::from_millis(0),
+            0.0,
+            0.0,
+        );
+        let now = Instant::now();
+        simulator.push(&simulation, now, 1);
+        assert!(simulator.pop_ready(now).is_empty());
+        assert_eq!(
+            simulator.pop_ready(now + Duration::from_millis(100)),
+            vec![1]
+        );
+    }
+
+    #[test]
+    fn packet_loss_drops_items() {
+        let mut simulator = Simulator::with_seed(1);
+        let simulation =
+            NetworkSimulation::new(Duration::from_millis(0), Duration::from_millis(0), 1.0, 0.0);
+        let now = Instant::now();
+        simulator.push(&simulation, now, 1);
+        assert!(simulator.pop_ready(now).is_empty());
+    }
+}
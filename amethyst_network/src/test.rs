@@ -115,6 +115,8 @@ mod test {
         let client_config = ServerConfig {
             udp_send_addr: client_send,
             udp_recv_addr: client_receive,
+            tcp_addr: None,
+            websocket_addr: None,
             max_throughput: 10000,
         };
 
@@ -122,6 +124,8 @@ mod test {
         let server_config = ServerConfig {
             udp_send_addr: server_send,
             udp_recv_addr: server_receive,
+            tcp_addr: None,
+            websocket_addr: None,
             max_throughput: 10000,
         };
 
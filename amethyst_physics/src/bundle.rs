@@ -0,0 +1,113 @@
+//! ECS physics bundle
+
+use amethyst_core::{bundle::SystemBundle, nalgebra::Vector3, specs::prelude::DispatcherBuilder};
+use amethyst_error::Error;
+
+use crate::{
+    character::CharacterControllerSystem,
+    debug::PhysicsDebugLinesSystem,
+    joints::SyncJointsToPhysicsSystem,
+    systems::{PhysicsStepSystem, SyncBodiesFromPhysicsSystem, SyncBodiesToPhysicsSystem},
+};
+
+/// Physics bundle
+///
+/// Adds the `CharacterControllerSystem`, the `SyncBodiesToPhysicsSystem`, the
+/// `SyncJointsToPhysicsSystem`, the `PhysicsStepSystem` and the `SyncBodiesFromPhysicsSystem`,
+/// with the names "character_controller", "physics_sync_to", "physics_sync_joints",
+/// "physics_step" and "physics_sync_from".
+///
+/// The bodies write to the `Transform` components, so the `TransformBundle` should depend on
+/// "physics_sync_from".
+///
+/// With `with_debug_lines`, the `PhysicsDebugLinesSystem` is added as "physics_debug_lines".
+///
+/// ## Errors
+///
+/// No errors will be returned by this bundle.
+pub struct PhysicsBundle<'a> {
+    gravity: Vector3<f32>,
+    max_steps: usize,
+    debug_lines: bool,
+    dep: &'a [&'a str],
+}
+
+impl<'a> Default for PhysicsBundle<'a> {
+    fn default() -> Self {
+        PhysicsBundle {
+            gravity: Vector3::new(0.0, -9.81, 0.0),
+            max_steps: 8,
+            debug_lines: false,
+            dep: &[],
+        }
+    }
+}
+
+impl<'a> PhysicsBundle<'a> {
+    /// Create a new physics bundle, with the earth's gravity along the negative Y axis.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set the gravity of the physics world.
+    pub fn with_gravity(mut self, gravity: Vector3<f32>) -> Self {
+        self.gravity = gravity;
+        self
+    }
+
+    /// Set the maximal number of physics steps per frame, 8 by default.
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Draw the physics world with the `DebugLines` resource, see `PhysicsDebugSettings`.
+    pub fn with_debug_lines(mut self) -> Self {
+        self.debug_lines = true;
+        self
+    }
+
+    /// Set dependencies for the physics systems.
+    pub fn with_dep(mut self, dep: &'a [&'a str]) -> Self {
+        self.dep = dep;
+        self
+    }
+}
+
+impl<'a, 'b, 'c> SystemBundle<'a, 'b> for PhysicsBundle<'c> {
+    fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<(), Error> {
+        builder.add(
+            CharacterControllerSystem::new(),
+            "character_controller",
+            self.dep,
+        );
+        builder.add(
+            SyncBodiesToPhysicsSystem::new(),
+            "physics_sync_to",
+            &["character_controller"],
+        );
+        builder.add(
+            SyncJointsToPhysicsSystem::new(),
+            "physics_sync_joints",
+            &["physics_sync_to"],
+        );
+        builder.add(
+            PhysicsStepSystem::new(self.gravity, self.max_steps),
+            "physics_step",
+            &["physics_sync_joints"],
+        );
+        builder.add(
+            SyncBodiesFromPhysicsSystem::new(),
+            "physics_sync_from",
+            &["physics_step"],
+        );
+        if self.debug_lines {
+            builder.add(
+                PhysicsDebugLinesSystem::new(),
+                "physics_debug_lines",
+                &["physics_step"],
+            );
+        }
+        Ok(())
+    }
+}
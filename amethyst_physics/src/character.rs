@@ -0,0 +1,421 @@
+//! Kinematic character controller.
+
+use amethyst_core::{
+    nalgebra::{Isometry3, Point3, Vector3},
+    specs::{Component, DenseVecStorage, Entities, Entity, Join, Read, System, WriteStorage},
+    Time, Transform,
+};
+use ncollide3d::shape::{Capsule, ShapeHandle};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{
+    query::{cast_shape, RaycastHit},
+    world::PhysicsWorld,
+};
+
+/// Maximal number of times the character slides along obstacles in a frame.
+const MAX_SLIDES: usize = 3;
+
+/// A character moved by the `CharacterControllerSystem`, as a capsule along the Y axis that
+/// slides along the colliders of the `PhysicsWorld` instead of going through them.
+///
+/// The horizontal part of the velocity is set by the game, typically from the input, and the
+/// character falls with the gravity of the `PhysicsWorld` when it is not grounded. Setting a
+/// positive vertical velocity makes it jump.
+///
+/// The character follows the kinematic or dynamic body it stands on. Its `Transform` is used as a
+/// global position, so the entity should not have a `Parent`, nor a `RigidBody`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CharacterController {
+    /// Radius of the capsule
+    pub radius: f32,
+    /// Half of the height of the cylindrical part of the capsule
+    pub half_height: f32,
+    /// Height of the steps the character climbs, and of the ledges it snaps down to
+    pub step_offset: f32,
+    /// Steepest slope the character can walk on, in radians
+    pub max_slope: f32,
+    /// Distance kept between the capsule and the obstacles
+    pub skin_width: f32,
+    /// Velocity of the character
+    pub velocity: Vector3<f32>,
+    /// Whether the character stands on a walkable surface
+    #[serde(skip)]
+    pub grounded: bool,
+    /// The entity the character stands on
+    #[serde(skip)]
+    pub ground: Option<Entity>,
+    #[serde(skip)]
+    ground_position: Option<Isometry3<f32>>,
+}
+
+impl CharacterController {
+    /// Creates a controller with a step offset of a quarter of the height of the capsule, and a
+    /// slope limit of 45 degrees.
+    pub fn new(radius: f32, half_height: f32) -> Self {
+        CharacterController {
+            radius,
+            half_height,
+            step_offset: (half_height + radius) / 2.0,
+            max_slope: std::f32::consts::FRAC_PI_4,
+            skin_width: 0.01,
+            velocity: Vector3::zeros(),
+            grounded: false,
+            ground: None,
+            ground_position: None,
+        }
+    }
+
+    /// Sets the height of the steps the character climbs.
+    pub fn with_step_offset(mut self, step_offset: f32) -> Self {
+        self.step_offset = step_offset;
+        self
+    }
+
+    /// Sets the steepest slope the character can walk on, in radians.
+    pub fn with_max_slope(mut self, max_slope: f32) -> Self {
+        self.max_slope = max_slope;
+        self
+    }
+
+    /// Sets the distance kept between the capsule and the obstacles.
+    pub fn with_skin_width(mut self, skin_width: f32) -> Self {
+        self.skin_width = skin_width;
+        self
+    }
+
+    fn is_walkable(&self, normal: &Vector3<f32>) -> bool {
+        normal.y >= self.max_slope.cos()
+    }
+}
+
+impl Component for CharacterController {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Moves the entities with a `CharacterController` and a `Transform`, sweeping their capsule
+/// against the colliders of the `PhysicsWorld`.
+///
+/// Sensors and the collider of the character itself are ignored.
+#[derive(Default)]
+pub struct CharacterControllerSystem;
+
+impl CharacterControllerSystem {
+    /// Creates a new `CharacterControllerSystem`.
+    pub fn new() -> Self {
+        CharacterControllerSystem
+    }
+}
+
+impl<'a> System<'a> for CharacterControllerSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, Time>,
+        Read<'a, PhysicsWorld>,
+        WriteStorage<'a, CharacterController>,
+        WriteStorage<'a, Transform>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, time, physics, mut controllers, mut transforms): Self::SystemData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("character_controller_system");
+
+        let delta = time.delta_seconds();
+        if delta <= 0.0 {
+            return;
+        }
+        let shapes = physics.collider_shapes();
+        let gravity = physics.gravity().y;
+
+        for (entity, controller, transform) in
+            (&*entities, &mut controllers, &mut transforms).join()
+        {
+            let obstacles = shapes
+                .iter()
+                .filter(|(other, _, _)| *other != entity && !physics.is_sensor(*other))
+                .cloned()
+                .collect::<Vec<_>>();
+            let sweep = Sweep {
+                controller: &*controller,
+                obstacles: &obstacles,
+                shape: ShapeHandle::new(Capsule::new(controller.half_height, controller.radius)),
+            };
+            let mut position = *transform.translation();
+
+            // Follow the platform the character stands on.
+            if let (Some(ground), Some(previous)) = (controller.ground, controller.ground_position)
+            {
+                if let Some(current) = physics.collider_position(ground) {
+                    let moved = current * previous.inverse();
+                    position = moved.transform_point(&(Point3::origin() + position)).coords;
+                }
+            }
+
+            let horizontal =
+                Vector3::new(controller.velocity.x, 0.0, controller.velocity.z) * delta;
+            position = sweep.slide(position, horizontal);
+
+            let mut velocity_y = controller.velocity.y + gravity * delta;
+            let fall = velocity_y * delta;
+            let (grounded, ground) = if fall > 0.0 {
+                match sweep.cast(position, Vector3::y(), fall) {
+                    Some(hit) => {
+                        // The character hit a ceiling.
+                        position.y += sweep.travel(&hit).min(fall);
+                        velocity_y = 0.0;
+                    }
+                    None => position.y += fall,
+                }
+                (false, None)
+            } else {
+                // Grounded characters snap down to follow slopes and stairs.
+                let snap = if controller.grounded {
+                    controller.step_offset
+                } else {
+                    0.0
+                };
+                match sweep.cast(position, -Vector3::y(), snap - fall) {
+                    Some(ref hit) if controller.is_walkable(&hit.normal) => {
+                        position.y -= sweep.travel(hit);
+                        velocity_y = 0.0;
+                        (true, Some(hit.entity))
+                    }
+                    Some(ref hit) => {
+                        position.y -= sweep.travel(hit).min(-fall);
+                        (false, None)
+                    }
+                    None => {
+                        position.y += fall;
+                        (false, None)
+                    }
+                }
+            };
+
+            controller.velocity.y = velocity_y;
+            controller.grounded = grounded;
+            controller.ground = ground;
+            controller.ground_position =
+                ground.and_then(|ground| physics.collider_position(ground));
+            *transform.translation_mut() = position;
+        }
+    }
+}
+
+/// Casts of the capsule of a character against its obstacles.
+struct Sweep<'a> {
+    controller: &'a CharacterController,
+    obstacles: &'a [(Entity, Isometry3<f32>, ShapeHandle<f32>)],
+    shape: ShapeHandle<f32>,
+}
+
+impl<'a> Sweep<'a> {
+    fn cast(
+        &self,
+        position: Vector3<f32>,
+        direction: Vector3<f32>,
+        distance: f32,
+    ) -> Option<RaycastHit> {
+        cast_shape(
+            self.obstacles,
+            &self.shape,
+            Isometry3::new(position, Vector3::zeros()),
+            direction,
+            distance + self.controller.skin_width,
+        )
+    }
+
+    /// The distance the capsule can travel before touching the obstacle that was hit.
+    fn travel(&self, hit: &RaycastHit) -> f32 {
+        (hit.distance - self.controller.skin_width).max(0.0)
+    }
+
+    /// Moves the capsule horizontally, sliding along walls and walkable slopes, and climbing steps.
+    fn slide(&self, mut position: Vector3<f32>, motion: Vector3<f32>) -> Vector3<f32> {
+        let mut remaining = motion;
+        let mut stepped = !self.controller.grounded;
+        for _ in 0..MAX_SLIDES {
+            let length = remaining.norm();
+            if length <= std::f32::EPSILON {
+                break;
+            }
+            let direction = remaining / length;
+            let hit = match self.cast(position, direction, length) {
+                Some(hit) => hit,
+                None => {
+                    position += remaining;
+                    break;
+                }
+            };
+            let travel = self.travel(&hit).min(length);
+            position += direction * travel;
+            remaining = direction * (length - travel);
+
+            if self.controller.is_walkable(&hit.normal) {
+                // Walk up the slope.
+                remaining -= hit.normal * remaining.dot(&hit.normal);
+                continue;
+            }
+            if !stepped {
+                stepped = true;
+                if let Some(climbed) = self.step_up(position, remaining) {
+                    position = climbed;
+                    break;
+                }
+            }
+            // Slide along the wall without climbing it.
+            let wall = Vector3::new(hit.normal.x, 0.0, hit.normal.z);
+            if wall.norm() <= std::f32::EPSILON {
+                break;
+            }
+            let wall = wall.normalize();
+            remaining -= wall * remaining.dot(&wall);
+        }
+        position
+    }
+
+    /// Moves the capsule over a step, returning `None` if there is no walkable surface on top of
+    /// it.
+    fn step_up(&self, position: Vector3<f32>, motion: Vector3<f32>) -> Option<Vector3<f32>> {
+        let length = motion.norm();
+        if length <= std::f32::EPSILON {
+            return None;
+        }
+        let direction = motion / length;
+
+        let step_offset = self.controller.step_offset;
+        let rise = self
+            .cast(position, Vector3::y(), step_offset)
+            .map_or(step_offset, |hit| self.travel(&hit).min(step_offset));
+        let raised = position + Vector3::y() * rise;
+
+        let advance = self
+            .cast(raised, direction, length)
+            .map_or(length, |hit| self.travel(&hit).min(length));
+        if advance <= self.controller.skin_width {
+            return None;
+        }
+        let advanced = raised + direction * advance;
+
+        let hit = self.cast(advanced, -Vector3::y(), rise)?;
+        if !self.controller.is_walkable(&hit.normal) {
+            return None;
+        }
+        Some(advanced - Vector3::y() * self.travel(&hit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::{
+        approx::assert_relative_eq,
+        specs::{Builder, Dispatcher, DispatcherBuilder, World},
+    };
+
+    use super::*;
+    use crate::{
+        components::{Collider, ColliderShape},
+        systems::SyncBodiesToPhysicsSystem,
+    };
+
+    fn setup() -> (World, Dispatcher<'static, 'static>) {
+        let mut world = World::new();
+        let mut dispatcher = DispatcherBuilder::new()
+            .with(SyncBodiesToPhysicsSystem::new(), "physics_sync_to", &[])
+            .with(
+                CharacterControllerSystem::new(),
+                "character_controller",
+                &["physics_sync_to"],
+            )
+            .build();
+        dispatcher.setup(&mut world.res);
+        world.write_resource::<Time>().set_delta_seconds(1.0 / 60.0);
+        (world, dispatcher)
+    }
+
+    fn cuboid(world: &mut World, center: [f32; 3], half_extents: [f32; 3]) -> Entity {
+        let mut transform = Transform::default();
+        transform.set_xyz(center[0], center[1], center[2]);
+        let half_extents = Vector3::new(half_extents[0], half_extents[1], half_extents[2]);
+        world
+            .create_entity()
+            .with(transform)
+            .with(Collider::new(ColliderShape::Cuboid { half_extents }))
+            .build()
+    }
+
+    fn character(world: &mut World, x: f32, y: f32) -> Entity {
+        let mut transform = Transform::default();
+        transform.set_xyz(x, y, 0.0);
+        world
+            .create_entity()
+            .with(transform)
+            .with(CharacterController::new(0.5, 0.5))
+            .build()
+    }
+
+    fn run(world: &mut World, dispatcher: &mut Dispatcher, frames: usize) {
+        for _ in 0..frames {
+            dispatcher.dispatch(&world.res);
+        }
+    }
+
+    #[test]
+    fn character_lands_on_the_ground() {
+        let (mut world, mut dispatcher) = setup();
+        // The top of the ground is at 0.5, the bottom of the character at 0.6.
+        let ground = cuboid(&mut world, [0.0, 0.0, 0.0], [10.0, 0.5, 10.0]);
+        let entity = character(&mut world, 0.0, 1.6);
+
+        run(&mut world, &mut dispatcher, 1);
+        assert!(
+            !world
+                .read_storage::<CharacterController>()
+                .get(entity)
+                .unwrap()
+                .grounded
+        );
+
+        run(&mut world, &mut dispatcher, 30);
+        let controllers = world.read_storage::<CharacterController>();
+        let controller = controllers.get(entity).unwrap();
+        assert!(controller.grounded);
+        assert_eq!(controller.ground, Some(ground));
+        assert_relative_eq!(controller.velocity.y, 0.0);
+        let transforms = world.read_storage::<Transform>();
+        let y = transforms.get(entity).unwrap().translation().y;
+        assert_relative_eq!(y, 1.5 + controller.skin_width, epsilon = 1.0e-3);
+    }
+
+    #[test]
+    fn character_climbs_steps() {
+        let (mut world, mut dispatcher) = setup();
+        cuboid(&mut world, [0.0, 0.0, 0.0], [10.0, 0.5, 10.0]);
+        // A step of 0.2 starting at x = 1, lower than the step offset of 0.5.
+        let step = cuboid(&mut world, [3.0, 0.6, 0.0], [2.0, 0.1, 10.0]);
+        let entity = character(&mut world, 0.0, 1.51);
+        run(&mut world, &mut dispatcher, 1);
+
+        world
+            .write_storage::<CharacterController>()
+            .get_mut(entity)
+            .unwrap()
+            .velocity
+            .x = 3.0;
+        run(&mut world, &mut dispatcher, 40);
+
+        let controllers = world.read_storage::<CharacterController>();
+        let controller = controllers.get(entity).unwrap();
+        assert!(controller.grounded);
+        assert_eq!(controller.ground, Some(step));
+        let transforms = world.read_storage::<Transform>();
+        let translation = transforms.get(entity).unwrap().translation();
+        assert!(translation.x > 1.0);
+        assert_relative_eq!(translation.y, 1.7 + controller.skin_width, epsilon = 1.0e-3);
+    }
+}
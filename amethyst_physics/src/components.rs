@@ -0,0 +1,218 @@
+//! Physics components.
+
+use amethyst_core::{
+    nalgebra::{Isometry3, Point3, Unit, Vector3},
+    specs::{Component, DenseVecStorage},
+};
+use log::error;
+use ncollide3d::shape::{Ball, Capsule, ConvexHull, Cuboid, Plane, ShapeHandle, TriMesh};
+use nphysics3d::object::BodyStatus as NBodyStatus;
+use serde::{Deserialize, Serialize};
+
+/// How a rigid body is simulated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BodyStatus {
+    /// The body is moved by forces, contacts and gravity.
+    Dynamic,
+    /// The body never moves.
+    Static,
+    /// The body is moved by its `Transform` and velocity only, and pushes dynamic bodies away.
+    Kinematic,
+}
+
+impl Default for BodyStatus {
+    fn default() -> Self {
+        BodyStatus::Dynamic
+    }
+}
+
+impl From<BodyStatus> for NBodyStatus {
+    fn from(status: BodyStatus) -> Self {
+        match status {
+            BodyStatus::Dynamic => NBodyStatus::Dynamic,
+            BodyStatus::Static => NBodyStatus::Static,
+            BodyStatus::Kinematic => NBodyStatus::Kinematic,
+        }
+    }
+}
+
+/// A rigid body simulated by the `PhysicsWorld`.
+///
+/// The body starts at the `Transform` of the entity, which is then overwritten with the position
+/// of the body after every physics step. Changing the `Transform` teleports the body. The
+/// `Transform` is used as a global position, so entities with a `RigidBody` should not have a
+/// `Parent`.
+///
+/// The mass of the body is computed from the density of its `Collider`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RigidBody {
+    /// How the body is simulated
+    pub status: BodyStatus,
+    /// Linear velocity, updated after every physics step
+    pub linear_velocity: Vector3<f32>,
+    /// Angular velocity, updated after every physics step
+    pub angular_velocity: Vector3<f32>,
+}
+
+impl RigidBody {
+    /// Creates a dynamic rigid body.
+    pub fn dynamic() -> Self {
+        Self::with_status(BodyStatus::Dynamic)
+    }
+
+    /// Creates a static rigid body.
+    pub fn fixed() -> Self {
+        Self::with_status(BodyStatus::Static)
+    }
+
+    /// Creates a kinematic rigid body.
+    pub fn kinematic() -> Self {
+        Self::with_status(BodyStatus::Kinematic)
+    }
+
+    /// Creates a rigid body with the given status.
+    pub fn with_status(status: BodyStatus) -> Self {
+        RigidBody {
+            status,
+            ..Default::default()
+        }
+    }
+}
+
+impl Component for RigidBody {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// The shape of a `Collider`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ColliderShape {
+    /// A sphere.
+    Ball {
+        /// Radius of the sphere
+        radius: f32,
+    },
+    /// A box.
+    Cuboid {
+        /// Half of the size of the box along each axis
+        half_extents: Vector3<f32>,
+    },
+    /// A capsule along the Y axis.
+    Capsule {
+        /// Half of the height of the cylindrical part
+        half_height: f32,
+        /// Radius of the capsule
+        radius: f32,
+    },
+    /// An infinite plane going through the origin of the entity.
+    Plane {
+        /// Normal of the plane, pointing outside of the solid half-space
+        normal: Vector3<f32>,
+    },
+    /// The convex hull of a set of points, see `convex_hull_from_mesh`.
+    ConvexHull {
+        /// The points inside the hull
+        points: Vec<Point3<f32>>,
+    },
+    /// A triangle mesh, only suited to static colliders, see `trimesh_from_mesh`.
+    TriMesh {
+        /// The vertices of the mesh
+        vertices: Vec<Point3<f32>>,
+        /// The indices of the vertices of each triangle
+        indices: Vec<Point3<usize>>,
+    },
+}
+
+impl ColliderShape {
+    /// Creates the shape, degenerate convex hulls are replaced by a point.
+    pub(crate) fn shape_handle(&self) -> ShapeHandle<f32> {
+        match *self {
+            ColliderShape::Ball { radius } => ShapeHandle::new(Ball::new(radius)),
+            ColliderShape::Cuboid { half_extents } => ShapeHandle::new(Cuboid::new(half_extents)),
+            ColliderShape::Capsule {
+                half_height,
+                radius,
+            } => ShapeHandle::new(Capsule::new(half_height, radius)),
+            ColliderShape::Plane { normal } => {
+                ShapeHandle::new(Plane::new(Unit::new_normalize(normal)))
+            }
+            ColliderShape::ConvexHull { ref points } => match ConvexHull::try_from_points(points) {
+                Some(hull) => ShapeHandle::new(hull),
+                None => {
+                    error!(
+                        "Failed to compute the convex hull of {} points",
+                        points.len()
+                    );
+                    ShapeHandle::new(Ball::new(0.0))
+                }
+            },
+            ColliderShape::TriMesh {
+                ref vertices,
+                ref indices,
+            } => ShapeHandle::new(TriMesh::new(vertices.clone(), indices.clone(), None)),
+        }
+    }
+}
+
+/// A collision shape, attached to the `RigidBody` of the same entity.
+///
+/// Colliders without a `RigidBody` are static, and placed at the `Transform` of their entity.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Collider {
+    /// The shape of the collider
+    pub shape: ColliderShape,
+    /// Position of the shape relative to the entity
+    pub offset: Isometry3<f32>,
+    /// Density used to compute the mass of the body
+    pub density: f32,
+    /// Bounciness, between 0 and 1
+    pub restitution: f32,
+    /// Friction coefficient
+    pub friction: f32,
+    /// Sensors do not collide, and emit `TriggerEvent`s instead
+    #[serde(default)]
+    pub sensor: bool,
+}
+
+impl Collider {
+    /// Creates a collider with a density of 1 and the default material.
+    pub fn new(shape: ColliderShape) -> Self {
+        Collider {
+            shape,
+            offset: Isometry3::identity(),
+            density: 1.0,
+            restitution: 0.0,
+            friction: 0.5,
+            sensor: false,
+        }
+    }
+
+    /// Sets the position of the shape relative to the entity.
+    pub fn with_offset(mut self, offset: Isometry3<f32>) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Sets the density.
+    pub fn with_density(mut self, density: f32) -> Self {
+        self.density = density;
+        self
+    }
+
+    /// Sets the restitution and friction.
+    pub fn with_material(mut self, restitution: f32, friction: f32) -> Self {
+        self.restitution = restitution;
+        self.friction = friction;
+        self
+    }
+
+    /// Makes the collider a sensor, which emits `TriggerEvent`s instead of colliding.
+    pub fn with_sensor(mut self) -> Self {
+        self.sensor = true;
+        self
+    }
+}
+
+impl Component for Collider {
+    type Storage = DenseVecStorage<Self>;
+}
@@ -0,0 +1,461 @@
+//! Debug lines showing the colliders, contacts and joints of the physics worlds.
+
+use amethyst_core::{
+    nalgebra::{Isometry2, Isometry3, Point3, Vector3},
+    specs::{Read, ReadStorage, System, Write},
+    GlobalTransform,
+};
+use amethyst_renderer::{DebugLines, Rgba};
+use ncollide2d::shape::ConvexPolygon;
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{
+    components::{BodyStatus, ColliderShape},
+    dim2::{ColliderShape2D, PhysicsWorld2D},
+    world::PhysicsWorld,
+};
+
+/// Number of segments of the circles.
+const SEGMENTS: usize = 16;
+
+/// Resource toggling the debug lines of the physics worlds at runtime, and choosing their colors.
+#[derive(Clone, Debug)]
+pub struct PhysicsDebugSettings {
+    /// Whether any line is drawn
+    pub enabled: bool,
+    /// Whether the shapes of the colliders are drawn
+    pub colliders: bool,
+    /// Whether the contact points and normals are drawn
+    pub contacts: bool,
+    /// Whether the anchors of the joints are drawn
+    pub joints: bool,
+    /// Color of the colliders of dynamic bodies
+    pub dynamic_color: Rgba,
+    /// Color of the colliders of kinematic bodies
+    pub kinematic_color: Rgba,
+    /// Color of the static colliders
+    pub static_color: Rgba,
+    /// Color of the sensors
+    pub sensor_color: Rgba,
+    /// Color of the contacts
+    pub contact_color: Rgba,
+    /// Color of the joints
+    pub joint_color: Rgba,
+    /// Color of the broken joints
+    pub broken_joint_color: Rgba,
+}
+
+impl Default for PhysicsDebugSettings {
+    fn default() -> Self {
+        PhysicsDebugSettings {
+            enabled: true,
+            colliders: true,
+            contacts: true,
+            joints: true,
+            dynamic_color: Rgba::GREEN,
+            kinematic_color: Rgba::BLUE,
+            static_color: Rgba(0.5, 0.5, 0.5, 1.0),
+            sensor_color: Rgba(1.0, 1.0, 0.0, 1.0),
+            contact_color: Rgba::RED,
+            joint_color: Rgba(1.0, 0.0, 1.0, 1.0),
+            broken_joint_color: Rgba(0.5, 0.0, 0.5, 1.0),
+        }
+    }
+}
+
+impl PhysicsDebugSettings {
+    fn body_color(&self, status: Option<BodyStatus>, sensor: bool) -> Rgba {
+        if sensor {
+            return self.sensor_color;
+        }
+        match status {
+            Some(BodyStatus::Dynamic) => self.dynamic_color,
+            Some(BodyStatus::Kinematic) => self.kinematic_color,
+            Some(BodyStatus::Static) | None => self.static_color,
+        }
+    }
+}
+
+/// Draws the colliders, contacts and joints of the `PhysicsWorld` with the `DebugLines` resource,
+/// according to the `PhysicsDebugSettings`.
+///
+/// The lines are rendered by the `DrawDebugLines` pass.
+#[derive(Default)]
+pub struct PhysicsDebugLinesSystem;
+
+impl PhysicsDebugLinesSystem {
+    /// Creates a new `PhysicsDebugLinesSystem`.
+    pub fn new() -> Self {
+        PhysicsDebugLinesSystem
+    }
+}
+
+impl<'a> System<'a> for PhysicsDebugLinesSystem {
+    type SystemData = (
+        Read<'a, PhysicsDebugSettings>,
+        Read<'a, PhysicsWorld>,
+        Write<'a, DebugLines>,
+    );
+
+    fn run(&mut self, (settings, physics, mut lines): Self::SystemData) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("physics_debug_lines_system");
+
+        if !settings.enabled {
+            return;
+        }
+
+        if settings.colliders {
+            for (entity, record) in &physics.colliders {
+                let collider = match physics.world.collider(record.handle) {
+                    Some(collider) => collider,
+                    None => continue,
+                };
+                let status = physics.bodies.get(entity).map(|body| body.status);
+                let color = settings.body_color(status, record.collider.sensor);
+                draw_shape(
+                    &mut lines,
+                    &record.collider.shape,
+                    collider.position(),
+                    color,
+                );
+            }
+        }
+
+        if settings.contacts {
+            for (_, _, _, manifold) in physics.world.collision_world().contact_pairs(true) {
+                for tracked in manifold.contacts() {
+                    let contact = &tracked.contact;
+                    draw_contact(
+                        &mut lines,
+                        contact.world1,
+                        contact.normal.into_inner(),
+                        settings.contact_color,
+                    );
+                }
+            }
+        }
+
+        if settings.joints {
+            for record in physics.joints.values() {
+                let anchor1 = physics.body_position(record.bodies.0) * record.joint.anchor;
+                let anchor2 = physics.body_position(record.bodies.1) * record.joint.other_anchor;
+                let color = if record.joint.broken {
+                    settings.broken_joint_color
+                } else {
+                    settings.joint_color
+                };
+                draw_cross(&mut lines, anchor1, 0.05, color);
+                draw_cross(&mut lines, anchor2, 0.05, color);
+                lines.draw_line(anchor1, anchor2, color);
+            }
+        }
+    }
+}
+
+/// Draws the colliders and contacts of the `PhysicsWorld2D` with the `DebugLines` resource,
+/// according to the `PhysicsDebugSettings`.
+///
+/// The colliders are drawn at the Z translation of the `GlobalTransform` of their entity.
+#[derive(Default)]
+pub struct Physics2DDebugLinesSystem;
+
+impl Physics2DDebugLinesSystem {
+    /// Creates a new `Physics2DDebugLinesSystem`.
+    pub fn new() -> Self {
+        Physics2DDebugLinesSystem
+    }
+}
+
+impl<'a> System<'a> for Physics2DDebugLinesSystem {
+    type SystemData = (
+        Read<'a, PhysicsDebugSettings>,
+        Read<'a, PhysicsWorld2D>,
+        ReadStorage<'a, GlobalTransform>,
+        Write<'a, DebugLines>,
+    );
+
+    fn run(&mut self, (settings, physics, globals, mut lines): Self::SystemData) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("physics2d_debug_lines_system");
+
+        if !settings.enabled {
+            return;
+        }
+
+        if settings.colliders {
+            for (entity, record) in &physics.colliders {
+                let collider = match physics.world.collider(record.handle) {
+                    Some(collider) => collider,
+                    None => continue,
+                };
+                let z = globals.get(*entity).map_or(0.0, |global| global.0[(2, 3)]);
+                let status = physics.bodies.get(entity).map(|body| body.status);
+                let color = settings.body_color(status, record.collider.sensor);
+                let position = isometry3(collider.position(), z);
+                draw_shape_2d(&mut lines, &record.collider.shape, &position, color);
+            }
+        }
+
+        if settings.contacts {
+            for (_, _, _, manifold) in physics.world.collision_world().contact_pairs(true) {
+                for tracked in manifold.contacts() {
+                    let contact = &tracked.contact;
+                    let normal = contact.normal.into_inner();
+                    draw_contact(
+                        &mut lines,
+                        Point3::new(contact.world1.x, contact.world1.y, 0.0),
+                        Vector3::new(normal.x, normal.y, 0.0),
+                        settings.contact_color,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// The position on the XY plane at the given depth.
+fn isometry3(position: &Isometry2<f32>, z: f32) -> Isometry3<f32> {
+    let translation = position.translation.vector;
+    Isometry3::new(
+        Vector3::new(translation.x, translation.y, z),
+        Vector3::z() * position.rotation.angle(),
+    )
+}
+
+fn draw_shape(
+    lines: &mut DebugLines,
+    shape: &ColliderShape,
+    position: &Isometry3<f32>,
+    color: Rgba,
+) {
+    match *shape {
+        ColliderShape::Ball { radius } => {
+            for &(a, b) in &[
+                (Vector3::x(), Vector3::y()),
+                (Vector3::y(), Vector3::z()),
+                (Vector3::z(), Vector3::x()),
+            ] {
+                draw_circle(lines, position, Vector3::zeros(), a, b, radius, color);
+            }
+        }
+        ColliderShape::Cuboid { half_extents } => {
+            draw_box(lines, position, -half_extents, half_extents, color)
+        }
+        ColliderShape::Capsule {
+            half_height,
+            radius,
+        } => {
+            let top = Vector3::y() * half_height;
+            for &center in &[top, -top] {
+                draw_circle(
+                    lines,
+                    position,
+                    center,
+                    Vector3::x(),
+                    Vector3::z(),
+                    radius,
+                    color,
+                );
+                draw_circle(
+                    lines,
+                    position,
+                    center,
+                    Vector3::x(),
+                    Vector3::y(),
+                    radius,
+                    color,
+                );
+                draw_circle(
+                    lines,
+                    position,
+                    center,
+                    Vector3::z(),
+                    Vector3::y(),
+                    radius,
+                    color,
+                );
+            }
+            for &side in &[Vector3::x(), -Vector3::x(), Vector3::z(), -Vector3::z()] {
+                let side = side * radius;
+                lines.draw_line(
+                    position * (Point3::origin() + top + side),
+                    position * (Point3::origin() + side - top),
+                    color,
+                );
+            }
+        }
+        ColliderShape::Plane { normal } => {
+            let normal = normal.normalize();
+            let tangent = if normal.x.abs() < 0.9 {
+                Vector3::x().cross(&normal).normalize()
+            } else {
+                Vector3::y().cross(&normal).normalize()
+            };
+            let bitangent = normal.cross(&tangent);
+            let corners = [
+                tangent + bitangent,
+                tangent - bitangent,
+                -tangent - bitangent,
+                -tangent + bitangent,
+            ];
+            for i in 0..corners.len() {
+                let next = corners[(i + 1) % corners.len()];
+                lines.draw_line(
+                    position * (Point3::origin() + corners[i] * 10.0),
+                    position * (Point3::origin() + next * 10.0),
+                    color,
+                );
+            }
+            lines.draw_direction(position * Point3::origin(), position * normal, color);
+        }
+        ColliderShape::ConvexHull { ref points } => {
+            // The box around the hull, computing its faces every frame would be too slow.
+            let (min, max) = points.iter().fold(
+                (
+                    Vector3::repeat(std::f32::MAX),
+                    Vector3::repeat(std::f32::MIN),
+                ),
+                |(min, max), point| (min.inf(&point.coords), max.sup(&point.coords)),
+            );
+            if !points.is_empty() {
+                draw_box(lines, position, min, max, color);
+            }
+        }
+        ColliderShape::TriMesh {
+            ref vertices,
+            ref indices,
+        } => {
+            for triangle in indices {
+                let corners = [triangle.x, triangle.y, triangle.z];
+                for i in 0..3 {
+                    let (a, b) = (corners[i], corners[(i + 1) % 3]);
+                    if let (Some(a), Some(b)) = (vertices.get(a), vertices.get(b)) {
+                        lines.draw_line(position * a, position * b, color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn draw_shape_2d(
+    lines: &mut DebugLines,
+    shape: &ColliderShape2D,
+    position: &Isometry3<f32>,
+    color: Rgba,
+) {
+    match *shape {
+        ColliderShape2D::Circle { radius } => draw_circle(
+            lines,
+            position,
+            Vector3::zeros(),
+            Vector3::x(),
+            Vector3::y(),
+            radius,
+            color,
+        ),
+        ColliderShape2D::Cuboid { half_extents } => {
+            let half_extents = Vector3::new(half_extents.x, half_extents.y, 0.0);
+            draw_box(lines, position, -half_extents, half_extents, color)
+        }
+        ColliderShape2D::Capsule {
+            half_height,
+            radius,
+        } => {
+            let top = Vector3::y() * half_height;
+            for &center in &[top, -top] {
+                draw_circle(
+                    lines,
+                    position,
+                    center,
+                    Vector3::x(),
+                    Vector3::y(),
+                    radius,
+                    color,
+                );
+            }
+            for &side in &[Vector3::x() * radius, -Vector3::x() * radius] {
+                lines.draw_line(
+                    position * (Point3::origin() + top + side),
+                    position * (Point3::origin() + side - top),
+                    color,
+                );
+            }
+        }
+        ColliderShape2D::ConvexPolygon { ref points } => {
+            let polygon = match ConvexPolygon::try_from_points(points) {
+                Some(polygon) => polygon,
+                None => return,
+            };
+            let points = polygon.points();
+            for i in 0..points.len() {
+                let (a, b) = (points[i], points[(i + 1) % points.len()]);
+                lines.draw_line(
+                    position * Point3::new(a.x, a.y, 0.0),
+                    position * Point3::new(b.x, b.y, 0.0),
+                    color,
+                );
+            }
+        }
+    }
+}
+
+/// Draws a circle around the center, in the plane of the two axes.
+fn draw_circle(
+    lines: &mut DebugLines,
+    position: &Isometry3<f32>,
+    center: Vector3<f32>,
+    axis1: Vector3<f32>,
+    axis2: Vector3<f32>,
+    radius: f32,
+    color: Rgba,
+) {
+    let point = |i: usize| {
+        let angle = i as f32 / SEGMENTS as f32 * 2.0 * std::f32::consts::PI;
+        position
+            * (Point3::origin() + center + (axis1 * angle.cos() + axis2 * angle.sin()) * radius)
+    };
+    for i in 0..SEGMENTS {
+        lines.draw_line(point(i), point(i + 1), color);
+    }
+}
+
+/// Draws the edges of a box, given its corners with the smallest and largest coordinates.
+fn draw_box(
+    lines: &mut DebugLines,
+    position: &Isometry3<f32>,
+    min: Vector3<f32>,
+    max: Vector3<f32>,
+    color: Rgba,
+) {
+    let corner = |i: usize| {
+        position
+            * Point3::new(
+                if i & 1 == 0 { min.x } else { max.x },
+                if i & 2 == 0 { min.y } else { max.y },
+                if i & 4 == 0 { min.z } else { max.z },
+            )
+    };
+    // Each edge joins two corners differing by a single coordinate.
+    for i in 0..8 {
+        for &bit in &[1, 2, 4] {
+            if i & bit == 0 {
+                lines.draw_line(corner(i), corner(i | bit), color);
+            }
+        }
+    }
+}
+
+fn draw_contact(lines: &mut DebugLines, point: Point3<f32>, normal: Vector3<f32>, color: Rgba) {
+    draw_cross(lines, point, 0.02, color);
+    lines.draw_direction(point, normal * 0.2, color);
+}
+
+fn draw_cross(lines: &mut DebugLines, point: Point3<f32>, size: f32, color: Rgba) {
+    for &axis in &[Vector3::x(), Vector3::y(), Vector3::z()] {
+        lines.draw_line(point - axis * size, point + axis * size, color);
+    }
+}
@@ -0,0 +1,102 @@
+//! ECS 2D physics bundle
+
+use amethyst_core::{bundle::SystemBundle, nalgebra::Vector2, specs::prelude::DispatcherBuilder};
+use amethyst_error::Error;
+
+use crate::{
+    debug::Physics2DDebugLinesSystem,
+    dim2::systems::{
+        Physics2DStepSystem, SyncBodies2DFromPhysicsSystem, SyncBodies2DToPhysicsSystem,
+    },
+};
+
+/// 2D physics bundle
+///
+/// Adds the `SyncBodies2DToPhysicsSystem`, the `Physics2DStepSystem` and the
+/// `SyncBodies2DFromPhysicsSystem`, with the names "physics2d_sync_to", "physics2d_step" and
+/// "physics2d_sync_from".
+///
+/// The bodies write to the `Transform` components, so the `TransformBundle` should depend on
+/// "physics2d_sync_from".
+///
+/// With `with_debug_lines`, the `Physics2DDebugLinesSystem` is added as "physics2d_debug_lines".
+///
+/// ## Errors
+///
+/// No errors will be returned by this bundle.
+pub struct Physics2DBundle<'a> {
+    gravity: Vector2<f32>,
+    max_steps: usize,
+    debug_lines: bool,
+    dep: &'a [&'a str],
+}
+
+impl<'a> Default for Physics2DBundle<'a> {
+    fn default() -> Self {
+        Physics2DBundle {
+            gravity: Vector2::new(0.0, -9.81),
+            max_steps: 8,
+            debug_lines: false,
+            dep: &[],
+        }
+    }
+}
+
+impl<'a> Physics2DBundle<'a> {
+    /// Create a new 2D physics bundle, with the earth's gravity along the negative Y axis.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set the gravity of the physics world, use zero for top-down games.
+    pub fn with_gravity(mut self, gravity: Vector2<f32>) -> Self {
+        self.gravity = gravity;
+        self
+    }
+
+    /// Set the maximal number of physics steps per frame, 8 by default.
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Draw the physics world with the `DebugLines` resource, see `PhysicsDebugSettings`.
+    pub fn with_debug_lines(mut self) -> Self {
+        self.debug_lines = true;
+        self
+    }
+
+    /// Set dependencies for the physics systems.
+    pub fn with_dep(mut self, dep: &'a [&'a str]) -> Self {
+        self.dep = dep;
+        self
+    }
+}
+
+impl<'a, 'b, 'c> SystemBundle<'a, 'b> for Physics2DBundle<'c> {
+    fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<(), Error> {
+        builder.add(
+            SyncBodies2DToPhysicsSystem::new(),
+            "physics2d_sync_to",
+            self.dep,
+        );
+        builder.add(
+            Physics2DStepSystem::new(self.gravity, self.max_steps),
+            "physics2d_step",
+            &["physics2d_sync_to"],
+        );
+        builder.add(
+            SyncBodies2DFromPhysicsSystem::new(),
+            "physics2d_sync_from",
+            &["physics2d_step"],
+        );
+        if self.debug_lines {
+            builder.add(
+                Physics2DDebugLinesSystem::new(),
+                "physics2d_debug_lines",
+                &["physics2d_step"],
+            );
+        }
+        Ok(())
+    }
+}
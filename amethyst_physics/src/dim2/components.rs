@@ -0,0 +1,194 @@
+//! 2D physics components.
+
+use amethyst_core::{
+    nalgebra::{Isometry2, Point2, Vector2},
+    specs::{Component, DenseVecStorage},
+};
+use log::error;
+use ncollide2d::shape::{Ball, Capsule, ConvexPolygon, Cuboid, ShapeHandle};
+use nphysics2d::object::BodyStatus as NBodyStatus;
+use serde::{Deserialize, Serialize};
+
+use crate::components::BodyStatus;
+
+impl From<BodyStatus> for NBodyStatus {
+    fn from(status: BodyStatus) -> Self {
+        match status {
+            BodyStatus::Dynamic => NBodyStatus::Dynamic,
+            BodyStatus::Static => NBodyStatus::Static,
+            BodyStatus::Kinematic => NBodyStatus::Kinematic,
+        }
+    }
+}
+
+/// A rigid body simulated by the `PhysicsWorld2D`, moving on the XY plane.
+///
+/// The body is placed at the X and Y translation and the rotation around the Z axis of the
+/// `Transform` of the entity. After every physics step, those are overwritten with the position of
+/// the body, and the Z translation is kept. Changing the `Transform` teleports the body. Entities
+/// with a `RigidBody2D` should not have a `Parent`.
+///
+/// The mass of the body is computed from the density of its `Collider2D`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RigidBody2D {
+    /// How the body is simulated
+    pub status: BodyStatus,
+    /// Linear velocity, updated after every physics step
+    pub linear_velocity: Vector2<f32>,
+    /// Angular velocity around the Z axis, updated after every physics step
+    pub angular_velocity: f32,
+    /// Prevents contacts and forces from rotating the body, for characters of platformers
+    pub lock_rotation: bool,
+}
+
+impl RigidBody2D {
+    /// Creates a dynamic rigid body.
+    pub fn dynamic() -> Self {
+        Self::with_status(BodyStatus::Dynamic)
+    }
+
+    /// Creates a static rigid body.
+    pub fn fixed() -> Self {
+        Self::with_status(BodyStatus::Static)
+    }
+
+    /// Creates a kinematic rigid body.
+    pub fn kinematic() -> Self {
+        Self::with_status(BodyStatus::Kinematic)
+    }
+
+    /// Creates a rigid body with the given status.
+    pub fn with_status(status: BodyStatus) -> Self {
+        RigidBody2D {
+            status,
+            ..Default::default()
+        }
+    }
+
+    /// Prevents contacts and forces from rotating the body.
+    pub fn with_locked_rotation(mut self) -> Self {
+        self.lock_rotation = true;
+        self
+    }
+}
+
+impl Component for RigidBody2D {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// The shape of a `Collider2D`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ColliderShape2D {
+    /// A circle.
+    Circle {
+        /// Radius of the circle
+        radius: f32,
+    },
+    /// A rectangle.
+    Cuboid {
+        /// Half of the width and height of the rectangle
+        half_extents: Vector2<f32>,
+    },
+    /// A capsule along the Y axis.
+    Capsule {
+        /// Half of the height of the rectangular part
+        half_height: f32,
+        /// Radius of the capsule
+        radius: f32,
+    },
+    /// The convex hull of a set of points, see `polygon_from_sprite`.
+    ConvexPolygon {
+        /// The points inside the polygon
+        points: Vec<Point2<f32>>,
+    },
+}
+
+impl ColliderShape2D {
+    /// Creates the shape, degenerate convex polygons are replaced by a point.
+    pub(crate) fn shape_handle(&self) -> ShapeHandle<f32> {
+        match *self {
+            ColliderShape2D::Circle { radius } => ShapeHandle::new(Ball::new(radius)),
+            ColliderShape2D::Cuboid { half_extents } => ShapeHandle::new(Cuboid::new(half_extents)),
+            ColliderShape2D::Capsule {
+                half_height,
+                radius,
+            } => ShapeHandle::new(Capsule::new(half_height, radius)),
+            ColliderShape2D::ConvexPolygon { ref points } => {
+                match ConvexPolygon::try_from_points(points) {
+                    Some(polygon) => ShapeHandle::new(polygon),
+                    None => {
+                        error!(
+                            "Failed to compute the convex polygon of {} points",
+                            points.len()
+                        );
+                        ShapeHandle::new(Ball::new(0.0))
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A 2D collision shape, attached to the `RigidBody2D` of the same entity.
+///
+/// Colliders without a `RigidBody2D` are static, and placed at the `Transform` of their entity.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Collider2D {
+    /// The shape of the collider
+    pub shape: ColliderShape2D,
+    /// Position of the shape relative to the entity
+    pub offset: Isometry2<f32>,
+    /// Density used to compute the mass of the body
+    pub density: f32,
+    /// Bounciness, between 0 and 1
+    pub restitution: f32,
+    /// Friction coefficient
+    pub friction: f32,
+    /// Sensors do not collide, and emit `TriggerEvent`s instead
+    #[serde(default)]
+    pub sensor: bool,
+}
+
+impl Collider2D {
+    /// Creates a collider with a density of 1 and the default material.
+    pub fn new(shape: ColliderShape2D) -> Self {
+        Collider2D {
+            shape,
+            offset: Isometry2::identity(),
+            density: 1.0,
+            restitution: 0.0,
+            friction: 0.5,
+            sensor: false,
+        }
+    }
+
+    /// Sets the position of the shape relative to the entity.
+    pub fn with_offset(mut self, offset: Isometry2<f32>) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Sets the density.
+    pub fn with_density(mut self, density: f32) -> Self {
+        self.density = density;
+        self
+    }
+
+    /// Sets the restitution and friction.
+    pub fn with_material(mut self, restitution: f32, friction: f32) -> Self {
+        self.restitution = restitution;
+        self.friction = friction;
+        self
+    }
+
+    /// Makes the collider a sensor, which emits `TriggerEvent`s instead of colliding.
+    pub fn with_sensor(mut self) -> Self {
+        self.sensor = true;
+        self
+    }
+}
+
+impl Component for Collider2D {
+    type Storage = DenseVecStorage<Self>;
+}
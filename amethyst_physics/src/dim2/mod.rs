@@ -0,0 +1,59 @@
+//! 2D physics, simulating bodies on the XY plane with `nphysics2d`.
+
+pub use self::{
+    bundle::Physics2DBundle,
+    components::{Collider2D, ColliderShape2D, RigidBody2D},
+    systems::{Physics2DStepSystem, SyncBodies2DFromPhysicsSystem, SyncBodies2DToPhysicsSystem},
+    world::PhysicsWorld2D,
+};
+
+use amethyst_core::{
+    nalgebra::{Isometry2, UnitQuaternion, Vector2},
+    Transform,
+};
+
+mod bundle;
+mod components;
+mod systems;
+mod world;
+
+/// The position of a `Transform` on the XY plane.
+pub(crate) fn transform_isometry2(transform: &Transform) -> Isometry2<f32> {
+    let translation = transform.translation();
+    let (_, _, angle) = transform.rotation().euler_angles();
+    Isometry2::new(Vector2::new(translation.x, translation.y), angle)
+}
+
+/// Moves the `Transform` to the position on the XY plane, keeping the Z translation and the
+/// rotations around the X and Y axes.
+pub(crate) fn set_transform_isometry2(transform: &mut Transform, position: &Isometry2<f32>) {
+    let (roll, pitch, _) = transform.rotation().euler_angles();
+    let translation = transform.translation_mut();
+    translation.x = position.translation.vector.x;
+    translation.y = position.translation.vector.y;
+    transform.set_rotation(UnitQuaternion::from_euler_angles(
+        roll,
+        pitch,
+        position.rotation.angle(),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn transform_round_trip() {
+        let mut transform = Transform::default();
+        transform.set_xyz(1.0, 2.0, 3.0);
+        let position = Isometry2::new(Vector2::new(4.0, 5.0), 0.5);
+        set_transform_isometry2(&mut transform, &position);
+
+        assert_eq!(transform.translation().z, 3.0);
+        let result = transform_isometry2(&transform);
+        assert_relative_eq!(result.translation.vector, position.translation.vector);
+        assert_relative_eq!(result.rotation.angle(), 0.5, epsilon = 1.0e-6);
+    }
+}
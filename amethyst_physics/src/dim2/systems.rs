@@ -0,0 +1,251 @@
+//! Systems synchronizing the 2D components with the 2D physics world, and stepping it.
+
+use amethyst_core::{
+    nalgebra::Vector2,
+    shrev::EventChannel,
+    specs::{Entities, Entity, Join, Read, ReadStorage, Resources, System, Write, WriteStorage},
+    Time, Transform,
+};
+use nphysics2d::{math::Velocity, object::BodyHandle};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{
+    components::BodyStatus,
+    dim2::{
+        components::{Collider2D, RigidBody2D},
+        set_transform_isometry2, transform_isometry2,
+        world::PhysicsWorld2D,
+    },
+    events::{CollisionEvent, TriggerEvent},
+};
+
+/// Creates, updates and removes the bodies and colliders of the `PhysicsWorld2D` according to the
+/// `RigidBody2D`, `Collider2D` and `Transform` components.
+#[derive(Default)]
+pub struct SyncBodies2DToPhysicsSystem {
+    scratch: Vec<Entity>,
+}
+
+impl SyncBodies2DToPhysicsSystem {
+    /// Creates a new `SyncBodies2DToPhysicsSystem`.
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<'a> System<'a> for SyncBodies2DToPhysicsSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Transform>,
+        ReadStorage<'a, RigidBody2D>,
+        ReadStorage<'a, Collider2D>,
+        Write<'a, PhysicsWorld2D>,
+    );
+
+    fn run(&mut self, (entities, transforms, bodies, colliders, mut physics): Self::SystemData) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("sync_bodies_2d_to_physics_system");
+
+        self.scratch.clear();
+        self.scratch
+            .extend(physics.bodies.keys().cloned().filter(|e| {
+                !entities.is_alive(*e) || !bodies.contains(*e) || !transforms.contains(*e)
+            }));
+        for entity in self.scratch.drain(..) {
+            physics.remove_body(entity);
+        }
+
+        self.scratch
+            .extend(physics.colliders.keys().cloned().filter(|e| {
+                !entities.is_alive(*e) || !colliders.contains(*e) || !transforms.contains(*e)
+            }));
+        for entity in self.scratch.drain(..) {
+            physics.remove_collider(entity);
+        }
+
+        for (entity, body, transform) in (&*entities, &bodies, &transforms).join() {
+            let isometry = *transform.isometry();
+            let (handle, position) = match physics.bodies.get_mut(&entity) {
+                Some(record) => {
+                    let position_changed = record.transform != isometry;
+                    if position_changed {
+                        record.position = transform_isometry2(transform);
+                    }
+                    let changed = position_changed
+                        || record.status != body.status
+                        || record.lock_rotation != body.lock_rotation
+                        || record.linear_velocity != body.linear_velocity
+                        || record.angular_velocity != body.angular_velocity;
+                    record.status = body.status;
+                    record.lock_rotation = body.lock_rotation;
+                    record.transform = isometry;
+                    record.linear_velocity = body.linear_velocity;
+                    record.angular_velocity = body.angular_velocity;
+                    if !changed {
+                        continue;
+                    }
+                    (
+                        record.handle,
+                        if position_changed {
+                            Some(record.position)
+                        } else {
+                            None
+                        },
+                    )
+                }
+                None => {
+                    physics.add_body(entity, body, isometry, transform_isometry2(transform));
+                    continue;
+                }
+            };
+
+            if let Some(rigid_body) = physics.world.rigid_body_mut(handle) {
+                rigid_body.set_status(body.status.into());
+                rigid_body.set_rotations_kinematic(body.lock_rotation);
+                if let Some(position) = position {
+                    rigid_body.set_position(position);
+                }
+                rigid_body.set_velocity(Velocity::new(body.linear_velocity, body.angular_velocity));
+                rigid_body.activate();
+            }
+        }
+
+        for (entity, collider, transform) in (&*entities, &colliders, &transforms).join() {
+            // Colliders of entities with a body are placed relatively to the body, the others
+            // are attached to the ground.
+            let (parent, isometry) = match physics.body_handle(entity) {
+                Some(handle) => (handle, None),
+                None => (BodyHandle::ground(), Some(*transform.isometry())),
+            };
+            let up_to_date = physics.colliders.get(&entity).map_or(false, |record| {
+                record.parent == parent
+                    && record.transform == isometry
+                    && record.collider == *collider
+            });
+            if !up_to_date {
+                physics.remove_collider(entity);
+                let transform = isometry.map(|isometry| (isometry, transform_isometry2(transform)));
+                physics.add_collider(entity, collider, parent, transform);
+            }
+        }
+    }
+}
+
+/// Steps the `PhysicsWorld2D` on the fixed timestep of the `Time` resource, and emits the
+/// `CollisionEvent`s and `TriggerEvent`s.
+///
+/// If the frame took longer than `max_steps` fixed timesteps, the simulation slows down instead
+/// of stepping more.
+pub struct Physics2DStepSystem {
+    gravity: Vector2<f32>,
+    max_steps: usize,
+}
+
+impl Physics2DStepSystem {
+    /// Creates a new `Physics2DStepSystem`, setting the gravity of the world.
+    pub fn new(gravity: Vector2<f32>, max_steps: usize) -> Self {
+        Physics2DStepSystem { gravity, max_steps }
+    }
+}
+
+impl<'a> System<'a> for Physics2DStepSystem {
+    type SystemData = (
+        Read<'a, Time>,
+        Write<'a, PhysicsWorld2D>,
+        Write<'a, EventChannel<CollisionEvent>>,
+        Write<'a, EventChannel<TriggerEvent>>,
+    );
+
+    fn run(
+        &mut self,
+        (time, mut physics, mut collision_events, mut trigger_events): Self::SystemData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("physics_2d_step_system");
+
+        let timestep = time.fixed_seconds();
+        physics.world.set_timestep(timestep);
+        physics.accumulator += time.delta_seconds();
+
+        let mut steps = 0;
+        while physics.accumulator >= timestep && steps < self.max_steps {
+            physics.accumulator -= timestep;
+            steps += 1;
+            let (collisions, triggers) = physics.step();
+            collision_events.iter_write(collisions);
+            trigger_events.iter_write(triggers);
+        }
+        if physics.accumulator >= timestep {
+            physics.accumulator %= timestep;
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        use amethyst_core::specs::prelude::SystemData;
+        Self::SystemData::setup(res);
+        res.fetch_mut::<PhysicsWorld2D>().set_gravity(self.gravity);
+    }
+}
+
+/// Writes the positions and velocities of the bodies of the `PhysicsWorld2D` to the `Transform`
+/// and `RigidBody2D` components.
+#[derive(Default)]
+pub struct SyncBodies2DFromPhysicsSystem;
+
+impl SyncBodies2DFromPhysicsSystem {
+    /// Creates a new `SyncBodies2DFromPhysicsSystem`.
+    pub fn new() -> Self {
+        SyncBodies2DFromPhysicsSystem
+    }
+}
+
+impl<'a> System<'a> for SyncBodies2DFromPhysicsSystem {
+    type SystemData = (
+        Write<'a, PhysicsWorld2D>,
+        WriteStorage<'a, Transform>,
+        WriteStorage<'a, RigidBody2D>,
+    );
+
+    fn run(&mut self, (mut physics, mut transforms, mut bodies): Self::SystemData) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("sync_bodies_2d_from_physics_system");
+
+        let PhysicsWorld2D {
+            ref world,
+            bodies: ref mut records,
+            ..
+        } = *physics;
+        for (entity, record) in records.iter_mut() {
+            if record.status == BodyStatus::Static {
+                continue;
+            }
+            let rigid_body = match world.rigid_body(record.handle) {
+                Some(rigid_body) => rigid_body,
+                None => continue,
+            };
+
+            let position = *rigid_body.position();
+            if record.position != position {
+                record.position = position;
+                if let Some(transform) = transforms.get_mut(*entity) {
+                    set_transform_isometry2(transform, &position);
+                    record.transform = *transform.isometry();
+                }
+            }
+
+            let velocity = rigid_body.velocity();
+            if record.linear_velocity != velocity.linear
+                || record.angular_velocity != velocity.angular
+            {
+                record.linear_velocity = velocity.linear;
+                record.angular_velocity = velocity.angular;
+                if let Some(body) = bodies.get_mut(*entity) {
+                    body.linear_velocity = velocity.linear;
+                    body.angular_velocity = velocity.angular;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! The 2D physics world resource.
+
+use amethyst_core::{
+    nalgebra::{Isometry2, Isometry3, Vector2},
+    specs::Entity,
+};
+use fnv::FnvHashMap;
+use ncollide2d::{events::ContactEvent, query::Proximity};
+use nphysics2d::{
+    math::Velocity,
+    object::{BodyHandle, ColliderDesc, ColliderHandle, Material, RigidBodyDesc},
+    world::World,
+};
+
+use crate::{
+    components::BodyStatus,
+    dim2::components::{Collider2D, RigidBody2D},
+    events::{CollisionEvent, TriggerEvent},
+};
+
+/// The body of an entity, and its state after the last synchronization.
+pub(crate) struct BodyRecord2D {
+    pub(crate) handle: BodyHandle,
+    pub(crate) status: BodyStatus,
+    pub(crate) lock_rotation: bool,
+    /// The isometry of the `Transform`, compared instead of the 2D position so the conversion does
+    /// not cause spurious teleports.
+    pub(crate) transform: Isometry3<f32>,
+    pub(crate) position: Isometry2<f32>,
+    pub(crate) linear_velocity: Vector2<f32>,
+    pub(crate) angular_velocity: f32,
+}
+
+/// The collider of an entity, and the component it was built from.
+pub(crate) struct ColliderRecord2D {
+    pub(crate) handle: ColliderHandle,
+    pub(crate) parent: BodyHandle,
+    pub(crate) collider: Collider2D,
+    pub(crate) transform: Option<Isometry3<f32>>,
+}
+
+/// Resource containing the nphysics2d world, and the bodies and colliders of the entities.
+///
+/// Bodies and colliders are created, updated and removed by the `SyncBodies2DToPhysicsSystem`, the
+/// `RigidBody2D` and `Collider2D` components should be used to modify them.
+pub struct PhysicsWorld2D {
+    pub(crate) world: World<f32>,
+    pub(crate) bodies: FnvHashMap<Entity, BodyRecord2D>,
+    pub(crate) colliders: FnvHashMap<Entity, ColliderRecord2D>,
+    pub(crate) collider_entities: FnvHashMap<ColliderHandle, Entity>,
+    pub(crate) accumulator: f32,
+}
+
+impl Default for PhysicsWorld2D {
+    fn default() -> Self {
+        PhysicsWorld2D::new(Vector2::new(0.0, -9.81))
+    }
+}
+
+impl PhysicsWorld2D {
+    /// Creates an empty world with the given gravity.
+    pub fn new(gravity: Vector2<f32>) -> Self {
+        let mut world = World::new();
+        world.set_gravity(gravity);
+        PhysicsWorld2D {
+            world,
+            bodies: FnvHashMap::default(),
+            colliders: FnvHashMap::default(),
+            collider_entities: FnvHashMap::default(),
+            accumulator: 0.0,
+        }
+    }
+
+    /// The gravity applied to dynamic bodies.
+    pub fn gravity(&self) -> &Vector2<f32> {
+        self.world.gravity()
+    }
+
+    /// Sets the gravity applied to dynamic bodies.
+    pub fn set_gravity(&mut self, gravity: Vector2<f32>) {
+        self.world.set_gravity(gravity);
+    }
+
+    /// The nphysics2d world, for features not exposed through components.
+    pub fn world(&self) -> &World<f32> {
+        &self.world
+    }
+
+    /// The nphysics2d world, for features not exposed through components.
+    ///
+    /// Bodies and colliders created by the physics systems must not be removed through it.
+    pub fn world_mut(&mut self) -> &mut World<f32> {
+        &mut self.world
+    }
+
+    /// The handle of the body of an entity.
+    pub fn body_handle(&self, entity: Entity) -> Option<BodyHandle> {
+        self.bodies.get(&entity).map(|record| record.handle)
+    }
+
+    /// The handle of the collider of an entity.
+    pub fn collider_handle(&self, entity: Entity) -> Option<ColliderHandle> {
+        self.colliders.get(&entity).map(|record| record.handle)
+    }
+
+    /// The entity a collider belongs to.
+    pub fn collider_entity(&self, handle: ColliderHandle) -> Option<Entity> {
+        self.collider_entities.get(&handle).cloned()
+    }
+
+    pub(crate) fn add_body(
+        &mut self,
+        entity: Entity,
+        body: &RigidBody2D,
+        transform: Isometry3<f32>,
+        position: Isometry2<f32>,
+    ) {
+        let handle = RigidBodyDesc::new()
+            .position(position)
+            .velocity(Velocity::new(body.linear_velocity, body.angular_velocity))
+            .status(body.status.into())
+            .kinematic_rotations(body.lock_rotation)
+            .build(&mut self.world)
+            .handle();
+        self.bodies.insert(
+            entity,
+            BodyRecord2D {
+                handle,
+                status: body.status,
+                lock_rotation: body.lock_rotation,
+                transform,
+                position,
+                linear_velocity: body.linear_velocity,
+                angular_velocity: body.angular_velocity,
+            },
+        );
+    }
+
+    pub(crate) fn remove_body(&mut self, entity: Entity) {
+        if let Some(record) = self.bodies.remove(&entity) {
+            // Removing a body removes the colliders attached to it.
+            if self
+                .colliders
+                .get(&entity)
+                .map_or(false, |collider| collider.parent == record.handle)
+            {
+                let collider = self.colliders.remove(&entity).expect("Unreachable");
+                self.collider_entities.remove(&collider.handle);
+            }
+            self.world.remove_bodies(&[record.handle]);
+        }
+    }
+
+    /// Adds the collider, attached to the given body, or to the ground at the given position.
+    pub(crate) fn add_collider(
+        &mut self,
+        entity: Entity,
+        collider: &Collider2D,
+        parent: BodyHandle,
+        transform: Option<(Isometry3<f32>, Isometry2<f32>)>,
+    ) {
+        let position = transform.map_or_else(Isometry2::identity, |(_, position)| position);
+        let handle = ColliderDesc::new(collider.shape.shape_handle())
+            .position(position * collider.offset)
+            .density(collider.density)
+            .material(Material::new(collider.restitution, collider.friction))
+            .sensor(collider.sensor)
+            .build_with_parent(parent, &mut self.world)
+            .expect("Unreachable: The parent of the collider exists")
+            .handle();
+        self.collider_entities.insert(handle, entity);
+        self.colliders.insert(
+            entity,
+            ColliderRecord2D {
+                handle,
+                parent,
+                collider: collider.clone(),
+                transform: transform.map(|(transform, _)| transform),
+            },
+        );
+    }
+
+    pub(crate) fn remove_collider(&mut self, entity: Entity) {
+        if let Some(record) = self.colliders.remove(&entity) {
+            self.collider_entities.remove(&record.handle);
+            self.world.remove_colliders(&[record.handle]);
+        }
+    }
+
+    /// Steps the world once, and returns the collisions that started or stopped and the colliders
+    /// that entered or exited sensors.
+    pub(crate) fn step(&mut self) -> (Vec<CollisionEvent>, Vec<TriggerEvent>) {
+        self.world.step();
+
+        let entities = &self.collider_entities;
+        let collisions = self
+            .world
+            .contact_events()
+            .iter()
+            .filter_map(|event| match *event {
+                ContactEvent::Started(first, second) => Some(CollisionEvent::Started(
+                    *entities.get(&first)?,
+                    *entities.get(&second)?,
+                )),
+                ContactEvent::Stopped(first, second) => Some(CollisionEvent::Stopped(
+                    *entities.get(&first)?,
+                    *entities.get(&second)?,
+                )),
+            })
+            .collect();
+
+        let mut triggers = Vec::new();
+        for event in self.world.proximity_events() {
+            let (first, second) = match (
+                entities.get(&event.collider1),
+                entities.get(&event.collider2),
+            ) {
+                (Some(first), Some(second)) => (*first, *second),
+                _ => continue,
+            };
+            let was_inside = event.prev_status == Proximity::Intersecting;
+            let is_inside = event.new_status == Proximity::Intersecting;
+            // When both colliders are sensors, each of them is triggered by the other.
+            for &(trigger, other) in &[(first, second), (second, first)] {
+                if !self.is_sensor(trigger) {
+                    continue;
+                }
+                match (was_inside, is_inside) {
+                    (false, true) => triggers.push(TriggerEvent::TriggerEntered { trigger, other }),
+                    (true, false) => triggers.push(TriggerEvent::TriggerExited { trigger, other }),
+                    _ => {}
+                }
+            }
+        }
+
+        (collisions, triggers)
+    }
+
+    fn is_sensor(&self, entity: Entity) -> bool {
+        self.colliders
+            .get(&entity)
+            .map_or(false, |record| record.collider.sensor)
+    }
+}
@@ -0,0 +1,35 @@
+//! Physics events.
+
+use amethyst_core::specs::Entity;
+
+/// Emitted into the `EventChannel<CollisionEvent>` resource when two colliders start or stop
+/// touching.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CollisionEvent {
+    /// The colliders of the two entities started touching.
+    Started(Entity, Entity),
+    /// The colliders of the two entities stopped touching.
+    Stopped(Entity, Entity),
+}
+
+/// Emitted into the `EventChannel<TriggerEvent>` resource when a collider enters or exits a sensor
+/// collider.
+///
+/// When both colliders are sensors, each of them emits an event with the other one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TriggerEvent {
+    /// A collider started intersecting the sensor.
+    TriggerEntered {
+        /// The entity of the sensor collider
+        trigger: Entity,
+        /// The entity of the collider that entered it
+        other: Entity,
+    },
+    /// A collider stopped intersecting the sensor.
+    TriggerExited {
+        /// The entity of the sensor collider
+        trigger: Entity,
+        /// The entity of the collider that exited it
+        other: Entity,
+    },
+}
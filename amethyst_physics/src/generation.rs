@@ -0,0 +1,170 @@
+//! Colliders generated from meshes and sprites.
+
+use amethyst_core::nalgebra::{Isometry2, Point2, Point3, Vector2, Vector3};
+use amethyst_renderer::{ImageData, MeshData, Sprite};
+use ncollide2d::shape::ConvexPolygon;
+use ncollide3d::shape::ConvexHull;
+
+use crate::{
+    components::ColliderShape,
+    dim2::{Collider2D, ColliderShape2D},
+};
+
+/// Creates the convex hull of the vertices of the mesh.
+///
+/// Returns `None` if the mesh is created by a `MeshCreator`, or if its vertices are degenerate.
+pub fn convex_hull_from_mesh(mesh: &MeshData) -> Option<ColliderShape> {
+    let points = mesh_positions(mesh)?;
+    ConvexHull::try_from_points(&points)?;
+    Some(ColliderShape::ConvexHull { points })
+}
+
+/// Creates a triangle mesh from the triangle list of the mesh.
+///
+/// Returns `None` if the mesh is created by a `MeshCreator`, or if it has no triangles.
+pub fn trimesh_from_mesh(mesh: &MeshData) -> Option<ColliderShape> {
+    let vertices = mesh_positions(mesh)?;
+    let indices = (0..vertices.len() / 3)
+        .map(|i| Point3::new(i * 3, i * 3 + 1, i * 3 + 2))
+        .collect::<Vec<_>>();
+    if indices.is_empty() {
+        return None;
+    }
+    Some(ColliderShape::TriMesh { vertices, indices })
+}
+
+/// Creates a rectangle collider covering the sprite as drawn by `DrawFlat2D`.
+pub fn cuboid_from_sprite(sprite: &Sprite) -> Collider2D {
+    Collider2D::new(ColliderShape2D::Cuboid {
+        half_extents: Vector2::new(sprite.width.abs() / 2.0, sprite.height.abs() / 2.0),
+    })
+    .with_offset(Isometry2::new(
+        Vector2::new(-sprite.offsets[0], -sprite.offsets[1]),
+        0.0,
+    ))
+}
+
+/// Creates a convex polygon collider around the pixels of the sprite with an alpha above the
+/// threshold, the image has to be the texture of the sprite sheet.
+///
+/// Returns `None` if the sprite has no such pixels.
+pub fn polygon_from_sprite(
+    sprite: &Sprite,
+    image: &ImageData,
+    alpha_threshold: u8,
+) -> Option<Collider2D> {
+    let image_w = image.rgba.width() as f32;
+    let image_h = image.rgba.height() as f32;
+    let coords = &sprite.tex_coords;
+
+    // Sprite texture coordinates start at the bottom of the image, pixel rows at the top.
+    let to_pixel_x = |u: f32| (u * image_w).round().max(0.0).min(image_w) as u32;
+    let to_pixel_y = |v: f32| ((1.0 - v) * image_h).round().max(0.0).min(image_h) as u32;
+    let (left, right) = (to_pixel_x(coords.left), to_pixel_x(coords.right));
+    let (top, bottom) = (to_pixel_y(coords.top), to_pixel_y(coords.bottom));
+    let (min_x, max_x) = (left.min(right), left.max(right));
+    let (min_y, max_y) = (top.min(bottom), top.max(bottom));
+
+    // Converts a pixel corner of the image to a position relative to the entity.
+    let to_local = |x: u32, y: u32| {
+        let u = (x as f32 / image_w - coords.left) / (coords.right - coords.left) - 0.5;
+        let v = (1.0 - y as f32 / image_h - coords.bottom) / (coords.top - coords.bottom) - 0.5;
+        // Rotated sprites are packed 90 degrees clockwise in the texture.
+        let (x, y) = if sprite.rotated { (-v, u) } else { (u, v) };
+        Point2::new(
+            x * sprite.width - sprite.offsets[0],
+            y * sprite.height - sprite.offsets[1],
+        )
+    };
+
+    // The hull of the outermost opaque pixels of each row contains all opaque pixels.
+    let mut points = Vec::new();
+    for y in min_y..max_y {
+        let opaque = |x: &u32| image.rgba.get_pixel(*x, y).data[3] > alpha_threshold;
+        let first = (min_x..max_x).find(opaque);
+        let last = (min_x..max_x).rev().find(opaque);
+        if let (Some(first), Some(last)) = (first, last) {
+            points.push(to_local(first, y));
+            points.push(to_local(first, y + 1));
+            points.push(to_local(last + 1, y));
+            points.push(to_local(last + 1, y + 1));
+        }
+    }
+
+    ConvexPolygon::try_from_points(&points)?;
+    Some(Collider2D::new(ColliderShape2D::ConvexPolygon { points }))
+}
+
+fn mesh_positions(mesh: &MeshData) -> Option<Vec<Point3<f32>>> {
+    let positions = match *mesh {
+        MeshData::PosColor(ref vertices) => vertices.iter().map(|v| point(&v.position)).collect(),
+        MeshData::PosColorNorm(ref vertices) => {
+            vertices.iter().map(|v| point(&v.position)).collect()
+        }
+        MeshData::PosTex(ref vertices) => vertices.iter().map(|v| point(&v.position)).collect(),
+        MeshData::PosNormTex(ref vertices) => vertices.iter().map(|v| point(&v.position)).collect(),
+        MeshData::PosNormTangTex(ref vertices) => {
+            vertices.iter().map(|v| point(&v.position)).collect()
+        }
+        MeshData::Creator(_) => return None,
+    };
+    Some(positions)
+}
+
+fn point(position: &Vector3<f32>) -> Point3<f32> {
+    Point3::new(position.x, position.y, position.z)
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_renderer::{PosTex, TextureCoordinates};
+
+    use super::*;
+
+    #[test]
+    fn trimesh_from_triangle_list() {
+        let vertex = |x, y| PosTex {
+            position: Vector3::new(x, y, 0.0),
+            tex_coord: Vector2::new(0.0, 0.0),
+        };
+        let mesh = MeshData::PosTex(vec![
+            vertex(0.0, 0.0),
+            vertex(1.0, 0.0),
+            vertex(0.0, 1.0),
+            vertex(1.0, 1.0),
+        ]);
+        match trimesh_from_mesh(&mesh) {
+            Some(ColliderShape::TriMesh { vertices, indices }) => {
+                assert_eq!(vertices.len(), 4);
+                assert_eq!(indices, vec![Point3::new(0, 1, 2)]);
+            }
+            shape => panic!("Unexpected shape: {:?}", shape),
+        }
+    }
+
+    #[test]
+    fn cuboid_covers_sprite() {
+        let sprite = Sprite {
+            width: 10.0,
+            height: 4.0,
+            offsets: [1.0, 2.0],
+            tex_coords: TextureCoordinates {
+                left: 0.0,
+                right: 1.0,
+                bottom: 0.0,
+                top: 1.0,
+            },
+            rotated: false,
+            page: 0,
+            name: None,
+        };
+        let collider = cuboid_from_sprite(&sprite);
+        assert_eq!(
+            collider.shape,
+            ColliderShape2D::Cuboid {
+                half_extents: Vector2::new(5.0, 2.0)
+            }
+        );
+        assert_eq!(collider.offset.translation.vector, Vector2::new(-1.0, -2.0));
+    }
+}
@@ -0,0 +1,383 @@
+//! Joints between the bodies of two entities.
+
+use amethyst_core::{
+    nalgebra::{Isometry3, Point3, Translation3, Unit, UnitQuaternion, Vector3},
+    specs::{Component, DenseVecStorage, Entities, Entity, Join, System, Write, WriteStorage},
+};
+use nphysics3d::{
+    joint::{
+        BallConstraint, ConstraintHandle, FixedConstraint, JointConstraint, PrismaticConstraint,
+        RevoluteConstraint,
+    },
+    math::Force,
+    object::BodyHandle,
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::world::PhysicsWorld;
+
+/// How a `Joint` constrains the two bodies.
+#[derive(Clone, Debug, PartialEq)]
+pub enum JointKind {
+    /// The bodies keep their relative position and orientation.
+    Fixed,
+    /// The bodies rotate around an axis going through the anchors, like a door.
+    Hinge {
+        /// The rotation axis, in the space of the entity of the joint
+        axis: Vector3<f32>,
+    },
+    /// The bodies rotate freely around the anchors, like a shoulder.
+    Ball,
+    /// The bodies slide along an axis, like a piston.
+    Prismatic {
+        /// The sliding axis, in the space of the entity of the joint
+        axis: Vector3<f32>,
+    },
+    /// A damped spring pulls the anchors towards the rest length, like a suspension.
+    Spring {
+        /// Distance between the anchors at which the spring applies no force
+        rest_length: f32,
+        /// Force per unit of stretch
+        stiffness: f32,
+        /// Force per unit of stretching velocity
+        damping: f32,
+    },
+}
+
+/// A joint between the `RigidBody` of its entity and the `RigidBody` of another entity, or a
+/// fixed point of the world.
+///
+/// The joint is created once both bodies exist, and removed with the component or either body.
+/// It breaks when the force or the torque needed to hold it exceeds the thresholds, and can be
+/// repaired by setting `broken` back to `false`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Joint {
+    /// How the bodies are constrained
+    pub kind: JointKind,
+    /// The other entity, or `None` to attach the body to the world
+    pub other: Option<Entity>,
+    /// The attachment point, in the space of the entity of the joint
+    pub anchor: Point3<f32>,
+    /// The attachment point, in the space of the other entity, or of the world
+    pub other_anchor: Point3<f32>,
+    /// Force above which the joint breaks
+    pub break_force: Option<f32>,
+    /// Torque above which the joint breaks, ignored by springs
+    pub break_torque: Option<f32>,
+    /// Whether the joint broke, broken joints do not constrain the bodies
+    pub broken: bool,
+}
+
+impl Joint {
+    /// Creates an unbreakable joint between the origins of the two entities.
+    pub fn new(kind: JointKind, other: Option<Entity>) -> Self {
+        Joint {
+            kind,
+            other,
+            anchor: Point3::origin(),
+            other_anchor: Point3::origin(),
+            break_force: None,
+            break_torque: None,
+            broken: false,
+        }
+    }
+
+    /// Sets the attachment points, in the space of each entity.
+    pub fn with_anchors(mut self, anchor: Point3<f32>, other_anchor: Point3<f32>) -> Self {
+        self.anchor = anchor;
+        self.other_anchor = other_anchor;
+        self
+    }
+
+    /// Sets the force and torque above which the joint breaks.
+    pub fn with_break_thresholds(mut self, force: Option<f32>, torque: Option<f32>) -> Self {
+        self.break_force = force;
+        self.break_torque = torque;
+        self
+    }
+}
+
+impl Component for Joint {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// The joint of an entity, and the component it was built from.
+pub(crate) struct JointRecord {
+    /// The constraint of the joint, `None` for springs and broken joints
+    pub(crate) constraint: Option<ConstraintHandle>,
+    pub(crate) bodies: (BodyHandle, BodyHandle),
+    pub(crate) joint: Joint,
+}
+
+impl PhysicsWorld {
+    /// The handle of the constraint of the joint of an entity, springs have none.
+    pub fn constraint_handle(&self, entity: Entity) -> Option<ConstraintHandle> {
+        self.joints
+            .get(&entity)
+            .and_then(|record| record.constraint)
+    }
+
+    /// The position of a body, the ground is at the origin.
+    pub(crate) fn body_position(&self, handle: BodyHandle) -> Isometry3<f32> {
+        self.world
+            .rigid_body(handle)
+            .map_or_else(Isometry3::identity, |body| *body.position())
+    }
+
+    pub(crate) fn add_joint(
+        &mut self,
+        entity: Entity,
+        joint: &Joint,
+        bodies: (BodyHandle, BodyHandle),
+    ) {
+        let constraint = if joint.broken {
+            None
+        } else {
+            self.build_constraint(joint, bodies)
+        };
+        self.joints.insert(
+            entity,
+            JointRecord {
+                constraint,
+                bodies,
+                joint: joint.clone(),
+            },
+        );
+    }
+
+    pub(crate) fn remove_joint(&mut self, entity: Entity) {
+        if let Some(record) = self.joints.remove(&entity) {
+            if let Some(handle) = record.constraint {
+                self.world.remove_constraint(handle);
+            }
+        }
+    }
+
+    /// Removes the joints attached to a body, before the body is removed.
+    pub(crate) fn remove_joints_of(&mut self, body: BodyHandle) {
+        let attached = self
+            .joints
+            .iter()
+            .filter(|(_, record)| record.bodies.0 == body || record.bodies.1 == body)
+            .map(|(entity, _)| *entity)
+            .collect::<Vec<_>>();
+        for entity in attached {
+            self.remove_joint(entity);
+        }
+    }
+
+    /// Applies the forces of the springs, and breaks the overstretched ones.
+    pub(crate) fn apply_springs(&mut self) {
+        let PhysicsWorld {
+            ref mut world,
+            ref mut joints,
+            ..
+        } = *self;
+        for record in joints.values_mut() {
+            let (rest_length, stiffness, damping) = match record.joint.kind {
+                JointKind::Spring {
+                    rest_length,
+                    stiffness,
+                    damping,
+                } if !record.joint.broken => (rest_length, stiffness, damping),
+                _ => continue,
+            };
+
+            // World space anchors, centers of mass and velocities of the two bodies.
+            let state = |handle: BodyHandle, anchor: &Point3<f32>| match world.rigid_body(handle) {
+                Some(body) => {
+                    let anchor = body.position() * anchor;
+                    let velocity = body.velocity();
+                    let center = body.center_of_mass();
+                    let anchor_velocity =
+                        velocity.linear + velocity.angular.cross(&(anchor - center));
+                    (anchor, center, anchor_velocity)
+                }
+                None => (*anchor, *anchor, Vector3::zeros()),
+            };
+            let (anchor1, center1, velocity1) = state(record.bodies.0, &record.joint.anchor);
+            let (anchor2, center2, velocity2) = state(record.bodies.1, &record.joint.other_anchor);
+
+            let offset = anchor2 - anchor1;
+            let length = offset.norm();
+            if length <= std::f32::EPSILON {
+                continue;
+            }
+            let direction = offset / length;
+            let stretching = (velocity2 - velocity1).dot(&direction);
+            let magnitude = stiffness * (length - rest_length) + damping * stretching;
+            if record
+                .joint
+                .break_force
+                .map_or(false, |threshold| magnitude.abs() > threshold)
+            {
+                record.joint.broken = true;
+                continue;
+            }
+
+            let force = direction * magnitude;
+            for &(handle, anchor, center, force) in &[
+                (record.bodies.0, anchor1, center1, force),
+                (record.bodies.1, anchor2, center2, -force),
+            ] {
+                if let Some(body) = world.rigid_body_mut(handle) {
+                    body.apply_force(&Force::new(force, (anchor - center).cross(&force)));
+                    body.activate();
+                }
+            }
+        }
+    }
+
+    /// Removes the constraints that broke during the last step.
+    pub(crate) fn remove_broken_constraints(&mut self) {
+        let PhysicsWorld {
+            ref mut world,
+            ref mut joints,
+            ..
+        } = *self;
+        for record in joints.values_mut() {
+            let handle = match record.constraint {
+                Some(handle) => handle,
+                None => continue,
+            };
+            if world.constraint(handle).is_broken() {
+                world.remove_constraint(handle);
+                record.constraint = None;
+                record.joint.broken = true;
+            }
+        }
+    }
+
+    fn build_constraint(
+        &mut self,
+        joint: &Joint,
+        (body1, body2): (BodyHandle, BodyHandle),
+    ) -> Option<ConstraintHandle> {
+        // The current orientation of the first body in the space of the second one, which the
+        // axes and frames of the second body are expressed with.
+        let relative: UnitQuaternion<f32> =
+            self.body_position(body2).rotation.inverse() * self.body_position(body1).rotation;
+        let anchor1 = joint.anchor;
+        let anchor2 = joint.other_anchor;
+
+        let handle = match joint.kind {
+            JointKind::Fixed => self.add_constraint(
+                FixedConstraint::new(
+                    body1,
+                    body2,
+                    Isometry3::new(anchor1.coords, Vector3::zeros()),
+                    Isometry3::from_parts(Translation3::from_vector(anchor2.coords), relative),
+                ),
+                joint,
+            ),
+            JointKind::Hinge { axis } => self.add_constraint(
+                RevoluteConstraint::new(
+                    body1,
+                    body2,
+                    anchor1,
+                    Unit::new_normalize(axis),
+                    anchor2,
+                    Unit::new_normalize(relative * axis),
+                ),
+                joint,
+            ),
+            JointKind::Ball => {
+                self.add_constraint(BallConstraint::new(body1, body2, anchor1, anchor2), joint)
+            }
+            JointKind::Prismatic { axis } => self.add_constraint(
+                PrismaticConstraint::new(body1, body2, anchor1, Unit::new_normalize(axis), anchor2),
+                joint,
+            ),
+            JointKind::Spring { .. } => return None,
+        };
+        Some(handle)
+    }
+
+    fn add_constraint<C: JointConstraint<f32>>(
+        &mut self,
+        mut constraint: C,
+        joint: &Joint,
+    ) -> ConstraintHandle {
+        if let Some(force) = joint.break_force {
+            constraint.set_break_force(force);
+        }
+        if let Some(torque) = joint.break_torque {
+            constraint.set_break_torque(torque);
+        }
+        self.world.add_constraint(constraint)
+    }
+}
+
+/// Creates, updates and removes the joints of the `PhysicsWorld` according to the `Joint`
+/// components, and marks the broken ones.
+///
+/// Runs after the `SyncBodiesToPhysicsSystem`, so the bodies of the joints exist.
+#[derive(Default)]
+pub struct SyncJointsToPhysicsSystem {
+    scratch: Vec<Entity>,
+}
+
+impl SyncJointsToPhysicsSystem {
+    /// Creates a new `SyncJointsToPhysicsSystem`.
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<'a> System<'a> for SyncJointsToPhysicsSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, Joint>,
+        Write<'a, PhysicsWorld>,
+    );
+
+    fn run(&mut self, (entities, mut joints, mut physics): Self::SystemData) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("sync_joints_to_physics_system");
+
+        self.scratch.clear();
+        self.scratch.extend(
+            physics
+                .joints
+                .keys()
+                .cloned()
+                .filter(|e| !entities.is_alive(*e) || !joints.contains(*e)),
+        );
+        for entity in self.scratch.drain(..) {
+            physics.remove_joint(entity);
+        }
+
+        for (entity, joint) in (&*entities, &mut joints).join() {
+            let body1 = physics.body_handle(entity);
+            let body2 = match joint.other {
+                Some(other) => physics.body_handle(other),
+                None => Some(BodyHandle::ground()),
+            };
+            let bodies = match (body1, body2) {
+                (Some(body1), Some(body2)) => (body1, body2),
+                _ => {
+                    physics.remove_joint(entity);
+                    continue;
+                }
+            };
+
+            if let Some(record) = physics.joints.get(&entity) {
+                // Report the joints that broke during the last steps.
+                if record.joint.broken && !joint.broken {
+                    let mut broken = joint.clone();
+                    broken.broken = true;
+                    if broken == record.joint {
+                        joint.broken = true;
+                    }
+                }
+                if record.bodies == bodies && record.joint == *joint {
+                    continue;
+                }
+            }
+            physics.remove_joint(entity);
+            physics.add_joint(entity, joint, bodies);
+        }
+    }
+}
@@ -0,0 +1,61 @@
+//! Amethyst physics crate, integrating `nphysics` with the ECS.
+//!
+//! Entities with a `RigidBody` and a `Transform` are simulated by the `PhysicsWorld`, and the
+//! `Collider` component gives them a shape. The world is stepped on the fixed timestep of the
+//! `Time` resource, and collisions are emitted as `CollisionEvent`s. Sensor colliders emit
+//! `TriggerEvent`s when other colliders enter or exit them.
+//!
+//! The 2D counterparts, `RigidBody2D`, `Collider2D` and `PhysicsWorld2D`, simulate bodies on the XY
+//! plane for platformers and top-down games.
+//!
+//! Collider shapes can be generated from meshes and sprites with `convex_hull_from_mesh`,
+//! `trimesh_from_mesh`, `cuboid_from_sprite` and `polygon_from_sprite`.
+//!
+//! Raycasts, shape casts and overlap queries are available on the `PhysicsWorld`, and through the
+//! `PhysicsQuery` system data, which falls back to `BoundingVolume` components without physics.
+//!
+//! `Joint` components connect two bodies with fixed, hinge, ball, prismatic or spring joints,
+//! which can break under a force or torque threshold.
+//!
+//! The `CharacterController` moves characters as kinematic capsules, climbing steps, stopping at
+//! steep slopes and following the platforms they stand on.
+//!
+//! The physics worlds can be drawn with the `DrawDebugLines` pass, see `PhysicsDebugSettings`.
+
+#![warn(missing_docs, rust_2018_idioms, rust_2018_compatibility)]
+
+pub use ncollide2d;
+pub use ncollide3d;
+pub use nphysics2d;
+pub use nphysics3d;
+
+pub use self::{
+    bundle::PhysicsBundle,
+    character::{CharacterController, CharacterControllerSystem},
+    components::{BodyStatus, Collider, ColliderShape, RigidBody},
+    debug::{Physics2DDebugLinesSystem, PhysicsDebugLinesSystem, PhysicsDebugSettings},
+    dim2::{
+        Collider2D, ColliderShape2D, Physics2DBundle, Physics2DStepSystem, PhysicsWorld2D,
+        RigidBody2D, SyncBodies2DFromPhysicsSystem, SyncBodies2DToPhysicsSystem,
+    },
+    events::{CollisionEvent, TriggerEvent},
+    generation::{
+        convex_hull_from_mesh, cuboid_from_sprite, polygon_from_sprite, trimesh_from_mesh,
+    },
+    joints::{Joint, JointKind, SyncJointsToPhysicsSystem},
+    query::{BoundingVolume, PhysicsQuery, RaycastHit},
+    systems::{PhysicsStepSystem, SyncBodiesFromPhysicsSystem, SyncBodiesToPhysicsSystem},
+    world::PhysicsWorld,
+};
+
+mod bundle;
+mod character;
+mod components;
+mod debug;
+mod dim2;
+mod events;
+mod generation;
+mod joints;
+mod query;
+mod systems;
+mod world;
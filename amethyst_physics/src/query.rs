@@ -0,0 +1,381 @@
+//! Raycasts, shape casts and overlap queries.
+
+use amethyst_core::{
+    nalgebra::{Isometry3, Point3, Vector3, Vector4},
+    specs::{Component, DenseVecStorage, Entities, Entity, Join, Read, ReadStorage},
+    GlobalTransform,
+};
+use ncollide3d::{
+    query::{self, Proximity, Ray},
+    shape::{Ball, Cuboid, ShapeHandle},
+};
+use serde::{Deserialize, Serialize};
+use shred_derive::SystemData;
+
+use crate::{components::ColliderShape, world::PhysicsWorld};
+
+/// The result of a raycast or shape cast.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RaycastHit {
+    /// The entity that was hit
+    pub entity: Entity,
+    /// The point of the surface that was hit
+    pub point: Point3<f32>,
+    /// The normal of the surface that was hit
+    pub normal: Vector3<f32>,
+    /// The distance travelled along the direction of the cast
+    pub distance: f32,
+}
+
+/// A rough volume of an entity, in the space of its `GlobalTransform`, used by the
+/// `PhysicsQuery` when there is no `PhysicsWorld`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum BoundingVolume {
+    /// A sphere.
+    Sphere {
+        /// Center of the sphere
+        center: Point3<f32>,
+        /// Radius of the sphere
+        radius: f32,
+    },
+    /// An axis aligned box.
+    Aabb {
+        /// The corner with the smallest coordinates
+        min: Point3<f32>,
+        /// The corner with the largest coordinates
+        max: Point3<f32>,
+    },
+}
+
+impl BoundingVolume {
+    /// The shape of the volume in world space, for the given global transform.
+    fn world_shape(&self, global: &GlobalTransform) -> (Isometry3<f32>, ShapeHandle<f32>) {
+        let transform_point = |point: &Point3<f32>| {
+            let point = global.0 * Vector4::new(point.x, point.y, point.z, 1.0);
+            Vector3::new(point.x, point.y, point.z)
+        };
+        match *self {
+            BoundingVolume::Sphere { ref center, radius } => {
+                let m = &global.0;
+                let scale = (0..3)
+                    .map(|i| Vector3::new(m[(0, i)], m[(1, i)], m[(2, i)]).norm())
+                    .fold(0.0, f32::max);
+                (
+                    Isometry3::new(transform_point(center), Vector3::zeros()),
+                    ShapeHandle::new(Ball::new(radius * scale)),
+                )
+            }
+            BoundingVolume::Aabb { ref min, ref max } => {
+                let corners = (0..8).map(|i| {
+                    transform_point(&Point3::new(
+                        if i & 1 == 0 { min.x } else { max.x },
+                        if i & 2 == 0 { min.y } else { max.y },
+                        if i & 4 == 0 { min.z } else { max.z },
+                    ))
+                });
+                let (low, high) = corners.fold(
+                    (
+                        Vector3::repeat(std::f32::MAX),
+                        Vector3::repeat(std::f32::MIN),
+                    ),
+                    |(low, high), corner| (low.inf(&corner), high.sup(&corner)),
+                );
+                (
+                    Isometry3::new((low + high) / 2.0, Vector3::zeros()),
+                    ShapeHandle::new(Cuboid::new((high - low) / 2.0)),
+                )
+            }
+        }
+    }
+}
+
+impl Component for BoundingVolume {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Spatial queries against the `PhysicsWorld`, or against the `BoundingVolume` components when
+/// there is no `PhysicsWorld` resource.
+#[derive(SystemData)]
+pub struct PhysicsQuery<'a> {
+    entities: Entities<'a>,
+    physics: Option<Read<'a, PhysicsWorld>>,
+    globals: ReadStorage<'a, GlobalTransform>,
+    volumes: ReadStorage<'a, BoundingVolume>,
+}
+
+impl<'a> PhysicsQuery<'a> {
+    /// Returns the closest hit of the ray, ignoring hits further than `max_distance`.
+    pub fn raycast(
+        &self,
+        origin: Point3<f32>,
+        direction: Vector3<f32>,
+        max_distance: f32,
+    ) -> Option<RaycastHit> {
+        closest(self.raycast_all(origin, direction, max_distance))
+    }
+
+    /// Returns all hits of the ray closer than `max_distance`, sorted by distance.
+    pub fn raycast_all(
+        &self,
+        origin: Point3<f32>,
+        direction: Vector3<f32>,
+        max_distance: f32,
+    ) -> Vec<RaycastHit> {
+        match self.physics {
+            Some(ref physics) => physics.raycast_all(origin, direction, max_distance),
+            None => cast_ray(self.volume_shapes(), origin, direction, max_distance),
+        }
+    }
+
+    /// Moves a sphere along the direction, and returns the first hit.
+    pub fn sphere_cast(
+        &self,
+        origin: Point3<f32>,
+        radius: f32,
+        direction: Vector3<f32>,
+        max_distance: f32,
+    ) -> Option<RaycastHit> {
+        let position = Isometry3::new(origin.coords, Vector3::zeros());
+        self.shape_cast(
+            &ColliderShape::Ball { radius },
+            position,
+            direction,
+            max_distance,
+        )
+    }
+
+    /// Moves a shape along the direction, and returns the first hit.
+    pub fn shape_cast(
+        &self,
+        shape: &ColliderShape,
+        position: Isometry3<f32>,
+        direction: Vector3<f32>,
+        max_distance: f32,
+    ) -> Option<RaycastHit> {
+        match self.physics {
+            Some(ref physics) => physics.shape_cast(shape, position, direction, max_distance),
+            None => cast_shape(
+                &self.volume_shapes(),
+                &shape.shape_handle(),
+                position,
+                direction,
+                max_distance,
+            ),
+        }
+    }
+
+    /// Returns the entities intersecting the shape.
+    pub fn overlap(&self, shape: &ColliderShape, position: Isometry3<f32>) -> Vec<Entity> {
+        match self.physics {
+            Some(ref physics) => physics.overlap(shape, position),
+            None => overlap(self.volume_shapes(), &shape.shape_handle(), position),
+        }
+    }
+
+    fn volume_shapes(&self) -> Vec<(Entity, Isometry3<f32>, ShapeHandle<f32>)> {
+        (&*self.entities, &self.globals, &self.volumes)
+            .join()
+            .map(|(entity, global, volume)| {
+                let (position, shape) = volume.world_shape(global);
+                (entity, position, shape)
+            })
+            .collect()
+    }
+}
+
+impl PhysicsWorld {
+    /// Returns the closest hit of the ray on the colliders, ignoring hits further than
+    /// `max_distance`.
+    pub fn raycast(
+        &self,
+        origin: Point3<f32>,
+        direction: Vector3<f32>,
+        max_distance: f32,
+    ) -> Option<RaycastHit> {
+        closest(self.raycast_all(origin, direction, max_distance))
+    }
+
+    /// Returns all hits of the ray on the colliders closer than `max_distance`, sorted by
+    /// distance.
+    pub fn raycast_all(
+        &self,
+        origin: Point3<f32>,
+        direction: Vector3<f32>,
+        max_distance: f32,
+    ) -> Vec<RaycastHit> {
+        cast_ray(self.collider_shapes(), origin, direction, max_distance)
+    }
+
+    /// Moves a shape along the direction, and returns the first hit on the colliders.
+    pub fn shape_cast(
+        &self,
+        shape: &ColliderShape,
+        position: Isometry3<f32>,
+        direction: Vector3<f32>,
+        max_distance: f32,
+    ) -> Option<RaycastHit> {
+        cast_shape(
+            &self.collider_shapes(),
+            &shape.shape_handle(),
+            position,
+            direction,
+            max_distance,
+        )
+    }
+
+    /// Returns the entities whose collider intersects the shape.
+    pub fn overlap(&self, shape: &ColliderShape, position: Isometry3<f32>) -> Vec<Entity> {
+        overlap(self.collider_shapes(), &shape.shape_handle(), position)
+    }
+
+    pub(crate) fn collider_shapes(&self) -> Vec<(Entity, Isometry3<f32>, ShapeHandle<f32>)> {
+        self.colliders
+            .iter()
+            .filter_map(|(entity, record)| {
+                let collider = self.world.collider(record.handle)?;
+                Some((*entity, *collider.position(), collider.shape().clone()))
+            })
+            .collect()
+    }
+}
+
+fn closest(hits: Vec<RaycastHit>) -> Option<RaycastHit> {
+    hits.into_iter().next()
+}
+
+fn cast_ray(
+    shapes: Vec<(Entity, Isometry3<f32>, ShapeHandle<f32>)>,
+    origin: Point3<f32>,
+    direction: Vector3<f32>,
+    max_distance: f32,
+) -> Vec<RaycastHit> {
+    let ray = Ray::new(origin, direction.normalize());
+    let mut hits = shapes
+        .into_iter()
+        .filter_map(|(entity, position, shape)| {
+            let hit = shape
+                .as_ray_cast()?
+                .toi_and_normal_with_ray(&position, &ray, true)?;
+            if hit.toi > max_distance {
+                return None;
+            }
+            Some(RaycastHit {
+                entity,
+                point: ray.origin + ray.dir * hit.toi,
+                normal: hit.normal,
+                distance: hit.toi,
+            })
+        })
+        .collect::<Vec<_>>();
+    sort_by_distance(&mut hits);
+    hits
+}
+
+pub(crate) fn cast_shape(
+    shapes: &[(Entity, Isometry3<f32>, ShapeHandle<f32>)],
+    cast: &ShapeHandle<f32>,
+    start: Isometry3<f32>,
+    direction: Vector3<f32>,
+    max_distance: f32,
+) -> Option<RaycastHit> {
+    let direction = direction.normalize();
+    let mut hits = shapes
+        .iter()
+        .filter_map(|(entity, position, shape)| {
+            // With a unit velocity, the time of impact is the distance.
+            let distance = query::time_of_impact(
+                &start,
+                &direction,
+                &**cast,
+                position,
+                &Vector3::zeros(),
+                &**shape,
+            )?;
+            if distance > max_distance {
+                return None;
+            }
+            let mut impact = start;
+            impact.translation.vector += direction * distance;
+            let (point, normal) = match query::contact(&impact, &**cast, position, &**shape, 1.0e-3)
+            {
+                Some(contact) => (contact.world2, -contact.normal.into_inner()),
+                None => (Point3::origin() + impact.translation.vector, -direction),
+            };
+            Some(RaycastHit {
+                entity: *entity,
+                point,
+                normal,
+                distance,
+            })
+        })
+        .collect::<Vec<_>>();
+    sort_by_distance(&mut hits);
+    closest(hits)
+}
+
+fn overlap(
+    shapes: Vec<(Entity, Isometry3<f32>, ShapeHandle<f32>)>,
+    shape: &ShapeHandle<f32>,
+    position: Isometry3<f32>,
+) -> Vec<Entity> {
+    shapes
+        .into_iter()
+        .filter(|(_, other_position, other)| {
+            query::proximity(&position, &**shape, other_position, &**other, 0.0)
+                == Proximity::Intersecting
+        })
+        .map(|(entity, _, _)| entity)
+        .collect()
+}
+
+fn sort_by_distance(hits: &mut Vec<RaycastHit>) {
+    hits.sort_by(|a, b| {
+        a.distance
+            .partial_cmp(&b.distance)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::{
+        approx::assert_relative_eq,
+        specs::{Builder, RunNow, System, World},
+    };
+
+    use super::*;
+
+    struct Raycast(Option<RaycastHit>);
+
+    impl<'a> System<'a> for Raycast {
+        type SystemData = PhysicsQuery<'a>;
+
+        fn run(&mut self, query: Self::SystemData) {
+            self.0 = query.raycast(Point3::origin(), Vector3::new(0.0, 0.0, -1.0), 100.0);
+        }
+    }
+
+    #[test]
+    fn raycast_bounding_volumes() {
+        let mut world = World::new();
+        world.register::<GlobalTransform>();
+        world.register::<BoundingVolume>();
+        let mut global = GlobalTransform::default();
+        global.0[(2, 3)] = -10.0;
+        let entity = world
+            .create_entity()
+            .with(global)
+            .with(BoundingVolume::Sphere {
+                center: Point3::origin(),
+                radius: 1.0,
+            })
+            .build();
+
+        let mut system = Raycast(None);
+        system.run_now(&world.res);
+        let hit = system.0.expect("The sphere should be hit");
+        assert_eq!(hit.entity, entity);
+        assert_relative_eq!(hit.distance, 9.0);
+        assert_relative_eq!(hit.normal, Vector3::new(0.0, 0.0, 1.0));
+    }
+}
@@ -0,0 +1,328 @@
+//! Systems synchronizing the components with the physics world, and stepping it.
+
+use amethyst_core::{
+    nalgebra::{Isometry3, Vector3},
+    shrev::EventChannel,
+    specs::{Entities, Entity, Join, Read, ReadStorage, Resources, System, Write, WriteStorage},
+    Time, Transform,
+};
+use nphysics3d::{math::Velocity, object::BodyHandle};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{
+    components::{BodyStatus, Collider, RigidBody},
+    events::{CollisionEvent, TriggerEvent},
+    world::PhysicsWorld,
+};
+
+/// Creates, updates and removes the bodies and colliders of the `PhysicsWorld` according to the
+/// `RigidBody`, `Collider` and `Transform` components.
+#[derive(Default)]
+pub struct SyncBodiesToPhysicsSystem {
+    scratch: Vec<Entity>,
+}
+
+impl SyncBodiesToPhysicsSystem {
+    /// Creates a new `SyncBodiesToPhysicsSystem`.
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<'a> System<'a> for SyncBodiesToPhysicsSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Transform>,
+        ReadStorage<'a, RigidBody>,
+        ReadStorage<'a, Collider>,
+        Write<'a, PhysicsWorld>,
+    );
+
+    fn run(&mut self, (entities, transforms, bodies, colliders, mut physics): Self::SystemData) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("sync_bodies_to_physics_system");
+
+        self.scratch.clear();
+        self.scratch
+            .extend(physics.bodies.keys().cloned().filter(|e| {
+                !entities.is_alive(*e) || !bodies.contains(*e) || !transforms.contains(*e)
+            }));
+        for entity in self.scratch.drain(..) {
+            physics.remove_body(entity);
+        }
+
+        self.scratch
+            .extend(physics.colliders.keys().cloned().filter(|e| {
+                !entities.is_alive(*e) || !colliders.contains(*e) || !transforms.contains(*e)
+            }));
+        for entity in self.scratch.drain(..) {
+            physics.remove_collider(entity);
+        }
+
+        for (entity, body, transform) in (&*entities, &bodies, &transforms).join() {
+            let position = *transform.isometry();
+            let handle = match physics.bodies.get_mut(&entity) {
+                Some(record) => {
+                    let status_changed = record.status != body.status;
+                    let position_changed = record.position != position;
+                    let velocity_changed = record.linear_velocity != body.linear_velocity
+                        || record.angular_velocity != body.angular_velocity;
+                    record.status = body.status;
+                    record.position = position;
+                    record.linear_velocity = body.linear_velocity;
+                    record.angular_velocity = body.angular_velocity;
+                    if !status_changed && !position_changed && !velocity_changed {
+                        continue;
+                    }
+                    record.handle
+                }
+                None => {
+                    physics.add_body(entity, body, position);
+                    continue;
+                }
+            };
+
+            if let Some(rigid_body) = physics.world.rigid_body_mut(handle) {
+                rigid_body.set_status(body.status.into());
+                rigid_body.set_position(position);
+                rigid_body.set_velocity(Velocity::new(body.linear_velocity, body.angular_velocity));
+                rigid_body.activate();
+            }
+        }
+
+        for (entity, collider, transform) in (&*entities, &colliders, &transforms).join() {
+            // Colliders of entities with a body are placed relatively to the body, the others
+            // are attached to the ground.
+            let (parent, position) = match physics.body_handle(entity) {
+                Some(handle) => (handle, Isometry3::identity()),
+                None => (BodyHandle::ground(), *transform.isometry()),
+            };
+            let up_to_date = physics.colliders.get(&entity).map_or(false, |record| {
+                record.parent == parent
+                    && record.position == position
+                    && record.collider == *collider
+            });
+            if !up_to_date {
+                physics.remove_collider(entity);
+                physics.add_collider(entity, collider, parent, position);
+            }
+        }
+    }
+}
+
+/// Steps the `PhysicsWorld` on the fixed timestep of the `Time` resource, and emits the
+/// `CollisionEvent`s and `TriggerEvent`s.
+///
+/// If the frame took longer than `max_steps` fixed timesteps, the simulation slows down instead
+/// of stepping more.
+pub struct PhysicsStepSystem {
+    gravity: Vector3<f32>,
+    max_steps: usize,
+}
+
+impl PhysicsStepSystem {
+    /// Creates a new `PhysicsStepSystem`, setting the gravity of the world.
+    pub fn new(gravity: Vector3<f32>, max_steps: usize) -> Self {
+        PhysicsStepSystem { gravity, max_steps }
+    }
+}
+
+impl<'a> System<'a> for PhysicsStepSystem {
+    type SystemData = (
+        Read<'a, Time>,
+        Write<'a, PhysicsWorld>,
+        Write<'a, EventChannel<CollisionEvent>>,
+        Write<'a, EventChannel<TriggerEvent>>,
+    );
+
+    fn run(
+        &mut self,
+        (time, mut physics, mut collision_events, mut trigger_events): Self::SystemData,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("physics_step_system");
+
+        let timestep = time.fixed_seconds();
+        physics.world.set_timestep(timestep);
+        physics.accumulator += time.delta_seconds();
+
+        let mut steps = 0;
+        while physics.accumulator >= timestep && steps < self.max_steps {
+            physics.accumulator -= timestep;
+            steps += 1;
+            let (collisions, triggers) = physics.step();
+            collision_events.iter_write(collisions);
+            trigger_events.iter_write(triggers);
+        }
+        if physics.accumulator >= timestep {
+            physics.accumulator %= timestep;
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        use amethyst_core::specs::prelude::SystemData;
+        Self::SystemData::setup(res);
+        res.fetch_mut::<PhysicsWorld>().set_gravity(self.gravity);
+    }
+}
+
+/// Writes the positions and velocities of the bodies of the `PhysicsWorld` to the `Transform`
+/// and `RigidBody` components.
+#[derive(Default)]
+pub struct SyncBodiesFromPhysicsSystem;
+
+impl SyncBodiesFromPhysicsSystem {
+    /// Creates a new `SyncBodiesFromPhysicsSystem`.
+    pub fn new() -> Self {
+        SyncBodiesFromPhysicsSystem
+    }
+}
+
+impl<'a> System<'a> for SyncBodiesFromPhysicsSystem {
+    type SystemData = (
+        Write<'a, PhysicsWorld>,
+        WriteStorage<'a, Transform>,
+        WriteStorage<'a, RigidBody>,
+    );
+
+    fn run(&mut self, (mut physics, mut transforms, mut bodies): Self::SystemData) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("sync_bodies_from_physics_system");
+
+        let PhysicsWorld {
+            ref world,
+            bodies: ref mut records,
+            ..
+        } = *physics;
+        for (entity, record) in records.iter_mut() {
+            if record.status == BodyStatus::Static {
+                continue;
+            }
+            let rigid_body = match world.rigid_body(record.handle) {
+                Some(rigid_body) => rigid_body,
+                None => continue,
+            };
+
+            let position = *rigid_body.position();
+            if record.position != position {
+                record.position = position;
+                if let Some(transform) = transforms.get_mut(*entity) {
+                    *transform.isometry_mut() = position;
+                }
+            }
+
+            let velocity = rigid_body.velocity();
+            if record.linear_velocity != velocity.linear
+                || record.angular_velocity != velocity.angular
+            {
+                record.linear_velocity = velocity.linear;
+                record.angular_velocity = velocity.angular;
+                if let Some(body) = bodies.get_mut(*entity) {
+                    body.linear_velocity = velocity.linear;
+                    body.angular_velocity = velocity.angular;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::{
+        approx::assert_relative_eq,
+        specs::{Builder, Dispatcher, DispatcherBuilder, World},
+    };
+
+    use super::*;
+    use crate::components::ColliderShape;
+
+    fn setup(max_steps: usize) -> (World, Dispatcher<'static, 'static>) {
+        let mut world = World::new();
+        let mut dispatcher = DispatcherBuilder::new()
+            .with(SyncBodiesToPhysicsSystem::new(), "physics_sync_to", &[])
+            .with(
+                PhysicsStepSystem::new(Vector3::new(0.0, -10.0, 0.0), max_steps),
+                "physics_step",
+                &["physics_sync_to"],
+            )
+            .with(
+                SyncBodiesFromPhysicsSystem::new(),
+                "physics_sync_from",
+                &["physics_step"],
+            )
+            .build();
+        dispatcher.setup(&mut world.res);
+        world.write_resource::<Time>().set_fixed_seconds(0.1);
+        (world, dispatcher)
+    }
+
+    fn falling_ball(world: &mut World) -> Entity {
+        world
+            .create_entity()
+            .with(Transform::default())
+            .with(RigidBody::dynamic())
+            .with(Collider::new(ColliderShape::Ball { radius: 0.5 }))
+            .build()
+    }
+
+    #[test]
+    fn dynamic_bodies_fall() {
+        let (mut world, mut dispatcher) = setup(8);
+        let entity = falling_ball(&mut world);
+        world.write_resource::<Time>().set_delta_seconds(0.1);
+        dispatcher.dispatch(&world.res);
+
+        let velocity = world
+            .read_storage::<RigidBody>()
+            .get(entity)
+            .unwrap()
+            .linear_velocity;
+        assert_relative_eq!(velocity, Vector3::new(0.0, -1.0, 0.0), epsilon = 1.0e-4);
+        let transforms = world.read_storage::<Transform>();
+        let translation = transforms.get(entity).unwrap().translation();
+        assert!(translation.y < 0.0);
+        assert_relative_eq!(translation.x, 0.0);
+        assert_relative_eq!(translation.z, 0.0);
+    }
+
+    #[test]
+    fn steps_are_capped() {
+        let (mut world, mut dispatcher) = setup(4);
+        let entity = falling_ball(&mut world);
+        world.write_resource::<Time>().set_delta_seconds(1.0);
+        dispatcher.dispatch(&world.res);
+
+        // Only 4 of the 10 steps are simulated, the rest of the frame is dropped.
+        let velocity = world
+            .read_storage::<RigidBody>()
+            .get(entity)
+            .unwrap()
+            .linear_velocity;
+        assert_relative_eq!(velocity.y, -4.0, epsilon = 1.0e-4);
+        assert!(world.read_resource::<PhysicsWorld>().accumulator < 0.1);
+    }
+
+    #[test]
+    fn removing_the_body_removes_its_records() {
+        let (mut world, mut dispatcher) = setup(8);
+        let entity = falling_ball(&mut world);
+        dispatcher.dispatch(&world.res);
+        let body = world
+            .read_resource::<PhysicsWorld>()
+            .body_handle(entity)
+            .unwrap();
+
+        world.write_storage::<RigidBody>().remove(entity);
+        dispatcher.dispatch(&world.res);
+
+        let physics = world.read_resource::<PhysicsWorld>();
+        assert!(physics.body_handle(entity).is_none());
+        assert!(physics.world().rigid_body(body).is_none());
+        // The collider remains, attached to the ground instead.
+        assert_eq!(physics.colliders.len(), 1);
+        assert_eq!(physics.collider_entities.len(), 1);
+        assert_eq!(physics.colliders[&entity].parent, BodyHandle::ground());
+    }
+}
@@ -0,0 +1,327 @@
+//! The physics world resource.
+
+use amethyst_core::{
+    nalgebra::{Isometry3, Vector3},
+    specs::Entity,
+};
+use fnv::FnvHashMap;
+use ncollide3d::{events::ContactEvent, query::Proximity};
+use nphysics3d::{
+    math::Velocity,
+    object::{BodyHandle, ColliderDesc, ColliderHandle, Material, RigidBodyDesc},
+    world::World,
+};
+
+use crate::{
+    components::{BodyStatus, Collider, RigidBody},
+    events::{CollisionEvent, TriggerEvent},
+    joints::JointRecord,
+};
+
+/// The body of an entity, and its state after the last synchronization.
+pub(crate) struct BodyRecord {
+    pub(crate) handle: BodyHandle,
+    pub(crate) status: BodyStatus,
+    pub(crate) position: Isometry3<f32>,
+    pub(crate) linear_velocity: Vector3<f32>,
+    pub(crate) angular_velocity: Vector3<f32>,
+}
+
+/// The collider of an entity, and the component it was built from.
+pub(crate) struct ColliderRecord {
+    pub(crate) handle: ColliderHandle,
+    pub(crate) parent: BodyHandle,
+    pub(crate) collider: Collider,
+    pub(crate) position: Isometry3<f32>,
+}
+
+/// Resource containing the nphysics world, and the bodies and colliders of the entities.
+///
+/// Bodies and colliders are created, updated and removed by the `SyncBodiesToPhysicsSystem`, the
+/// `RigidBody` and `Collider` components should be used to modify them. Joints are managed the
+/// same way by the `SyncJointsToPhysicsSystem`.
+pub struct PhysicsWorld {
+    pub(crate) world: World<f32>,
+    pub(crate) bodies: FnvHashMap<Entity, BodyRecord>,
+    pub(crate) colliders: FnvHashMap<Entity, ColliderRecord>,
+    pub(crate) collider_entities: FnvHashMap<ColliderHandle, Entity>,
+    pub(crate) joints: FnvHashMap<Entity, JointRecord>,
+    pub(crate) accumulator: f32,
+}
+
+impl Default for PhysicsWorld {
+    fn default() -> Self {
+        PhysicsWorld::new(Vector3::new(0.0, -9.81, 0.0))
+    }
+}
+
+impl PhysicsWorld {
+    /// Creates an empty world with the given gravity.
+    pub fn new(gravity: Vector3<f32>) -> Self {
+        let mut world = World::new();
+        world.set_gravity(gravity);
+        PhysicsWorld {
+            world,
+            bodies: FnvHashMap::default(),
+            colliders: FnvHashMap::default(),
+            collider_entities: FnvHashMap::default(),
+            joints: FnvHashMap::default(),
+            accumulator: 0.0,
+        }
+    }
+
+    /// The gravity applied to dynamic bodies.
+    pub fn gravity(&self) -> &Vector3<f32> {
+        self.world.gravity()
+    }
+
+    /// Sets the gravity applied to dynamic bodies.
+    pub fn set_gravity(&mut self, gravity: Vector3<f32>) {
+        self.world.set_gravity(gravity);
+    }
+
+    /// The nphysics world, for features not exposed through components.
+    pub fn world(&self) -> &World<f32> {
+        &self.world
+    }
+
+    /// The nphysics world, for features not exposed through components.
+    ///
+    /// Bodies and colliders created by the physics systems must not be removed through it.
+    pub fn world_mut(&mut self) -> &mut World<f32> {
+        &mut self.world
+    }
+
+    /// The handle of the body of an entity.
+    pub fn body_handle(&self, entity: Entity) -> Option<BodyHandle> {
+        self.bodies.get(&entity).map(|record| record.handle)
+    }
+
+    /// The handle of the collider of an entity.
+    pub fn collider_handle(&self, entity: Entity) -> Option<ColliderHandle> {
+        self.colliders.get(&entity).map(|record| record.handle)
+    }
+
+    /// The entity a collider belongs to.
+    pub fn collider_entity(&self, handle: ColliderHandle) -> Option<Entity> {
+        self.collider_entities.get(&handle).cloned()
+    }
+
+    /// The position of the collider of an entity.
+    pub fn collider_position(&self, entity: Entity) -> Option<Isometry3<f32>> {
+        let record = self.colliders.get(&entity)?;
+        self.world
+            .collider(record.handle)
+            .map(|collider| *collider.position())
+    }
+
+    pub(crate) fn add_body(&mut self, entity: Entity, body: &RigidBody, position: Isometry3<f32>) {
+        let handle = RigidBodyDesc::new()
+            .position(position)
+            .velocity(Velocity::new(body.linear_velocity, body.angular_velocity))
+            .status(body.status.into())
+            .build(&mut self.world)
+            .handle();
+        self.bodies.insert(
+            entity,
+            BodyRecord {
+                handle,
+                status: body.status,
+                position,
+                linear_velocity: body.linear_velocity,
+                angular_velocity: body.angular_velocity,
+            },
+        );
+    }
+
+    pub(crate) fn remove_body(&mut self, entity: Entity) {
+        if let Some(record) = self.bodies.remove(&entity) {
+            self.remove_joints_of(record.handle);
+            // Removing a body removes the colliders attached to it.
+            if self
+                .colliders
+                .get(&entity)
+                .map_or(false, |collider| collider.parent == record.handle)
+            {
+                let collider = self.colliders.remove(&entity).expect("Unreachable");
+                self.collider_entities.remove(&collider.handle);
+            }
+            self.world.remove_bodies(&[record.handle]);
+        }
+    }
+
+    /// Adds the collider, attached to the given body or to the ground. The position is relative
+    /// to the parent.
+    pub(crate) fn add_collider(
+        &mut self,
+        entity: Entity,
+        collider: &Collider,
+        parent: BodyHandle,
+        position: Isometry3<f32>,
+    ) {
+        let handle = ColliderDesc::new(collider.shape.shape_handle())
+            .position(position * collider.offset)
+            .density(collider.density)
+            .material(Material::new(collider.restitution, collider.friction))
+            .sensor(collider.sensor)
+            .build_with_parent(parent, &mut self.world)
+            .expect("Unreachable: The parent of the collider exists")
+            .handle();
+        self.collider_entities.insert(handle, entity);
+        self.colliders.insert(
+            entity,
+            ColliderRecord {
+                handle,
+                parent,
+                collider: collider.clone(),
+                position,
+            },
+        );
+    }
+
+    pub(crate) fn remove_collider(&mut self, entity: Entity) {
+        if let Some(record) = self.colliders.remove(&entity) {
+            self.collider_entities.remove(&record.handle);
+            self.world.remove_colliders(&[record.handle]);
+        }
+    }
+
+    /// Steps the world once, and returns the collisions that started or stopped and the colliders
+    /// that entered or exited sensors.
+    pub(crate) fn step(&mut self) -> (Vec<CollisionEvent>, Vec<TriggerEvent>) {
+        self.apply_springs();
+        self.world.step();
+        self.remove_broken_constraints();
+
+        let entities = &self.collider_entities;
+        let collisions = self
+            .world
+            .contact_events()
+            .iter()
+            .filter_map(|event| match *event {
+                ContactEvent::Started(first, second) => Some(CollisionEvent::Started(
+                    *entities.get(&first)?,
+                    *entities.get(&second)?,
+                )),
+                ContactEvent::Stopped(first, second) => Some(CollisionEvent::Stopped(
+                    *entities.get(&first)?,
+                    *entities.get(&second)?,
+                )),
+            })
+            .collect();
+
+        let mut triggers = Vec::new();
+        for event in self.world.proximity_events() {
+            let (first, second) = match (
+                entities.get(&event.collider1),
+                entities.get(&event.collider2),
+            ) {
+                (Some(first), Some(second)) => (*first, *second),
+                _ => continue,
+            };
+            let was_inside = event.prev_status == Proximity::Intersecting;
+            let is_inside = event.new_status == Proximity::Intersecting;
+            // When both colliders are sensors, each of them is triggered by the other.
+            for &(trigger, other) in &[(first, second), (second, first)] {
+                if !self.is_sensor(trigger) {
+                    continue;
+                }
+                match (was_inside, is_inside) {
+                    (false, true) => triggers.push(TriggerEvent::TriggerEntered { trigger, other }),
+                    (true, false) => triggers.push(TriggerEvent::TriggerExited { trigger, other }),
+                    _ => {}
+                }
+            }
+        }
+
+        (collisions, triggers)
+    }
+
+    pub(crate) fn is_sensor(&self, entity: Entity) -> bool {
+        self.colliders
+            .get(&entity)
+            .map_or(false, |record| record.collider.sensor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::specs::{Builder, World};
+
+    use super::*;
+    use crate::components::ColliderShape;
+
+    fn ball(radius: f32) -> Collider {
+        Collider::new(ColliderShape::Ball { radius })
+    }
+
+    /// Moves a ball through a static sensor, and returns the trigger events.
+    fn pass_through_sensor(
+        sensor: Entity,
+        ball_entity: Entity,
+        ball: Collider,
+    ) -> Vec<TriggerEvent> {
+        let mut physics = PhysicsWorld::new(Vector3::zeros());
+        physics.add_collider(
+            sensor,
+            &ball(1.0).with_sensor(),
+            BodyHandle::ground(),
+            Isometry3::identity(),
+        );
+        let mut body = RigidBody::dynamic();
+        body.linear_velocity = Vector3::new(0.0, -10.0, 0.0);
+        physics.add_body(other, &body, Isometry3::translation(0.0, 3.0, 0.0));
+        let handle = physics.body_handle(other).unwrap();
+        physics.add_collider(other, &collider, handle, Isometry3::identity());
+
+        let mut triggers = Vec::new();
+        for _ in 0..60 {
+            let (_, step_triggers) = physics.step();
+            triggers.extend(step_triggers);
+        }
+        triggers
+    }
+
+    #[test]
+    fn sensors_emit_trigger_events() {
+        let mut world = World::new();
+        let sensor = world.create_entity().build();
+        let other = world.create_entity().build();
+
+        let triggers = pass_through_sensor(sensor, other, ball(0.5));
+        assert_eq!(
+            triggers,
+            vec![
+                TriggerEvent::TriggerEntered {
+                    trigger: sensor,
+                    other,
+                },
+                TriggerEvent::TriggerExited {
+                    trigger: sensor,
+                    other,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn both_sensors_are_triggered() {
+        let mut world = World::new();
+        let sensor = world.create_entity().build();
+        let other = world.create_entity().build();
+
+        let triggers = pass_through_sensor(sensor, other, ball(0.5).with_sensor());
+        assert_eq!(triggers.len(), 4);
+        for &(trigger, other) in &[(sensor, other), (other, sensor)] {
+            let entered = triggers
+                .iter()
+                .position(|event| *event == TriggerEvent::TriggerEntered { trigger, other })
+                .expect("Both sensors should be entered");
+            let exited = triggers
+                .iter()
+                .position(|event| *event == TriggerEvent::TriggerExited { trigger, other })
+                .expect("Both sensors should be exited");
+            assert!(entered < exited);
+        }
+    }
+}
@@ -0,0 +1,341 @@
+//! Packing of images into a texture atlas at runtime.
+
+use image::{GenericImage, RgbaImage};
+
+use amethyst_assets::{AssetStorage, Handle, Loader};
+use amethyst_error::{format_err, Error};
+
+use crate::{ImageData, Sprite, SpriteSheet, Texture, TextureData, TextureMetadata};
+
+/// Packed atlas image and the sprites of the packed images, in the order they were added.
+#[derive(Clone, Debug)]
+pub struct TextureAtlas {
+    /// The atlas image
+    pub image: ImageData,
+    /// The sprites of the packed images
+    pub sprites: Vec<Sprite>,
+}
+
+/// Packs images into a single atlas texture and creates its `SpriteSheet`.
+///
+/// The images must be available on the CPU, as returned by the `from_data` functions of the
+/// image formats such as `PngFormat`: the data of loaded `Texture`s only lives on the GPU. The
+/// sprite of an image has the index returned when adding it, and its name if it has one.
+///
+/// Sprites drawn scaled with linear filtering or mipmaps blend in the pixels around them in the
+/// atlas. Extruding the images repeats their edge pixels around them, so the blended pixels have
+/// the colors of the sprites.
+///
+/// Atlases drawn zoomed out alias unless they have mip levels, generated from metadata such as
+/// `TextureMetadata::srgb().with_generated_mips(true)` set with `with_metadata`.
+///
+/// ```rust,no_run
+/// # use amethyst_assets::{AssetStorage, Loader};
+/// # use amethyst_renderer::{
+/// #     PngFormat, SpriteSheet, Texture, TextureAtlasBuilder, TextureMetadata,
+/// # };
+/// # fn load(bytes: &[u8], loader: &Loader, textures: &AssetStorage<Texture>,
+/// #     sheets: &AssetStorage<SpriteSheet>) -> Result<(), amethyst_error::Error> {
+/// let mut builder = TextureAtlasBuilder::new().with_padding(1).with_extrusion(1);
+/// builder.add_texture_data("player", PngFormat::from_data(bytes, TextureMetadata::srgb())?)?;
+/// let sprite_sheet = builder.build(loader, textures, sheets)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct TextureAtlasBuilder {
+    images: Vec<(Option<String>, RgbaImage)>,
+    padding: u32,
+    extrusion: u32,
+    max_size: u32,
+    metadata: TextureMetadata,
+}
+
+impl Default for TextureAtlasBuilder {
+    fn default() -> Self {
+        TextureAtlasBuilder {
+            images: Vec::new(),
+            padding: 0,
+            extrusion: 0,
+            max_size: 4096,
+            metadata: TextureMetadata::srgb_scale(),
+        }
+    }
+}
+
+impl TextureAtlasBuilder {
+    /// Creates a builder without padding, making atlases of at most 4096 by 4096 pixels.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the number of transparent pixels between the packed images.
+    pub fn with_padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Sets the number of times the edge pixels of the images are repeated around them, outside
+    /// of their sprites.
+    pub fn with_extrusion(mut self, extrusion: u32) -> Self {
+        self.extrusion = extrusion;
+        self
+    }
+
+    /// Sets the maximum width and height of the atlas.
+    pub fn with_max_size(mut self, max_size: u32) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Sets the metadata of the atlas texture, nearest filtering in the sRGB color space by
+    /// default.
+    pub fn with_metadata(mut self, metadata: TextureMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Adds an image, and returns the index of its sprite.
+    pub fn add_image(&mut self, image: ImageData) -> usize {
+        self.images.push((None, image.rgba));
+        self.images.len() - 1
+    }
+
+    /// Adds a named image, and returns the index of its sprite.
+    pub fn add_named_image<N: Into<String>>(&mut self, name: N, image: ImageData) -> usize {
+        self.images.push((Some(name.into()), image.rgba));
+        self.images.len() - 1
+    }
+
+    /// Adds the image of texture data returned by an image format, and returns the index of its
+    /// sprite.
+    ///
+    /// Fails if the texture data is not an image.
+    pub fn add_texture_data<N: Into<String>>(
+        &mut self,
+        name: N,
+        data: TextureData,
+    ) -> Result<usize, Error> {
+        match data {
+            TextureData::Image(image, _) => Ok(self.add_named_image(name, image)),
+            _ => Err(format_err!(
+                "Only image texture data can be packed in an atlas"
+            )),
+        }
+    }
+
+    /// Packs the images into an atlas image.
+    ///
+    /// The images are packed in rows from the tallest to the shortest. The atlas has power of two
+    /// dimensions and is at most as tall as wide. Fails if the images don't fit in the maximum
+    /// size.
+    pub fn pack(self) -> Result<TextureAtlas, Error> {
+        let padding = self.padding;
+        let mut order: Vec<usize> = (0..self.images.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(self.images[i].1.height()));
+
+        let area: u32 = self
+            .images
+            .iter()
+            .map(|(_, image)| {
+                let (width, height) = self.extruded_size(image);
+                (width + padding) * (height + padding)
+            })
+            .sum();
+        let widest = self
+            .images
+            .iter()
+            .map(|(_, image)| self.extruded_size(image).0)
+            .max()
+            .unwrap_or(1);
+        let mut width = (f64::from(area).sqrt() as u32)
+            .max(widest)
+            .max(1)
+            .next_power_of_two();
+
+        let (positions, height) = loop {
+            if width > self.max_size {
+                return Err(format_err!(
+                    "The images don't fit in an atlas of {} by {} pixels",
+                    self.max_size,
+                    self.max_size
+                ));
+            }
+            let (positions, used_height) = self.pack_rows(&order, width);
+            let height = used_height.max(1).next_power_of_two();
+            if height <= width {
+                break (positions, height);
+            }
+            width *= 2;
+        };
+
+        let extrusion = self.extrusion;
+        let mut atlas = RgbaImage::new(width, height);
+        let mut sprites = Vec::with_capacity(self.images.len());
+        for ((name, image), (x, y)) in self.images.into_iter().zip(positions) {
+            let (x, y) = (x + extrusion, y + extrusion);
+            atlas.copy_from(&image, x, y);
+            if extrusion > 0 {
+                extrude(&mut atlas, &image, x, y, extrusion);
+            }
+            let mut sprite = Sprite::from_pixel_values(
+                width,
+                height,
+                image.width(),
+                image.height(),
+                x,
+                y,
+                [0.0; 2],
+            );
+            sprite.name = name;
+            sprites.push(sprite);
+        }
+        Ok(TextureAtlas {
+            image: ImageData { rgba: atlas },
+            sprites,
+        })
+    }
+
+    /// Packs the images and loads the atlas texture and its sprite sheet.
+    pub fn build(
+        self,
+        loader: &Loader,
+        textures: &AssetStorage<Texture>,
+        sprite_sheets: &AssetStorage<SpriteSheet>,
+    ) -> Result<Handle<SpriteSheet>, Error> {
+        let metadata = self.metadata.clone();
+        let atlas = self.pack()?;
+        let texture_data = TextureData::Image(atlas.image, metadata);
+        let texture = loader.load_from_data(texture_data, (), textures);
+        let sprite_sheet = SpriteSheet {
+            texture,
+            sprites: atlas.sprites,
+            pages: Vec::new(),
+            normal_maps: Vec::new(),
+            sampler: None,
+        };
+        Ok(loader.load_from_data(sprite_sheet, (), sprite_sheets))
+    }
+
+    /// Returns the positions of the images in rows of the given width, and the height of the
+    /// rows.
+    fn pack_rows(&self, order: &[usize], width: u32) -> (Vec<(u32, u32)>, u32) {
+        let mut positions = vec![(0, 0); self.images.len()];
+        let (mut x, mut y, mut row_height) = (0, 0, 0);
+        for &i in order {
+            let (image_width, image_height) = self.extruded_size(&self.images[i].1);
+            if x > 0 && x + image_width > width {
+                x = 0;
+                y += row_height + self.padding;
+                row_height = 0;
+            }
+            positions[i] = (x, y);
+            x += image_width + self.padding;
+            row_height = row_height.max(image_height);
+        }
+        (positions, y + row_height)
+    }
+
+    /// Returns the size of an image with its extruded edges.
+    fn extruded_size(&self, image: &RgbaImage) -> (u32, u32) {
+        let extruded = |size: u32| {
+            if size > 0 {
+                size + 2 * self.extrusion
+            } else {
+                0
+            }
+        };
+        (extruded(image.width()), extruded(image.height()))
+    }
+}
+
+/// Repeats the edge pixels of an image copied at `(x, y)` in the atlas, `extrusion` times around
+/// it.
+fn extrude(atlas: &mut RgbaImage, image: &RgbaImage, x: u32, y: u32, extrusion: u32) {
+    let (width, height) = (image.width() as i64, image.height() as i64);
+    if width == 0 || height == 0 {
+        return;
+    }
+    let extrusion = i64::from(extrusion);
+    for dy in -extrusion..height + extrusion {
+        for dx in -extrusion..width + extrusion {
+            if dx >= 0 && dx < width && dy >= 0 && dy < height {
+                continue;
+            }
+            let source = *image.get_pixel(
+                dx.max(0).min(width - 1) as u32,
+                dy.max(0).min(height - 1) as u32,
+            );
+            atlas.put_pixel(
+                (i64::from(x) + dx) as u32,
+                (i64::from(y) + dy) as u32,
+                source,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::*;
+
+    fn image(width: u32, height: u32, value: u8) -> ImageData {
+        ImageData {
+            rgba: RgbaImage::from_pixel(width, height, Rgba([value; 4])),
+        }
+    }
+
+    #[test]
+    fn images_are_packed_without_overlap() {
+        let mut builder = TextureAtlasBuilder::new().with_padding(1);
+        builder.add_image(image(16, 8, 1));
+        builder.add_named_image("tall", image(4, 16, 2));
+        builder.add_image(image(8, 8, 3));
+        let atlas = builder.pack().unwrap();
+
+        let rgba = &atlas.image.rgba;
+        assert!(rgba.width().is_power_of_two() && rgba.height().is_power_of_two());
+        assert_eq!(atlas.sprites[1].name, Some("tall".to_string()));
+        // Each image keeps its pixels, so none was overwritten by another.
+        let mut counts = [0; 4];
+        for pixel in rgba.pixels() {
+            counts[pixel.data[0] as usize] += 1;
+        }
+        assert_eq!(&counts[1..], &[16 * 8, 4 * 16, 8 * 8]);
+    }
+
+    #[test]
+    fn edges_are_extruded_around_the_sprites() {
+        let mut builder = TextureAtlasBuilder::new().with_extrusion(2);
+        builder.add_image(ImageData {
+            rgba: RgbaImage::from_fn(2, 1, |x, _| Rgba([x as u8 + 1; 4])),
+        });
+        let atlas = builder.pack().unwrap();
+
+        let rgba = &atlas.image.rgba;
+        let row = |y| {
+            (0..6)
+                .map(|x| rgba.get_pixel(x, y).data[0])
+                .collect::<Vec<_>>()
+        };
+        for y in 0..5 {
+            assert_eq!(row(y), vec![1, 1, 1, 2, 2, 2]);
+        }
+        let coords = &atlas.sprites[0].tex_coords;
+        let atlas_width = rgba.width() as f32;
+        assert_eq!(
+            (coords.left, coords.right),
+            (2.0 / atlas_width, 4.0 / atlas_width)
+        );
+    }
+
+    #[test]
+    fn too_large_images_fail() {
+        let mut builder = TextureAtlasBuilder::new().with_max_size(16);
+        builder.add_image(image(16, 16, 1));
+        builder.add_image(image(1, 1, 1));
+        assert!(builder.pack().is_err());
+    }
+}
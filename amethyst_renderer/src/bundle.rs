@@ -59,7 +59,8 @@ where
         self
     }
 
-    /// Enable transparent sprite sorting, with the given dependencies
+    /// Enable transparent sprite sorting and culling of off-screen sprites, with the given
+    /// dependencies
     pub fn with_sprite_visibility_sorting(mut self, dep: &'a [&'a str]) -> Self {
         self.sprite_visibility_sorting = Some(dep);
         self
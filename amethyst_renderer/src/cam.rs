@@ -65,6 +65,22 @@ impl Camera {
         Self::from(Projection::orthographic(-1., 1., -1., 1.))
     }
 
+    /// Create a camera for 2D measuring the world in pixels.
+    ///
+    /// Will use an orthographic projection of `width` by `height` units centered on the camera,
+    /// so sprites are drawn at their size in pixels in a window of the same size. Add a
+    /// `CameraOrtho` to the camera entity to keep it in sync with the window size.
+    /// View transformation will be multiplicative identity.
+    pub fn pixel_2d(width: f32, height: f32) -> Self {
+        let (half_width, half_height) = (width / 2.0, height / 2.0);
+        Self::from(Projection::orthographic(
+            -half_width,
+            half_width,
+            -half_height,
+            half_height,
+        ))
+    }
+
     /// Create a standard camera for 3D.
     ///
     /// Will use a perspective projection with aspect from the given screen dimensions and a field
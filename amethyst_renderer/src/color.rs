@@ -1,6 +1,9 @@
 //! Color value types.
 
-use amethyst_core::specs::{Component, DenseVecStorage};
+use amethyst_assets::{PrefabData, ProgressCounter};
+use amethyst_core::specs::{Component, DenseVecStorage, Entity, WriteStorage};
+use amethyst_derive::PrefabData;
+use amethyst_error::Error;
 
 use gfx::shade::{Formatted, ToUniform};
 use gfx_core::shade::{BaseType, ContainerType, UniformValue};
@@ -15,10 +18,16 @@ use serde::{Deserialize, Serialize};
 /// transparency if the rendering pass would normally be capable of rendering that entity
 /// transparently.
 ///
+/// The `DrawFlat2D` pass multiplies the color of sprites and images with it, which tints them or
+/// fades them out. The `SpriteVisibilitySortingSystem` draws the sprites with an alpha lower than
+/// `1.0` after the opaque ones, like `Transparent` sprites, so they blend with the sprites behind
+/// them.
+///
 /// ## More than a Component
 /// This structure has more uses than just as a component, and you'll find it in other places
 /// throughout the `amethyst_renderer` API.
-#[derive(Clone, Copy, Debug, Deserialize, PartialEq, PartialOrd, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, PartialOrd, Serialize, PrefabData)]
+#[prefab(Component)]
 pub struct Rgba(pub f32, pub f32, pub f32, pub f32);
 
 impl Rgba {
@@ -0,0 +1,98 @@
+//! Color grading of the rendered scene with a lookup table.
+
+use amethyst_assets::Handle;
+
+use crate::tex::Texture;
+
+/// Resource configuring the color grading applied by the `DrawColorGrading` pass.
+///
+/// The lookup table is a strip of `N` slices of `N` by `N` texels side by side, such as a
+/// 256 by 16 PNG for `N` = 16. Red grows to the right of each slice, green grows downwards, and
+/// blue grows from one slice to the next. It should be loaded with `TextureMetadata::unorm()`, so
+/// its colors are not converted from sRGB.
+///
+/// Mood and day/night shifts are done by swapping the lookup table, or by changing the intensity
+/// over time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorGrading {
+    /// The lookup table, the scene is drawn as is while it is `None` or not loaded.
+    pub lut: Option<Handle<Texture>>,
+    /// Whether the color grading is applied.
+    pub enabled: bool,
+    /// Mix between the original colors at 0 and the graded colors at 1.
+    pub intensity: f32,
+}
+
+impl Default for ColorGrading {
+    fn default() -> Self {
+        ColorGrading {
+            lut: None,
+            enabled: true,
+            intensity: 1.0,
+        }
+    }
+}
+
+impl ColorGrading {
+    /// Creates a color grading with the lookup table at full intensity.
+    pub fn new(lut: Handle<Texture>) -> Self {
+        ColorGrading {
+            lut: Some(lut),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the intensity.
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// Enables or disables the color grading, returning whether it is now enabled.
+    pub fn toggle(&mut self) -> bool {
+        self.enabled = !self.enabled;
+        self.enabled
+    }
+
+    /// Intensity with which the lookup table is applied, 0 when disabled or without lookup table.
+    pub fn effective_intensity(&self) -> f32 {
+        if self.enabled && self.lut.is_some() {
+            self.intensity.max(0.0).min(1.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+
+    use amethyst_assets::{AssetStorage, Loader};
+    use rayon::ThreadPoolBuilder;
+
+    use crate::formats::TextureData;
+
+    #[test]
+    fn intensity_without_lut_or_disabled() {
+        let mut grading = ColorGrading::default().with_intensity(0.5);
+        assert_eq!(grading.effective_intensity(), 0.0);
+
+        let pool = ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        let loader = Loader::new(".", Arc::new(pool));
+        grading.lut = Some(loader.load_from_data(
+            TextureData::color([1.0; 4]),
+            (),
+            &AssetStorage::<Texture>::new(),
+        ));
+        assert_eq!(grading.effective_intensity(), 0.5);
+        assert!(!grading.toggle());
+        assert_eq!(grading.effective_intensity(), 0.0);
+        assert!(grading.toggle());
+
+        grading.intensity = 2.0;
+        assert_eq!(grading.effective_intensity(), 1.0);
+    }
+}
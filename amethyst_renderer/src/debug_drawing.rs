@@ -1,9 +1,13 @@
 use amethyst_core::{
     nalgebra::{Point3, Vector3},
     specs::{Component, DenseVecStorage},
+    GlobalTransform,
 };
 
-use crate::{color::Rgba, vertex::PosColorNorm};
+use crate::{
+    color::Rgba,
+    vertex::{PosColor, PosColorNorm},
+};
 
 /// Debug lines are stored as a position, a direction and a color.
 ///
@@ -11,6 +15,39 @@ use crate::{color::Rgba, vertex::PosColorNorm};
 /// but is similar to other 'VertexFormat's.
 pub type DebugLine = PosColorNorm;
 
+/// Lines of a grid of `cells[0]` by `cells[1]` cells, starting at `origin` and spanned by the
+/// sides of a cell.
+fn grid_lines(
+    origin: Point3<f32>,
+    side_x: Vector3<f32>,
+    side_y: Vector3<f32>,
+    cells: [u32; 2],
+    color: Rgba,
+) -> impl Iterator<Item = DebugLine> {
+    let (width, height) = (side_x * cells[0] as f32, side_y * cells[1] as f32);
+    let columns = (0..=cells[0]).map(move |x| (origin + side_x * x as f32, height));
+    let rows = (0..=cells[1]).map(move |y| (origin + side_y * y as f32, width));
+    columns
+        .chain(rows)
+        .map(move |(position, direction)| DebugLine {
+            position: position.coords,
+            color: color.into(),
+            normal: direction,
+        })
+}
+
+/// Lines of the X, Y and Z axes of a transform, in red, green and blue.
+fn axes_lines(transform: &GlobalTransform, length: f32) -> impl Iterator<Item = DebugLine> {
+    let matrix = transform.0;
+    let colors = vec![Rgba::RED, Rgba::GREEN, Rgba::BLUE];
+    let axes = (0..3).map(move |i| matrix.column(i).xyz().normalize() * length);
+    axes.zip(colors).map(move |(axis, color)| DebugLine {
+        position: matrix.column(3).xyz(),
+        color: color.into(),
+        normal: axis,
+    })
+}
+
 /// Component that stores persistent debug lines to be rendered in DebugLinesPass draw pass.
 /// The vector can only be cleared manually.
 #[derive(Debug, Default)]
@@ -59,6 +96,25 @@ impl DebugLinesComponent {
         self.lines.push(vertex);
     }
 
+    /// Adds the lines of a grid of `cells[0]` by `cells[1]` cells, starting at `origin` and spanned
+    /// by the sides of a cell.
+    pub fn add_grid(
+        &mut self,
+        origin: Point3<f32>,
+        side_x: Vector3<f32>,
+        side_y: Vector3<f32>,
+        cells: [u32; 2],
+        color: Rgba,
+    ) {
+        self.lines
+            .extend(grid_lines(origin, side_x, side_y, cells, color));
+    }
+
+    /// Adds the X, Y and Z axes of a transform with the given length, in red, green and blue.
+    pub fn add_axes(&mut self, transform: &GlobalTransform, length: f32) {
+        self.lines.extend(axes_lines(transform, length));
+    }
+
     /// Clears lines buffer.
     ///
     /// As lines are persistent, it's necessary to use this function for updating or deleting lines.
@@ -68,11 +124,13 @@ impl DebugLinesComponent {
 }
 
 /// Resource that stores non-persistent debug lines to be rendered in DebugLinesPass draw pass.
-/// The vector is automatically cleared after being rendered.
+/// The vectors are automatically cleared after being rendered.
 #[derive(Debug, Default)]
 pub struct DebugLines {
     /// Lines to be rendered
     pub lines: Vec<DebugLine>,
+    /// Lines to be rendered over the scene, in pixels from the top-left corner of the screen
+    pub screen_lines: Vec<DebugLine>,
 }
 
 impl DebugLines {
@@ -80,6 +138,7 @@ impl DebugLines {
     pub fn new() -> DebugLines {
         DebugLines {
             lines: Vec::<DebugLine>::new(),
+            screen_lines: Vec::<DebugLine>::new(),
         }
     }
 
@@ -110,4 +169,166 @@ impl DebugLines {
 
         self.lines.push(vertex);
     }
+
+    /// Submits the lines of a grid of `cells[0]` by `cells[1]` cells, starting at `origin` and
+    /// spanned by the sides of a cell.
+    pub fn draw_grid(
+        &mut self,
+        origin: Point3<f32>,
+        side_x: Vector3<f32>,
+        side_y: Vector3<f32>,
+        cells: [u32; 2],
+        color: Rgba,
+    ) {
+        self.lines
+            .extend(grid_lines(origin, side_x, side_y, cells, color));
+    }
+
+    /// Submits the X, Y and Z axes of a transform with the given length, in red, green and blue.
+    pub fn draw_axes(&mut self, transform: &GlobalTransform, length: f32) {
+        self.lines.extend(axes_lines(transform, length));
+    }
+
+    /// Submits a line to be rendered over the scene, in pixels from the top-left corner of the
+    /// screen.
+    pub fn draw_screen_line(&mut self, start: [f32; 2], end: [f32; 2], color: Rgba) {
+        self.screen_lines.push(DebugLine {
+            position: Vector3::new(start[0], start[1], 0.0),
+            color: color.into(),
+            normal: Vector3::new(end[0] - start[0], end[1] - start[1], 0.0),
+        });
+    }
+}
+
+/// Resource collecting the 2D shapes drawn by the `DrawDebug2D` pass, for collision shapes and
+/// paths.
+///
+/// The shapes are submitted every frame, in world coordinates on the plane at Z `0`, and cleared
+/// after being drawn.
+#[derive(Debug)]
+pub struct DebugDraw2D {
+    /// Ends of the drawn line segments
+    pub(crate) vertices: Vec<PosColor>,
+    /// Number of segments of the circles
+    pub circle_segments: usize,
+}
+
+impl Default for DebugDraw2D {
+    fn default() -> Self {
+        DebugDraw2D {
+            vertices: Vec::new(),
+            circle_segments: 32,
+        }
+    }
+}
+
+impl DebugDraw2D {
+    /// Creates a new `DebugDraw2D` without shapes.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Submits a line from `start` to `end`.
+    pub fn line(&mut self, start: [f32; 2], end: [f32; 2], color: Rgba) {
+        for point in &[start, end] {
+            self.vertices.push(PosColor {
+                position: Vector3::new(point[0], point[1], 0.0),
+                color: color.into(),
+            });
+        }
+    }
+
+    /// Submits the lines joining the points, without closing the path.
+    pub fn path(&mut self, points: &[[f32; 2]], color: Rgba) {
+        for pair in points.windows(2) {
+            self.line(pair[0], pair[1], color);
+        }
+    }
+
+    /// Submits the outline of a polygon, closing it from its last point to its first one.
+    pub fn polygon(&mut self, points: &[[f32; 2]], color: Rgba) {
+        self.path(points, color);
+        if points.len() > 2 {
+            self.line(points[points.len() - 1], points[0], color);
+        }
+    }
+
+    /// Submits the outline of an axis-aligned box from its minimal to its maximal corner.
+    pub fn aabb(&mut self, min: [f32; 2], max: [f32; 2], color: Rgba) {
+        let corners = [min, [max[0], min[1]], max, [min[0], max[1]]];
+        self.polygon(&corners, color);
+    }
+
+    /// Submits the outline of a circle, made of `circle_segments` lines.
+    pub fn circle(&mut self, center: [f32; 2], radius: f32, color: Rgba) {
+        let segments = self.circle_segments.max(3);
+        let points = (0..segments)
+            .map(|i| {
+                let angle = i as f32 / segments as f32 * 2.0 * std::f32::consts::PI;
+                [
+                    center[0] + radius * angle.cos(),
+                    center[1] + radius * angle.sin(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        self.polygon(&points, color);
+    }
+
+    /// Removes the submitted shapes.
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use amethyst_core::Transform;
+
+    #[test]
+    fn shapes_are_drawn_with_lines() {
+        let mut draw = DebugDraw2D::new();
+        draw.aabb([0.0, 0.0], [2.0, 1.0], Rgba::RED);
+        assert_eq!(draw.vertices.len(), 8);
+        assert_eq!(draw.vertices[3].position, Vector3::new(2.0, 1.0, 0.0));
+        assert_eq!(draw.vertices[7].position, Vector3::new(0.0, 0.0, 0.0));
+
+        draw.clear();
+        draw.path(&[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0]], Rgba::WHITE);
+        assert_eq!(draw.vertices.len(), 4);
+
+        draw.clear();
+        draw.circle_segments = 4;
+        draw.circle([1.0, 1.0], 2.0, Rgba::WHITE);
+        assert_eq!(draw.vertices.len(), 8);
+        assert!((draw.vertices[1].position - Vector3::new(1.0, 3.0, 0.0)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn grids_and_axes_are_drawn_with_lines() {
+        let mut lines = DebugLines::new();
+        lines.draw_grid(
+            Point3::new(0.0, 0.0, 0.0),
+            Vector3::new(2.0, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.0),
+            [3, 2],
+            Rgba::WHITE,
+        );
+        assert_eq!(lines.lines.len(), 4 + 3);
+        assert_eq!(lines.lines[3].position, Vector3::new(6.0, 0.0, 0.0));
+        assert_eq!(lines.lines[3].normal, Vector3::new(0.0, 0.0, 2.0));
+        assert_eq!(lines.lines[6].position, Vector3::new(0.0, 0.0, 2.0));
+        assert_eq!(lines.lines[6].normal, Vector3::new(6.0, 0.0, 0.0));
+
+        lines.lines.clear();
+        let mut transform = Transform::default();
+        transform.set_xyz(1.0, 2.0, 3.0);
+        transform.set_scale(4.0, 4.0, 4.0);
+        lines.draw_axes(&GlobalTransform(transform.matrix()), 0.5);
+        assert_eq!(lines.lines.len(), 3);
+        assert_eq!(lines.lines[1].position, Vector3::new(1.0, 2.0, 3.0));
+        assert_eq!(lines.lines[1].normal, Vector3::new(0.0, 0.5, 0.0));
+        assert_eq!(lines.lines[2].color, <[f32; 4]>::from(Rgba::BLUE));
+    }
 }
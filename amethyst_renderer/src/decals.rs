@@ -0,0 +1,261 @@
+//! Decals baked into render targets, for marks left on the ground.
+
+use amethyst_core::nalgebra::{Vector2, Vector3};
+
+use crate::{
+    color::Rgba,
+    sprite::{Sprite, SpriteRender},
+    vertex::PosColorTex,
+};
+
+/// Sprite stamped into the decal layers of the `Decals` resource.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Decal {
+    /// Sprite of the decal
+    pub sprite: SpriteRender,
+    /// Position of the decal in world coordinates
+    pub position: [f32; 2],
+    /// Rotation of the decal around its position, in radians
+    pub rotation: f32,
+    /// Scale of the decal
+    pub scale: f32,
+    /// Color the sprite is tinted with
+    pub tint: Rgba,
+}
+
+impl Decal {
+    /// Creates a decal of the sprite at the given position, without rotation, scale or tint.
+    pub fn new(sprite: SpriteRender, position: [f32; 2]) -> Self {
+        Decal {
+            sprite,
+            position,
+            rotation: 0.0,
+            scale: 1.0,
+            tint: Rgba::WHITE,
+        }
+    }
+
+    /// Sets the rotation of the decal around its position, in radians.
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Sets the scale of the decal.
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Sets the color the sprite is tinted with.
+    pub fn with_tint(mut self, tint: Rgba) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    /// Returns the two triangles of the decal, drawing the given sprite.
+    pub(crate) fn vertices(&self, sprite: &Sprite) -> [PosColorTex; 6] {
+        let (sin, cos) = self.rotation.sin_cos();
+        let axis_x = Vector2::new(cos, sin) * self.scale;
+        let axis_y = Vector2::new(-sin, cos) * self.scale;
+        let coords = &sprite.tex_coords;
+        let origin = Vector2::new(self.position[0], self.position[1]);
+        let lerp = |from: f32, to: f32, t: f32| from + (to - from) * t;
+        let corner = |s: f32, t: f32| {
+            // The offsets shift the sprite left and down relative to its position.
+            let x = (s - 0.5) * sprite.width - sprite.offsets[0];
+            let y = (t - 0.5) * sprite.height - sprite.offsets[1];
+            let position = origin + axis_x * x + axis_y * y;
+            // A sprite rotated clockwise in the texture goes up the horizontal texture axis.
+            let tex_coord = if sprite.rotated {
+                [
+                    lerp(coords.left, coords.right, t),
+                    lerp(coords.bottom, coords.top, 1.0 - s),
+                ]
+            } else {
+                [
+                    lerp(coords.left, coords.right, s),
+                    lerp(coords.bottom, coords.top, t),
+                ]
+            };
+            PosColorTex {
+                position: Vector3::new(position.x, position.y, 0.0),
+                color: self.tint.into(),
+                tex_coord: tex_coord.into(),
+            }
+        };
+        let (bottom_left, bottom_right) = (corner(0.0, 0.0), corner(1.0, 0.0));
+        let (top_left, top_right) = (corner(0.0, 1.0), corner(1.0, 1.0));
+        [
+            bottom_left,
+            bottom_right,
+            top_right,
+            bottom_left,
+            top_right,
+            top_left,
+        ]
+    }
+}
+
+/// Area of the world baked into a render target.
+#[derive(Clone, Debug)]
+struct DecalLayer {
+    target: String,
+    min: [f32; 2],
+    max: [f32; 2],
+    pending: Vec<Decal>,
+}
+
+/// Resource stamping sprites such as blood or scorch marks into render targets for good, so
+/// persistent decals don't remain entities.
+///
+/// Each layer covers an area of the world, such as a chunk of a tilemap, and is drawn by the
+/// `DrawDecals` pass of its render target. The target should have a fixed size, be drawn by a
+/// stage which does not clear it, and be shown over the ground with the texture returned by
+/// `RenderTargetTextures::insert`, stretched over the area of the layer.
+///
+/// A layer covering a Tiled map loaded by `amethyst_tiled` is sized with the `TiledMap` on the
+/// root of the map, whose `bounds` are relative to the root:
+///
+/// ```rust,ignore
+/// let (min, max) = tiled_map.bounds();
+/// let [x, y] = [root_translation.x, root_translation.y];
+/// decals.add_layer("ground_decals", [min[0] + x, min[1] + y], [max[0] + x, max[1] + y]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Decals {
+    layers: Vec<DecalLayer>,
+    margin: f32,
+}
+
+impl Decals {
+    /// Creates the resource without any layer.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Declares a layer baking the decals of the area between `min` and `max`, in world
+    /// coordinates, into the named render target.
+    pub fn with_layer<N: Into<String>>(mut self, target: N, min: [f32; 2], max: [f32; 2]) -> Self {
+        self.add_layer(target, min, max);
+        self
+    }
+
+    /// Declares a layer baking the decals of the area between `min` and `max`, in world
+    /// coordinates, into the named render target.
+    pub fn add_layer<N: Into<String>>(&mut self, target: N, min: [f32; 2], max: [f32; 2]) {
+        self.layers.push(DecalLayer {
+            target: target.into(),
+            min,
+            max,
+            pending: Vec::new(),
+        });
+    }
+
+    /// Sets how far outside of its area a decal is also stamped into a layer, in world units.
+    ///
+    /// Decals are stamped into the layers covering their position, so decals larger than `0` are
+    /// cut at the edges of the layers unless the margin is half the size of the largest decal.
+    pub fn with_margin(mut self, margin: f32) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /// Stamps a decal into the layers covering it, returning whether it is covered by a layer.
+    ///
+    /// The decal is drawn once its sprite sheet and texture are loaded.
+    pub fn stamp(&mut self, decal: Decal) -> bool {
+        let margin = self.margin;
+        let [x, y] = decal.position;
+        let mut covered = false;
+        for layer in &mut self.layers {
+            if x >= layer.min[0] - margin
+                && x <= layer.max[0] + margin
+                && y >= layer.min[1] - margin
+                && y <= layer.max[1] + margin
+            {
+                layer.pending.push(decal.clone());
+                covered = true;
+            }
+        }
+        covered
+    }
+
+    /// Returns the area of the layer of the named render target, as its `min` and `max` corners.
+    pub fn area(&self, target: &str) -> Option<([f32; 2], [f32; 2])> {
+        self.layer(target).map(|layer| (layer.min, layer.max))
+    }
+
+    /// Returns the decals waiting to be drawn into the named render target.
+    pub(crate) fn pending_mut(&mut self, target: &str) -> Option<&mut Vec<Decal>> {
+        self.layers
+            .iter_mut()
+            .find(|layer| layer.target == target)
+            .map(|layer| &mut layer.pending)
+    }
+
+    fn layer(&self, target: &str) -> Option<&DecalLayer> {
+        self.layers.iter().find(|layer| layer.target == target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+
+    use amethyst_assets::{AssetStorage, Handle, Loader};
+    use rayon::ThreadPoolBuilder;
+
+    use crate::{formats::TextureData, sprite::SpriteSheet};
+
+    fn sprite_sheet(sprites: Vec<Sprite>) -> Handle<SpriteSheet> {
+        let pool = ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        let loader = Loader::new(".", Arc::new(pool));
+        let texture = loader.load_from_data(TextureData::color([1.0; 4]), (), &AssetStorage::new());
+        let sheet = SpriteSheet {
+            texture,
+            sprites,
+            pages: Vec::new(),
+            normal_maps: Vec::new(),
+            sampler: None,
+        };
+        loader.load_from_data(sheet, (), &AssetStorage::new())
+    }
+
+    #[test]
+    fn decals_are_stamped_into_covering_layers() {
+        let sheet = sprite_sheet(Vec::new());
+        let decal = |x, y| Decal::new(SpriteRender::new(sheet.clone(), 0), [x, y]);
+
+        let mut decals = Decals::new()
+            .with_layer("chunk_0", [0.0, 0.0], [64.0, 64.0])
+            .with_layer("chunk_1", [64.0, 0.0], [128.0, 64.0])
+            .with_margin(4.0);
+        assert!(decals.stamp(decal(10.0, 10.0)));
+        assert!(decals.stamp(decal(66.0, 10.0)));
+        assert!(!decals.stamp(decal(10.0, 100.0)));
+        assert_eq!(decals.pending_mut("chunk_0").unwrap().len(), 2);
+        assert_eq!(decals.pending_mut("chunk_1").unwrap().len(), 1);
+        assert!(decals.pending_mut("chunk_2").is_none());
+        assert_eq!(decals.area("chunk_1"), Some(([64.0, 0.0], [128.0, 64.0])));
+    }
+
+    #[test]
+    fn decal_vertices_follow_rotation_and_offsets() {
+        let sprite = Sprite::from_pixel_values(32, 32, 8, 4, 0, 0, [2.0, 0.0]);
+        let sheet = sprite_sheet(vec![sprite.clone()]);
+        let decal = Decal::new(SpriteRender::new(sheet, 0), [10.0, 10.0])
+            .with_rotation(std::f32::consts::FRAC_PI_2)
+            .with_tint(Rgba::RED);
+
+        let vertices = decal.vertices(&sprite);
+        // The bottom left corner is at (-6, -2) from the position before the quarter turn.
+        let bottom_left = vertices[0].position;
+        assert!((bottom_left - Vector3::new(12.0, 4.0, 0.0)).norm() < 1e-5);
+        assert_eq!(vertices[0].tex_coord, Vector2::new(0.0, 0.875));
+        assert_eq!(vertices[2].tex_coord, Vector2::new(0.25, 1.0));
+        assert_eq!(vertices[5].color, [1.0, 0.0, 0.0, 1.0]);
+    }
+}
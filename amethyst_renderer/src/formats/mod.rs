@@ -1,7 +1,7 @@
 //! Provides texture formats
 //!
 
-pub use self::{mesh::*, mtl::*, sprite::*, texture::*};
+pub use self::{mesh::*, mtl::*, sprite::*, texture::*, texture_packer::*};
 
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
@@ -15,6 +15,7 @@ mod mesh;
 mod mtl;
 mod sprite;
 mod texture;
+mod texture_packer;
 
 /// Internal mesh loading
 ///
@@ -1,11 +1,19 @@
+use std::sync::{Arc, Mutex};
+
 use ron::de::from_bytes as from_ron_bytes;
 use serde::{Deserialize, Serialize};
 
 use amethyst_assets::{AssetStorage, Handle, Loader, PrefabData, ProgressCounter, SimpleFormat};
-use amethyst_core::specs::prelude::{Entity, Read, ReadExpect, WriteStorage};
-use amethyst_error::Error;
+use amethyst_core::{
+    specs::prelude::{Entity, Read, ReadExpect, WriteStorage},
+    transform::Transform,
+};
+use amethyst_error::{format_err, Error};
 
-use crate::{error, Sprite, SpriteRender, SpriteSheet, Texture, TextureFormat, TexturePrefab};
+use crate::{
+    error, tex::SamplerInfo, Flipped, Rgba, Sprite, SpriteRender, SpriteSheet, Texture,
+    TextureFormat, TexturePrefab,
+};
 
 /// Structure acting as scaffolding for serde when loading a spritesheet file.
 /// Positions originate in the top-left corner (bitmap image convention).
@@ -21,17 +29,150 @@ pub struct SpritePosition {
     pub height: u32,
     /// Number of pixels to shift the sprite to the left and down relative to the entity holding it
     pub offsets: Option<[f32; 2]>,
+    /// Page of the sprite sheet holding the sprite, `0` for its first texture
+    #[serde(default)]
+    pub page: usize,
+    /// Point the sprite rotates and scales around, placed on the entity holding it, as fractions
+    /// of the sprite size from its top-left corner. Used instead of `offsets` if present.
+    #[serde(default)]
+    pub pivot: Option<[f32; 2]>,
+    /// Name of the sprite
+    pub name: Option<String>,
+}
+
+impl SpritePosition {
+    /// Offsets of the sprite, computed from its pivot if it has one.
+    fn sprite_offsets(&self) -> [f32; 2] {
+        match self.pivot {
+            Some([x, y]) => [
+                (x - 0.5) * self.width as f32,
+                (0.5 - y) * self.height as f32,
+            ],
+            None => self.offsets.unwrap_or([0.0; 2]),
+        }
+    }
+}
+
+/// Grid of sprites of the same size in a sprite sheet, numbered row by row from its top-left
+/// corner.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpriteGrid {
+    /// Width of the sprites
+    pub tile_width: u32,
+    /// Height of the sprites
+    pub tile_height: u32,
+    /// Number of sprites in a row
+    pub columns: u32,
+    /// Number of rows of sprites
+    pub rows: u32,
+    /// Number of pixels between the edges of the sprite sheet and the sprites
+    #[serde(default)]
+    pub margin: u32,
+    /// Number of pixels between two sprites
+    #[serde(default)]
+    pub spacing: u32,
+    /// Number of pixels to shift the sprites to the left and down relative to the entity holding
+    /// them
+    #[serde(default)]
+    pub offsets: Option<[f32; 2]>,
+    /// Page of the sprite sheet holding the sprites
+    #[serde(default)]
+    pub page: usize,
+}
+
+impl SpriteGrid {
+    /// Positions of the sprites of the grid, row by row.
+    fn positions<'a>(&'a self) -> impl Iterator<Item = SpritePosition> + 'a {
+        let step_x = self.tile_width + self.spacing;
+        let step_y = self.tile_height + self.spacing;
+        (0..self.rows).flat_map(move |row| {
+            (0..self.columns).map(move |column| SpritePosition {
+                x: self.margin + column * step_x,
+                y: self.margin + row * step_y,
+                width: self.tile_width,
+                height: self.tile_height,
+                offsets: self.offsets,
+                page: self.page,
+                pivot: None,
+                name: None,
+            })
+        })
+    }
 }
 
 /// Structure acting as scaffolding for serde when loading a spritesheet file.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct SerializedSpriteSheet {
-    /// Width of the sprite sheet
+    /// Width of the sprite sheet, `0` in prefabs for the width of its texture
+    #[serde(default)]
     pub spritesheet_width: u32,
-    /// Height of the sprite sheet
+    /// Height of the sprite sheet, `0` in prefabs for the height of its texture
+    #[serde(default)]
     pub spritesheet_height: u32,
+    /// Grid of sprites numbered before the sprites of `sprites`
+    #[serde(default)]
+    pub grid: Option<SpriteGrid>,
     /// Description of the sprites
+    #[serde(default)]
     pub sprites: Vec<SpritePosition>,
+    /// Sampler of the textures of the sprite sheet, overriding the sampler of their metadata
+    #[serde(default)]
+    pub sampler: Option<SamplerInfo>,
+    /// Number of texels the texture coordinates of the sprites are moved inwards, usually `0.5`
+    /// to keep filtering from blending in the texels around the sprites
+    #[serde(default)]
+    pub uv_inset: f32,
+}
+
+impl SerializedSpriteSheet {
+    /// Builds the sprites of the grid followed by the listed sprites.
+    pub(crate) fn build_sprites(&self) -> Vec<Sprite> {
+        self.build_sprites_of_size(self.spritesheet_width, self.spritesheet_height)
+    }
+
+    /// Builds the sprites, in a sprite sheet of the size of the given texture if its size is
+    /// left out.
+    pub(crate) fn build_sprites_in(&self, texture: Option<&Texture>) -> Result<Vec<Sprite>, Error> {
+        if self.spritesheet_width != 0 && self.spritesheet_height != 0 {
+            return Ok(self.build_sprites());
+        }
+        let (width, height) = texture.map(Texture::size).ok_or_else(|| {
+            format_err!("The size of the sprite sheet is required before its texture is loaded")
+        })?;
+        Ok(self.build_sprites_of_size(width as u32, height as u32))
+    }
+
+    /// Returns the index of the first sprite with the given name.
+    pub(crate) fn sprite_index(&self, name: &str) -> Option<usize> {
+        let grid = self
+            .grid
+            .as_ref()
+            .map_or(0, |grid| (grid.columns * grid.rows) as usize);
+        self.sprites
+            .iter()
+            .position(|sp| sp.name.as_ref().map_or(false, |sprite| sprite == name))
+            .map(|index| grid + index)
+    }
+
+    fn build_sprites_of_size(&self, width: u32, height: u32) -> Vec<Sprite> {
+        let grid = self.grid.iter().flat_map(SpriteGrid::positions);
+        grid.chain(self.sprites.iter().cloned())
+            .map(|sp| {
+                let mut sprite = Sprite::from_pixel_values(
+                    width,
+                    height,
+                    sp.width,
+                    sp.height,
+                    sp.x,
+                    sp.y,
+                    sp.sprite_offsets(),
+                );
+                sprite.page = sp.page;
+                sprite.name = sp.name;
+                sprite.with_inset(self.uv_inset)
+            })
+            .collect()
+    }
 }
 
 /// Allows loading of sprite sheets in RON format.
@@ -58,6 +199,13 @@ pub struct SerializedSpriteSheet {
 ///             height: 16.0,
 ///             // Number of pixels to shift the sprite to the left and down relative to the entity holding it when rendering
 ///             offsets: (0.0, 0.0), // This is optional and defaults to (0.0, 0.0)
+///             // Point of the sprite placed on the entity, as fractions of the sprite size from
+///             // its top-left corner, used instead of the offsets. (0.5, 1.0) is the bottom.
+///             pivot: (0.5, 1.0), // This is optional
+///             // Texture page of the sprite, for sprite sheets split across several textures
+///             page: 0, // This is optional and defaults to 0
+///             // Name to find the sprite with `SpriteSheet::sprite_index`
+///             name: "player_idle_0", // This is optional
 ///         ),
 ///         (
 ///             x: 16.0,
@@ -69,6 +217,42 @@ pub struct SerializedSpriteSheet {
 /// )
 /// ```
 ///
+/// Sprite sheets of sprites of the same size laid out in a grid can describe the grid instead of
+/// listing the sprites, which are numbered row by row from the top-left corner:
+/// ```text,ignore
+/// (
+///     spritesheet_width: 100,
+///     spritesheet_height: 50,
+///     grid: (
+///         tile_width: 16,
+///         tile_height: 16,
+///         columns: 5,
+///         rows: 2,
+///         // Pixels around the grid, optional and defaults to 0
+///         margin: 1,
+///         // Pixels between the sprites, optional and defaults to 0
+///         spacing: 2,
+///     ),
+/// )
+/// ```
+///
+/// The textures of a sprite sheet can be sampled differently than set by their `TextureMetadata`,
+/// such as pixel art sprite sheets sampled with nearest filtering:
+/// ```text,ignore
+/// (
+///     spritesheet_width: 48,
+///     spritesheet_height: 16,
+///     sampler: Some((filter: Scale, wrap_mode: (Clamp, Clamp, Clamp))),
+///     sprites: [
+///         // ...
+///     ],
+/// )
+/// ```
+///
+/// Sprites drawn scaled with linear filtering or mipmaps blend in the texels around them in the
+/// texture. Moving their texture coordinates inwards by half a texel with `uv_inset: 0.5` keeps
+/// the filtering inside the sprites.
+///
 /// Such a spritesheet description can be loaded using a `Loader` by passing it the handle of the corresponding loaded texture.
 /// ```rust,no_run
 /// # use amethyst_assets::{Loader, AssetStorage};
@@ -95,6 +279,13 @@ pub struct SerializedSpriteSheet {
 /// );
 /// # }
 /// ```
+///
+/// The sprites must be on page `0`, the `SpriteRenderPrefab` loads sprite sheets with several
+/// pages.
+///
+/// Sprite sheets loaded from a file are reloaded when the file changes, if hot reloading is
+/// enabled with the `HotReloadBundle`. The `SpriteRender`s keep their sprite sheet handle and
+/// sprite number, so they are drawn with the new sprites on the next frame.
 #[derive(Clone, Deserialize, Serialize)]
 pub struct SpriteSheetFormat;
 
@@ -107,20 +298,13 @@ impl SimpleFormat<SpriteSheet> for SpriteSheetFormat {
         let sheet: SerializedSpriteSheet =
             from_ron_bytes(&bytes).map_err(|_| error::Error::LoadSpritesheetError)?;
 
-        let mut sprites: Vec<Sprite> = Vec::with_capacity(sheet.sprites.len());
-        for sp in sheet.sprites {
-            let sprite = Sprite::from_pixel_values(
-                sheet.spritesheet_width as u32,
-                sheet.spritesheet_height as u32,
-                sp.width as u32,
-                sp.height as u32,
-                sp.x as u32,
-                sp.y as u32,
-                sp.offsets.unwrap_or([0.0; 2]),
-            );
-            sprites.push(sprite);
-        }
-        Ok(SpriteSheet { texture, sprites })
+        Ok(SpriteSheet {
+            texture,
+            sprites: sheet.build_sprites_in(None)?,
+            pages: Vec::new(),
+            normal_maps: Vec::new(),
+            sampler: sheet.sampler,
+        })
     }
 }
 
@@ -129,10 +313,20 @@ impl SimpleFormat<SpriteSheet> for SpriteSheetFormat {
 pub struct SpriteRenderPrefab {
     /// Spritesheet texture
     pub texture: TexturePrefab<TextureFormat>,
+    /// Textures of the pages of the sprite sheet after the first one
+    #[serde(default)]
+    pub pages: Vec<TexturePrefab<TextureFormat>>,
+    /// Normal maps of the pages of the sprite sheet, starting with the first one
+    #[serde(default)]
+    pub normal_maps: Vec<TexturePrefab<TextureFormat>>,
     /// Sprite coordinates on the texture
     pub sprite_sheet: SerializedSpriteSheet,
     /// Index of the sprite on the sprite sheet
+    #[serde(default)]
     pub sprite_number: usize,
+    /// Name of the sprite on the sprite sheet, used instead of `sprite_number` if present
+    #[serde(default)]
+    pub sprite_name: Option<String>,
 }
 
 impl<'a> PrefabData<'a> for SpriteRenderPrefab {
@@ -152,28 +346,39 @@ impl<'a> PrefabData<'a> for SpriteRenderPrefab {
     ) -> Result<(), Error> {
         let (tex_data, loader, sheet_storage, render_storage) = system_data;
 
-        let mut sprites: Vec<Sprite> = Vec::with_capacity(self.sprite_sheet.sprites.len());
-        for sp in &self.sprite_sheet.sprites {
-            let sprite = Sprite::from_pixel_values(
-                self.sprite_sheet.spritesheet_width as u32,
-                self.sprite_sheet.spritesheet_height as u32,
-                sp.width as u32,
-                sp.height as u32,
-                sp.x as u32,
-                sp.y as u32,
-                sp.offsets.unwrap_or([0.0; 2]),
-            );
-            sprites.push(sprite);
-        }
-
         let texture = self.texture.add_to_entity(entity, tex_data, entities)?;
+        let sprites = self
+            .sprite_sheet
+            .build_sprites_in(tex_data.1.get(&texture))?;
+        let pages = self
+            .pages
+            .iter()
+            .map(|page| page.add_to_entity(entity, tex_data, entities))
+            .collect::<Result<_, _>>()?;
+        let normal_maps = self
+            .normal_maps
+            .iter()
+            .map(|normal_map| normal_map.add_to_entity(entity, tex_data, entities))
+            .collect::<Result<_, _>>()?;
 
-        let sheet = SpriteSheet { texture, sprites };
+        let sheet = SpriteSheet {
+            texture,
+            sprites,
+            pages,
+            normal_maps,
+            sampler: self.sprite_sheet.sampler,
+        };
+        let sprite_number = match self.sprite_name {
+            Some(ref name) => sheet
+                .sprite_index(name)
+                .ok_or_else(|| format_err!("No sprite named {} in the sprite sheet", name))?,
+            None => self.sprite_number,
+        };
         let sheet_handle = loader.load_from_data(sheet, (), sheet_storage);
 
         let render = SpriteRender {
             sprite_sheet: sheet_handle,
-            sprite_number: self.sprite_number,
+            sprite_number,
         };
         render_storage.insert(entity, render)?;
 
@@ -185,6 +390,377 @@ impl<'a> PrefabData<'a> for SpriteRenderPrefab {
         progress: &mut ProgressCounter,
         (tex_data, _, _, _): &mut Self::SystemData,
     ) -> Result<bool, Error> {
-        self.texture.load_sub_assets(progress, tex_data)
+        let mut ret = self.texture.load_sub_assets(progress, tex_data)?;
+        for page in self.pages.iter_mut().chain(&mut self.normal_maps) {
+            ret |= page.load_sub_assets(progress, tex_data)?;
+        }
+        Ok(ret)
+    }
+}
+
+/// Sprite sheet of a `SpriteScenePrefab`.
+///
+/// The sprite sheet is loaded once for the prefab, and shared by the entities created from it.
+/// Sprite sheets described in the prefab without their size are built for the first entity, once
+/// their texture is loaded.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum SpriteSheetPrefab {
+    /// Sprite sheet described in the prefab
+    Sheet {
+        /// Sprite sheet texture
+        texture: TexturePrefab<TextureFormat>,
+        /// Sprite coordinates on the texture
+        sprite_sheet: SerializedSpriteSheet,
+    },
+
+    /// Sprite sheet file in the `SpriteSheetFormat`, with its texture
+    File(String, TexturePrefab<TextureFormat>),
+
+    /// Clone handle only
+    #[serde(skip)]
+    Handle(Handle<SpriteSheet>),
+
+    /// Sprite sheet described in the prefab without its size, waiting for its texture
+    #[serde(skip)]
+    Deferred {
+        /// Sprite sheet texture
+        texture: Handle<Texture>,
+        /// Sprite coordinates on the texture
+        sprite_sheet: SerializedSpriteSheet,
+        /// Sprite sheet built for the first entity, shared by the next ones
+        built: Arc<Mutex<Option<Handle<SpriteSheet>>>>,
+    },
+}
+
+impl<'a> PrefabData<'a> for SpriteSheetPrefab {
+    type SystemData = (
+        <TexturePrefab<TextureFormat> as PrefabData<'a>>::SystemData,
+        ReadExpect<'a, Loader>,
+        Read<'a, AssetStorage<SpriteSheet>>,
+    );
+    type Result = Handle<SpriteSheet>;
+
+    fn add_to_entity(
+        &self,
+        _: Entity,
+        (tex_data, loader, sheet_storage): &mut Self::SystemData,
+        _: &[Entity],
+    ) -> Result<Handle<SpriteSheet>, Error> {
+        match *self {
+            SpriteSheetPrefab::Handle(ref handle) => Ok(handle.clone()),
+
+            SpriteSheetPrefab::Deferred {
+                ref texture,
+                ref sprite_sheet,
+                ref built,
+            } => {
+                let mut built = built.lock().expect("Sprite sheet of the prefab poisoned");
+                if let Some(ref handle) = *built {
+                    return Ok(handle.clone());
+                }
+                let sheet = SpriteSheet {
+                    texture: texture.clone(),
+                    sprites: sprite_sheet.build_sprites_in(tex_data.1.get(texture))?,
+                    pages: Vec::new(),
+                    normal_maps: Vec::new(),
+                    sampler: sprite_sheet.sampler,
+                };
+                let handle = loader.load_from_data(sheet, (), sheet_storage);
+                *built = Some(handle.clone());
+                Ok(handle)
+            }
+
+            _ => unreachable!(),
+        }
+    }
+
+    fn load_sub_assets(
+        &mut self,
+        progress: &mut ProgressCounter,
+        (tex_data, loader, sheet_storage): &mut Self::SystemData,
+    ) -> Result<bool, Error> {
+        let handle = match *self {
+            SpriteSheetPrefab::Sheet {
+                ref mut texture,
+                ref sprite_sheet,
+            } => {
+                texture.load_sub_assets(progress, tex_data)?;
+                // The size of the sprite sheet is the size of its texture if it is left out.
+                if sprite_sheet.spritesheet_width == 0 || sprite_sheet.spritesheet_height == 0 {
+                    *self = SpriteSheetPrefab::Deferred {
+                        texture: loaded_texture(texture),
+                        sprite_sheet: sprite_sheet.clone(),
+                        built: Default::default(),
+                    };
+                    return Ok(true);
+                }
+                let sheet = SpriteSheet {
+                    texture: loaded_texture(texture),
+                    sprites: sprite_sheet.build_sprites(),
+                    pages: Vec::new(),
+                    normal_maps: Vec::new(),
+                    sampler: sprite_sheet.sampler,
+                };
+                loader.load_from_data(sheet, &mut *progress, sheet_storage)
+            }
+
+            SpriteSheetPrefab::File(ref name, ref mut texture) => {
+                texture.load_sub_assets(progress, tex_data)?;
+                loader.load(
+                    name.as_str(),
+                    SpriteSheetFormat,
+                    loaded_texture(texture),
+                    &mut *progress,
+                    sheet_storage,
+                )
+            }
+
+            SpriteSheetPrefab::Handle(_) | SpriteSheetPrefab::Deferred { .. } => return Ok(false),
+        };
+        *self = SpriteSheetPrefab::Handle(handle);
+        Ok(true)
+    }
+}
+
+/// Returns the handle of a texture prefab once its sub assets are loaded.
+fn loaded_texture(texture: &TexturePrefab<TextureFormat>) -> Handle<Texture> {
+    match *texture {
+        TexturePrefab::Handle(ref handle) => handle.clone(),
+        _ => unreachable!(),
+    }
+}
+
+/// `PrefabData` for loading sprite entities: a `SpriteRender` from a sprite sheet declared in the
+/// prefab or loaded from a file, with the `Transform`, `Flipped` and tint of the entity.
+///
+/// Example of a prefab entity:
+/// ```text,ignore
+/// #![enable(implicit_some)]
+/// Prefab(
+///     entities: [
+///         PrefabEntity(
+///             data: (
+///                 sheet: File(
+///                     "sprites/characters.ron",
+///                     File("texture/characters.png", Png, TextureMetadata(channel: Srgb)),
+///                 ),
+///                 // Either the number or the name of the sprite
+///                 sprite_name: "player_idle_0",
+///                 // The other fields are optional
+///                 transform: (translation: (100.0, 50.0, 0.0)),
+///                 flipped: Horizontal,
+///                 tint: (1.0, 0.5, 0.5, 1.0),
+///             ),
+///         ),
+///     ],
+/// )
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpriteScenePrefab {
+    /// Sprite sheet of the sprite
+    pub sheet: SpriteSheetPrefab,
+    /// Index of the sprite on the sprite sheet
+    #[serde(default)]
+    pub sprite_number: usize,
+    /// Name of the sprite on the sprite sheet, used instead of `sprite_number` if present
+    #[serde(default)]
+    pub sprite_name: Option<String>,
+    /// Position, rotation and scale of the entity
+    #[serde(default)]
+    pub transform: Option<Transform>,
+    /// Flipping of the sprite
+    #[serde(default)]
+    pub flipped: Option<Flipped>,
+    /// Color the sprite is tinted with
+    #[serde(default)]
+    pub tint: Option<Rgba>,
+}
+
+impl<'a> PrefabData<'a> for SpriteScenePrefab {
+    type SystemData = (
+        <SpriteSheetPrefab as PrefabData<'a>>::SystemData,
+        WriteStorage<'a, SpriteRender>,
+        WriteStorage<'a, Transform>,
+        WriteStorage<'a, Flipped>,
+        WriteStorage<'a, Rgba>,
+    );
+    type Result = ();
+
+    fn add_to_entity(
+        &self,
+        entity: Entity,
+        system_data: &mut Self::SystemData,
+        entities: &[Entity],
+    ) -> Result<(), Error> {
+        let (sheet_data, renders, transforms, flips, tints) = system_data;
+
+        let sprite_sheet = self.sheet.add_to_entity(entity, sheet_data, entities)?;
+        let sprite_number = match self.sprite_name {
+            Some(ref name) => match self.sheet {
+                SpriteSheetPrefab::Deferred {
+                    ref sprite_sheet, ..
+                } => sprite_sheet.sprite_index(name),
+                _ => sheet_data
+                    .2
+                    .get(&sprite_sheet)
+                    .and_then(|sheet| sheet.sprite_index(name)),
+            }
+            .ok_or_else(|| format_err!("No sprite named {} in the sprite sheet", name))?,
+            None => self.sprite_number,
+        };
+        renders.insert(
+            entity,
+            SpriteRender {
+                sprite_sheet,
+                sprite_number,
+            },
+        )?;
+        if let Some(ref transform) = self.transform {
+            transforms.insert(entity, transform.clone())?;
+        }
+        if let Some(flipped) = self.flipped {
+            flips.insert(entity, flipped)?;
+        }
+        if let Some(tint) = self.tint {
+            tints.insert(entity, tint)?;
+        }
+
+        Ok(())
+    }
+
+    fn load_sub_assets(
+        &mut self,
+        progress: &mut ProgressCounter,
+        (sheet_data, _, _, _, _): &mut Self::SystemData,
+    ) -> Result<bool, Error> {
+        self.sheet.load_sub_assets(progress, sheet_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tex::{FilterMethod, WrapMode};
+
+    #[test]
+    fn pivot_overrides_offsets() {
+        let mut position = SpritePosition {
+            x: 0,
+            y: 0,
+            width: 16,
+            height: 32,
+            offsets: Some([1.0, 2.0]),
+            page: 0,
+            pivot: None,
+            name: None,
+        };
+        assert_eq!(position.sprite_offsets(), [1.0, 2.0]);
+        position.pivot = Some([0.5, 0.5]);
+        assert_eq!(position.sprite_offsets(), [0.0, 0.0]);
+        // Pivoting around the feet draws the sprite above the entity.
+        position.pivot = Some([0.5, 1.0]);
+        assert_eq!(position.sprite_offsets(), [0.0, -16.0]);
+        position.pivot = Some([0.0, 0.0]);
+        assert_eq!(position.sprite_offsets(), [-8.0, 16.0]);
+    }
+
+    #[test]
+    fn grid_generates_sprites_row_by_row() {
+        let sheet: SerializedSpriteSheet = from_ron_bytes(
+            b"(
+                spritesheet_width: 100,
+                spritesheet_height: 50,
+                grid: (tile_width: 16, tile_height: 8, columns: 3, rows: 2, margin: 1, spacing: 2),
+                sprites: [(x: 60, y: 0, width: 4, height: 4, offsets: None, name: Some(\"dot\"))],
+            )",
+        )
+        .unwrap();
+        let positions = sheet
+            .grid
+            .as_ref()
+            .unwrap()
+            .positions()
+            .map(|sp| (sp.x, sp.y))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            positions,
+            vec![(1, 1), (19, 1), (37, 1), (1, 11), (19, 11), (37, 11)]
+        );
+
+        let sprites = sheet.build_sprites();
+        assert_eq!(sprites.len(), 7);
+        assert_eq!(sprites[4].width, 16.0);
+        assert_eq!(sprites[6].name, Some("dot".to_string()));
+    }
+
+    #[test]
+    fn sampler_overrides_texture_sampler() {
+        let sheet: SerializedSpriteSheet =
+            from_ron_bytes(b"(spritesheet_width: 16, spritesheet_height: 16)").unwrap();
+        assert_eq!(sheet.sampler, None);
+
+        let sheet: SerializedSpriteSheet = from_ron_bytes(
+            b"(
+                spritesheet_width: 16,
+                spritesheet_height: 16,
+                sampler: Some((filter: Scale, wrap_mode: (Tile, Tile, Clamp))),
+            )",
+        )
+        .unwrap();
+        let sampler = sheet.sampler.unwrap();
+        assert_eq!(sampler.filter, FilterMethod::Scale);
+        assert_eq!(sampler.wrap_mode.0, WrapMode::Tile);
+    }
+
+    #[test]
+    fn sprite_scene_prefab_from_ron() {
+        let prefab: SpriteScenePrefab = ron::de::from_str(
+            "#![enable(implicit_some)]
+            (
+                sheet: File(\"sprites.ron\", File(\"sprites.png\", Png, (channel: Srgb))),
+                sprite_name: \"idle\",
+                flipped: Horizontal,
+                tint: (1.0, 0.5, 0.5, 1.0),
+            )",
+        )
+        .unwrap();
+        match prefab.sheet {
+            SpriteSheetPrefab::File(ref name, TexturePrefab::File(ref texture, _, _)) => {
+                assert_eq!(
+                    (name.as_str(), texture.as_str()),
+                    ("sprites.ron", "sprites.png")
+                );
+            }
+            ref sheet => panic!("Unexpected sprite sheet {:?}", sheet),
+        }
+        assert_eq!(prefab.sprite_name, Some("idle".to_string()));
+        assert_eq!(prefab.sprite_number, 0);
+        assert!(prefab.transform.is_none());
+        assert_eq!(prefab.flipped, Some(Flipped::Horizontal));
+        assert_eq!(prefab.tint, Some(Rgba(1.0, 0.5, 0.5, 1.0)));
+    }
+
+    #[test]
+    fn sprite_sheet_size_is_required_without_texture() {
+        let sheet: SerializedSpriteSheet = from_ron_bytes(
+            b"(
+                grid: (tile_width: 16, tile_height: 16, columns: 2, rows: 2),
+                sprites: [(x: 0, y: 32, width: 8, height: 8, offsets: None, name: Some(\"dot\"))],
+            )",
+        )
+        .unwrap();
+        assert_eq!(sheet.spritesheet_width, 0);
+        assert!(sheet.build_sprites_in(None).is_err());
+        assert_eq!(sheet.sprite_index("dot"), Some(4));
+        assert_eq!(sheet.sprite_index("line"), None);
+
+        let sheet = SerializedSpriteSheet {
+            spritesheet_width: 32,
+            spritesheet_height: 40,
+            ..sheet
+        };
+        let sprites = sheet.build_sprites_in(None).unwrap();
+        assert_eq!(sprites[4].name, Some("dot".to_string()));
     }
 }
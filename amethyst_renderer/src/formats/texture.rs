@@ -31,6 +31,15 @@ pub struct TextureMetadata {
     /// Mipmapping levels. The default is one level.
     #[serde(default = "serde_helper::default_mip_levels")]
     pub mip_levels: u8,
+    /// Generate the mip levels of images when loading them, down to one pixel, replacing
+    /// `mip_levels`. The sampler should use `FilterMethod::Trilinear` or
+    /// `FilterMethod::Anisotropic` to sample them. The `size` must be left out or be the size of
+    /// the image.
+    ///
+    /// The levels are not block compressed, as the graphics backend has no compressed surface
+    /// formats.
+    #[serde(default)]
+    pub generate_mips: bool,
     /// Dynamic texture
     #[serde(default)]
     pub dynamic: bool,
@@ -58,6 +67,7 @@ impl TextureMetadata {
         TextureMetadata {
             sampler: serde_helper::default_sampler(),
             mip_levels: serde_helper::default_mip_levels(),
+            generate_mips: false,
             dynamic: false,
             format: SurfaceFormat::get_surface_type(),
             size: None,
@@ -106,6 +116,12 @@ impl TextureMetadata {
         self
     }
 
+    /// Generates the mip levels of images when loading them.
+    pub fn with_generated_mips(mut self, generate_mips: bool) -> Self {
+        self.generate_mips = generate_mips;
+        self
+    }
+
     /// Surface type
     pub fn with_format(mut self, format: SurfaceType) -> Self {
         self.format = format;
@@ -473,24 +489,120 @@ fn create_texture_asset_from_image(
 ) -> Result<Texture, Error> {
     let fmt = SurfaceType::R8_G8_B8_A8;
     let chan = options.channel;
+    let generate_mips = options.generate_mips;
     let rgba = image.rgba;
     let w = rgba.width();
     let h = rgba.height();
     if w > u32::from(u16::max_value()) || h > u32::from(u16::max_value()) {
         return Err(Error::from(error::Error::UnsupportedTextureSize(w, h)));
     }
-    let tb = apply_options(
+    // The mip levels are generated from the image, so they only fit a texture of its size.
+    if let Some(size) = options.size.filter(|_| generate_mips) {
+        if size != (w as u16, h as u16) {
+            return Err(Error::from(error::Error::PixelDataMismatch(format!(
+                "mip levels of a {}x{} image generated for a texture of size {:?}",
+                w, h, size
+            ))));
+        }
+    }
+    let mips = if generate_mips {
+        mip_chain(&rgba, chan)
+            .into_iter()
+            .map(RgbaImage::into_raw)
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let mut tb = apply_options(
         TextureBuilder::new(rgba.into_raw())
             .with_format(fmt)
             .with_channel_type(chan)
             .with_size(w as u16, h as u16),
         options,
     );
+    if generate_mips {
+        tb = tb.with_mip_data(mips);
+    }
     renderer
         .create_texture(tb)
         .with_context(|_| error::Error::CreateTextureError)
 }
 
+/// Generates the mip levels below the image, down to one pixel, averaging blocks of 2x2 pixels.
+///
+/// The colors are weighted by their alpha, so the transparent pixels around sprites don't darken
+/// their edges, and averaged in linear space for `Srgb` images.
+fn mip_chain(image: &RgbaImage, channel: ChannelType) -> Vec<RgbaImage> {
+    let srgb = channel == ChannelType::Srgb;
+    let to_linear = |value: u8| {
+        let value = f32::from(value) / 255.0;
+        if !srgb {
+            value
+        } else if value <= 0.040_45 {
+            value / 12.92
+        } else {
+            ((value + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let from_linear = |value: f32| {
+        let value = if !srgb {
+            value
+        } else if value <= 0.003_130_8 {
+            value * 12.92
+        } else {
+            1.055 * value.powf(1.0 / 2.4) - 0.055
+        };
+        (value * 255.0).round().max(0.0).min(255.0) as u8
+    };
+
+    let mut mips = Vec::<RgbaImage>::new();
+    loop {
+        let previous = mips.last().unwrap_or(image);
+        if previous.width() == 1 && previous.height() == 1 {
+            return mips;
+        }
+        let (width, height) = (
+            (previous.width() / 2).max(1),
+            (previous.height() / 2).max(1),
+        );
+        let mut mip = RgbaImage::new(width, height);
+        for (x, y, pixel) in mip.enumerate_pixels_mut() {
+            let mut color = [0.0; 3];
+            let mut alpha = 0.0;
+            let mut count = 0.0;
+            // The last row and column of odd sizes are folded into the last pixels of the level.
+            let end_y = if y + 1 == height {
+                previous.height()
+            } else {
+                y * 2 + 2
+            };
+            let end_x = if x + 1 == width {
+                previous.width()
+            } else {
+                x * 2 + 2
+            };
+            for sy in (y * 2)..end_y {
+                for sx in (x * 2)..end_x {
+                    let source = previous.get_pixel(sx, sy);
+                    let weight = f32::from(source.data[3]) / 255.0;
+                    for (sum, &value) in color.iter_mut().zip(&source.data[..3]) {
+                        *sum += to_linear(value) * weight;
+                    }
+                    alpha += weight;
+                    count += 1.0;
+                }
+            }
+            if alpha > 0.0 {
+                for (value, sum) in pixel.data.iter_mut().zip(&color) {
+                    *value = from_linear(sum / alpha);
+                }
+            }
+            pixel.data[3] = (alpha / count * 255.0).round() as u8;
+        }
+        mips.push(mip);
+    }
+}
+
 /// Aggregate texture format
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum TextureFormat {
@@ -543,7 +655,10 @@ mod serde_helper {
 
 #[cfg(test)]
 mod tests {
-    use super::TextureData;
+    use gfx::format::ChannelType;
+    use image::{Rgba, RgbaImage};
+
+    use super::{mip_chain, TextureData};
 
     #[test]
     fn texture_data_from_f32_3() {
@@ -554,4 +669,33 @@ mod tests {
             _ => panic!("Expected [f32; 3] to turn into TextureData::Rgba"),
         }
     }
+
+    #[test]
+    fn mip_chain_goes_down_to_one_pixel() {
+        let mut image = RgbaImage::from_pixel(5, 2, Rgba([0, 0, 0, 0]));
+        image.put_pixel(0, 0, Rgba([200, 100, 0, 255]));
+        image.put_pixel(1, 1, Rgba([200, 100, 0, 255]));
+
+        let mips = mip_chain(&image, ChannelType::Unorm);
+        let sizes = mips.iter().map(|mip| mip.dimensions()).collect::<Vec<_>>();
+        assert_eq!(sizes, vec![(2, 1), (1, 1)]);
+        // Transparent pixels don't darken the color of the opaque ones.
+        assert_eq!(mips[0].get_pixel(0, 0), &Rgba([200, 100, 0, 128]));
+        assert_eq!(mips[0].get_pixel(1, 0), &Rgba([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn srgb_mips_are_averaged_in_linear_space() {
+        let mut image = RgbaImage::from_pixel(2, 1, Rgba([0, 0, 0, 255]));
+        image.put_pixel(1, 0, Rgba([255, 255, 255, 255]));
+
+        assert_eq!(
+            mip_chain(&image, ChannelType::Unorm)[0].get_pixel(0, 0)[0],
+            128
+        );
+        assert_eq!(
+            mip_chain(&image, ChannelType::Srgb)[0].get_pixel(0, 0)[0],
+            188
+        );
+    }
 }
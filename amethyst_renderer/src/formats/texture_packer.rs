@@ -0,0 +1,250 @@
+use std::fmt;
+
+use serde::{
+    de::{Deserializer, MapAccess, Visitor},
+    Deserialize, Serialize,
+};
+
+use amethyst_assets::{Handle, SimpleFormat};
+use amethyst_error::Error;
+
+use crate::{error, Sprite, SpriteSheet, Texture};
+
+/// Position and size of a rectangle in pixels, from the top-left corner.
+#[derive(Clone, Copy, Debug, Deserialize)]
+struct Rect {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+struct Size {
+    w: u32,
+    h: u32,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Pivot {
+    x: f32,
+    y: f32,
+}
+
+impl Default for Pivot {
+    fn default() -> Self {
+        Pivot { x: 0.5, y: 0.5 }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Frame {
+    /// Name of the sprite, the key of the frame in the hash variant.
+    filename: Option<String>,
+    /// Region of the texture, with the size of the unrotated sprite.
+    frame: Rect,
+    #[serde(default)]
+    rotated: bool,
+    /// Region of the untrimmed sprite covered by the frame.
+    sprite_source_size: Option<Rect>,
+    /// Size of the untrimmed sprite.
+    source_size: Option<Size>,
+    #[serde(default)]
+    pivot: Pivot,
+}
+
+/// Frames of the hash variant, in the order of the file.
+struct FrameMap(Vec<Frame>);
+
+impl<'de> Deserialize<'de> for FrameMap {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct FrameMapVisitor;
+
+        impl<'de> Visitor<'de> for FrameMapVisitor {
+            type Value = FrameMap;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("a map of frames")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<FrameMap, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut frames = Vec::with_capacity(map.size_hint().unwrap_or(0));
+                while let Some((name, mut frame)) = map.next_entry::<String, Frame>()? {
+                    frame.filename = Some(name);
+                    frames.push(frame);
+                }
+                Ok(FrameMap(frames))
+            }
+        }
+
+        deserializer.deserialize_map(FrameMapVisitor)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Frames {
+    Array(Vec<Frame>),
+    Hash(FrameMap),
+}
+
+#[derive(Deserialize)]
+struct Meta {
+    size: Size,
+}
+
+#[derive(Deserialize)]
+struct TexturePackerSheet {
+    frames: Frames,
+    meta: Meta,
+}
+
+impl Frame {
+    fn to_sprite(&self, image_w: u32, image_h: u32) -> Sprite {
+        let Rect { x, y, w, h } = self.frame;
+        let source = self.sprite_source_size.unwrap_or(Rect { x: 0, y: 0, w, h });
+        let source_size = self.source_size.unwrap_or(Size { w, h });
+
+        // Position of the center of the trimmed sprite relative to the pivot, with Y going up.
+        let center_x = source.x as f32 + w as f32 / 2.0 - self.pivot.x * source_size.w as f32;
+        let center_y = self.pivot.y * source_size.h as f32 - (source.y as f32 + h as f32 / 2.0);
+        let offsets = [-center_x, -center_y];
+
+        let mut sprite = if self.rotated {
+            let mut sprite = Sprite::from_pixel_values(image_w, image_h, h, w, x, y, offsets);
+            sprite.width = w as f32;
+            sprite.height = h as f32;
+            sprite.rotated = true;
+            sprite
+        } else {
+            Sprite::from_pixel_values(image_w, image_h, w, h, x, y, offsets)
+        };
+        sprite.name = self.filename.clone();
+        sprite
+    }
+}
+
+/// Allows loading of sprite sheets in the JSON format of TexturePacker.
+///
+/// Both the hash and the array variants are supported. The sprites are in the order of the
+/// file, and are named after the file names of the frames. Trimmed sprites are offset to keep
+/// their position in the untrimmed sprite, with the entity at the pivot point, the center of the
+/// untrimmed sprite by default. Rotated sprites are drawn back in their original orientation by
+/// the `DrawFlat2D` pass.
+///
+/// Like `SpriteSheetFormat`, it is loaded with the handle of the texture of the sprite sheet.
+///
+/// The JSON export of Aseprite uses the same format, its frame tags can be loaded as animations
+/// with `AsepriteAnimations` of `amethyst_animation`.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct TexturePackerFormat;
+
+impl SimpleFormat<SpriteSheet> for TexturePackerFormat {
+    const NAME: &'static str = "TEXTURE_PACKER";
+
+    type Options = Handle<Texture>;
+
+    fn import(&self, bytes: Vec<u8>, texture: Self::Options) -> Result<SpriteSheet, Error> {
+        let sheet: TexturePackerSheet =
+            serde_json::from_slice(&bytes).map_err(|_| error::Error::LoadSpritesheetError)?;
+        let frames = match sheet.frames {
+            Frames::Array(frames) => frames,
+            Frames::Hash(FrameMap(frames)) => frames,
+        };
+        let sprites = frames
+            .iter()
+            .map(|frame| frame.to_sprite(sheet.meta.size.w, sheet.meta.size.h))
+            .collect();
+        Ok(SpriteSheet {
+            texture,
+            sprites,
+            pages: Vec::new(),
+            normal_maps: Vec::new(),
+            sampler: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(json: &str) -> Vec<Frame> {
+        match serde_json::from_str::<TexturePackerSheet>(json)
+            .unwrap()
+            .frames
+        {
+            Frames::Array(frames) => frames,
+            Frames::Hash(FrameMap(frames)) => frames,
+        }
+    }
+
+    #[test]
+    fn hash_and_array_keep_order() {
+        let frame = |x| {
+            format!(
+                r#"{{"frame": {{"x": {}, "y": 0, "w": 8, "h": 8}}, "rotated": false}}"#,
+                x
+            )
+        };
+        let meta = r#""meta": {"size": {"w": 16, "h": 8}}"#;
+        let hash = format!(
+            r#"{{"frames": {{"b.png": {}, "a.png": {}}}, {}}}"#,
+            frame(0),
+            frame(8),
+            meta
+        );
+        let array = format!(r#"{{"frames": [{}, {}], {}}}"#, frame(0), frame(8), meta);
+        for json in &[hash, array] {
+            let xs: Vec<u32> = frames(json).iter().map(|f| f.frame.x).collect();
+            assert_eq!(xs, vec![0, 8]);
+        }
+    }
+
+    #[test]
+    fn hash_keys_name_sprites() {
+        let json = r#"{
+            "frames": {"player_idle_0": {"frame": {"x": 0, "y": 0, "w": 8, "h": 8}}},
+            "meta": {"size": {"w": 8, "h": 8}}
+        }"#;
+        let frames = frames(json);
+        let sprite = frames[0].to_sprite(8, 8);
+        assert_eq!(sprite.name, Some("player_idle_0".to_string()));
+    }
+
+    #[test]
+    fn trimmed_frame_keeps_position() {
+        let frame: Frame = serde_json::from_str(
+            r#"{
+                "frame": {"x": 0, "y": 0, "w": 4, "h": 2},
+                "trimmed": true,
+                "spriteSourceSize": {"x": 6, "y": 0, "w": 4, "h": 2},
+                "sourceSize": {"w": 10, "h": 10}
+            }"#,
+        )
+        .unwrap();
+        let sprite = frame.to_sprite(16, 16);
+        // The trimmed sprite is in the top right corner of the untrimmed one.
+        assert_eq!(sprite.offsets, [-3.0, -4.0]);
+        assert_eq!((sprite.width, sprite.height), (4.0, 2.0));
+    }
+
+    #[test]
+    fn rotated_frame_swaps_texture_region() {
+        let frame: Frame =
+            serde_json::from_str(r#"{"frame": {"x": 0, "y": 0, "w": 4, "h": 2}, "rotated": true}"#)
+                .unwrap();
+        let sprite = frame.to_sprite(16, 16);
+        assert!(sprite.rotated);
+        assert_eq!((sprite.width, sprite.height), (4.0, 2.0));
+        assert_eq!(sprite.tex_coords.right, 2.0 / 16.0);
+        assert_eq!(sprite.tex_coords.bottom, 12.0 / 16.0);
+    }
+}
@@ -5,6 +5,9 @@ use amethyst_core::specs::{
 
 /// Hidden mesh component
 /// Useful for entities, that should not be rendered, but stay loaded in memory.
+///
+/// Meshes, sprites, particles, UI elements and debug lines components of hidden entities are not
+/// drawn, while they keep their render components.
 #[derive(Clone, Debug, Default)]
 pub struct Hidden;
 
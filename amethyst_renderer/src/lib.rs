@@ -27,17 +27,21 @@
 #![warn(missing_docs, rust_2018_idioms, rust_2018_compatibility)]
 
 pub use crate::{
+    atlas::{TextureAtlas, TextureAtlasBuilder},
     blink::{Blink, BlinkSystem},
     bundle::RenderBundle,
     cam::{ActiveCamera, ActiveCameraPrefab, Camera, CameraPrefab, Projection},
     color::Rgba,
+    color_grading::ColorGrading,
     config::DisplayConfig,
-    debug_drawing::{DebugLines, DebugLinesComponent},
+    debug_drawing::{DebugDraw2D, DebugLines, DebugLinesComponent},
+    decals::{Decal, Decals},
     formats::{
         build_mesh_with_combo, create_mesh_asset, create_texture_asset, BmpFormat,
         ComboMeshCreator, GraphicsPrefab, ImageData, JpgFormat, MaterialPrefab, MeshCreator,
-        MeshData, ObjFormat, PngFormat, SpriteRenderPrefab, SpriteSheetFormat, TextureData,
-        TextureFormat, TextureMetadata, TexturePrefab, TgaFormat,
+        MeshData, ObjFormat, PngFormat, SerializedSpriteSheet, SpriteGrid, SpritePosition,
+        SpriteRenderPrefab, SpriteScenePrefab, SpriteSheetFormat, SpriteSheetPrefab, TextureData,
+        TextureFormat, TextureMetadata, TexturePackerFormat, TexturePrefab, TgaFormat,
     },
     hidden::{Hidden, HiddenPropagate},
     hide_system::HideHierarchySystem,
@@ -47,35 +51,50 @@ pub use crate::{
     light::{DirectionalLight, Light, LightPrefab, PointLight, SpotLight, SunLight},
     mesh::{vertex_data, Mesh, MeshBuilder, MeshHandle, VertexBuffer},
     mtl::{Material, MaterialDefaults, TextureOffset},
+    offscreen::{OffscreenCamera, RenderTargetTextures},
+    parallax::{ParallaxLayer, ParallaxSystem},
+    particle::{EmitterShape, ParticleEmitter2D, ParticleSystem2D},
     pass::{
-        get_camera, set_vertex_args, DebugLinesParams, DrawDebugLines, DrawFlat, DrawFlat2D,
-        DrawFlatSeparate, DrawPbm, DrawPbmSeparate, DrawShaded, DrawShadedSeparate, DrawSkybox,
-        SkyboxColor,
+        get_camera, set_vertex_args, DebugLinesParams, DrawColorGrading, DrawDebug2D,
+        DrawDebugLines, DrawDecals, DrawFlat, DrawFlat2D, DrawFlatSeparate, DrawParticles2D,
+        DrawPbm, DrawPbmSeparate, DrawShaded, DrawShadedSeparate, DrawShadowMap, DrawShadows2D,
+        DrawSkybox, DrawTransition, SkyboxColor,
     },
     pipe::{
         ColorBuffer, Data, DepthBuffer, DepthMode, Effect, EffectBuilder, Init, Meta, NewEffect,
         Pipeline, PipelineBuild, PipelineBuilder, PipelineData, PolyPipeline, PolyStage,
         PolyStages, Stage, StageBuilder, Target, TargetBuilder, Targets,
     },
+    render_layers::{Layer, RenderLayerSystem, RenderLayers},
     renderer::Renderer,
     resources::{AmbientColor, ScreenDimensions, WindowMessages},
+    shadow_map::DirectionalShadows,
     shape::{InternalShape, Shape, ShapePrefab, ShapeUpload},
     skinning::{
         AnimatedComboMeshCreator, AnimatedVertexBufferCombination, JointIds, JointTransforms,
         JointTransformsPrefab, JointWeights,
     },
-    sprite::{Flipped, Sprite, SpriteRender, SpriteSheet, SpriteSheetHandle, TextureCoordinates},
-    sprite_visibility::{SpriteVisibility, SpriteVisibilitySortingSystem},
+    sprite::{
+        Billboard, BlendMode, DrawOrder, Flipped, NineSlice, Palette, ScreenSpace, ShadowCaster2D,
+        SortOffset, Sprite, SpriteDeform, SpriteMask, SpriteMaterialOverride, SpriteOutline,
+        SpriteRender, SpriteSheet, SpriteSheetHandle, TextureCoordinates,
+    },
+    sprite_animation::{LoopMode, SpriteAnimation, SpriteAnimationSystem},
+    sprite_stats::{SpriteRenderCounts, SpriteRenderStats},
+    sprite_trail::{SpriteTrail, SpriteTrailSystem},
+    sprite_visibility::{SpriteSortMode, SpriteVisibility, SpriteVisibilitySortingSystem},
     system::RenderSystem,
     tex::{
         FilterMethod, SamplerInfo, SurfaceType, Texture, TextureBuilder, TextureHandle, WrapMode,
     },
+    texture_scroll::{TextureScroll, TextureScrollSystem},
+    transition::{Easing, ScreenTransition, TransitionEffect},
     transparent::{
         Blend, BlendChannel, BlendValue, ColorMask, Equation, Factor, Transparent, ALPHA, REPLACE,
     },
     types::{Encoder, Factory, PipelineState, Resources},
     vertex::{
-        Attribute, AttributeFormat, Attributes, Color, Normal, PosColor, PosColorNorm,
+        Attribute, AttributeFormat, Attributes, Color, Normal, PosColor, PosColorNorm, PosColorTex,
         PosNormTangTex, PosNormTex, PosTex, Position, Query, Separate, Tangent, TexCoord,
         VertexBufferCombination, VertexFormat, With,
     },
@@ -89,12 +108,15 @@ pub mod pipe;
 #[macro_use]
 mod macros;
 
+mod atlas;
 mod blink;
 mod bundle;
 mod cam;
 mod color;
+mod color_grading;
 mod config;
 mod debug_drawing;
+mod decals;
 mod formats;
 mod hidden;
 mod hide_system;
@@ -102,15 +124,25 @@ mod input;
 mod light;
 mod mesh;
 mod mtl;
+mod offscreen;
+mod parallax;
+mod particle;
 mod pass;
+mod render_layers;
 mod renderer;
 mod resources;
+mod shadow_map;
 mod shape;
 mod skinning;
 mod sprite;
+mod sprite_animation;
+mod sprite_stats;
+mod sprite_trail;
 mod sprite_visibility;
 mod system;
 mod tex;
+mod texture_scroll;
+mod transition;
 mod transparent;
 mod types;
 mod vertex;
@@ -0,0 +1,107 @@
+//! Rendering of cameras to textures, for minimaps and screens in the scene.
+
+use amethyst_assets::{AssetStorage, Handle, Loader};
+use amethyst_core::specs::prelude::{Component, DenseVecStorage};
+
+use crate::{
+    formats::TextureData,
+    tex::{SamplerInfo, Texture},
+};
+
+/// Camera drawing to a render target instead of the screen, for the `DrawFlat2D` passes created
+/// with `with_offscreen_camera` and the same target name.
+///
+/// The camera keeps its `Camera` and `Transform`, and should not be the `ActiveCamera`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OffscreenCamera {
+    /// Name of the render target drawn by the camera
+    pub target: String,
+}
+
+impl OffscreenCamera {
+    /// Creates a camera drawing to the named render target.
+    pub fn new<N: Into<String>>(target: N) -> Self {
+        OffscreenCamera {
+            target: target.into(),
+        }
+    }
+}
+
+impl Component for OffscreenCamera {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Resource exposing render targets as `Texture` assets, drawn as `UiImage`s, sprites or material
+/// albedos.
+///
+/// The `RenderSystem` replaces the texture of each handle by the first color buffer of its target
+/// every frame, following the target when the window is resized. Targets created with a fixed
+/// size keep their size and content when the window is resized.
+#[derive(Clone, Debug, Default)]
+pub struct RenderTargetTextures {
+    textures: Vec<(String, SamplerInfo, Handle<Texture>)>,
+}
+
+impl RenderTargetTextures {
+    /// Returns the handle of the texture of the named render target, sampled with the given
+    /// sampler.
+    ///
+    /// The texture is transparent until the render target is drawn.
+    pub fn insert<N: Into<String>>(
+        &mut self,
+        target: N,
+        sampler: SamplerInfo,
+        loader: &Loader,
+        storage: &AssetStorage<Texture>,
+    ) -> Handle<Texture> {
+        let target = target.into();
+        if let Some(handle) = self.get(&target) {
+            return handle.clone();
+        }
+        let handle = loader.load_from_data(TextureData::color([0.0; 4]), (), storage);
+        self.textures.push((target, sampler, handle.clone()));
+        handle
+    }
+
+    /// Returns the handle of the texture of the named render target, if it was inserted.
+    pub fn get(&self, target: &str) -> Option<&Handle<Texture>> {
+        self.textures
+            .iter()
+            .find(|(name, _, _)| name == target)
+            .map(|(_, _, handle)| handle)
+    }
+
+    /// Returns the render targets with their sampler and texture.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &(String, SamplerInfo, Handle<Texture>)> {
+        self.textures.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+
+    use rayon::ThreadPoolBuilder;
+
+    use crate::tex::{FilterMethod, WrapMode};
+
+    #[test]
+    fn target_textures_are_inserted_once() {
+        let pool = ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        let loader = Loader::new(".", Arc::new(pool));
+        let storage = AssetStorage::new();
+        let sampler = SamplerInfo::new(FilterMethod::Scale, WrapMode::Clamp);
+
+        let mut textures = RenderTargetTextures::default();
+        let handle = textures.insert("minimap", sampler, &loader, &storage);
+        assert_eq!(
+            textures.insert("minimap", sampler, &loader, &storage),
+            handle
+        );
+        assert_eq!(textures.get("minimap"), Some(&handle));
+        assert_eq!(textures.get("mirror"), None);
+        assert_eq!(textures.iter().count(), 1);
+    }
+}
@@ -0,0 +1,132 @@
+//! Module for the ParallaxLayer component and ParallaxSystem.
+
+use amethyst_assets::{PrefabData, ProgressCounter};
+use amethyst_core::{
+    specs::{
+        Component, DenseVecStorage, Entities, Entity, Join, Read, ReadStorage, System, WriteStorage,
+    },
+    Transform,
+};
+use amethyst_derive::PrefabData;
+use amethyst_error::Error;
+use serde::{Deserialize, Serialize};
+
+use crate::cam::{ActiveCamera, Camera};
+
+/// # ParallaxLayer Component
+/// Moves a background or foreground layer with the active camera, slower or faster than the
+/// world, to give depth to 2D scenes. You must add the `ParallaxSystem` to your dispatcher before
+/// the `TransformSystem` for this to take effect.
+///
+/// The layer scrolls on the screen by `scroll_factor` times the movement of the camera: `1.0`
+/// moves it with the world, `0.0` keeps it fixed on the screen and `0.5` makes it look twice as
+/// far as the world. The layer and the camera are expected to have no parent.
+///
+/// With a `tile_size`, the layer is moved by whole tiles to stay in front of the camera, so a
+/// layer repeating its texture over the screen plus one tile looks infinite.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, PrefabData)]
+#[prefab(Component)]
+pub struct ParallaxLayer {
+    /// Fraction of the movement of the camera scrolling the layer, on the X and Y axes.
+    pub scroll_factor: [f32; 2],
+    /// Position of the layer when the camera is at the origin.
+    #[serde(default)]
+    pub origin: [f32; 2],
+    /// Size of the repeated part of the layer on the X and Y axes, `0.0` to not repeat it.
+    #[serde(default)]
+    pub tile_size: [f32; 2],
+}
+
+impl ParallaxLayer {
+    /// Creates a `ParallaxLayer` scrolling by the given fractions of the camera movement.
+    pub fn new(scroll_x: f32, scroll_y: f32) -> Self {
+        ParallaxLayer {
+            scroll_factor: [scroll_x, scroll_y],
+            ..ParallaxLayer::default()
+        }
+    }
+
+    /// Repeats the layer every `width` units on the X axis and `height` units on the Y axis.
+    pub fn with_tile_size(mut self, width: f32, height: f32) -> Self {
+        self.tile_size = [width, height];
+        self
+    }
+}
+
+impl Component for ParallaxLayer {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Position of a layer on an axis, for the given position of the camera.
+fn layer_position(camera: f32, scroll_factor: f32, origin: f32, tile_size: f32) -> f32 {
+    let position = origin + camera * (1.0 - scroll_factor);
+    if tile_size > 0.0 {
+        position + ((camera - position) / tile_size).round() * tile_size
+    } else {
+        position
+    }
+}
+
+/// System moving the `Transform` of the entities with a `ParallaxLayer` component with the
+/// active camera.
+#[derive(Default)]
+pub struct ParallaxSystem;
+
+impl<'a> System<'a> for ParallaxSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, ActiveCamera>,
+        ReadStorage<'a, Camera>,
+        ReadStorage<'a, ParallaxLayer>,
+        WriteStorage<'a, Transform>,
+    );
+
+    fn run(&mut self, (entities, active, cameras, layers, mut transforms): Self::SystemData) {
+        let camera = active
+            .entity
+            .or_else(|| {
+                (&*entities, &cameras)
+                    .join()
+                    .map(|(entity, _)| entity)
+                    .next()
+            })
+            .and_then(|entity| transforms.get(entity))
+            .map(|transform| *transform.translation());
+        let camera = match camera {
+            Some(camera) => camera,
+            None => return,
+        };
+
+        for (layer, transform) in (&layers, &mut transforms).join() {
+            let x = layer_position(
+                camera.x,
+                layer.scroll_factor[0],
+                layer.origin[0],
+                layer.tile_size[0],
+            );
+            let y = layer_position(
+                camera.y,
+                layer.scroll_factor[1],
+                layer.origin[1],
+                layer.tile_size[1],
+            );
+            transform.set_x(x).set_y(y);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layer_scrolls_by_factor() {
+        assert_eq!(layer_position(100.0, 1.0, 10.0, 0.0), 10.0);
+        assert_eq!(layer_position(100.0, 0.0, 10.0, 0.0), 110.0);
+        // Half as fast on the screen: the layer follows half of the camera movement.
+        assert_eq!(layer_position(100.0, 0.5, 10.0, 0.0), 60.0);
+        // Tiles of 32 units: moved by a whole tile to stay in front of the camera.
+        assert_eq!(layer_position(100.0, 0.5, 10.0, 32.0), 92.0);
+        assert_eq!(layer_position(-100.0, 0.5, 10.0, 32.0), -104.0);
+    }
+}
@@ -0,0 +1,365 @@
+//! Module for the ParticleEmitter2D component and ParticleSystem2D.
+
+use std::{collections::VecDeque, f32::consts::PI, mem};
+
+use amethyst_core::{
+    nalgebra::Vector4,
+    specs::{Component, DenseVecStorage, Entities, Join, Read, ReadStorage, System, WriteStorage},
+    GlobalTransform, Time,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{sprite::SpriteRender, Rgba};
+
+/// Area of the entity where the particles of a `ParticleEmitter2D` are emitted.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum EmitterShape {
+    /// At the position of the entity
+    Point,
+    /// In a disk of the given radius centered on the entity
+    Circle(f32),
+    /// In a rectangle of the given width and height centered on the entity
+    Rectangle([f32; 2]),
+}
+
+impl Default for EmitterShape {
+    fn default() -> Self {
+        EmitterShape::Point
+    }
+}
+
+/// A particle of a `ParticleEmitter2D`, moved by the `DrawParticles2D` pass from where and when
+/// it was emitted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Particle {
+    pub(crate) position: [f32; 2],
+    pub(crate) velocity: [f32; 2],
+    pub(crate) birth: f32,
+}
+
+/// # ParticleEmitter2D Component
+/// Emits particles drawing a sprite, for fire, smoke, sparks and explosions. You must add the
+/// `ParticleSystem2D` to your dispatcher after the `TransformSystem` to emit the particles, and
+/// the `DrawParticles2D` pass to draw them.
+///
+/// The particles are not entities: the system only records where, when and in which direction
+/// each particle is emitted, and the pass moves and colors all the particles of an emitter on the
+/// GPU with a single draw call. Emitted particles stay where they are in the world when the
+/// entity moves.
+///
+/// Over the lifetime of a particle, its color, its size and the factor of its speed go from the
+/// first to the second value of their pair, and its sprite goes through the `frame_count`
+/// sprites of the sprite sheet following the sprite of `sprite`.
+#[derive(Clone, Debug)]
+pub struct ParticleEmitter2D {
+    /// Sprite of the particles, the first frame of their animation
+    pub sprite: SpriteRender,
+    /// Number of sprites of the animation of the particles, at most 64
+    pub frame_count: usize,
+    /// Area where the particles are emitted, relative to the entity
+    pub shape: EmitterShape,
+    /// Number of particles emitted per second
+    pub rate: f32,
+    /// Maximal number of particles alive at the same time
+    pub max_particles: usize,
+    /// Seconds a particle lives
+    pub lifetime: f32,
+    /// Angle of the direction of the particles in radians, relative to the X axis of the entity
+    pub direction: f32,
+    /// Maximal angle in radians between the direction of a particle and `direction`
+    pub spread: f32,
+    /// Minimal and maximal initial speed of the particles, in units per second
+    pub speed: [f32; 2],
+    /// Acceleration of the particles, in units per second squared
+    pub gravity: [f32; 2],
+    /// Color of the particles when emitted and when they die
+    pub color: [Rgba; 2],
+    /// Scale of the sprite of the particles when emitted and when they die
+    pub size: [f32; 2],
+    /// Factor of the speed of the particles when emitted and when they die
+    pub speed_factor: [f32; 2],
+    /// Whether particles are emitted at `rate`, the emitted particles live on when disabled
+    pub emitting: bool,
+    pub(crate) particles: VecDeque<Particle>,
+    /// Clock of the emitter, the time of birth of the particles
+    pub(crate) time: f32,
+    /// Fraction of a particle left to emit
+    accumulator: f32,
+    /// Number of particles to emit at once on the next frame
+    burst: usize,
+    /// State of the xorshift random number generator, seeded by the system
+    rng: u64,
+}
+
+impl ParticleEmitter2D {
+    /// Creates an emitter of white particles, 10 per second living for a second and going up at
+    /// 50 to 100 units per second in all directions.
+    pub fn new(sprite: SpriteRender) -> Self {
+        ParticleEmitter2D {
+            sprite,
+            frame_count: 1,
+            shape: EmitterShape::Point,
+            rate: 10.0,
+            max_particles: 1000,
+            lifetime: 1.0,
+            direction: PI / 2.0,
+            spread: PI,
+            speed: [50.0, 100.0],
+            gravity: [0.0, 0.0],
+            color: [Rgba::WHITE, Rgba::WHITE],
+            size: [1.0, 1.0],
+            speed_factor: [1.0, 1.0],
+            emitting: true,
+            particles: VecDeque::new(),
+            time: 0.0,
+            accumulator: 0.0,
+            burst: 0,
+            rng: 0,
+        }
+    }
+
+    /// Animates the particles over the given number of sprites.
+    pub fn with_frames(mut self, frame_count: usize) -> Self {
+        self.frame_count = frame_count;
+        self
+    }
+
+    /// Sets the area where the particles are emitted.
+    pub fn with_shape(mut self, shape: EmitterShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    /// Sets the number of particles emitted per second.
+    pub fn with_rate(mut self, rate: f32) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    /// Sets the maximal number of particles alive at the same time.
+    pub fn with_max_particles(mut self, max_particles: usize) -> Self {
+        self.max_particles = max_particles;
+        self
+    }
+
+    /// Sets the seconds a particle lives.
+    pub fn with_lifetime(mut self, lifetime: f32) -> Self {
+        self.lifetime = lifetime;
+        self
+    }
+
+    /// Sets the angle of the direction of the particles and the maximal angle between it and the
+    /// direction of a particle, in radians.
+    pub fn with_direction(mut self, direction: f32, spread: f32) -> Self {
+        self.direction = direction;
+        self.spread = spread;
+        self
+    }
+
+    /// Sets the minimal and maximal initial speed of the particles.
+    pub fn with_speed(mut self, min: f32, max: f32) -> Self {
+        self.speed = [min, max];
+        self
+    }
+
+    /// Sets the acceleration of the particles.
+    pub fn with_gravity(mut self, x: f32, y: f32) -> Self {
+        self.gravity = [x, y];
+        self
+    }
+
+    /// Sets the color of the particles when emitted and when they die.
+    pub fn with_colors(mut self, start: Rgba, end: Rgba) -> Self {
+        self.color = [start, end];
+        self
+    }
+
+    /// Sets the scale of the particles when emitted and when they die.
+    pub fn with_sizes(mut self, start: f32, end: f32) -> Self {
+        self.size = [start, end];
+        self
+    }
+
+    /// Sets the factor of the speed of the particles when emitted and when they die.
+    pub fn with_speed_factors(mut self, start: f32, end: f32) -> Self {
+        self.speed_factor = [start, end];
+        self
+    }
+
+    /// Emits the given number of particles at once on the next frame, even if not `emitting`.
+    pub fn burst(&mut self, count: usize) {
+        self.burst += count;
+    }
+
+    /// Returns the number of particles alive.
+    pub fn particle_count(&self) -> usize {
+        self.particles.len()
+    }
+
+    /// Advances the clock of the emitter, removing the dead particles and emitting the new ones.
+    fn update(&mut self, global: &GlobalTransform, seconds: f32) {
+        self.time += seconds;
+        while let Some(particle) = self.particles.front() {
+            if particle.birth + self.lifetime > self.time {
+                break;
+            }
+            self.particles.pop_front();
+        }
+        // The clock is kept small, for the precision of the ages computed by the shader.
+        if self.time > 1000.0 {
+            for particle in &mut self.particles {
+                particle.birth -= self.time;
+            }
+            self.time = 0.0;
+        }
+
+        let mut count = mem::replace(&mut self.burst, 0);
+        if self.emitting && self.rate > 0.0 {
+            self.accumulator += seconds * self.rate;
+            let emitted = self.accumulator.floor();
+            self.accumulator -= emitted;
+            count += emitted as usize;
+        }
+        for _ in 0..count {
+            if self.particles.len() >= self.max_particles {
+                self.accumulator = 0.0;
+                break;
+            }
+            let particle = self.emit(global);
+            self.particles.push_back(particle);
+        }
+    }
+
+    /// Creates a particle in the shape of the emitter.
+    fn emit(&mut self, global: &GlobalTransform) -> Particle {
+        let local = match self.shape {
+            EmitterShape::Point => [0.0, 0.0],
+            EmitterShape::Circle(radius) => {
+                let (distance, angle) = (radius * self.random().sqrt(), 2.0 * PI * self.random());
+                [distance * angle.cos(), distance * angle.sin()]
+            }
+            EmitterShape::Rectangle([width, height]) => [
+                (self.random() - 0.5) * width,
+                (self.random() - 0.5) * height,
+            ],
+        };
+        let position = global.0 * Vector4::new(local[0], local[1], 0.0, 1.0);
+
+        let angle = self.direction + self.spread * (2.0 * self.random() - 1.0);
+        let speed = self.speed[0] + (self.speed[1] - self.speed[0]) * self.random();
+        let direction = global.0 * Vector4::new(angle.cos(), angle.sin(), 0.0, 0.0);
+        let norm = direction.x.hypot(direction.y);
+        let velocity = if norm > 0.0 {
+            [direction.x / norm * speed, direction.y / norm * speed]
+        } else {
+            [0.0, 0.0]
+        };
+
+        Particle {
+            position: [position.x, position.y],
+            velocity,
+            birth: self.time,
+        }
+    }
+
+    // Random number between 0 and 1, using xorshift.
+    fn random(&mut self) -> f32 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        (self.rng >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+impl Component for ParticleEmitter2D {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// System emitting the particles of the entities with a `ParticleEmitter2D` component.
+#[derive(Default)]
+pub struct ParticleSystem2D;
+
+impl<'a> System<'a> for ParticleSystem2D {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, Time>,
+        ReadStorage<'a, GlobalTransform>,
+        WriteStorage<'a, ParticleEmitter2D>,
+    );
+
+    fn run(&mut self, (entities, time, globals, mut emitters): Self::SystemData) {
+        for (entity, global, emitter) in (&*entities, &globals, &mut emitters).join() {
+            // xorshift must not be seeded with 0
+            if emitter.rng == 0 {
+                emitter.rng = (u64::from(entity.id()) << 32) | 1;
+            }
+            emitter.update(global, time.delta_seconds());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+
+    use amethyst_assets::{AssetStorage, Loader};
+    use rayon::ThreadPoolBuilder;
+
+    use crate::{formats::TextureData, sprite::SpriteSheet, tex::Texture};
+
+    fn emitter() -> ParticleEmitter2D {
+        let pool = ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        let loader = Loader::new(".", Arc::new(pool));
+        let texture = loader.load_from_data(
+            TextureData::color([1.0; 4]),
+            (),
+            &AssetStorage::<Texture>::new(),
+        );
+        let sprite_sheet = SpriteSheet {
+            texture,
+            sprites: Vec::new(),
+            pages: Vec::new(),
+            normal_maps: Vec::new(),
+            sampler: None,
+        };
+        let sprite = SpriteRender {
+            sprite_sheet: loader.load_from_data(sprite_sheet, (), &AssetStorage::new()),
+            sprite_number: 0,
+        };
+        let mut emitter = ParticleEmitter2D::new(sprite);
+        emitter.rng = 1;
+        emitter
+    }
+
+    #[test]
+    fn emits_at_rate_and_removes_dead_particles() {
+        let mut emitter = emitter().with_rate(10.0).with_lifetime(0.5);
+        let global = GlobalTransform::default();
+        emitter.update(&global, 0.25);
+        assert_eq!(emitter.particle_count(), 2);
+        emitter.update(&global, 0.25);
+        assert_eq!(emitter.particle_count(), 5);
+        emitter.emitting = false;
+        emitter.update(&global, 0.25);
+        assert_eq!(emitter.particle_count(), 3);
+        emitter.update(&global, 0.25);
+        assert_eq!(emitter.particle_count(), 0);
+    }
+
+    #[test]
+    fn particles_start_in_shape_with_speed() {
+        let mut emitter = emitter()
+            .with_shape(EmitterShape::Circle(2.0))
+            .with_speed(3.0, 3.0)
+            .with_max_particles(100);
+        emitter.burst(100);
+        emitter.update(&GlobalTransform::default(), 0.0);
+        assert_eq!(emitter.particle_count(), 100);
+        for particle in &emitter.particles {
+            assert!(particle.position[0].hypot(particle.position[1]) <= 2.0);
+            assert!((particle.velocity[0].hypot(particle.velocity[1]) - 3.0).abs() < 1e-4);
+        }
+    }
+}
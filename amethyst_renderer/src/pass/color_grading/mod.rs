@@ -0,0 +1,134 @@
+//! Color grading pass
+
+use amethyst_assets::AssetStorage;
+use amethyst_core::specs::Read;
+use amethyst_error::Error;
+use gfx::{
+    memory::Typed,
+    texture::{FilterMethod, SamplerInfo, WrapMode},
+    IndexBuffer,
+};
+use gfx_core::state::ColorMask;
+use log::warn;
+
+use crate::{
+    color_grading::ColorGrading,
+    pipe::{
+        pass::{Pass, PassData},
+        Effect, NewEffect, Targets,
+    },
+    tex::Texture,
+    transparent::REPLACE,
+    types::{Encoder, Factory, RawShaderResourceView, Sampler, Slice},
+};
+
+static VERT_SRC: &[u8] = include_bytes!("../shaders/vertex/fullscreen.glsl");
+static FRAG_SRC: &[u8] = include_bytes!("../shaders/fragment/color_grading.glsl");
+
+/// Draws the scene rendered to a target to the screen, graded with the lookup table of the
+/// `ColorGrading` resource.
+///
+/// The sprite and UI passes draw to the target given with `with_source`, and this pass is added
+/// to a later stage drawing to the screen. The scene is drawn as is while the color grading is
+/// disabled or its lookup table is not loaded.
+#[derive(Clone, Debug, Default)]
+pub struct DrawColorGrading {
+    source: Option<String>,
+    source_view: Option<RawShaderResourceView>,
+    sampler: Option<Sampler>,
+}
+
+impl DrawColorGrading {
+    /// Create instance of `DrawColorGrading` pass
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Grades the first color buffer of the named target.
+    pub fn with_source<N: Into<String>>(mut self, target: N) -> Self {
+        self.source = Some(target.into());
+        self
+    }
+}
+
+impl<'a> PassData<'a> for DrawColorGrading {
+    type Data = (Read<'a, ColorGrading>, Read<'a, AssetStorage<Texture>>);
+}
+
+impl Pass for DrawColorGrading {
+    fn compile(&mut self, effect: NewEffect<'_>) -> Result<Effect, Error> {
+        use gfx::Factory;
+
+        self.sampler = Some(
+            effect
+                .factory
+                .create_sampler(SamplerInfo::new(FilterMethod::Bilinear, WrapMode::Clamp)),
+        );
+        effect
+            .simple(VERT_SRC, FRAG_SRC)
+            .without_back_face_culling()
+            .with_raw_global("lut_size")
+            .with_raw_global("intensity")
+            .with_texture("source")
+            .with_texture("lut")
+            .with_blended_output("color", ColorMask::all(), REPLACE, None)
+            .build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        _factory: Factory,
+        (grading, tex_storage): <Self as PassData<'a>>::Data,
+    ) {
+        let source = match self.source_view {
+            Some(ref source) => source,
+            None => return,
+        };
+        let sampler = self
+            .sampler
+            .as_ref()
+            .expect("Pass doesn't seem to be compiled.");
+        let intensity = grading.effective_intensity();
+        let lut = grading
+            .lut
+            .as_ref()
+            .and_then(|lut| tex_storage.get(lut))
+            .map(|lut| (lut, intensity))
+            .filter(|_| intensity > 0.0);
+
+        effect.update_global("lut_size", lut.map_or(1, |(lut, _)| lut.size().1) as f32);
+        effect.update_global("intensity", lut.map_or(0.0, |(_, intensity)| intensity));
+        effect.data.textures.push(source.clone());
+        effect.data.samplers.push(sampler.clone());
+        // The source stands in for the lookup table while it is not applied.
+        let lut_view = lut.map_or(source, |(lut, _)| lut.view());
+        effect.data.textures.push(lut_view.clone());
+        effect.data.samplers.push(sampler.clone());
+        let slice = Slice {
+            start: 0,
+            end: 3,
+            base_vertex: 0,
+            instances: None,
+            buffer: IndexBuffer::Auto,
+        };
+        effect.draw(&slice, encoder);
+        effect.clear();
+    }
+
+    fn new_targets(&mut self, targets: &Targets) {
+        let name = match self.source {
+            Some(ref name) => name,
+            None => return,
+        };
+        self.source_view = targets
+            .get(name)
+            .and_then(|target| target.color_buf(0))
+            .and_then(|buffer| buffer.as_input.as_ref())
+            .map(|view| view.raw().clone());
+        if self.source_view.is_none() {
+            warn!("Color grading source target {:?} not found", name);
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! 2D debug shapes pass
+
+use gfx::{pso::buffer::ElemStride, Primitive};
+use gfx_core::state::ColorMask;
+
+use amethyst_core::{
+    nalgebra as na,
+    specs::{Read, ReadStorage, Write},
+    transform::GlobalTransform,
+};
+use amethyst_error::Error;
+
+use crate::{
+    cam::{ActiveCamera, Camera},
+    debug_drawing::DebugDraw2D,
+    mesh::Mesh,
+    pass::util::{get_camera, set_attribute_buffers, set_vertex_args, setup_vertex_args},
+    pipe::{
+        pass::{Pass, PassData},
+        Effect, NewEffect,
+    },
+    transparent::ALPHA,
+    types::{Encoder, Factory},
+    vertex::{PosColor, VertexFormat},
+    Rgba,
+};
+
+static VERT_SRC: &[u8] = include_bytes!("../shaders/vertex/debug_2d.glsl");
+static FRAG_SRC: &[u8] = include_bytes!("../shaders/fragment/debug_2d.glsl");
+
+/// Draws the lines, circles, boxes and polygons submitted to the `DebugDraw2D` resource, then
+/// clears it.
+///
+/// The shapes are drawn with lines of one pixel, over the scene without depth test, so this pass
+/// should be added after the passes drawing the world.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DrawDebug2D;
+
+impl DrawDebug2D {
+    /// Create instance of `DrawDebug2D` pass
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<'a> PassData<'a> for DrawDebug2D {
+    type Data = (
+        Read<'a, ActiveCamera>,
+        ReadStorage<'a, Camera>,
+        ReadStorage<'a, GlobalTransform>,
+        Write<'a, DebugDraw2D>,
+    );
+}
+
+impl Pass for DrawDebug2D {
+    fn compile(&mut self, effect: NewEffect<'_>) -> Result<Effect, Error> {
+        let mut builder = effect.simple(VERT_SRC, FRAG_SRC);
+        builder.with_raw_vertex_buffer(PosColor::ATTRIBUTES, PosColor::size() as ElemStride, 0);
+        setup_vertex_args(&mut builder);
+        builder.with_primitive_type(Primitive::LineList);
+        builder.with_blended_output("color", ColorMask::all(), ALPHA, None);
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        mut factory: Factory,
+        (active, camera, global, mut debug_draw): <Self as PassData<'a>>::Data,
+    ) {
+        if debug_draw.vertices.is_empty() {
+            return;
+        }
+
+        let mesh = Mesh::build(&debug_draw.vertices)
+            .build(&mut factory)
+            .expect("Failed to create 2D debug shapes mesh");
+        debug_draw.clear();
+        if !set_attribute_buffers(effect, &mesh, &[PosColor::ATTRIBUTES]) {
+            effect.clear();
+            return;
+        }
+
+        let camera = get_camera(active, &camera, &global);
+        set_vertex_args(
+            effect,
+            encoder,
+            camera,
+            &GlobalTransform(na::one()),
+            Rgba::WHITE,
+        );
+
+        effect.draw(mesh.slice(), encoder);
+        effect.clear();
+    }
+}
@@ -8,7 +8,7 @@ use log::{debug, trace};
 
 use amethyst_core::{
     nalgebra as na,
-    specs::{Join, Read, ReadStorage, Write, WriteStorage},
+    specs::{Entities, Join, Read, ReadExpect, ReadStorage, Write, WriteStorage},
     transform::GlobalTransform,
 };
 use amethyst_error::Error;
@@ -16,12 +16,15 @@ use amethyst_error::Error;
 use crate::{
     cam::{ActiveCamera, Camera},
     debug_drawing::{DebugLine, DebugLines, DebugLinesComponent},
+    hidden::{Hidden, HiddenPropagate},
     mesh::Mesh,
     pass::util::{get_camera, set_attribute_buffers, set_vertex_args, setup_vertex_args},
     pipe::{
         pass::{Pass, PassData},
         DepthMode, Effect, NewEffect,
     },
+    render_layers::{Layer, RenderLayers},
+    resources::ScreenDimensions,
     types::{Encoder, Factory},
     vertex::{Color, Normal, Position, Query},
     Rgba,
@@ -45,6 +48,11 @@ impl Default for DebugLinesParams {
 
 /// Draw several simple lines for debugging
 ///
+/// The lines of the `DebugLinesComponent`s are drawn by increasing order of their `Layer` in the
+/// `RenderLayers`, the ones without declared layer being in order `0`, and the lines of the
+/// `DebugLines` resource are drawn last. The screen lines of the resource are drawn on the near
+/// plane of the camera, over the scene. The components of `Hidden` entities are not drawn.
+///
 /// See the [crate level documentation](index.html) for information about interleaved and separate
 /// passes.
 ///
@@ -72,12 +80,18 @@ where
     V: Query<(Position, Color, Normal)>,
 {
     type Data = (
+        Entities<'a>,
         Read<'a, ActiveCamera>,
         ReadStorage<'a, Camera>,
         ReadStorage<'a, GlobalTransform>,
         WriteStorage<'a, DebugLinesComponent>, // DebugLines components
         Option<Write<'a, DebugLines>>,         // DebugLines resource
         Read<'a, DebugLinesParams>,
+        Read<'a, RenderLayers>,
+        ReadStorage<'a, Layer>,
+        Option<ReadExpect<'a, ScreenDimensions>>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, HiddenPropagate>,
     );
 }
 
@@ -106,18 +120,55 @@ where
         encoder: &mut Encoder,
         effect: &mut Effect,
         mut factory: Factory,
-        (active, camera, global, lines_components, lines_resource, lines_params): <Self as PassData<'a>>::Data,
+        (
+            entities,
+            active,
+            camera,
+            global,
+            lines_components,
+            lines_resource,
+            lines_params,
+            render_layers,
+            layer,
+            screen_dimensions,
+            hidden,
+            hidden_prop,
+        ): <Self as PassData<'a>>::Data,
     ) {
         trace!("Drawing debug lines pass");
+        let camera = get_camera(active, &camera, &global);
         let debug_lines = {
             let mut lines = Vec::<DebugLine>::new();
 
-            for debug_lines_component in (&lines_components).join() {
+            let mut components = (&*entities, &lines_components, !&hidden, !&hidden_prop)
+                .join()
+                .map(|(entity, component, _, _)| {
+                    let order = layer
+                        .get(entity)
+                        .and_then(|layer| render_layers.order(&layer.0))
+                        .unwrap_or(0);
+                    (order, component)
+                })
+                .collect::<Vec<_>>();
+            // The sort is stable, keeping the order of the components of a layer.
+            components.sort_by_key(|(order, _)| *order);
+            for (_, debug_lines_component) in components {
                 lines.extend(&debug_lines_component.lines);
             }
 
             if let Some(mut lines_resource) = lines_resource {
                 lines.append(&mut lines_resource.lines);
+                let screen_lines = lines_resource.screen_lines.drain(..);
+                if let Some(to_world) = screen_to_world(camera, screen_dimensions) {
+                    lines.extend(screen_lines.map(|line| {
+                        let start = to_world(line.position);
+                        DebugLine {
+                            position: start,
+                            color: line.color,
+                            normal: to_world(line.position + line.normal) - start,
+                        }
+                    }));
+                }
             };
 
             lines
@@ -128,7 +179,6 @@ where
             return;
         }
 
-        let camera = get_camera(active, &camera, &global);
         effect.update_global(
             "camera_position",
             camera
@@ -160,3 +210,23 @@ where
         effect.clear();
     }
 }
+
+/// Returns the function placing points in pixels from the top-left corner of the screen on the
+/// near plane of the camera.
+fn screen_to_world(
+    camera: Option<(&Camera, &GlobalTransform)>,
+    screen_dimensions: Option<ReadExpect<'_, ScreenDimensions>>,
+) -> Option<impl Fn(na::Vector3<f32>) -> na::Vector3<f32>> {
+    let (camera, global) = camera?;
+    let (width, height) = screen_dimensions.map(|dim| (dim.width(), dim.height()))?;
+    let inverse = (camera.proj * global.0.try_inverse()?).try_inverse()?;
+    Some(move |pixel: na::Vector3<f32>| {
+        let ndc = na::Point3::new(
+            pixel.x / width * 2.0 - 1.0,
+            1.0 - pixel.y / height * 2.0,
+            // Slightly behind the near plane, to not be clipped.
+            -0.999,
+        );
+        inverse.transform_point(&ndc).coords
+    })
+}
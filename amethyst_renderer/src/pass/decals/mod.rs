@@ -0,0 +1,173 @@
+//! Decals pass
+
+use gfx::pso::buffer::ElemStride;
+use gfx_core::state::ColorMask;
+use log::warn;
+
+use amethyst_assets::{AssetStorage, Handle};
+use amethyst_core::{
+    nalgebra::{self as na, Matrix4, Vector3},
+    specs::{Read, Write},
+    transform::GlobalTransform,
+};
+use amethyst_error::Error;
+
+use crate::{
+    cam::{Camera, Projection},
+    decals::Decals,
+    mesh::Mesh,
+    pass::util::{add_texture, set_attribute_buffers, set_vertex_args, setup_vertex_args},
+    pipe::{
+        pass::{Pass, PassData},
+        Effect, NewEffect, Targets,
+    },
+    sprite::SpriteSheet,
+    tex::Texture,
+    transparent::ALPHA,
+    types::{Encoder, Factory, RenderTargetView},
+    vertex::{PosColorTex, VertexFormat},
+    Rgba,
+};
+
+static VERT_SRC: &[u8] = include_bytes!("../shaders/vertex/decal.glsl");
+static FRAG_SRC: &[u8] = include_bytes!("../shaders/fragment/decal.glsl");
+
+/// Draws the decals stamped into a layer of the `Decals` resource into its render target, once.
+///
+/// The pass is added to a stage drawing to the render target of the layer, which must not clear
+/// it: the target is cleared by the pass when it is created, then keeps the decals. Decals are
+/// drawn once their sprite sheet and texture are loaded.
+#[derive(Clone, Debug)]
+pub struct DrawDecals {
+    target: String,
+    output: Option<RenderTargetView>,
+    cleared: bool,
+}
+
+impl DrawDecals {
+    /// Create instance of `DrawDecals` pass drawing the decals of the layer of the named target
+    pub fn new<N: Into<String>>(target: N) -> Self {
+        DrawDecals {
+            target: target.into(),
+            output: None,
+            cleared: false,
+        }
+    }
+}
+
+impl<'a> PassData<'a> for DrawDecals {
+    type Data = (
+        Write<'a, Decals>,
+        Read<'a, AssetStorage<SpriteSheet>>,
+        Read<'a, AssetStorage<Texture>>,
+    );
+}
+
+impl Pass for DrawDecals {
+    fn compile(&mut self, effect: NewEffect<'_>) -> Result<Effect, Error> {
+        let mut builder = effect.simple(VERT_SRC, FRAG_SRC);
+        builder.with_raw_vertex_buffer(
+            PosColorTex::ATTRIBUTES,
+            PosColorTex::size() as ElemStride,
+            0,
+        );
+        setup_vertex_args(&mut builder);
+        builder.with_texture("albedo");
+        builder.with_blended_output("color", ColorMask::all(), ALPHA, None);
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        mut factory: Factory,
+        (mut decals, sheet_storage, tex_storage): <Self as PassData<'a>>::Data,
+    ) {
+        if !self.cleared {
+            if let Some(ref output) = self.output {
+                encoder.clear(output, [0.0; 4]);
+                self.cleared = true;
+            }
+        }
+
+        let (min, max) = match decals.area(&self.target) {
+            Some(area) => area,
+            None => return,
+        };
+        let pending = match decals.pending_mut(&self.target) {
+            Some(pending) => pending,
+            None => return,
+        };
+
+        let mut batches = Vec::<(Handle<Texture>, Vec<PosColorTex>)>::new();
+        // The decals whose sprite sheet or texture is not loaded yet are kept for a later frame.
+        pending.retain(|decal| {
+            let sheet = match sheet_storage.get(&decal.sprite.sprite_sheet) {
+                Some(sheet) => sheet,
+                None => return true,
+            };
+            let found = sheet
+                .sprites
+                .get(decal.sprite.sprite_number)
+                .and_then(|sprite| Some((sprite, sheet.page(sprite.page)?)));
+            let (sprite, texture) = match found {
+                Some((_, texture)) if tex_storage.get(texture).is_none() => return true,
+                Some(found) => found,
+                None => {
+                    warn!(
+                        "Decal sprite {} is missing from its sprite sheet",
+                        decal.sprite.sprite_number
+                    );
+                    return false;
+                }
+            };
+            let vertices = decal.vertices(sprite);
+            match batches.iter_mut().find(|(handle, _)| handle == texture) {
+                Some((_, batch)) => batch.extend_from_slice(&vertices),
+                None => batches.push((texture.clone(), vertices.to_vec())),
+            }
+            false
+        });
+
+        let camera = Camera::from(Projection::orthographic(min[0], max[0], min[1], max[1]));
+        let view = GlobalTransform(Matrix4::new_translation(&Vector3::new(0.0, 0.0, 1.0)));
+        for (texture, vertices) in batches {
+            let texture = tex_storage
+                .get(&texture)
+                .expect("Unreachable: Texture is loaded");
+            let mesh = Mesh::build(vertices)
+                .build(&mut factory)
+                .expect("Failed to create decals mesh");
+            if !set_attribute_buffers(effect, &mesh, &[PosColorTex::ATTRIBUTES]) {
+                effect.clear();
+                return;
+            }
+            set_vertex_args(
+                effect,
+                encoder,
+                Some((&camera, &view)),
+                &GlobalTransform(na::one()),
+                Rgba::WHITE,
+            );
+            add_texture(effect, texture);
+            effect.draw(mesh.slice(), encoder);
+            effect.clear();
+        }
+    }
+
+    fn new_targets(&mut self, targets: &Targets) {
+        let output = targets
+            .get(&self.target)
+            .and_then(|target| target.color_buf(0))
+            .map(|buffer| buffer.as_output.clone());
+        if output.is_none() {
+            warn!("Decals target {:?} not found", self.target);
+        }
+        // Targets with a fixed size are kept when the window is resized, with their decals.
+        if output != self.output {
+            self.output = output;
+            self.cleared = false;
+        }
+    }
+}
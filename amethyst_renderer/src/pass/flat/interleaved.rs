@@ -177,6 +177,7 @@ where
                         Some(global),
                         &[V::QUERIED_ATTRIBUTES],
                         &TEXTURES,
+                        None,
                     );
                 }
             }
@@ -204,6 +205,7 @@ where
                         Some(global),
                         &[V::QUERIED_ATTRIBUTES],
                         &TEXTURES,
+                        None,
                     );
                 }
 
@@ -223,6 +225,7 @@ where
                             global.get(*entity),
                             &[V::QUERIED_ATTRIBUTES],
                             &TEXTURES,
+                            None,
                         );
                     }
                 }
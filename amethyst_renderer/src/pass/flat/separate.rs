@@ -202,6 +202,7 @@ impl Pass for DrawFlatSeparate {
                         Some(global),
                         &ATTRIBUTES,
                         &TEXTURES,
+                        None,
                     );
                 }
             }
@@ -230,6 +231,7 @@ impl Pass for DrawFlatSeparate {
                         Some(global),
                         &ATTRIBUTES,
                         &TEXTURES,
+                        None,
                     );
                 }
 
@@ -249,6 +251,7 @@ impl Pass for DrawFlatSeparate {
                             global.get(*entity),
                             &ATTRIBUTES,
                             &TEXTURES,
+                            None,
                         );
                     }
                 }
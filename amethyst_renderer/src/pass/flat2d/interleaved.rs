@@ -1,47 +1,119 @@
 //! Flat forward drawing pass that mimics a blit.
 
+use std::{cmp::Ordering, mem, ops::Index};
+
 use derivative::Derivative;
 use gfx::pso::buffer::ElemStride;
-use gfx_core::state::{Blend, ColorMask};
-use glsl_layout::Uniform;
+use gfx_core::state::{Blend, ColorMask, Comparison, Stencil, StencilOp};
+use glsl_layout::{int, uint, Uniform};
+use hibitset::BitSet;
 use log::warn;
 
 use amethyst_assets::{AssetStorage, Handle};
 use amethyst_core::{
-    nalgebra::Vector4,
-    specs::prelude::{Join, Read, ReadStorage},
-    transform::GlobalTransform,
+    nalgebra::{Matrix4, Vector3, Vector4},
+    specs::prelude::{Entities, Entity, Join, Read, ReadExpect, ReadStorage, Write},
+    transform::{GlobalTransform, Parent},
+    Time,
 };
 use amethyst_error::Error;
 
 use crate::{
-    cam::{ActiveCamera, Camera},
+    cam::{ActiveCamera, Camera, Projection},
     hidden::{Hidden, HiddenPropagate},
+    light::Light,
     mesh::MeshHandle,
-    pass::util::{
-        add_texture, default_transparency, get_camera, set_view_args, setup_textures, ViewArgs,
+    offscreen::OffscreenCamera,
+    pass::{
+        shaded_util::{set_light_args, setup_light_buffers},
+        util::{
+            add_texture, add_texture_with_sampler, default_transparency, get_camera, set_view_args,
+            setup_textures, ViewArgs,
+        },
     },
     pipe::{
         pass::{Pass, PassData},
-        DepthMode, Effect, NewEffect,
+        DepthMode, Effect, EffectBuilder, NewEffect,
+    },
+    resources::{AmbientColor, ScreenDimensions},
+    sprite::{
+        Billboard, BlendMode, DrawOrder, Flipped, NineSlice, Palette, ScreenSpace, SpriteDeform,
+        SpriteMask, SpriteMaterialOverride, SpriteOutline, SpritePart, SpriteRender, SpriteSheet,
     },
-    sprite::{Flipped, SpriteRender, SpriteSheet},
+    sprite_stats::{SpriteRenderCounts, SpriteRenderStats},
+    sprite_trail::SpriteTrail,
     sprite_visibility::SpriteVisibility,
-    tex::{Texture, TextureHandle},
-    types::{Encoder, Factory, Slice},
+    tex::{SamplerInfo, Texture, TextureHandle},
+    types::{Encoder, Factory, Sampler, Slice},
     vertex::{Attributes, Query, VertexFormat},
-    Color, Rgba,
+    Color, Rgba, ALPHA,
 };
 
 use super::*;
 
+/// Z coordinate of the camera of the sprites in screen space, which are visible from
+/// `-SCREEN_CAMERA_Z` to just below it.
+const SCREEN_CAMERA_Z: f32 = 1000.0;
+
+/// Stencil value of the first sprite mask, above the value the stencil buffer is cleared to with
+/// the depth buffer.
+const FIRST_MASK_STENCIL: u8 = 2;
+
 /// Draws sprites on a 2D quad.
+///
+/// Sprites and images sharing a texture are drawn with a single instanced draw call. The opaque
+/// ones are reordered by texture to share as many draw calls as possible.
+///
+/// The sprites with a `DrawOrder` are drawn after the others, in that order.
+///
+/// The sprites with a `BlendMode` are blended with it when transparency is enabled.
+///
+/// The sprites and images in `ScreenSpace` are only drawn by a pass created with
+/// `with_screen_space(true)`.
+///
+/// The sprites and images restricted by a `SpriteMask` are drawn after the others.
+///
+/// The sprites and images with a `SpriteOutline` are drawn over their outline.
+///
+/// The sprites and images with a `Palette` are drawn in indexed colors.
+///
+/// The sprites and images with a `SpriteDeform` have the corners of their quad moved.
+///
+/// The sprites and images with a `SpriteTrail` are drawn over their faded copies.
+///
+/// The sprites and images with a `Billboard` are turned to face the camera.
+///
+/// The sprites and images with a `SpriteMaterialOverride` are drawn with the shader of the pass
+/// registered with `with_sprite_shader` under its name.
+///
+/// The sprites and images are shaded by the `Light`s when the pass is created with
+/// `with_lighting(true)`.
 #[derive(Derivative, Clone, Debug)]
 #[derivative(Default(bound = "Self: Pass"))]
 pub struct DrawFlat2D {
     #[derivative(Default(value = "default_transparency()"))]
     transparency: Option<(ColorMask, Blend, Option<DepthMode>)>,
     batch: TextureBatch,
+    /// Variants of the effect by blend mode, whether the sprites are masked and whether they
+    /// have a palette, for the sprites not drawn with the main effect
+    effect_variants: Vec<((BlendMode, bool, bool), Effect)>,
+    /// Sprite masks writing their stencil value, drawn before the sprites
+    mask_batch: TextureBatch,
+    /// Effect writing the sprite masks to the stencil buffer, if it is supported
+    mask_effect: Option<Effect>,
+    /// Effect drawing the outlines of the sprites, set when the pass is compiled
+    outline_effect: Option<Effect>,
+    pixel_snapping: bool,
+    screen_space: bool,
+    lighting: bool,
+    /// Names and fragment shaders of the `SpriteMaterialOverride`s
+    sprite_shaders: Vec<(String, &'static [u8])>,
+    /// Effects of the sprite shaders by index and whether the sprites are masked
+    shader_effects: Vec<((usize, bool), Effect)>,
+    /// Target of the `OffscreenCamera` used instead of the active camera
+    offscreen_camera: Option<String>,
+    /// `DrawOrder` layers of the drawn sprites, all of them if `None`
+    layers: Option<Vec<i32>>,
 }
 
 impl DrawFlat2D
@@ -80,48 +152,226 @@ where
         self
     }
 
+    /// Snaps the sprites and images to whole pixels of the screen, which is disabled by default.
+    ///
+    /// The bottom left corner of each quad is moved to the nearest pixel, so pixel art doesn't
+    /// shimmer when the sprites or the camera move by fractions of pixels. Texels are drawn on
+    /// whole pixels when the camera shows an integer number of pixels per texel, such as with the
+    /// `PixelsPerUnit` mode of `CameraOrtho` in `amethyst_utils`.
+    pub fn with_pixel_snapping(mut self, snap: bool) -> Self {
+        self.pixel_snapping = snap;
+        self
+    }
+
+    /// Draws only the sprites and images in `ScreenSpace`, which is disabled by default.
+    ///
+    /// Their `GlobalTransform` is in pixels from the bottom left corner of the screen, regardless
+    /// of the camera, so HUD icons can use sprite sheets. They are drawn without depth test from
+    /// the lowest to the highest `DrawOrder`, then Z coordinate, so this pass should be added after
+    /// the passes drawing the world.
+    pub fn with_screen_space(mut self, screen_space: bool) -> Self {
+        self.screen_space = screen_space;
+        self
+    }
+
+    /// Shades the sprites and images by the point and directional `Light`s and the `AmbientColor`,
+    /// which is disabled by default.
+    ///
+    /// The sprites are shaded with the normals of the normal map of their page in the sprite
+    /// sheet, or as if facing the camera without one. Lights should be placed in front of the
+    /// sprites, at a positive Z coordinate relative to them. Outlines and sprites with a
+    /// `Palette` are not shaded.
+    pub fn with_lighting(mut self, lighting: bool) -> Self {
+        self.lighting = lighting;
+        self
+    }
+
+    /// Registers a fragment shader drawing the sprites and images with a `SpriteMaterialOverride`
+    /// of the given name.
+    ///
+    /// The shader receives the same inputs as the default sprite shader, the `albedo` texture and
+    /// the `tex_uv` and `color` of the `VertexData` block, with the parameters of the override in
+    /// the `params` of an `in MaterialData { vec4 params; } material;` block. It writes the
+    /// `color` output.
+    pub fn with_sprite_shader<S: Into<String>>(mut self, name: S, frag_src: &'static [u8]) -> Self {
+        self.sprite_shaders.push((name.into(), frag_src));
+        self
+    }
+
+    /// Draws the view of the `OffscreenCamera` of the named render target instead of the active
+    /// camera, for minimaps and screens in the scene.
+    ///
+    /// The pass is added to a stage drawing to that target, and the `RenderTargetTextures`
+    /// resource makes the target a texture. The `SpriteVisibility` of the active camera is not
+    /// used by this pass, which draws the sprites without culling.
+    pub fn with_offscreen_camera<N: Into<String>>(mut self, target: N) -> Self {
+        self.offscreen_camera = Some(target.into());
+        self
+    }
+
+    /// Draws only the sprites and images of the given `DrawOrder` layers, the ones without
+    /// `DrawOrder` being in layer `0`.
+    pub fn with_layers(mut self, layers: Vec<i32>) -> Self {
+        self.layers = Some(layers);
+        self
+    }
+
     fn attributes() -> Attributes<'static> {
-        <SpriteInstance as Query<(DirX, DirY, Pos, OffsetU, OffsetV, Depth, Color)>>::QUERIED_ATTRIBUTES
+        <SpriteInstance as Query<(
+            DirX,
+            DirY,
+            Pos,
+            OffsetU,
+            OffsetV,
+            Depth,
+            Color,
+            DeformBottom,
+            DeformTop,
+            MaterialParams,
+        )>>::QUERIED_ATTRIBUTES
     }
 }
 
 impl<'a> PassData<'a> for DrawFlat2D {
     type Data = (
+        Entities<'a>,
         Read<'a, ActiveCamera>,
-        ReadStorage<'a, Camera>,
+        (ReadStorage<'a, Camera>, ReadStorage<'a, OffscreenCamera>),
         Read<'a, AssetStorage<SpriteSheet>>,
         Read<'a, AssetStorage<Texture>>,
         Option<Read<'a, SpriteVisibility>>,
-        ReadStorage<'a, Hidden>,
-        ReadStorage<'a, HiddenPropagate>,
+        (ReadStorage<'a, Hidden>, ReadStorage<'a, HiddenPropagate>),
         ReadStorage<'a, SpriteRender>,
         ReadStorage<'a, GlobalTransform>,
         ReadStorage<'a, TextureHandle>,
         ReadStorage<'a, Flipped>,
         ReadStorage<'a, MeshHandle>,
         ReadStorage<'a, Rgba>,
+        ReadStorage<'a, DrawOrder>,
+        ReadStorage<'a, NineSlice>,
+        ReadStorage<'a, BlendMode>,
+        ReadStorage<'a, ScreenSpace>,
+        ReadStorage<'a, SpriteMask>,
+        ReadStorage<'a, SpriteOutline>,
+        ReadStorage<'a, Palette>,
+        ReadStorage<'a, SpriteDeform>,
+        ReadStorage<'a, Parent>,
+        Option<ReadExpect<'a, ScreenDimensions>>,
+        (ReadStorage<'a, Light>, Read<'a, AmbientColor>),
+        (
+            ReadStorage<'a, SpriteTrail>,
+            ReadStorage<'a, SpriteMaterialOverride>,
+            ReadStorage<'a, Billboard>,
+        ),
+        (Read<'a, Time>, Write<'a, SpriteRenderStats>),
     );
 }
 
 impl Pass for DrawFlat2D {
-    fn compile(&mut self, effect: NewEffect<'_>) -> Result<Effect, Error> {
-        use std::mem;
-
-        let mut builder = effect.simple(VERT_SRC, FRAG_SRC);
-        builder
-            .without_back_face_culling()
-            .with_raw_constant_buffer(
-                "ViewArgs",
-                mem::size_of::<<ViewArgs as Uniform>::Std140>(),
-                1,
-            )
-            .with_raw_vertex_buffer(Self::attributes(), SpriteInstance::size() as ElemStride, 1);
-        setup_textures(&mut builder, &TEXTURES);
+    fn compile(&mut self, mut effect: NewEffect<'_>) -> Result<Effect, Error> {
+        // The depth buffer of the metal backend has no stencil.
+        let masks_supported = cfg!(not(feature = "metal"));
+        let masked = Stencil::new(
+            Comparison::Equal,
+            !0,
+            (StencilOp::Keep, StencilOp::Keep, StencilOp::Keep),
+        );
+
+        self.mask_effect = None;
+        if masks_supported {
+            let mut builder = sprite_effect_builder(effect.reborrow(), MASK_FRAG_SRC);
+            builder
+                .with_blended_output("color", ColorMask::empty(), ALPHA, None)
+                .with_output_stencil(Stencil::new(
+                    Comparison::Always,
+                    !0,
+                    (StencilOp::Keep, StencilOp::Keep, StencilOp::Replace),
+                ));
+            self.mask_effect = Some(builder.build()?);
+        }
+
+        // Sprites in screen space are drawn over the world, ignoring its depth.
+        let screen_space = self.screen_space;
+        let depth = |depth| if screen_space { None } else { depth };
+
+        let mut builder = sprite_effect_builder(effect.reborrow(), OUTLINE_FRAG_SRC);
         match self.transparency {
-            Some((mask, blend, depth)) => builder.with_blended_output("color", mask, blend, depth),
-            None => builder.with_output("color", Some(DepthMode::LessEqualWrite)),
+            Some((mask, blend, transparency_depth)) => {
+                builder.with_blended_output("color", mask, blend, depth(transparency_depth))
+            }
+            None => builder.with_output("color", depth(Some(DepthMode::LessEqualWrite))),
         };
-        builder.build()
+        self.outline_effect = Some(builder.build()?);
+
+        self.shader_effects.clear();
+        for (index, &(_, frag_src)) in self.sprite_shaders.iter().enumerate() {
+            let mut builder = sprite_effect_builder(effect.reborrow(), frag_src);
+            match self.transparency {
+                Some((mask, blend, transparency_depth)) => {
+                    builder.with_blended_output("color", mask, blend, depth(transparency_depth))
+                }
+                None => builder.with_output("color", depth(Some(DepthMode::LessEqualWrite))),
+            };
+            self.shader_effects.push(((index, false), builder.build()?));
+            if masks_supported {
+                builder.with_output_stencil(masked);
+                self.shader_effects.push(((index, true), builder.build()?));
+            }
+        }
+
+        self.effect_variants.clear();
+        let mut main_effect = None;
+        for &paletted in &[false, true] {
+            let mut builder = if paletted {
+                let mut builder = sprite_effect_builder(effect.reborrow(), PALETTE_FRAG_SRC);
+                builder.with_texture("palette").with_raw_constant_buffer(
+                    "PaletteArgs",
+                    mem::size_of::<<PaletteArgs as Uniform>::Std140>(),
+                    1,
+                );
+                builder
+            } else if self.lighting {
+                let mut builder = sprite_effect_builder(effect.reborrow(), LIT_FRAG_SRC);
+                builder.with_texture("normal_map").with_raw_constant_buffer(
+                    "SpriteLightArgs",
+                    mem::size_of::<<SpriteLightArgs as Uniform>::Std140>(),
+                    1,
+                );
+                setup_light_buffers(&mut builder);
+                builder
+            } else {
+                sprite_effect_builder(effect.reborrow(), FRAG_SRC)
+            };
+            match self.transparency {
+                Some((mask, blend, transparency_depth)) => {
+                    builder.with_blended_output("color", mask, blend, depth(transparency_depth))
+                }
+                None => builder.with_output("color", depth(Some(DepthMode::LessEqualWrite))),
+            };
+            for &masked_variant in &[false, true] {
+                if masked_variant {
+                    if !masks_supported {
+                        break;
+                    }
+                    builder.with_output_stencil(masked);
+                }
+                for &mode in &[BlendMode::Alpha, BlendMode::Additive, BlendMode::Multiply] {
+                    // Without transparency, the blend modes of the sprites are ignored.
+                    if let Some((_, blend, _)) = self.transparency {
+                        builder.with_output_blend(match mode {
+                            BlendMode::Alpha => blend,
+                            _ => mode.blend(),
+                        });
+                    }
+                    let variant = builder.build()?;
+                    match (mode, masked_variant, paletted) {
+                        (BlendMode::Alpha, false, false) => main_effect = Some(variant),
+                        key => self.effect_variants.push((key, variant)),
+                    }
+                }
+            }
+        }
+        Ok(main_effect.expect("Unreachable: The main effect is built with the variants"))
     }
 
     fn apply<'a, 'b: 'a>(
@@ -130,141 +380,429 @@ impl Pass for DrawFlat2D {
         effect: &mut Effect,
         mut factory: Factory,
         (
+            entities,
             active,
-            camera,
+            (camera, offscreen_camera),
             sprite_sheet_storage,
             tex_storage,
             visibility,
-            hidden,
-            hidden_prop,
+            (hidden, hidden_prop),
             sprite_render,
             global,
             texture_handle,
             flipped,
             mesh,
             rgba,
+            draw_order,
+            nine_slice,
+            blend_mode,
+            screen_space,
+            sprite_mask,
+            sprite_outline,
+            palette,
+            sprite_deform,
+            parents,
+            screen_dimensions,
+            (light, ambient_color),
+            (sprite_trail, material_override, billboard),
+            (time, mut stats),
         ): <Self as PassData<'a>>::Data,
     ) {
-        let camera = get_camera(active, &camera, &global);
+        let screen_camera = match screen_dimensions {
+            Some(ref screen_dimensions) if self.screen_space => {
+                Some(screen_camera(screen_dimensions))
+            }
+            _ => None,
+        };
+        let camera = match screen_camera {
+            Some((ref camera, ref transform)) => Some((camera, transform)),
+            None => match self.offscreen_camera {
+                Some(ref target) => (&camera, &global, &offscreen_camera)
+                    .join()
+                    .find(|(_, _, offscreen)| offscreen.target == *target)
+                    .map(|(camera, global, _)| (camera, global)),
+                None => get_camera(active, &camera, &global),
+            },
+        };
+        // The visibility of the sprites is computed for the active camera only.
+        let visibility = visibility.filter(|_| self.offscreen_camera.is_none());
+        let layers = &self.layers;
+        let in_layers = |draw_order: Option<&DrawOrder>| {
+            let layer = draw_order.map_or(0, |draw_order| draw_order.layer);
+            layers
+                .as_ref()
+                .map_or(true, |layers| layers.contains(&layer))
+        };
+        // The billboards are turned towards the camera before being added to the batches.
+        let camera_global = camera
+            .filter(|_| !self.screen_space)
+            .map(|(_, global)| *global);
+        let face = |global: &GlobalTransform, billboard: Option<&Billboard>| match (
+            billboard,
+            camera_global.as_ref(),
+        ) {
+            (Some(billboard), Some(camera)) => billboard.face(global, camera),
+            _ => *global,
+        };
+        let snap = match screen_dimensions {
+            Some(ref screen_dimensions) if self.pixel_snapping => {
+                PixelSnap::new(camera, screen_dimensions)
+            }
+            _ => None,
+        };
+
+        let shaders = &self.sprite_shaders;
+        // Sprites, and images that are not drawn on a mesh.
+        let drawn = |entity: Entity| {
+            sprite_render.contains(entity)
+                || (texture_handle.contains(entity) && !mesh.contains(entity))
+        };
+        // Adds a sprite or an image with its components, the only place where they are read.
+        // The masks are drawn by their shape only, without outline, palette, trail or shader.
+        let add_entity = |batch: &mut TextureBatch, entity: Entity, mask: u8, is_mask: bool| {
+            let palette = palette.get(entity).filter(|_| !is_mask);
+            let outline = sprite_outline.get(entity).filter(|_| !is_mask);
+            let trail = sprite_trail.get(entity).filter(|_| !is_mask);
+            let material =
+                sprite_material(shaders, material_override.get(entity)).filter(|_| !is_mask);
+            let global = global
+                .get(entity)
+                .map(|global| face(global, billboard.get(entity)));
+            let components = SpriteComponents {
+                flipped: flipped.get(entity),
+                rgba: rgba.get(entity),
+                nine_slice: nine_slice.get(entity),
+                blend_mode: blend_mode.get(entity),
+                palette,
+                deform: sprite_deform.get(entity),
+                outline,
+                trail,
+                material,
+            };
+            if let Some(sprite_render) = sprite_render.get(entity) {
+                batch.add_sprite(
+                    sprite_render,
+                    global.as_ref(),
+                    components,
+                    mask,
+                    &sprite_sheet_storage,
+                    &tex_storage,
+                );
+            } else if let Some(texture_handle) = texture_handle.get(entity) {
+                batch.add_image(
+                    texture_handle,
+                    global.as_ref(),
+                    components,
+                    mask,
+                    &tex_storage,
+                )
+            }
+        };
 
+        // The masks of this pass are written to the stencil buffer, and the sprites they restrict
+        // are drawn after the others.
+        let mut masks = Vec::new();
+        let mut masked = BitSet::new();
+        let mut masked_sprites = Vec::new();
+        if self.mask_effect.is_some() {
+            for (entity, _) in (&*entities, &sprite_mask).join() {
+                if screen_space.contains(entity) != self.screen_space {
+                    continue;
+                }
+                if masks.len() > usize::from(u8::max_value() - FIRST_MASK_STENCIL) {
+                    warn!("Too many sprite masks, the others are ignored.");
+                    break;
+                }
+                let stencil = FIRST_MASK_STENCIL + masks.len() as u8;
+                masks.push((entity, stencil));
+                masked.add(entity.id());
+                if !hidden.contains(entity) && !hidden_prop.contains(entity) {
+                    add_entity(&mut self.mask_batch, entity, stencil, true);
+                }
+            }
+        }
+        if !masks.is_empty() {
+            for (entity, parent, global, _, _) in
+                (&*entities, &parents, &global, !&hidden, !&hidden_prop).join()
+            {
+                if !drawn(entity) || screen_space.contains(entity) != self.screen_space {
+                    continue;
+                }
+                if let Some(stencil) = find_mask(parent.entity, &parents, &masks) {
+                    masked.add(entity.id());
+                    if in_layers(draw_order.get(entity)) {
+                        let draw_order = draw_order.get(entity).cloned().unwrap_or_default();
+                        masked_sprites.push(((entity, stencil), draw_order, global.0[(2, 3)]));
+                    }
+                }
+            }
+            sort_by_draw_order(&mut masked_sprites);
+        }
+
+        // The sprites without `DrawOrder` are in layer 0.
+        let unordered = in_layers(None);
         match visibility {
-            None => {
-                for (sprite_render, global, flipped, rgba, _, _) in (
-                    &sprite_render,
+            _ if self.screen_space => {
+                let mut ordered = (
+                    &*entities,
                     &global,
-                    flipped.maybe(),
-                    rgba.maybe(),
+                    &screen_space,
                     !&hidden,
                     !&hidden_prop,
+                    !&masked,
                 )
                     .join()
-                {
-                    self.batch.add_sprite(
-                        sprite_render,
-                        Some(global),
-                        flipped,
-                        rgba,
-                        &sprite_sheet_storage,
-                        &tex_storage,
-                    );
+                    .filter(|(entity, _, _, _, _, _)| {
+                        sprite_render.contains(*entity) || !mesh.contains(*entity)
+                    })
+                    .map(|(entity, global, _, _, _, _)| {
+                        let draw_order = draw_order.get(entity).cloned().unwrap_or_default();
+                        (entity, draw_order, global.0[(2, 3)])
+                    })
+                    .collect::<Vec<_>>();
+                sort_by_draw_order(&mut ordered);
+                for (entity, _, _) in ordered {
+                    add_entity(&mut self.batch, entity, 0, false);
                 }
-
-                for (image_render, global, flipped, rgba, _, _, _) in (
-                    &texture_handle,
-                    &global,
-                    flipped.maybe(),
-                    rgba.maybe(),
+            }
+            None => {
+                let unordered_entities = (
+                    &*entities,
                     !&hidden,
                     !&hidden_prop,
-                    !&mesh,
+                    !&draw_order,
+                    !&screen_space,
+                    !&masked,
                 )
                     .join()
-                {
-                    self.batch
-                        .add_image(image_render, Some(global), flipped, rgba, &tex_storage);
+                    .filter(|_| unordered);
+                for (entity, _, _, _, _, _) in unordered_entities {
+                    if drawn(entity) {
+                        add_entity(&mut self.batch, entity, 0, false);
+                    }
                 }
 
                 self.batch.sort();
-            }
-            Some(ref visibility) => {
-                for (sprite_render, global, flipped, rgba, _) in (
-                    &sprite_render,
+
+                let mut ordered = (
+                    &*entities,
+                    &draw_order,
                     &global,
-                    flipped.maybe(),
-                    rgba.maybe(),
-                    &visibility.visible_unordered,
+                    !&hidden,
+                    !&hidden_prop,
+                    !&screen_space,
+                    !&masked,
                 )
                     .join()
-                {
-                    self.batch.add_sprite(
-                        sprite_render,
-                        Some(global),
-                        flipped,
-                        rgba,
-                        &sprite_sheet_storage,
-                        &tex_storage,
-                    );
+                    .filter(|(entity, draw_order, _, _, _, _, _)| {
+                        (sprite_render.contains(*entity) || !mesh.contains(*entity))
+                            && in_layers(Some(*draw_order))
+                    })
+                    .map(|(entity, draw_order, global, _, _, _, _)| {
+                        (entity, *draw_order, global.0[(2, 3)])
+                    })
+                    .collect::<Vec<_>>();
+                sort_by_draw_order(&mut ordered);
+                for (entity, _, _) in ordered {
+                    add_entity(&mut self.batch, entity, 0, false);
                 }
-
-                for (image_render, global, flipped, rgba, _, _) in (
-                    &texture_handle,
-                    &global,
-                    flipped.maybe(),
-                    rgba.maybe(),
-                    &visibility.visible_unordered,
-                    !&mesh,
-                )
+            }
+            Some(ref visibility) => {
+                let unordered_entities = (&*entities, &visibility.visible_unordered, !&masked)
                     .join()
-                {
-                    self.batch
-                        .add_image(image_render, Some(global), flipped, rgba, &tex_storage);
+                    .filter(|_| unordered);
+                for (entity, _, _) in unordered_entities {
+                    if drawn(entity) {
+                        add_entity(&mut self.batch, entity, 0, false);
+                    }
                 }
 
                 // We are free to optimize the order of the opaque sprites.
                 self.batch.sort();
 
                 for entity in &visibility.visible_ordered {
-                    if let Some(sprite_render) = sprite_render.get(*entity) {
-                        self.batch.add_sprite(
-                            sprite_render,
-                            global.get(*entity),
-                            flipped.get(*entity),
-                            rgba.get(*entity),
-                            &sprite_sheet_storage,
-                            &tex_storage,
-                        );
-                    } else if let Some(texture_handle) = texture_handle.get(*entity) {
-                        self.batch.add_image(
-                            texture_handle,
-                            global.get(*entity),
-                            flipped.get(*entity),
-                            rgba.get(*entity),
-                            &tex_storage,
-                        )
+                    if !masked.contains(entity.id()) && in_layers(draw_order.get(*entity)) {
+                        add_entity(&mut self.batch, *entity, 0, false);
                     }
                 }
             }
         }
+        for ((entity, stencil), _, _) in masked_sprites {
+            add_entity(&mut self.batch, entity, stencil, false);
+        }
+
+        if self.lighting {
+            set_light_args(effect, encoder, &light, &global, &ambient_color, camera);
+            for ((_, _, paletted), variant) in &mut self.effect_variants {
+                if !*paletted {
+                    set_light_args(variant, encoder, &light, &global, &ambient_color, camera);
+                }
+            }
+        }
+
+        let counts = stats.counts(time.frame_number());
+        if let Some(ref mut mask_effect) = self.mask_effect {
+            let effects = BatchEffects {
+                main: mask_effect,
+                variants: &mut [],
+                outline: None,
+                shaders: &mut [],
+                lit: false,
+            };
+            self.mask_batch.encode(
+                encoder,
+                &mut factory,
+                effects,
+                camera,
+                snap.as_ref(),
+                &sprite_sheet_storage,
+                &tex_storage,
+                counts,
+            );
+        }
+        self.mask_batch.reset();
+        let effects = BatchEffects {
+            main: effect,
+            variants: &mut self.effect_variants,
+            outline: self.outline_effect.as_mut(),
+            shaders: &mut self.shader_effects,
+            lit: self.lighting,
+        };
         self.batch.encode(
             encoder,
             &mut factory,
-            effect,
+            effects,
             camera,
+            snap.as_ref(),
             &sprite_sheet_storage,
             &tex_storage,
+            counts,
         );
         self.batch.reset();
     }
 }
 
+/// Creates the builder of an effect drawing sprites with the given fragment shader.
+fn sprite_effect_builder<'f>(effect: NewEffect<'f>, frag_src: &'f [u8]) -> EffectBuilder<'f> {
+    let mut builder = effect.simple(VERT_SRC, frag_src);
+    builder
+        .without_back_face_culling()
+        .with_raw_constant_buffer(
+            "ViewArgs",
+            mem::size_of::<<ViewArgs as Uniform>::Std140>(),
+            1,
+        )
+        .with_raw_vertex_buffer(
+            DrawFlat2D::attributes(),
+            SpriteInstance::size() as ElemStride,
+            1,
+        );
+    setup_textures(&mut builder, &TEXTURES);
+    builder
+}
+
+/// Finds the stencil value of the nearest mask of an entity, from its parent.
+fn find_mask(
+    mut entity: Entity,
+    parents: &ReadStorage<'_, Parent>,
+    masks: &[(Entity, u8)],
+) -> Option<u8> {
+    loop {
+        if let Some(&(_, stencil)) = masks.iter().find(|(mask, _)| *mask == entity) {
+            return Some(stencil);
+        }
+        entity = parents.get(entity)?.entity;
+    }
+}
+
+/// Finds the index of the sprite shader of a material override with its parameters, warning
+/// about the shaders not registered in the pass.
+fn sprite_material(
+    shaders: &[(String, &'static [u8])],
+    material: Option<&SpriteMaterialOverride>,
+) -> Option<(usize, [f32; 4])> {
+    let material = material?;
+    match shaders
+        .iter()
+        .position(|(name, _)| *name == material.shader)
+    {
+        Some(index) => Some((index, material.params)),
+        None => {
+            warn!(
+                "Sprite shader `{}` is not registered in `DrawFlat2D`, drawing the default one.",
+                material.shader
+            );
+            None
+        }
+    }
+}
+
+/// Sorts entities by draw order, then by Z coordinate.
+fn sort_by_draw_order<T>(ordered: &mut [(T, DrawOrder, f32)]) {
+    ordered.sort_by(|a, b| {
+        a.1.cmp(&b.1)
+            .then_with(|| a.2.partial_cmp(&b.2).unwrap_or(Ordering::Equal))
+    });
+}
+
+/// Camera showing the screen in pixels, from the bottom left corner, in front of the sprites.
+fn screen_camera(screen_dimensions: &ScreenDimensions) -> (Camera, GlobalTransform) {
+    let projection = Projection::orthographic(
+        0.0,
+        screen_dimensions.width(),
+        0.0,
+        screen_dimensions.height(),
+    );
+    let position = Matrix4::new_translation(&Vector3::new(0.0, 0.0, SCREEN_CAMERA_Z));
+    (Camera::from(projection), GlobalTransform(position))
+}
+
+/// Blend mode, mask, whether it is a sprite rather than an outline, texture id, palette, normal
+/// map id and shader of a sprite.
+type BatchKey = (
+    BlendMode,
+    u8,
+    bool,
+    u32,
+    Option<(u32, u32)>,
+    Option<u32>,
+    Option<usize>,
+    Option<usize>,
+);
+
+/// Row of the palette of the sprites drawn in indexed colors.
+#[repr(C, align(16))]
+#[derive(Clone, Copy, Debug, Uniform)]
+struct PaletteArgs {
+    row: int,
+}
+
+/// Whether the lit sprites have a normal map.
+#[repr(C, align(16))]
+#[derive(Clone, Copy, Debug, Uniform)]
+struct SpriteLightArgs {
+    normal_mapped: uint,
+}
+
 #[derive(Clone, Debug)]
 enum TextureDrawData {
     Sprite {
         texture_handle: Handle<Texture>,
         render: SpriteRender,
+        normal_map: Option<Handle<Texture>>,
         flipped: Option<Flipped>,
         rgba: Option<Rgba>,
         transform: GlobalTransform,
+        nine_slice: Option<NineSlice>,
+        blend_mode: BlendMode,
+        palette: Option<Palette>,
+        deform: Option<SpriteDeform>,
+        mask: u8,
+        outline: bool,
+        shader: Option<usize>,
+        params: [f32; 4],
+        /// Index of the sampler of the sprite sheet in the batch, if it has one
+        sampler: Option<usize>,
     },
     Image {
         texture_handle: Handle<Texture>,
@@ -273,6 +811,13 @@ enum TextureDrawData {
         rgba: Option<Rgba>,
         width: usize,
         height: usize,
+        blend_mode: BlendMode,
+        palette: Option<Palette>,
+        deform: Option<SpriteDeform>,
+        mask: u8,
+        outline: bool,
+        shader: Option<usize>,
+        params: [f32; 4],
     },
 }
 
@@ -291,6 +836,130 @@ impl TextureDrawData {
         }
     }
 
+    pub fn blend_mode(&self) -> BlendMode {
+        match self {
+            TextureDrawData::Sprite { blend_mode, .. } => *blend_mode,
+            TextureDrawData::Image { blend_mode, .. } => *blend_mode,
+        }
+    }
+
+    /// Stencil value of the mask restricting the sprite or of the mask itself, `0` if unmasked.
+    pub fn mask(&self) -> u8 {
+        match self {
+            TextureDrawData::Sprite { mask, .. } => *mask,
+            TextureDrawData::Image { mask, .. } => *mask,
+        }
+    }
+
+    /// Normal map of the page of a sprite, images have none.
+    pub fn normal_map(&self) -> Option<&Handle<Texture>> {
+        match self {
+            TextureDrawData::Sprite { normal_map, .. } => normal_map.as_ref(),
+            TextureDrawData::Image { .. } => None,
+        }
+    }
+
+    pub fn palette(&self) -> Option<&Palette> {
+        match self {
+            TextureDrawData::Sprite { palette, .. } => palette.as_ref(),
+            TextureDrawData::Image { palette, .. } => palette.as_ref(),
+        }
+    }
+
+    /// Index of the sprite shader of the pass drawing the sprite, if it is not the default one.
+    pub fn shader(&self) -> Option<usize> {
+        match self {
+            TextureDrawData::Sprite { shader, .. } => *shader,
+            TextureDrawData::Image { shader, .. } => *shader,
+        }
+    }
+
+    /// Index of the sampler of the sprite sheet of a sprite in its batch, images have none.
+    pub fn sampler(&self) -> Option<usize> {
+        match self {
+            TextureDrawData::Sprite { sampler, .. } => *sampler,
+            TextureDrawData::Image { .. } => None,
+        }
+    }
+
+    /// Whether this is a copy of a sprite drawing its outline.
+    pub fn outline(&self) -> bool {
+        match self {
+            TextureDrawData::Sprite { outline, .. } => *outline,
+            TextureDrawData::Image { outline, .. } => *outline,
+        }
+    }
+
+    /// Sprites with the same key are drawn with the same draw call when they follow each other.
+    ///
+    /// Outlines have a lower key than the sprites, so they are drawn under them when sorted.
+    pub fn batch_key(&self) -> BatchKey {
+        (
+            self.blend_mode(),
+            self.mask(),
+            !self.outline(),
+            self.tex_id(),
+            self.palette()
+                .map(|palette| (palette.texture.id(), palette.row)),
+            self.normal_map().map(Handle::id),
+            self.shader(),
+            self.sampler(),
+        )
+    }
+
+    /// Creates a copy of the sprite drawing its outline, moved by the given offset.
+    fn outline_copy(&self, color: Rgba, offset: &Vector3<f32>) -> TextureDrawData {
+        let mut copy = self.clone();
+        match copy {
+            TextureDrawData::Sprite {
+                ref mut rgba,
+                ref mut transform,
+                ref mut palette,
+                ref mut outline,
+                ref mut shader,
+                ..
+            }
+            | TextureDrawData::Image {
+                ref mut rgba,
+                ref mut transform,
+                ref mut palette,
+                ref mut outline,
+                ref mut shader,
+                ..
+            } => {
+                *rgba = Some(color);
+                *palette = None;
+                *shader = None;
+                transform.0 *= Matrix4::new_translation(offset);
+                *outline = true;
+            }
+        }
+        copy
+    }
+
+    /// Creates a faded copy of the sprite drawing its trail at the given transform.
+    fn trail_copy(&self, global: &GlobalTransform, alpha: f32) -> TextureDrawData {
+        let mut copy = self.clone();
+        match copy {
+            TextureDrawData::Sprite {
+                ref mut rgba,
+                ref mut transform,
+                ..
+            }
+            | TextureDrawData::Image {
+                ref mut rgba,
+                ref mut transform,
+                ..
+            } => {
+                let mut color = rgba.unwrap_or(Rgba::WHITE);
+                color.3 *= alpha;
+                *rgba = Some(color);
+                *transform = *global;
+            }
+        }
+        copy
+    }
+
     pub fn flipped(&self) -> &Option<Flipped> {
         match self {
             TextureDrawData::Sprite { flipped, .. } => flipped,
@@ -299,9 +968,46 @@ impl TextureDrawData {
     }
 }
 
+/// Components of an entity changing how its sprite or image is drawn.
+#[derive(Clone, Copy, Debug)]
+struct SpriteComponents<'a> {
+    flipped: Option<&'a Flipped>,
+    rgba: Option<&'a Rgba>,
+    /// Ignored by the images, which are not cut into a nine slice
+    nine_slice: Option<&'a NineSlice>,
+    blend_mode: Option<&'a BlendMode>,
+    palette: Option<&'a Palette>,
+    deform: Option<&'a SpriteDeform>,
+    outline: Option<&'a SpriteOutline>,
+    trail: Option<&'a SpriteTrail>,
+    /// Index of the sprite shader and its parameters
+    material: Option<(usize, [f32; 4])>,
+}
+
+/// Effects a batch is drawn with.
+struct BatchEffects<'a> {
+    /// Effect drawing the sprites without another effect
+    main: &'a mut Effect,
+    /// Variants of the main effect by blend mode, whether the sprites are masked and whether they
+    /// have a palette
+    variants: &'a mut [((BlendMode, bool, bool), Effect)],
+    /// Effect drawing the outlines of the sprites
+    outline: Option<&'a mut Effect>,
+    /// Effects of the sprite shaders by index and whether the sprites are masked
+    shaders: &'a mut [((usize, bool), Effect)],
+    /// Whether the sprites are shaded by the lights
+    lit: bool,
+}
+
 #[derive(Clone, Default, Debug)]
 struct TextureBatch {
     textures: Vec<TextureDrawData>,
+    /// Instance data of the current draw call, kept to reuse its allocation
+    instance_data: Vec<f32>,
+    /// Samplers of the sprite sheets overriding the samplers of their textures
+    sampler_infos: Vec<SamplerInfo>,
+    /// Samplers created for `sampler_infos`, created when encoding
+    samplers: Vec<Sampler>,
 }
 
 impl TextureBatch {
@@ -309,8 +1015,8 @@ impl TextureBatch {
         &mut self,
         texture_handle: &TextureHandle,
         global: Option<&GlobalTransform>,
-        flipped: Option<&Flipped>,
-        rgba: Option<&Rgba>,
+        components: SpriteComponents<'_>,
+        mask: u8,
         tex_storage: &AssetStorage<Texture>,
     ) {
         let global = match global {
@@ -326,22 +1032,34 @@ impl TextureBatch {
             }
         };
 
-        self.textures.push(TextureDrawData::Image {
-            texture_handle: texture_handle.clone(),
-            transform: *global,
-            flipped: flipped.cloned(),
-            rgba: rgba.cloned(),
-            width: texture_dims.0,
-            height: texture_dims.1,
-        });
+        self.push(
+            TextureDrawData::Image {
+                texture_handle: texture_handle.clone(),
+                transform: *global,
+                flipped: components.flipped.cloned(),
+                rgba: components.rgba.cloned(),
+                width: texture_dims.0,
+                height: texture_dims.1,
+                blend_mode: components.blend_mode.cloned().unwrap_or_default(),
+                palette: components.palette.cloned(),
+                deform: components.deform.cloned(),
+                mask,
+                shader: components.material.map(|(shader, _)| shader),
+                params: components.material.map_or([0.0; 4], |(_, params)| params),
+                outline: false,
+            },
+            components.outline,
+            components.trail,
+            tex_storage,
+        );
     }
 
     pub fn add_sprite(
         &mut self,
         sprite_render: &SpriteRender,
         global: Option<&GlobalTransform>,
-        flipped: Option<&Flipped>,
-        rgba: Option<&Rgba>,
+        components: SpriteComponents<'_>,
+        mask: u8,
         sprite_sheet_storage: &AssetStorage<SpriteSheet>,
         tex_storage: &AssetStorage<Texture>,
     ) {
@@ -350,17 +1068,40 @@ impl TextureBatch {
             None => return,
         };
 
-        let texture_handle = match sprite_sheet_storage.get(&sprite_render.sprite_sheet) {
+        let sprite_sheet = sprite_sheet_storage.get(&sprite_render.sprite_sheet);
+        let (texture_handle, normal_map, sampler) = match sprite_sheet {
             Some(sprite_sheet) => {
-                if tex_storage.get(&sprite_sheet.texture).is_none() {
+                // The sprite sheet may have been reloaded with fewer sprites.
+                if sprite_render.sprite_number >= sprite_sheet.sprites.len() {
                     warn!(
-                        "Texture not loaded for texture: `{:?}`.",
-                        sprite_sheet.texture
+                        "Sprite number {} is out of the {} sprites of its sprite sheet.",
+                        sprite_render.sprite_number,
+                        sprite_sheet.sprites.len()
                     );
                     return;
                 }
+                let page = sprite_sheet.sprites[sprite_render.sprite_number].page;
+                let texture = match sprite_sheet.page(page) {
+                    Some(texture) => texture,
+                    None => {
+                        warn!(
+                            "Sprite number {} is on page {}, missing from its sprite sheet.",
+                            sprite_render.sprite_number, page
+                        );
+                        return;
+                    }
+                };
+                if tex_storage.get(texture).is_none() {
+                    warn!("Texture not loaded for texture: `{:?}`.", texture);
+                    return;
+                }
 
-                sprite_sheet.texture.clone()
+                let sampler = sprite_sheet.sampler.map(|info| self.sampler_index(info));
+                (
+                    texture.clone(),
+                    sprite_sheet.normal_map(page).cloned(),
+                    sampler,
+                )
             }
             None => {
                 warn!(
@@ -371,29 +1112,91 @@ impl TextureBatch {
             }
         };
 
-        self.textures.push(TextureDrawData::Sprite {
-            texture_handle,
-            render: sprite_render.clone(),
-            flipped: flipped.cloned(),
-            rgba: rgba.cloned(),
-            transform: *global,
-        });
+        self.push(
+            TextureDrawData::Sprite {
+                texture_handle,
+                render: sprite_render.clone(),
+                normal_map,
+                flipped: components.flipped.cloned(),
+                rgba: components.rgba.cloned(),
+                transform: *global,
+                nine_slice: components.nine_slice.cloned(),
+                blend_mode: components.blend_mode.cloned().unwrap_or_default(),
+                palette: components.palette.cloned(),
+                deform: components.deform.cloned(),
+                mask,
+                shader: components.material.map(|(shader, _)| shader),
+                params: components.material.map_or([0.0; 4], |(_, params)| params),
+                outline: false,
+                sampler,
+            },
+            components.outline,
+            components.trail,
+            tex_storage,
+        );
+    }
+
+    /// Returns the index of a sampler of sprite sheets, the samplers being kept between frames so
+    /// the batch keys stay the same.
+    fn sampler_index(&mut self, info: SamplerInfo) -> usize {
+        match self.sampler_infos.iter().position(|known| *known == info) {
+            Some(index) => index,
+            None => {
+                self.sampler_infos.push(info);
+                self.sampler_infos.len() - 1
+            }
+        }
+    }
+
+    /// Adds a sprite, after the copies drawing its trail and its outline in eight directions if it
+    /// has them.
+    fn push(
+        &mut self,
+        quad: TextureDrawData,
+        outline: Option<&SpriteOutline>,
+        trail: Option<&SpriteTrail>,
+        tex_storage: &AssetStorage<Texture>,
+    ) {
+        if let Some(palette) = quad.palette() {
+            if tex_storage.get(&palette.texture).is_none() {
+                warn!("Palette not loaded for texture: `{:?}`.", palette.texture);
+                return;
+            }
+        }
+        if let Some(trail) = trail {
+            for (transform, alpha) in trail.ghosts() {
+                self.textures.push(quad.trail_copy(transform, alpha));
+            }
+        }
+        if let Some(outline) = outline {
+            for i in 0..8 {
+                let angle = i as f32 * std::f32::consts::FRAC_PI_4;
+                let offset = Vector3::new(angle.cos(), angle.sin(), 0.0) * outline.thickness;
+                self.textures
+                    .push(quad.outline_copy(outline.color, &offset));
+            }
+        }
+        self.textures.push(quad);
     }
 
     /// Optimize the sprite order to generating more coherent batches.
+    ///
+    /// The sprites are grouped by blend mode, mask and texture, keeping the order they were added
+    /// in within a group. Alpha blended sprites are drawn first.
     pub fn sort(&mut self) {
-        // Only takes the texture into account for now.
-        self.textures.sort_by(|a, b| a.tex_id().cmp(&b.tex_id()));
+        self.textures.sort_by_key(TextureDrawData::batch_key);
     }
 
     pub fn encode(
-        &self,
+        &mut self,
         encoder: &mut Encoder,
         factory: &mut Factory,
-        effect: &mut Effect,
+        effects: BatchEffects<'_>,
         camera: Option<(&Camera, &GlobalTransform)>,
+        snap: Option<&PixelSnap>,
         sprite_sheet_storage: &AssetStorage<SpriteSheet>,
         tex_storage: &AssetStorage<Texture>,
+        counts: &mut SpriteRenderCounts,
     ) {
         use gfx::{
             buffer,
@@ -404,17 +1207,36 @@ impl TextureBatch {
         if self.textures.is_empty() {
             return;
         }
+        let BatchEffects {
+            main: effect,
+            variants: effect_variants,
+            outline: mut outline_effect,
+            shaders: shader_effects,
+            lit,
+        } = effects;
+        counts.sprites += self.textures.len();
+        let mut last_texture = None;
 
         // Sprite vertex shader
         set_view_args(effect, encoder, camera);
+        for (_, variant) in effect_variants.iter_mut() {
+            set_view_args(variant, encoder, camera);
+        }
+        if let Some(ref mut outline_effect) = outline_effect {
+            set_view_args(outline_effect, encoder, camera);
+        }
+        for (_, shader_effect) in shader_effects.iter_mut() {
+            set_view_args(shader_effect, encoder, camera);
+        }
+
+        for info in &self.sampler_infos[self.samplers.len()..] {
+            self.samplers.push(factory.create_sampler(*info));
+        }
 
-        // We might be able to improve performance here if we
-        // preallocate the maximum needed capacity. We need to
-        // iterate over the sprites though to find out the longest
-        // chain of sprites with the same texture, so we would need
-        // to check if it actually results in an improvement over just
-        // doing the allocations.
-        let mut instance_data = Vec::<f32>::new();
+        // The instance data is kept between frames, so its allocation grows to the longest chain
+        // of sprites with the same texture and is reused afterwards.
+        let mut instance_data = std::mem::replace(&mut self.instance_data, Vec::new());
+        instance_data.clear();
         let mut num_instances = 0;
         let num_quads = self.textures.len();
 
@@ -430,11 +1252,14 @@ impl TextureBatch {
                 _ => (false, false),
             };
 
-            let (dir_x, dir_y, pos, uv_left, uv_right, uv_top, uv_bottom, rgba) = match quad {
+            match quad {
                 TextureDrawData::Sprite {
                     render,
                     transform,
                     rgba,
+                    nine_slice,
+                    deform,
+                    params,
                     ..
                 } => {
                     let sprite_sheet = sprite_sheet_storage
@@ -445,41 +1270,90 @@ impl TextureBatch {
 
                     // Append sprite to instance data.
                     let sprite_data = &sprite_sheet.sprites[render.sprite_number];
-
                     let tex_coords = &sprite_data.tex_coords;
-                    let (uv_left, uv_right) = if flip_horizontal {
-                        (tex_coords.right, tex_coords.left)
-                    } else {
-                        (tex_coords.left, tex_coords.right)
-                    };
-                    let (uv_bottom, uv_top) = if flip_vertical {
-                        (tex_coords.top, tex_coords.bottom)
-                    } else {
-                        (tex_coords.bottom, tex_coords.top)
-                    };
+                    let rgba = rgba.unwrap_or(Rgba::WHITE);
 
+                    // A flipped sprite is mirrored around the entity, by mirroring its axes.
                     let transform = &transform.0;
+                    let axis_x: Vector4<f32> =
+                        transform.column(0) * if flip_horizontal { -1.0 } else { 1.0 };
+                    let axis_y: Vector4<f32> =
+                        transform.column(1) * if flip_vertical { -1.0 } else { 1.0 };
+                    let origin: Vector4<f32> = transform.column(3).into_owned();
+
+                    let lerp = |from: f32, to: f32, t: f32| from + (to - from) * t;
+                    let u = |t: f32| lerp(tex_coords.left, tex_coords.right, t);
+                    let v = |t: f32| lerp(tex_coords.bottom, tex_coords.top, t);
 
-                    let dir_x = transform.column(0) * sprite_data.width;
-                    let dir_y = transform.column(1) * sprite_data.height;
+                    let parts = match nine_slice {
+                        Some(nine_slice) => nine_slice.parts(sprite_data),
+                        None => SpritePart::whole(sprite_data),
+                    };
+                    // The parts are deformed along with the rectangle covering them.
+                    let bounds = parts_bounds(&parts);
+                    for part in parts {
+                        // The offsets of the sprite are negated in the centers of its parts, to
+                        // shift it left and down relative to the entity, in regards to pivot
+                        // points. This is the convention adopted in:
+                        //
+                        // * libgdx: <https://gamedev.stackexchange.com/q/22553>
+                        // * godot: <https://godotengine.org/qa/9784>
+                        let pos = origin + axis_x * part.center[0] + axis_y * part.center[1];
 
-                    // The offsets are negated to shift the sprite left and down relative to the entity, in
-                    // regards to pivot points. This is the convention adopted in:
-                    //
-                    // * libgdx: <https://gamedev.stackexchange.com/q/22553>
-                    // * godot: <https://godotengine.org/qa/9784>
-                    let pos = transform
-                        * Vector4::new(-sprite_data.offsets[0], -sprite_data.offsets[1], 0.0, 1.0);
+                        let [x_start, x_end] = part.x_range;
+                        let [y_start, y_end] = part.y_range;
 
-                    (
-                        dir_x, dir_y, pos, uv_left, uv_right, uv_top, uv_bottom, rgba,
-                    )
+                        // A sprite rotated clockwise in the texture is drawn on a quad rotated
+                        // counterclockwise: the horizontal texture axis goes up the sprite and
+                        // the vertical texture axis goes left.
+                        let (dir_x, dir_y, uv) = if sprite_data.rotated {
+                            (
+                                axis_y * part.size[1],
+                                axis_x * -part.size[0],
+                                [u(y_start), u(y_end), v(1.0 - x_end), v(1.0 - x_start)],
+                            )
+                        } else {
+                            (
+                                axis_x * part.size[0],
+                                axis_y * part.size[1],
+                                [u(x_start), u(x_end), v(y_start), v(y_end)],
+                            )
+                        };
+                        let pos = snap.map_or(pos, |snap| snap.snap(&pos, &dir_x, &dir_y));
+                        let deform = deform.map_or([0.0; 8], |deform| {
+                            // The corners of the quad of a rotated sprite are turned a quarter
+                            // counterclockwise, like its axes.
+                            let points = [[-0.5, -0.5], [0.5, -0.5], [-0.5, 0.5], [0.5, 0.5]]
+                                .iter()
+                                .map(|&[x, y]| if sprite_data.rotated { [-y, x] } else { [x, y] })
+                                .map(|[x, y]| {
+                                    [
+                                        (part.center[0] + x * part.size[0] - bounds[0]) / bounds[2],
+                                        (part.center[1] + y * part.size[1] - bounds[1]) / bounds[3],
+                                    ]
+                                });
+                            deform_offsets(&deform, points, &axis_x, &axis_y)
+                        });
+                        push_instance(
+                            &mut instance_data,
+                            &dir_x,
+                            &dir_y,
+                            &pos,
+                            uv,
+                            rgba,
+                            &deform,
+                            params,
+                        );
+                        num_instances += 1;
+                    }
                 }
                 TextureDrawData::Image {
                     transform,
                     width,
                     height,
                     rgba,
+                    deform,
+                    params,
                     ..
                 } => {
                     let (uv_left, uv_right) = if flip_horizontal {
@@ -499,28 +1373,83 @@ impl TextureBatch {
                     let dir_y = transform.column(1) * (*height as f32);
 
                     let pos = transform * Vector4::new(1.0, 1.0, 0.0, 1.0);
+                    let pos = snap.map_or(pos, |snap| snap.snap(&pos, &dir_x, &dir_y));
+                    let deform = deform.map_or([0.0; 8], |deform| {
+                        let points = [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0]];
+                        let (axis_x, axis_y) = (transform.column(0), transform.column(1));
+                        deform_offsets(&deform, points.iter().cloned(), &axis_x, &axis_y)
+                    });
 
-                    (
-                        dir_x, dir_y, pos, uv_left, uv_right, uv_top, uv_bottom, rgba,
-                    )
+                    push_instance(
+                        &mut instance_data,
+                        &dir_x,
+                        &dir_y,
+                        &pos,
+                        [uv_left, uv_right, uv_bottom, uv_top],
+                        rgba.unwrap_or(Rgba::WHITE),
+                        &deform,
+                        params,
+                    );
+                    num_instances += 1;
                 }
-            };
-            let rgba = rgba.unwrap_or(Rgba::WHITE);
-            instance_data.extend(&[
-                dir_x.x, dir_x.y, dir_y.x, dir_y.y, pos.x, pos.y, uv_left, uv_right, uv_bottom,
-                uv_top, pos.z, rgba.0, rgba.1, rgba.2, rgba.3,
-            ]);
-            num_instances += 1;
-
-            // Need to flush outstanding draw calls due to state switch (texture).
+            }
+
+            // Need to flush outstanding draw calls due to state switch (texture, blend mode, mask
+            // or outline).
             //
             // 1. We are at the last sprite and want to submit all pending work.
-            // 2. The next sprite will use a different texture triggering a flush.
-            let need_flush = i >= num_quads - 1
-                || self.textures[i + 1].texture_handle().id() != quad.texture_handle().id();
+            // 2. The next sprite will use a different texture, blend mode, mask or outline
+            //    triggering a flush.
+            let need_flush =
+                i >= num_quads - 1 || self.textures[i + 1].batch_key() != quad.batch_key();
 
             if need_flush {
-                add_texture(effect, texture);
+                let key = (
+                    quad.blend_mode(),
+                    quad.mask() != 0,
+                    quad.palette().is_some(),
+                );
+                let shader_key = quad.shader().map(|shader| (shader, quad.mask() != 0));
+                let shader_effect = shader_effects
+                    .iter_mut()
+                    .find(|(k, _)| Some(*k) == shader_key);
+                // Sprites drawn with their own shader only bind their texture.
+                let custom = shader_effect.is_some();
+                let effect = match (quad.outline(), outline_effect.as_mut(), shader_effect) {
+                    (true, Some(outline_effect), _) => &mut **outline_effect,
+                    (_, _, Some((_, shader_effect))) => shader_effect,
+                    _ => match effect_variants.iter_mut().find(|(k, _)| *k == key) {
+                        Some((_, variant)) => variant,
+                        None => &mut *effect,
+                    },
+                };
+                // The stencil value is written by the masks and tested by the masked sprites.
+                if let Some((_, ref mut stencil)) = effect.data.out_depth {
+                    *stencil = (quad.mask(), quad.mask());
+                }
+                let sampler = quad
+                    .sampler()
+                    .map_or_else(|| texture.sampler(), |index| &self.samplers[index]);
+                add_texture_with_sampler(effect, texture, sampler);
+                // Sprites without normal map bind their texture in its place, unused by the shader.
+                if lit && !quad.outline() && !custom && quad.palette().is_none() {
+                    let normal_map = quad.normal_map().and_then(|map| tex_storage.get(map));
+                    add_texture_with_sampler(effect, normal_map.unwrap_or(texture), sampler);
+                    let light_args = SpriteLightArgs {
+                        normal_mapped: normal_map.is_some() as u32,
+                    };
+                    effect.update_constant_buffer("SpriteLightArgs", &light_args.std140(), encoder);
+                }
+                if let Some(palette) = quad.palette().filter(|_| !custom) {
+                    let palette_texture = tex_storage.get(&palette.texture).expect(
+                        "Unreachable: Existence of palette checked when collecting the sprites",
+                    );
+                    add_texture(effect, palette_texture);
+                    let palette_args = PaletteArgs {
+                        row: palette.row as i32,
+                    };
+                    effect.update_constant_buffer("PaletteArgs", &palette_args.std140(), encoder);
+                }
 
                 let vbuf = factory
                     .create_buffer_immutable(&instance_data, buffer::Role::Vertex, Bind::empty())
@@ -543,13 +1472,251 @@ impl TextureBatch {
 
                 effect.clear();
 
+                counts.batches += 1;
+                if last_texture.map_or(false, |id| id != quad.tex_id()) {
+                    counts.texture_switches += 1;
+                }
+                last_texture = Some(quad.tex_id());
                 num_instances = 0;
                 instance_data.clear();
             }
         }
+
+        self.instance_data = instance_data;
     }
 
     pub fn reset(&mut self) {
         self.textures.clear();
     }
 }
+
+/// Moves quads to whole pixels of the screen.
+#[derive(Clone, Debug)]
+struct PixelSnap {
+    view_proj: Matrix4<f32>,
+    inverse: Matrix4<f32>,
+    screen_size: [f32; 2],
+}
+
+impl PixelSnap {
+    fn new(
+        camera: Option<(&Camera, &GlobalTransform)>,
+        screen_dimensions: &ScreenDimensions,
+    ) -> Option<Self> {
+        let view_proj = match camera {
+            Some((camera, global)) => camera.proj * global.0.try_inverse()?,
+            None => Matrix4::identity(),
+        };
+        Some(PixelSnap {
+            view_proj,
+            inverse: view_proj.try_inverse()?,
+            screen_size: [screen_dimensions.width(), screen_dimensions.height()],
+        })
+    }
+
+    /// Returns the center of the quad, moved so its bottom left corner is on a pixel.
+    fn snap(&self, pos: &Vector4<f32>, dir_x: &Vector4<f32>, dir_y: &Vector4<f32>) -> Vector4<f32> {
+        let corner = self.view_proj * (pos - dir_x * 0.5 - dir_y * 0.5);
+        if corner.w <= 0.0 {
+            return *pos;
+        }
+        let mut offset = Vector4::zeros();
+        for axis in 0..2 {
+            let size = self.screen_size[axis];
+            let pixel = (corner[axis] / corner.w + 1.0) / 2.0 * size;
+            offset[axis] = (pixel.round() - pixel) * 2.0 / size * corner.w;
+        }
+        pos + self.inverse * offset
+    }
+}
+
+/// Appends the data of a quad to the instance data.
+///
+/// `uv` holds the left, right, bottom and top texture coordinates.
+fn push_instance(
+    instance_data: &mut Vec<f32>,
+    dir_x: &Vector4<f32>,
+    dir_y: &Vector4<f32>,
+    pos: &Vector4<f32>,
+    uv: [f32; 4],
+    rgba: Rgba,
+    deform: &[f32; 8],
+    params: &[f32; 4],
+) {
+    instance_data.extend(&[
+        dir_x.x, dir_x.y, dir_y.x, dir_y.y, pos.x, pos.y, uv[0], uv[1], uv[2], uv[3], pos.z,
+        rgba.0, rgba.1, rgba.2, rgba.3,
+    ]);
+    instance_data.extend(deform);
+    instance_data.extend(params);
+}
+
+/// Left, bottom, width and height of the rectangle covering the parts of a sprite.
+fn parts_bounds(parts: &[SpritePart]) -> [f32; 4] {
+    let mut min = [std::f32::INFINITY; 2];
+    let mut max = [std::f32::NEG_INFINITY; 2];
+    for part in parts {
+        for axis in 0..2 {
+            min[axis] = min[axis].min(part.center[axis] - part.size[axis] / 2.0);
+            max[axis] = max[axis].max(part.center[axis] + part.size[axis] / 2.0);
+        }
+    }
+    [min[0], min[1], max[0] - min[0], max[1] - min[1]]
+}
+
+/// Offsets of the bottom left, bottom right, top left and top right corners of a quad, from their
+/// points in the deformed rectangle and the axes of the sprite.
+fn deform_offsets<I, V>(deform: &SpriteDeform, points: I, axis_x: &V, axis_y: &V) -> [f32; 8]
+where
+    I: Iterator<Item = [f32; 2]>,
+    V: Index<usize, Output = f32>,
+{
+    let mut offsets = [0.0; 8];
+    for (corner, point) in offsets.chunks_mut(2).zip(points) {
+        let offset = deform.offset(point);
+        corner[0] = axis_x[0] * offset[0] + axis_y[0] * offset[1];
+        corner[1] = axis_x[1] * offset[0] + axis_y[1] * offset[1];
+    }
+    offsets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+
+    use amethyst_assets::Loader;
+    use rayon::ThreadPoolBuilder;
+
+    use crate::formats::TextureData;
+
+    fn image(
+        texture_handle: &Handle<Texture>,
+        blend_mode: BlendMode,
+        width: usize,
+    ) -> TextureDrawData {
+        TextureDrawData::Image {
+            texture_handle: texture_handle.clone(),
+            transform: GlobalTransform::default(),
+            flipped: None,
+            rgba: None,
+            width,
+            height: 1,
+            blend_mode,
+            palette: None,
+            deform: None,
+            mask: 0,
+            outline: false,
+            shader: None,
+            params: [0.0; 4],
+        }
+    }
+
+    #[test]
+    fn batch_groups_sprites_by_texture_in_order() {
+        let pool = ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        let loader = Loader::new(".", Arc::new(pool));
+        let storage = AssetStorage::new();
+        let red = loader.load_from_data(TextureData::color([1.0, 0.0, 0.0, 1.0]), (), &storage);
+        let blue = loader.load_from_data(TextureData::color([0.0, 0.0, 1.0, 1.0]), (), &storage);
+
+        let mut batch = TextureBatch::default();
+        batch.textures = vec![
+            image(&red, BlendMode::Additive, 0),
+            image(&blue, BlendMode::Alpha, 1),
+            image(&red, BlendMode::Alpha, 2),
+            image(&blue, BlendMode::Alpha, 3),
+            image(&red, BlendMode::Alpha, 4),
+        ];
+        let widths = |batch: &TextureBatch| {
+            batch
+                .textures
+                .iter()
+                .map(|quad| match quad {
+                    TextureDrawData::Image { width, .. } => *width,
+                    TextureDrawData::Sprite { .. } => unreachable!(),
+                })
+                .collect::<Vec<_>>()
+        };
+        let (first, second) = if red.id() < blue.id() {
+            (vec![2, 4], vec![1, 3])
+        } else {
+            (vec![1, 3], vec![2, 4])
+        };
+        let expected = [first, second, vec![0]].concat();
+        batch.sort();
+        assert_eq!(widths(&batch), expected);
+
+        // A sprite changing texture is moved to its new group the next frame.
+        batch.textures = vec![
+            image(&red, BlendMode::Additive, 0),
+            image(&blue, BlendMode::Alpha, 1),
+            image(&blue, BlendMode::Alpha, 2),
+            image(&blue, BlendMode::Alpha, 3),
+            image(&red, BlendMode::Alpha, 4),
+        ];
+        let expected = if red.id() < blue.id() {
+            vec![4, 1, 2, 3, 0]
+        } else {
+            vec![1, 2, 3, 4, 0]
+        };
+        batch.sort();
+        assert_eq!(widths(&batch), expected);
+    }
+
+    #[test]
+    fn pixel_snap_moves_corner_to_pixel() {
+        let camera = Camera::from(Projection::orthographic(0.0, 50.0, 0.0, 50.0));
+        let global = GlobalTransform::default();
+        let screen_dimensions = ScreenDimensions::new(100, 100, 1.0);
+        let snap = PixelSnap::new(Some((&camera, &global)), &screen_dimensions).unwrap();
+
+        // Two pixels per unit: the corner at 4.15 units, 8.3 pixels, moves to 8 pixels.
+        let pos = Vector4::new(6.15, 6.0, 0.0, 1.0);
+        let dir = |x, y| Vector4::new(x, y, 0.0, 0.0);
+        let snapped = snap.snap(&pos, &dir(4.0, 0.0), &dir(0.0, 4.0));
+        assert!((snapped.x - 6.0).abs() < 1e-4);
+        assert!((snapped.y - 6.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn sprite_material_finds_registered_shader() {
+        let shaders = vec![
+            ("dissolve".to_string(), FRAG_SRC),
+            ("flash".to_string(), FRAG_SRC),
+        ];
+        let flash = SpriteMaterialOverride::new("flash").with_params([1.0, 0.0, 0.0, 0.5]);
+        assert_eq!(
+            sprite_material(&shaders, Some(&flash)),
+            Some((1, [1.0, 0.0, 0.0, 0.5]))
+        );
+        assert_eq!(
+            sprite_material(&shaders, Some(&SpriteMaterialOverride::new("glow"))),
+            None
+        );
+        assert_eq!(sprite_material(&shaders, None), None);
+    }
+
+    #[test]
+    fn deform_offsets_follow_axes() {
+        let deform = SpriteDeform::skew(2.0);
+        let points = [[0.0, 0.0], [1.0, 0.0], [0.0, 0.5], [1.0, 1.0]];
+        let axis_x = Vector4::new(2.0, 0.0, 0.0, 0.0);
+        let axis_y = Vector4::new(0.0, 3.0, 0.0, 0.0);
+        let offsets = deform_offsets(&deform, points.iter().cloned(), &axis_x, &axis_y);
+        assert_eq!(offsets, [0.0, 0.0, 0.0, 0.0, 2.0, 0.0, 4.0, 0.0]);
+    }
+
+    #[test]
+    fn screen_camera_shows_screen_pixels() {
+        let (camera, global) = screen_camera(&ScreenDimensions::new(200, 100, 1.0));
+        let view_proj = camera.proj * global.0.try_inverse().unwrap();
+        let corner = view_proj * Vector4::new(200.0, 100.0, 0.0, 1.0);
+        assert!((corner.x / corner.w - 1.0).abs() < 1e-4);
+        assert!((corner.y / corner.w - 1.0).abs() < 1e-4);
+        let origin = view_proj * Vector4::new(0.0, 0.0, 0.0, 1.0);
+        assert!((origin.x / origin.w + 1.0).abs() < 1e-4);
+        assert!((origin.y / origin.w + 1.0).abs() < 1e-4);
+    }
+}
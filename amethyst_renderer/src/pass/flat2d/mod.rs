@@ -17,6 +17,10 @@ use crate::{
 
 static VERT_SRC: &[u8] = include_bytes!("../shaders/vertex/sprite.glsl");
 static FRAG_SRC: &[u8] = include_bytes!("../shaders/fragment/sprite.glsl");
+static MASK_FRAG_SRC: &[u8] = include_bytes!("../shaders/fragment/sprite_mask.glsl");
+static OUTLINE_FRAG_SRC: &[u8] = include_bytes!("../shaders/fragment/sprite_outline.glsl");
+static PALETTE_FRAG_SRC: &[u8] = include_bytes!("../shaders/fragment/sprite_palette.glsl");
+static LIT_FRAG_SRC: &[u8] = include_bytes!("../shaders/fragment/sprite_lit.glsl");
 
 static TEXTURES: [TextureType; 1] = [TextureType::Albedo];
 
@@ -74,6 +78,33 @@ impl Attribute for Depth {
     type Repr = f32;
 }
 
+#[derive(Clone, Debug)]
+enum DeformBottom {}
+impl Attribute for DeformBottom {
+    const NAME: &'static str = "deform_bottom";
+    const FORMAT: Format = Format(SurfaceType::R32_G32_B32_A32, ChannelType::Float);
+    const SIZE: u32 = 16;
+    type Repr = [f32; 4];
+}
+
+#[derive(Clone, Debug)]
+enum DeformTop {}
+impl Attribute for DeformTop {
+    const NAME: &'static str = "deform_top";
+    const FORMAT: Format = Format(SurfaceType::R32_G32_B32_A32, ChannelType::Float);
+    const SIZE: u32 = 16;
+    type Repr = [f32; 4];
+}
+
+#[derive(Clone, Debug)]
+enum MaterialParams {}
+impl Attribute for MaterialParams {
+    const NAME: &'static str = "material_params";
+    const FORMAT: Format = Format(SurfaceType::R32_G32_B32_A32, ChannelType::Float);
+    const SIZE: u32 = 16;
+    type Repr = [f32; 4];
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 struct SpriteInstance {
@@ -84,6 +115,9 @@ struct SpriteInstance {
     pub v_offset: [f32; 2],
     pub depth: f32,
     pub color: [f32; 4],
+    pub deform_bottom: [f32; 4],
+    pub deform_top: [f32; 4],
+    pub material_params: [f32; 4],
 }
 
 unsafe impl Pod for SpriteInstance {}
@@ -97,6 +131,9 @@ impl VertexFormat for SpriteInstance {
         (OffsetV::NAME, <Self as With<OffsetV>>::FORMAT),
         (Depth::NAME, <Self as With<Depth>>::FORMAT),
         (Color::NAME, <Self as With<Color>>::FORMAT),
+        (DeformBottom::NAME, <Self as With<DeformBottom>>::FORMAT),
+        (DeformTop::NAME, <Self as With<DeformTop>>::FORMAT),
+        (MaterialParams::NAME, <Self as With<MaterialParams>>::FORMAT),
     ];
 }
 
@@ -148,3 +185,45 @@ impl With<Color> for SpriteInstance {
         format: Color::FORMAT,
     };
 }
+
+impl With<DeformBottom> for SpriteInstance {
+    const FORMAT: AttributeFormat = Element {
+        offset: DirX::SIZE
+            + DirY::SIZE
+            + Pos::SIZE
+            + OffsetU::SIZE
+            + OffsetV::SIZE
+            + Depth::SIZE
+            + Color::SIZE,
+        format: DeformBottom::FORMAT,
+    };
+}
+
+impl With<DeformTop> for SpriteInstance {
+    const FORMAT: AttributeFormat = Element {
+        offset: DirX::SIZE
+            + DirY::SIZE
+            + Pos::SIZE
+            + OffsetU::SIZE
+            + OffsetV::SIZE
+            + Depth::SIZE
+            + Color::SIZE
+            + DeformBottom::SIZE,
+        format: DeformTop::FORMAT,
+    };
+}
+
+impl With<MaterialParams> for SpriteInstance {
+    const FORMAT: AttributeFormat = Element {
+        offset: DirX::SIZE
+            + DirY::SIZE
+            + Pos::SIZE
+            + OffsetU::SIZE
+            + OffsetV::SIZE
+            + Depth::SIZE
+            + Color::SIZE
+            + DeformBottom::SIZE
+            + DeformTop::SIZE,
+        format: MaterialParams::FORMAT,
+    };
+}
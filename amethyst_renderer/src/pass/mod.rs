@@ -1,22 +1,36 @@
 //! Different kinds of render passes.
 //
 pub use self::{
+    color_grading::DrawColorGrading,
+    debug_2d::DrawDebug2D,
     debug_lines::*,
+    decals::DrawDecals,
     flat::*,
     flat2d::*,
+    particle2d::DrawParticles2D,
     pbm::*,
     shaded::*,
+    shadow2d::DrawShadows2D,
+    shadow_map::DrawShadowMap,
     skinning::set_skinning_buffers,
     skybox::*,
+    transition::DrawTransition,
     util::{get_camera, set_vertex_args},
 };
 
+mod color_grading;
+mod debug_2d;
 mod debug_lines;
+mod decals;
 mod flat;
 mod flat2d;
+mod particle2d;
 mod pbm;
 mod shaded;
 mod shaded_util;
+mod shadow2d;
+mod shadow_map;
 mod skinning;
 mod skybox;
+mod transition;
 mod util;
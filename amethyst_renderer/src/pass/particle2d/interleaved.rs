@@ -0,0 +1,251 @@
+//! Pass drawing the particles of 2D emitters.
+
+use std::mem;
+
+use derivative::Derivative;
+use gfx::pso::buffer::ElemStride;
+use gfx_core::state::ColorMask;
+use glsl_layout::{float, int, vec2, vec4, Uniform};
+use log::warn;
+
+use amethyst_assets::AssetStorage;
+use amethyst_core::{
+    specs::prelude::{Join, Read, ReadStorage},
+    transform::GlobalTransform,
+};
+use amethyst_error::Error;
+
+use crate::{
+    cam::{ActiveCamera, Camera},
+    hidden::{Hidden, HiddenPropagate},
+    particle::ParticleEmitter2D,
+    pass::util::{add_texture_with_sampler, get_camera, set_view_args, setup_textures, ViewArgs},
+    pipe::{
+        pass::{Pass, PassData},
+        DepthMode, Effect, NewEffect,
+    },
+    sprite::{BlendMode, SpriteSheet},
+    tex::{SamplerInfo, Texture},
+    types::{Encoder, Factory, Sampler, Slice},
+    vertex::{Attributes, Query, VertexFormat},
+};
+
+use super::*;
+
+/// Maximal number of frames of the animation of the particles.
+const MAX_FRAMES: usize = 64;
+
+/// Draws the particles of the entities with a `ParticleEmitter2D`.
+///
+/// The particles of each emitter are drawn with a single instanced draw call, at the Z coordinate
+/// of the emitter. They are tested against the depth buffer without writing to it, so this pass
+/// should be added after the passes drawing the opaque sprites.
+#[derive(Derivative, Clone, Debug)]
+#[derivative(Default)]
+pub struct DrawParticles2D {
+    blend_mode: BlendMode,
+    /// Instance data of the current draw call, kept to reuse its allocation
+    instance_data: Vec<f32>,
+    /// Samplers created for the sprite sheets overriding the samplers of their textures
+    samplers: Vec<(SamplerInfo, Sampler)>,
+}
+
+impl DrawParticles2D {
+    /// Create instance of `DrawParticles2D` pass
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Blends the particles with the given mode, `BlendMode::Alpha` by default.
+    ///
+    /// `BlendMode::Additive` makes fire and sparks glow where the particles overlap.
+    pub fn with_blend_mode(mut self, blend_mode: BlendMode) -> Self {
+        self.blend_mode = blend_mode;
+        self
+    }
+
+    fn attributes() -> Attributes<'static> {
+        <ParticleInstance as Query<(StartPos, Velocity, Birth)>>::QUERIED_ATTRIBUTES
+    }
+}
+
+/// Curves and animation of the particles of an emitter.
+#[derive(Clone, Copy, Debug, Uniform)]
+struct ParticleArgs {
+    color_start: vec4,
+    color_end: vec4,
+    sprite_size: vec2,
+    gravity: vec2,
+    time: float,
+    lifetime: float,
+    depth: float,
+    size_start: float,
+    size_end: float,
+    speed_start: float,
+    speed_end: float,
+    frame_count: int,
+}
+
+impl<'a> PassData<'a> for DrawParticles2D {
+    type Data = (
+        Read<'a, ActiveCamera>,
+        ReadStorage<'a, Camera>,
+        ReadStorage<'a, GlobalTransform>,
+        ReadStorage<'a, ParticleEmitter2D>,
+        Read<'a, AssetStorage<SpriteSheet>>,
+        Read<'a, AssetStorage<Texture>>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, HiddenPropagate>,
+    );
+}
+
+impl Pass for DrawParticles2D {
+    fn compile(&mut self, effect: NewEffect<'_>) -> Result<Effect, Error> {
+        let mut builder = effect.simple(VERT_SRC, FRAG_SRC);
+        builder
+            .without_back_face_culling()
+            .with_raw_constant_buffer(
+                "ViewArgs",
+                mem::size_of::<<ViewArgs as Uniform>::Std140>(),
+                1,
+            )
+            .with_raw_constant_buffer(
+                "ParticleArgs",
+                mem::size_of::<<ParticleArgs as Uniform>::Std140>(),
+                1,
+            )
+            .with_raw_constant_buffer("ParticleFrames", mem::size_of::<[f32; 4]>(), MAX_FRAMES)
+            .with_raw_vertex_buffer(
+                Self::attributes(),
+                ParticleInstance::size() as ElemStride,
+                1,
+            )
+            .with_blended_output(
+                "color",
+                ColorMask::all(),
+                self.blend_mode.blend(),
+                Some(DepthMode::LessEqualTest),
+            );
+        setup_textures(&mut builder, &TEXTURES);
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        mut factory: Factory,
+        (
+            active,
+            camera,
+            global,
+            emitters,
+            sprite_sheet_storage,
+            tex_storage,
+            hidden,
+            hidden_prop,
+        ): <Self as PassData<'a>>::Data,
+    ) {
+        use gfx::{
+            buffer,
+            memory::{Bind, Typed},
+            Factory,
+        };
+
+        set_view_args(effect, encoder, get_camera(active, &camera, &global));
+
+        for (emitter, global, _, _) in (&emitters, &global, !&hidden, !&hidden_prop).join() {
+            if emitter.particles.is_empty() {
+                continue;
+            }
+            let sprite_sheet = match sprite_sheet_storage.get(&emitter.sprite.sprite_sheet) {
+                Some(sprite_sheet) => sprite_sheet,
+                None => continue,
+            };
+            let first = emitter.sprite.sprite_number;
+            let frame_count = emitter.frame_count.max(1).min(MAX_FRAMES);
+            let sprites = match sprite_sheet.sprites.get(first..first + frame_count) {
+                Some(sprites) => sprites,
+                None => {
+                    warn!(
+                        "Sprites {} to {} of particles are out of their sprite sheet.",
+                        first,
+                        first + frame_count - 1
+                    );
+                    continue;
+                }
+            };
+            let texture = match sprite_sheet
+                .page(sprites[0].page)
+                .and_then(|texture| tex_storage.get(texture))
+            {
+                Some(texture) => texture,
+                None => continue,
+            };
+
+            let frames = sprites
+                .iter()
+                .map(|sprite| {
+                    let coords = &sprite.tex_coords;
+                    [coords.left, coords.right, coords.bottom, coords.top]
+                })
+                .collect::<Vec<_>>();
+            let particle_args = ParticleArgs {
+                color_start: <[f32; 4]>::from(emitter.color[0]).into(),
+                color_end: <[f32; 4]>::from(emitter.color[1]).into(),
+                sprite_size: [sprites[0].width, sprites[0].height].into(),
+                gravity: emitter.gravity.into(),
+                time: emitter.time,
+                lifetime: emitter.lifetime,
+                depth: global.0[(2, 3)],
+                size_start: emitter.size[0],
+                size_end: emitter.size[1],
+                speed_start: emitter.speed_factor[0],
+                speed_end: emitter.speed_factor[1],
+                frame_count: frames.len() as i32,
+            };
+            effect.update_constant_buffer("ParticleArgs", &particle_args.std140(), encoder);
+            effect.update_buffer("ParticleFrames", &frames[..], encoder);
+            let sampler = match sprite_sheet.sampler {
+                Some(info) => match self.samplers.iter().position(|(known, _)| *known == info) {
+                    Some(index) => &self.samplers[index].1,
+                    None => {
+                        self.samplers.push((info, factory.create_sampler(info)));
+                        &self.samplers[self.samplers.len() - 1].1
+                    }
+                },
+                None => texture.sampler(),
+            };
+            add_texture_with_sampler(effect, texture, sampler);
+
+            self.instance_data.clear();
+            for particle in &emitter.particles {
+                self.instance_data.extend(&[
+                    particle.position[0],
+                    particle.position[1],
+                    particle.velocity[0],
+                    particle.velocity[1],
+                    particle.birth,
+                ]);
+            }
+            let vbuf = factory
+                .create_buffer_immutable(&self.instance_data, buffer::Role::Vertex, Bind::empty())
+                .expect("Unable to create immutable buffer for `DrawParticles2D`");
+            for _ in Self::attributes() {
+                effect.data.vertex_bufs.push(vbuf.raw().clone());
+            }
+
+            effect.draw(
+                &Slice {
+                    start: 0,
+                    end: 6,
+                    base_vertex: 0,
+                    instances: Some((emitter.particles.len() as u32, 0)),
+                    buffer: Default::default(),
+                },
+                encoder,
+            );
+            effect.clear();
+        }
+    }
+}
@@ -0,0 +1,86 @@
+pub use self::interleaved::DrawParticles2D;
+
+mod interleaved;
+
+use gfx::{
+    format::{ChannelType, Format, SurfaceType},
+    pso::buffer::Element,
+    traits::Pod,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    pass::util::TextureType,
+    vertex::{Attribute, AttributeFormat, Attributes, VertexFormat, With},
+};
+
+static VERT_SRC: &[u8] = include_bytes!("../shaders/vertex/particle2d.glsl");
+static FRAG_SRC: &[u8] = include_bytes!("../shaders/fragment/sprite.glsl");
+
+static TEXTURES: [TextureType; 1] = [TextureType::Albedo];
+
+#[derive(Clone, Debug)]
+enum StartPos {}
+impl Attribute for StartPos {
+    const NAME: &'static str = "start_pos";
+    const FORMAT: Format = Format(SurfaceType::R32_G32, ChannelType::Float);
+    const SIZE: u32 = 8;
+    type Repr = [f32; 2];
+}
+
+#[derive(Clone, Debug)]
+enum Velocity {}
+impl Attribute for Velocity {
+    const NAME: &'static str = "velocity";
+    const FORMAT: Format = Format(SurfaceType::R32_G32, ChannelType::Float);
+    const SIZE: u32 = 8;
+    type Repr = [f32; 2];
+}
+
+#[derive(Clone, Debug)]
+enum Birth {}
+impl Attribute for Birth {
+    const NAME: &'static str = "birth";
+    const FORMAT: Format = Format(SurfaceType::R32, ChannelType::Float);
+    const SIZE: u32 = 4;
+    type Repr = f32;
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+struct ParticleInstance {
+    pub start_pos: [f32; 2],
+    pub velocity: [f32; 2],
+    pub birth: f32,
+}
+
+unsafe impl Pod for ParticleInstance {}
+
+impl VertexFormat for ParticleInstance {
+    const ATTRIBUTES: Attributes<'static> = &[
+        (StartPos::NAME, <Self as With<StartPos>>::FORMAT),
+        (Velocity::NAME, <Self as With<Velocity>>::FORMAT),
+        (Birth::NAME, <Self as With<Birth>>::FORMAT),
+    ];
+}
+
+impl With<StartPos> for ParticleInstance {
+    const FORMAT: AttributeFormat = Element {
+        offset: 0,
+        format: StartPos::FORMAT,
+    };
+}
+
+impl With<Velocity> for ParticleInstance {
+    const FORMAT: AttributeFormat = Element {
+        offset: StartPos::SIZE,
+        format: Velocity::FORMAT,
+    };
+}
+
+impl With<Birth> for ParticleInstance {
+    const FORMAT: AttributeFormat = Element {
+        offset: StartPos::SIZE + Velocity::SIZE,
+        format: Birth::FORMAT,
+    };
+}
@@ -20,14 +20,17 @@ use crate::{
     mesh::{Mesh, MeshHandle},
     mtl::{Material, MaterialDefaults},
     pass::{
-        shaded_util::{set_light_args, setup_light_buffers},
+        shaded_util::{
+            set_light_args, set_shadow_args, setup_light_buffers, setup_shadow_map, ShadowMapInput,
+        },
         util::{default_transparency, draw_mesh, get_camera, setup_textures, setup_vertex_args},
     },
     pipe::{
         pass::{Pass, PassData},
-        DepthMode, Effect, NewEffect,
+        DepthMode, Effect, NewEffect, Targets,
     },
     resources::AmbientColor,
+    shadow_map::DirectionalShadows,
     tex::Texture,
     types::{Encoder, Factory},
     vertex::{Normal, Position, Query, Tangent, TexCoord},
@@ -51,6 +54,7 @@ pub struct DrawPbm<V> {
     _pd: PhantomData<V>,
     #[derivative(Default(value = "default_transparency()"))]
     transparency: Option<(ColorMask, Blend, Option<DepthMode>)>,
+    shadow_map: ShadowMapInput,
 }
 
 impl<V> DrawPbm<V>
@@ -88,6 +92,13 @@ where
         self.transparency = Some((mask, blend, depth));
         self
     }
+
+    /// Shades the first directional light with the shadow map drawn into the depth buffer of the
+    /// named target by the `DrawShadowMap` pass, set up with the `DirectionalShadows` resource.
+    pub fn with_shadow_map<N: Into<String>>(mut self, target: N) -> Self {
+        self.shadow_map = ShadowMapInput::new(target);
+        self
+    }
 }
 
 impl<'a, V> PassData<'a> for DrawPbm<V>
@@ -98,6 +109,7 @@ where
         Read<'a, ActiveCamera>,
         ReadStorage<'a, Camera>,
         Read<'a, AmbientColor>,
+        Read<'a, DirectionalShadows>,
         Read<'a, AssetStorage<Mesh>>,
         Read<'a, AssetStorage<Texture>>,
         ReadExpect<'a, MaterialDefaults>,
@@ -117,11 +129,13 @@ where
     V: Query<(Position, Normal, Tangent, TexCoord)>,
 {
     fn compile(&mut self, effect: NewEffect<'_>) -> Result<Effect, Error> {
+        self.shadow_map.compile(&mut *effect.factory)?;
         let mut builder = effect.simple(VERT_SRC, FRAG_SRC);
         builder.with_raw_vertex_buffer(V::QUERIED_ATTRIBUTES, V::size() as ElemStride, 0);
         setup_vertex_args(&mut builder);
         setup_light_buffers(&mut builder);
         setup_textures(&mut builder, &TEXTURES);
+        setup_shadow_map(&mut builder);
         match self.transparency {
             Some((mask, blend, depth)) => builder.with_blended_output("color", mask, blend, depth),
             None => builder.with_output("color", Some(DepthMode::LessEqualWrite)),
@@ -138,6 +152,7 @@ where
             active,
             camera,
             ambient,
+            shadows,
             mesh_storage,
             tex_storage,
             material_defaults,
@@ -154,6 +169,8 @@ where
         let camera = get_camera(active, &camera, &global);
 
         set_light_args(effect, encoder, &light, &global, &ambient, camera);
+        set_shadow_args(effect, &shadows, &self.shadow_map);
+        let shadow_map = self.shadow_map.binding();
 
         match visibility {
             None => {
@@ -181,6 +198,7 @@ where
                         Some(global),
                         &[V::QUERIED_ATTRIBUTES],
                         &TEXTURES,
+                        shadow_map,
                     );
                 }
             }
@@ -208,6 +226,7 @@ where
                         Some(global),
                         &[V::QUERIED_ATTRIBUTES],
                         &TEXTURES,
+                        shadow_map,
                     );
                 }
 
@@ -227,10 +246,15 @@ where
                             global.get(*entity),
                             &[V::QUERIED_ATTRIBUTES],
                             &TEXTURES,
+                            shadow_map,
                         );
                     }
                 }
             }
         }
     }
+
+    fn new_targets(&mut self, targets: &Targets) {
+        self.shadow_map.new_targets(targets);
+    }
 }
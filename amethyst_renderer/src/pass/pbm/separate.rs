@@ -21,15 +21,18 @@ use crate::{
     mesh::{Mesh, MeshHandle},
     mtl::{Material, MaterialDefaults},
     pass::{
-        shaded_util::{set_light_args, setup_light_buffers},
+        shaded_util::{
+            set_light_args, set_shadow_args, setup_light_buffers, setup_shadow_map, ShadowMapInput,
+        },
         skinning::{create_skinning_effect, setup_skinning_buffers},
         util::{default_transparency, draw_mesh, get_camera, setup_textures, setup_vertex_args},
     },
     pipe::{
         pass::{Pass, PassData},
-        DepthMode, Effect, NewEffect,
+        DepthMode, Effect, NewEffect, Targets,
     },
     resources::AmbientColor,
+    shadow_map::DirectionalShadows,
     skinning::JointTransforms,
     tex::Texture,
     types::{Encoder, Factory},
@@ -57,6 +60,7 @@ pub struct DrawPbmSeparate {
     skinning: bool,
     #[derivative(Default(value = "default_transparency()"))]
     transparency: Option<(ColorMask, Blend, Option<DepthMode>)>,
+    shadow_map: ShadowMapInput,
 }
 
 impl DrawPbmSeparate {
@@ -97,6 +101,13 @@ impl DrawPbmSeparate {
         self.transparency = Some((mask, blend, depth));
         self
     }
+
+    /// Shades the first directional light with the shadow map drawn into the depth buffer of the
+    /// named target by the `DrawShadowMap` pass, set up with the `DirectionalShadows` resource.
+    pub fn with_shadow_map<N: Into<String>>(mut self, target: N) -> Self {
+        self.shadow_map = ShadowMapInput::new(target);
+        self
+    }
 }
 
 impl<'a> PassData<'a> for DrawPbmSeparate {
@@ -104,6 +115,7 @@ impl<'a> PassData<'a> for DrawPbmSeparate {
         Read<'a, ActiveCamera>,
         ReadStorage<'a, Camera>,
         Read<'a, AmbientColor>,
+        Read<'a, DirectionalShadows>,
         Read<'a, AssetStorage<Mesh>>,
         Read<'a, AssetStorage<Texture>>,
         ReadExpect<'a, MaterialDefaults>,
@@ -124,6 +136,7 @@ impl Pass for DrawPbmSeparate {
         #[cfg(feature = "profiler")]
         profile_scope!("render_pass_pbm_compile");
 
+        self.shadow_map.compile(&mut *effect.factory)?;
         let mut builder = if self.skinning {
             create_skinning_effect(effect, FRAG_SRC)
         } else {
@@ -156,6 +169,7 @@ impl Pass for DrawPbmSeparate {
         setup_vertex_args(&mut builder);
         setup_light_buffers(&mut builder);
         setup_textures(&mut builder, &TEXTURES);
+        setup_shadow_map(&mut builder);
         match self.transparency {
             Some((mask, blend, depth)) => builder.with_blended_output("color", mask, blend, depth),
             None => builder.with_output("color", Some(DepthMode::LessEqualWrite)),
@@ -172,6 +186,7 @@ impl Pass for DrawPbmSeparate {
             active,
             camera,
             ambient,
+            shadows,
             mesh_storage,
             tex_storage,
             material_defaults,
@@ -192,6 +207,8 @@ impl Pass for DrawPbmSeparate {
         let camera = get_camera(active, &camera, &global);
 
         set_light_args(effect, encoder, &light, &global, &ambient, camera);
+        set_shadow_args(effect, &shadows, &self.shadow_map);
+        let shadow_map = self.shadow_map.binding();
 
         match visibility {
             None => {
@@ -220,6 +237,7 @@ impl Pass for DrawPbmSeparate {
                         Some(global),
                         &ATTRIBUTES,
                         &TEXTURES,
+                        shadow_map,
                     );
                 }
             }
@@ -248,6 +266,7 @@ impl Pass for DrawPbmSeparate {
                         Some(global),
                         &ATTRIBUTES,
                         &TEXTURES,
+                        shadow_map,
                     );
                 }
 
@@ -267,10 +286,15 @@ impl Pass for DrawPbmSeparate {
                             global.get(*entity),
                             &ATTRIBUTES,
                             &TEXTURES,
+                            shadow_map,
                         );
                     }
                 }
             }
         }
     }
+
+    fn new_targets(&mut self, targets: &Targets) {
+        self.shadow_map.new_targets(targets);
+    }
 }
@@ -19,15 +19,18 @@ use crate::{
     mesh::{Mesh, MeshHandle},
     mtl::{Material, MaterialDefaults},
     pass::{
-        shaded_util::{set_light_args, setup_light_buffers},
+        shaded_util::{
+            set_light_args, set_shadow_args, setup_light_buffers, setup_shadow_map, ShadowMapInput,
+        },
         skinning::{create_skinning_effect, setup_skinning_buffers},
         util::{default_transparency, draw_mesh, get_camera, setup_textures, setup_vertex_args},
     },
     pipe::{
         pass::{Pass, PassData},
-        DepthMode, Effect, NewEffect,
+        DepthMode, Effect, NewEffect, Targets,
     },
     resources::AmbientColor,
+    shadow_map::DirectionalShadows,
     skinning::JointTransforms,
     tex::Texture,
     types::{Encoder, Factory},
@@ -54,6 +57,7 @@ pub struct DrawShadedSeparate {
     skinning: bool,
     #[derivative(Default(value = "default_transparency()"))]
     transparency: Option<(ColorMask, Blend, Option<DepthMode>)>,
+    shadow_map: ShadowMapInput,
 }
 
 impl DrawShadedSeparate {
@@ -94,6 +98,13 @@ impl DrawShadedSeparate {
         self.transparency = Some((mask, blend, depth));
         self
     }
+
+    /// Shades the first directional light with the shadow map drawn into the depth buffer of the
+    /// named target by the `DrawShadowMap` pass, set up with the `DirectionalShadows` resource.
+    pub fn with_shadow_map<N: Into<String>>(mut self, target: N) -> Self {
+        self.shadow_map = ShadowMapInput::new(target);
+        self
+    }
 }
 
 impl<'a> PassData<'a> for DrawShadedSeparate {
@@ -101,6 +112,7 @@ impl<'a> PassData<'a> for DrawShadedSeparate {
         Read<'a, ActiveCamera>,
         ReadStorage<'a, Camera>,
         Read<'a, AmbientColor>,
+        Read<'a, DirectionalShadows>,
         Read<'a, AssetStorage<Mesh>>,
         Read<'a, AssetStorage<Texture>>,
         ReadExpect<'a, MaterialDefaults>,
@@ -119,6 +131,7 @@ impl<'a> PassData<'a> for DrawShadedSeparate {
 impl Pass for DrawShadedSeparate {
     fn compile(&mut self, effect: NewEffect<'_>) -> Result<Effect, Error> {
         debug!("Building shaded pass");
+        self.shadow_map.compile(&mut *effect.factory)?;
         let mut builder = if self.skinning {
             create_skinning_effect(effect, FRAG_SRC)
         } else {
@@ -147,6 +160,7 @@ impl Pass for DrawShadedSeparate {
         setup_vertex_args(&mut builder);
         setup_light_buffers(&mut builder);
         setup_textures(&mut builder, &TEXTURES);
+        setup_shadow_map(&mut builder);
         match self.transparency {
             Some((mask, blend, depth)) => builder.with_blended_output("color", mask, blend, depth),
             None => builder.with_output("color", Some(DepthMode::LessEqualWrite)),
@@ -163,6 +177,7 @@ impl Pass for DrawShadedSeparate {
             active,
             camera,
             ambient,
+            shadows,
             mesh_storage,
             tex_storage,
             material_defaults,
@@ -181,6 +196,8 @@ impl Pass for DrawShadedSeparate {
         let camera = get_camera(active, &camera, &global);
 
         set_light_args(effect, encoder, &light, &global, &ambient, camera);
+        set_shadow_args(effect, &shadows, &self.shadow_map);
+        let shadow_map = self.shadow_map.binding();
 
         match visibility {
             None => {
@@ -209,6 +226,7 @@ impl Pass for DrawShadedSeparate {
                         Some(global),
                         &ATTRIBUTES,
                         &TEXTURES,
+                        shadow_map,
                     );
                 }
             }
@@ -237,6 +255,7 @@ impl Pass for DrawShadedSeparate {
                         Some(global),
                         &ATTRIBUTES,
                         &TEXTURES,
+                        shadow_map,
                     );
                 }
 
@@ -256,10 +275,15 @@ impl Pass for DrawShadedSeparate {
                             global.get(*entity),
                             &ATTRIBUTES,
                             &TEXTURES,
+                            shadow_map,
                         );
                     }
                 }
             }
         }
     }
+
+    fn new_targets(&mut self, targets: &Targets) {
+        self.shadow_map.new_targets(targets);
+    }
 }
@@ -1,18 +1,24 @@
 use std::mem;
 
+use gfx::texture::{FilterMethod, SamplerInfo, WrapMode};
 use glsl_layout::*;
+use log::warn;
 
 use amethyst_core::{
+    nalgebra::Matrix4,
     specs::prelude::{Join, ReadStorage},
     GlobalTransform,
 };
+use amethyst_error::Error;
 
 use crate::{
     cam::Camera,
     light::Light,
-    pipe::{Effect, EffectBuilder},
+    pipe::{Effect, EffectBuilder, Targets},
     resources::AmbientColor,
-    types::Encoder,
+    shadow_map::DirectionalShadows,
+    tex::Texture,
+    types::{Encoder, Factory, RawShaderResourceView, Sampler},
 };
 
 #[derive(Clone, Copy, Debug, Uniform)]
@@ -162,3 +168,84 @@ pub(crate) fn setup_light_buffers(builder: &mut EffectBuilder<'_>) {
         .with_raw_global("ambient_color")
         .with_raw_global("camera_position");
 }
+
+/// Depth buffer of the target the `DrawShadowMap` pass draws to, sampled by the lit passes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct ShadowMapInput {
+    target: Option<String>,
+    depth: Option<RawShaderResourceView>,
+    /// Texture bound instead of the shadow map while it is missing, and the sampler of both
+    fallback: Option<(RawShaderResourceView, Sampler)>,
+}
+
+impl ShadowMapInput {
+    /// Samples the depth buffer of the named target.
+    pub(crate) fn new<N: Into<String>>(target: N) -> Self {
+        ShadowMapInput {
+            target: Some(target.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Creates the texture standing in for the shadow map, and its sampler.
+    pub(crate) fn compile(&mut self, factory: &mut Factory) -> Result<(), Error> {
+        use gfx::Factory;
+
+        let fallback = Texture::from_color_val([1.0; 4]).build(factory)?;
+        let sampler =
+            factory.create_sampler(SamplerInfo::new(FilterMethod::Scale, WrapMode::Clamp));
+        self.fallback = Some((fallback.view().clone(), sampler));
+        Ok(())
+    }
+
+    /// Finds the depth buffer of the target among the new targets.
+    pub(crate) fn new_targets(&mut self, targets: &Targets) {
+        use gfx::memory::Typed;
+
+        let name = match self.target {
+            Some(ref name) => name,
+            None => return,
+        };
+        self.depth = targets
+            .get(name)
+            .and_then(|target| target.depth_buf())
+            .and_then(|buffer| buffer.as_input.as_ref())
+            .map(|view| view.raw().clone());
+        if self.depth.is_none() {
+            warn!(
+                "Shadow map target {:?} not found or without depth buffer",
+                name
+            );
+        }
+    }
+
+    /// Returns the view and sampler to bind to the shadow map of the shaders.
+    pub(crate) fn binding(&self) -> Option<(&RawShaderResourceView, &Sampler)> {
+        let (fallback, sampler) = self.fallback.as_ref()?;
+        Some((self.depth.as_ref().unwrap_or(fallback), sampler))
+    }
+}
+
+pub(crate) fn set_shadow_args(
+    effect: &mut Effect,
+    shadows: &DirectionalShadows,
+    input: &ShadowMapInput,
+) {
+    let matrix = shadows.light_matrix().filter(|_| input.depth.is_some());
+    let light_matrix: [[f32; 4]; 4] = matrix.unwrap_or_else(Matrix4::identity).into();
+    effect.update_global("shadow_matrix", light_matrix);
+    // The bias is converted to the depth of the shadow map, covering `depth` world units.
+    effect.update_global("shadow_bias", shadows.bias / shadows.depth.max(1.0e-6));
+    effect.update_global("shadow_pcf_radius", shadows.pcf_radius as i32);
+    effect.update_global("has_shadow_map", matrix.is_some() as i32);
+}
+
+/// Declares the shadow map after the textures of the materials, as it is added after them.
+pub(crate) fn setup_shadow_map(builder: &mut EffectBuilder<'_>) {
+    builder
+        .with_texture("shadow_map")
+        .with_raw_global("shadow_matrix")
+        .with_raw_global("shadow_bias")
+        .with_raw_global("shadow_pcf_radius")
+        .with_raw_global("has_shadow_map");
+}
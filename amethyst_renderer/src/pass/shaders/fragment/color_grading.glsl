@@ -0,0 +1,36 @@
+// Grades the colors of the source texture with a lookup table strip.
+
+#version 150 core
+
+uniform sampler2D source;
+uniform sampler2D lut;
+uniform float lut_size;
+uniform float intensity;
+
+in vec2 tex_coord;
+
+out vec4 color;
+
+// Samples the slice of the lookup table, red to the right and green downwards.
+vec3 lookup(vec2 red_green, float slice) {
+    float x = (red_green.x * (lut_size - 1.0) + 0.5 + slice * lut_size) / (lut_size * lut_size);
+    float y = 1.0 - (red_green.y * (lut_size - 1.0) + 0.5) / lut_size;
+    return texture(lut, vec2(x, y)).rgb;
+}
+
+void main() {
+    vec4 base = texture(source, tex_coord);
+    if (intensity <= 0.0) {
+        color = base;
+        return;
+    }
+    vec3 clamped = clamp(base.rgb, 0.0, 1.0);
+    float blue = clamped.b * (lut_size - 1.0);
+    float slice = floor(blue);
+    vec3 graded = mix(
+        lookup(clamped.rg, slice),
+        lookup(clamped.rg, min(slice + 1.0, lut_size - 1.0)),
+        blue - slice
+    );
+    color = vec4(mix(base.rgb, graded, intensity), base.a);
+}
@@ -0,0 +1,11 @@
+// Draws the 2D debug lines with their color.
+
+#version 150 core
+
+in vec4 vertex_color;
+
+out vec4 color;
+
+void main() {
+    color = vertex_color;
+}
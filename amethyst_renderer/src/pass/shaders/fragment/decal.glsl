@@ -0,0 +1,14 @@
+// Draws the sprites of the decals tinted with their color.
+
+#version 150 core
+
+uniform sampler2D albedo;
+
+in vec4 vertex_color;
+in vec2 vertex_tex_coord;
+
+out vec4 color;
+
+void main() {
+    color = texture(albedo, vertex_tex_coord) * vertex_color;
+}
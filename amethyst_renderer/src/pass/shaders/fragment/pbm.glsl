@@ -55,6 +55,12 @@ uniform sampler2D roughness;
 uniform sampler2D ambient_occlusion;
 uniform sampler2D caveat;
 
+uniform sampler2D shadow_map;
+uniform mat4 shadow_matrix;
+uniform float shadow_bias;
+uniform int shadow_pcf_radius;
+uniform int has_shadow_map;
+
 layout (std140) uniform AlbedoOffset {
     vec2 u_offset;
     vec2 v_offset;
@@ -110,6 +116,29 @@ vec2 tex_coords(vec2 coord, vec2 u, vec2 v) {
     return vec2(tex_coord(coord.x, u), tex_coord(coord.y, v));
 }
 
+// Returns the share of the first directional light reaching the position, comparing its depth
+// to the texels of the shadow map around it.
+float shadow_factor(vec3 position) {
+    if (has_shadow_map == 0) {
+        return 1.0;
+    }
+    vec4 light_position = shadow_matrix * vec4(position, 1.0);
+    vec3 coords = light_position.xyz / light_position.w * 0.5 + 0.5;
+    if (any(lessThan(coords.xy, vec2(0.0))) || any(greaterThan(coords, vec3(1.0)))) {
+        return 1.0;
+    }
+    vec2 texel = 1.0 / vec2(textureSize(shadow_map, 0));
+    float lit = 0.0;
+    for (int x = -shadow_pcf_radius; x <= shadow_pcf_radius; x++) {
+        for (int y = -shadow_pcf_radius; y <= shadow_pcf_radius; y++) {
+            float depth = texture(shadow_map, coords.xy + vec2(x, y) * texel).r;
+            lit += coords.z - shadow_bias > depth ? 0.0 : 1.0;
+        }
+    }
+    float width = float(shadow_pcf_radius * 2 + 1);
+    return lit / (width * width);
+}
+
 float normal_distribution(vec3 N, vec3 H, float a) {
     float a2 = a * a;
     float NdotH = max(dot(N, H), 0.0);
@@ -211,9 +240,10 @@ void main() {
         lighted += light;
     }
 
+    float shadow = shadow_factor(vertex.position);
     for (int i = 0; i < directional_light_count; i++) {
         vec3 light_direction = -normalize(dlight[i].direction);
-        float attenuation = 1.0;
+        float attenuation = i == 0 ? shadow : 1.0;
 
         vec3 light = compute_light(vec3(attenuation),
                                    dlight[i].color,
@@ -33,6 +33,12 @@ uniform vec3 camera_position;
 uniform sampler2D albedo;
 uniform sampler2D emission;
 
+uniform sampler2D shadow_map;
+uniform mat4 shadow_matrix;
+uniform float shadow_bias;
+uniform int shadow_pcf_radius;
+uniform int has_shadow_map;
+
 layout (std140) uniform AlbedoOffset {
     vec2 u_offset;
     vec2 v_offset;
@@ -61,6 +67,29 @@ vec2 tex_coords(vec2 coord, vec2 u, vec2 v) {
     return vec2(tex_coord(coord.x, u), tex_coord(coord.y, v));
 }
 
+// Returns the share of the first directional light reaching the position, comparing its depth
+// to the texels of the shadow map around it.
+float shadow_factor(vec3 position) {
+    if (has_shadow_map == 0) {
+        return 1.0;
+    }
+    vec4 light_position = shadow_matrix * vec4(position, 1.0);
+    vec3 coords = light_position.xyz / light_position.w * 0.5 + 0.5;
+    if (any(lessThan(coords.xy, vec2(0.0))) || any(greaterThan(coords, vec3(1.0)))) {
+        return 1.0;
+    }
+    vec2 texel = 1.0 / vec2(textureSize(shadow_map, 0));
+    float lit = 0.0;
+    for (int x = -shadow_pcf_radius; x <= shadow_pcf_radius; x++) {
+        for (int y = -shadow_pcf_radius; y <= shadow_pcf_radius; y++) {
+            float depth = texture(shadow_map, coords.xy + vec2(x, y) * texel).r;
+            lit += coords.z - shadow_bias > depth ? 0.0 : 1.0;
+        }
+    }
+    float width = float(shadow_pcf_radius * 2 + 1);
+    return lit / (width * width);
+}
+
 void main() {
     vec4 color = texture(albedo, tex_coords(vertex.tex_coord, albedo_offset.u_offset, albedo_offset.v_offset));
     vec4 ecolor = texture(emission, tex_coords(vertex.tex_coord, emission_offset.u_offset, emission_offset.v_offset));
@@ -77,11 +106,12 @@ void main() {
         float attenuation = (plight[i].intensity / dist2);
         lighting += diffuse * attenuation;
     }
+    float shadow = shadow_factor(vertex.position);
     for (uint i = 0u; i < directional_light_count; i++) {
         vec3 dir = dlight[i].direction;
         float diff = max(dot(-dir, normal), 0.0);
         vec3 diffuse = diff * dlight[i].color;
-        lighting += diffuse;
+        lighting += diffuse * (i == 0u ? shadow : 1.0);
     }
     lighting += ambient_color;
     out_color = (vec4(lighting, 1.0) * color + ecolor) * vertex.color;
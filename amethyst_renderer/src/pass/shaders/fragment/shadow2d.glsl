@@ -0,0 +1,11 @@
+// Draws the shadows of the 2D occluders in a single color.
+
+#version 150 core
+
+uniform vec4 shadow_color;
+
+out vec4 color;
+
+void main() {
+    color = shadow_color;
+}
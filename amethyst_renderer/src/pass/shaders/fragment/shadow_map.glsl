@@ -0,0 +1,9 @@
+// Writes the depth seen from the light, which is also shown in the color buffer for debugging.
+
+#version 150 core
+
+out vec4 color;
+
+void main() {
+    color = vec4(vec3(gl_FragCoord.z), 1.0);
+}
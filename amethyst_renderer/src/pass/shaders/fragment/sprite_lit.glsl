@@ -0,0 +1,78 @@
+// Draws a sprite shaded by the lights, with the normals of its normal map.
+
+#version 150 core
+
+layout (std140) uniform FragmentArgs {
+    uint point_light_count;
+    uint directional_light_count;
+};
+
+struct PointLight {
+    vec3 position;
+    vec3 color;
+    float pad; // Workaround for bug in mac's implementation of opengl (loads garbage when accessing members of structures in arrays with dynamic indices).
+    float intensity;
+};
+
+layout (std140) uniform PointLights {
+    PointLight plight[128];
+};
+
+struct DirectionalLight {
+    vec3 color;
+    vec3 direction;
+};
+
+layout (std140) uniform DirectionalLights {
+    DirectionalLight dlight[16];
+};
+
+layout (std140) uniform SpriteLightArgs {
+    uint normal_mapped;
+};
+
+uniform vec3 ambient_color;
+uniform vec3 camera_position;
+
+uniform sampler2D albedo;
+uniform sampler2D normal_map;
+
+in VertexData {
+    vec2 tex_uv;
+    vec4 color;
+} vertex;
+
+in LightingData {
+    vec3 position;
+    vec2 tangent;
+    vec2 bitangent;
+} lighting;
+
+out vec4 color;
+
+void main() {
+    vec4 albedo_color = texture(albedo, vertex.tex_uv);
+    // Sprites without normal map face the camera, towards positive Z.
+    vec3 normal = vec3(0.0, 0.0, 1.0);
+    if (normal_mapped != 0u) {
+        vec3 mapped = texture(normal_map, vertex.tex_uv).rgb * 2.0 - 1.0;
+        normal = normalize(
+            mapped.x * vec3(lighting.tangent, 0.0)
+            + mapped.y * vec3(lighting.bitangent, 0.0)
+            + mapped.z * normal
+        );
+    }
+
+    vec3 light = ambient_color;
+    for (uint i = 0u; i < point_light_count; i++) {
+        vec3 dist = plight[i].position - lighting.position;
+        float diff = max(dot(normalize(dist), normal), 0.0);
+        float attenuation = plight[i].intensity / dot(dist, dist);
+        light += diff * normalize(plight[i].color) * attenuation;
+    }
+    for (uint i = 0u; i < directional_light_count; i++) {
+        float diff = max(dot(-dlight[i].direction, normal), 0.0);
+        light += diff * dlight[i].color;
+    }
+    color = vec4(light, 1.0) * albedo_color * vertex.color;
+}
@@ -0,0 +1,20 @@
+// Writes the shape of a sprite mask to the stencil buffer.
+
+#version 150 core
+
+uniform sampler2D albedo;
+
+in VertexData {
+    vec2 tex_uv;
+    vec4 color;
+} vertex;
+
+out vec4 color;
+
+void main() {
+    color = texture(albedo, vertex.tex_uv) * vertex.color;
+    // Transparent pixels are outside of the mask.
+    if (color.a < 0.5) {
+        discard;
+    }
+}
@@ -0,0 +1,20 @@
+// Draws the shape of a sprite in the color of its outline.
+
+#version 150 core
+
+uniform sampler2D albedo;
+
+in VertexData {
+    vec2 tex_uv;
+    vec4 color;
+} vertex;
+
+out vec4 color;
+
+void main() {
+    // The outline follows the pixels of the sprite with an alpha of at least 0.5.
+    if (texture(albedo, vertex.tex_uv).a < 0.5) {
+        discard;
+    }
+    color = vertex.color;
+}
@@ -0,0 +1,25 @@
+// Draws a sprite in indexed colors, taken from a row of its palette.
+
+#version 150 core
+
+uniform sampler2D albedo;
+uniform sampler2D palette;
+
+layout (std140) uniform PaletteArgs {
+    int row;
+};
+
+in VertexData {
+    vec2 tex_uv;
+    vec4 color;
+} vertex;
+
+out vec4 color;
+
+void main() {
+    vec4 indexed = texture(albedo, vertex.tex_uv);
+    // The red channel holds the index of the color in the row, from 0 to 255.
+    int index = int(indexed.r * 255.0 + 0.5);
+    vec4 palette_color = texelFetch(palette, ivec2(index, row), 0);
+    color = vec4(palette_color.rgb, palette_color.a * indexed.a) * vertex.color;
+}
@@ -0,0 +1,17 @@
+// Draws the source texture, or the fill color, with the given opacity.
+
+#version 150 core
+
+uniform sampler2D source;
+uniform vec4 fill_color;
+uniform float textured;
+uniform float opacity;
+
+in vec2 tex_coord;
+
+out vec4 color;
+
+void main() {
+    vec4 base = textured > 0.5 ? texture(source, tex_coord) : fill_color;
+    color = vec4(base.rgb, base.a * opacity);
+}
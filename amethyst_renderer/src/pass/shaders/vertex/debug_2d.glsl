@@ -0,0 +1,20 @@
+// Transforms the ends of the 2D debug lines, which are in world coordinates.
+
+#version 150 core
+
+layout (std140) uniform VertexArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 model;
+    uniform vec4 rgba;
+};
+
+in vec3 position;
+in vec4 color;
+
+out vec4 vertex_color;
+
+void main() {
+    vertex_color = color;
+    gl_Position = proj * view * vec4(position, 1.0);
+}
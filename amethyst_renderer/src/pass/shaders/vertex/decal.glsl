@@ -0,0 +1,23 @@
+// Transforms the corners of the decals, which are in world coordinates.
+
+#version 150 core
+
+layout (std140) uniform VertexArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 model;
+    uniform vec4 rgba;
+};
+
+in vec3 position;
+in vec4 color;
+in vec2 tex_coord;
+
+out vec4 vertex_color;
+out vec2 vertex_tex_coord;
+
+void main() {
+    vertex_color = color;
+    vertex_tex_coord = tex_coord;
+    gl_Position = proj * view * vec4(position, 1.0);
+}
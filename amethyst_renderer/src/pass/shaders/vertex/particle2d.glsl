@@ -0,0 +1,67 @@
+// Moves, scales, colors and animates the particles of an emitter by their age.
+
+#version 150 core
+
+layout (std140) uniform ViewArgs {
+    mat4 proj;
+    mat4 view;
+};
+
+layout (std140) uniform ParticleArgs {
+    vec4 color_start;
+    vec4 color_end;
+    vec2 sprite_size;
+    vec2 gravity;
+    float time;
+    float lifetime;
+    float depth;
+    float size_start;
+    float size_end;
+    float speed_start;
+    float speed_end;
+    int frame_count;
+};
+
+// Texture coordinates of the frames, left, right, bottom and top.
+layout (std140) uniform ParticleFrames {
+    vec4 frames[64];
+};
+
+// Where, when and in which direction the particle was emitted.
+in vec2 start_pos;
+in vec2 velocity;
+in float birth;
+
+out VertexData {
+    vec2 tex_uv;
+    vec4 color;
+} vertex;
+
+const vec2 positions[6] = vec2[](
+    // First triangle
+    vec2(-0.5, -0.5), // Left bottom
+    vec2(0.5, -0.5), // Right bottom
+    vec2(0.5, 0.5), // Right top
+
+    // Second triangle
+    vec2(0.5, 0.5), // Right top
+    vec2(-0.5, 0.5), // Left top
+    vec2(-0.5, -0.5)  // Left bottom
+);
+
+void main() {
+    float age = time - birth;
+    float t = clamp(age / lifetime, 0.0, 1.0);
+    // Distance travelled with the speed factor going from start to end over the lifetime.
+    float travel = lifetime * (speed_start * t + (speed_end - speed_start) * t * t / 2.0);
+    vec2 center = start_pos + velocity * travel + gravity * age * age / 2.0;
+
+    vec2 corner = positions[gl_VertexID];
+    vec2 pos = center + corner * sprite_size * mix(size_start, size_end, t);
+
+    int frame = min(int(t * float(frame_count)), frame_count - 1);
+    vec4 uv = frames[frame];
+    vertex.tex_uv = vec2(mix(uv.x, uv.y, corner.x + 0.5), mix(uv.z, uv.w, corner.y + 0.5));
+    vertex.color = mix(color_start, color_end, t);
+    gl_Position = proj * view * vec4(pos, depth, 1.0);
+}
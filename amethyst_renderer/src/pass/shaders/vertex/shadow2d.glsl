@@ -0,0 +1,15 @@
+// Draws the shadows of the 2D occluders, extruded to infinity away from the lights.
+
+#version 150 core
+
+layout (std140) uniform ViewArgs {
+    mat4 proj;
+    mat4 view;
+};
+
+// Points at infinity have a `w` of `0.0`.
+in vec4 position;
+
+void main() {
+    gl_Position = proj * view * position;
+}
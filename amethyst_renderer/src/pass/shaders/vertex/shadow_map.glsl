@@ -0,0 +1,16 @@
+// Transforms the meshes into the view of the light casting the shadows.
+
+#version 150 core
+
+layout (std140) uniform VertexArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 model;
+    uniform vec4 rgba;
+};
+
+in vec3 position;
+
+void main() {
+    gl_Position = proj * view * model * vec4(position, 1.0);
+}
@@ -17,12 +17,31 @@ in vec2 v_offset;
 
 in vec4 color;
 
+// Offsets of the corners of the quad, left then right.
+in vec4 deform_bottom;
+in vec4 deform_top;
+
+// Parameters of the shader of the sprite.
+in vec4 material_params;
+
 
 out VertexData {
     vec2 tex_uv;
     vec4 color;
 } vertex;
 
+// Position and axes of the sprite in the world, used by the lit sprites.
+out LightingData {
+    vec3 position;
+    vec2 tangent;
+    vec2 bitangent;
+} lighting;
+
+// Parameters of the custom shaders of the sprites.
+out MaterialData {
+    vec4 params;
+} material;
+
 const vec2 positions[6] = vec2[](
     // First triangle
     vec2(-0.5, -0.5), // Left bottom
@@ -44,9 +63,16 @@ void main() {
     float tex_u = positions[gl_VertexID][0];
     float tex_v = positions[gl_VertexID][1];
 
-    vec2 uv = pos + tex_u * dir_x + tex_v * dir_y;
+    vec4 deform_side = tex_v < 0.0 ? deform_bottom : deform_top;
+    vec2 deform = tex_u < 0.0 ? deform_side.xy : deform_side.zw;
+
+    vec2 uv = pos + tex_u * dir_x + tex_v * dir_y + deform;
     vertex.tex_uv = texture_coords(vec2(tex_u, tex_v), u_offset, v_offset);
     vertex.color = color;
+    lighting.position = vec3(uv, depth);
+    lighting.tangent = normalize(dir_x);
+    lighting.bitangent = normalize(dir_y);
+    material.params = material_params;
     vec4 vertex = vec4(uv, depth, 1.0);
     gl_Position = proj * view * vertex;
 }
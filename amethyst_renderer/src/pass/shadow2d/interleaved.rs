@@ -0,0 +1,318 @@
+//! Pass drawing the shadows of 2D occluders.
+
+use std::mem;
+
+use derivative::Derivative;
+use gfx::pso::buffer::ElemStride;
+use gfx_core::state::ColorMask;
+use glsl_layout::Uniform;
+
+use amethyst_assets::AssetStorage;
+use amethyst_core::{
+    nalgebra::Vector4,
+    specs::prelude::{Join, Read, ReadStorage},
+    transform::GlobalTransform,
+};
+use amethyst_error::Error;
+
+use crate::{
+    cam::{ActiveCamera, Camera},
+    hidden::{Hidden, HiddenPropagate},
+    light::Light,
+    pass::util::{get_camera, set_view_args, ViewArgs},
+    pipe::{
+        pass::{Pass, PassData},
+        Effect, NewEffect,
+    },
+    sprite::{Flipped, ShadowCaster2D, Sprite, SpriteRender, SpriteSheet},
+    types::{Encoder, Factory, Slice},
+    vertex::{Query, VertexFormat},
+    Rgba, ALPHA,
+};
+
+use super::*;
+
+/// Draws the shadows cast by the entities with a `ShadowCaster2D` from the point and directional
+/// `Light`s.
+///
+/// The shadows are drawn in the shadow color over the whole target, without depth test, so this
+/// pass should be added after the passes drawing the world and before the passes drawing the
+/// HUD. Shadows of several lights or occluders darken each other where they overlap.
+#[derive(Derivative, Clone, Debug)]
+#[derivative(Default)]
+pub struct DrawShadows2D {
+    #[derivative(Default(value = "Rgba(0.0, 0.0, 0.0, 0.5)"))]
+    shadow_color: Rgba,
+    light_size: f32,
+    /// Vertices of the shadows of the current frame, kept to reuse their allocation
+    vertices: Vec<f32>,
+}
+
+impl DrawShadows2D {
+    /// Create instance of `DrawShadows2D` pass
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the color of the shadows, black with an alpha of `0.5` by default.
+    pub fn with_shadow_color(mut self, color: Rgba) -> Self {
+        self.shadow_color = color;
+        self
+    }
+
+    /// Softens the shadows of the point lights as if they were disks of the given radius, `0.0`
+    /// by default for hard shadows.
+    ///
+    /// Soft shadows are drawn from several points of the lights, with a penumbra fading from the
+    /// shadow color, which multiplies the number of shadows drawn by five.
+    pub fn with_soft_shadows(mut self, light_size: f32) -> Self {
+        self.light_size = light_size;
+        self
+    }
+}
+
+impl<'a> PassData<'a> for DrawShadows2D {
+    type Data = (
+        Read<'a, ActiveCamera>,
+        ReadStorage<'a, Camera>,
+        ReadStorage<'a, GlobalTransform>,
+        ReadStorage<'a, Light>,
+        ReadStorage<'a, ShadowCaster2D>,
+        ReadStorage<'a, SpriteRender>,
+        ReadStorage<'a, Flipped>,
+        Read<'a, AssetStorage<SpriteSheet>>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, HiddenPropagate>,
+    );
+}
+
+impl Pass for DrawShadows2D {
+    fn compile(&mut self, effect: NewEffect<'_>) -> Result<Effect, Error> {
+        let mut builder = effect.simple(VERT_SRC, FRAG_SRC);
+        builder
+            .without_back_face_culling()
+            .with_raw_constant_buffer(
+                "ViewArgs",
+                mem::size_of::<<ViewArgs as Uniform>::Std140>(),
+                1,
+            )
+            .with_raw_vertex_buffer(
+                <ShadowVertex as Query<(HomogeneousPosition,)>>::QUERIED_ATTRIBUTES,
+                ShadowVertex::size() as ElemStride,
+                0,
+            )
+            .with_raw_global("shadow_color")
+            .with_blended_output("color", ColorMask::all(), ALPHA, None);
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        mut factory: Factory,
+        (
+            active,
+            camera,
+            global,
+            light,
+            shadow_caster,
+            sprite_render,
+            flipped,
+            sprite_sheet_storage,
+            hidden,
+            hidden_prop,
+        ): <Self as PassData<'a>>::Data,
+    ) {
+        use gfx::{
+            buffer,
+            memory::{Bind, Typed},
+            Factory,
+        };
+
+        let occluders = (
+            &shadow_caster,
+            &global,
+            sprite_render.maybe(),
+            flipped.maybe(),
+            !&hidden,
+            !&hidden_prop,
+        )
+            .join()
+            .filter_map(|(caster, global, sprite_render, flipped, _, _)| {
+                let polygon = match caster {
+                    ShadowCaster2D::Sprite => {
+                        let sprite_render = sprite_render?;
+                        let sprite_sheet = sprite_sheet_storage.get(&sprite_render.sprite_sheet)?;
+                        sprite_rectangle(
+                            sprite_sheet.sprites.get(sprite_render.sprite_number)?,
+                            flipped,
+                        )
+                    }
+                    ShadowCaster2D::Polygon(points) => points.clone(),
+                };
+                let polygon = polygon
+                    .iter()
+                    .map(|point| {
+                        let point = global.0 * Vector4::new(point[0], point[1], 0.0, 1.0);
+                        [point.x, point.y]
+                    })
+                    .collect::<Vec<_>>();
+                Some((polygon, global.0[(2, 3)]))
+            })
+            .collect::<Vec<_>>();
+
+        let samples = light_samples(self.light_size);
+        self.vertices.clear();
+        for (light, global) in (&light, &global).join() {
+            // Directional lights are drawn once per sample too, to be as dark as the point lights.
+            let shadow_light = match light {
+                Light::Point(_) => None,
+                Light::Directional(light) if light.direction[..2] != [0.0, 0.0] => {
+                    Some(ShadowLight::Directional([
+                        light.direction[0],
+                        light.direction[1],
+                    ]))
+                }
+                _ => continue,
+            };
+            for sample in &samples {
+                let shadow_light = shadow_light.unwrap_or_else(|| {
+                    ShadowLight::Point([global.0[(0, 3)] + sample[0], global.0[(1, 3)] + sample[1]])
+                });
+                for (polygon, z) in &occluders {
+                    push_shadow(&mut self.vertices, polygon, *z, shadow_light);
+                }
+            }
+        }
+        if self.vertices.is_empty() {
+            return;
+        }
+
+        // The samples are blended over each other to the alpha of the shadow color.
+        let mut color = self.shadow_color;
+        color.3 = 1.0 - (1.0 - color.3).powf(1.0 / samples.len() as f32);
+        effect.update_global("shadow_color", color);
+        set_view_args(effect, encoder, get_camera(active, &camera, &global));
+
+        let vbuf = factory
+            .create_buffer_immutable(&self.vertices, buffer::Role::Vertex, Bind::empty())
+            .expect("Unable to create immutable buffer for `DrawShadows2D`");
+        effect.data.vertex_bufs.push(vbuf.raw().clone());
+        effect.draw(
+            &Slice {
+                start: 0,
+                end: (self.vertices.len() / 4) as u32,
+                base_vertex: 0,
+                instances: None,
+                buffer: Default::default(),
+            },
+            encoder,
+        );
+        effect.clear();
+    }
+}
+
+/// Light casting shadows on the XY plane.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ShadowLight {
+    /// Light at a position, casting shadows away from it
+    Point([f32; 2]),
+    /// Light going in a direction, casting shadows along it
+    Directional([f32; 2]),
+}
+
+/// Offsets of the points of a light casting its shadows, only its center for hard shadows.
+fn light_samples(light_size: f32) -> Vec<[f32; 2]> {
+    if light_size > 0.0 {
+        let s = light_size;
+        vec![[0.0, 0.0], [s, 0.0], [-s, 0.0], [0.0, s], [0.0, -s]]
+    } else {
+        vec![[0.0, 0.0]]
+    }
+}
+
+/// Corners of the rectangle of a sprite relative to its entity, mirrored like the sprite.
+fn sprite_rectangle(sprite: &Sprite, flipped: Option<&Flipped>) -> Vec<[f32; 2]> {
+    let mut center = [-sprite.offsets[0], -sprite.offsets[1]];
+    match flipped {
+        Some(Flipped::Horizontal) => center[0] = -center[0],
+        Some(Flipped::Vertical) => center[1] = -center[1],
+        Some(Flipped::Both) => center = [-center[0], -center[1]],
+        _ => {}
+    }
+    let (half_width, half_height) = (sprite.width / 2.0, sprite.height / 2.0);
+    vec![
+        [center[0] - half_width, center[1] - half_height],
+        [center[0] + half_width, center[1] - half_height],
+        [center[0] + half_width, center[1] + half_height],
+        [center[0] - half_width, center[1] + half_height],
+    ]
+}
+
+/// Appends the triangles of the shadow of a convex polygon at the given Z coordinate.
+///
+/// Each edge facing away from the light is extruded to infinity away from the light, as points
+/// with a `w` of `0.0`. The shadows of these edges cover the shadow of the polygon without
+/// overlapping each other.
+fn push_shadow(vertices: &mut Vec<f32>, polygon: &[[f32; 2]], z: f32, light: ShadowLight) {
+    let edges = || {
+        polygon
+            .iter()
+            .zip(polygon.iter().cycle().skip(1))
+            .map(|(a, b)| (*a, *b))
+    };
+    // The outward normals of the edges depend on the winding of the polygon.
+    let area: f32 = edges().map(|(a, b)| a[0] * b[1] - b[0] * a[1]).sum();
+    if area == 0.0 {
+        return;
+    }
+    let winding = area.signum();
+    let away = |point: [f32; 2]| match light {
+        ShadowLight::Point(position) => [point[0] - position[0], point[1] - position[1]],
+        ShadowLight::Directional(direction) => direction,
+    };
+
+    for (a, b) in edges() {
+        let normal = [(b[1] - a[1]) * winding, (a[0] - b[0]) * winding];
+        let middle = away([(a[0] + b[0]) / 2.0, (a[1] + b[1]) / 2.0]);
+        if normal[0] * middle[0] + normal[1] * middle[1] <= 0.0 {
+            continue;
+        }
+        let (far_a, far_b) = (away(a), away(b));
+        vertices.extend(&[
+            a[0], a[1], z, 1.0, b[0], b[1], z, 1.0, far_b[0], far_b[1], 0.0, 0.0,
+        ]);
+        vertices.extend(&[
+            a[0], a[1], z, 1.0, far_b[0], far_b[1], 0.0, 0.0, far_a[0], far_a[1], 0.0, 0.0,
+        ]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shadow_extrudes_back_edges() {
+        let square = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]];
+        // Seen from the left, below the top, the left edge faces the light.
+        let mut vertices = Vec::new();
+        push_shadow(&mut vertices, &square, 0.0, ShadowLight::Point([-1.0, 0.5]));
+        assert_eq!(vertices.len(), 3 * 6 * 4);
+        assert!(vertices.chunks(4).all(|v| v[3] == 1.0 || v[0] > 0.0));
+
+        // The winding of the polygon does not matter.
+        let mut reversed = square;
+        reversed.reverse();
+        let mut vertices = Vec::new();
+        push_shadow(
+            &mut vertices,
+            &reversed,
+            0.0,
+            ShadowLight::Directional([1.0, 0.0]),
+        );
+        assert_eq!(vertices.len(), 6 * 4);
+        assert!(vertices.chunks(4).all(|v| v[3] == 0.0 || v[0] == 1.0));
+    }
+}
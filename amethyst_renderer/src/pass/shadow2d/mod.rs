@@ -0,0 +1,47 @@
+pub use self::interleaved::DrawShadows2D;
+
+mod interleaved;
+
+use gfx::{
+    format::{ChannelType, Format, SurfaceType},
+    pso::buffer::Element,
+    traits::Pod,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::vertex::{Attribute, AttributeFormat, Attributes, VertexFormat, With};
+
+static VERT_SRC: &[u8] = include_bytes!("../shaders/vertex/shadow2d.glsl");
+static FRAG_SRC: &[u8] = include_bytes!("../shaders/fragment/shadow2d.glsl");
+
+/// Position in homogeneous coordinates, with a `w` of `0.0` for the points at infinity.
+#[derive(Clone, Debug)]
+enum HomogeneousPosition {}
+impl Attribute for HomogeneousPosition {
+    const NAME: &'static str = "position";
+    const FORMAT: Format = Format(SurfaceType::R32_G32_B32_A32, ChannelType::Float);
+    const SIZE: u32 = 16;
+    type Repr = [f32; 4];
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+struct ShadowVertex {
+    pub position: [f32; 4],
+}
+
+unsafe impl Pod for ShadowVertex {}
+
+impl VertexFormat for ShadowVertex {
+    const ATTRIBUTES: Attributes<'static> = &[(
+        HomogeneousPosition::NAME,
+        <Self as With<HomogeneousPosition>>::FORMAT,
+    )];
+}
+
+impl With<HomogeneousPosition> for ShadowVertex {
+    const FORMAT: AttributeFormat = Element {
+        offset: 0,
+        format: HomogeneousPosition::FORMAT,
+    };
+}
@@ -0,0 +1,259 @@
+//! Shadow map pass
+
+use gfx::pso::buffer::ElemStride;
+
+use amethyst_assets::AssetStorage;
+use amethyst_core::{
+    nalgebra::Point3,
+    specs::prelude::{Join, Read, ReadStorage, Write},
+    transform::GlobalTransform,
+};
+use amethyst_error::Error;
+
+use crate::{
+    cam::{ActiveCamera, Camera},
+    hidden::{Hidden, HiddenPropagate},
+    light::Light,
+    mesh::{Mesh, MeshHandle},
+    pass::util::{get_camera, set_attribute_buffers, set_vertex_args, setup_vertex_args},
+    pipe::{
+        pass::{Pass, PassData},
+        DepthMode, Effect, NewEffect, Target, TargetBuilder,
+    },
+    shadow_map::DirectionalShadows,
+    types::{Encoder, Factory},
+    vertex::{
+        Attributes, PosColor, PosColorNorm, PosColorTex, PosNormTangTex, PosNormTex, PosTex,
+        Position, Query, Separate, VertexFormat,
+    },
+    Rgba,
+};
+
+static VERT_SRC: &[u8] = include_bytes!("../shaders/vertex/shadow_map.glsl");
+static FRAG_SRC: &[u8] = include_bytes!("../shaders/fragment/shadow_map.glsl");
+
+/// Draws the depth of the meshes seen from the first `DirectionalLight` into a shadow map, over
+/// the area set by the `DirectionalShadows` resource.
+///
+/// The pass is added to a stage drawing to a target with a depth buffer, such as the one returned
+/// by `DrawShadowMap::target`, which clears its depth to `1.0`. This stage comes before the ones
+/// of the lit passes sampling the shadow map, set up with their `with_shadow_map`.
+///
+/// The meshes cast shadows whether their vertices are interleaved, as drawn by `DrawShaded` and
+/// `DrawPbm`, or in separate buffers, as drawn by `DrawShadedSeparate` and `DrawPbmSeparate`, in
+/// their bind pose when they are skinned. Meshes of other vertex formats are skipped.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DrawShadowMap {
+    /// Effects reading the positions of interleaved vertex buffers, by the stride of their
+    /// vertices, set when the pass is compiled
+    interleaved_effects: Vec<(usize, Effect)>,
+}
+
+impl DrawShadowMap {
+    /// Create instance of `DrawShadowMap` pass
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns a target with a square depth buffer, whose width in texels is the resolution of
+    /// the shadow map.
+    pub fn target<N: Into<String>>(name: N, resolution: u32) -> TargetBuilder {
+        Target::named(name)
+            .with_depth_buf(true)
+            .with_size((resolution, resolution))
+    }
+}
+
+impl<'a> PassData<'a> for DrawShadowMap {
+    type Data = (
+        Read<'a, ActiveCamera>,
+        ReadStorage<'a, Camera>,
+        Write<'a, DirectionalShadows>,
+        Read<'a, AssetStorage<Mesh>>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, HiddenPropagate>,
+        ReadStorage<'a, MeshHandle>,
+        ReadStorage<'a, GlobalTransform>,
+        ReadStorage<'a, Light>,
+    );
+}
+
+/// Returns the position attribute and the stride of a vertex format.
+fn layout<V: Query<(Position,)>>() -> (Attributes<'static>, usize) {
+    (V::QUERIED_ATTRIBUTES, V::size())
+}
+
+/// Vertex buffer layouts of the meshes casting shadows, the separate position buffers first.
+fn position_layouts() -> [(Attributes<'static>, usize); 7] {
+    [
+        layout::<Separate<Position>>(),
+        layout::<PosColor>(),
+        layout::<PosColorNorm>(),
+        layout::<PosTex>(),
+        layout::<PosColorTex>(),
+        layout::<PosNormTex>(),
+        layout::<PosNormTangTex>(),
+    ]
+}
+
+/// Finds the layout of the positions of a mesh, given the stride of its vertex buffer holding the
+/// requested attributes.
+fn position_layout<F>(stride: F) -> Option<(Attributes<'static>, usize)>
+where
+    F: Fn(Attributes<'static>) -> Option<usize>,
+{
+    position_layouts()
+        .iter()
+        .cloned()
+        .find(|&(attributes, size)| stride(attributes) == Some(size))
+}
+
+fn build_effect(
+    effect: NewEffect<'_>,
+    (attributes, stride): (Attributes<'static>, usize),
+) -> Result<Effect, Error> {
+    let mut builder = effect.simple(VERT_SRC, FRAG_SRC);
+    builder.with_raw_vertex_buffer(attributes, stride as ElemStride, 0);
+    setup_vertex_args(&mut builder);
+    builder.with_output("color", Some(DepthMode::LessEqualWrite));
+    builder.build()
+}
+
+impl Pass for DrawShadowMap {
+    fn compile(&mut self, mut effect: NewEffect<'_>) -> Result<Effect, Error> {
+        let layouts = position_layouts();
+        self.interleaved_effects.clear();
+        for &(attributes, stride) in &layouts[1..] {
+            let interleaved = build_effect(effect.reborrow(), (attributes, stride))?;
+            self.interleaved_effects.push((stride, interleaved));
+        }
+        build_effect(effect, layouts[0])
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        _factory: Factory,
+        (
+            active,
+            camera,
+            mut shadows,
+            mesh_storage,
+            hidden,
+            hidden_prop,
+            mesh,
+            global,
+            light,
+        ): <Self as PassData<'a>>::Data,
+    ) {
+        let direction = light.join().find_map(|light| match *light {
+            Light::Directional(ref light) => Some(light.direction),
+            _ => None,
+        });
+        let center = shadows
+            .center
+            .or_else(|| {
+                get_camera(active, &camera, &global).map(|(_, transform)| {
+                    let position = transform.0.column(3);
+                    [position[0], position[1], position[2]]
+                })
+            })
+            .unwrap_or([0.0; 3]);
+        let center = Point3::new(center[0], center[1], center[2]);
+        shadows.light = direction.and_then(|direction| shadows.light_view(direction, center));
+        let (proj, view) = match shadows.light {
+            Some(light) => light,
+            None => return,
+        };
+
+        let light_camera = Camera { proj };
+        let light_transform = GlobalTransform(
+            view.try_inverse()
+                .expect("Unable to get inverse of the view of the light"),
+        );
+        for (mesh, global, _, _) in (&mesh, &global, !&hidden, !&hidden_prop).join() {
+            let mesh = match mesh_storage.get(mesh) {
+                Some(mesh) => mesh,
+                None => continue,
+            };
+            // Each effect reads the positions of the vertex buffers of one stride.
+            let (attributes, stride) = match position_layout(|attributes| {
+                mesh.buffer(attributes)
+                    .map(|buffer| buffer.get_info().stride)
+            }) {
+                Some(layout) => layout,
+                None => continue,
+            };
+            let effect = if stride == Separate::<Position>::size() {
+                &mut *effect
+            } else {
+                match self
+                    .interleaved_effects
+                    .iter_mut()
+                    .find(|(size, _)| *size == stride)
+                {
+                    Some((_, effect)) => effect,
+                    None => continue,
+                }
+            };
+            if !set_attribute_buffers(effect, mesh, &[attributes]) {
+                effect.clear();
+                continue;
+            }
+            set_vertex_args(
+                effect,
+                encoder,
+                Some((&light_camera, &light_transform)),
+                global,
+                Rgba::WHITE,
+            );
+            effect.draw(mesh.slice(), encoder);
+            effect.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::vertex::{Normal, TexCoord};
+
+    /// Finds the stride of the vertex format holding the attributes, like `Mesh::buffer`.
+    fn stride_in<V: VertexFormat>(attributes: Attributes<'_>) -> Option<usize> {
+        if attributes.iter().all(|attr| V::ATTRIBUTES.contains(attr)) {
+            Some(V::size())
+        } else {
+            None
+        }
+    }
+
+    #[test]
+    fn meshes_are_drawn_with_the_layout_of_their_vertices() {
+        let (attributes, stride) = position_layout(stride_in::<PosNormTex>).unwrap();
+        assert_eq!(
+            attributes,
+            <PosNormTex as Query<(Position,)>>::QUERIED_ATTRIBUTES
+        );
+        assert_eq!(stride, PosNormTex::size());
+
+        let (_, stride) = position_layout(stride_in::<PosNormTangTex>).unwrap();
+        assert_eq!(stride, PosNormTangTex::size());
+
+        let (_, stride) = position_layout(stride_in::<Separate<Position>>).unwrap();
+        assert_eq!(stride, Separate::<Position>::size());
+
+        // Meshes without positions cast no shadow.
+        assert!(position_layout(stride_in::<Separate<Normal>>).is_none());
+        assert!(position_layout(stride_in::<Separate<TexCoord>>).is_none());
+    }
+
+    #[test]
+    fn layouts_have_distinct_strides() {
+        let layouts = position_layouts();
+        for (i, &(_, stride)) in layouts.iter().enumerate() {
+            assert!(layouts[i + 1..].iter().all(|&(_, other)| other != stride));
+        }
+    }
+}
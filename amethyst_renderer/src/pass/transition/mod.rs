@@ -0,0 +1,233 @@
+//! Screen transition pass
+
+use amethyst_core::specs::Write;
+use amethyst_error::Error;
+use gfx::{
+    handle::RawRenderTargetView,
+    memory::Typed,
+    texture::{FilterMethod, SamplerInfo, WrapMode},
+    IndexBuffer,
+};
+use gfx_core::state::ColorMask;
+use log::{error, warn};
+
+use crate::{
+    pipe::{
+        pass::{Pass, PassData},
+        Effect, NewEffect, Targets,
+    },
+    transition::{ScreenTransition, TransitionEffect},
+    transparent::ALPHA,
+    types::{ColorFormat, Encoder, Factory, RawShaderResourceView, Resources, Sampler, Slice},
+};
+
+static VERT_SRC: &[u8] = include_bytes!("../shaders/vertex/fullscreen.glsl");
+static FRAG_SRC: &[u8] = include_bytes!("../shaders/fragment/transition.glsl");
+
+/// Draws the `ScreenTransition` over the screen.
+///
+/// Fades only need the pass to be the last one drawing to the screen. Crossfades need the scene
+/// to be rendered to a target, given with `with_source`: the pass then draws that target to the
+/// screen, and keeps its last frame before a state change to fade it out over the new state.
+#[derive(Clone, Debug, Default)]
+pub struct DrawTransition {
+    source: Option<String>,
+    source_view: Option<RawShaderResourceView>,
+    sampler: Option<Sampler>,
+    snapshot: Option<Snapshot>,
+}
+
+/// Texture keeping the last frame of the previous state.
+#[derive(Clone, Debug)]
+struct Snapshot {
+    view: RawShaderResourceView,
+    target: RawRenderTargetView<Resources>,
+    size: (u16, u16),
+    /// Whether a frame was captured in the texture
+    captured: bool,
+}
+
+impl DrawTransition {
+    /// Create instance of `DrawTransition` pass
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Draws the first color buffer of the named target to the screen, and uses it for
+    /// crossfades.
+    pub fn with_source<N: Into<String>>(mut self, target: N) -> Self {
+        self.source = Some(target.into());
+        self
+    }
+
+    fn snapshot(
+        &mut self,
+        factory: &mut Factory,
+        size: (u16, u16),
+    ) -> Result<&mut Snapshot, Error> {
+        use gfx::Factory;
+
+        let outdated = self
+            .snapshot
+            .as_ref()
+            .map_or(true, |snapshot| snapshot.size != size);
+        if outdated {
+            let (_, view, target) = factory.create_render_target::<ColorFormat>(size.0, size.1)?;
+            self.snapshot = Some(Snapshot {
+                view: view.raw().clone(),
+                target: target.raw().clone(),
+                size,
+                captured: false,
+            });
+        }
+        Ok(self.snapshot.as_mut().expect("Unreachable: Created above"))
+    }
+}
+
+impl<'a> PassData<'a> for DrawTransition {
+    type Data = Write<'a, ScreenTransition>;
+}
+
+impl Pass for DrawTransition {
+    fn compile(&mut self, effect: NewEffect<'_>) -> Result<Effect, Error> {
+        use gfx::Factory;
+
+        self.sampler = Some(
+            effect
+                .factory
+                .create_sampler(SamplerInfo::new(FilterMethod::Bilinear, WrapMode::Clamp)),
+        );
+        effect
+            .simple(VERT_SRC, FRAG_SRC)
+            .without_back_face_culling()
+            .with_raw_global("fill_color")
+            .with_raw_global("textured")
+            .with_raw_global("opacity")
+            .with_texture("source")
+            .with_blended_output("color", ColorMask::all(), ALPHA, None)
+            .build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        mut factory: Factory,
+        mut transition: <Self as PassData<'a>>::Data,
+    ) {
+        let (width, height) = match effect.data.out_blends.first() {
+            Some(target) => {
+                let (width, height, _, _) = target.get_dimensions();
+                (width, height)
+            }
+            None => return,
+        };
+        let sampler = self
+            .sampler
+            .clone()
+            .expect("Pass doesn't seem to be compiled.");
+        let source = self.source_view.clone();
+        let snapshot = match self.snapshot(&mut factory, (width, height)) {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                error!("Failed to create the screen transition texture: {}", e);
+                return;
+            }
+        };
+
+        if let Some(ref source) = source {
+            if transition.capture_requested() {
+                let target = Some(&snapshot.target);
+                draw(effect, encoder, target, source, &sampler, None, 1.0);
+                snapshot.captured = true;
+            }
+            draw(effect, encoder, None, source, &sampler, None, 1.0);
+        }
+        // Without source the crossfade has nothing to capture, and cuts to the new state.
+        transition.set_captured();
+
+        let opacity = transition.opacity();
+        if opacity <= 0.0 {
+            return;
+        }
+        match transition.effect {
+            Some(TransitionEffect::FadeToColor(color)) => {
+                let color = Some(color.into());
+                draw(
+                    effect,
+                    encoder,
+                    None,
+                    &snapshot.view,
+                    &sampler,
+                    color,
+                    opacity,
+                );
+            }
+            Some(TransitionEffect::Crossfade) if snapshot.captured => {
+                draw(
+                    effect,
+                    encoder,
+                    None,
+                    &snapshot.view,
+                    &sampler,
+                    None,
+                    opacity,
+                );
+            }
+            _ => {}
+        }
+    }
+
+    fn new_targets(&mut self, targets: &Targets) {
+        let name = match self.source {
+            Some(ref name) => name,
+            None => return,
+        };
+        self.source_view = targets
+            .get(name)
+            .and_then(|target| target.color_buf(0))
+            .and_then(|buffer| buffer.as_input.as_ref())
+            .map(|view| view.raw().clone());
+        if self.source_view.is_none() {
+            warn!("Screen transition source target {:?} not found", name);
+        }
+    }
+}
+
+/// Draws the texture, or the fill color if given, over the whole target. Draws to the target of
+/// the stage if no target is given.
+fn draw(
+    effect: &mut Effect,
+    encoder: &mut Encoder,
+    target: Option<&RawRenderTargetView<Resources>>,
+    texture: &RawShaderResourceView,
+    sampler: &Sampler,
+    fill_color: Option<[f32; 4]>,
+    opacity: f32,
+) {
+    let previous = target.map(|target| {
+        let depth = effect.data.out_depth.take();
+        let out = std::mem::replace(&mut effect.data.out_blends[0], target.clone());
+        (out, depth)
+    });
+
+    effect.update_global("fill_color", fill_color.unwrap_or([0.0; 4]));
+    effect.update_global("textured", if fill_color.is_some() { 0.0 } else { 1.0 });
+    effect.update_global("opacity", opacity);
+    effect.data.textures.push(texture.clone());
+    effect.data.samplers.push(sampler.clone());
+    let slice = Slice {
+        start: 0,
+        end: 3,
+        base_vertex: 0,
+        instances: None,
+        buffer: IndexBuffer::Auto,
+    };
+    effect.draw(&slice, encoder);
+    effect.clear();
+
+    if let Some((out, depth)) = previous {
+        effect.data.out_blends[0] = out;
+        effect.data.out_depth = depth;
+    }
+}
@@ -22,7 +22,7 @@ use crate::{
     pipe::{DepthMode, Effect, EffectBuilder},
     skinning::JointTransforms,
     tex::Texture,
-    types::Encoder,
+    types::{Encoder, RawShaderResourceView, Sampler},
     vertex::Attributes,
     Rgba,
 };
@@ -92,8 +92,13 @@ pub(crate) fn set_attribute_buffers(
 }
 
 pub(crate) fn add_texture(effect: &mut Effect, texture: &Texture) {
+    add_texture_with_sampler(effect, texture, texture.sampler());
+}
+
+/// Binds a texture sampled with another sampler than its own.
+pub(crate) fn add_texture_with_sampler(effect: &mut Effect, texture: &Texture, sampler: &Sampler) {
     effect.data.textures.push(texture.view().clone());
-    effect.data.samplers.push(texture.sampler().clone());
+    effect.data.samplers.push(sampler.clone());
 }
 
 pub(crate) fn setup_textures(builder: &mut EffectBuilder<'_>, types: &[TextureType]) {
@@ -347,6 +352,7 @@ pub(crate) fn draw_mesh(
     global: Option<&GlobalTransform>,
     attributes: &[Attributes<'static>],
     textures: &[TextureType],
+    shadow_map: Option<(&RawShaderResourceView, &Sampler)>,
 ) {
     #[cfg(feature = "profiler")]
     profile_scope!("render_drawmesh");
@@ -387,6 +393,10 @@ pub(crate) fn draw_mesh(
         &material_defaults.0,
         textures,
     );
+    if let Some((view, sampler)) = shadow_map {
+        effect.data.textures.push(view.clone());
+        effect.data.samplers.push(sampler.clone());
+    }
 
     effect.draw(mesh.slice(), encoder);
     effect.clear();
@@ -164,6 +164,15 @@ impl<'f> NewEffect<'f> {
         }
     }
 
+    /// Borrows the factory and target again, to build several effects in `Pass::compile`.
+    pub fn reborrow(&mut self) -> NewEffect<'_> {
+        NewEffect {
+            factory: &mut *self.factory,
+            out: self.out,
+            multisampling: self.multisampling,
+        }
+    }
+
     pub fn simple<S: Into<&'f [u8]>>(self, vs: S, ps: S) -> EffectBuilder<'f> {
         let src = ProgramSource::Simple(vs.into(), ps.into());
         EffectBuilder::new(self.factory, self.out, self.multisampling, src)
@@ -310,6 +319,29 @@ impl<'a> EffectBuilder<'a> {
         self
     }
 
+    /// Replaces the blend state of the blended outputs, to build variants of an `Effect` blending
+    /// differently.
+    pub fn with_output_blend(&mut self, blend: Blend) -> &mut Self {
+        for output in &mut self.init.out_blends {
+            output.2 = blend;
+        }
+        self
+    }
+
+    /// Replaces the stencil test of the output target, to build variants of an `Effect` masked
+    /// differently. Outputs without depth test are given one always passing.
+    pub fn with_output_stencil(&mut self, stencil: Stencil) -> &mut Self {
+        let depth = self.init.out_depth.map_or(
+            Depth {
+                fun: Comparison::Always,
+                write: false,
+            },
+            |(depth, _)| depth,
+        );
+        self.init.out_depth = Some((depth, stencil));
+        self
+    }
+
     /// Adds a texture sampler to this `Effect`.
     pub fn with_texture(&mut self, name: &'a str) -> &mut Self {
         self.init.samplers.push(name);
@@ -347,7 +379,7 @@ impl<'a> EffectBuilder<'a> {
             .const_bufs
             .iter()
             .enumerate()
-            .zip(self.const_bufs.drain(..))
+            .zip(self.const_bufs.iter().cloned())
             .map(|((i, name), info)| {
                 let cbuf = fac.create_buffer_raw(info)?;
                 data.const_bufs.push(cbuf);
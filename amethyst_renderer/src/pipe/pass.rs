@@ -4,7 +4,7 @@ use amethyst_core::specs::prelude::SystemData;
 use amethyst_error::Error;
 
 use crate::{
-    pipe::{Effect, NewEffect, Target},
+    pipe::{Effect, NewEffect, Target, Targets},
     types::{Encoder, Factory},
 };
 
@@ -29,6 +29,10 @@ pub trait Pass: for<'a> PassData<'a> {
         factory: Factory,
         data: <Self as PassData<'b>>::Data,
     );
+
+    /// Called with all the targets of the pipeline when it is built and when they are recreated,
+    /// for passes reading other targets than the one of their stage.
+    fn new_targets(&mut self, _targets: &Targets) {}
 }
 
 /// A compiled pass.  These are created and managed by the `Renderer`.  This should not be
@@ -47,9 +51,11 @@ where
         mut pass: P,
         fac: &mut Factory,
         out: &Target,
+        targets: &Targets,
         multisampling: u16,
     ) -> Result<Self, Error> {
         let effect = pass.compile(NewEffect::new(fac, out, multisampling))?;
+        pass.new_targets(targets);
         Ok(CompiledPass {
             effect,
             inner: pass,
@@ -71,7 +77,10 @@ impl<P> CompiledPass<P> {
     }
 
     /// Distributes new target data to the pass.
-    pub fn new_target(&mut self, target: &Target) {
+    pub fn new_target(&mut self, target: &Target, targets: &Targets)
+    where
+        P: Pass,
+    {
         // Distribute new targets that don't blend.
         self.effect.data.out_colors.clear();
         self.effect
@@ -88,5 +97,7 @@ impl<P> CompiledPass<P> {
 
         // Distribute new depth buffer
         self.effect.data.out_depth = target.depth_buf().map(|db| (db.as_output.clone(), (0, 0)));
+
+        self.inner.new_targets(targets);
     }
 }
@@ -70,7 +70,7 @@ pub trait Passes: for<'a> PassesData<'a> {
     );
 
     /// Distributes new targets
-    fn new_target(&mut self, new_target: &Target);
+    fn new_target(&mut self, new_target: &Target, targets: &Targets);
 }
 
 impl<'a, HP> PassesData<'a> for List<(CompiledPass<HP>, List<()>)>
@@ -94,9 +94,9 @@ where
         hp.apply(encoder, factory, hd);
     }
 
-    fn new_target(&mut self, new_target: &Target) {
+    fn new_target(&mut self, new_target: &Target, targets: &Targets) {
         let List((ref mut hp, _)) = *self;
-        hp.new_target(new_target);
+        hp.new_target(new_target, targets);
     }
 }
 
@@ -124,10 +124,10 @@ where
         tp.apply(encoder, factory, td);
     }
 
-    fn new_target(&mut self, new_target: &Target) {
+    fn new_target(&mut self, new_target: &Target, targets: &Targets) {
         let List((ref mut hp, ref mut tp)) = *self;
-        hp.new_target(new_target);
-        tp.new_target(new_target);
+        hp.new_target(new_target, targets);
+        tp.new_target(new_target, targets);
     }
 }
 
@@ -182,7 +182,7 @@ where
         match new_targets.get(&self.target_name) {
             Some(target) => {
                 self.target = target.clone();
-                self.passes.new_target(target);
+                self.passes.new_target(target, new_targets);
             }
             None => {
                 error!("Target name {:?} not found!", self.target_name);
@@ -256,7 +256,7 @@ impl<Q> StageBuilder<Q> {
         let passes = self
             .passes
             .into_list()
-            .fmap(CompilePass::new(fac, &out, multisampling))
+            .fmap(CompilePass::new(fac, &out, targets, multisampling))
             .r#try()?;
 
         Ok(Stage {
@@ -286,14 +286,21 @@ impl<Q> StageBuilder<Queue<Q>> {
 pub struct CompilePass<'a> {
     factory: &'a mut Factory,
     target: &'a Target,
+    targets: &'a Targets,
     multisampling: u16,
 }
 
 impl<'a> CompilePass<'a> {
-    fn new(factory: &'a mut Factory, target: &'a Target, multisampling: u16) -> Self {
+    fn new(
+        factory: &'a mut Factory,
+        target: &'a Target,
+        targets: &'a Targets,
+        multisampling: u16,
+    ) -> Self {
         CompilePass {
             factory,
             target,
+            targets,
             multisampling,
         }
     }
@@ -305,7 +312,13 @@ where
 {
     type Output = Result<CompiledPass<P>, Error>;
     fn call_once(self, (pass,): (P,)) -> Result<CompiledPass<P>, Error> {
-        CompiledPass::compile(pass, self.factory, self.target, self.multisampling)
+        CompiledPass::compile(
+            pass,
+            self.factory,
+            self.target,
+            self.targets,
+            self.multisampling,
+        )
     }
 }
 impl<'a, P> HetFnMut<(P,)> for CompilePass<'a>
@@ -313,6 +326,12 @@ where
     P: Pass,
 {
     fn call_mut(&mut self, (pass,): (P,)) -> Result<CompiledPass<P>, Error> {
-        CompiledPass::compile(pass, self.factory, self.target, self.multisampling)
+        CompiledPass::compile(
+            pass,
+            self.factory,
+            self.target,
+            self.targets,
+            self.multisampling,
+        )
     }
 }
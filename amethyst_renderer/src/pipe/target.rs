@@ -7,8 +7,12 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "profiler")]
 use thread_profiler::profile_scope;
 
-use crate::types::{
-    DepthStencilView, Encoder, Factory, RenderTargetView, ShaderResourceView, Window,
+use crate::{
+    tex::Texture,
+    types::{
+        DepthStencilView, Encoder, Factory, RawTexture, RenderTargetView, Sampler,
+        ShaderResourceView, Window,
+    },
 };
 
 /// Target color buffer.
@@ -39,8 +43,12 @@ pub type Targets = HashMap<String, Target>;
 #[derive(Clone, Debug, PartialEq)]
 pub struct Target {
     color_bufs: Vec<ColorBuffer>,
+    /// Textures of the color buffers, empty for the main target
+    color_textures: Vec<RawTexture>,
     depth_buf: Option<DepthBuffer>,
     size: (u32, u32),
+    /// Whether the target keeps its size when the window is resized
+    fixed_size: bool,
 }
 
 impl Target {
@@ -48,8 +56,10 @@ impl Target {
     pub(crate) fn new(cb: ColorBuffer, db: DepthBuffer, size: (u32, u32)) -> Self {
         Target {
             color_bufs: vec![cb],
+            color_textures: Vec::new(),
             depth_buf: Some(db),
             size,
+            fixed_size: false,
         }
     }
 
@@ -92,6 +102,26 @@ impl Target {
         self.color_bufs.as_ref()
     }
 
+    /// Returns the color buffer with index `i` as a texture sampled with the given sampler.
+    ///
+    /// The main target has no texture.
+    pub fn color_texture(&self, i: usize, sampler: &Sampler) -> Option<Texture> {
+        use gfx::memory::Typed;
+
+        let view = self.color_bufs.get(i)?.as_input.as_ref()?;
+        let texture = self.color_textures.get(i)?;
+        Some(Texture::from_raw(
+            texture.clone(),
+            view.raw().clone(),
+            sampler.clone(),
+        ))
+    }
+
+    /// Returns whether the target was created with a size, kept when the window is resized.
+    pub fn has_fixed_size(&self) -> bool {
+        self.fixed_size
+    }
+
     /// Returns the render target's depth-stencil buffer, if it has one.
     pub fn depth_buf(&self) -> Option<&DepthBuffer> {
         self.depth_buf.as_ref()
@@ -184,16 +214,21 @@ impl TargetBuilder {
 
         let size = self.custom_size.unwrap_or(size);
 
-        let color_bufs = (0..self.num_color_bufs)
+        let (color_bufs, color_textures) = (0..self.num_color_bufs)
             .map(|_| {
+                use gfx::memory::Typed;
+
                 let (w, h) = (size.0 as u16, size.1 as u16);
-                let (_, res, rt) = fac.create_render_target(w, h)?;
-                Ok(ColorBuffer {
+                let (texture, res, rt) = fac.create_render_target(w, h)?;
+                let buffer = ColorBuffer {
                     as_input: Some(res),
                     as_output: rt,
-                })
+                };
+                Ok((buffer, texture.raw().clone()))
             })
-            .collect::<Result<_, Error>>()?;
+            .collect::<Result<Vec<_>, Error>>()?
+            .into_iter()
+            .unzip();
 
         let depth_buf = if self.has_depth_buf {
             let (w, h) = (size.0 as u16, size.1 as u16);
@@ -209,8 +244,10 @@ impl TargetBuilder {
 
         let target = Target {
             color_bufs,
+            color_textures,
             depth_buf,
             size,
+            fixed_size: self.custom_size.is_some(),
         };
 
         Ok((self.name, target))
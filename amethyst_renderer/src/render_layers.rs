@@ -0,0 +1,155 @@
+//! Named layers ordering the sprites and debug lines of 2D scenes.
+
+use std::collections::HashSet;
+
+use amethyst_assets::{PrefabData, ProgressCounter};
+use amethyst_core::specs::{
+    Component, DenseVecStorage, Entities, Join, Read, ReadStorage, System, WriteStorage,
+};
+use amethyst_derive::PrefabData;
+use amethyst_error::Error;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::sprite::DrawOrder;
+
+/// Resource declaring the layers of the scene by name, each with the `DrawOrder` layer it is
+/// drawn in.
+///
+/// Entities are placed in a layer by their `Layer` component, instead of raw Z coordinates or
+/// `DrawOrder` layers spread across the code.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RenderLayers {
+    layers: Vec<(String, i32)>,
+}
+
+impl RenderLayers {
+    /// Creates the layers without any layer.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Declares a layer drawn in the given order, the layers with a higher order are drawn over
+    /// the ones with a lower order.
+    ///
+    /// Sprites without `Layer` or `DrawOrder` are in order `0`.
+    pub fn with_layer<N: Into<String>>(mut self, name: N, order: i32) -> Self {
+        self.set_layer(name, order);
+        self
+    }
+
+    /// Declares a layer, or changes the order of a declared layer.
+    pub fn set_layer<N: Into<String>>(&mut self, name: N, order: i32) {
+        let name = name.into();
+        match self.layers.iter_mut().find(|(known, _)| *known == name) {
+            Some(layer) => layer.1 = order,
+            None => self.layers.push((name, order)),
+        }
+    }
+
+    /// Returns the order of the named layer, if it is declared.
+    pub fn order(&self, name: &str) -> Option<i32> {
+        self.layers
+            .iter()
+            .find(|(known, _)| known == name)
+            .map(|(_, order)| *order)
+    }
+
+    /// Returns the names of the layers, from the lowest to the highest order.
+    pub fn names(&self) -> Vec<&str> {
+        let mut layers = self.layers.iter().collect::<Vec<_>>();
+        layers.sort_by_key(|(_, order)| *order);
+        layers.into_iter().map(|(name, _)| name.as_str()).collect()
+    }
+}
+
+/// Places the entity in a layer declared by the `RenderLayers` resource.
+///
+/// The `RenderLayerSystem` sets the layer of the `DrawOrder` of the sprites and images, keeping
+/// their order in the layer, and the `DrawDebugLines` pass draws the `DebugLinesComponent`s by
+/// layer.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, PrefabData)]
+#[prefab(Component)]
+pub struct Layer(pub String);
+
+impl Layer {
+    /// Places the entity in the named layer.
+    pub fn new<N: Into<String>>(name: N) -> Self {
+        Layer(name.into())
+    }
+}
+
+impl Component for Layer {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// System setting the `DrawOrder` of the entities with a `Layer` from the `RenderLayers`.
+///
+/// It should run before the `SpriteVisibilitySortingSystem`. Entities in an undeclared layer keep
+/// their `DrawOrder`.
+#[derive(Debug, Default)]
+pub struct RenderLayerSystem {
+    /// Undeclared layers already warned about
+    unknown: HashSet<String>,
+}
+
+impl RenderLayerSystem {
+    /// Creates a new `RenderLayerSystem`.
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<'a> System<'a> for RenderLayerSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, RenderLayers>,
+        ReadStorage<'a, Layer>,
+        WriteStorage<'a, DrawOrder>,
+    );
+
+    fn run(&mut self, (entities, layers, layer_storage, mut draw_orders): Self::SystemData) {
+        for (entity, layer) in (&*entities, &layer_storage).join() {
+            let order = match layers.order(&layer.0) {
+                Some(order) => order,
+                None => {
+                    if self.unknown.insert(layer.0.clone()) {
+                        warn!(
+                            "Render layer {:?} is not declared in `RenderLayers`.",
+                            layer.0
+                        );
+                    }
+                    continue;
+                }
+            };
+            match draw_orders.get_mut(entity) {
+                Some(draw_order) => draw_order.layer = order,
+                None => {
+                    draw_orders
+                        .insert(entity, DrawOrder::new(order, 0))
+                        .expect("Unreachable: Entity is alive");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layers_are_ordered() {
+        let mut layers = RenderLayers::new()
+            .with_layer("ui", 10)
+            .with_layer("background", -10)
+            .with_layer("actors", 0);
+        assert_eq!(layers.order("background"), Some(-10));
+        assert_eq!(layers.order("sky"), None);
+        assert_eq!(layers.names(), vec!["background", "actors", "ui"]);
+
+        layers.set_layer("background", 20);
+        assert_eq!(layers.order("background"), Some(20));
+        assert_eq!(layers.names(), vec!["actors", "ui", "background"]);
+    }
+}
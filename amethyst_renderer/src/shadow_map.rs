@@ -0,0 +1,131 @@
+//! Shadows cast by directional lights.
+
+use amethyst_core::nalgebra::{Matrix4, Point3, Vector3};
+
+/// Resource configuring the shadows cast by the first `DirectionalLight`.
+///
+/// The `DrawShadowMap` pass draws the depth of the scene seen from the light into the depth
+/// buffer of a target, which the lit passes sample with `with_shadow_map`. The shadow map covers
+/// a square area around the center, following the active camera unless a center is set, so
+/// smaller extents give sharper shadows.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DirectionalShadows {
+    /// Half the width of the area covered by the shadow map, in world units.
+    pub extent: f32,
+    /// Depth of the area covered by the shadow map along the light, in world units, centered on
+    /// the center of the area.
+    pub depth: f32,
+    /// Depth subtracted from the surfaces before comparing them to the shadow map, in world
+    /// units, so they don't shadow themselves.
+    pub bias: f32,
+    /// Radius of the texels of the shadow map averaged around each surface by percentage closer
+    /// filtering, to soften the edges of the shadows. `0` gives hard shadows.
+    pub pcf_radius: u32,
+    /// Center of the area covered by the shadow map, or `None` to follow the active camera.
+    pub center: Option<[f32; 3]>,
+    /// Projection and view of the light, set by the `DrawShadowMap` pass for the frame
+    pub(crate) light: Option<(Matrix4<f32>, Matrix4<f32>)>,
+}
+
+impl Default for DirectionalShadows {
+    fn default() -> Self {
+        DirectionalShadows {
+            extent: 20.0,
+            depth: 100.0,
+            bias: 0.05,
+            pcf_radius: 1,
+            center: None,
+            light: None,
+        }
+    }
+}
+
+impl DirectionalShadows {
+    /// Creates shadows covering 40 by 40 world units around the active camera, averaging 3 by 3
+    /// texels around each surface.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets half the width of the area covered by the shadow map.
+    pub fn with_extent(mut self, extent: f32) -> Self {
+        self.extent = extent;
+        self
+    }
+
+    /// Sets the depth of the area covered by the shadow map along the light.
+    pub fn with_depth(mut self, depth: f32) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Sets the depth subtracted from the surfaces before comparing them to the shadow map.
+    pub fn with_bias(mut self, bias: f32) -> Self {
+        self.bias = bias;
+        self
+    }
+
+    /// Sets the radius of the texels averaged around each surface.
+    pub fn with_pcf_radius(mut self, pcf_radius: u32) -> Self {
+        self.pcf_radius = pcf_radius;
+        self
+    }
+
+    /// Sets the center of the area covered by the shadow map, instead of following the camera.
+    pub fn with_center(mut self, center: [f32; 3]) -> Self {
+        self.center = Some(center);
+        self
+    }
+
+    /// Returns the matrix projecting world coordinates into the shadow map, once it is drawn.
+    pub fn light_matrix(&self) -> Option<Matrix4<f32>> {
+        self.light.map(|(proj, view)| proj * view)
+    }
+
+    /// Returns the projection and the view of a light shining in the direction onto the area
+    /// around the center, or `None` without a direction.
+    pub(crate) fn light_view(
+        &self,
+        direction: [f32; 3],
+        center: Point3<f32>,
+    ) -> Option<(Matrix4<f32>, Matrix4<f32>)> {
+        let direction = Vector3::from_row_slice(&direction).try_normalize(1.0e-6)?;
+        let up = if direction.y.abs() > 0.99 {
+            Vector3::z()
+        } else {
+            Vector3::y()
+        };
+        let eye = center - direction * (self.depth / 2.0);
+        let view = Matrix4::look_at_rh(&eye, &center, &up);
+        let (extent, depth) = (self.extent, self.depth);
+        let proj = Matrix4::new_orthographic(-extent, extent, -extent, extent, 0.0, depth);
+        Some((proj, view))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use amethyst_core::nalgebra::Vector4;
+
+    #[test]
+    fn light_view_covers_area_around_center() {
+        let mut shadows = DirectionalShadows::new().with_extent(10.0).with_depth(40.0);
+        let center = Point3::new(5.0, 0.0, 5.0);
+        assert!(shadows.light_view([0.0; 3], center).is_none());
+        assert_eq!(shadows.light_matrix(), None);
+
+        shadows.light = shadows.light_view([0.0, -2.0, 0.0], center);
+        let matrix = shadows.light_matrix().unwrap();
+        let project = |x, y, z| matrix * Vector4::new(x, y, z, 1.0);
+        // The center is in the middle of the shadow map, and the light shines from above.
+        let middle = project(5.0, 0.0, 5.0);
+        assert!(middle.x.abs() < 1e-5 && middle.y.abs() < 1e-5 && middle.z.abs() < 1e-5);
+        assert!((project(5.0, 20.0, 5.0).z + 1.0).abs() < 1e-5);
+        assert!((project(5.0, -20.0, 5.0).z - 1.0).abs() < 1e-5);
+        let corner = project(15.0, 0.0, 15.0);
+        assert!((corner.x.abs() - 1.0).abs() < 1e-5);
+        assert!((corner.y.abs() - 1.0).abs() < 1e-5);
+    }
+}
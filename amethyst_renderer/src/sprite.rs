@@ -1,10 +1,20 @@
 use serde::{Deserialize, Serialize};
+use smallvec::{smallvec, SmallVec};
 
-use amethyst_assets::{Asset, Handle, ProcessingState};
-use amethyst_core::specs::prelude::{Component, DenseVecStorage, VecStorage};
+use amethyst_assets::{Asset, AssetStorage, Handle, ProcessingState};
+use amethyst_core::{
+    nalgebra::{Matrix4, Vector3},
+    specs::prelude::{Component, DenseVecStorage, NullStorage, VecStorage},
+    transform::GlobalTransform,
+};
 use amethyst_error::Error;
 
-use crate::Texture;
+use crate::{
+    formats::{SerializedSpriteSheet, SpriteGrid, SpritePosition},
+    tex::SamplerInfo,
+    transparent::{Blend, BlendChannel, BlendValue, Equation, Factor, ALPHA},
+    Rgba, Texture,
+};
 
 /// An asset handle to sprite sheet metadata.
 pub type SpriteSheetHandle = Handle<SpriteSheet>;
@@ -12,12 +22,128 @@ pub type SpriteSheetHandle = Handle<SpriteSheet>;
 /// Meta data for a sprite sheet texture.
 ///
 /// Contains a handle to the texture and the sprite coordinates on the texture.
+///
+/// Large sprite sheets can be split across several textures, as low-end GPUs limit the size of
+/// textures. The sprites of the first page are on `texture`, and the sprites of the other pages on
+/// the textures of `pages`.
+///
+/// Sprites drawn with lighting by `DrawFlat2D` are shaded with the normal map of their page, if
+/// `normal_maps` has one. Its texels hold the normal of the sprite, with red going right, green
+/// going up and blue coming out of the sprite.
+///
+/// The textures are sampled with `sampler` if the sprite sheet has one, instead of the sampler of
+/// their `TextureMetadata`. Pixel art atlases use nearest filtering this way, without changing the
+/// sampling of the other sprite sheets sharing their textures.
 #[derive(Clone, Debug, PartialEq)]
 pub struct SpriteSheet {
     /// `Texture` handle of the spritesheet texture
     pub texture: Handle<Texture>,
     /// A list of sprites in this sprite sheet.
     pub sprites: Vec<Sprite>,
+    /// `Texture` handles of the pages after the first one
+    pub pages: Vec<Handle<Texture>>,
+    /// `Texture` handles of the normal maps of the pages, starting with the first one
+    pub normal_maps: Vec<Handle<Texture>>,
+    /// Filtering and wrapping of the textures of the sprites, overriding their own sampler
+    pub sampler: Option<SamplerInfo>,
+}
+
+impl SpriteSheet {
+    /// Creates a sprite sheet of the sprites of a grid in a texture, without a sprite sheet file.
+    ///
+    /// The size of the texture in pixels is the one returned by `Texture::size` once it is loaded,
+    /// `from_texture_grid` reads it from the loaded texture.
+    pub fn from_grid(texture: Handle<Texture>, image_size: (u32, u32), grid: SpriteGrid) -> Self {
+        Self::from_serialized(texture, image_size, Some(grid), Vec::new())
+    }
+
+    /// Creates a sprite sheet of the sprites of the given regions of a texture, without a sprite
+    /// sheet file.
+    ///
+    /// The size of the texture in pixels is the one returned by `Texture::size` once it is loaded,
+    /// `from_texture_regions` reads it from the loaded texture.
+    pub fn from_regions(
+        texture: Handle<Texture>,
+        image_size: (u32, u32),
+        regions: Vec<SpritePosition>,
+    ) -> Self {
+        Self::from_serialized(texture, image_size, None, regions)
+    }
+
+    /// Creates a sprite sheet of the sprites of a grid in a loaded texture.
+    ///
+    /// Returns `None` if the texture is not loaded yet.
+    pub fn from_texture_grid(
+        texture: Handle<Texture>,
+        storage: &AssetStorage<Texture>,
+        grid: SpriteGrid,
+    ) -> Option<Self> {
+        let image_size = texture_size(&texture, storage)?;
+        Some(Self::from_grid(texture, image_size, grid))
+    }
+
+    /// Creates a sprite sheet of the sprites of the given regions of a loaded texture.
+    ///
+    /// Returns `None` if the texture is not loaded yet.
+    pub fn from_texture_regions(
+        texture: Handle<Texture>,
+        storage: &AssetStorage<Texture>,
+        regions: Vec<SpritePosition>,
+    ) -> Option<Self> {
+        let image_size = texture_size(&texture, storage)?;
+        Some(Self::from_regions(texture, image_size, regions))
+    }
+
+    fn from_serialized(
+        texture: Handle<Texture>,
+        (spritesheet_width, spritesheet_height): (u32, u32),
+        grid: Option<SpriteGrid>,
+        sprites: Vec<SpritePosition>,
+    ) -> Self {
+        let sheet = SerializedSpriteSheet {
+            spritesheet_width,
+            spritesheet_height,
+            grid,
+            sprites,
+            sampler: None,
+            uv_inset: 0.0,
+        };
+        SpriteSheet {
+            texture,
+            sprites: sheet.build_sprites(),
+            pages: Vec::new(),
+            normal_maps: Vec::new(),
+            sampler: None,
+        }
+    }
+
+    /// Returns the index of the first sprite with the given name, to use as the
+    /// `sprite_number` of a `SpriteRender`.
+    ///
+    /// Unlike indices, names don't change when the sprite sheet is packed again.
+    pub fn sprite_index(&self, name: &str) -> Option<usize> {
+        self.sprites
+            .iter()
+            .position(|sprite| sprite.name.as_ref().map(String::as_str) == Some(name))
+    }
+
+    /// Returns the texture of a page, `texture` for page `0`.
+    pub fn page(&self, page: usize) -> Option<&Handle<Texture>> {
+        match page {
+            0 => Some(&self.texture),
+            _ => self.pages.get(page - 1),
+        }
+    }
+
+    /// Returns the normal map of a page, if it has one.
+    pub fn normal_map(&self, page: usize) -> Option<&Handle<Texture>> {
+        self.normal_maps.get(page)
+    }
+}
+
+/// Returns the size in pixels of a loaded texture.
+fn texture_size(texture: &Handle<Texture>, storage: &AssetStorage<Texture>) -> Option<(u32, u32)> {
+    Texture::size_of(texture, storage).map(|(width, height)| (width as u32, height as u32))
 }
 
 impl Asset for SpriteSheet {
@@ -34,6 +160,9 @@ impl From<SpriteSheet> for Result<ProcessingState<SpriteSheet>, Error> {
 
 /// Information about whether or not a texture should be flipped
 /// when rendering.
+///
+/// Flipped sprites are mirrored around their entity: the UVs are swapped and the sprite offsets
+/// are mirrored, so the sprite keeps its pivot point.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Flipped {
     /// Don't flip the texture
@@ -50,6 +179,461 @@ impl Component for Flipped {
     type Storage = DenseVecStorage<Self>;
 }
 
+/// Explicit draw order of a sprite or image drawn by the `DrawFlat2D` pass.
+///
+/// The entities with a `DrawOrder` are drawn after the other opaque sprites, by increasing layer,
+/// then by increasing order in the layer, then from far to near. Semi-transparent sprites without
+/// a `DrawOrder` are in layer `0` with order `0`. The layer of the entities with a named `Layer`
+/// is set by the `RenderLayerSystem`.
+///
+/// The depth test of the pass still applies, so sprites of a higher layer must not be further
+/// away than the sprites they cover, or the depth test must be disabled with
+/// `DrawFlat2D::with_transparency_settings`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct DrawOrder {
+    /// Layer of the sprite, such as background, foreground or HUD
+    pub layer: i32,
+    /// Order of the sprite in its layer
+    pub order: i32,
+}
+
+impl DrawOrder {
+    /// Creates a draw order in the given layer.
+    pub fn new(layer: i32, order: i32) -> Self {
+        DrawOrder { layer, order }
+    }
+}
+
+impl Component for DrawOrder {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// How the `DrawFlat2D` pass blends a sprite or image with what is drawn behind it.
+///
+/// Sprites without a `BlendMode` use alpha blending. The other modes are only available when the
+/// transparency of the pass is enabled, and are drawn as semi-transparent by the
+/// `SpriteVisibilitySortingSystem`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum BlendMode {
+    /// Covers the colors behind the sprite by its alpha
+    Alpha,
+    /// Adds the colors of the sprite, weighted by its alpha, to the colors behind it, for glows,
+    /// lasers and other lights
+    Additive,
+    /// Multiplies the colors behind the sprite by its colors, for shadows and color filters.
+    /// Transparent pixels of the sprite must be white to leave the colors behind unchanged.
+    Multiply,
+}
+
+impl Default for BlendMode {
+    fn default() -> Self {
+        BlendMode::Alpha
+    }
+}
+
+impl BlendMode {
+    /// Returns the blend state of the mode.
+    pub fn blend(self) -> Blend {
+        // The alpha behind the sprite is kept by the additive and multiplicative modes.
+        let keep_alpha = BlendChannel {
+            equation: Equation::Add,
+            source: Factor::Zero,
+            destination: Factor::One,
+        };
+        match self {
+            BlendMode::Alpha => ALPHA,
+            BlendMode::Additive => Blend {
+                color: BlendChannel {
+                    equation: Equation::Add,
+                    source: Factor::ZeroPlus(BlendValue::SourceAlpha),
+                    destination: Factor::One,
+                },
+                alpha: keep_alpha,
+            },
+            BlendMode::Multiply => Blend {
+                color: BlendChannel {
+                    equation: Equation::Add,
+                    source: Factor::Zero,
+                    destination: Factor::ZeroPlus(BlendValue::SourceColor),
+                },
+                alpha: keep_alpha,
+            },
+        }
+    }
+}
+
+impl Component for BlendMode {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Moves the point of the entity sorted by the `SpriteVisibilitySortingSystem` in
+/// `SpriteSortMode::Y`, such as to the feet of a character drawn above its entity.
+///
+/// The offset is in units of the entity along its Y axis, and is scaled by its `Transform`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SortOffset(pub f32);
+
+impl Component for SortOffset {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Draws a sprite or image in pixels of the screen instead of the world, for HUD icons.
+///
+/// These entities are only drawn by a `DrawFlat2D` pass created with `with_screen_space(true)`,
+/// where their `GlobalTransform` is in pixels from the bottom left corner of the screen, and are
+/// skipped by the other `DrawFlat2D` passes and the `SpriteVisibilitySortingSystem`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScreenSpace;
+
+impl Component for ScreenSpace {
+    type Storage = NullStorage<Self>;
+}
+
+/// Restricts the sprites and images of the children of the entity to the shape of its own sprite
+/// or image, for minimap windows, health bar fills and portals.
+///
+/// The sprite or image of the mask is not drawn, its pixels with an alpha lower than `0.5` are
+/// outside of the mask. The `DrawFlat2D` pass writes the masks to the stencil buffer before
+/// drawing the masked sprites after the others, ordered by `DrawOrder` then Z coordinate. A sprite
+/// is only restricted by its nearest mask, and overlapping masks cut each other. Up to 254 masks
+/// are drawn per pass, and masks are not supported with the `metal` backend which has no stencil
+/// buffer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpriteMask;
+
+impl Component for SpriteMask {
+    type Storage = NullStorage<Self>;
+}
+
+/// Draws an outline around a sprite or image, for selection highlighting.
+///
+/// The outline follows the pixels of the sprite with an alpha of at least `0.5`. It is drawn by
+/// the `DrawFlat2D` pass under the sprite, as copies of its shape moved by the thickness in eight
+/// directions, so it is not restricted by `SpriteMask`s and may be cut by the edges of sprites
+/// without transparent borders.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpriteOutline {
+    /// Color of the outline
+    pub color: Rgba,
+    /// Thickness of the outline in pixels of the sprite, scaled by the `Transform` like the sprite
+    pub thickness: f32,
+}
+
+impl SpriteOutline {
+    /// Creates an outline of the given color and thickness.
+    pub fn new(color: Rgba, thickness: f32) -> Self {
+        SpriteOutline { color, thickness }
+    }
+}
+
+impl Component for SpriteOutline {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Draws a sprite or image in indexed colors taken from a row of a palette texture, for team
+/// colors and character variants of pixel art.
+///
+/// The red channel of the texture of the sprite is the index of the color in the row, from `0` to
+/// `255`, and its alpha is kept. Load that texture with `TextureMetadata::unorm()` and nearest
+/// filtering so the indices are not changed. Row `0` is the top row of the palette image.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Palette {
+    /// Texture holding a palette in each row
+    pub texture: Handle<Texture>,
+    /// Row of the palette used by the sprite
+    pub row: u32,
+}
+
+impl Palette {
+    /// Creates a palette using the given row of the texture.
+    pub fn new(texture: Handle<Texture>, row: u32) -> Self {
+        Palette { texture, row }
+    }
+}
+
+impl Component for Palette {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Moves the corners of the quad of a sprite or image, for squash and stretch or pseudo 3D floors
+/// without custom meshes.
+///
+/// The offsets are in pixels of the sprite along its axes, and are scaled by the `Transform` like
+/// the sprite. The quad is drawn as two triangles, so its texture is not corrected for
+/// perspective. The parts of a 9-slice sprite are deformed along with the whole sprite.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpriteDeform {
+    /// Offset of the bottom left corner
+    pub bottom_left: [f32; 2],
+    /// Offset of the bottom right corner
+    pub bottom_right: [f32; 2],
+    /// Offset of the top left corner
+    pub top_left: [f32; 2],
+    /// Offset of the top right corner
+    pub top_right: [f32; 2],
+}
+
+impl SpriteDeform {
+    /// Creates a deformation moving the top corners horizontally by `offset` pixels, leaning the
+    /// sprite to the right for a positive offset.
+    pub fn skew(offset: f32) -> Self {
+        SpriteDeform {
+            top_left: [offset, 0.0],
+            top_right: [offset, 0.0],
+            ..SpriteDeform::default()
+        }
+    }
+
+    /// Interpolates the offset of a point of the sprite, from `[0.0, 0.0]` at its bottom left
+    /// corner to `[1.0, 1.0]` at its top right corner.
+    pub fn offset(&self, point: [f32; 2]) -> [f32; 2] {
+        let lerp = |from: [f32; 2], to: [f32; 2], t: f32| {
+            [
+                from[0] + (to[0] - from[0]) * t,
+                from[1] + (to[1] - from[1]) * t,
+            ]
+        };
+        let bottom = lerp(self.bottom_left, self.bottom_right, point[0]);
+        let top = lerp(self.top_left, self.top_right, point[0]);
+        lerp(bottom, top, point[1])
+    }
+}
+
+impl Component for SpriteDeform {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Turns a sprite or image in a 3D scene to always face the camera, for impostors, health bars
+/// and foliage cards.
+///
+/// The `DrawFlat2D` pass replaces the rotation of the entity while drawing it, keeping its
+/// position and scale. It has no effect on the sprites in `ScreenSpace`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Billboard {
+    /// Faces the camera on all axes, parallel to the screen.
+    Spherical,
+    /// Only turns around the Y axis of the entity towards the camera, keeping it upright.
+    Cylindrical,
+}
+
+impl Billboard {
+    /// Returns the transform of the entity turned to face the camera with the given transform.
+    pub fn face(self, global: &GlobalTransform, camera: &GlobalTransform) -> GlobalTransform {
+        let axis = |matrix: &Matrix4<f32>, i: usize| {
+            Vector3::new(matrix[(0, i)], matrix[(1, i)], matrix[(2, i)])
+        };
+        let (x, y, z) = (axis(&global.0, 0), axis(&global.0, 1), axis(&global.0, 2));
+        let position = axis(&global.0, 3);
+        let (right, up, back) = match self {
+            Billboard::Spherical => (
+                axis(&camera.0, 0).normalize(),
+                axis(&camera.0, 1).normalize(),
+                axis(&camera.0, 2).normalize(),
+            ),
+            Billboard::Cylindrical => {
+                let up = match y.try_normalize(std::f32::EPSILON) {
+                    Some(up) => up,
+                    None => return *global,
+                };
+                let to_camera = axis(&camera.0, 3) - position;
+                let back = match (to_camera - up * to_camera.dot(&up)).try_normalize(1.0e-6) {
+                    Some(back) => back,
+                    None => return *global,
+                };
+                (up.cross(&back), up, back)
+            }
+        };
+        let mut faced = global.0;
+        for (i, column) in [right * x.norm(), up * y.norm(), back * z.norm()]
+            .iter()
+            .enumerate()
+        {
+            faced.column_mut(i).copy_from(&column.to_homogeneous());
+        }
+        GlobalTransform(faced)
+    }
+}
+
+impl Component for Billboard {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Draws a sprite or image with a fragment shader registered on the `DrawFlat2D` pass with
+/// `with_sprite_shader`, for effects such as dissolve or grayscale.
+///
+/// The sprite is drawn with its texture, color, mask and outline, but without its `BlendMode`,
+/// `Palette` and lighting, which are left to the shader. Sprites with a shader unknown to the
+/// pass are drawn with the default shader.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpriteMaterialOverride {
+    /// Name of the shader on the pass
+    pub shader: String,
+    /// Parameters of the shader, such as the progress of a dissolve
+    #[serde(default)]
+    pub params: [f32; 4],
+}
+
+impl SpriteMaterialOverride {
+    /// Draws the sprite with the shader of the given name, with parameters at `0.0`.
+    pub fn new<S: Into<String>>(shader: S) -> Self {
+        SpriteMaterialOverride {
+            shader: shader.into(),
+            params: [0.0; 4],
+        }
+    }
+
+    /// Sets the parameters of the shader.
+    pub fn with_params(mut self, params: [f32; 4]) -> Self {
+        self.params = params;
+        self
+    }
+}
+
+impl Component for SpriteMaterialOverride {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Casts shadows of the entity from the point and directional `Light`s, drawn by the
+/// `DrawShadows2D` pass.
+///
+/// Shadows are cast on the XY plane from the X and Y coordinates of the lights, and start behind
+/// the edges of the occluder facing away from the light, so the occluder is not shadowed by itself.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ShadowCaster2D {
+    /// Occludes the rectangle of the `SpriteRender` of the entity.
+    Sprite,
+    /// Occludes a convex polygon, with its points relative to the entity and scaled by its
+    /// `Transform`.
+    Polygon(Vec<[f32; 2]>),
+}
+
+impl Component for ShadowCaster2D {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Draws a sprite as a 9-slice sprite, to make panels and buttons of any size from one sprite.
+///
+/// The sprite is cut by its borders into nine parts. The corners keep their size, the left and
+/// right borders stretch vertically, the bottom and top borders stretch horizontally and the
+/// center stretches in both directions. Borders larger than the drawn size are shrunk.
+///
+/// The sizes are in pixels of the sprite, and are scaled by the `Transform` like the sprite.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NineSlice {
+    /// Width of the drawn sprite
+    pub width: f32,
+    /// Height of the drawn sprite
+    pub height: f32,
+    /// Width of the left border of the sprite
+    pub left: f32,
+    /// Width of the right border of the sprite
+    pub right: f32,
+    /// Height of the bottom border of the sprite
+    pub bottom: f32,
+    /// Height of the top border of the sprite
+    pub top: f32,
+}
+
+impl NineSlice {
+    /// Creates a 9-slice sprite of the given size, with borders of the same size on every side.
+    pub fn new(width: f32, height: f32, border: f32) -> Self {
+        NineSlice {
+            width,
+            height,
+            left: border,
+            right: border,
+            bottom: border,
+            top: border,
+        }
+    }
+
+    /// Sets the size of the left, right, bottom and top borders.
+    pub fn with_borders(mut self, left: f32, right: f32, bottom: f32, top: f32) -> Self {
+        self.left = left;
+        self.right = right;
+        self.bottom = bottom;
+        self.top = top;
+        self
+    }
+
+    /// Returns the parts of the sprite drawn on their own quad, skipping the empty ones.
+    pub(crate) fn parts(&self, sprite: &Sprite) -> SmallVec<[SpritePart; 9]> {
+        let columns = slices(self.width, sprite.width, self.left, self.right);
+        let rows = slices(self.height, sprite.height, self.bottom, self.top);
+        let mut parts = SmallVec::new();
+        for &(y, height, bottom, top) in &rows {
+            for &(x, width, left, right) in &columns {
+                if width > 0.0 && height > 0.0 {
+                    parts.push(SpritePart {
+                        center: [
+                            x + width / 2.0 - sprite.offsets[0],
+                            y + height / 2.0 - sprite.offsets[1],
+                        ],
+                        size: [width, height],
+                        x_range: [left, right],
+                        y_range: [bottom, top],
+                    });
+                }
+            }
+        }
+        parts
+    }
+}
+
+impl Component for NineSlice {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Cuts a side of a 9-slice sprite into its start, stretched and end slices.
+///
+/// Returns the start and length of each slice, relative to the center of the drawn sprite, and
+/// the fractions of the sprite they show.
+fn slices(length: f32, sprite_length: f32, start: f32, end: f32) -> [(f32, f32, f32, f32); 3] {
+    let shrink = if start + end > length {
+        length / (start + end)
+    } else {
+        1.0
+    };
+    let (start_length, end_length) = (start * shrink, end * shrink);
+    let (start_fraction, end_fraction) = (start / sprite_length, 1.0 - end / sprite_length);
+    let origin = -length / 2.0;
+    [
+        (origin, start_length, 0.0, start_fraction),
+        (
+            origin + start_length,
+            length - start_length - end_length,
+            start_fraction,
+            end_fraction,
+        ),
+        (length / 2.0 - end_length, end_length, end_fraction, 1.0),
+    ]
+}
+
+/// A part of a sprite drawn on its own quad.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct SpritePart {
+    /// Center of the quad in pixels relative to the entity
+    pub center: [f32; 2],
+    /// Width and height of the quad in pixels
+    pub size: [f32; 2],
+    /// Fractions of the width of the sprite shown, from left to right
+    pub x_range: [f32; 2],
+    /// Fractions of the height of the sprite shown, from bottom to top
+    pub y_range: [f32; 2],
+}
+
+impl SpritePart {
+    /// Returns the single part of a sprite drawn as a whole.
+    pub(crate) fn whole(sprite: &Sprite) -> SmallVec<[SpritePart; 9]> {
+        smallvec![SpritePart {
+            center: [-sprite.offsets[0], -sprite.offsets[1]],
+            size: [sprite.width, sprite.height],
+            x_range: [0.0, 1.0],
+            y_range: [0.0, 1.0],
+        }]
+    }
+}
+
 /// Dimensions and texture coordinates of each sprite in a sprite sheet.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Sprite {
@@ -61,6 +645,16 @@ pub struct Sprite {
     pub offsets: [f32; 2],
     /// Texture coordinates of the sprite
     pub tex_coords: TextureCoordinates,
+    /// Whether the sprite is rotated 90 degrees clockwise in the texture, as packed by tools
+    /// such as TexturePacker. The width and height are the ones of the unrotated sprite.
+    #[serde(default)]
+    pub rotated: bool,
+    /// Page of the sprite sheet holding the sprite, `0` for its first texture
+    #[serde(default)]
+    pub page: usize,
+    /// Name of the sprite, to find it in the sprite sheet with `SpriteSheet::sprite_index`
+    #[serde(default)]
+    pub name: Option<String>,
 }
 
 /// Texture coordinates of the sprite
@@ -141,10 +735,40 @@ impl Sprite {
             height: sprite_h as f32,
             offsets,
             tex_coords,
+            rotated: false,
+            page: 0,
+            name: None,
         }
     }
 }
 
+impl Sprite {
+    /// Moves the texture coordinates of the sprite inwards by the given number of texels on each
+    /// side, so that filtering and mipmaps don't blend in the texels around the sprite.
+    ///
+    /// Half a texel keeps linear filtering inside the sprite. The sprite keeps its size, the
+    /// texels on its edges are drawn slightly narrower.
+    pub fn with_inset(mut self, texels: f32) -> Sprite {
+        // The texture of a rotated sprite is as wide as the sprite is tall.
+        let (texels_x, texels_y) = if self.rotated {
+            (self.height, self.width)
+        } else {
+            (self.width, self.height)
+        };
+        if texels_x <= 0.0 || texels_y <= 0.0 {
+            return self;
+        }
+        let coords = &mut self.tex_coords;
+        let inset_x = (coords.right - coords.left) / texels_x * texels;
+        let inset_y = (coords.top - coords.bottom) / texels_y * texels;
+        coords.left += inset_x;
+        coords.right -= inset_x;
+        coords.bottom += inset_y;
+        coords.top -= inset_y;
+        self
+    }
+}
+
 impl From<((f32, f32), [f32; 4])> for Sprite {
     fn from((dimensions, tex_coords): ((f32, f32), [f32; 4])) -> Self {
         Self::from((dimensions, [0.0; 2], tex_coords))
@@ -158,6 +782,9 @@ impl From<((f32, f32), [f32; 2], [f32; 4])> for Sprite {
             height,
             offsets,
             tex_coords: TextureCoordinates::from(tex_coords),
+            rotated: false,
+            page: 0,
+            name: None,
         }
     }
 }
@@ -196,13 +823,30 @@ pub struct SpriteRender {
     pub sprite_number: usize,
 }
 
+impl SpriteRender {
+    /// Creates the information to render the given sprite of a sprite sheet.
+    ///
+    /// The sprite sheet does not need to be loaded yet, the sprite is drawn once it is.
+    pub fn new(sprite_sheet: SpriteSheetHandle, sprite_number: usize) -> Self {
+        SpriteRender {
+            sprite_sheet,
+            sprite_number,
+        }
+    }
+}
+
 impl Component for SpriteRender {
     type Storage = VecStorage<Self>;
 }
 
 #[cfg(test)]
 mod test {
-    use super::{Sprite, TextureCoordinates};
+    use super::{Billboard, NineSlice, Sprite, TextureCoordinates};
+
+    use amethyst_core::{
+        nalgebra::{Matrix4, UnitQuaternion, Vector3},
+        transform::GlobalTransform,
+    };
 
     #[test]
     fn texture_coordinates_from_tuple_maps_fields_correctly() {
@@ -243,6 +887,9 @@ mod test {
                     bottom: 0.75,
                     top: 1.0,
                 },
+                rotated: false,
+                page: 0,
+                name: None,
             },
             ((10., 40.), [5., 20.], [0.0, 0.5, 0.75, 1.0]).into()
         );
@@ -261,6 +908,9 @@ mod test {
                     bottom: 0.75,
                     top: 1.0,
                 },
+                rotated: false,
+                page: 0,
+                name: None,
             },
             ((10., 40.), [0.0, 0.5, 0.75, 1.0]).into()
         );
@@ -287,4 +937,57 @@ mod test {
             )
         );
     }
+
+    #[test]
+    fn inset_moves_texture_coordinates_inwards() {
+        let sprite = Sprite::from_pixel_values(64, 32, 16, 8, 16, 8, [0.0; 2]).with_inset(0.5);
+        let coords = &sprite.tex_coords;
+        assert_eq!((coords.left, coords.right), (16.5 / 64.0, 31.5 / 64.0));
+        assert_eq!((coords.bottom, coords.top), (16.5 / 32.0, 23.5 / 32.0));
+        assert_eq!((sprite.width, sprite.height), (16.0, 8.0));
+    }
+
+    #[test]
+    fn nine_slice_keeps_corners() {
+        let sprite = Sprite::from_pixel_values(16, 16, 16, 16, 0, 0, [0.0; 2]);
+        let parts = NineSlice::new(40.0, 20.0, 4.0).parts(&sprite);
+        assert_eq!(parts.len(), 9);
+        // Bottom left corner
+        assert_eq!(parts[0].center, [-18.0, -8.0]);
+        assert_eq!(parts[0].size, [4.0, 4.0]);
+        assert_eq!(parts[0].x_range, [0.0, 0.25]);
+        // Stretched center
+        assert_eq!(parts[4].center, [0.0, 0.0]);
+        assert_eq!(parts[4].size, [32.0, 12.0]);
+        assert_eq!(parts[4].y_range, [0.25, 0.75]);
+    }
+
+    #[test]
+    fn nine_slice_shrinks_large_borders() {
+        let sprite = Sprite::from_pixel_values(16, 16, 16, 16, 0, 0, [0.0; 2]);
+        let parts = NineSlice::new(4.0, 40.0, 4.0).parts(&sprite);
+        // The empty center column is skipped.
+        assert_eq!(parts.len(), 6);
+        assert_eq!(parts[0].size, [2.0, 4.0]);
+    }
+
+    #[test]
+    fn billboard_faces_camera() {
+        let global = GlobalTransform(Matrix4::new_scaling(2.0));
+        // Camera on the right of the entity and above it, looking to the left.
+        let rotation =
+            UnitQuaternion::from_axis_angle(&Vector3::y_axis(), std::f32::consts::FRAC_PI_2);
+        let camera = GlobalTransform(
+            Matrix4::new_translation(&Vector3::new(10.0, 3.0, 0.0)) * rotation.to_homogeneous(),
+        );
+
+        for billboard in &[Billboard::Spherical, Billboard::Cylindrical] {
+            let faced = billboard.face(&global, &camera).0;
+            let axes = [[0.0, 0.0, -2.0], [0.0, 2.0, 0.0], [2.0, 0.0, 0.0]];
+            for (i, axis) in axes.iter().enumerate() {
+                let expected = Vector3::from(*axis).to_homogeneous();
+                assert!((faced.column(i) - expected).norm() < 1e-5);
+            }
+        }
+    }
 }
@@ -0,0 +1,254 @@
+//! Module for the SpriteAnimation component and SpriteAnimationSystem.
+
+use amethyst_assets::{PrefabData, ProgressCounter};
+use amethyst_core::{
+    specs::{Component, DenseVecStorage, Entity, Join, Read, System, WriteStorage},
+    Time,
+};
+use amethyst_derive::PrefabData;
+use amethyst_error::Error;
+use serde::{Deserialize, Serialize};
+
+use crate::SpriteRender;
+
+/// How a `SpriteAnimation` goes on after its last frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LoopMode {
+    /// Stops on the last frame.
+    Once,
+    /// Starts again from the first frame.
+    Loop,
+    /// Plays backwards to the first frame, then forwards again.
+    PingPong,
+}
+
+impl Default for LoopMode {
+    fn default() -> Self {
+        LoopMode::Loop
+    }
+}
+
+/// # SpriteAnimation Component
+/// Changes the `sprite_number` of the `SpriteRender` of the entity, showing each frame of the
+/// animation for its duration. You must add the `SpriteAnimationSystem` to your dispatcher for
+/// this to take effect.
+///
+/// For animations blending with others or animating other components, use `amethyst_animation`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, PrefabData)]
+#[prefab(Component)]
+pub struct SpriteAnimation {
+    /// Sprite numbers of the frames, in the sprite sheet of the `SpriteRender`.
+    pub frames: Vec<usize>,
+    /// Duration of each frame in seconds. The last duration is used for the frames without one.
+    pub durations: Vec<f32>,
+    /// How the animation goes on after its last frame.
+    #[serde(default)]
+    pub loop_mode: LoopMode,
+    /// Multiplier of the playback speed, `1.0` by default.
+    #[serde(default = "default_speed")]
+    pub speed: f32,
+    /// Whether to use the unscaled time.
+    #[serde(default)]
+    pub absolute_time: bool,
+    #[serde(skip, default = "default_playing")]
+    playing: bool,
+    #[serde(skip)]
+    current: usize,
+    #[serde(skip)]
+    timer: f32,
+    #[serde(skip)]
+    backwards: bool,
+}
+
+fn default_speed() -> f32 {
+    1.0
+}
+
+fn default_playing() -> bool {
+    true
+}
+
+impl SpriteAnimation {
+    /// Creates a looping animation showing each frame for the same duration in seconds.
+    pub fn new(frames: Vec<usize>, frame_duration: f32) -> Self {
+        SpriteAnimation {
+            frames,
+            durations: vec![frame_duration],
+            loop_mode: LoopMode::Loop,
+            speed: 1.0,
+            absolute_time: false,
+            playing: true,
+            current: 0,
+            timer: 0.0,
+            backwards: false,
+        }
+    }
+
+    /// Sets the duration of each frame in seconds.
+    pub fn with_durations(mut self, durations: Vec<f32>) -> Self {
+        self.durations = durations;
+        self
+    }
+
+    /// Sets how the animation goes on after its last frame.
+    pub fn with_loop_mode(mut self, loop_mode: LoopMode) -> Self {
+        self.loop_mode = loop_mode;
+        self
+    }
+
+    /// Sets the multiplier of the playback speed.
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Resumes the animation.
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+
+    /// Pauses the animation on its current frame.
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// Returns whether the animation is playing, it is paused or finished otherwise.
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Plays the animation again from its first frame.
+    pub fn restart(&mut self) {
+        self.current = 0;
+        self.timer = 0.0;
+        self.backwards = false;
+        self.playing = true;
+    }
+
+    /// Returns the sprite number of the current frame, if the animation has frames.
+    pub fn sprite_number(&self) -> Option<usize> {
+        self.frames.get(self.current).cloned()
+    }
+
+    fn duration(&self, frame: usize) -> f32 {
+        self.durations
+            .get(frame)
+            .or_else(|| self.durations.last())
+            .cloned()
+            .unwrap_or(0.0)
+    }
+
+    /// Advances the animation by the given time in seconds.
+    fn advance(&mut self, seconds: f32) {
+        if !self.playing || self.frames.is_empty() {
+            return;
+        }
+        self.timer += seconds * self.speed.max(0.0);
+        let mut steps = 0;
+        // Frames without duration are skipped one per update, instead of looping forever.
+        while self.timer >= self.duration(self.current) && steps < self.frames.len() * 2 {
+            self.timer -= self.duration(self.current);
+            steps += 1;
+            if !self.next_frame() {
+                self.timer = 0.0;
+                self.playing = false;
+                break;
+            }
+        }
+    }
+
+    /// Moves to the next frame, returns `false` if the animation is finished.
+    fn next_frame(&mut self) -> bool {
+        let last = self.frames.len() - 1;
+        match self.loop_mode {
+            LoopMode::Once if self.current == last => return false,
+            LoopMode::Loop if self.current == last => self.current = 0,
+            LoopMode::PingPong if last == 0 => {}
+            LoopMode::PingPong if self.backwards && self.current == 0 => {
+                self.backwards = false;
+                self.current = 1;
+            }
+            LoopMode::PingPong if !self.backwards && self.current == last => {
+                self.backwards = true;
+                self.current = last - 1;
+            }
+            LoopMode::PingPong if self.backwards => self.current -= 1,
+            _ => self.current += 1,
+        }
+        true
+    }
+}
+
+impl Component for SpriteAnimation {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// System updating the `sprite_number` of the `SpriteRender` of the entities with a
+/// `SpriteAnimation` component.
+#[derive(Default)]
+pub struct SpriteAnimationSystem;
+
+impl<'a> System<'a> for SpriteAnimationSystem {
+    type SystemData = (
+        Read<'a, Time>,
+        WriteStorage<'a, SpriteAnimation>,
+        WriteStorage<'a, SpriteRender>,
+    );
+
+    fn run(&mut self, (time, mut animations, mut sprite_renders): Self::SystemData) {
+        for (animation, sprite_render) in (&mut animations, &mut sprite_renders).join() {
+            if animation.absolute_time {
+                animation.advance(time.delta_real_seconds());
+            } else {
+                animation.advance(time.delta_seconds());
+            }
+
+            match animation.sprite_number() {
+                Some(sprite_number) if sprite_number != sprite_render.sprite_number => {
+                    sprite_render.sprite_number = sprite_number;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(animation: &mut SpriteAnimation, updates: usize) -> Vec<usize> {
+        (0..updates)
+            .map(|_| {
+                animation.advance(0.1);
+                animation.sprite_number().unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn loop_modes() {
+        let mut looping = SpriteAnimation::new(vec![4, 5, 6], 0.1);
+        assert_eq!(frames(&mut looping, 4), vec![5, 6, 4, 5]);
+
+        let mut once = SpriteAnimation::new(vec![4, 5, 6], 0.1).with_loop_mode(LoopMode::Once);
+        assert_eq!(frames(&mut once, 4), vec![5, 6, 6, 6]);
+        assert!(!once.is_playing());
+
+        let mut ping_pong =
+            SpriteAnimation::new(vec![4, 5, 6], 0.1).with_loop_mode(LoopMode::PingPong);
+        assert_eq!(frames(&mut ping_pong, 5), vec![5, 6, 5, 4, 5]);
+    }
+
+    #[test]
+    fn pause_and_speed() {
+        let mut animation = SpriteAnimation::new(vec![0, 1, 2, 3], 0.1).with_speed(2.0);
+        assert_eq!(frames(&mut animation, 1), vec![2]);
+        animation.pause();
+        assert_eq!(frames(&mut animation, 1), vec![2]);
+        animation.play();
+        animation.speed = 1.0;
+        animation.durations = vec![0.1, 0.1, 0.1, 0.5];
+        assert_eq!(frames(&mut animation, 3), vec![3, 3, 3]);
+    }
+}
@@ -0,0 +1,63 @@
+//! Statistics of the rendering of sprites.
+
+/// Work done to draw the sprites and images of a frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SpriteRenderCounts {
+    /// Instanced draw calls of the `DrawFlat2D` passes
+    pub batches: usize,
+    /// Quads of sprites and images submitted to the `DrawFlat2D` passes, including the parts of
+    /// 9-slice sprites, outlines and trails
+    pub sprites: usize,
+    /// Sprites and images outside the view of the camera, skipped by the
+    /// `SpriteVisibilitySortingSystem`
+    pub culled: usize,
+    /// Draw calls binding another texture than the previous draw call of their pass
+    pub texture_switches: usize,
+}
+
+/// Resource counting the work done by the `SpriteVisibilitySortingSystem` and the `DrawFlat2D`
+/// passes, to profile 2D scenes without a GPU debugger.
+///
+/// The counts are summed over the passes of a frame, and published when the next frame starts.
+#[derive(Clone, Debug, Default)]
+pub struct SpriteRenderStats {
+    last: SpriteRenderCounts,
+    current: SpriteRenderCounts,
+    frame: u64,
+}
+
+impl SpriteRenderStats {
+    /// Returns the counts of the last rendered frame.
+    pub fn last_frame(&self) -> SpriteRenderCounts {
+        self.last
+    }
+
+    /// Returns the counts of the given frame, publishing the counts of the previous frame when a
+    /// new frame starts.
+    pub(crate) fn counts(&mut self, frame: u64) -> &mut SpriteRenderCounts {
+        if frame != self.frame {
+            self.last = self.current;
+            self.current = SpriteRenderCounts::default();
+            self.frame = frame;
+        }
+        &mut self.current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_are_published_on_next_frame() {
+        let mut stats = SpriteRenderStats::default();
+        stats.counts(1).culled += 3;
+        stats.counts(1).batches += 2;
+        stats.counts(1).batches += 1;
+        assert_eq!(stats.last_frame(), SpriteRenderCounts::default());
+
+        stats.counts(2).sprites += 1;
+        let last = stats.last_frame();
+        assert_eq!((last.batches, last.culled, last.sprites), (3, 3, 0));
+    }
+}
@@ -0,0 +1,136 @@
+//! Module for the SpriteTrail component and SpriteTrailSystem.
+
+use std::collections::VecDeque;
+
+use amethyst_assets::{PrefabData, ProgressCounter};
+use amethyst_core::{
+    specs::{Component, DenseVecStorage, Entity, Join, Read, ReadStorage, System, WriteStorage},
+    GlobalTransform, Time,
+};
+use amethyst_derive::PrefabData;
+use amethyst_error::Error;
+use serde::{Deserialize, Serialize};
+
+/// # SpriteTrail Component
+/// Draws faded copies of the sprite or image of the entity where it was in the previous frames,
+/// for afterimages of dashes and fast projectiles. You must add the `SpriteTrailSystem` to your
+/// dispatcher after the `TransformSystem` to record the transforms of the entity.
+///
+/// The copies are drawn by the `DrawFlat2D` pass under the sprite, without outline, the nearest
+/// one with `start_alpha` times the alpha of the sprite and the others fading out linearly.
+#[derive(Clone, Debug, Serialize, Deserialize, PrefabData)]
+#[prefab(Component)]
+pub struct SpriteTrail {
+    /// Number of copies drawn behind the sprite.
+    pub length: usize,
+    /// Seconds between the recorded transforms, `0.0` to record them every frame.
+    #[serde(default)]
+    pub interval: f32,
+    /// Alpha multiplier of the copy nearest to the sprite.
+    #[serde(default = "default_start_alpha")]
+    pub start_alpha: f32,
+    /// Recorded transforms, from the newest to the oldest.
+    #[serde(skip)]
+    transforms: VecDeque<GlobalTransform>,
+    /// Seconds since the last recorded transform.
+    #[serde(skip)]
+    elapsed: f32,
+}
+
+fn default_start_alpha() -> f32 {
+    0.5
+}
+
+impl SpriteTrail {
+    /// Creates a `SpriteTrail` of `length` copies, recorded every `interval` seconds.
+    pub fn new(length: usize, interval: f32) -> Self {
+        SpriteTrail {
+            length,
+            interval,
+            start_alpha: default_start_alpha(),
+            transforms: VecDeque::new(),
+            elapsed: 0.0,
+        }
+    }
+
+    /// Sets the alpha multiplier of the copy nearest to the sprite.
+    pub fn with_start_alpha(mut self, start_alpha: f32) -> Self {
+        self.start_alpha = start_alpha;
+        self
+    }
+
+    /// Forgets the recorded transforms, so the trail restarts from the current position, such as
+    /// after teleporting the entity.
+    pub fn clear(&mut self) {
+        self.transforms.clear();
+        self.elapsed = 0.0;
+    }
+
+    /// Returns the transforms of the copies with their alpha multipliers, from the oldest to the
+    /// newest so the newer copies are drawn over the older ones.
+    pub fn ghosts(&self) -> impl Iterator<Item = (&GlobalTransform, f32)> {
+        let length = self.length as f32;
+        let start_alpha = self.start_alpha;
+        self.transforms
+            .iter()
+            .enumerate()
+            .rev()
+            .map(move |(i, transform)| (transform, start_alpha * (1.0 - i as f32 / length)))
+    }
+
+    /// Records the transform if the interval has passed since the last one.
+    fn record(&mut self, transform: &GlobalTransform, seconds: f32) {
+        self.elapsed += seconds;
+        if !self.transforms.is_empty() && self.elapsed < self.interval {
+            return;
+        }
+        self.elapsed = 0.0;
+        self.transforms.push_front(*transform);
+        self.transforms.truncate(self.length);
+    }
+}
+
+impl Component for SpriteTrail {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// System recording the `GlobalTransform` of the entities with a `SpriteTrail` component.
+#[derive(Default)]
+pub struct SpriteTrailSystem;
+
+impl<'a> System<'a> for SpriteTrailSystem {
+    type SystemData = (
+        Read<'a, Time>,
+        ReadStorage<'a, GlobalTransform>,
+        WriteStorage<'a, SpriteTrail>,
+    );
+
+    fn run(&mut self, (time, globals, mut trails): Self::SystemData) {
+        for (global, trail) in (&globals, &mut trails).join() {
+            trail.record(global, time.delta_seconds());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use amethyst_core::nalgebra::{Matrix4, Vector3};
+
+    #[test]
+    fn trail_keeps_latest_transforms() {
+        let at = |x| GlobalTransform(Matrix4::new_translation(&Vector3::new(x, 0.0, 0.0)));
+        let mut trail = SpriteTrail::new(2, 0.1).with_start_alpha(1.0);
+        trail.record(&at(0.0), 0.05);
+        trail.record(&at(1.0), 0.05);
+        trail.record(&at(2.0), 0.06);
+        trail.record(&at(3.0), 0.1);
+
+        let ghosts = trail
+            .ghosts()
+            .map(|(transform, alpha)| (transform.0[(0, 3)], alpha))
+            .collect::<Vec<_>>();
+        assert_eq!(ghosts, vec![(2.0, 0.5), (3.0, 1.0)]);
+    }
+}
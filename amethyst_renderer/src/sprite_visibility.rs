@@ -1,16 +1,25 @@
 use std::cmp::Ordering;
 
 use hibitset::BitSet;
+use serde::{Deserialize, Serialize};
 
+use amethyst_assets::AssetStorage;
 use amethyst_core::{
-    nalgebra::{Point3, Vector3},
+    nalgebra::{Matrix4, Point3, Vector3, Vector4},
     specs::prelude::{Entities, Entity, Join, Read, ReadStorage, System, Write},
-    GlobalTransform,
+    GlobalTransform, Time,
 };
 
 use crate::{
     cam::{ActiveCamera, Camera},
+    color::Rgba,
     hidden::{Hidden, HiddenPropagate},
+    sprite::{
+        BlendMode, DrawOrder, Flipped, NineSlice, ScreenSpace, SortOffset, SpriteRender,
+        SpriteSheet,
+    },
+    sprite_stats::SpriteRenderStats,
+    tex::{Texture, TextureHandle},
     transparent::Transparent,
 };
 
@@ -24,11 +33,37 @@ pub struct SpriteVisibility {
     pub visible_ordered: Vec<Entity>,
 }
 
+/// Resource selecting how the `SpriteVisibilitySortingSystem` orders the sprites.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpriteSortMode {
+    /// Orders the semi-transparent sprites by position on the Z axis, from far to near.
+    Z,
+    /// Orders every sprite by position on the Y axis from top to bottom, then on the Z axis, so
+    /// characters pass in front of and behind each other in top-down and isometric games. The
+    /// sorted point of an entity is moved by its `SortOffset`.
+    Y,
+}
+
+impl Default for SpriteSortMode {
+    fn default() -> Self {
+        SpriteSortMode::Z
+    }
+}
+
 /// Determines what entities to be drawn. Will also sort transparent entities back to front based on
 /// position on the Z axis.
 ///
+/// Sprites and images entirely outside the view of the camera are culled: their bounds are
+/// computed from their `GlobalTransform`, the size and offsets of their sprite or image, their
+/// `NineSlice` and their `Flipped` state. Without camera, nothing is culled. Entities drawn in
+/// `ScreenSpace` are skipped.
+///
 /// The sprite render pass should draw all sprites without semi-transparent pixels, then draw the
-/// sprites with semi-transparent pixels from far to near.
+/// sprites with semi-transparent pixels from far to near. The entities with a `DrawOrder` are
+/// ordered too, by layer, then order in the layer, then position on the Z axis. Sprites tinted by
+/// an `Rgba` with an alpha lower than `1.0` or blended with another `BlendMode` than alpha blending
+/// are drawn as semi-transparent. With the `SpriteSortMode::Y` resource, every sprite is ordered by
+/// position on the Y axis instead.
 ///
 /// Note that this should run after `GlobalTransform` has been updated for the current frame, and
 /// before rendering occurs.
@@ -41,8 +76,10 @@ pub struct SpriteVisibilitySortingSystem {
 #[derive(Clone)]
 struct Internals {
     entity: Entity,
-    transparent: bool,
+    ordered: bool,
+    draw_order: DrawOrder,
     centroid: Point3<f32>,
+    sort_y: f32,
     from_camera: Vector3<f32>,
 }
 
@@ -63,20 +100,62 @@ impl<'a> System<'a> for SpriteVisibilitySortingSystem {
         ReadStorage<'a, Camera>,
         ReadStorage<'a, Transparent>,
         ReadStorage<'a, GlobalTransform>,
+        ReadStorage<'a, DrawOrder>,
+        Read<'a, AssetStorage<SpriteSheet>>,
+        Read<'a, AssetStorage<Texture>>,
+        ReadStorage<'a, SpriteRender>,
+        ReadStorage<'a, TextureHandle>,
+        ReadStorage<'a, Flipped>,
+        ReadStorage<'a, NineSlice>,
+        ReadStorage<'a, Rgba>,
+        ReadStorage<'a, BlendMode>,
+        ReadStorage<'a, ScreenSpace>,
+        Read<'a, SpriteSortMode>,
+        ReadStorage<'a, SortOffset>,
+        Read<'a, Time>,
+        Write<'a, SpriteRenderStats>,
     );
 
     fn run(
         &mut self,
-        (entities, mut visibility, hidden, hidden_prop, active, camera, transparent, global): Self::SystemData,
+        (
+            entities,
+            mut visibility,
+            hidden,
+            hidden_prop,
+            active,
+            camera,
+            transparent,
+            global,
+            draw_order,
+            sprite_sheet_storage,
+            tex_storage,
+            sprite_render,
+            texture_handle,
+            flipped,
+            nine_slice,
+            rgba,
+            blend_mode,
+            screen_space,
+            sort_mode,
+            sort_offset,
+            time,
+            mut stats,
+        ): Self::SystemData,
     ) {
         let origin = Point3::origin();
 
         // The camera position is used to determine culling, but the sprites are ordered based on
         // the Z coordinate
-        let camera: Option<&GlobalTransform> = active
+        let camera_entity = active
             .entity
-            .and_then(|entity| global.get(entity))
-            .or_else(|| (&camera, &global).join().map(|cg| cg.1).next());
+            .filter(|entity| global.contains(*entity))
+            .or_else(|| (&*entities, &camera, &global).join().map(|c| c.0).next());
+        let view_proj = camera_entity.and_then(|entity| {
+            let view = global.get(entity)?.0.try_inverse()?;
+            Some(camera.get(entity)?.proj * view)
+        });
+        let camera: Option<&GlobalTransform> = camera_entity.and_then(|entity| global.get(entity));
         let camera_backward = camera
             .map(|c| c.0.column(2).xyz().into())
             .unwrap_or_else(Vector3::z);
@@ -85,34 +164,75 @@ impl<'a> System<'a> for SpriteVisibilitySortingSystem {
             .unwrap_or_else(|| origin);
 
         self.centroids.clear();
+        let mut candidates = 0;
         self.centroids.extend(
-            (&*entities, &global, !&hidden, !&hidden_prop)
+            (&*entities, &global, !&hidden, !&hidden_prop, !&screen_space)
                 .join()
-                .map(|(entity, global, _, _)| (entity, global.0.transform_point(&origin)))
-                .map(|(entity, centroid)| Internals {
+                .inspect(|_| candidates += 1)
+                .filter(|(entity, global, _, _, _)| {
+                    let view_proj = match view_proj {
+                        Some(ref view_proj) => view_proj,
+                        None => return true,
+                    };
+                    let bounds = if let Some(sprite_render) = sprite_render.get(*entity) {
+                        sprite_sheet_storage
+                            .get(&sprite_render.sprite_sheet)
+                            .and_then(|sheet| sheet.sprites.get(sprite_render.sprite_number))
+                            .map(|sprite| {
+                                let size = match nine_slice.get(*entity) {
+                                    Some(nine_slice) => [nine_slice.width, nine_slice.height],
+                                    None => [sprite.width, sprite.height],
+                                };
+                                ([-sprite.offsets[0], -sprite.offsets[1]], size)
+                            })
+                    } else if let Some(texture_handle) = texture_handle.get(*entity) {
+                        // Images are drawn one unit right and up of their entity.
+                        tex_storage
+                            .get(texture_handle)
+                            .map(|texture| texture.size())
+                            .map(|(width, height)| ([1.0, 1.0], [width as f32, height as f32]))
+                    } else {
+                        None
+                    };
+                    match bounds {
+                        Some((center, size)) => {
+                            in_view(view_proj, &global.0, flipped.get(*entity), center, size)
+                        }
+                        None => true,
+                    }
+                })
+                .map(|(entity, global, _, _, _)| {
+                    let offset = sort_offset.get(entity).map_or(0.0, |offset| offset.0);
+                    let sort_point = global.0.transform_point(&Point3::new(0.0, offset, 0.0));
+                    (entity, global.0.transform_point(&origin), sort_point.y)
+                })
+                .map(|(entity, centroid, sort_y)| Internals {
                     entity,
-                    transparent: transparent.contains(entity),
+                    ordered: *sort_mode == SpriteSortMode::Y
+                        || transparent.contains(entity)
+                        || draw_order.contains(entity)
+                        || rgba.get(entity).map_or(false, |rgba| rgba.3 < 1.0)
+                        || blend_mode
+                            .get(entity)
+                            .map_or(false, |m| *m != BlendMode::Alpha),
+                    draw_order: draw_order.get(entity).cloned().unwrap_or_default(),
                     centroid,
+                    sort_y,
                     from_camera: centroid - camera_centroid,
                 })
                 // filter entities behind the camera
                 .filter(|c| c.from_camera.dot(&camera_backward) < 0.),
         );
+        stats.counts(time.frame_number()).culled += candidates - self.centroids.len();
         self.transparent.clear();
         self.transparent
-            .extend(self.centroids.iter().filter(|c| c.transparent).cloned());
-
-        // Note: Smaller Z values are placed first, so that semi-transparent sprite colors blend
-        // correctly. This is opposite to the mesh visibility sorting system.
-        self.transparent.sort_by(|a, b| {
-            a.centroid
-                .z
-                .partial_cmp(&b.centroid.z)
-                .unwrap_or(Ordering::Equal)
-        });
+            .extend(self.centroids.iter().filter(|c| c.ordered).cloned());
+
+        let sort_mode = *sort_mode;
+        self.transparent.sort_by(|a, b| compare(sort_mode, a, b));
         visibility.visible_unordered.clear();
         for c in &self.centroids {
-            if !c.transparent {
+            if !c.ordered {
                 visibility.visible_unordered.add(c.entity.id());
             }
         }
@@ -123,3 +243,136 @@ impl<'a> System<'a> for SpriteVisibilitySortingSystem {
             .extend(self.transparent.iter().map(|c| c.entity));
     }
 }
+
+/// Compares the order in which two sprites are drawn.
+fn compare(sort_mode: SpriteSortMode, a: &Internals, b: &Internals) -> Ordering {
+    let by_z = || {
+        a.centroid
+            .z
+            .partial_cmp(&b.centroid.z)
+            .unwrap_or(Ordering::Equal)
+    };
+    // Note: Smaller Z values are placed first, so that semi-transparent sprite colors blend
+    // correctly. This is opposite to the mesh visibility sorting system.
+    a.draw_order
+        .cmp(&b.draw_order)
+        .then_with(|| match sort_mode {
+            SpriteSortMode::Z => by_z(),
+            // Higher sprites are further away, and are drawn first.
+            SpriteSortMode::Y => b
+                .sort_y
+                .partial_cmp(&a.sort_y)
+                .unwrap_or(Ordering::Equal)
+                .then_with(by_z),
+        })
+}
+
+/// Returns whether a quad, with its center and size relative to the entity, may be in the view of
+/// the camera.
+///
+/// The quad is outside the view if its corners are all outside the same side of the view. Quads
+/// with corners behind a perspective camera are considered in view.
+fn in_view(
+    view_proj: &Matrix4<f32>,
+    transform: &Matrix4<f32>,
+    flipped: Option<&Flipped>,
+    center: [f32; 2],
+    size: [f32; 2],
+) -> bool {
+    // A flipped sprite is mirrored around its entity.
+    let (flip_x, flip_y) = match flipped {
+        Some(Flipped::Horizontal) => (-1.0, 1.0),
+        Some(Flipped::Vertical) => (1.0, -1.0),
+        Some(Flipped::Both) => (-1.0, -1.0),
+        _ => (1.0, 1.0),
+    };
+    let to_clip = view_proj * transform;
+    let corners: Vec<Vector4<f32>> = [(-0.5, -0.5), (0.5, -0.5), (-0.5, 0.5), (0.5, 0.5)]
+        .iter()
+        .map(|(x, y)| {
+            to_clip
+                * Vector4::new(
+                    (center[0] + x * size[0]) * flip_x,
+                    (center[1] + y * size[1]) * flip_y,
+                    0.0,
+                    1.0,
+                )
+        })
+        .collect();
+    if corners.iter().any(|corner| corner.w <= 0.0) {
+        return true;
+    }
+    (0..2).all(|axis| {
+        !corners.iter().all(|corner| corner[axis] < -corner.w)
+            && !corners.iter().all(|corner| corner[axis] > corner.w)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use amethyst_core::specs::World;
+
+    use crate::cam::Projection;
+
+    #[test]
+    fn y_sort_draws_higher_sprites_first() {
+        let mut world = World::new();
+        let mut sprite = |y: f32, z: f32| Internals {
+            entity: world.create_entity().build(),
+            ordered: true,
+            draw_order: DrawOrder::default(),
+            centroid: Point3::new(0.0, y, z),
+            sort_y: y,
+            from_camera: Vector3::zeros(),
+        };
+        let (low, high) = (sprite(0.0, 1.0), sprite(5.0, 0.0));
+        assert_eq!(compare(SpriteSortMode::Y, &high, &low), Ordering::Less);
+        assert_eq!(compare(SpriteSortMode::Z, &high, &low), Ordering::Less);
+        let front = sprite(5.0, 2.0);
+        assert_eq!(compare(SpriteSortMode::Y, &front, &high), Ordering::Greater);
+        assert_eq!(compare(SpriteSortMode::Y, &front, &low), Ordering::Less);
+    }
+
+    #[test]
+    fn quads_outside_view_are_culled() {
+        let camera = Camera::from(Projection::orthographic(0.0, 100.0, 0.0, 100.0));
+        let at = |x: f32, y: f32| Matrix4::new_translation(&Vector3::new(x, y, 0.0));
+        let size = [10.0, 10.0];
+
+        assert!(in_view(&camera.proj, &at(50.0, 50.0), None, [0.0; 2], size));
+        // Overlapping the left edge of the view.
+        assert!(in_view(&camera.proj, &at(-4.0, 50.0), None, [0.0; 2], size));
+        assert!(!in_view(
+            &camera.proj,
+            &at(-6.0, 50.0),
+            None,
+            [0.0; 2],
+            size
+        ));
+        assert!(!in_view(
+            &camera.proj,
+            &at(50.0, 120.0),
+            None,
+            [0.0; 2],
+            size
+        ));
+        // The offsets of the sprite move it back in view, unless it is flipped.
+        assert!(in_view(
+            &camera.proj,
+            &at(-6.0, 50.0),
+            None,
+            [8.0, 0.0],
+            size
+        ));
+        let flipped = Some(&Flipped::Horizontal);
+        assert!(!in_view(
+            &camera.proj,
+            &at(-6.0, 50.0),
+            flipped,
+            [8.0, 0.0],
+            size
+        ));
+    }
+}
@@ -24,10 +24,12 @@ use crate::{
     formats::{create_mesh_asset, create_texture_asset},
     mesh::Mesh,
     mtl::{Material, MaterialDefaults},
+    offscreen::RenderTargetTextures,
     pipe::{PipelineBuild, PipelineData, PolyPipeline},
     renderer::Renderer,
     resources::{ScreenDimensions, WindowMessages},
-    tex::Texture,
+    tex::{SamplerInfo, Texture},
+    types::Sampler,
 };
 
 /// Rendering system.
@@ -38,6 +40,9 @@ pub struct RenderSystem<P> {
     #[derivative(Debug = "ignore")]
     renderer: Renderer,
     cached_size: (f64, f64),
+    /// Samplers of the textures of the render targets
+    #[derivative(Debug = "ignore")]
+    target_samplers: Vec<(SamplerInfo, Sampler)>,
     // This only exists to allow the system to re-use a vec allocation
     // during event compression.  It's length 0 except during `fn render`.
     event_vec: Vec<Event>,
@@ -88,6 +93,7 @@ where
             pipe,
             renderer,
             cached_size,
+            target_samplers: Vec::new(),
             event_vec: Vec::with_capacity(20),
         }
     }
@@ -115,6 +121,43 @@ where
         );
     }
 
+    fn target_textures(&mut self, (target_textures, mut texture_storage): TargetTextureData<'_>) {
+        use gfx::Factory;
+
+        let target_textures = match target_textures {
+            Some(target_textures) => target_textures,
+            None => return,
+        };
+        for (name, info, handle) in target_textures.iter() {
+            let target = match self.pipe.targets().get(name) {
+                Some(target) => target,
+                None => continue,
+            };
+            let sampler = match self
+                .target_samplers
+                .iter()
+                .position(|(known, _)| known == info)
+            {
+                Some(index) => &self.target_samplers[index].1,
+                None => {
+                    let sampler = self.renderer.factory.create_sampler(*info);
+                    self.target_samplers.push((*info, sampler));
+                    &self.target_samplers[self.target_samplers.len() - 1].1
+                }
+            };
+            let texture = match target.color_texture(0, sampler) {
+                Some(texture) => texture,
+                None => continue,
+            };
+            // The placeholder texture is replaced once it is loaded.
+            if let Some(current) = texture_storage.get_mut(handle) {
+                if *current != texture {
+                    *current = texture;
+                }
+            }
+        }
+    }
+
     fn window_management(&mut self, (mut window_messages, mut screen_dimensions): WindowData<'_>) {
         // Process window commands
         for mut command in window_messages.queue.drain() {
@@ -167,6 +210,11 @@ type AssetLoadingData<'a> = (
     Write<'a, AssetStorage<Texture>>,
 );
 
+type TargetTextureData<'a> = (
+    Option<Read<'a, RenderTargetTextures>>,
+    Write<'a, AssetStorage<Texture>>,
+);
+
 type WindowData<'a> = (Write<'a, WindowMessages>, WriteExpect<'a, ScreenDimensions>);
 
 type RenderData<'a, P> = (
@@ -186,6 +234,11 @@ where
             profile_scope!("render_system_assetloading");
             self.asset_loading(AssetLoadingData::fetch(res));
         }
+        {
+            #[cfg(feature = "profiler")]
+            profile_scope!("render_system_targettextures");
+            self.target_textures(TargetTextureData::fetch(res));
+        }
         {
             #[cfg(feature = "profiler")]
             profile_scope!("render_system_windowmanagement");
@@ -200,6 +253,7 @@ where
 
     fn setup(&mut self, res: &mut Resources) {
         AssetLoadingData::setup(res);
+        TargetTextureData::setup(res);
         WindowData::setup(res);
         RenderData::<P>::setup(res);
 
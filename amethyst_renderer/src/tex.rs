@@ -9,7 +9,7 @@ use serde::{Deserialize, Serialize};
 
 use std::marker::PhantomData;
 
-use amethyst_assets::{Asset, Handle};
+use amethyst_assets::{Asset, AssetStorage, Handle};
 use amethyst_core::specs::prelude::DenseVecStorage;
 use amethyst_error::Error;
 
@@ -41,6 +41,19 @@ impl Texture {
         TextureBuilder::from_color_val(rgba)
     }
 
+    /// Creates a texture from the resources of a render target.
+    pub(crate) fn from_raw(
+        texture: RawTexture,
+        view: RawShaderResourceView,
+        sampler: Sampler,
+    ) -> Self {
+        Texture {
+            sampler,
+            texture,
+            view,
+        }
+    }
+
     /// Returns the sampler for the texture.
     pub fn sampler(&self) -> &Sampler {
         &self.sampler
@@ -56,6 +69,14 @@ impl Texture {
         let (w, h, _, _) = self.texture.get_info().kind.get_dimensions();
         (w as usize, h as usize)
     }
+
+    /// Returns the dimensions ``(width, height)`` of the texture of the handle, once it is loaded.
+    pub fn size_of(
+        handle: &Handle<Texture>,
+        storage: &AssetStorage<Texture>,
+    ) -> Option<(usize, usize)> {
+        storage.get(handle).map(Texture::size)
+    }
 }
 
 impl Asset for Texture {
@@ -68,6 +89,7 @@ impl Asset for Texture {
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct TextureBuilder<D, T> {
     data: D,
+    mips: Vec<Vec<T>>,
     info: Info,
     channel_type: ChannelType,
     sampler: SamplerInfo,
@@ -104,6 +126,7 @@ where
 
         TextureBuilder {
             data,
+            mips: Vec::new(),
             info: Info {
                 kind: Kind::D2(1, 1, AaMode::Single),
                 levels: 1,
@@ -125,12 +148,33 @@ where
 
     /// Sets the number of mipmap levels to generate.
     ///
-    /// FIXME: Only encoders can generate mipmap levels.
+    /// The levels are not generated by the builder, provide their data with `with_mip_data`.
     pub fn mip_levels(mut self, val: u8) -> Self {
         self.info.levels = val;
         self
     }
 
+    /// Sets the data of the mipmap levels below the texture, from the largest to the smallest,
+    /// and the number of levels.
+    ///
+    /// Each level is half the size of the level above it, rounded down, and at least one pixel,
+    /// the last one being at most one pixel wide and high. The levels are uncompressed, as block
+    /// compressed surface formats are not supported.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are 255 levels or more below the texture.
+    pub fn with_mip_data(mut self, mips: Vec<Vec<T>>) -> Self {
+        assert!(
+            mips.len() < usize::from(u8::max_value()),
+            "Too many mipmap levels: {}",
+            mips.len() + 1
+        );
+        self.info.levels = mips.len() as u8 + 1;
+        self.mips = mips;
+        self
+    }
+
     /// Sets the texture width and height in pixels.
     pub fn with_size(mut self, w: u16, h: u16) -> Self {
         use gfx::texture::{AaMode, Kind};
@@ -159,43 +203,52 @@ where
 
     /// Builds and returns the new texture.
     pub fn build(self, fac: &mut Factory) -> Result<Texture, Error> {
-        use std::mem::size_of;
+        use std::{iter, mem::size_of};
 
         use gfx::{format::Swizzle, memory::cast_slice, texture::ResourceDesc, Factory};
 
         // This variable has to live here to make sure the flipped
-        // buffer lives long enough. (If one exists)
-        let mut v_flip_buffer;
-        let mut data = self.data.as_ref();
+        // buffers live long enough. (If they exist)
+        let v_flip_buffers;
+        let mut levels = iter::once(self.data.as_ref())
+            .chain(self.mips.iter().map(Vec::as_slice))
+            .collect::<Vec<_>>();
+        let (w, h, _, _) = self.info.kind.get_dimensions();
+        // Each level halves the size of the texture down to one pixel, `log2(max(w, h)) + 1` total.
+        let max_levels = (16 - w.max(h).max(1).leading_zeros()) as usize;
+        if levels.len() > max_levels {
+            let error = format!(
+                "Too many mipmap levels: Expected at most {} for a texture of {}x{} (actual: {})",
+                max_levels,
+                w,
+                h,
+                levels.len()
+            );
+            return Err(error::Error::PixelDataMismatch(error).into());
+        }
 
         if cfg!(feature = "opengl") {
             let pixel_width = (self.info.format.get_total_bits() / 8) as usize / size_of::<T>();
-            v_flip_buffer = Vec::with_capacity(data.len());
-            let (w, h, _, _) = self.info.kind.get_dimensions();
-            let w = w as usize;
-            let h = h as usize;
-            if w * h * pixel_width != data.len() {
-                let error = format!(
-                    "Texture size mismatch: Expected pixel data vector of length {:?} (actual: {:?})",
-                    w * h * pixel_width,
-                    data.len()
-                );
-                return Err(error::Error::PixelDataMismatch(error).into());
-            }
-            for y in 0..h {
-                for x in 0..(w * pixel_width) {
-                    v_flip_buffer.push(data[x + (h - y - 1) * w * pixel_width]);
-                    // Uncomment this if you need to debug this.
-                    // println!("x: {}, y: {}, w: {}, h: {}, pw: {}", x, y, w, h, pixel_width);
-                }
-            }
-            data = &v_flip_buffer;
+            v_flip_buffers = levels
+                .iter()
+                .enumerate()
+                .map(|(level, data)| {
+                    let w = (w as usize >> level).max(1);
+                    let h = (h as usize >> level).max(1);
+                    flip_vertically(*data, w, h, pixel_width)
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            levels = v_flip_buffers.iter().map(Vec::as_slice).collect();
         }
 
+        let data = levels
+            .iter()
+            .map(|data| cast_slice(*data))
+            .collect::<Vec<&[u8]>>();
         let tex = fac.create_texture_raw(
             self.info,
             Some(self.channel_type),
-            Some((&[cast_slice(data)], Mipmap::Provided)),
+            Some((&data, Mipmap::Provided)),
         )?;
 
         let desc = ResourceDesc {
@@ -216,3 +269,29 @@ where
         })
     }
 }
+
+/// Flips the rows of pixel data, which OpenGL expects from the bottom up.
+fn flip_vertically<T: Copy>(
+    data: &[T],
+    w: usize,
+    h: usize,
+    pixel_width: usize,
+) -> Result<Vec<T>, Error> {
+    if w * h * pixel_width != data.len() {
+        let error = format!(
+            "Texture size mismatch: Expected pixel data vector of length {:?} (actual: {:?})",
+            w * h * pixel_width,
+            data.len()
+        );
+        return Err(error::Error::PixelDataMismatch(error).into());
+    }
+    let mut v_flip_buffer = Vec::with_capacity(data.len());
+    for y in 0..h {
+        for x in 0..(w * pixel_width) {
+            v_flip_buffer.push(data[x + (h - y - 1) * w * pixel_width]);
+            // Uncomment this if you need to debug this.
+            // println!("x: {}, y: {}, w: {}, h: {}, pw: {}", x, y, w, h, pixel_width);
+        }
+    }
+    Ok(v_flip_buffer)
+}
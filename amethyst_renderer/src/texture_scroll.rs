@@ -0,0 +1,98 @@
+//! Module for the TextureScroll component and TextureScrollSystem.
+
+use amethyst_assets::{PrefabData, ProgressCounter};
+use amethyst_core::{
+    specs::{Component, DenseVecStorage, Entity, Join, Read, ReadStorage, System, WriteStorage},
+    Time,
+};
+use amethyst_derive::PrefabData;
+use amethyst_error::Error;
+use serde::{Deserialize, Serialize};
+
+use crate::Material;
+
+/// # TextureScroll Component
+/// Scrolls the albedo texture of the `Material` of the entity, moving its `albedo_offset` by the
+/// velocity every second. You must add the `TextureScrollSystem` to your dispatcher for this to
+/// take effect.
+///
+/// The texture should be created with a `WrapMode::Tile` sampler to repeat while scrolling.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, PrefabData)]
+#[prefab(Component)]
+pub struct TextureScroll {
+    /// Velocity along the U coordinate, in textures per second.
+    pub u: f32,
+    /// Velocity along the V coordinate, in textures per second.
+    pub v: f32,
+    /// Whether to use the unscaled time.
+    #[serde(default)]
+    pub absolute_time: bool,
+}
+
+impl TextureScroll {
+    /// Creates a `TextureScroll` with the given velocities in textures per second.
+    pub fn new(u: f32, v: f32) -> Self {
+        TextureScroll {
+            u,
+            v,
+            absolute_time: false,
+        }
+    }
+}
+
+impl Component for TextureScroll {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Moves the start and end of a texture offset, keeping the start within one texture so the
+/// offset does not lose precision over time.
+fn scroll(offset: &mut (f32, f32), distance: f32) {
+    let start = offset.0 + distance;
+    let wrap = start.floor();
+    *offset = (start - wrap, offset.1 + distance - wrap);
+}
+
+/// System scrolling the albedo texture of the `Material` of the entities with a `TextureScroll`
+/// component.
+#[derive(Default)]
+pub struct TextureScrollSystem;
+
+impl<'a> System<'a> for TextureScrollSystem {
+    type SystemData = (
+        Read<'a, Time>,
+        ReadStorage<'a, TextureScroll>,
+        WriteStorage<'a, Material>,
+    );
+
+    fn run(&mut self, (time, scrolls, mut materials): Self::SystemData) {
+        for (scroll_speed, material) in (&scrolls, &mut materials).join() {
+            let seconds = if scroll_speed.absolute_time {
+                time.delta_real_seconds()
+            } else {
+                time.delta_seconds()
+            };
+            scroll(&mut material.albedo_offset.u, scroll_speed.u * seconds);
+            scroll(&mut material.albedo_offset.v, scroll_speed.v * seconds);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scroll_wraps_start() {
+        let mut offset = (0.0, 1.0);
+        scroll(&mut offset, 0.25);
+        assert_eq!(offset, (0.25, 1.25));
+        scroll(&mut offset, 1.0);
+        assert_eq!(offset, (0.25, 1.25));
+        scroll(&mut offset, -0.5);
+        assert_eq!(offset, (0.75, 1.75));
+
+        let mut half = (0.5, 1.0);
+        scroll(&mut half, 0.75);
+        assert_eq!(half, (0.25, 0.75));
+    }
+}
@@ -0,0 +1,230 @@
+//! Screen effects played when the application changes state.
+
+use serde::{Deserialize, Serialize};
+
+use crate::color::Rgba;
+
+/// Curve applied to the progress of a transition.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum Easing {
+    /// Constant speed.
+    Linear,
+    /// Starts slowly.
+    EaseIn,
+    /// Ends slowly.
+    EaseOut,
+    /// Starts and ends slowly.
+    EaseInOut,
+}
+
+impl Easing {
+    /// Applies the curve to a progress between 0 and 1.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.max(0.0).min(1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+impl Default for Easing {
+    fn default() -> Self {
+        Easing::Linear
+    }
+}
+
+/// Effect drawn over the screen during a state transition.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum TransitionEffect {
+    /// Fades the screen to the color before the state changes, and back from it after.
+    FadeToColor(Rgba),
+    /// Keeps the last frame of the previous state, fading it out over the new state.
+    ///
+    /// Requires the scene to be rendered to a target read by the `DrawTransition` pass.
+    Crossfade,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Phase {
+    Idle,
+    /// The screen is being covered, the state changes when it is.
+    Out {
+        elapsed: f32,
+        captured: bool,
+    },
+    /// The screen is being revealed.
+    In {
+        elapsed: f32,
+    },
+}
+
+/// Resource configuring the effect played when the `StateMachine` pushes, pops or switches
+/// states, drawn by the `DrawTransition` pass.
+///
+/// When an effect is set, the state machine waits for the screen to be covered before changing
+/// state, and the new state is then revealed over the same duration.
+#[derive(Clone, Debug)]
+pub struct ScreenTransition {
+    /// The effect, no effect is played if `None`.
+    pub effect: Option<TransitionEffect>,
+    /// Duration in seconds of each half of the transition.
+    pub duration: f32,
+    /// Curve applied to the progress of the transition.
+    pub easing: Easing,
+    phase: Phase,
+}
+
+impl Default for ScreenTransition {
+    fn default() -> Self {
+        ScreenTransition {
+            effect: None,
+            duration: 0.5,
+            easing: Easing::default(),
+            phase: Phase::Idle,
+        }
+    }
+}
+
+impl ScreenTransition {
+    /// Creates a transition playing the effect, each half lasting the given duration in seconds.
+    pub fn new(effect: TransitionEffect, duration: f32) -> Self {
+        ScreenTransition {
+            effect: Some(effect),
+            duration,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the easing curve.
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Whether a transition is playing.
+    pub fn is_active(&self) -> bool {
+        self.phase != Phase::Idle
+    }
+
+    /// Starts covering the screen, returns `false` if there is no effect.
+    pub fn start(&mut self) -> bool {
+        if self.effect.is_none() {
+            return false;
+        }
+        self.phase = Phase::Out {
+            elapsed: 0.0,
+            captured: false,
+        };
+        true
+    }
+
+    /// Advances the transition by the given seconds.
+    pub fn advance(&mut self, delta: f32) {
+        match self.phase {
+            Phase::Idle => {}
+            Phase::Out {
+                ref mut elapsed, ..
+            } => *elapsed += delta,
+            Phase::In { ref mut elapsed } => {
+                *elapsed += delta;
+                if *elapsed >= self.duration {
+                    self.phase = Phase::Idle;
+                }
+            }
+        }
+    }
+
+    /// Whether the screen is covered, so the state can change.
+    ///
+    /// A crossfade covers the screen once the last frame is captured, or after the duration if it
+    /// can't be captured.
+    pub fn is_covered(&self) -> bool {
+        match (self.phase, self.effect) {
+            (Phase::Out { captured: true, .. }, Some(TransitionEffect::Crossfade)) => true,
+            (Phase::Out { elapsed, .. }, _) => elapsed >= self.duration,
+            _ => false,
+        }
+    }
+
+    /// Starts revealing the screen, once the state changed.
+    pub fn reveal(&mut self) {
+        self.phase = Phase::In { elapsed: 0.0 };
+    }
+
+    /// Opacity of the effect drawn over the screen.
+    pub fn opacity(&self) -> f32 {
+        let progress = |elapsed: f32| {
+            if self.duration > 0.0 {
+                self.easing.apply(elapsed / self.duration)
+            } else {
+                1.0
+            }
+        };
+        match (self.phase, self.effect) {
+            (Phase::Idle, _) | (_, None) => 0.0,
+            (Phase::Out { .. }, Some(TransitionEffect::Crossfade)) => 0.0,
+            (Phase::Out { elapsed, .. }, _) => progress(elapsed),
+            (Phase::In { elapsed }, _) => 1.0 - progress(elapsed),
+        }
+    }
+
+    /// Whether the pass should capture the current frame for a crossfade.
+    pub(crate) fn capture_requested(&self) -> bool {
+        match (self.phase, self.effect) {
+            (Phase::Out { captured, .. }, Some(TransitionEffect::Crossfade)) => !captured,
+            _ => false,
+        }
+    }
+
+    pub(crate) fn set_captured(&mut self) {
+        if let Phase::Out {
+            ref mut captured, ..
+        } = self.phase
+        {
+            *captured = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fade_phases() {
+        let mut transition = ScreenTransition::new(TransitionEffect::FadeToColor(Rgba::BLACK), 1.0);
+        assert!(transition.start());
+        transition.advance(0.5);
+        assert!(!transition.is_covered());
+        assert_eq!(transition.opacity(), 0.5);
+        transition.advance(0.5);
+        assert!(transition.is_covered());
+
+        transition.reveal();
+        assert_eq!(transition.opacity(), 1.0);
+        transition.advance(1.0);
+        assert!(!transition.is_active());
+        assert_eq!(transition.opacity(), 0.0);
+    }
+
+    #[test]
+    fn crossfade_waits_for_capture() {
+        let mut transition = ScreenTransition::new(TransitionEffect::Crossfade, 1.0);
+        transition.start();
+        assert!(transition.capture_requested());
+        assert!(!transition.is_covered());
+        transition.set_captured();
+        assert!(transition.is_covered());
+        assert!(!transition.capture_requested());
+    }
+
+    #[test]
+    fn no_effect() {
+        let mut transition = ScreenTransition::default();
+        assert!(!transition.start());
+        assert!(!transition.is_active());
+    }
+}
@@ -259,6 +259,49 @@ impl With<TexCoord> for PosTex {
     };
 }
 
+/// Vertex format with position, RGBA8 color and UV texture coordinate attributes.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PosColorTex {
+    /// Position of the vertex in 3D space.
+    pub position: Vector3<f32>,
+    /// RGBA color value of the vertex.
+    pub color: [f32; 4],
+    /// UV texture coordinates used by the vertex.
+    pub tex_coord: Vector2<f32>,
+}
+
+unsafe impl Pod for PosColorTex {}
+
+impl VertexFormat for PosColorTex {
+    const ATTRIBUTES: Attributes<'static> = &[
+        (Position::NAME, <Self as With<Position>>::FORMAT),
+        (Color::NAME, <Self as With<Color>>::FORMAT),
+        (TexCoord::NAME, <Self as With<TexCoord>>::FORMAT),
+    ];
+}
+
+impl With<Position> for PosColorTex {
+    const FORMAT: AttributeFormat = Element {
+        offset: 0,
+        format: Position::FORMAT,
+    };
+}
+
+impl With<Color> for PosColorTex {
+    const FORMAT: AttributeFormat = Element {
+        offset: Position::SIZE,
+        format: Color::FORMAT,
+    };
+}
+
+impl With<TexCoord> for PosColorTex {
+    const FORMAT: AttributeFormat = Element {
+        offset: Position::SIZE + Color::SIZE,
+        format: TexCoord::FORMAT,
+    };
+}
+
 /// Vertex format with position, normal, and UV texture coordinate attributes.
 #[repr(C)]
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
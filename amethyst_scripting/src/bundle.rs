@@ -0,0 +1,82 @@
+//! ECS scripting bundle
+
+use amethyst_core::{bundle::SystemBundle, specs::prelude::DispatcherBuilder};
+use amethyst_error::Error;
+
+use crate::{
+    component::{ComponentAccess, ScriptComponent},
+    host::ScriptHost,
+};
+
+/// Scripting bundle
+///
+/// Adds the `ScriptHost` as a thread local system, running the scripts after the other systems.
+/// Only the components whitelisted with `with_component` and `with_read_only_component` can be
+/// accessed by the scripts, with the ids given by the order of the calls.
+///
+/// ## Errors
+///
+/// No errors will be returned by this bundle.
+pub struct ScriptingBundle {
+    components: Vec<ComponentAccess>,
+    max_memory_pages: usize,
+    fuel: u64,
+}
+
+impl Default for ScriptingBundle {
+    fn default() -> Self {
+        ScriptingBundle {
+            components: Vec::new(),
+            max_memory_pages: 256,
+            fuel: 1_000_000,
+        }
+    }
+}
+
+impl ScriptingBundle {
+    /// Create a new scripting bundle, without whitelisted components.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Allow the scripts to read and write a component, under the given name.
+    pub fn with_component<C: ScriptComponent, N: Into<String>>(mut self, name: N) -> Self {
+        self.components
+            .push(ComponentAccess::new::<C>(name.into(), true));
+        self
+    }
+
+    /// Allow the scripts to read a component, under the given name.
+    pub fn with_read_only_component<C: ScriptComponent, N: Into<String>>(
+        mut self,
+        name: N,
+    ) -> Self {
+        self.components
+            .push(ComponentAccess::new::<C>(name.into(), false));
+        self
+    }
+
+    /// Set the maximal memory of each script, in pages of 64 KiB, 256 pages by default.
+    pub fn with_max_memory_pages(mut self, pages: usize) -> Self {
+        self.max_memory_pages = pages;
+        self
+    }
+
+    /// Set the number of instructions a script can run in each call to its `init` or `update`
+    /// before the call is aborted, 1 000 000 by default.
+    pub fn with_fuel(mut self, fuel: u64) -> Self {
+        self.fuel = fuel;
+        self
+    }
+}
+
+impl<'a, 'b> SystemBundle<'a, 'b> for ScriptingBundle {
+    fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<(), Error> {
+        builder.add_thread_local(ScriptHost::new(
+            self.components,
+            self.max_memory_pages,
+            self.fuel,
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+//! Components exposed to the scripts.
+
+use amethyst_core::{
+    nalgebra::{Quaternion, UnitQuaternion, Vector3},
+    specs::{
+        prelude::SystemData, Component, Entities, Entity, Join, ReadStorage, Resources,
+        WriteStorage,
+    },
+    Transform,
+};
+
+/// A component that can be whitelisted for the scripts, with the `ScriptingBundle`.
+///
+/// Scripts see components as flat lists of `f32` fields, whose order is part of the ABI of the
+/// game and should not change once mods rely on it.
+pub trait ScriptComponent: Component + Send + Sync {
+    /// Appends the fields of the component to the values.
+    fn read(&self, values: &mut Vec<f32>);
+
+    /// Sets the fields of the component, returns `false` if the values are invalid, in which case
+    /// the component must be left unchanged.
+    fn write(&mut self, values: &[f32]) -> bool;
+}
+
+/// Fields: the translation, the rotation quaternion as `i, j, k, w`, and the scale.
+impl ScriptComponent for Transform {
+    fn read(&self, values: &mut Vec<f32>) {
+        values.extend(self.translation().iter());
+        values.extend(self.rotation().quaternion().coords.iter());
+        values.extend(self.scale().iter());
+    }
+
+    fn write(&mut self, values: &[f32]) -> bool {
+        if values.len() != 10 || values.iter().any(|value| !value.is_finite()) {
+            return false;
+        }
+        let rotation = Quaternion::new(values[6], values[3], values[4], values[5]);
+        if rotation.norm() <= std::f32::EPSILON {
+            return false;
+        }
+        *self.translation_mut() = Vector3::new(values[0], values[1], values[2]);
+        self.set_rotation(UnitQuaternion::from_quaternion(rotation));
+        *self.scale_mut() = Vector3::new(values[7], values[8], values[9]);
+        true
+    }
+}
+
+/// Access to the storage of a whitelisted component, without knowing its type.
+pub(crate) struct ComponentAccess {
+    pub(crate) name: String,
+    pub(crate) writable: bool,
+    setup: fn(&mut Resources),
+    entities: fn(&Resources, &mut Vec<Entity>),
+    read: fn(&Resources, Entity, &mut Vec<f32>) -> bool,
+    write: fn(&Resources, Entity, &[f32]) -> bool,
+}
+
+impl ComponentAccess {
+    pub(crate) fn new<C: ScriptComponent>(name: String, writable: bool) -> Self {
+        ComponentAccess {
+            name,
+            writable,
+            setup: setup::<C>,
+            entities: entities::<C>,
+            read: read::<C>,
+            write: write::<C>,
+        }
+    }
+
+    pub(crate) fn setup(&self, res: &mut Resources) {
+        (self.setup)(res)
+    }
+
+    /// Appends the entities having the component.
+    pub(crate) fn entities(&self, res: &Resources, entities: &mut Vec<Entity>) {
+        (self.entities)(res, entities)
+    }
+
+    /// Appends the fields of the component of the entity, returns `false` if it has none.
+    pub(crate) fn read(&self, res: &Resources, entity: Entity, values: &mut Vec<f32>) -> bool {
+        (self.read)(res, entity, values)
+    }
+
+    /// Sets the fields of the component of the entity, returns `false` if it has none, if the
+    /// component is read-only or if the values are invalid.
+    pub(crate) fn write(&self, res: &Resources, entity: Entity, values: &[f32]) -> bool {
+        self.writable && (self.write)(res, entity, values)
+    }
+}
+
+fn setup<C: ScriptComponent>(res: &mut Resources) {
+    WriteStorage::<'_, C>::setup(res);
+}
+
+fn entities<C: ScriptComponent>(res: &Resources, out: &mut Vec<Entity>) {
+    let (entities, storage) = <(Entities<'_>, ReadStorage<'_, C>)>::fetch(res);
+    out.extend((&*entities, &storage).join().map(|(entity, _)| entity));
+}
+
+fn read<C: ScriptComponent>(res: &Resources, entity: Entity, values: &mut Vec<f32>) -> bool {
+    match ReadStorage::<'_, C>::fetch(res).get(entity) {
+        Some(component) => {
+            component.read(values);
+            true
+        }
+        None => false,
+    }
+}
+
+fn write<C: ScriptComponent>(res: &Resources, entity: Entity, values: &[f32]) -> bool {
+    WriteStorage::<'_, C>::fetch(res)
+        .get_mut(entity)
+        .map_or(false, |component| component.write(values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transform_round_trip() {
+        let mut transform = Transform::default();
+        transform.set_xyz(1.0, 2.0, 3.0).set_scale(2.0, 2.0, 2.0);
+        let mut values = Vec::new();
+        transform.read(&mut values);
+        assert_eq!(
+            values,
+            vec![1.0, 2.0, 3.0, 0.0, 0.0, 0.0, 1.0, 2.0, 2.0, 2.0]
+        );
+
+        values[0] = 5.0;
+        assert!(transform.write(&values));
+        assert_eq!(transform.translation().x, 5.0);
+
+        assert!(!transform.write(&values[..9]));
+        values[6] = 0.0;
+        assert!(!transform.write(&values));
+        assert_eq!(transform.translation().x, 5.0);
+    }
+}
@@ -0,0 +1,643 @@
+//! The host running the scripts, and the functions it exposes to them.
+
+use amethyst_core::{
+    specs::{prelude::SystemData, Entities, Entity, Resources, RunNow},
+    Time,
+};
+use amethyst_error::{format_err, Error};
+use log::{error, info};
+use std::fmt;
+use wasmi::{
+    Error as InterpreterError, Externals, FuncInstance, FuncRef, HostError, ImportsBuilder,
+    MemoryRef, Module, ModuleImportResolver, ModuleInstance, ModuleRef, RuntimeArgs, RuntimeValue,
+    Signature, Trap, TrapKind, ValueType,
+};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{component::ComponentAccess, scripts::Scripts};
+
+/// Name of the module the host functions are imported from.
+const HOST_MODULE: &str = "amethyst";
+
+/// Name of the module the metering function is imported from by the instrumented scripts.
+const METERING_MODULE: &str = "env";
+
+const LOG: usize = 0;
+const COMPONENT_ID: usize = 1;
+const QUERY: usize = 2;
+const GET: usize = 3;
+const SET: usize = 4;
+const GAS: usize = 5;
+
+/// Returned by the host functions for unknown components, or entities without the component.
+const NOT_FOUND: i32 = -1;
+
+/// Runs the scripts of the `Scripts` resource, calling their `update` export every frame with the
+/// delta seconds of the `Time` resource.
+///
+/// The host is a thread local system, as it needs access to the storages of all the whitelisted
+/// components, and the interpreter cannot be sent to other threads. Each call into a script is
+/// aborted once it has run the number of instructions given by the fuel of the host.
+pub struct ScriptHost {
+    components: Vec<ComponentAccess>,
+    max_memory_pages: usize,
+    fuel: u64,
+    scripts: Vec<Script>,
+}
+
+struct Script {
+    name: String,
+    instance: ModuleRef,
+    memory: MemoryRef,
+}
+
+impl ScriptHost {
+    pub(crate) fn new(
+        components: Vec<ComponentAccess>,
+        max_memory_pages: usize,
+        fuel: u64,
+    ) -> Self {
+        ScriptHost {
+            components,
+            max_memory_pages,
+            fuel,
+            scripts: Vec::new(),
+        }
+    }
+
+    fn instantiate(&self, res: &Resources, name: &str, bytes: &[u8]) -> Result<Script, Error> {
+        let module = Module::from_buffer(meter(bytes)?)?;
+        let imports = ImportsBuilder::new()
+            .with_resolver(HOST_MODULE, &HostResolver)
+            .with_resolver(METERING_MODULE, &MeteringResolver);
+        let not_started = ModuleInstance::new(&module, &imports)?;
+
+        let memory = not_started
+            .not_started_instance()
+            .export_by_name("memory")
+            .and_then(|export| export.as_memory().cloned())
+            .ok_or_else(|| format_err!("The script does not export its memory"))?;
+        match memory.maximum() {
+            Some(maximum) if maximum.0 <= self.max_memory_pages => {}
+            _ => {
+                return Err(format_err!(
+                    "The memory of the script must have a maximum of at most {} pages",
+                    self.max_memory_pages
+                ));
+            }
+        }
+
+        let mut context =
+            ScriptContext::new(res, &self.components, name, memory.clone(), self.fuel);
+        let instance = not_started.run_start(&mut context)?;
+        if instance.export_by_name("init").is_some() {
+            // The start function and `init` share the fuel of a single call.
+            instance.invoke_export("init", &[], &mut context)?;
+        }
+        Ok(Script {
+            name: name.to_string(),
+            instance,
+            memory,
+        })
+    }
+}
+
+impl<'a> RunNow<'a> for ScriptHost {
+    fn run_now(&mut self, res: &'a Resources) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("script_host");
+
+        let (pending, unloading) = {
+            let mut scripts = res.fetch_mut::<Scripts>();
+            (
+                std::mem::replace(&mut scripts.pending, Vec::new()),
+                std::mem::replace(&mut scripts.unloading, Vec::new()),
+            )
+        };
+        if !pending.is_empty() || !unloading.is_empty() {
+            self.scripts
+                .retain(|script| !unloading.contains(&script.name));
+            for (name, bytes) in pending {
+                match self.instantiate(res, &name, &bytes) {
+                    Ok(script) => {
+                        self.scripts.retain(|loaded| loaded.name != name);
+                        self.scripts.push(script);
+                    }
+                    Err(e) => error!("Failed to load script `{}`: {}", name, e),
+                }
+            }
+            res.fetch_mut::<Scripts>().loaded = self
+                .scripts
+                .iter()
+                .map(|script| script.name.clone())
+                .collect();
+        }
+
+        let delta = res.fetch::<Time>().delta_seconds();
+        for script in &self.scripts {
+            let mut context = ScriptContext::new(
+                res,
+                &self.components,
+                &script.name,
+                script.memory.clone(),
+                self.fuel,
+            );
+            if let Err(e) =
+                script
+                    .instance
+                    .invoke_export("update", &[RuntimeValue::from(delta)], &mut context)
+            {
+                error!("Script `{}` failed: {}", script.name, e);
+            }
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        res.entry::<Scripts>().or_insert_with(Scripts::default);
+        res.entry::<Time>().or_insert_with(Time::default);
+        for component in &self.components {
+            component.setup(res);
+        }
+    }
+}
+
+/// Instruments a script to call the metering function of the host with the number of
+/// instructions of each block it runs, before running it.
+fn meter(bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    let module = parity_wasm::deserialize_buffer(bytes)?;
+    let module = pwasm_utils::inject_gas_counter(module, &Default::default())
+        .map_err(|_| format_err!("The script could not be instrumented"))?;
+    Ok(parity_wasm::serialize(module)?)
+}
+
+/// Resolves the metering function called by the instrumented scripts.
+struct MeteringResolver;
+
+impl ModuleImportResolver for MeteringResolver {
+    fn resolve_func(
+        &self,
+        field_name: &str,
+        signature: &Signature,
+    ) -> Result<FuncRef, InterpreterError> {
+        let params = &[ValueType::I32][..];
+        if field_name != "gas" || signature.params() != params || signature.return_type().is_some()
+        {
+            return Err(InterpreterError::Instantiation(format!(
+                "Function `{}` is not provided by the host",
+                field_name
+            )));
+        }
+        Ok(FuncInstance::alloc_host(Signature::new(params, None), GAS))
+    }
+}
+
+/// Trap of a script which ran out of fuel.
+#[derive(Debug)]
+struct OutOfFuel;
+
+impl fmt::Display for OutOfFuel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The script ran out of fuel")
+    }
+}
+
+impl HostError for OutOfFuel {}
+
+/// Resolves the functions imported by the scripts to the host functions.
+struct HostResolver;
+
+impl ModuleImportResolver for HostResolver {
+    fn resolve_func(
+        &self,
+        field_name: &str,
+        signature: &Signature,
+    ) -> Result<FuncRef, InterpreterError> {
+        use wasmi::ValueType::{I32, I64};
+
+        let (index, params, result): (usize, &[ValueType], _) = match field_name {
+            "log" => (LOG, &[I32, I32][..], None),
+            "component_id" => (COMPONENT_ID, &[I32, I32][..], Some(I32)),
+            "query" => (QUERY, &[I32, I32, I32][..], Some(I32)),
+            "get" => (GET, &[I64, I32, I32, I32][..], Some(I32)),
+            "set" => (SET, &[I64, I32, I32, I32][..], Some(I32)),
+            _ => {
+                return Err(InterpreterError::Instantiation(format!(
+                    "Function `{}` is not provided by the host",
+                    field_name
+                )));
+            }
+        };
+        if signature.params() != params || signature.return_type() != result {
+            return Err(InterpreterError::Instantiation(format!(
+                "Function `{}` is imported with the wrong signature",
+                field_name
+            )));
+        }
+        Ok(FuncInstance::alloc_host(
+            Signature::new(params, result),
+            index,
+        ))
+    }
+}
+
+/// The state of the host functions during a call into a script.
+struct ScriptContext<'a> {
+    res: &'a Resources,
+    components: &'a [ComponentAccess],
+    script: &'a str,
+    memory: MemoryRef,
+    /// Number of instructions the script can still run during this call
+    fuel: u64,
+    entities: Vec<Entity>,
+    values: Vec<f32>,
+}
+
+impl<'a> ScriptContext<'a> {
+    fn new(
+        res: &'a Resources,
+        components: &'a [ComponentAccess],
+        script: &'a str,
+        memory: MemoryRef,
+        fuel: u64,
+    ) -> Self {
+        ScriptContext {
+            res,
+            components,
+            script,
+            memory,
+            fuel,
+            entities: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+
+    fn read_bytes(&self, ptr: i32, len: i32) -> Result<Vec<u8>, Trap> {
+        if ptr < 0 || len < 0 {
+            return Err(Trap::new(TrapKind::MemoryAccessOutOfBounds));
+        }
+        self.memory
+            .get(ptr as u32, len as usize)
+            .map_err(|_| Trap::new(TrapKind::MemoryAccessOutOfBounds))
+    }
+
+    fn write_bytes(&self, ptr: i32, bytes: &[u8]) -> Result<(), Trap> {
+        if ptr < 0 {
+            return Err(Trap::new(TrapKind::MemoryAccessOutOfBounds));
+        }
+        self.memory
+            .set(ptr as u32, bytes)
+            .map_err(|_| Trap::new(TrapKind::MemoryAccessOutOfBounds))
+    }
+
+    fn read_string(&self, ptr: i32, len: i32) -> Result<String, Trap> {
+        Ok(String::from_utf8_lossy(&self.read_bytes(ptr, len)?).into_owned())
+    }
+
+    fn component(&self, id: i32) -> Option<&'a ComponentAccess> {
+        if id < 0 {
+            return None;
+        }
+        self.components.get(id as usize)
+    }
+
+    /// The living entity with the given id, of its index and generation.
+    fn entity(&self, id: i64) -> Option<Entity> {
+        let (index, generation) = (id as u32, (id >> 32) as i32);
+        let entities = Entities::fetch(self.res);
+        let entity = entities.entity(index);
+        if entity.gen().id() == generation && entities.is_alive(entity) {
+            Some(entity)
+        } else {
+            None
+        }
+    }
+
+    /// Charges instructions to the fuel of the call, trapping once it runs out.
+    fn charge(&mut self, instructions: i32) -> Result<(), Trap> {
+        let instructions = instructions.max(0) as u64;
+        if instructions > self.fuel {
+            self.fuel = 0;
+            return Err(Trap::new(TrapKind::Host(Box::new(OutOfFuel))));
+        }
+        self.fuel -= instructions;
+        Ok(())
+    }
+
+    fn component_id(&self, ptr: i32, len: i32) -> Result<i32, Trap> {
+        let name = self.read_string(ptr, len)?;
+        Ok(self
+            .components
+            .iter()
+            .position(|component| component.name == name)
+            .map_or(NOT_FOUND, |id| id as i32))
+    }
+
+    fn query(&mut self, component: i32, ptr: i32, capacity: i32) -> Result<i32, Trap> {
+        let component = match self.component(component) {
+            Some(component) => component,
+            None => return Ok(NOT_FOUND),
+        };
+        self.entities.clear();
+        component.entities(self.res, &mut self.entities);
+        let bytes = self
+            .entities
+            .iter()
+            .take(capacity.max(0) as usize)
+            .flat_map(|&entity| entity_id(entity).to_le_bytes().to_vec())
+            .collect::<Vec<_>>();
+        self.write_bytes(ptr, &bytes)?;
+        Ok(self.entities.len() as i32)
+    }
+
+    fn get(&mut self, entity: i64, component: i32, ptr: i32, capacity: i32) -> Result<i32, Trap> {
+        let (entity, component) = match (self.entity(entity), self.component(component)) {
+            (Some(entity), Some(component)) => (entity, component),
+            _ => return Ok(NOT_FOUND),
+        };
+        self.values.clear();
+        if !component.read(self.res, entity, &mut self.values) {
+            return Ok(NOT_FOUND);
+        }
+        let bytes = self
+            .values
+            .iter()
+            .take(capacity.max(0) as usize)
+            .flat_map(|value| value.to_bits().to_le_bytes().to_vec())
+            .collect::<Vec<_>>();
+        self.write_bytes(ptr, &bytes)?;
+        Ok(self.values.len() as i32)
+    }
+
+    fn set(&mut self, entity: i64, component: i32, ptr: i32, len: i32) -> Result<i32, Trap> {
+        let (entity, component) = match (self.entity(entity), self.component(component)) {
+            (Some(entity), Some(component)) => (entity, component),
+            _ => return Ok(NOT_FOUND),
+        };
+        let bytes = self.read_bytes(ptr, len.max(0).saturating_mul(4))?;
+        let values = bytes
+            .chunks(4)
+            .map(|chunk| {
+                f32::from_bits(u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            })
+            .collect::<Vec<_>>();
+        if component.write(self.res, entity, &values) {
+            Ok(0)
+        } else {
+            Ok(NOT_FOUND)
+        }
+    }
+}
+
+/// The id of an entity given to the scripts, of its index and generation.
+fn entity_id(entity: Entity) -> i64 {
+    (i64::from(entity.gen().id()) << 32) | i64::from(entity.id())
+}
+
+impl<'a> Externals for ScriptContext<'a> {
+    fn invoke_index(
+        &mut self,
+        index: usize,
+        args: RuntimeArgs<'_>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let arg = |n: usize| args.nth_checked::<i32>(n);
+        let result = match index {
+            LOG => {
+                let message = self.read_string(arg(0)?, arg(1)?)?;
+                info!("[{}] {}", self.script, message);
+                return Ok(None);
+            }
+            COMPONENT_ID => self.component_id(arg(0)?, arg(1)?)?,
+            QUERY => self.query(arg(0)?, arg(1)?, arg(2)?)?,
+            GET => self.get(args.nth_checked(0)?, arg(1)?, arg(2)?, arg(3)?)?,
+            SET => self.set(args.nth_checked(0)?, arg(1)?, arg(2)?, arg(3)?)?,
+            GAS => {
+                self.charge(arg(0)?)?;
+                return Ok(None);
+            }
+            _ => return Err(Trap::new(TrapKind::UnexpectedSignature)),
+        };
+        Ok(Some(RuntimeValue::I32(result)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::{
+        specs::{Builder, World},
+        Transform,
+    };
+
+    use super::*;
+
+    /// Appends a name, prefixed with its length.
+    fn name(out: &mut Vec<u8>, name: &str) {
+        out.push(name.len() as u8);
+        out.extend_from_slice(name.as_bytes());
+    }
+
+    /// Appends a section, prefixed with its id and length.
+    fn section(out: &mut Vec<u8>, id: u8, content: &[u8]) {
+        out.push(id);
+        out.push(content.len() as u8);
+        out.extend_from_slice(content);
+    }
+
+    /// A module exporting `query`, `get` and `set` functions forwarding their arguments to the
+    /// host functions, an empty `update`, a `spin` function looping forever and a memory of one
+    /// page.
+    fn module() -> Vec<u8> {
+        const I32: u8 = 0x7f;
+        const I64: u8 = 0x7e;
+        const F32: u8 = 0x7d;
+        let mut module = vec![0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+
+        // Types: (i32, i32, i32) -> i32, (i64, i32, i32, i32) -> i32, (f32) -> () and () -> ().
+        let types = [
+            &[0x04, 0x60, 3, I32, I32, I32, 1, I32][..],
+            &[0x60, 4, I64, I32, I32, I32, 1, I32][..],
+            &[0x60, 1, F32, 0][..],
+            &[0x60, 0, 0][..],
+        ]
+        .concat();
+        section(&mut module, 1, &types);
+
+        let mut imports = vec![3];
+        for &(field, ty) in &[("query", 0), ("get", 1), ("set", 1)] {
+            name(&mut imports, HOST_MODULE);
+            name(&mut imports, field);
+            imports.extend_from_slice(&[0x00, ty]);
+        }
+        section(&mut module, 2, &imports);
+
+        // Functions 3 to 7, after the imported ones.
+        section(&mut module, 3, &[5, 0, 1, 1, 2, 3]);
+        // One memory of exactly one page.
+        section(&mut module, 5, &[1, 0x01, 1, 1]);
+
+        let mut exports = vec![6];
+        for &(field, kind, index) in &[
+            ("memory", 0x02, 0),
+            ("query", 0x00, 3),
+            ("get", 0x00, 4),
+            ("set", 0x00, 5),
+            ("update", 0x00, 6),
+            ("spin", 0x00, 7),
+        ] {
+            name(&mut exports, field);
+            exports.extend_from_slice(&[kind, index]);
+        }
+        section(&mut module, 7, &exports);
+
+        // Bodies: `local.get` of each argument, `call` of the import, and `end`.
+        let mut code = vec![5];
+        for &(params, function) in &[(3, 0), (4, 1), (4, 2)] {
+            code.push(4 + 2 * params);
+            code.push(0);
+            for param in 0..params {
+                code.extend_from_slice(&[0x20, param]);
+            }
+            code.extend_from_slice(&[0x10, function, 0x0b]);
+        }
+        code.extend_from_slice(&[2, 0, 0x0b]);
+        // `loop`, `br 0`, `end` and `end`.
+        code.extend_from_slice(&[7, 0, 0x03, 0x40, 0x0c, 0x00, 0x0b, 0x0b]);
+        section(&mut module, 10, &code);
+        module
+    }
+
+    fn call(
+        script: &Script,
+        context: &mut ScriptContext<'_>,
+        function: &str,
+        args: &[i64],
+    ) -> Result<i32, InterpreterError> {
+        // The entity, first argument of `get` and `set`, is the only `i64`.
+        let args = args
+            .iter()
+            .enumerate()
+            .map(|(i, &arg)| match (i, function) {
+                (0, "get") | (0, "set") => RuntimeValue::I64(arg),
+                _ => RuntimeValue::I32(arg as i32),
+            })
+            .collect::<Vec<_>>();
+        let result = script.instance.invoke_export(function, &args, context)?;
+        Ok(result
+            .and_then(|value| value.try_into())
+            .expect("The function should return an i32"))
+    }
+
+    #[test]
+    fn scripts_access_components() {
+        let mut world = World::new();
+        let mut host = ScriptHost::new(
+            vec![
+                ComponentAccess::new::<Transform>("transform".to_string(), true),
+                ComponentAccess::new::<Transform>("read_only_transform".to_string(), false),
+            ],
+            1,
+            1_000,
+        );
+        RunNow::setup(&mut host, &mut world.res);
+        let mut transform = Transform::default();
+        transform.set_xyz(1.0, 2.0, 3.0);
+        let entity = world.create_entity().with(transform.clone()).build();
+        let dead = world.create_entity().with(transform).build();
+        world.delete_entity(dead).unwrap();
+        world.maintain();
+        // The index of the deleted entity is reused, with another generation.
+        let reused = world.create_entity().with(Transform::default()).build();
+        assert_eq!(reused.id(), dead.id());
+
+        world.write_resource::<Scripts>().load("test", module());
+        host.run_now(&world.res);
+        assert!(world.read_resource::<Scripts>().is_loaded("test"));
+
+        let script = &host.scripts[0];
+        let mut context = ScriptContext::new(
+            &world.res,
+            &host.components,
+            "test",
+            script.memory.clone(),
+            1_000,
+        );
+        let id = entity_id(entity);
+        let mut invoke = |function, args: &[i64]| call(script, &mut context, function, args);
+
+        assert_eq!(invoke("query", &[0, 0, 4]).unwrap(), 2);
+        assert_eq!(script.memory.get(0, 8).unwrap(), id.to_le_bytes().to_vec());
+        let reused_id = entity_id(reused).to_le_bytes().to_vec();
+        assert_eq!(script.memory.get(8, 8).unwrap(), reused_id);
+        assert_eq!(invoke("query", &[2, 0, 4]).unwrap(), NOT_FOUND);
+        assert_eq!(invoke("query", &[-1, 0, 4]).unwrap(), NOT_FOUND);
+        // Only the number of entities is returned without capacity.
+        assert_eq!(invoke("query", &[0, 0, -1]).unwrap(), 2);
+
+        assert_eq!(invoke("get", &[id, 1, 16, 10]).unwrap(), 10);
+        let x = script.memory.get(16, 4).unwrap();
+        assert_eq!(
+            f32::from_bits(u32::from_le_bytes([x[0], x[1], x[2], x[3]])),
+            1.0
+        );
+        // The id of the deleted entity does not give access to the entity reusing its index.
+        assert_eq!(
+            invoke("get", &[entity_id(dead), 0, 16, 10]).unwrap(),
+            NOT_FOUND
+        );
+        assert_eq!(invoke("get", &[entity_id(reused), 0, 16, 10]).unwrap(), 10);
+        assert_eq!(invoke("get", &[-1, 0, 16, 10]).unwrap(), NOT_FOUND);
+
+        let values = [5.0f32, 2.0, 3.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0]
+            .iter()
+            .flat_map(|value| value.to_bits().to_le_bytes().to_vec())
+            .collect::<Vec<_>>();
+        script.memory.set(100, &values).unwrap();
+        assert_eq!(invoke("set", &[id, 1, 100, 10]).unwrap(), NOT_FOUND);
+        assert_eq!(invoke("set", &[id, 0, 100, 9]).unwrap(), NOT_FOUND);
+        assert_eq!(invoke("set", &[id, 0, 100, -10]).unwrap(), NOT_FOUND);
+        assert_eq!(invoke("set", &[id, 0, 100, 10]).unwrap(), 0);
+
+        // Accessing memory outside of the memory of the script traps.
+        assert!(invoke("query", &[0, 65534, 4]).is_err());
+        assert!(invoke("query", &[0, -4, 4]).is_err());
+        assert!(invoke("get", &[id, 0, 65530, 10]).is_err());
+        assert!(invoke("set", &[id, 0, 65530, 10]).is_err());
+        assert!(invoke("set", &[id, 0, -40, 10]).is_err());
+
+        let transforms = world.read_storage::<Transform>();
+        assert_eq!(transforms.get(entity).unwrap().translation().x, 5.0);
+    }
+
+    #[test]
+    fn scripts_with_too_much_memory_are_rejected() {
+        let mut world = World::new();
+        let mut host = ScriptHost::new(Vec::new(), 0, 1_000);
+        RunNow::setup(&mut host, &mut world.res);
+        world.write_resource::<Scripts>().load("test", module());
+        host.run_now(&world.res);
+        assert!(!world.read_resource::<Scripts>().is_loaded("test"));
+    }
+
+    #[test]
+    fn scripts_running_out_of_fuel_are_aborted() {
+        let mut world = World::new();
+        let mut host = ScriptHost::new(Vec::new(), 1, 1_000);
+        RunNow::setup(&mut host, &mut world.res);
+        world.write_resource::<Scripts>().load("test", module());
+        host.run_now(&world.res);
+
+        let script = &host.scripts[0];
+        let mut context = ScriptContext::new(&world.res, &[], "test", script.memory.clone(), 1_000);
+        // `spin` loops forever, until the fuel runs out.
+        let out_of_fuel = match script.instance.invoke_export("spin", &[], &mut context) {
+            Err(InterpreterError::Trap(trap)) => match *trap.kind() {
+                TrapKind::Host(ref e) => e.downcast_ref::<OutOfFuel>().is_some(),
+                _ => false,
+            },
+            _ => false,
+        };
+        assert!(out_of_fuel);
+        assert_eq!(context.fuel, 0);
+    }
+}
@@ -0,0 +1,49 @@
+//! Amethyst scripting crate, running gameplay modules compiled to WebAssembly in a sandbox.
+//!
+//! Scripts are loaded through the `Scripts` resource, and run by the `ScriptHost` added by the
+//! `ScriptingBundle`. They are interpreted, and can only touch the components whitelisted by the
+//! game, so mods made by players cannot access the file system, the network or the rest of the
+//! world. Their memory is limited by the bundle, and must declare a maximum, for example with
+//! the `-C link-arg=--max-memory=<bytes>` flag of rustc.
+//!
+//! Scripts are instrumented when loading them, to charge the instructions they run to a fuel
+//! budget set by the bundle. A call into a script which runs out of fuel is aborted, so a script
+//! stuck in a loop does not block the game.
+//!
+//! # ABI
+//!
+//! Scripts may export `init()`, called once after loading, and must export `update(delta: f32)`,
+//! called every frame with the delta seconds of the `Time` resource, and their `memory`.
+//!
+//! The following functions can be imported from the `amethyst` module. Entities are identified
+//! by an `i64` holding their index in the low 32 bits and their generation in the high 32 bits,
+//! so the id of a deleted entity is not valid for the entity reusing its index. Components are
+//! identified by the id returned by `component_id`, and pointers are offsets in the memory of the
+//! script. Functions return -1 for unknown components, dead entities or entities without the
+//! component.
+//!
+//! * `log(ptr: i32, len: i32)` logs an UTF-8 message.
+//! * `component_id(ptr: i32, len: i32) -> i32` returns the id of the component with the given
+//!   UTF-8 name.
+//! * `query(component: i32, ptr: i32, capacity: i32) -> i32` writes the ids of up to `capacity`
+//!   entities having the component as `i64`s, and returns the number of such entities.
+//! * `get(entity: i64, component: i32, ptr: i32, capacity: i32) -> i32` writes up to `capacity`
+//!   fields of the component of the entity as `f32`s, and returns the number of fields.
+//! * `set(entity: i64, component: i32, ptr: i32, len: i32) -> i32` sets the fields of the component
+//!   of the entity from `len` `f32`s, and returns 0, or -1 if the component is read-only or the
+//!   values are invalid.
+//!
+//! Components are exposed to the scripts by implementing `ScriptComponent`.
+
+#![warn(missing_docs, rust_2018_idioms, rust_2018_compatibility)]
+
+pub use wasmi;
+
+pub use self::{
+    bundle::ScriptingBundle, component::ScriptComponent, host::ScriptHost, scripts::Scripts,
+};
+
+mod bundle;
+mod component;
+mod host;
+mod scripts;
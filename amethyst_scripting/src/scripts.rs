@@ -0,0 +1,50 @@
+//! The resource loading and unloading scripts.
+
+use std::{fs, path::Path};
+
+use amethyst_error::{format_err, Error};
+
+/// Resource listing the loaded scripts, used to load and unload them.
+///
+/// Scripts are instantiated by the `ScriptHost` at the beginning of its next run, errors are
+/// logged and the script is not loaded.
+#[derive(Debug, Default)]
+pub struct Scripts {
+    pub(crate) pending: Vec<(String, Vec<u8>)>,
+    pub(crate) unloading: Vec<String>,
+    pub(crate) loaded: Vec<String>,
+}
+
+impl Scripts {
+    /// Loads a compiled WebAssembly module, replacing the script with the same name.
+    pub fn load<N: Into<String>>(&mut self, name: N, bytes: Vec<u8>) {
+        self.pending.push((name.into(), bytes));
+    }
+
+    /// Loads a `.wasm` file, named after the file without its extension.
+    pub fn load_file<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        let name = path
+            .file_stem()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| format_err!("Invalid script path: {}", path.display()))?;
+        let bytes = fs::read(path)?;
+        self.load(name, bytes);
+        Ok(())
+    }
+
+    /// Unloads the script with the given name.
+    pub fn unload(&mut self, name: &str) {
+        self.unloading.push(name.to_string());
+    }
+
+    /// Whether a script with the given name is running.
+    pub fn is_loaded(&self, name: &str) -> bool {
+        self.loaded.iter().any(|loaded| loaded == name)
+    }
+
+    /// The names of the running scripts.
+    pub fn loaded(&self) -> &[String] {
+        &self.loaded
+    }
+}
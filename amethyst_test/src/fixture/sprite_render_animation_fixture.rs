@@ -96,7 +96,13 @@ impl SpriteRenderAnimationFixture {
                 height: 10.0,
                 offsets: [5.; 2],
                 tex_coords: [0.0, 1.0, 0.0, 1.0].into(),
+                rotated: false,
+                page: 0,
+                name: None,
             }],
+            pages: Vec::new(),
+            normal_maps: Vec::new(),
+            sampler: None,
         }
     }
 }
@@ -0,0 +1,380 @@
+//! Auto-tiling of the tile layers of Tiled maps with the Wang sets of their tilesets.
+
+use amethyst_assets::{AssetStorage, Handle};
+use amethyst_core::{
+    specs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, Read, ReadStorage, WriteStorage,
+    },
+    transform::{Parent, Transform},
+};
+use amethyst_error::{format_err, Error};
+use amethyst_renderer::{DrawOrder, Flipped, SpriteAnimation, SpriteRender, SpriteSheet};
+use shred_derive::SystemData;
+
+use crate::{TiledMap, TiledOrientation};
+
+/// Positions of the edges and corners of a tile in the order of `WangId`, in half tiles from the
+/// center of the tile with Y going down.
+const POSITIONS: [[i32; 2]; 8] = [
+    [0, -1],
+    [1, -1],
+    [1, 0],
+    [1, 1],
+    [0, 1],
+    [-1, 1],
+    [-1, 0],
+    [-1, -1],
+];
+
+/// Colors of the edges and corners of a Wang tile, clockwise from the top edge: top, top-right,
+/// right, bottom-right, bottom, bottom-left, left and top-left. The color 0 is no color.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct WangId(pub [u8; 8]);
+
+/// A Wang set of a tileset, the rules choosing the tile matching the colors around a cell.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WangSet {
+    /// Name of the Wang set
+    pub name: String,
+    /// Sprite sheet of the tileset
+    pub sprite_sheet: Handle<SpriteSheet>,
+    /// Sprite numbers of the tiles of the set, with their colors
+    pub tiles: Vec<(usize, WangId)>,
+}
+
+impl WangSet {
+    /// Returns the sprite number of the tile with the most edges and corners of the given colors,
+    /// the first one of the set on ties. `None` if the set has no tiles.
+    pub fn resolve(&self, id: WangId) -> Option<usize> {
+        best_tile(&self.tiles, id)
+    }
+
+    /// Returns the colors of a sprite, `None` if it is not a tile of the set.
+    pub fn wang_id(&self, sprite: &SpriteRender) -> Option<WangId> {
+        if sprite.sprite_sheet != self.sprite_sheet {
+            return None;
+        }
+        self.tiles
+            .iter()
+            .find(|(number, _)| *number == sprite.sprite_number)
+            .map(|(_, id)| *id)
+    }
+}
+
+fn best_tile(tiles: &[(usize, WangId)], id: WangId) -> Option<usize> {
+    tiles
+        .iter()
+        .rev()
+        .max_by_key(|(_, tile)| tile.0.iter().zip(&id.0).filter(|(a, b)| a == b).count())
+        .map(|(number, _)| *number)
+}
+
+/// Wang sets of the tilesets of a Tiled map, placed on the root entity of the map.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TiledWangSets(pub Vec<WangSet>);
+
+impl TiledWangSets {
+    /// Returns the Wang set with the given name.
+    pub fn get(&self, name: &str) -> Option<&WangSet> {
+        self.0.iter().find(|set| set.name == name)
+    }
+}
+
+impl Component for TiledWangSets {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Wang set of a tileset, with the index of the sprite sheet of the tileset.
+#[derive(Clone, Debug)]
+pub(crate) struct WangSetPrefab {
+    pub(crate) name: String,
+    pub(crate) sheet: usize,
+    pub(crate) tiles: Vec<(usize, WangId)>,
+}
+
+/// Tiles of a tile layer of a Tiled map, placed on the entity of the layer.
+///
+/// The tiles are changed at runtime with the `TiledTileEditor`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TiledTiles {
+    width: u32,
+    height: u32,
+    draw_layer: i32,
+    entities: Vec<Option<Entity>>,
+    wang_ids: Vec<WangId>,
+}
+
+impl TiledTiles {
+    /// Returns the entity of a tile, `None` if the cell is empty or outside of the layer.
+    pub fn tile(&self, tile: [i32; 2]) -> Option<Entity> {
+        self.index(tile).and_then(|index| self.entities[index])
+    }
+
+    /// Returns the colors of the edges and corners of a tile, `None` outside of the layer.
+    pub fn wang_id(&self, tile: [i32; 2]) -> Option<WangId> {
+        self.index(tile).map(|index| self.wang_ids[index])
+    }
+
+    fn index(&self, tile: [i32; 2]) -> Option<usize> {
+        let [column, row] = tile;
+        if column < 0 || row < 0 || column >= self.width as i32 || row >= self.height as i32 {
+            return None;
+        }
+        Some(row as usize * self.width as usize + column as usize)
+    }
+
+    /// Paints a color on the edges and corners of a tile and on those of its neighbors touching
+    /// it. Returns the tiles to resolve: the painted one and its neighbors which have a tile.
+    fn paint(&mut self, tile: [i32; 2], color: u8) -> Vec<([i32; 2], WangId)> {
+        let mut painted = Vec::new();
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let neighbor = [tile[0] + dx, tile[1] + dy];
+                let index = match self.index(neighbor) {
+                    Some(index) => index,
+                    None => continue,
+                };
+                let wang_id = &mut self.wang_ids[index];
+                for (position, slot) in POSITIONS.iter().zip(wang_id.0.iter_mut()) {
+                    if (2 * dx + position[0]).abs() <= 1 && (2 * dy + position[1]).abs() <= 1 {
+                        *slot = color;
+                    }
+                }
+                if (dx, dy) == (0, 0) || self.entities[index].is_some() {
+                    painted.push((neighbor, *wang_id));
+                }
+            }
+        }
+        painted
+    }
+}
+
+impl Component for TiledTiles {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Tiles of a tile layer, with the indices of their entities in the prefab.
+#[derive(Clone, Debug)]
+pub(crate) struct TilesPrefab {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) draw_layer: i32,
+    pub(crate) indices: Vec<Option<usize>>,
+    pub(crate) wang_ids: Vec<WangId>,
+}
+
+impl TilesPrefab {
+    pub(crate) fn tiles(&self, entities: &[Entity]) -> TiledTiles {
+        TiledTiles {
+            width: self.width,
+            height: self.height,
+            draw_layer: self.draw_layer,
+            entities: self
+                .indices
+                .iter()
+                .map(|index| index.map(|index| entities[index]))
+                .collect(),
+            wang_ids: self.wang_ids.clone(),
+        }
+    }
+}
+
+/// Changes the tiles of the tile layers of Tiled maps at runtime, such as in level editors.
+///
+/// Painting a color of a Wang set on a tile sets the colors of its edges and corners, and those
+/// of its neighbors touching them, then replaces the tiles with the tiles of the set matching
+/// their colors best. Auto-tiling is meant for orthogonal and isometric maps, where the tiles
+/// share their edges and corners with eight neighbors.
+#[derive(SystemData)]
+pub struct TiledTileEditor<'a> {
+    entities: Entities<'a>,
+    sprite_sheets: Read<'a, AssetStorage<SpriteSheet>>,
+    maps: ReadStorage<'a, TiledMap>,
+    wang_sets: ReadStorage<'a, TiledWangSets>,
+    tiles: WriteStorage<'a, TiledTiles>,
+    parents: WriteStorage<'a, Parent>,
+    transforms: WriteStorage<'a, Transform>,
+    sprite_renders: WriteStorage<'a, SpriteRender>,
+    flips: WriteStorage<'a, Flipped>,
+    animations: WriteStorage<'a, SpriteAnimation>,
+    draw_orders: WriteStorage<'a, DrawOrder>,
+}
+
+impl<'a> TiledTileEditor<'a> {
+    /// Sets the sprite of a tile of a tile layer, or removes the tile with `None`.
+    ///
+    /// The tile takes the colors of its sprite in the Wang sets of the map, or no colors.
+    pub fn set_tile(
+        &mut self,
+        layer: Entity,
+        tile: [i32; 2],
+        sprite: Option<SpriteRender>,
+    ) -> Result<(), Error> {
+        let map = self.map_of(layer, tile)?;
+        let wang_id = sprite
+            .as_ref()
+            .and_then(|sprite| {
+                let sets = self.wang_sets.get(map)?;
+                sets.0.iter().find_map(|set| set.wang_id(sprite))
+            })
+            .unwrap_or_default();
+        let tiles = self.tiles.get_mut(layer).expect("unreachable");
+        let index = tiles.index(tile).expect("unreachable");
+        tiles.wang_ids[index] = wang_id;
+        self.replace(layer, map, tile, sprite)
+    }
+
+    /// Paints a color of a Wang set of the map on a tile, and replaces it and its neighbors with
+    /// the tiles of the set matching their colors.
+    ///
+    /// The empty neighbors keep the painted colors without getting a tile, until they are painted.
+    pub fn paint(
+        &mut self,
+        layer: Entity,
+        tile: [i32; 2],
+        wang_set: &str,
+        color: u8,
+    ) -> Result<(), Error> {
+        let map = self.map_of(layer, tile)?;
+        let set = self
+            .wang_sets
+            .get(map)
+            .and_then(|sets| sets.get(wang_set))
+            .ok_or_else(|| format_err!("Tiled map has no Wang set {}", wang_set))?
+            .clone();
+        let painted = self
+            .tiles
+            .get_mut(layer)
+            .expect("unreachable")
+            .paint(tile, color);
+        for (tile, wang_id) in painted {
+            let sprite = set.resolve(wang_id).map(|sprite_number| SpriteRender {
+                sprite_sheet: set.sprite_sheet.clone(),
+                sprite_number,
+            });
+            self.replace(layer, map, tile, sprite)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the root of the map of a tile layer, checking that the tile is in the layer.
+    fn map_of(&self, layer: Entity, tile: [i32; 2]) -> Result<Entity, Error> {
+        let tiles = self
+            .tiles
+            .get(layer)
+            .ok_or_else(|| format_err!("{:?} is not a tile layer of a Tiled map", layer))?;
+        if tiles.index(tile).is_none() {
+            return Err(format_err!("Tile {:?} is outside of the tile layer", tile));
+        }
+        self.parents
+            .get(layer)
+            .map(|parent| parent.entity)
+            .filter(|map| self.maps.contains(*map))
+            .ok_or_else(|| format_err!("Tile layer {:?} is not a child of a Tiled map", layer))
+    }
+
+    /// Replaces the sprite of a tile, creating or deleting its entity.
+    fn replace(
+        &mut self,
+        layer: Entity,
+        map: Entity,
+        tile: [i32; 2],
+        sprite: Option<SpriteRender>,
+    ) -> Result<(), Error> {
+        let tiles = self.tiles.get_mut(layer).expect("unreachable");
+        let index = tiles.index(tile).expect("unreachable");
+        let sprite = match sprite {
+            Some(sprite) => sprite,
+            None => {
+                if let Some(entity) = tiles.entities[index].take() {
+                    self.entities.delete(entity)?;
+                }
+                return Ok(());
+            }
+        };
+        let size = self
+            .sprite_sheets
+            .get(&sprite.sprite_sheet)
+            .and_then(|sheet| sheet.sprites.get(sprite.sprite_number))
+            .map(|sprite| [sprite.width, sprite.height])
+            .ok_or_else(|| format_err!("Sprite {} of tile is not loaded", sprite.sprite_number))?;
+
+        let entity = match tiles.entities[index] {
+            Some(entity) => entity,
+            None => {
+                let entity = self.entities.create();
+                self.parents.insert(entity, Parent { entity: layer })?;
+                tiles.entities[index] = Some(entity);
+                entity
+            }
+        };
+        let grid = self.maps.get(map).expect("unreachable");
+        let order = match grid.orientation {
+            TiledOrientation::Orthogonal => 0,
+            _ => index as i32,
+        };
+        let center = grid.image_center(tile, size);
+        let mut transform = Transform::default();
+        transform.set_xyz(center[0], center[1], 0.0);
+        self.transforms.insert(entity, transform)?;
+        self.sprite_renders.insert(entity, sprite)?;
+        self.draw_orders
+            .insert(entity, DrawOrder::new(tiles.draw_layer, order))?;
+        self.flips.remove(entity);
+        self.animations.remove(entity);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn best_tile_is_resolved() {
+        let tiles = vec![
+            (0, WangId([0, 1, 0, 1, 0, 1, 0, 1])),
+            (1, WangId([0, 2, 0, 2, 0, 1, 0, 1])),
+            (2, WangId([0, 2, 0, 2, 0, 2, 0, 2])),
+        ];
+        assert_eq!(best_tile(&tiles, WangId([0, 2, 0, 2, 0, 1, 0, 1])), Some(1));
+        assert_eq!(best_tile(&tiles, WangId([0, 2, 0, 2, 0, 2, 0, 2])), Some(2));
+        // Tiles 1 and 2 both have a single different corner.
+        assert_eq!(best_tile(&tiles, WangId([0, 2, 0, 2, 0, 2, 0, 1])), Some(1));
+        assert_eq!(best_tile(&tiles, WangId([0; 8])), Some(0));
+        assert_eq!(best_tile(&[], WangId([0; 8])), None);
+    }
+
+    #[test]
+    fn paint_colors_touching_edges_and_corners() {
+        let mut tiles = TiledTiles {
+            width: 3,
+            height: 2,
+            draw_layer: 0,
+            entities: vec![None; 6],
+            wang_ids: vec![WangId::default(); 6],
+        };
+        let painted = tiles.paint([1, 0], 1);
+        // The neighbors have no tile, only the painted tile is resolved.
+        assert_eq!(painted, vec![([1, 0], WangId([1; 8]))]);
+        assert_eq!(
+            tiles.wang_id([0, 0]),
+            Some(WangId([0, 1, 1, 1, 0, 0, 0, 0]))
+        );
+        assert_eq!(
+            tiles.wang_id([2, 1]),
+            Some(WangId([0, 0, 0, 0, 0, 0, 0, 1]))
+        );
+        assert_eq!(
+            tiles.wang_id([1, 1]),
+            Some(WangId([1, 1, 0, 0, 0, 0, 0, 1]))
+        );
+
+        tiles.paint([2, 1], 2);
+        assert_eq!(
+            tiles.wang_id([1, 0]),
+            Some(WangId([1, 1, 1, 2, 1, 1, 1, 1]))
+        );
+        assert_eq!(tiles.wang_id([2, 2]), None);
+    }
+}
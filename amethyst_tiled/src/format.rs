@@ -0,0 +1,356 @@
+//! Tiled map format
+
+use std::sync::Arc;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use amethyst_assets::{Format, FormatValue, Prefab, Source};
+use amethyst_core::{
+    nalgebra::{Rotation2, Vector2},
+    transform::Transform,
+    Named,
+};
+use amethyst_error::{format_err, Error, ResultExt};
+use amethyst_renderer::{
+    DrawOrder, Flipped, HiddenPropagate, Sprite, SpriteAnimation, TextureFormat, TextureMetadata,
+    TexturePrefab,
+};
+
+use crate::{
+    autotile::{TilesPrefab, WangId, WangSetPrefab},
+    map::{
+        load_map, LayerKind, Map, Object, Shape, Tileset, FLIPPED_DIAGONALLY, FLIPPED_HORIZONTALLY,
+        FLIPPED_VERTICALLY,
+    },
+    TiledLayer, TiledObject, TiledObjectShape, TiledOrientation, TiledPrefab, TilesetPrefab,
+};
+
+/// Tiled map format, loading orthogonal, isometric, staggered and hexagonal maps saved as `.tmx`
+/// files by the Tiled editor.
+///
+/// Using the `TiledMapLoaderSystem` a `Handle<TiledMapAsset>` from this format can be attached
+/// to an entity, which becomes the root of the map:
+///
+/// - The tilesets with a single image, embedded in the map or in `.tsx` files, are loaded as
+///   `SpriteSheet`s with a sprite for each tile. Their images are loaded with the path relative to
+///   the file of the tileset.
+/// - The root has a `TiledMap` with the orientation and size of the map, converting between tile
+///   and world coordinates.
+/// - Each tile layer is a child entity of the root with a `TiledLayer`, and each tile of the layer
+///   is a child entity of the layer with a `SpriteRender`, its `Flipped` state and a `DrawOrder`
+///   keeping the order of the layers.
+/// - Each object layer is a child entity of the root with a `TiledLayer`, and each object is a
+///   child entity of the layer with a `TiledObject` holding its type, size, shape and custom
+///   properties. Tile objects also have a `SpriteRender` scaled to their size.
+/// - Animated tiles and tile objects have a looping `SpriteAnimation` with the frames of their
+///   tile, played by the `SpriteAnimationSystem`. The tiles are loaded at the same time, so all the
+///   tiles with the same animation show the same frame.
+/// - The Wang sets of the tilesets are in a `TiledWangSets` on the root, and each tile layer has
+///   `TiledTiles` with the entities of its tiles. The `TiledTileEditor` changes the tiles at
+///   runtime, resolving the neighbors of the painted tiles with the rules of a Wang set.
+///
+/// The map is on the XY plane with Y going up, its top-left corner at the origin of the root, one
+/// unit per pixel. The tiles of the maps other than orthogonal are drawn in the order of the
+/// layer, row by row, so the tiles in front overlap the ones behind. The objects of isometric
+/// maps are placed on the tiles, with their shape unprojected. Hidden layers and objects have a
+/// `HiddenPropagate`. Diagonally flipped tiles, collections of images and infinite maps are not
+/// supported.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TiledMapFormat;
+
+impl Format<Prefab<TiledPrefab>> for TiledMapFormat {
+    const NAME: &'static str = "TiledMap";
+
+    type Options = ();
+
+    fn import(
+        &self,
+        name: String,
+        source: Arc<dyn Source>,
+        _options: (),
+        _create_reload: bool,
+    ) -> Result<FormatValue<Prefab<TiledPrefab>>, Error> {
+        let map = load_map(&*source, &name)
+            .with_context(|_| format_err!("Failed to import Tiled map {}", name))?;
+        Ok(FormatValue::data(build_prefab(&map)))
+    }
+}
+
+fn build_prefab(map: &Map) -> Prefab<TiledPrefab> {
+    let tilesets: Vec<Option<TilesetPrefab>> = map.tilesets.iter().map(tileset_prefab).collect();
+    // Index of the sprite sheet of each tileset, `None` for the unsupported tilesets.
+    let mut sheet = 0;
+    let sheets: Vec<Option<usize>> = tilesets
+        .iter()
+        .map(|tileset| {
+            tileset.as_ref().map(|_| {
+                sheet += 1;
+                sheet - 1
+            })
+        })
+        .collect();
+    let wang_sets = map
+        .tilesets
+        .iter()
+        .zip(&sheets)
+        .filter_map(|(tileset, sheet)| Some((tileset, (*sheet)?)))
+        .flat_map(|(tileset, sheet)| {
+            tileset.wang_sets.iter().map(move |set| WangSetPrefab {
+                name: set.name.clone(),
+                sheet,
+                tiles: set
+                    .tiles
+                    .iter()
+                    .map(|&(tile_id, wang_id)| (tile_id as usize, wang_id))
+                    .collect(),
+            })
+        })
+        .collect();
+    let mut prefab = Prefab::new_main(TiledPrefab {
+        tilesets: Some(tilesets.iter().flatten().cloned().collect()),
+        wang_set_prefabs: Some(wang_sets),
+        map: Some(map.grid.clone()),
+        ..TiledPrefab::default()
+    });
+
+    for (layer_index, layer) in map.layers.iter().enumerate() {
+        let draw_layer = layer_index as i32;
+        let mut transform = Transform::default();
+        transform.set_xyz(layer.offset[0], -layer.offset[1], 0.0);
+        let layer_entity = prefab.add(
+            Some(0),
+            Some(TiledPrefab {
+                transform: Some(transform),
+                name: Some(Named::new(layer.name.clone())),
+                layer: Some(TiledLayer {
+                    name: layer.name.clone(),
+                    properties: layer.properties.clone(),
+                }),
+                hidden: if layer.visible {
+                    None
+                } else {
+                    Some(HiddenPropagate)
+                },
+                ..TiledPrefab::default()
+            }),
+        );
+
+        match layer.kind {
+            LayerKind::Tiles { width, ref gids } => {
+                let mut tiles = TilesPrefab {
+                    width,
+                    height: gids.len() as u32 / width.max(1),
+                    draw_layer,
+                    indices: vec![None; gids.len()],
+                    wang_ids: vec![WangId::default(); gids.len()],
+                };
+                for (i, &gid) in gids.iter().enumerate() {
+                    let order = match map.grid.orientation {
+                        TiledOrientation::Orthogonal => 0,
+                        _ => i as i32,
+                    };
+                    let (column, row) = (i as u32 % width, i as u32 / width);
+                    let (tileset, tile) = match find_tile(map, &sheets, gid) {
+                        Some(tile) => tile,
+                        None => continue,
+                    };
+                    let center = map.grid.image_center(
+                        [column as i32, row as i32],
+                        [tileset.tile_width as f32, tileset.tile_height as f32],
+                    );
+                    let mut transform = Transform::default();
+                    transform.set_xyz(center[0], center[1], 0.0);
+                    if let Some(wang_id) = tile_wang_id(tileset, tile.1) {
+                        tiles.wang_ids[i] = wang_id;
+                    }
+                    tiles.indices[i] = Some(prefab.add(
+                        Some(layer_entity),
+                        Some(TiledPrefab {
+                            transform: Some(transform),
+                            tile: Some(tile),
+                            flipped: flipped(gid),
+                            animation: tile_animation(tileset, tile.1),
+                            draw_order: Some(DrawOrder::new(draw_layer, order)),
+                            ..TiledPrefab::default()
+                        }),
+                    ));
+                }
+                prefab.data_or_default(layer_entity).tiles = Some(tiles);
+            }
+            LayerKind::Objects(ref objects) => {
+                for (order, object) in objects.iter().enumerate() {
+                    let tile = object.gid.and_then(|gid| find_tile(map, &sheets, gid));
+                    let mut data = TiledPrefab {
+                        transform: Some(object_transform(
+                            object,
+                            map.grid.object_position(object.x, object.y),
+                            tile.map(|(t, _)| t),
+                        )),
+                        name: if object.name.is_empty() {
+                            None
+                        } else {
+                            Some(Named::new(object.name.clone()))
+                        },
+                        object: Some(tiled_object(object)),
+                        hidden: if object.visible {
+                            None
+                        } else {
+                            Some(HiddenPropagate)
+                        },
+                        ..TiledPrefab::default()
+                    };
+                    if let (Some(gid), Some((tileset, tile))) = (object.gid, tile) {
+                        data.tile = Some(tile);
+                        data.flipped = flipped(gid);
+                        data.animation = tile_animation(tileset, tile.1);
+                        data.draw_order = Some(DrawOrder::new(draw_layer, order as i32));
+                    }
+                    prefab.add(Some(layer_entity), Some(data));
+                }
+            }
+        }
+    }
+    prefab
+}
+
+/// Creates the texture and sprites of a tileset, `None` if it is not a single image.
+fn tileset_prefab(tileset: &Tileset) -> Option<TilesetPrefab> {
+    let image = match tileset.image {
+        Some(ref image) => image,
+        None => {
+            warn!(
+                "Tileset {} is a collection of images, which is not supported",
+                tileset.name
+            );
+            return None;
+        }
+    };
+    let texture_format = match image.source.rsplit('.').next() {
+        Some("jpg") | Some("jpeg") => TextureFormat::Jpg,
+        Some("bmp") => TextureFormat::Bmp,
+        Some("tga") => TextureFormat::Tga,
+        _ => TextureFormat::Png,
+    };
+    let sprites = (0..tileset.tile_count)
+        .map(|tile| {
+            let (column, row) = (tile % tileset.columns, tile / tileset.columns);
+            Sprite::from_pixel_values(
+                image.width,
+                image.height,
+                tileset.tile_width,
+                tileset.tile_height,
+                tileset.margin + column * (tileset.tile_width + tileset.spacing),
+                tileset.margin + row * (tileset.tile_height + tileset.spacing),
+                [0.0; 2],
+            )
+        })
+        .collect();
+    Some(TilesetPrefab {
+        texture: TexturePrefab::File(
+            image.source.clone(),
+            texture_format,
+            TextureMetadata::srgb_scale(),
+        ),
+        sprites,
+    })
+}
+
+/// Finds the tileset of a global tile id, with the index of its sprite sheet and of the sprite.
+fn find_tile<'a>(
+    map: &'a Map,
+    sheets: &[Option<usize>],
+    gid: u32,
+) -> Option<(&'a Tileset, (usize, usize))> {
+    let id = gid & !(FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY);
+    if id == 0 {
+        return None;
+    }
+    let index = map.tilesets.iter().rposition(|t| t.first_gid <= id)?;
+    let tileset = &map.tilesets[index];
+    let sheet = sheets[index]?;
+    let sprite = id - tileset.first_gid;
+    if sprite >= tileset.tile_count {
+        warn!("Tile id {} is not in tileset {}", id, tileset.name);
+        return None;
+    }
+    Some((tileset, (sheet, sprite as usize)))
+}
+
+/// Creates the animation of a tile from its frames, `None` if it is not animated.
+fn tile_animation(tileset: &Tileset, sprite: usize) -> Option<SpriteAnimation> {
+    let frames = tileset.animations.get(&(sprite as u32))?;
+    let sprites = frames.iter().map(|f| f.tile_id as usize).collect();
+    let durations = frames.iter().map(|f| f.duration as f32 / 1000.0).collect();
+    Some(SpriteAnimation::new(sprites, 0.0).with_durations(durations))
+}
+
+/// Returns the colors of a tile in the first Wang set of its tileset containing it.
+fn tile_wang_id(tileset: &Tileset, sprite: usize) -> Option<WangId> {
+    tileset.wang_sets.iter().find_map(|set| {
+        set.tiles
+            .iter()
+            .find(|(tile_id, _)| *tile_id as usize == sprite)
+            .map(|(_, wang_id)| *wang_id)
+    })
+}
+
+fn flipped(gid: u32) -> Option<Flipped> {
+    match (
+        gid & FLIPPED_HORIZONTALLY != 0,
+        gid & FLIPPED_VERTICALLY != 0,
+    ) {
+        (true, true) => Some(Flipped::Both),
+        (true, false) => Some(Flipped::Horizontal),
+        (false, true) => Some(Flipped::Vertical),
+        (false, false) => None,
+    }
+}
+
+/// Creates the transform of an object at the given position in pixels, at the center of its
+/// rectangle.
+///
+/// Objects are rotated around their position, the top-left corner of their rectangle or the
+/// bottom-left corner for tile objects, which are scaled to their size.
+fn object_transform(object: &Object, position: [f32; 2], tileset: Option<&Tileset>) -> Transform {
+    let angle = -object.rotation.to_radians();
+    let half_size = match tileset {
+        Some(_) => Vector2::new(object.width / 2.0, object.height / 2.0),
+        None => Vector2::new(object.width / 2.0, -object.height / 2.0),
+    };
+    let center = Vector2::new(position[0], -position[1]) + Rotation2::new(angle) * half_size;
+
+    let mut transform = Transform::default();
+    transform.set_xyz(center.x, center.y, 0.0);
+    transform.set_rotation_euler(0.0, 0.0, angle);
+    if let Some(tileset) = tileset {
+        transform.set_scale(
+            object.width / tileset.tile_width as f32,
+            object.height / tileset.tile_height as f32,
+            1.0,
+        );
+    }
+    transform
+}
+
+fn tiled_object(object: &Object) -> TiledObject {
+    // Points are relative to the position of the object, which is the center of objects with
+    // points as they have no size.
+    let points =
+        |points: &[[f32; 2]]| -> Vec<[f32; 2]> { points.iter().map(|p| [p[0], -p[1]]).collect() };
+    TiledObject {
+        id: object.id,
+        name: object.name.clone(),
+        object_type: object.object_type.clone(),
+        width: object.width,
+        height: object.height,
+        shape: match object.shape {
+            Shape::Rectangle => TiledObjectShape::Rectangle,
+            Shape::Ellipse => TiledObjectShape::Ellipse,
+            Shape::Point => TiledObjectShape::Point,
+            Shape::Polygon(ref p) => TiledObjectShape::Polygon(points(p)),
+            Shape::Polyline(ref p) => TiledObjectShape::Polyline(points(p)),
+        },
+        properties: object.properties.clone(),
+    }
+}
@@ -0,0 +1,322 @@
+//! A crate for loading Tiled maps into Amethyst
+//!
+//! Maps saved by the [Tiled](https://www.mapeditor.org/) editor are loaded with the
+//! `TiledMapFormat` as prefabs, see its documentation for the entities of a map.
+
+#![warn(missing_docs, rust_2018_idioms, rust_2018_compatibility)]
+
+pub use crate::{
+    autotile::{TiledTileEditor, TiledTiles, TiledWangSets, WangId, WangSet},
+    format::TiledMapFormat,
+    orientation::{StaggerAxis, StaggerIndex, TiledMap, TiledOrientation},
+    picking::{TilePick, TiledPicker},
+};
+
+use std::collections::HashMap;
+
+use amethyst_assets::{
+    AssetStorage, Handle, Prefab, PrefabData, PrefabLoaderSystem, ProgressCounter,
+};
+use amethyst_core::{
+    specs::prelude::{Component, DenseVecStorage, Entity, Read, Write, WriteStorage},
+    transform::Transform,
+    Named,
+};
+use amethyst_error::{format_err, Error};
+use amethyst_renderer::{
+    DrawOrder, Flipped, HiddenPropagate, Sprite, SpriteAnimation, SpriteRender, SpriteSheet,
+    TextureFormat, TexturePrefab,
+};
+
+use crate::autotile::{TilesPrefab, WangSet, WangSetPrefab};
+
+mod autotile;
+mod format;
+mod map;
+mod orientation;
+mod picking;
+
+/// Load `TiledMapAsset`s
+pub type TiledMapLoaderSystem = PrefabLoaderSystem<TiledPrefab>;
+
+/// Tiled map asset as returned by the `TiledMapFormat`
+pub type TiledMapAsset = Prefab<TiledPrefab>;
+
+/// A tile or object layer of a Tiled map, placed on the parent entity of its tiles or objects.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TiledLayer {
+    /// Name of the layer
+    pub name: String,
+    /// Custom properties of the layer, with their values as written in the map
+    pub properties: HashMap<String, String>,
+}
+
+impl Component for TiledLayer {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Shape of a `TiledObject`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TiledObjectShape {
+    /// Rectangle of the size of the object
+    Rectangle,
+    /// Ellipse fitting in the size of the object
+    Ellipse,
+    /// Point without size
+    Point,
+    /// Closed polygon, with the points relative to the entity with Y going up
+    Polygon(Vec<[f32; 2]>),
+    /// Open line, with the points relative to the entity with Y going up
+    Polyline(Vec<[f32; 2]>),
+}
+
+/// An object of an object layer of a Tiled map.
+///
+/// The `Transform` of the entity is at the center of the object, and has its rotation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TiledObject {
+    /// Unique id of the object in the map
+    pub id: u32,
+    /// Name of the object
+    pub name: String,
+    /// Type of the object, empty if it has none
+    pub object_type: String,
+    /// Width of the object in pixels
+    pub width: f32,
+    /// Height of the object in pixels
+    pub height: f32,
+    /// Shape of the object
+    pub shape: TiledObjectShape,
+    /// Custom properties of the object, with their values as written in the map
+    pub properties: HashMap<String, String>,
+}
+
+impl TiledObject {
+    /// Returns whether the shape of the object contains a point, relative to the entity of the
+    /// object with Y going up and without its rotation. Points and polylines contain no point.
+    pub fn contains(&self, point: [f32; 2]) -> bool {
+        let [x, y] = point;
+        let (half_width, half_height) = (self.width / 2.0, self.height / 2.0);
+        match self.shape {
+            TiledObjectShape::Rectangle => x.abs() <= half_width && y.abs() <= half_height,
+            TiledObjectShape::Ellipse => {
+                half_width > 0.0
+                    && half_height > 0.0
+                    && (x / half_width).powi(2) + (y / half_height).powi(2) <= 1.0
+            }
+            TiledObjectShape::Polygon(ref points) => {
+                // Even-odd rule: the point is inside if a ray from it crosses an odd number of
+                // edges.
+                let edges = points.iter().zip(points.iter().cycle().skip(1));
+                edges
+                    .filter(|(a, b)| (a[1] > y) != (b[1] > y))
+                    .filter(|(a, b)| x < a[0] + (y - a[1]) / (b[1] - a[1]) * (b[0] - a[0]))
+                    .count()
+                    % 2
+                    == 1
+            }
+            TiledObjectShape::Point | TiledObjectShape::Polyline(_) => false,
+        }
+    }
+}
+
+impl Component for TiledObject {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Texture and sprites of a tileset, loaded as a `SpriteSheet`.
+#[derive(Clone, Debug)]
+pub(crate) struct TilesetPrefab {
+    pub(crate) texture: TexturePrefab<TextureFormat>,
+    pub(crate) sprites: Vec<Sprite>,
+}
+
+/// Used during Tiled map loading to contain the sprite sheets of the tilesets of the map
+#[derive(Default, Clone, Debug)]
+pub struct TiledSpriteSheets {
+    pub(crate) sprite_sheets: Vec<Handle<SpriteSheet>>,
+}
+
+/// `PrefabData` for loading Tiled maps.
+#[derive(Debug, Clone, Default)]
+pub struct TiledPrefab {
+    /// `Transform` of the layers, tiles and objects
+    pub transform: Option<Transform>,
+    /// Name of the layers and named objects
+    pub name: Option<Named>,
+    /// Sprite of the tiles and tile objects after sub asset loading is done
+    pub sprite_render: Option<SpriteRender>,
+    /// Flip of the flipped tiles
+    pub flipped: Option<Flipped>,
+    /// Frames of the animated tiles and tile objects
+    pub animation: Option<SpriteAnimation>,
+    /// Draw order of the tiles and tile objects, by layer of the map
+    pub draw_order: Option<DrawOrder>,
+    /// Tile or object layer
+    pub layer: Option<TiledLayer>,
+    /// Object of an object layer
+    pub object: Option<TiledObject>,
+    /// Hides the hidden layers and objects
+    pub hidden: Option<HiddenPropagate>,
+    /// Orientation and size of the map, on its root
+    pub map: Option<TiledMap>,
+    /// Wang sets of the tilesets, on the root after sub asset loading is done
+    pub wang_sets: Option<TiledWangSets>,
+    pub(crate) tilesets: Option<Vec<TilesetPrefab>>,
+    pub(crate) wang_set_prefabs: Option<Vec<WangSetPrefab>>,
+    /// Index of the sprite sheet and of the sprite of tiles and tile objects.
+    pub(crate) tile: Option<(usize, usize)>,
+    /// Tiles of a tile layer, with the indices of their entities in the prefab.
+    pub(crate) tiles: Option<TilesPrefab>,
+}
+
+impl<'a> PrefabData<'a> for TiledPrefab {
+    type SystemData = (
+        <Transform as PrefabData<'a>>::SystemData,
+        <Named as PrefabData<'a>>::SystemData,
+        <TexturePrefab<TextureFormat> as PrefabData<'a>>::SystemData,
+        Read<'a, AssetStorage<SpriteSheet>>,
+        WriteStorage<'a, SpriteRender>,
+        WriteStorage<'a, Flipped>,
+        WriteStorage<'a, SpriteAnimation>,
+        WriteStorage<'a, DrawOrder>,
+        WriteStorage<'a, TiledLayer>,
+        WriteStorage<'a, TiledObject>,
+        WriteStorage<'a, HiddenPropagate>,
+        WriteStorage<'a, TiledMap>,
+        WriteStorage<'a, TiledWangSets>,
+        WriteStorage<'a, TiledTiles>,
+        Write<'a, TiledSpriteSheets>,
+    );
+    type Result = ();
+
+    fn add_to_entity(
+        &self,
+        entity: Entity,
+        system_data: &mut Self::SystemData,
+        entities: &[Entity],
+    ) -> Result<(), Error> {
+        let (
+            ref mut transforms,
+            ref mut names,
+            _,
+            _,
+            ref mut sprite_renders,
+            ref mut flips,
+            ref mut animations,
+            ref mut draw_orders,
+            ref mut layers,
+            ref mut objects,
+            ref mut hiddens,
+            ref mut maps,
+            ref mut wang_sets,
+            ref mut tiles,
+            _,
+        ) = system_data;
+        if let Some(ref transform) = self.transform {
+            transform.add_to_entity(entity, transforms, entities)?;
+        }
+        if let Some(ref name) = self.name {
+            name.add_to_entity(entity, names, entities)?;
+        }
+        if let Some(ref sprite_render) = self.sprite_render {
+            sprite_renders.insert(entity, sprite_render.clone())?;
+        }
+        if let Some(flipped) = self.flipped {
+            flips.insert(entity, flipped)?;
+        }
+        if let Some(ref animation) = self.animation {
+            animations.insert(entity, animation.clone())?;
+        }
+        if let Some(draw_order) = self.draw_order {
+            draw_orders.insert(entity, draw_order)?;
+        }
+        if let Some(ref layer) = self.layer {
+            layers.insert(entity, layer.clone())?;
+        }
+        if let Some(ref object) = self.object {
+            objects.insert(entity, object.clone())?;
+        }
+        if let Some(ref hidden) = self.hidden {
+            hiddens.insert(entity, hidden.clone())?;
+        }
+        if let Some(ref map) = self.map {
+            maps.insert(entity, map.clone())?;
+        }
+        if let Some(ref sets) = self.wang_sets {
+            wang_sets.insert(entity, sets.clone())?;
+        }
+        if let Some(ref layer_tiles) = self.tiles {
+            tiles.insert(entity, layer_tiles.tiles(entities))?;
+        }
+        Ok(())
+    }
+
+    fn load_sub_assets(
+        &mut self,
+        progress: &mut ProgressCounter,
+        system_data: &mut Self::SystemData,
+    ) -> Result<bool, Error> {
+        let (
+            _,
+            _,
+            ref mut textures,
+            ref sprite_sheet_storage,
+            _,
+            _,
+            _,
+            _,
+            _,
+            _,
+            _,
+            _,
+            _,
+            _,
+            ref mut sheets,
+        ) = system_data;
+        let mut ret = false;
+        if let Some(ref mut tilesets) = self.tilesets {
+            sheets.sprite_sheets.clear();
+            for tileset in tilesets.iter_mut() {
+                tileset.texture.load_sub_assets(progress, textures)?;
+                let texture = match tileset.texture {
+                    TexturePrefab::Handle(ref handle) => handle.clone(),
+                    _ => return Err(format_err!("Tileset texture was not loaded")),
+                };
+                let sprite_sheet = SpriteSheet {
+                    texture,
+                    sprites: tileset.sprites.clone(),
+                    pages: Vec::new(),
+                    normal_maps: Vec::new(),
+                    sampler: None,
+                };
+                sheets.sprite_sheets.push(textures.0.load_from_data(
+                    sprite_sheet,
+                    &mut *progress,
+                    sprite_sheet_storage,
+                ));
+                ret = true;
+            }
+        }
+        if let Some(ref prefabs) = self.wang_set_prefabs {
+            let sets = prefabs.iter().filter_map(|set| {
+                Some(WangSet {
+                    name: set.name.clone(),
+                    sprite_sheet: sheets.sprite_sheets.get(set.sheet)?.clone(),
+                    tiles: set.tiles.clone(),
+                })
+            });
+            self.wang_sets = Some(TiledWangSets(sets.collect()));
+        }
+        if let Some((sheet, sprite_number)) = self.tile {
+            self.sprite_render = sheets
+                .sprite_sheets
+                .get(sheet)
+                .map(|sprite_sheet| SpriteRender {
+                    sprite_sheet: sprite_sheet.clone(),
+                    sprite_number,
+                });
+        }
+        Ok(ret)
+    }
+}
@@ -0,0 +1,707 @@
+//! Parsing of the XML files of Tiled.
+
+use std::{collections::HashMap, io::Read, str::FromStr};
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+use log::warn;
+use xmltree::Element;
+
+use amethyst_assets::Source;
+use amethyst_error::{format_err, Error, ResultExt};
+
+use crate::{
+    autotile::WangId,
+    orientation::{StaggerAxis, StaggerIndex, TiledMap, TiledOrientation},
+};
+
+/// Flag of the global tile ids of horizontally flipped tiles.
+pub(crate) const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+/// Flag of the global tile ids of vertically flipped tiles.
+pub(crate) const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+/// Flag of the global tile ids of diagonally flipped tiles.
+pub(crate) const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+
+/// A Tiled map, from a `.tmx` file.
+#[derive(Clone, Debug)]
+pub(crate) struct Map {
+    pub grid: TiledMap,
+    pub tilesets: Vec<Tileset>,
+    pub layers: Vec<Layer>,
+}
+
+/// A tileset of a map, embedded in the map or from a `.tsx` file.
+#[derive(Clone, Debug)]
+pub(crate) struct Tileset {
+    pub first_gid: u32,
+    pub name: String,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub spacing: u32,
+    pub margin: u32,
+    pub tile_count: u32,
+    pub columns: u32,
+    /// Image of the tiles, `None` for collections of images.
+    pub image: Option<Image>,
+    /// Frames of the animated tiles, by tile id.
+    pub animations: HashMap<u32, Vec<Frame>>,
+    pub wang_sets: Vec<WangRules>,
+}
+
+/// A Wang set of a tileset, with the colors of the edges and corners of its tiles.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct WangRules {
+    pub name: String,
+    /// Tile ids in the tileset and their colors.
+    pub tiles: Vec<(u32, WangId)>,
+}
+
+/// A frame of the animation of a tile.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Frame {
+    /// Id of the tile shown, in the tileset of the animated tile.
+    pub tile_id: u32,
+    /// Duration of the frame in milliseconds.
+    pub duration: u32,
+}
+
+/// An image of a tileset.
+#[derive(Clone, Debug)]
+pub(crate) struct Image {
+    /// Path of the image, relative to the asset directory.
+    pub source: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A tile or object layer, with the offset and visibility of its groups applied.
+#[derive(Clone, Debug)]
+pub(crate) struct Layer {
+    pub name: String,
+    pub offset: [f32; 2],
+    pub visible: bool,
+    pub properties: HashMap<String, String>,
+    pub kind: LayerKind,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum LayerKind {
+    /// Global tile ids of the tiles of the layer, row by row from the top-left corner.
+    Tiles {
+        width: u32,
+        gids: Vec<u32>,
+    },
+    Objects(Vec<Object>),
+}
+
+/// An object of an object layer, with its position in pixels from the top-left corner.
+#[derive(Clone, Debug)]
+pub(crate) struct Object {
+    pub id: u32,
+    pub name: String,
+    pub object_type: String,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// Clockwise rotation in degrees.
+    pub rotation: f32,
+    pub gid: Option<u32>,
+    pub visible: bool,
+    pub shape: Shape,
+    pub properties: HashMap<String, String>,
+}
+
+/// Shape of an object, with the points in pixels from the position of the object.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Shape {
+    Rectangle,
+    Ellipse,
+    Point,
+    Polygon(Vec<[f32; 2]>),
+    Polyline(Vec<[f32; 2]>),
+}
+
+/// Loads the map at `path` from the source, with its external tilesets.
+pub(crate) fn load_map(source: &dyn Source, path: &str) -> Result<Map, Error> {
+    let bytes = source.load(path)?;
+    let root = Element::parse(&bytes[..])
+        .with_context(|_| format_err!("Failed to parse Tiled map {}", path))?;
+    parse_map(&root, path, &mut |tileset_path| {
+        let bytes = source.load(tileset_path)?;
+        Element::parse(&bytes[..])
+            .with_context(|_| format_err!("Failed to parse Tiled tileset {}", tileset_path))
+    })
+}
+
+/// Parses the root element of a map, loading its external tilesets with `load_tileset`.
+fn parse_map<F>(root: &Element, path: &str, load_tileset: &mut F) -> Result<Map, Error>
+where
+    F: FnMut(&str) -> Result<Element, Error>,
+{
+    if root.name != "map" {
+        return Err(format_err!("{} is not a Tiled map", path));
+    }
+    let orientation = match attr::<String>(root, "orientation")?.as_str() {
+        "orthogonal" => TiledOrientation::Orthogonal,
+        "isometric" => TiledOrientation::Isometric,
+        "staggered" => TiledOrientation::Staggered {
+            axis: stagger_axis(root)?,
+            index: stagger_index(root)?,
+        },
+        "hexagonal" => TiledOrientation::Hexagonal {
+            axis: stagger_axis(root)?,
+            index: stagger_index(root)?,
+            side_length: attr(root, "hexsidelength")?,
+        },
+        orientation => {
+            return Err(format_err!(
+                "Orientation {} of Tiled map {} is not supported",
+                orientation,
+                path
+            ));
+        }
+    };
+    if attr_or(root, "infinite", 0)? != 0 {
+        return Err(format_err!("Infinite Tiled maps are not supported"));
+    }
+
+    let mut tilesets = Vec::new();
+    for element in root.children.iter().filter(|c| c.name == "tileset") {
+        let first_gid = attr(element, "firstgid")?;
+        let tileset = match element.attributes.get("source") {
+            Some(source) => {
+                let tileset_path = resolve_path(path, source);
+                let external = load_tileset(&tileset_path)?;
+                parse_tileset(&external, first_gid, &tileset_path)?
+            }
+            None => parse_tileset(element, first_gid, path)?,
+        };
+        tilesets.push(tileset);
+    }
+
+    let mut layers = Vec::new();
+    parse_layers(root, [0.0, 0.0], true, &mut layers)?;
+
+    Ok(Map {
+        grid: TiledMap {
+            orientation,
+            width: attr(root, "width")?,
+            height: attr(root, "height")?,
+            tile_width: attr(root, "tilewidth")?,
+            tile_height: attr(root, "tileheight")?,
+        },
+        tilesets,
+        layers,
+    })
+}
+
+fn stagger_axis(root: &Element) -> Result<StaggerAxis, Error> {
+    match attr::<String>(root, "staggeraxis")?.as_str() {
+        "x" => Ok(StaggerAxis::X),
+        "y" => Ok(StaggerAxis::Y),
+        axis => Err(format_err!("Invalid stagger axis {}", axis)),
+    }
+}
+
+fn stagger_index(root: &Element) -> Result<StaggerIndex, Error> {
+    match attr::<String>(root, "staggerindex")?.as_str() {
+        "odd" => Ok(StaggerIndex::Odd),
+        "even" => Ok(StaggerIndex::Even),
+        index => Err(format_err!("Invalid stagger index {}", index)),
+    }
+}
+
+/// Parses a tileset element, resolving the path of its image relative to the file at `path`.
+fn parse_tileset(element: &Element, first_gid: u32, path: &str) -> Result<Tileset, Error> {
+    let tile_width = attr(element, "tilewidth")?;
+    let tile_height = attr(element, "tileheight")?;
+    let spacing = attr_or(element, "spacing", 0)?;
+    let margin = attr_or(element, "margin", 0)?;
+    let image = match element.get_child("image") {
+        Some(image) => Some(Image {
+            source: resolve_path(path, &attr::<String>(image, "source")?),
+            width: attr(image, "width")?,
+            height: attr(image, "height")?,
+        }),
+        None => None,
+    };
+
+    // Older versions of Tiled don't save the number of columns and tiles.
+    let (columns, rows) = match image {
+        Some(ref image) => (
+            (image.width.saturating_sub(2 * margin) + spacing) / (tile_width + spacing),
+            (image.height.saturating_sub(2 * margin) + spacing) / (tile_height + spacing),
+        ),
+        None => (0, 0),
+    };
+    let columns = attr_or(element, "columns", columns)?;
+    let tile_count = attr_or(element, "tilecount", columns * rows)?;
+
+    let mut animations = HashMap::new();
+    for tile in element.children.iter().filter(|c| c.name == "tile") {
+        if let Some(animation) = tile.get_child("animation") {
+            let frames = animation
+                .children
+                .iter()
+                .filter(|c| c.name == "frame")
+                .map(|frame| {
+                    Ok(Frame {
+                        tile_id: attr(frame, "tileid")?,
+                        duration: attr(frame, "duration")?,
+                    })
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            if !frames.is_empty() {
+                animations.insert(attr(tile, "id")?, frames);
+            }
+        }
+    }
+
+    let mut wang_sets = Vec::new();
+    if let Some(element) = element.get_child("wangsets") {
+        for wang_set in element.children.iter().filter(|c| c.name == "wangset") {
+            let tiles = wang_set
+                .children
+                .iter()
+                .filter(|c| c.name == "wangtile")
+                .map(|tile| {
+                    let wang_id = parse_wang_id(&attr::<String>(tile, "wangid")?)?;
+                    Ok((attr(tile, "tileid")?, wang_id))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            wang_sets.push(WangRules {
+                name: attr_or(wang_set, "name", String::new())?,
+                tiles,
+            });
+        }
+    }
+
+    Ok(Tileset {
+        first_gid,
+        name: attr_or(element, "name", String::new())?,
+        tile_width,
+        tile_height,
+        spacing,
+        margin,
+        tile_count,
+        columns,
+        image,
+        animations,
+        wang_sets,
+    })
+}
+
+/// Parses the colors of a Wang tile, saved as a hexadecimal number with a digit per edge and
+/// corner by Tiled 1.4 and earlier, such as `0x10101010`, or as a list such as `0,1,0,1,0,1,0,1`.
+fn parse_wang_id(id: &str) -> Result<WangId, Error> {
+    let mut colors = [0; 8];
+    if id.starts_with("0x") {
+        let id = u32::from_str_radix(&id[2..], 16)
+            .with_context(|_| format_err!("Invalid Wang tile id {}", id))?;
+        for (i, color) in colors.iter_mut().enumerate() {
+            *color = ((id >> (i * 4)) & 0xf) as u8;
+        }
+    } else {
+        let values = id
+            .split(',')
+            .map(|color| color.trim().parse::<u8>())
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|_| format_err!("Invalid Wang tile id {}", id))?;
+        if values.len() != 8 {
+            return Err(format_err!("Invalid Wang tile id {}", id));
+        }
+        colors.copy_from_slice(&values);
+    }
+    Ok(WangId(colors))
+}
+
+/// Parses the layers of a map or group, flattening the groups into their layers.
+fn parse_layers(
+    parent: &Element,
+    offset: [f32; 2],
+    visible: bool,
+    layers: &mut Vec<Layer>,
+) -> Result<(), Error> {
+    for element in &parent.children {
+        let name = attr_or(element, "name", String::new())?;
+        let offset = [
+            offset[0] + attr_or(element, "offsetx", 0.0)?,
+            offset[1] + attr_or(element, "offsety", 0.0)?,
+        ];
+        let visible = visible && attr_or(element, "visible", 1)? != 0;
+        let kind = match element.name.as_str() {
+            "layer" => {
+                let width = attr(element, "width")?;
+                let height: u32 = attr(element, "height")?;
+                let data = element
+                    .get_child("data")
+                    .ok_or_else(|| format_err!("Tile layer {} has no data", name))?;
+                if data.get_child("chunk").is_some() {
+                    return Err(format_err!("Infinite Tiled maps are not supported"));
+                }
+                let gids = parse_data(data)
+                    .with_context(|_| format_err!("Failed to parse tile layer {}", name))?;
+                if gids.len() != (width * height) as usize {
+                    return Err(format_err!(
+                        "Tile layer {} has {} tiles instead of {}",
+                        name,
+                        gids.len(),
+                        width * height
+                    ));
+                }
+                LayerKind::Tiles { width, gids }
+            }
+            "objectgroup" => LayerKind::Objects(
+                element
+                    .children
+                    .iter()
+                    .filter(|c| c.name == "object")
+                    .map(parse_object)
+                    .collect::<Result<_, Error>>()?,
+            ),
+            "group" => {
+                parse_layers(element, offset, visible, layers)?;
+                continue;
+            }
+            "imagelayer" => {
+                warn!("Image layer {} of Tiled map is not supported", name);
+                continue;
+            }
+            _ => continue,
+        };
+        layers.push(Layer {
+            name,
+            offset,
+            visible,
+            properties: parse_properties(element)?,
+            kind,
+        });
+    }
+    Ok(())
+}
+
+/// Parses the global tile ids of a data element, in the XML, CSV or base64 encoding.
+fn parse_data(data: &Element) -> Result<Vec<u32>, Error> {
+    let text = data.text.as_ref().map(String::as_str).unwrap_or("");
+    match data.attributes.get("encoding").map(String::as_str) {
+        None => data
+            .children
+            .iter()
+            .filter(|c| c.name == "tile")
+            .map(|tile| attr_or(tile, "gid", 0))
+            .collect(),
+        Some("csv") => text
+            .split(',')
+            .map(|gid| {
+                gid.trim()
+                    .parse::<u32>()
+                    .with_context(|_| format_err!("Invalid tile id {}", gid.trim()))
+            })
+            .collect(),
+        Some("base64") => {
+            let text: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+            let bytes =
+                base64::decode(&text).with_context(|_| format_err!("Invalid base64 tile data"))?;
+            let bytes = match data.attributes.get("compression").map(String::as_str) {
+                None => bytes,
+                Some("zlib") => decompress(ZlibDecoder::new(&bytes[..]))?,
+                Some("gzip") => decompress(GzDecoder::new(&bytes[..]))?,
+                Some(compression) => {
+                    return Err(format_err!(
+                        "Unsupported tile data compression {}",
+                        compression
+                    ));
+                }
+            };
+            if bytes.len() % 4 != 0 {
+                return Err(format_err!("Tile data is not a list of 32 bits tile ids"));
+            }
+            Ok(bytes
+                .chunks(4)
+                .map(|b| {
+                    u32::from(b[0])
+                        | u32::from(b[1]) << 8
+                        | u32::from(b[2]) << 16
+                        | u32::from(b[3]) << 24
+                })
+                .collect())
+        }
+        Some(encoding) => Err(format_err!("Unsupported tile data encoding {}", encoding)),
+    }
+}
+
+fn decompress<R: Read>(mut reader: R) -> Result<Vec<u8>, Error> {
+    let mut bytes = Vec::new();
+    reader
+        .read_to_end(&mut bytes)
+        .with_context(|_| format_err!("Failed to decompress tile data"))?;
+    Ok(bytes)
+}
+
+fn parse_object(element: &Element) -> Result<Object, Error> {
+    let shape = if element.get_child("ellipse").is_some() {
+        Shape::Ellipse
+    } else if element.get_child("point").is_some() {
+        Shape::Point
+    } else if let Some(polygon) = element.get_child("polygon") {
+        Shape::Polygon(parse_points(&attr::<String>(polygon, "points")?)?)
+    } else if let Some(polyline) = element.get_child("polyline") {
+        Shape::Polyline(parse_points(&attr::<String>(polyline, "points")?)?)
+    } else {
+        Shape::Rectangle
+    };
+    Ok(Object {
+        id: attr_or(element, "id", 0)?,
+        name: attr_or(element, "name", String::new())?,
+        object_type: attr_or(element, "type", String::new())?,
+        x: attr(element, "x")?,
+        y: attr(element, "y")?,
+        width: attr_or(element, "width", 0.0)?,
+        height: attr_or(element, "height", 0.0)?,
+        rotation: attr_or(element, "rotation", 0.0)?,
+        gid: match element.attributes.get("gid") {
+            Some(_) => Some(attr(element, "gid")?),
+            None => None,
+        },
+        visible: attr_or(element, "visible", 1)? != 0,
+        shape,
+        properties: parse_properties(element)?,
+    })
+}
+
+/// Parses the points of a polygon or polyline, such as `0,0 32,0 32,-8`.
+fn parse_points(points: &str) -> Result<Vec<[f32; 2]>, Error> {
+    points
+        .split_whitespace()
+        .map(|point| {
+            let mut coordinates = point.split(',').map(f32::from_str);
+            match (coordinates.next(), coordinates.next(), coordinates.next()) {
+                (Some(Ok(x)), Some(Ok(y)), None) => Ok([x, y]),
+                _ => Err(format_err!("Invalid point {}", point)),
+            }
+        })
+        .collect()
+}
+
+/// Parses the custom properties of an element, with their values as written in the file.
+fn parse_properties(element: &Element) -> Result<HashMap<String, String>, Error> {
+    let mut properties = HashMap::new();
+    if let Some(element) = element.get_child("properties") {
+        for property in element.children.iter().filter(|c| c.name == "property") {
+            // Multi-line strings are saved as the text of the property.
+            let value = match property.attributes.get("value") {
+                Some(value) => value.clone(),
+                None => property.text.clone().unwrap_or_default(),
+            };
+            properties.insert(attr(property, "name")?, value);
+        }
+    }
+    Ok(properties)
+}
+
+fn attr<T: FromStr>(element: &Element, name: &str) -> Result<T, Error> {
+    let value = element
+        .attributes
+        .get(name)
+        .ok_or_else(|| format_err!("Missing attribute {} of {}", name, element.name))?;
+    value.parse().map_err(|_| {
+        format_err!(
+            "Invalid attribute {}=\"{}\" of {}",
+            name,
+            value,
+            element.name
+        )
+    })
+}
+
+fn attr_or<T: FromStr>(element: &Element, name: &str, default: T) -> Result<T, Error> {
+    if element.attributes.contains_key(name) {
+        attr(element, name)
+    } else {
+        Ok(default)
+    }
+}
+
+/// Resolves a path relative to the directory of the file at `base`.
+pub(crate) fn resolve_path(base: &str, path: &str) -> String {
+    let mut segments: Vec<&str> = base.split('/').collect();
+    segments.pop();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.2" orientation="orthogonal" width="2" height="2" tilewidth="16"
+     tileheight="16" infinite="0">
+ <tileset firstgid="1" source="../tilesets/ground.tsx"/>
+ <tileset firstgid="5" name="items" tilewidth="8" tileheight="8" tilecount="2" columns="2">
+  <image source="items.png" width="16" height="8"/>
+ </tileset>
+ <group name="background" offsetx="4">
+  <layer name="ground" width="2" height="2">
+   <properties><property name="solid" type="bool" value="true"/></properties>
+   <data encoding="csv">1,2,
+0,2147483652</data>
+  </layer>
+ </group>
+ <objectgroup name="spawns" visible="0">
+  <object id="3" name="player" type="Spawn" x="8" y="24" width="16" height="8">
+   <properties><property name="health" type="int" value="3"/></properties>
+  </object>
+  <object id="4" x="0" y="0"><polygon points="0,0 16,0 16,-8"/></object>
+ </objectgroup>
+</map>"#;
+
+    const TILESET: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<tileset name="ground" tilewidth="16" tileheight="16" spacing="1" margin="1">
+ <image source="ground.png" width="35" height="35"/>
+ <tile id="2">
+  <animation>
+   <frame tileid="2" duration="100"/>
+   <frame tileid="3" duration="300"/>
+  </animation>
+ </tile>
+ <wangsets>
+  <wangset name="shore" tile="-1">
+   <wangcornercolor name="sand" color="#ffff00" tile="-1" probability="1"/>
+   <wangcornercolor name="water" color="#0000ff" tile="-1" probability="1"/>
+   <wangtile tileid="0" wangid="0x10101010"/>
+   <wangtile tileid="1" wangid="0x10102020"/>
+  </wangset>
+ </wangsets>
+</tileset>"##;
+
+    fn map() -> Map {
+        let root = Element::parse(MAP.as_bytes()).unwrap();
+        parse_map(&root, "maps/level.tmx", &mut |path| {
+            assert_eq!(path, "tilesets/ground.tsx");
+            Ok(Element::parse(TILESET.as_bytes()).unwrap())
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn tilesets_are_parsed() {
+        let map = map();
+        let ground = &map.tilesets[0];
+        assert_eq!(
+            (ground.first_gid, ground.columns, ground.tile_count),
+            (1, 2, 4)
+        );
+        assert_eq!(ground.image.as_ref().unwrap().source, "tilesets/ground.png");
+        assert_eq!(
+            ground.animations[&2],
+            vec![
+                Frame {
+                    tile_id: 2,
+                    duration: 100
+                },
+                Frame {
+                    tile_id: 3,
+                    duration: 300
+                },
+            ]
+        );
+        assert_eq!(ground.wang_sets[0].name, "shore");
+        assert_eq!(
+            ground.wang_sets[0].tiles,
+            vec![
+                (0, WangId([0, 1, 0, 1, 0, 1, 0, 1])),
+                (1, WangId([0, 2, 0, 2, 0, 1, 0, 1])),
+            ]
+        );
+        let items = &map.tilesets[1];
+        assert_eq!(
+            (items.first_gid, items.columns, items.tile_count),
+            (5, 2, 2)
+        );
+        assert_eq!(items.image.as_ref().unwrap().source, "maps/items.png");
+        assert!(items.animations.is_empty());
+    }
+
+    #[test]
+    fn layers_are_parsed() {
+        let map = map();
+        let ground = &map.layers[0];
+        assert_eq!(ground.offset, [4.0, 0.0]);
+        assert_eq!(ground.properties["solid"], "true");
+        match ground.kind {
+            LayerKind::Tiles { width, ref gids } => {
+                assert_eq!(width, 2);
+                assert_eq!(gids, &vec![1, 2, 0, FLIPPED_HORIZONTALLY | 4]);
+            }
+            _ => panic!("expected a tile layer"),
+        }
+
+        let spawns = &map.layers[1];
+        assert!(!spawns.visible);
+        match spawns.kind {
+            LayerKind::Objects(ref objects) => {
+                assert_eq!(objects[0].object_type, "Spawn");
+                assert_eq!(objects[0].properties["health"], "3");
+                assert_eq!(objects[0].shape, Shape::Rectangle);
+                let points = vec![[0.0, 0.0], [16.0, 0.0], [16.0, -8.0]];
+                assert_eq!(objects[1].shape, Shape::Polygon(points));
+            }
+            _ => panic!("expected an object layer"),
+        }
+    }
+
+    #[test]
+    fn orientation_is_parsed() {
+        assert_eq!(map().grid.orientation, TiledOrientation::Orthogonal);
+        let root = Element::parse(
+            r#"<map orientation="hexagonal" width="4" height="3" tilewidth="14" tileheight="16"
+                    hexsidelength="8" staggeraxis="y" staggerindex="even"/>"#
+                .as_bytes(),
+        )
+        .unwrap();
+        let map = parse_map(&root, "hex.tmx", &mut |_| unreachable!()).unwrap();
+        let orientation = TiledOrientation::Hexagonal {
+            axis: StaggerAxis::Y,
+            index: StaggerIndex::Even,
+            side_length: 8,
+        };
+        assert_eq!(map.grid.orientation, orientation);
+        assert_eq!((map.grid.width, map.grid.height), (4, 3));
+    }
+
+    #[test]
+    fn wang_ids_are_parsed() {
+        let id = WangId([1, 2, 0, 0, 0, 0, 0, 3]);
+        assert_eq!(parse_wang_id("0x30000021").unwrap(), id);
+        assert_eq!(parse_wang_id("1,2,0,0,0,0,0,3").unwrap(), id);
+        assert!(parse_wang_id("1,2,3").is_err());
+    }
+
+    #[test]
+    fn base64_data_is_decoded() {
+        // The tile ids 1, 2, 0 and 7 in little endian.
+        let data = r#"<data encoding="base64">
+            AQAAAAIAAAAAAAAABwAAAA==
+        </data>"#;
+        let data = Element::parse(data.as_bytes()).unwrap();
+        assert_eq!(parse_data(&data).unwrap(), vec![1, 2, 0, 7]);
+    }
+
+    #[test]
+    fn paths_are_resolved() {
+        assert_eq!(resolve_path("level.tmx", "tiles.png"), "tiles.png");
+        assert_eq!(
+            resolve_path("maps/level.tmx", "../textures/./tiles.png"),
+            "textures/tiles.png"
+        );
+    }
+}
@@ -0,0 +1,519 @@
+//! Orientations of Tiled maps and conversions between tile and world coordinates.
+
+use amethyst_core::specs::prelude::{Component, DenseVecStorage};
+
+/// Axis along which every other row or column of a staggered or hexagonal map is shifted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StaggerAxis {
+    /// Every other column is shifted down by half a tile
+    X,
+    /// Every other row is shifted right by half a tile
+    Y,
+}
+
+/// Rows or columns shifted in a staggered or hexagonal map.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StaggerIndex {
+    /// The odd rows or columns are shifted
+    Odd,
+    /// The even rows or columns are shifted
+    Even,
+}
+
+/// Projection of the tiles of a Tiled map.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TiledOrientation {
+    /// Rectangular tiles in rows and columns
+    Orthogonal,
+    /// Diamond tiles, with the columns going down to the right and the rows down to the left
+    Isometric,
+    /// Diamond tiles in rows and columns, every other one shifted by half a tile
+    Staggered {
+        /// Axis of the shifted rows or columns
+        axis: StaggerAxis,
+        /// Shifted rows or columns
+        index: StaggerIndex,
+    },
+    /// Hexagonal tiles in rows and columns, every other one shifted by half a tile
+    Hexagonal {
+        /// Axis of the shifted rows or columns
+        axis: StaggerAxis,
+        /// Shifted rows or columns
+        index: StaggerIndex,
+        /// Length in pixels of the sides of the hexagons along the stagger axis
+        side_length: u32,
+    },
+}
+
+/// Orientation and size of a Tiled map, placed on the root entity of the map.
+///
+/// Tile coordinates are the column and row of a tile as in Tiled, `[0, 0]` for the top-left
+/// tile. World coordinates are relative to the root of the map, with Y going up and the top-left
+/// corner of the map at the origin.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TiledMap {
+    /// Projection of the tiles
+    pub orientation: TiledOrientation,
+    /// Number of columns of the map
+    pub width: u32,
+    /// Number of rows of the map
+    pub height: u32,
+    /// Width of the cells of the map in pixels
+    pub tile_width: u32,
+    /// Height of the cells of the map in pixels
+    pub tile_height: u32,
+}
+
+impl Component for TiledMap {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl TiledMap {
+    /// Returns whether the tile is in the map.
+    pub fn contains(&self, tile: [i32; 2]) -> bool {
+        tile[0] >= 0 && tile[1] >= 0 && tile[0] < self.width as i32 && tile[1] < self.height as i32
+    }
+
+    /// Returns the center of a tile in world coordinates.
+    pub fn tile_to_world(&self, tile: [i32; 2]) -> [f32; 2] {
+        let origin = self.cell_origin(tile);
+        [
+            origin[0] + self.tile_width as f32 / 2.0,
+            -origin[1] - self.tile_height as f32 / 2.0,
+        ]
+    }
+
+    /// Returns the tile under a point in world coordinates, which may be outside of the map.
+    pub fn world_to_tile(&self, point: [f32; 2]) -> [i32; 2] {
+        let (tile_width, tile_height) = (self.tile_width as f32, self.tile_height as f32);
+        let (x, y) = (point[0], -point[1]);
+        match self.orientation {
+            TiledOrientation::Orthogonal => [
+                (x / tile_width).floor() as i32,
+                (y / tile_height).floor() as i32,
+            ],
+            TiledOrientation::Isometric => {
+                let x = (x - self.height as f32 * tile_width / 2.0) / tile_width;
+                let y = y / tile_height;
+                [(y + x).floor() as i32, (y - x).floor() as i32]
+            }
+            TiledOrientation::Staggered { axis, .. } | TiledOrientation::Hexagonal { axis, .. } => {
+                // The cells overlap, the point is in the nearest one around the estimated cell.
+                let (step_x, step_y) = match axis {
+                    StaggerAxis::X => (self.stagger_step(), tile_height),
+                    StaggerAxis::Y => (tile_width, self.stagger_step()),
+                };
+                let estimate = [(x / step_x).floor() as i32, (y / step_y).floor() as i32];
+                let mut nearest = estimate;
+                let mut min_distance = std::f32::INFINITY;
+                for column in estimate[0] - 1..=estimate[0] + 1 {
+                    for row in estimate[1] - 1..=estimate[1] + 1 {
+                        let center = self.tile_to_world([column, row]);
+                        let distance = self.cell_distance(point, center);
+                        if distance < min_distance {
+                            min_distance = distance;
+                            nearest = [column, row];
+                        }
+                    }
+                }
+                nearest
+            }
+        }
+    }
+
+    /// Returns the bottom-left and top-right corners of the rectangle covering the cells of the
+    /// map, in world coordinates.
+    pub fn bounds(&self) -> ([f32; 2], [f32; 2]) {
+        let (width, height) = (self.width as i32, self.height as i32);
+        let (tile_width, tile_height) = (self.tile_width as f32, self.tile_height as f32);
+        let mut min = [std::f32::INFINITY; 2];
+        let mut max = [std::f32::NEG_INFINITY; 2];
+        // The outermost cells are in the first and last two columns and rows, as every other
+        // column or row of staggered and hexagonal maps is shifted.
+        let edges = |size: i32| vec![0, 1, size - 2, size - 1].into_iter();
+        for column in edges(width).filter(|&column| column >= 0 && column < width) {
+            for row in edges(height).filter(|&row| row >= 0 && row < height) {
+                let origin = self.cell_origin([column, row]);
+                min[0] = min[0].min(origin[0]);
+                min[1] = min[1].min(-origin[1] - tile_height);
+                max[0] = max[0].max(origin[0] + tile_width);
+                max[1] = max[1].max(-origin[1]);
+            }
+        }
+        if min[0] > max[0] {
+            return ([0.0; 2], [0.0; 2]);
+        }
+        (min, max)
+    }
+
+    /// Returns the tiles sharing an edge with a tile, which may be outside of the map.
+    ///
+    /// Tiles of hexagonal maps have six neighbors, the other tiles have four.
+    pub fn neighbors(&self, tile: [i32; 2]) -> Vec<[i32; 2]> {
+        let [column, row] = tile;
+        match self.orientation {
+            TiledOrientation::Orthogonal | TiledOrientation::Isometric => vec![
+                [column + 1, row],
+                [column, row - 1],
+                [column - 1, row],
+                [column, row + 1],
+            ],
+            TiledOrientation::Hexagonal { axis, index, .. } => HEX_DIRECTIONS
+                .iter()
+                .map(|direction| hex_neighbor(axis, index, tile, direction))
+                .collect(),
+            // Diamonds only share an edge with the diamonds of the neighbor rows or columns.
+            TiledOrientation::Staggered { axis, index } => HEX_DIRECTIONS
+                .iter()
+                .map(|direction| hex_neighbor(axis, index, tile, direction))
+                .filter(|neighbor| match axis {
+                    StaggerAxis::X => neighbor[0] != column,
+                    StaggerAxis::Y => neighbor[1] != row,
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the cube coordinates of a tile of a hexagonal map, `None` for the other maps.
+    ///
+    /// The cube coordinates `[q, r, s]` of a hexagon sum to `0`, each of them being constant
+    /// along one of the three axes of the grid. Neighbors differ by `1` on two coordinates.
+    pub fn tile_to_cube(&self, tile: [i32; 2]) -> Option<[i32; 3]> {
+        match self.orientation {
+            TiledOrientation::Hexagonal { axis, index, .. } => {
+                Some(offset_to_cube(axis, index, tile))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the tile at cube coordinates of a hexagonal map, `None` for the other maps.
+    pub fn cube_to_tile(&self, cube: [i32; 3]) -> Option<[i32; 2]> {
+        match self.orientation {
+            TiledOrientation::Hexagonal { axis, index, .. } => {
+                Some(cube_to_offset(axis, index, cube))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the number of steps between neighbors from a tile to another of a hexagonal map,
+    /// `None` for the other maps.
+    pub fn hex_distance(&self, from: [i32; 2], to: [i32; 2]) -> Option<u32> {
+        let (from, to) = (self.tile_to_cube(from)?, self.tile_to_cube(to)?);
+        let steps: i32 = from.iter().zip(&to).map(|(a, b)| (a - b).abs()).sum();
+        Some(steps as u32 / 2)
+    }
+
+    /// Returns the top-left corner of the bounding box of a cell, in pixels with Y going down.
+    ///
+    /// The image of a tile is drawn from the bottom-left corner of this box.
+    pub(crate) fn cell_origin(&self, tile: [i32; 2]) -> [f32; 2] {
+        let (tile_width, tile_height) = (self.tile_width as i32, self.tile_height as i32);
+        let [column, row] = tile;
+        let origin = match self.orientation {
+            TiledOrientation::Orthogonal => [column * tile_width, row * tile_height],
+            TiledOrientation::Isometric => [
+                (column - row - 1 + self.height as i32) * tile_width / 2,
+                (column + row) * tile_height / 2,
+            ],
+            TiledOrientation::Staggered { axis, index }
+            | TiledOrientation::Hexagonal { axis, index, .. } => {
+                let staggered = |i: i32| (i & 1 == 1) != (index == StaggerIndex::Even);
+                let step = self.stagger_step() as i32;
+                match axis {
+                    StaggerAxis::X => {
+                        let shift = if staggered(column) {
+                            tile_height / 2
+                        } else {
+                            0
+                        };
+                        [column * step, row * tile_height + shift]
+                    }
+                    StaggerAxis::Y => {
+                        let shift = if staggered(row) { tile_width / 2 } else { 0 };
+                        [column * tile_width + shift, row * step]
+                    }
+                }
+            }
+        };
+        [origin[0] as f32, origin[1] as f32]
+    }
+
+    /// Returns the center of the image of a tile of the given size in a cell, in world coordinates.
+    ///
+    /// Images larger than the cells of the map overflow them to the top and right.
+    pub(crate) fn image_center(&self, tile: [i32; 2], size: [f32; 2]) -> [f32; 2] {
+        let origin = self.cell_origin(tile);
+        let (left, bottom) = (origin[0], origin[1] + self.tile_height as f32);
+        [left + size[0] / 2.0, -bottom + size[1] / 2.0]
+    }
+
+    /// Returns the position in pixels of an object at the given coordinates in the map file.
+    ///
+    /// The objects of isometric maps are placed in the coordinates of the tiles, with a unit of
+    /// the tile height along the columns and the rows.
+    pub(crate) fn object_position(&self, x: f32, y: f32) -> [f32; 2] {
+        match self.orientation {
+            TiledOrientation::Isometric => {
+                let (tile_width, tile_height) = (self.tile_width as f32, self.tile_height as f32);
+                let (column, row) = (x / tile_height, y / tile_height);
+                [
+                    (column - row + self.height as f32) * tile_width / 2.0,
+                    (column + row) * tile_height / 2.0,
+                ]
+            }
+            _ => [x, y],
+        }
+    }
+
+    /// Distance in pixels between two neighbor cells along the stagger axis.
+    fn stagger_step(&self) -> f32 {
+        let (size, side_length) = match self.orientation {
+            TiledOrientation::Staggered { axis, .. } => (self.size_along(axis), 0),
+            TiledOrientation::Hexagonal {
+                axis, side_length, ..
+            } => (self.size_along(axis), side_length),
+            _ => return 0.0,
+        };
+        ((size - side_length) / 2 + side_length) as f32
+    }
+
+    fn size_along(&self, axis: StaggerAxis) -> u32 {
+        match axis {
+            StaggerAxis::X => self.tile_width,
+            StaggerAxis::Y => self.tile_height,
+        }
+    }
+
+    /// Distance between a point and the center of a cell, in the shape of the cells: a point is
+    /// nearer to the center of the cell it is in than to the other centers.
+    fn cell_distance(&self, point: [f32; 2], center: [f32; 2]) -> f32 {
+        let (dx, dy) = (point[0] - center[0], point[1] - center[1]);
+        match self.orientation {
+            TiledOrientation::Hexagonal { .. } => dx * dx + dy * dy,
+            _ => dx.abs() / self.tile_width as f32 + dy.abs() / self.tile_height as f32,
+        }
+    }
+}
+
+/// Differences of the cube coordinates of the neighbors of a hexagon.
+const HEX_DIRECTIONS: [[i32; 3]; 6] = [
+    [1, -1, 0],
+    [1, 0, -1],
+    [0, 1, -1],
+    [-1, 1, 0],
+    [-1, 0, 1],
+    [0, -1, 1],
+];
+
+fn hex_neighbor(
+    axis: StaggerAxis,
+    index: StaggerIndex,
+    tile: [i32; 2],
+    direction: &[i32; 3],
+) -> [i32; 2] {
+    let cube = offset_to_cube(axis, index, tile);
+    cube_to_offset(
+        axis,
+        index,
+        [
+            cube[0] + direction[0],
+            cube[1] + direction[1],
+            cube[2] + direction[2],
+        ],
+    )
+}
+
+/// Converts the column and row of a hexagon to cube coordinates.
+///
+/// With `StaggerAxis::X`, `q` is the column. With `StaggerAxis::Y`, `r` is the row. Every other
+/// row or column is shifted forward, so the shift is even and the divisions exact.
+fn offset_to_cube(axis: StaggerAxis, index: StaggerIndex, tile: [i32; 2]) -> [i32; 3] {
+    let shift = |i: i32| match index {
+        StaggerIndex::Odd => (i - (i & 1)) / 2,
+        StaggerIndex::Even => (i + (i & 1)) / 2,
+    };
+    let [column, row] = tile;
+    let (q, r) = match axis {
+        StaggerAxis::X => (column, row - shift(column)),
+        StaggerAxis::Y => (column - shift(row), row),
+    };
+    [q, r, -q - r]
+}
+
+fn cube_to_offset(axis: StaggerAxis, index: StaggerIndex, cube: [i32; 3]) -> [i32; 2] {
+    let shift = |i: i32| match index {
+        StaggerIndex::Odd => (i - (i & 1)) / 2,
+        StaggerIndex::Even => (i + (i & 1)) / 2,
+    };
+    let (q, r) = (cube[0], cube[1]);
+    match axis {
+        StaggerAxis::X => [q, r + shift(q)],
+        StaggerAxis::Y => [q + shift(r), r],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(orientation: TiledOrientation) -> TiledMap {
+        TiledMap {
+            orientation,
+            width: 6,
+            height: 5,
+            tile_width: 32,
+            tile_height: 16,
+        }
+    }
+
+    fn assert_round_trip(map: &TiledMap) {
+        for column in -1..=6 {
+            for row in -1..=5 {
+                let center = map.tile_to_world([column, row]);
+                assert_eq!(map.world_to_tile(center), [column, row]);
+                // Points near the center are in the same tile.
+                let near = [center[0] + 3.0, center[1] - 2.0];
+                assert_eq!(map.world_to_tile(near), [column, row]);
+            }
+        }
+    }
+
+    #[test]
+    fn orthogonal_tiles() {
+        let map = map(TiledOrientation::Orthogonal);
+        assert_eq!(map.tile_to_world([1, 2]), [48.0, -40.0]);
+        assert_eq!(map.world_to_tile([63.0, -33.0]), [1, 2]);
+        assert!(map.contains([5, 4]));
+        assert!(!map.contains([6, 0]));
+        assert_eq!(map.bounds(), ([0.0, -80.0], [192.0, 0.0]));
+        assert_round_trip(&map);
+    }
+
+    #[test]
+    fn isometric_tiles() {
+        let map = map(TiledOrientation::Isometric);
+        // The top tile is at the middle of the height of the map, in columns.
+        assert_eq!(map.tile_to_world([0, 0]), [80.0, -8.0]);
+        assert_eq!(map.tile_to_world([1, 0]), [96.0, -16.0]);
+        assert_eq!(map.tile_to_world([0, 1]), [64.0, -16.0]);
+        // The corners of the diamonds are shared with their neighbors.
+        assert_eq!(map.world_to_tile([80.0, -1.0]), [0, 0]);
+        assert_eq!(map.world_to_tile([66.0, -8.0]), [0, 0]);
+        assert_eq!(map.world_to_tile([64.0, -8.5]), [0, 1]);
+        assert_eq!(map.object_position(16.0, 0.0), [96.0, 8.0]);
+        assert_eq!(map.bounds(), ([0.0, -88.0], [176.0, 0.0]));
+        assert_round_trip(&map);
+    }
+
+    #[test]
+    fn staggered_tiles() {
+        let rows = map(TiledOrientation::Staggered {
+            axis: StaggerAxis::Y,
+            index: StaggerIndex::Odd,
+        });
+        assert_eq!(rows.tile_to_world([0, 0]), [16.0, -8.0]);
+        assert_eq!(rows.tile_to_world([0, 1]), [32.0, -16.0]);
+        assert_eq!(rows.tile_to_world([1, 2]), [48.0, -24.0]);
+        // Between the diamonds of the even rows is a diamond of an odd row.
+        assert_eq!(rows.world_to_tile([31.0, -9.0]), [0, 1]);
+        // The odd rows overflow the even ones by half a tile on the right.
+        assert_eq!(rows.bounds(), ([0.0, -48.0], [208.0, 0.0]));
+        assert_round_trip(&rows);
+
+        let columns = map(TiledOrientation::Staggered {
+            axis: StaggerAxis::X,
+            index: StaggerIndex::Even,
+        });
+        assert_eq!(columns.tile_to_world([0, 0]), [16.0, -16.0]);
+        assert_eq!(columns.tile_to_world([1, 0]), [32.0, -8.0]);
+        assert_round_trip(&columns);
+    }
+
+    #[test]
+    fn hexagonal_tiles() {
+        let map = TiledMap {
+            orientation: TiledOrientation::Hexagonal {
+                axis: StaggerAxis::Y,
+                index: StaggerIndex::Odd,
+                side_length: 8,
+            },
+            width: 6,
+            height: 5,
+            tile_width: 14,
+            tile_height: 16,
+        };
+        // Rows are 12 pixels apart, the odd ones shifted by half a tile.
+        assert_eq!(map.tile_to_world([0, 0]), [7.0, -8.0]);
+        assert_eq!(map.tile_to_world([0, 1]), [14.0, -20.0]);
+        assert_eq!(map.tile_to_world([0, 2]), [7.0, -32.0]);
+        assert_round_trip(&map);
+    }
+
+    #[test]
+    fn hexagonal_neighbors() {
+        let mut hex = map(TiledOrientation::Hexagonal {
+            axis: StaggerAxis::Y,
+            index: StaggerIndex::Odd,
+            side_length: 8,
+        });
+        let mut neighbors = hex.neighbors([2, 1]);
+        neighbors.sort();
+        // The odd rows are shifted right, so their neighbors are on the right.
+        assert_eq!(
+            neighbors,
+            vec![[1, 1], [2, 0], [2, 2], [3, 0], [3, 1], [3, 2]]
+        );
+        let mut neighbors = hex.neighbors([2, 2]);
+        neighbors.sort();
+        assert_eq!(
+            neighbors,
+            vec![[1, 1], [1, 2], [1, 3], [2, 1], [2, 3], [3, 2]]
+        );
+        assert_eq!(hex.hex_distance([0, 0], [3, 3]), Some(5));
+        assert_eq!(hex.hex_distance([-2, -1], [-2, -1]), Some(0));
+
+        hex.orientation = TiledOrientation::Hexagonal {
+            axis: StaggerAxis::X,
+            index: StaggerIndex::Even,
+            side_length: 8,
+        };
+        let mut neighbors = hex.neighbors([2, 1]);
+        neighbors.sort();
+        // The even columns are shifted down, so their neighbors are below.
+        assert_eq!(
+            neighbors,
+            vec![[1, 1], [1, 2], [2, 0], [2, 2], [3, 1], [3, 2]]
+        );
+        for column in -3..3 {
+            for row in -3..3 {
+                let cube = hex.tile_to_cube([column, row]).unwrap();
+                assert_eq!(cube.iter().sum::<i32>(), 0);
+                assert_eq!(hex.cube_to_tile(cube), Some([column, row]));
+                for neighbor in hex.neighbors([column, row]) {
+                    assert_eq!(hex.hex_distance([column, row], neighbor), Some(1));
+                }
+            }
+        }
+        assert_eq!(map(TiledOrientation::Isometric).tile_to_cube([0, 0]), None);
+    }
+
+    #[test]
+    fn staggered_neighbors() {
+        let map = map(TiledOrientation::Staggered {
+            axis: StaggerAxis::Y,
+            index: StaggerIndex::Odd,
+        });
+        let mut neighbors = map.neighbors([1, 2]);
+        neighbors.sort();
+        assert_eq!(neighbors, vec![[0, 1], [0, 3], [1, 1], [1, 3]]);
+        // The diamonds of the neighbors touch the diamond of the tile.
+        let center = map.tile_to_world([1, 2]);
+        for neighbor in neighbors {
+            let other = map.tile_to_world(neighbor);
+            assert_eq!((center[0] - other[0]).abs(), 16.0);
+            assert_eq!((center[1] - other[1]).abs(), 8.0);
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! Picking of the tiles and objects of Tiled maps under a point of the screen.
+
+use amethyst_core::{
+    nalgebra::{Matrix4, Vector4},
+    specs::prelude::{Entities, Entity, Join, Read, ReadExpect, ReadStorage},
+    transform::{GlobalTransform, Parent, Transform},
+};
+use amethyst_renderer::{ActiveCamera, Camera, HiddenPropagate, ScreenDimensions};
+use shred_derive::SystemData;
+
+use crate::{TiledMap, TiledObject};
+
+/// A tile of a Tiled map under a point of the screen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TilePick {
+    /// Root entity of the map
+    pub map: Entity,
+    /// Column and row of the tile
+    pub tile: [i32; 2],
+    /// Visible object of the map under the point, if any
+    pub object: Option<Entity>,
+    /// Point under the screen point, in world coordinates of the map
+    pub point: [f32; 2],
+}
+
+/// Finds the tiles of the Tiled maps under points of the screen, such as the mouse cursor.
+///
+/// The point of the screen is projected through the active camera, or the first camera if there
+/// is none, on the planes of the maps. Maps can be moved, rotated and scaled by the transform of
+/// their root, and the camera can be orthographic with any zoom or perspective.
+#[derive(SystemData)]
+pub struct TiledPicker<'a> {
+    entities: Entities<'a>,
+    active_camera: Read<'a, ActiveCamera>,
+    cameras: ReadStorage<'a, Camera>,
+    globals: ReadStorage<'a, GlobalTransform>,
+    transforms: ReadStorage<'a, Transform>,
+    parents: ReadStorage<'a, Parent>,
+    screen_dimensions: ReadExpect<'a, ScreenDimensions>,
+    maps: ReadStorage<'a, TiledMap>,
+    objects: ReadStorage<'a, TiledObject>,
+    hidden: ReadStorage<'a, HiddenPropagate>,
+}
+
+impl<'a> TiledPicker<'a> {
+    /// Returns the tile under a point of the screen in pixels from its top-left corner, of the
+    /// visible map nearest to the camera.
+    pub fn pick(&self, screen_position: [f32; 2]) -> Option<TilePick> {
+        let (camera, camera_global) = self
+            .active_camera
+            .entity
+            .and_then(|entity| Some((self.cameras.get(entity)?, self.globals.get(entity)?)))
+            .or_else(|| (&self.cameras, &self.globals).join().next())?;
+        let view_proj = camera.proj * camera_global.0.try_inverse()?;
+        let ray = screen_ray(
+            &view_proj.try_inverse()?,
+            screen_position,
+            [
+                self.screen_dimensions.width(),
+                self.screen_dimensions.height(),
+            ],
+        );
+
+        let (entity, map, distance, point) = (&*self.entities, &self.maps, &self.globals)
+            .join()
+            .filter(|(entity, _, _)| !self.hidden.contains(*entity))
+            .filter_map(|(entity, map, global)| {
+                let (distance, point) = plane_intersection(&ray, &global.0.try_inverse()?)?;
+                let tile = map.world_to_tile(point);
+                Some((entity, map, distance, point)).filter(|_| map.contains(tile))
+            })
+            .min_by(|a, b| a.2.partial_cmp(&b.2).unwrap_or(std::cmp::Ordering::Equal))?;
+
+        let world_point = ray.0 + (ray.1 - ray.0) * distance;
+        let object = (&*self.entities, &self.objects, &self.globals)
+            .join()
+            .filter(|(object, _, _)| self.in_map(*object, entity))
+            .filter(|(object_entity, object, global)| {
+                let local = match global.0.try_inverse() {
+                    Some(inverse) => inverse * world_point,
+                    None => return false,
+                };
+                // Tile objects are scaled from the size of their tile to their own size.
+                let scale = self
+                    .transforms
+                    .get(*object_entity)
+                    .map_or([1.0, 1.0], |t| [t.scale().x, t.scale().y]);
+                object.contains([local.x * scale[0], local.y * scale[1]])
+            })
+            .map(|(object, _, _)| object)
+            .last();
+
+        Some(TilePick {
+            map: entity,
+            tile: map.world_to_tile(point),
+            object,
+            point,
+        })
+    }
+
+    /// Returns whether an entity is a visible descendant of the root of a map.
+    fn in_map(&self, mut entity: Entity, map: Entity) -> bool {
+        loop {
+            if self.hidden.contains(entity) {
+                return false;
+            }
+            entity = match self.parents.get(entity) {
+                Some(parent) => parent.entity,
+                None => return false,
+            };
+            if entity == map {
+                return true;
+            }
+        }
+    }
+}
+
+/// Ray through a point of the screen, from the near plane to the far plane of the camera.
+type Ray = (Vector4<f32>, Vector4<f32>);
+
+/// Returns the ray through a point of the screen, from the inverse of the view projection.
+fn screen_ray(inverse_view_proj: &Matrix4<f32>, position: [f32; 2], size: [f32; 2]) -> Ray {
+    let x = 2.0 * position[0] / size[0] - 1.0;
+    let y = 1.0 - 2.0 * position[1] / size[1];
+    let unproject = |z| {
+        let point = inverse_view_proj * Vector4::new(x, y, z, 1.0);
+        point / point.w
+    };
+    (unproject(-1.0), unproject(1.0))
+}
+
+/// Returns the fraction of the ray where it crosses the XY plane of a transform, with the point in
+/// the coordinates of the transform, given its inverse.
+fn plane_intersection(ray: &Ray, inverse: &Matrix4<f32>) -> Option<(f32, [f32; 2])> {
+    let (near, far) = (inverse * ray.0, inverse * ray.1);
+    if near.z == far.z {
+        return None;
+    }
+    let distance = near.z / (near.z - far.z);
+    let point = near + (far - near) * distance;
+    Some((distance, [point.x, point.y])).filter(|_| distance >= 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use amethyst_core::nalgebra::Vector3;
+    use amethyst_renderer::Projection;
+
+    use crate::{TiledObjectShape, TiledOrientation};
+
+    #[test]
+    fn screen_point_is_projected_on_map() {
+        // A zoomed camera showing 200 by 100 units centered on (100, -50), at Z 10.
+        let camera = Camera::from(Projection::orthographic(-100.0, 100.0, -50.0, 50.0));
+        let view = Matrix4::new_translation(&Vector3::new(100.0, -50.0, 10.0));
+        let inverse = (camera.proj * view.try_inverse().unwrap())
+            .try_inverse()
+            .unwrap();
+        let ray = screen_ray(&inverse, [500.0, 100.0], [800.0, 400.0]);
+
+        let (_, point) = plane_intersection(&ray, &Matrix4::identity()).unwrap();
+        assert!((point[0] - 125.0).abs() < 1e-3);
+        assert!((point[1] - -25.0).abs() < 1e-3);
+
+        // The map is moved 20 units to the right.
+        let map_global = Matrix4::new_translation(&Vector3::new(20.0, 0.0, 0.0));
+        let (_, point) = plane_intersection(&ray, &map_global.try_inverse().unwrap()).unwrap();
+        assert!((point[0] - 105.0).abs() < 1e-3);
+
+        let map = TiledMap {
+            orientation: TiledOrientation::Orthogonal,
+            width: 10,
+            height: 10,
+            tile_width: 16,
+            tile_height: 16,
+        };
+        assert_eq!(map.world_to_tile(point), [6, 1]);
+    }
+
+    #[test]
+    fn object_shapes_contain_points() {
+        let mut object = TiledObject {
+            id: 1,
+            name: String::new(),
+            object_type: String::new(),
+            width: 20.0,
+            height: 10.0,
+            shape: TiledObjectShape::Rectangle,
+            properties: Default::default(),
+        };
+        assert!(object.contains([9.0, -4.0]));
+        assert!(!object.contains([11.0, 0.0]));
+        object.shape = TiledObjectShape::Ellipse;
+        assert!(object.contains([0.0, 4.0]));
+        assert!(!object.contains([9.0, -4.0]));
+        object.shape = TiledObjectShape::Polygon(vec![[0.0, 0.0], [16.0, 0.0], [16.0, 8.0]]);
+        assert!(object.contains([12.0, 2.0]));
+        assert!(!object.contains([4.0, 6.0]));
+        object.shape = TiledObjectShape::Point;
+        assert!(!object.contains([0.0, 0.0]));
+    }
+}
@@ -9,12 +9,14 @@ use amethyst_audio::AudioFormat;
 use amethyst_core::{bundle::SystemBundle, specs::prelude::DispatcherBuilder};
 use amethyst_error::Error;
 use amethyst_renderer::{BlinkSystem, TextureFormat};
+use winit::VirtualKeyCode;
 
 use crate::{
-    CacheSelectionOrderSystem, FontAsset, FontFormat, NoCustomUi, ResizeSystem,
-    SelectionKeyboardSystem, SelectionMouseSystem, TextEditingInputSystem, TextEditingMouseSystem,
-    ToNativeWidget, UiButtonActionRetriggerSystem, UiButtonSystem, UiLoaderSystem, UiMouseSystem,
-    UiSoundRetriggerSystem, UiSoundSystem, UiTransformSystem,
+    CacheSelectionOrderSystem, ConsoleSystem, DebugOverlaySystem, FontAsset, FontFormat,
+    NoCustomUi, ResizeSystem, SelectionKeyboardSystem, SelectionMouseSystem,
+    TextEditingInputSystem, TextEditingMouseSystem, ToNativeWidget, UiButtonActionRetriggerSystem,
+    UiButtonSystem, UiLoaderSystem, UiMouseSystem, UiSoundRetriggerSystem, UiSoundSystem,
+    UiTransformSystem,
 };
 
 /// UI bundle
@@ -114,3 +116,85 @@ where
         Ok(())
     }
 }
+
+/// Developer console bundle
+///
+/// Adds the `ConsoleSystem` as a thread local system, opening and closing the `Console` with the
+/// grave accent key by default. Requires the `UiBundle`.
+///
+/// ## Errors
+///
+/// No errors will be returned by this bundle.
+pub struct ConsoleBundle {
+    toggle_key: VirtualKeyCode,
+}
+
+impl Default for ConsoleBundle {
+    fn default() -> Self {
+        ConsoleBundle {
+            toggle_key: VirtualKeyCode::Grave,
+        }
+    }
+}
+
+impl ConsoleBundle {
+    /// Create a new console bundle.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set the key opening and closing the console.
+    pub fn with_toggle_key(mut self, toggle_key: VirtualKeyCode) -> Self {
+        self.toggle_key = toggle_key;
+        self
+    }
+}
+
+impl<'a, 'b> SystemBundle<'a, 'b> for ConsoleBundle {
+    fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<(), Error> {
+        builder.add_thread_local(ConsoleSystem::new(self.toggle_key));
+        Ok(())
+    }
+}
+
+/// Performance overlay bundle
+///
+/// Adds the `DebugOverlaySystem` as a thread local system, showing and hiding the `DebugOverlay`
+/// with the F3 key by default. Requires the `UiBundle`.
+///
+/// The timings of the systems are only shown for the systems made timed with `SystemExt::timed`.
+///
+/// ## Errors
+///
+/// No errors will be returned by this bundle.
+pub struct DebugOverlayBundle {
+    toggle_key: VirtualKeyCode,
+}
+
+impl Default for DebugOverlayBundle {
+    fn default() -> Self {
+        DebugOverlayBundle {
+            toggle_key: VirtualKeyCode::F3,
+        }
+    }
+}
+
+impl DebugOverlayBundle {
+    /// Create a new debug overlay bundle.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set the key showing and hiding the overlay.
+    pub fn with_toggle_key(mut self, toggle_key: VirtualKeyCode) -> Self {
+        self.toggle_key = toggle_key;
+        self
+    }
+}
+
+impl<'a, 'b> SystemBundle<'a, 'b> for DebugOverlayBundle {
+    fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<(), Error> {
+        builder.add_thread_local(DebugOverlaySystem::new(self.toggle_key));
+        Ok(())
+    }
+}
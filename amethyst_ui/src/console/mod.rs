@@ -0,0 +1,377 @@
+//! In-game developer console.
+
+pub use self::system::ConsoleSystem;
+
+use std::{collections::BTreeMap, fmt::Display, str::FromStr, sync::Arc};
+
+use amethyst_core::specs::prelude::Resources;
+use amethyst_error::{format_err, Error};
+use shred::Resource;
+
+mod system;
+
+/// Handler of a console command, called with the arguments of the command. The returned text is
+/// printed in the console.
+pub type CommandHandler = Arc<dyn Fn(&[&str], &Resources) -> Result<String, Error> + Send + Sync>;
+
+/// Maximal number of lines kept in the output of the console.
+const MAX_OUTPUT_LINES: usize = 200;
+
+/// Maximal number of entries kept in the history of the console.
+const MAX_HISTORY: usize = 100;
+
+struct Command {
+    help: String,
+    handler: CommandHandler,
+}
+
+/// Resource holding the commands and variables of the developer console, and its state.
+///
+/// Commands are registered with `register`, and variables bound to fields of resources with
+/// `register_variable`. Typing the name of a variable prints its value, and typing its name
+/// followed by a value sets it. The `help` and `clear` commands are always available.
+///
+/// The console is drawn and controlled by the `ConsoleSystem`. Games should ignore gameplay input
+/// while it `is_open`.
+pub struct Console {
+    commands: BTreeMap<String, Command>,
+    variables: BTreeMap<String, Command>,
+    pub(crate) open: bool,
+    pub(crate) input: String,
+    pub(crate) output: Vec<String>,
+    history: Vec<String>,
+    history_index: Option<usize>,
+    pending: Vec<String>,
+    pub(crate) dirty: bool,
+}
+
+impl Default for Console {
+    fn default() -> Self {
+        Console {
+            commands: BTreeMap::new(),
+            variables: BTreeMap::new(),
+            open: false,
+            input: String::new(),
+            output: Vec::new(),
+            history: Vec::new(),
+            history_index: None,
+            pending: Vec::new(),
+            dirty: true,
+        }
+    }
+}
+
+impl Console {
+    /// Creates an empty console.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Registers a command, replacing the command with the same name.
+    pub fn register<N, H, F>(&mut self, name: N, help: H, handler: F)
+    where
+        N: Into<String>,
+        H: Into<String>,
+        F: Fn(&[&str], &Resources) -> Result<String, Error> + Send + Sync + 'static,
+    {
+        self.commands.insert(
+            name.into(),
+            Command {
+                help: help.into(),
+                handler: Arc::new(handler),
+            },
+        );
+    }
+
+    /// Registers a variable bound to a field of a resource, using `FromStr` and `Display` to
+    /// parse and print its value.
+    pub fn register_variable<N, H, R, T, F>(&mut self, name: N, help: H, field: F)
+    where
+        N: Into<String>,
+        H: Into<String>,
+        R: Resource,
+        T: FromStr + Display,
+        <T as FromStr>::Err: Display,
+        F: Fn(&mut R) -> &mut T + Send + Sync + 'static,
+    {
+        let name = name.into();
+        let variable = name.clone();
+        let handler = move |args: &[&str], res: &Resources| {
+            let mut resource = res
+                .try_fetch_mut::<R>()
+                .ok_or_else(|| format_err!("The resource of `{}` does not exist", variable))?;
+            let value = field(&mut resource);
+            if !args.is_empty() {
+                *value = args
+                    .join(" ")
+                    .parse()
+                    .map_err(|e| format_err!("Invalid value for `{}`: {}", variable, e))?;
+            }
+            Ok(format!("{} = {}", variable, value))
+        };
+        self.variables.insert(
+            name,
+            Command {
+                help: help.into(),
+                handler: Arc::new(handler),
+            },
+        );
+    }
+
+    /// Whether the console is shown and capturing the keyboard.
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Shows or hides the console.
+    pub fn set_open(&mut self, open: bool) {
+        self.open = open;
+        self.dirty = true;
+    }
+
+    /// Prints a line in the console.
+    pub fn print<L: Into<String>>(&mut self, line: L) {
+        for line in line.into().lines() {
+            self.output.push(line.to_string());
+        }
+        if self.output.len() > MAX_OUTPUT_LINES {
+            let excess = self.output.len() - MAX_OUTPUT_LINES;
+            self.output.drain(..excess);
+        }
+        self.dirty = true;
+    }
+
+    /// Clears the output of the console.
+    pub fn clear(&mut self) {
+        self.output.clear();
+        self.dirty = true;
+    }
+
+    /// Queues a command line, executed by the `ConsoleSystem` on its next run.
+    pub fn submit<L: Into<String>>(&mut self, line: L) {
+        self.pending.push(line.into());
+    }
+
+    /// The previously submitted command lines, the most recent last.
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    /// The names of the commands and variables starting with the prefix.
+    pub fn completions(&self, prefix: &str) -> Vec<&str> {
+        let builtins = ["clear", "help"];
+        let mut names = builtins
+            .iter()
+            .cloned()
+            .chain(self.commands.keys().map(String::as_str))
+            .chain(self.variables.keys().map(String::as_str))
+            .filter(|name| name.starts_with(prefix))
+            .collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Submits the input line.
+    pub(crate) fn submit_input(&mut self) {
+        let line = std::mem::replace(&mut self.input, String::new());
+        let trimmed = line.trim();
+        if !trimmed.is_empty() {
+            if self.history.last().map(String::as_str) != Some(trimmed) {
+                self.history.push(trimmed.to_string());
+            }
+            if self.history.len() > MAX_HISTORY {
+                self.history.remove(0);
+            }
+            self.submit(trimmed);
+        }
+        self.history_index = None;
+        self.dirty = true;
+    }
+
+    /// Replaces the input with an older entry of the history.
+    pub(crate) fn history_previous(&mut self) {
+        if self.history.is_empty() {
+            return;
+        }
+        let index = match self.history_index {
+            Some(index) => index.saturating_sub(1),
+            None => self.history.len() - 1,
+        };
+        self.history_index = Some(index);
+        self.input = self.history[index].clone();
+        self.dirty = true;
+    }
+
+    /// Replaces the input with a more recent entry of the history, or clears it.
+    pub(crate) fn history_next(&mut self) {
+        match self.history_index {
+            Some(index) if index + 1 < self.history.len() => {
+                self.history_index = Some(index + 1);
+                self.input = self.history[index + 1].clone();
+            }
+            Some(_) => {
+                self.history_index = None;
+                self.input.clear();
+            }
+            None => {}
+        }
+        self.dirty = true;
+    }
+
+    /// Completes the name in the input to the longest common prefix of the matching names, and
+    /// prints the matches if there are several.
+    pub(crate) fn complete_input(&mut self) {
+        if self.input.contains(char::is_whitespace) {
+            return;
+        }
+        let matches = self
+            .completions(&self.input)
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        match matches.len() {
+            0 => {}
+            1 => self.input = format!("{} ", matches[0]),
+            _ => {
+                self.input = common_prefix(&matches).to_string();
+                self.print(matches.join("  "));
+            }
+        }
+        self.dirty = true;
+    }
+
+    /// Takes the queued command lines.
+    pub(crate) fn take_pending(&mut self) -> Vec<String> {
+        std::mem::replace(&mut self.pending, Vec::new())
+    }
+
+    /// The handler of a command or variable.
+    pub(crate) fn handler(&self, name: &str) -> Option<CommandHandler> {
+        self.commands
+            .get(name)
+            .or_else(|| self.variables.get(name))
+            .map(|command| command.handler.clone())
+    }
+
+    /// The help of the commands and variables.
+    pub(crate) fn help(&self) -> String {
+        let mut help = String::from("Commands:");
+        for (name, command) in &self.commands {
+            help.push_str(&format!("\n  {} - {}", name, command.help));
+        }
+        help.push_str("\nVariables:");
+        for (name, variable) in &self.variables {
+            help.push_str(&format!("\n  {} - {}", name, variable.help));
+        }
+        help
+    }
+}
+
+/// Splits a command line into words, keeping the text between double quotes together.
+pub(crate) fn tokenize(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+    let mut in_word = false;
+    for c in line.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_word = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if in_word {
+                    words.push(std::mem::replace(&mut word, String::new()));
+                    in_word = false;
+                }
+            }
+            c => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+fn common_prefix(names: &[String]) -> &str {
+    let first = &names[0];
+    let mut end = first.len();
+    for name in &names[1..] {
+        end = first
+            .char_indices()
+            .zip(name.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((i, a), _)| i + a.len_utf8())
+            .min(end);
+    }
+    &first[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenize_quotes() {
+        assert_eq!(
+            tokenize("spawn  \"big tree\" 3"),
+            vec!["spawn", "big tree", "3"]
+        );
+        assert_eq!(tokenize("say \"\""), vec!["say", ""]);
+    }
+
+    #[test]
+    fn complete_common_prefix() {
+        let mut console = Console::new();
+        console.register("spawn_tree", "", |_, _| Ok(String::new()));
+        console.register("spawn_rock", "", |_, _| Ok(String::new()));
+        console.input = "sp".to_string();
+        console.complete_input();
+        assert_eq!(console.input, "spawn_");
+        console.input.push('t');
+        console.complete_input();
+        assert_eq!(console.input, "spawn_tree ");
+    }
+
+    #[test]
+    fn navigate_history() {
+        let mut console = Console::new();
+        for line in &["first", "second"] {
+            console.input = line.to_string();
+            console.submit_input();
+        }
+        console.history_previous();
+        assert_eq!(console.input, "second");
+        console.history_previous();
+        console.history_previous();
+        assert_eq!(console.input, "first");
+        console.history_next();
+        assert_eq!(console.input, "second");
+        console.history_next();
+        assert_eq!(console.input, "");
+    }
+
+    #[test]
+    fn variable_bound_to_resource() {
+        #[derive(Default)]
+        struct Settings {
+            speed: f32,
+        }
+
+        let mut res = Resources::new();
+        res.insert(Settings::default());
+        let mut console = Console::new();
+        console.register_variable("speed", "", |settings: &mut Settings| &mut settings.speed);
+
+        let handler = console.handler("speed").unwrap();
+        assert_eq!(handler(&["2.5"], &res).unwrap(), "speed = 2.5");
+        assert_eq!(res.fetch::<Settings>().speed, 2.5);
+        assert!(handler(&["fast"], &res).is_err());
+    }
+}
@@ -0,0 +1,254 @@
+use amethyst_assets::{AssetStorage, Loader};
+use amethyst_core::{
+    shrev::{EventChannel, ReaderId},
+    specs::{
+        prelude::{Entity, Resources, RunNow, SystemData, WriteStorage},
+        world::EntitiesRes,
+    },
+};
+use amethyst_error::format_err;
+use amethyst_renderer::Hidden;
+use log::error;
+use winit::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use super::{tokenize, Console};
+use crate::{get_default_font, Anchor, FontAsset, LineMode, Stretch, UiText, UiTransform};
+
+/// Height of the output of the console, in pixels.
+const OUTPUT_HEIGHT: f32 = 300.0;
+/// Font size of the console.
+const FONT_SIZE: f32 = 16.0;
+const TEXT_COLOR: [f32; 4] = [0.9, 0.9, 0.9, 1.0];
+
+/// Shows the `Console` at the top of the screen when the toggle key is pressed, sends the keyboard
+/// input to it while it is open, and executes the submitted commands.
+///
+/// The system is thread local, as command handlers get access to all the resources.
+pub struct ConsoleSystem {
+    toggle_key: VirtualKeyCode,
+    reader: Option<ReaderId<Event>>,
+    /// The entities of the output and of the input line
+    entities: Option<(Entity, Entity)>,
+    /// Whether the character typed with the toggle key should be ignored
+    skip_char: bool,
+}
+
+impl ConsoleSystem {
+    /// Creates a new `ConsoleSystem`, opened and closed with the given key.
+    pub fn new(toggle_key: VirtualKeyCode) -> Self {
+        ConsoleSystem {
+            toggle_key,
+            reader: None,
+            entities: None,
+            skip_char: false,
+        }
+    }
+
+    fn handle_input(&mut self, res: &Resources) {
+        let events = res.fetch::<EventChannel<Event>>();
+        let mut console = res.fetch_mut::<Console>();
+        let reader = self
+            .reader
+            .as_mut()
+            .expect("`ConsoleSystem::setup` was not called before `ConsoleSystem::run_now`");
+        for event in events.read(reader) {
+            let event = match *event {
+                Event::WindowEvent { ref event, .. } => event,
+                _ => continue,
+            };
+            match *event {
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            state: ElementState::Pressed,
+                            virtual_keycode: Some(key),
+                            ..
+                        },
+                    ..
+                } => {
+                    if key == self.toggle_key {
+                        let open = !console.is_open();
+                        console.set_open(open);
+                        self.skip_char = true;
+                        continue;
+                    }
+                    self.skip_char = false;
+                    if !console.is_open() {
+                        continue;
+                    }
+                    match key {
+                        VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => {
+                            console.submit_input()
+                        }
+                        VirtualKeyCode::Back => {
+                            console.input.pop();
+                            console.dirty = true;
+                        }
+                        VirtualKeyCode::Up => console.history_previous(),
+                        VirtualKeyCode::Down => console.history_next(),
+                        VirtualKeyCode::Tab => console.complete_input(),
+                        VirtualKeyCode::Escape => console.set_open(false),
+                        _ => {}
+                    }
+                }
+                WindowEvent::ReceivedCharacter(c) => {
+                    if self.skip_char {
+                        self.skip_char = false;
+                    } else if console.is_open() && !c.is_control() {
+                        console.input.push(c);
+                        console.dirty = true;
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.skip_char = false;
+    }
+
+    fn execute(&mut self, res: &Resources) {
+        let lines = res.fetch_mut::<Console>().take_pending();
+        for line in lines {
+            let words = tokenize(&line);
+            let (name, args) = match words.split_first() {
+                Some(split) => split,
+                None => continue,
+            };
+            let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+            res.fetch_mut::<Console>().print(format!("> {}", line));
+
+            // The console is not borrowed while the handler runs, so it can be used by it.
+            let result = match name.as_str() {
+                "help" => Ok(res.fetch::<Console>().help()),
+                "clear" => {
+                    res.fetch_mut::<Console>().clear();
+                    Ok(String::new())
+                }
+                _ => {
+                    let handler = res.fetch::<Console>().handler(name);
+                    match handler {
+                        Some(handler) => handler(&args, res),
+                        None => Err(format_err!("Unknown command `{}`", name)),
+                    }
+                }
+            };
+
+            let mut console = res.fetch_mut::<Console>();
+            match result {
+                Ok(ref output) if output.is_empty() => {}
+                Ok(output) => console.print(output),
+                Err(e) => console.print(format!("Error: {}", e)),
+            }
+        }
+    }
+
+    fn update_ui(&mut self, res: &Resources) {
+        let mut console = res.fetch_mut::<Console>();
+        if !console.dirty {
+            return;
+        }
+        if self.entities.is_none() && !console.is_open() {
+            return;
+        }
+        console.dirty = false;
+
+        let (output, input) = match self.entities {
+            Some(entities) => entities,
+            None => {
+                let entities = self.create_entities(res);
+                self.entities = Some(entities);
+                entities
+            }
+        };
+
+        let (mut texts, mut hiddens) =
+            <(WriteStorage<'_, UiText>, WriteStorage<'_, Hidden>)>::fetch(res);
+        let visible_lines = (OUTPUT_HEIGHT / (FONT_SIZE * 1.2)) as usize;
+        let first = console.output.len().saturating_sub(visible_lines);
+        if let Some(text) = texts.get_mut(output) {
+            text.text = console.output[first..].join("\n");
+        }
+        if let Some(text) = texts.get_mut(input) {
+            text.text = format!("> {}", console.input);
+        }
+        for &entity in &[output, input] {
+            if console.is_open() {
+                hiddens.remove(entity);
+            } else if let Err(e) = hiddens.insert(entity, Hidden) {
+                error!("Failed to hide the console: {}", e);
+            }
+        }
+    }
+
+    fn create_entities(&self, res: &Resources) -> (Entity, Entity) {
+        let font = get_default_font(
+            &res.fetch::<Loader>(),
+            &res.fetch::<AssetStorage<FontAsset>>(),
+        );
+        let (mut transforms, mut texts) =
+            <(WriteStorage<'_, UiTransform>, WriteStorage<'_, UiText>)>::fetch(res);
+        let entities = res.fetch::<EntitiesRes>();
+
+        let output = entities.create();
+        let transform = UiTransform::new(
+            "console_output".to_string(),
+            Anchor::TopMiddle,
+            0.0,
+            -OUTPUT_HEIGHT / 2.0,
+            1000.0,
+            0.0,
+            OUTPUT_HEIGHT,
+        )
+        .with_stretch(Stretch::X { x_margin: 10.0 });
+        let mut text = UiText::new(font.clone(), String::new(), TEXT_COLOR, FONT_SIZE);
+        text.line_mode = LineMode::Wrap;
+        text.align = Anchor::BottomLeft;
+        transforms.insert(output, transform).expect("Unreachable");
+        texts.insert(output, text).expect("Unreachable");
+
+        let input = entities.create();
+        let transform = UiTransform::new(
+            "console_input".to_string(),
+            Anchor::TopMiddle,
+            0.0,
+            -OUTPUT_HEIGHT - FONT_SIZE,
+            1000.0,
+            0.0,
+            FONT_SIZE * 1.5,
+        )
+        .with_stretch(Stretch::X { x_margin: 10.0 });
+        let mut text = UiText::new(font, String::new(), TEXT_COLOR, FONT_SIZE);
+        text.align = Anchor::MiddleLeft;
+        transforms.insert(input, transform).expect("Unreachable");
+        texts.insert(input, text).expect("Unreachable");
+
+        (output, input)
+    }
+}
+
+impl<'a> RunNow<'a> for ConsoleSystem {
+    fn run_now(&mut self, res: &'a Resources) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("console_system");
+
+        self.handle_input(res);
+        self.execute(res);
+        self.update_ui(res);
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        <(
+            WriteStorage<'_, UiTransform>,
+            WriteStorage<'_, UiText>,
+            WriteStorage<'_, Hidden>,
+        )>::setup(res);
+        res.entry::<Console>().or_insert_with(Console::default);
+        self.reader = Some(
+            res.entry::<EventChannel<Event>>()
+                .or_insert_with(EventChannel::default)
+                .register_reader(),
+        );
+    }
+}
@@ -0,0 +1,395 @@
+//! Performance overlay showing the frame rate, a frame time graph, the entity count and the
+//! timings of the timed systems.
+
+use std::collections::VecDeque;
+
+use amethyst_assets::{AssetStorage, Loader};
+use amethyst_core::{
+    shrev::{EventChannel, ReaderId},
+    specs::{
+        prelude::{Entity, Join, Resources, RunNow, SystemData, WriteStorage},
+        world::EntitiesRes,
+    },
+    timing::duration_to_secs,
+    SystemTimings, Time,
+};
+use amethyst_renderer::{Hidden, Texture, TextureHandle};
+use log::error;
+use winit::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use crate::{get_default_font, Anchor, FontAsset, LineMode, UiText, UiTransform};
+
+/// Number of frames shown in the frame time graph.
+const GRAPH_SAMPLES: usize = 120;
+/// Width of a bar of the frame time graph, in pixels.
+const BAR_WIDTH: f32 = 2.0;
+/// Height of the frame time graph, in pixels.
+const GRAPH_HEIGHT: f32 = 80.0;
+/// Frame time filling the height of the graph, in seconds.
+const GRAPH_MAX_FRAME_TIME: f32 = 1.0 / 30.0;
+/// Frame time above which the bars are drawn as slow, in seconds.
+const TARGET_FRAME_TIME: f32 = 1.0 / 60.0;
+/// Interval between two refreshes of the text, in seconds, so it stays readable.
+const TEXT_REFRESH_INTERVAL: f32 = 0.25;
+const MARGIN: f32 = 10.0;
+const TEXT_WIDTH: f32 = 400.0;
+const TEXT_HEIGHT: f32 = 600.0;
+const FONT_SIZE: f32 = 14.0;
+const OVERLAY_Z: f32 = 1000.0;
+const TEXT_COLOR: [f32; 4] = [0.9, 0.9, 0.9, 1.0];
+const BACKGROUND_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+const FAST_COLOR: [f32; 4] = [0.2, 0.8, 0.2, 1.0];
+const SLOW_COLOR: [f32; 4] = [0.9, 0.7, 0.1, 1.0];
+const VERY_SLOW_COLOR: [f32; 4] = [0.9, 0.2, 0.2, 1.0];
+
+/// Resource holding the state of the performance overlay and the recent frame times.
+///
+/// The overlay is drawn and updated by the `DebugOverlaySystem`. The timings of the systems are
+/// read from the `SystemTimings` resource, filled by the systems made timed with
+/// `SystemExt::timed`.
+pub struct DebugOverlay {
+    open: bool,
+    frame_times: VecDeque<f32>,
+}
+
+impl Default for DebugOverlay {
+    fn default() -> Self {
+        DebugOverlay {
+            open: false,
+            frame_times: VecDeque::with_capacity(GRAPH_SAMPLES),
+        }
+    }
+}
+
+impl DebugOverlay {
+    /// Creates a hidden overlay.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Whether the overlay is shown.
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Shows or hides the overlay.
+    pub fn set_open(&mut self, open: bool) {
+        self.open = open;
+    }
+
+    /// The durations of the recent frames in seconds, the most recent last.
+    pub fn frame_times(&self) -> impl Iterator<Item = f32> + '_ {
+        self.frame_times.iter().cloned()
+    }
+
+    /// The average duration of the recent frames, in seconds.
+    pub fn average_frame_time(&self) -> f32 {
+        if self.frame_times.is_empty() {
+            return 0.0;
+        }
+        self.frame_times.iter().sum::<f32>() / self.frame_times.len() as f32
+    }
+
+    /// The average number of frames per second over the recent frames.
+    pub fn fps(&self) -> f32 {
+        let frame_time = self.average_frame_time();
+        if frame_time > 0.0 {
+            1.0 / frame_time
+        } else {
+            0.0
+        }
+    }
+
+    /// Records the duration of a frame, forgetting the oldest one if there are too many.
+    pub(crate) fn push_frame_time(&mut self, frame_time: f32) {
+        if self.frame_times.len() == GRAPH_SAMPLES {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(frame_time);
+    }
+}
+
+struct OverlayEntities {
+    text: Entity,
+    background: Entity,
+    bars: Vec<Entity>,
+    /// The textures of the fast, slow and very slow bars
+    textures: [TextureHandle; 3],
+}
+
+/// Shows the `DebugOverlay` in the top left corner of the screen when the toggle key is pressed,
+/// and keeps it up to date.
+///
+/// The system is thread local, as it reads the timings of the other systems.
+pub struct DebugOverlaySystem {
+    toggle_key: VirtualKeyCode,
+    reader: Option<ReaderId<Event>>,
+    entities: Option<OverlayEntities>,
+    /// Whether the entities of the overlay are currently shown
+    shown: bool,
+    since_refresh: f32,
+}
+
+impl DebugOverlaySystem {
+    /// Creates a new `DebugOverlaySystem`, shown and hidden with the given key.
+    pub fn new(toggle_key: VirtualKeyCode) -> Self {
+        DebugOverlaySystem {
+            toggle_key,
+            reader: None,
+            entities: None,
+            shown: false,
+            since_refresh: TEXT_REFRESH_INTERVAL,
+        }
+    }
+
+    fn handle_input(&mut self, res: &Resources) {
+        let events = res.fetch::<EventChannel<Event>>();
+        let mut overlay = res.fetch_mut::<DebugOverlay>();
+        let reader = self.reader.as_mut().expect(
+            "`DebugOverlaySystem::setup` was not called before `DebugOverlaySystem::run_now`",
+        );
+        for event in events.read(reader) {
+            if let Event::WindowEvent {
+                event:
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                state: ElementState::Pressed,
+                                virtual_keycode: Some(key),
+                                ..
+                            },
+                        ..
+                    },
+                ..
+            } = *event
+            {
+                if key == self.toggle_key {
+                    let open = !overlay.is_open();
+                    overlay.set_open(open);
+                }
+            }
+        }
+    }
+
+    fn update_ui(&mut self, res: &Resources) {
+        let overlay = res.fetch::<DebugOverlay>();
+        if !overlay.is_open() {
+            if self.shown {
+                self.set_shown(res, false);
+            }
+            return;
+        }
+        if self.entities.is_none() {
+            self.entities = Some(self.create_entities(res));
+        }
+        if !self.shown {
+            self.set_shown(res, true);
+            self.since_refresh = TEXT_REFRESH_INTERVAL;
+        }
+        let entities = self.entities.as_ref().expect("Unreachable");
+
+        let (mut transforms, mut textures) = <(
+            WriteStorage<'_, UiTransform>,
+            WriteStorage<'_, TextureHandle>,
+        )>::fetch(res);
+        let offset = GRAPH_SAMPLES - overlay.frame_times.len();
+        for (&bar, frame_time) in entities.bars[offset..].iter().zip(overlay.frame_times()) {
+            let height = (frame_time / GRAPH_MAX_FRAME_TIME).min(1.0) * GRAPH_HEIGHT;
+            if let Some(transform) = transforms.get_mut(bar) {
+                transform.height = height;
+                transform.local_y = -MARGIN - GRAPH_HEIGHT + height / 2.0;
+            }
+            let texture = if frame_time <= TARGET_FRAME_TIME {
+                &entities.textures[0]
+            } else if frame_time <= GRAPH_MAX_FRAME_TIME {
+                &entities.textures[1]
+            } else {
+                &entities.textures[2]
+            };
+            if let Err(e) = textures.insert(bar, texture.clone()) {
+                error!("Failed to update the frame time graph: {}", e);
+            }
+        }
+
+        self.since_refresh += res.fetch::<Time>().delta_real_seconds();
+        if self.since_refresh < TEXT_REFRESH_INTERVAL {
+            return;
+        }
+        self.since_refresh = 0.0;
+
+        let mut text = format!(
+            "FPS: {:.1} ({:.2} ms)\nEntities: {}",
+            overlay.fps(),
+            overlay.average_frame_time() * 1000.0,
+            (&*res.fetch::<EntitiesRes>()).join().count(),
+        );
+        if let Some(timings) = res.try_fetch::<SystemTimings>() {
+            let timings = timings.timings();
+            if !timings.is_empty() {
+                text.push_str("\nSystems:");
+            }
+            for (name, duration) in timings {
+                text.push_str(&format!(
+                    "\n  {}: {:.3} ms",
+                    name,
+                    duration_to_secs(duration) * 1000.0
+                ));
+            }
+        }
+        if let Some(ui_text) = WriteStorage::<'_, UiText>::fetch(res).get_mut(entities.text) {
+            ui_text.text = text;
+        }
+    }
+
+    fn set_shown(&mut self, res: &Resources, shown: bool) {
+        self.shown = shown;
+        let entities = match self.entities {
+            Some(ref entities) => entities,
+            None => return,
+        };
+        let mut hiddens = WriteStorage::<'_, Hidden>::fetch(res);
+        let labels = [entities.text, entities.background];
+        for &entity in labels.iter().chain(entities.bars.iter()) {
+            if shown {
+                hiddens.remove(entity);
+            } else if let Err(e) = hiddens.insert(entity, Hidden) {
+                error!("Failed to hide the debug overlay: {}", e);
+            }
+        }
+    }
+
+    fn create_entities(&self, res: &Resources) -> OverlayEntities {
+        let loader = res.fetch::<Loader>();
+        let font = get_default_font(&loader, &res.fetch::<AssetStorage<FontAsset>>());
+        let texture_storage = res.fetch::<AssetStorage<Texture>>();
+        let load_color =
+            |color: [f32; 4]| loader.load_from_data(color.into(), (), &texture_storage);
+        let background_texture = load_color(BACKGROUND_COLOR);
+        let textures = [
+            load_color(FAST_COLOR),
+            load_color(SLOW_COLOR),
+            load_color(VERY_SLOW_COLOR),
+        ];
+
+        let (mut transforms, mut texts, mut texture_handles) = <(
+            WriteStorage<'_, UiTransform>,
+            WriteStorage<'_, UiText>,
+            WriteStorage<'_, TextureHandle>,
+        )>::fetch(res);
+        let entities = res.fetch::<EntitiesRes>();
+        let graph_width = GRAPH_SAMPLES as f32 * BAR_WIDTH;
+
+        let background = entities.create();
+        let transform = UiTransform::new(
+            "debug_overlay_graph".to_string(),
+            Anchor::TopLeft,
+            MARGIN + graph_width / 2.0,
+            -MARGIN - GRAPH_HEIGHT / 2.0,
+            OVERLAY_Z - 1.0,
+            graph_width,
+            GRAPH_HEIGHT,
+        )
+        .as_transparent();
+        transforms
+            .insert(background, transform)
+            .expect("Unreachable");
+        texture_handles
+            .insert(background, background_texture)
+            .expect("Unreachable");
+
+        let bars = (0..GRAPH_SAMPLES)
+            .map(|i| {
+                let bar = entities.create();
+                let transform = UiTransform::new(
+                    format!("debug_overlay_bar_{}", i),
+                    Anchor::TopLeft,
+                    MARGIN + (i as f32 + 0.5) * BAR_WIDTH,
+                    -MARGIN - GRAPH_HEIGHT,
+                    OVERLAY_Z,
+                    BAR_WIDTH,
+                    0.0,
+                )
+                .as_transparent();
+                transforms.insert(bar, transform).expect("Unreachable");
+                texture_handles
+                    .insert(bar, textures[0].clone())
+                    .expect("Unreachable");
+                bar
+            })
+            .collect();
+
+        let text = entities.create();
+        let transform = UiTransform::new(
+            "debug_overlay_text".to_string(),
+            Anchor::TopLeft,
+            MARGIN + TEXT_WIDTH / 2.0,
+            -2.0 * MARGIN - GRAPH_HEIGHT - TEXT_HEIGHT / 2.0,
+            OVERLAY_Z,
+            TEXT_WIDTH,
+            TEXT_HEIGHT,
+        )
+        .as_transparent();
+        let mut ui_text = UiText::new(font, String::new(), TEXT_COLOR, FONT_SIZE);
+        ui_text.line_mode = LineMode::Wrap;
+        ui_text.align = Anchor::TopLeft;
+        transforms.insert(text, transform).expect("Unreachable");
+        texts.insert(text, ui_text).expect("Unreachable");
+
+        OverlayEntities {
+            text,
+            background,
+            bars,
+            textures,
+        }
+    }
+}
+
+impl<'a> RunNow<'a> for DebugOverlaySystem {
+    fn run_now(&mut self, res: &'a Resources) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("debug_overlay_system");
+
+        self.handle_input(res);
+        let frame_time = res.fetch::<Time>().delta_real_seconds();
+        res.fetch_mut::<DebugOverlay>().push_frame_time(frame_time);
+        self.update_ui(res);
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        <(
+            WriteStorage<'_, UiTransform>,
+            WriteStorage<'_, UiText>,
+            WriteStorage<'_, TextureHandle>,
+            WriteStorage<'_, Hidden>,
+        )>::setup(res);
+        res.entry::<DebugOverlay>()
+            .or_insert_with(DebugOverlay::default);
+        self.reader = Some(
+            res.entry::<EventChannel<Event>>()
+                .or_insert_with(EventChannel::default)
+                .register_reader(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_times_are_bounded() {
+        let mut overlay = DebugOverlay::new();
+        assert_eq!(overlay.fps(), 0.0);
+        for _ in 0..GRAPH_SAMPLES {
+            overlay.push_frame_time(0.1);
+        }
+        overlay.push_frame_time(0.02);
+        assert_eq!(overlay.frame_times().count(), GRAPH_SAMPLES);
+        assert_eq!(overlay.frame_times().last(), Some(0.02));
+        let expected = (0.1 * (GRAPH_SAMPLES - 1) as f32 + 0.02) / GRAPH_SAMPLES as f32;
+        assert!((overlay.average_frame_time() - expected).abs() < 1e-6);
+    }
+}
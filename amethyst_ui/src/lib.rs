@@ -3,11 +3,13 @@
 #![warn(missing_docs, rust_2018_idioms, rust_2018_compatibility)]
 
 pub use self::{
-    bundle::UiBundle,
+    bundle::{ConsoleBundle, DebugOverlayBundle, UiBundle},
     button::{
         UiButton, UiButtonAction, UiButtonActionRetrigger, UiButtonActionRetriggerSystem,
         UiButtonActionType, UiButtonBuilder, UiButtonBuilderResources, UiButtonSystem,
     },
+    console::{CommandHandler, Console, ConsoleSystem},
+    debug_overlay::{DebugOverlay, DebugOverlaySystem},
     event::{targeted, Interactable, UiEvent, UiEventType, UiMouseSystem},
     event_retrigger::{EventReceiver, EventRetriggerSystem},
     font::{
@@ -28,10 +30,13 @@ pub use self::{
     text::{LineMode, TextEditing, TextEditingMouseSystem, UiText},
     text_editing::TextEditingInputSystem,
     transform::{UiFinder, UiTransform},
+    world_text::{DrawWorldText, WorldText},
 };
 
 mod bundle;
 mod button;
+mod console;
+mod debug_overlay;
 mod event;
 mod event_retrigger;
 mod font;
@@ -46,3 +51,4 @@ mod sound;
 mod text;
 mod text_editing;
 mod transform;
+mod world_text;
@@ -120,19 +120,7 @@ impl Pass for DrawUi {
         let data = Shape::Plane(None).generate_vertices::<Vec<PosTex>>(None);
         self.mesh = Some(Mesh::build(data).build(&mut effect.factory)?);
 
-        // Create the effect
-        use std::mem;
-        effect
-            .simple(VERT_SRC, FRAG_SRC)
-            .with_raw_constant_buffer(
-                "VertexArgs",
-                mem::size_of::<<VertexArgs as Uniform>::Std140>(),
-                1,
-            )
-            .with_raw_vertex_buffer(PosTex::ATTRIBUTES, PosTex::size() as ElemStride, 0)
-            .with_texture("albedo")
-            .with_blended_output("color", ColorMask::all(), blend::ALPHA, None)
-            .build()
+        build_effect(effect)
     }
 
     fn apply<'a, 'b: 'a>(
@@ -616,7 +604,24 @@ impl Pass for DrawUi {
     }
 }
 
-fn multiply_colors(a: [f32; 4], b: [f32; 4]) -> [f32; 4] {
+/// Creates the effect drawing the quads of the UI, also used by the `DrawWorldText` pass for its
+/// render targets.
+pub(crate) fn build_effect(effect: NewEffect<'_>) -> Result<Effect, Error> {
+    use std::mem;
+    effect
+        .simple(VERT_SRC, FRAG_SRC)
+        .with_raw_constant_buffer(
+            "VertexArgs",
+            mem::size_of::<<VertexArgs as Uniform>::Std140>(),
+            1,
+        )
+        .with_raw_vertex_buffer(PosTex::ATTRIBUTES, PosTex::size() as ElemStride, 0)
+        .with_texture("albedo")
+        .with_blended_output("color", ColorMask::all(), blend::ALPHA, None)
+        .build()
+}
+
+pub(crate) fn multiply_colors(a: [f32; 4], b: [f32; 4]) -> [f32; 4] {
     [a[0] * b[0], a[1] * b[1], a[2] * b[2], a[3] * b[3]]
 }
 
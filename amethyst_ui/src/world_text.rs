@@ -0,0 +1,203 @@
+//! Text drawn in the world instead of on the screen.
+
+use derive_new::new;
+use fnv::{FnvHashMap as HashMap, FnvHashSet as HashSet};
+use gfx::preset::depth::LESS_EQUAL_TEST;
+use gfx_glyph::{
+    BuiltInLineBreaker, FontId, GlyphBrush, GlyphBrushBuilder, Layout, Scale, SectionText,
+    VariedSection,
+};
+use log::error;
+
+#[cfg(feature = "profiler")]
+use thread_profiler::profile_scope;
+
+use amethyst_assets::AssetStorage;
+use amethyst_core::{
+    nalgebra::Matrix4,
+    specs::prelude::{Component, DenseVecStorage, Join, Read, ReadStorage},
+    transform::GlobalTransform,
+};
+use amethyst_error::Error;
+use amethyst_renderer::{
+    get_camera,
+    pipe::{
+        pass::{Pass, PassData},
+        Effect, NewEffect,
+    },
+    ActiveCamera, Camera, Encoder, Factory, Hidden, HiddenPropagate, Resources, Rgba,
+};
+
+use crate::{
+    pass::{build_effect, multiply_colors},
+    Anchor, FontAsset, FontHandle,
+};
+
+/// A component drawing text in the world by the `DrawWorldText` pass, such as damage numbers or
+/// name tags.
+///
+/// The text is on the XY plane of the `GlobalTransform` of the entity, one unit per pixel of the
+/// font size, so it moves and scales with the camera like sprites. Scale the `Transform` down to
+/// draw it in a world with smaller units.
+#[derive(Clone, Debug)]
+pub struct WorldText {
+    /// The string rendered by this.
+    pub text: String,
+    /// The height of a line of text in units of the entity, and in pixels of the glyphs.
+    pub font_size: f32,
+    /// The color of the rendered text, using a range of 0.0 to 1.0 per channel.
+    pub color: [f32; 4],
+    /// The font used for rendering.
+    pub font: FontHandle,
+    /// Point of the text placed on the entity, `Anchor::Middle` centers it.
+    pub align: Anchor,
+}
+
+impl WorldText {
+    /// Initializes a new `WorldText` centered on its entity.
+    ///
+    /// # Parameters
+    ///
+    /// * `font`: A handle to a `Font` asset
+    /// * `text`: the glyphs to render
+    /// * `color`: RGBA color with a maximum of 1.0 and a minimum of 0.0 for each channel
+    /// * `font_size`: the height of a line of text
+    pub fn new(font: FontHandle, text: String, color: [f32; 4], font_size: f32) -> WorldText {
+        WorldText {
+            text,
+            font_size,
+            color,
+            font,
+            align: Anchor::Middle,
+        }
+    }
+}
+
+impl Component for WorldText {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Draw the `WorldText` of the entities with a `GlobalTransform`, as seen by the active camera.
+///
+/// The text is tested against the depth buffer without writing to it, so this pass should be
+/// added after the passes drawing the world and before `DrawUi`.
+#[derive(new)]
+pub struct DrawWorldText {
+    /// Glyph brushes of the fonts, by id of their handle.
+    #[new(default)]
+    glyph_brushes: HashMap<u32, GlyphBrush<'static, Resources, Factory>>,
+}
+
+impl<'a> PassData<'a> for DrawWorldText {
+    type Data = (
+        Read<'a, ActiveCamera>,
+        ReadStorage<'a, Camera>,
+        ReadStorage<'a, GlobalTransform>,
+        Read<'a, AssetStorage<FontAsset>>,
+        ReadStorage<'a, WorldText>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, HiddenPropagate>,
+        ReadStorage<'a, Rgba>,
+    );
+}
+
+impl Pass for DrawWorldText {
+    fn compile(&mut self, effect: NewEffect<'_>) -> Result<Effect, Error> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("world_text_pass_build");
+
+        // The glyph brushes have their own pipelines, the effect only provides the targets.
+        build_effect(effect)
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        factory: Factory,
+        (
+            active,
+            camera,
+            global,
+            font_storage,
+            world_text,
+            hidden,
+            hidden_prop,
+            rgba,
+        ): <Self as PassData<'a>>::Data,
+    ) {
+        #[cfg(feature = "profiler")]
+        profile_scope!("world_text_pass_apply");
+
+        let view_proj = get_camera(active, &camera, &global)
+            .and_then(|(camera, global)| global.0.try_inverse().map(|view| camera.proj * view))
+            .unwrap_or_else(Matrix4::identity);
+        // Glyphs are given in normalized device coordinates of the target, with the section at
+        // its top-left corner. They are brought back to pixels on the XY plane of the entity.
+        let (width, height, _, _) = effect.data.out_blends[0].get_dimensions();
+        let (half_width, half_height) = (f32::from(width) / 2.0, f32::from(height) / 2.0);
+        #[rustfmt::skip]
+        let glyphs_to_local = Matrix4::new(
+            half_width, 0.0, 0.0, half_width,
+            0.0, half_height, 0.0, -half_height,
+            0.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        );
+
+        let mut unused_glyph_brushes = self.glyph_brushes.keys().cloned().collect::<HashSet<_>>();
+
+        for (text, global, rgba, _, _) in
+            (&world_text, &global, rgba.maybe(), !&hidden, !&hidden_prop).join()
+        {
+            let font = match font_storage.get(&text.font) {
+                Some(font) => font,
+                None => continue,
+            };
+            unused_glyph_brushes.remove(&text.font.id());
+            let brush = self.glyph_brushes.entry(text.font.id()).or_insert_with(|| {
+                GlyphBrushBuilder::using_font(font.0.clone())
+                    .depth_test(LESS_EQUAL_TEST)
+                    .build(factory.clone())
+            });
+
+            let rgba: [f32; 4] = rgba.cloned().unwrap_or(Rgba::WHITE).into();
+            let section = VariedSection {
+                screen_position: (0.0, 0.0),
+                bounds: (std::f32::INFINITY, std::f32::INFINITY),
+                z: 0.0,
+                // Wrapping without bounds keeps the line breaks of the text.
+                layout: Layout::Wrap {
+                    line_breaker: BuiltInLineBreaker::UnicodeLineBreaker,
+                    h_align: text.align.horizontal_align(),
+                    v_align: text.align.vertical_align(),
+                },
+                text: vec![SectionText {
+                    text: &text.text,
+                    scale: Scale::uniform(text.font_size),
+                    color: multiply_colors(text.color, rgba),
+                    font_id: FontId(0),
+                }],
+            };
+
+            let transform: [[f32; 4]; 4] = (view_proj * global.0 * glyphs_to_local).into();
+            brush.queue(section);
+            if let Err(err) = brush.draw_queued_with_transform(
+                transform,
+                encoder,
+                &effect.data.out_blends[0],
+                &effect
+                    .data
+                    .out_depth
+                    .as_ref()
+                    .expect("Unable to get depth of effect")
+                    .0,
+            ) {
+                error!("Unable to draw world text! Error: {:?}", err);
+            }
+        }
+
+        for id in unused_glyph_brushes.drain() {
+            self.glyph_brushes.remove(&id);
+        }
+    }
+}
@@ -1,6 +1,6 @@
-//! Provides the directory of the executable.
+//! Provides the directory of the executable, and the directories where the user's data is stored.
 
-use std::{env, io, path};
+use std::{env, fs, io, path};
 
 /// Returns the cargo manifest directory when running the executable with cargo or the directory in
 /// which the executable resides otherwise, traversing symlinks if necessary.
@@ -42,3 +42,106 @@ where
 {
     Ok(application_root_dir()?.join(path))
 }
+
+/// The directories where an application stores the data, configuration and cache of the user,
+/// following the conventions of the platform.
+///
+/// * Linux: `$XDG_DATA_HOME`, `$XDG_CONFIG_HOME` and `$XDG_CACHE_HOME`.
+/// * macOS: `~/Library/Application Support`, `~/Library/Preferences` and `~/Library/Caches`.
+/// * Windows: `{FOLDERID_RoamingAppData}` for the data and configuration, and
+///   `{FOLDERID_LocalAppData}` for the cache.
+///
+/// Each directory is joined with the name of the application.
+///
+/// The directories are created when they are first requested. This type is meant to be added to
+/// the world as a resource, so systems can find where to save without hardcoding paths.
+#[derive(Debug, Clone)]
+pub struct ApplicationDirs {
+    data: path::PathBuf,
+    config: path::PathBuf,
+    cache: path::PathBuf,
+}
+
+impl ApplicationDirs {
+    /// Finds the directories of the application with the given name.
+    ///
+    /// Returns an error if the platform has no such directories, or the home directory of the
+    /// user can not be found.
+    pub fn new<N>(name: N) -> Result<Self, io::Error>
+    where
+        N: AsRef<path::Path>,
+    {
+        let find = |dir: Option<path::PathBuf>, kind: &str| {
+            dir.map(|dir| dir.join(name.as_ref())).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Failed to find the {} directory of the user", kind),
+                )
+            })
+        };
+        Ok(ApplicationDirs {
+            data: find(dirs::data_dir(), "data")?,
+            config: find(dirs::config_dir(), "config")?,
+            cache: find(dirs::cache_dir(), "cache")?,
+        })
+    }
+
+    /// Uses subdirectories of the given directory, for portable installations or tests.
+    pub fn portable<P>(root: P) -> Self
+    where
+        P: AsRef<path::Path>,
+    {
+        let root = root.as_ref();
+        ApplicationDirs {
+            data: root.join("data"),
+            config: root.join("config"),
+            cache: root.join("cache"),
+        }
+    }
+
+    /// The directory of the user's data, created if it does not exist.
+    pub fn data_dir(&self) -> Result<path::PathBuf, io::Error> {
+        create(&self.data)
+    }
+
+    /// The directory of the user's configuration, created if it does not exist.
+    pub fn config_dir(&self) -> Result<path::PathBuf, io::Error> {
+        create(&self.config)
+    }
+
+    /// The directory of the cached data, which can be deleted at any time, created if it does not
+    /// exist.
+    pub fn cache_dir(&self) -> Result<path::PathBuf, io::Error> {
+        create(&self.cache)
+    }
+
+    /// The `saves` subdirectory of the data directory, created if it does not exist.
+    pub fn save_dir(&self) -> Result<path::PathBuf, io::Error> {
+        create(&self.data.join("saves"))
+    }
+}
+
+fn create(dir: &path::Path) -> Result<path::PathBuf, io::Error> {
+    fs::create_dir_all(dir)?;
+    Ok(dir.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn portable_dirs_are_created_on_demand() {
+        let root = env::temp_dir().join("amethyst_utils_portable_dirs");
+        let _ = fs::remove_dir_all(&root);
+        let dirs = ApplicationDirs::portable(&root);
+        assert!(!root.exists());
+
+        let save_dir = dirs.save_dir().unwrap();
+        assert_eq!(save_dir, root.join("data").join("saves"));
+        assert!(save_dir.is_dir());
+        assert!(!root.join("config").exists());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
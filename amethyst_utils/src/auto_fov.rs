@@ -2,7 +2,7 @@
 
 use amethyst_assets::PrefabData;
 use amethyst_core::specs::{
-    Component, Entity, HashMapStorage, Join, ReadExpect, ReadStorage, System, WriteStorage,
+    Component, Entity, HashMapStorage, Join, ReadExpect, System, WriteStorage,
 };
 use amethyst_derive::PrefabData;
 use amethyst_error::Error;
@@ -10,6 +10,31 @@ use amethyst_renderer::{Camera, ScreenDimensions};
 
 use serde::{Deserialize, Serialize};
 
+/// How the view of a camera follows the aspect ratio of the screen
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum FovPolicy {
+    /// Grows the horizontal FOV with the aspect ratio according to `fovx_growth_rate`, keeping the
+    /// vertical FOV of the camera.
+    Growth,
+
+    /// Keeps the vertical FOV of the base aspect ratio, widening the horizontal FOV on wider
+    /// screens. The vertical FOV shrinks once the horizontal FOV reaches `max_fovx`.
+    HorPlus,
+
+    /// Keeps the horizontal FOV at `base_fovx`, shrinking the vertical FOV on wider screens.
+    VertMinus,
+
+    /// Always shows the whole view of the base aspect ratio, using `HorPlus` on screens wider than
+    /// it and `VertMinus` on narrower ones, such as portrait screens.
+    Fit,
+}
+
+impl Default for FovPolicy {
+    fn default() -> Self {
+        FovPolicy::Growth
+    }
+}
+
 /// A component describing the behavior of the camera in accordance with the screen dimensions
 #[derive(Clone, Deserialize, PrefabData, Serialize)]
 #[prefab(Component)]
@@ -32,6 +57,13 @@ pub struct AutoFov {
 
     /// The maximum value the horizontal FOV can have
     max_fovx: f32,
+
+    /// How the view follows the screen aspect ratio
+    policy: FovPolicy,
+
+    /// The extents of an orthographic camera when it was first adjusted
+    #[serde(skip)]
+    ortho_base: Option<(f32, f32)>,
 }
 
 impl AutoFov {
@@ -87,6 +119,13 @@ impl AutoFov {
         self.max_fovx
     }
 
+    /// How the view follows the screen aspect ratio
+    ///
+    /// Defaults to `FovPolicy::Growth`.
+    pub fn policy(&self) -> FovPolicy {
+        self.policy
+    }
+
     /// Sets `base_fovx` to the given value
     ///
     /// This function panics if the given value is not between `min_fovx` and `max_fovx`.
@@ -178,6 +217,27 @@ impl AutoFov {
         self.max_fovx = max;
     }
 
+    /// Sets `policy` to the given value
+    pub fn set_policy(&mut self, policy: FovPolicy) {
+        self.policy = policy;
+    }
+
+    /// Computes the new horizontal and vertical FOV from the current screen aspect ratio and
+    /// vertical FOV, according to the policy
+    pub fn new_fov(&self, current_aspect_ratio: f32, fovy: f32) -> (f32, f32) {
+        let fovx = match self.resolved_policy(current_aspect_ratio) {
+            FovPolicy::Growth => return (self.new_fovx(current_aspect_ratio, fovy), fovy),
+            FovPolicy::HorPlus => {
+                let base_fovy = fovy_from_fovx(self.base_fovx, self.base_aspect_value());
+                fovx_from_fovy(base_fovy, current_aspect_ratio)
+                    .max(self.min_fovx)
+                    .min(self.max_fovx)
+            }
+            _ => self.base_fovx,
+        };
+        (fovx, fovy_from_fovx(fovx, current_aspect_ratio))
+    }
+
     /// Computes the new horizontal FOV from the current screen aspect ratio and vertical FOV
     pub fn new_fovx(&self, current_aspect_ratio: f32, fovy: f32) -> f32 {
         let delta_aspect = current_aspect_ratio - self.base_aspect_value();
@@ -193,6 +253,41 @@ impl AutoFov {
         new_fovx.max(self.min_fovx).min(self.max_fovx)
     }
 
+    /// Computes the new horizontal and vertical scales of an orthographic projection, from the
+    /// current screen aspect ratio and the scales at the base aspect ratio
+    ///
+    /// `FovPolicy::Growth` keeps the vertical extent like `FovPolicy::HorPlus`, as the FOV limits
+    /// have no meaning for orthographic projections.
+    pub fn new_ortho_scales(
+        &self,
+        current_aspect_ratio: f32,
+        base_scales: (f32, f32),
+    ) -> (f32, f32) {
+        // The signs are kept apart, as the axes of orthographic projections can be flipped.
+        let (base_x, base_y) = base_scales;
+        match self.resolved_policy(current_aspect_ratio) {
+            FovPolicy::VertMinus => (
+                base_x,
+                base_x.abs() * current_aspect_ratio * base_y.signum(),
+            ),
+            _ => (
+                base_y.abs() / current_aspect_ratio * base_x.signum(),
+                base_y,
+            ),
+        }
+    }
+
+    /// Replaces `FovPolicy::Fit` with the policy it uses at the given aspect ratio
+    fn resolved_policy(&self, current_aspect_ratio: f32) -> FovPolicy {
+        match self.policy {
+            FovPolicy::Fit if current_aspect_ratio >= self.base_aspect_value() => {
+                FovPolicy::HorPlus
+            }
+            FovPolicy::Fit => FovPolicy::VertMinus,
+            policy => policy,
+        }
+    }
+
     #[inline]
     fn base_aspect_value(&self) -> f32 {
         self.base_aspect_ratio.0 as f32 / self.base_aspect_ratio.1 as f32
@@ -212,6 +307,8 @@ impl Default for AutoFov {
             base_aspect_ratio: (16, 9),
             min_fovx: 0.1,
             max_fovx: std::f32::consts::PI,
+            policy: FovPolicy::Growth,
+            ortho_base: None,
         }
     }
 }
@@ -221,6 +318,10 @@ impl Default for AutoFov {
 /// For a camera component to be managed by this system, the entity with the camera component should
 /// also have an `AutoFov` component attached to it.
 ///
+/// The extents of orthographic cameras are adjusted according to the `FovPolicy` of the `AutoFov`,
+/// relative to their extents when the system first sees them, which are assumed to match the
+/// base aspect ratio.
+///
 /// If the camera is being loaded by a prefab, it is best to have the `PrefabLoaderSystem` loading
 /// the camera as a dependency of this system. It enables the system to adjust the camera right
 /// after it is created -- simply put, in the same frame.
@@ -229,21 +330,49 @@ pub struct AutoFovSystem;
 impl<'a> System<'a> for AutoFovSystem {
     type SystemData = (
         ReadExpect<'a, ScreenDimensions>,
-        ReadStorage<'a, AutoFov>,
+        WriteStorage<'a, AutoFov>,
         WriteStorage<'a, Camera>,
     );
 
-    fn run(&mut self, (screen, auto_fovs, mut cameras): Self::SystemData) {
+    fn run(&mut self, (screen, mut auto_fovs, mut cameras): Self::SystemData) {
         let current_aspect = screen.aspect_ratio();
 
-        for (camera, auto_fov) in (&mut cameras, &auto_fovs).join() {
-            let fovy = get_fovy(camera);
-            let fovx = auto_fov.new_fovx(current_aspect, fovy);
-            set_aspect(camera, fovx / fovy);
+        for (camera, auto_fov) in (&mut cameras, &mut auto_fovs).join() {
+            if is_orthographic(camera) {
+                let base = *auto_fov
+                    .ortho_base
+                    .get_or_insert((camera.proj[(0, 0)], camera.proj[(1, 1)]));
+                let (scale_x, scale_y) = auto_fov.new_ortho_scales(current_aspect, base);
+                camera.proj[(0, 0)] = scale_x;
+                camera.proj[(1, 1)] = scale_y;
+            } else if auto_fov.policy == FovPolicy::Growth {
+                let fovy = get_fovy(camera);
+                let fovx = auto_fov.new_fovx(current_aspect, fovy);
+                set_aspect(camera, fovx / fovy);
+            } else {
+                let (fovx, fovy) = auto_fov.new_fov(current_aspect, get_fovy(camera));
+                camera.proj[(0, 0)] = 1.0 / (fovx / 2.0).tan();
+                camera.proj[(1, 1)] = 1.0 / (fovy / 2.0).tan();
+            }
         }
     }
 }
 
+#[inline]
+fn is_orthographic(camera: &Camera) -> bool {
+    camera.proj[(3, 3)] != 0.0
+}
+
+#[inline]
+fn fovx_from_fovy(fovy: f32, aspect_ratio: f32) -> f32 {
+    ((fovy / 2.0).tan() * aspect_ratio).atan() * 2.0
+}
+
+#[inline]
+fn fovy_from_fovx(fovx: f32, aspect_ratio: f32) -> f32 {
+    ((fovx / 2.0).tan() / aspect_ratio).atan() * 2.0
+}
+
 #[inline]
 fn get_fovy(camera: &Camera) -> f32 {
     (1.0 / camera.proj[(1, 1)]).atan() * 2.0
@@ -253,3 +382,42 @@ fn get_fovy(camera: &Camera) -> f32 {
 fn set_aspect(camera: &mut Camera, new_aspect: f32) {
     camera.proj[(0, 0)] = camera.proj[(1, 1)] / new_aspect;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    #[test]
+    fn hor_plus_keeps_base_fovy() {
+        let mut auto_fov = AutoFov::new();
+        auto_fov.set_policy(FovPolicy::HorPlus);
+        let base_fovy = fovy_from_fovx(auto_fov.base_fovx(), 16.0 / 9.0);
+
+        let (fovx, fovy) = auto_fov.new_fov(21.0 / 9.0, 0.5);
+        assert!((fovy - base_fovy).abs() < EPSILON);
+        assert!(fovx > auto_fov.base_fovx());
+    }
+
+    #[test]
+    fn fit_keeps_base_fovx_on_portrait_screens() {
+        let mut auto_fov = AutoFov::new();
+        auto_fov.set_policy(FovPolicy::Fit);
+        let base_fovy = fovy_from_fovx(auto_fov.base_fovx(), 16.0 / 9.0);
+
+        let (fovx, fovy) = auto_fov.new_fov(9.0 / 16.0, 0.5);
+        assert!((fovx - auto_fov.base_fovx()).abs() < EPSILON);
+        assert!(fovy > base_fovy);
+    }
+
+    #[test]
+    fn ortho_scales_keep_flipped_axes() {
+        let mut auto_fov = AutoFov::new();
+        auto_fov.set_policy(FovPolicy::VertMinus);
+        assert_eq!(auto_fov.new_ortho_scales(2.0, (0.5, -1.0)), (0.5, -1.0));
+        auto_fov.set_policy(FovPolicy::HorPlus);
+        assert_eq!(auto_fov.new_ortho_scales(2.0, (0.5, -1.0)), (0.5, -1.0));
+        assert_eq!(auto_fov.new_ortho_scales(4.0, (0.5, -1.0)), (0.25, -1.0));
+    }
+}
@@ -0,0 +1,460 @@
+//! Provides a 2D camera following an entity.
+
+use amethyst_core::{
+    nalgebra::{Orthographic3, Point3, Vector2},
+    specs::{Component, DenseVecStorage, Entity, Join, Read, ReadStorage, System, WriteStorage},
+    GlobalTransform, Parent, Time, Transform,
+};
+use amethyst_renderer::Camera;
+
+use crate::ortho_camera::CameraOrthoWorldCoordinates;
+
+/// `Component` attached to the entities followed by the cameras, to aim the cameras at a point
+/// relative to them.
+///
+/// Cameras whose `CameraFollow` has no target entity follow the center of all the entities with
+/// this component, such as the players of a local multiplayer game.
+#[derive(Debug, Clone, Default)]
+pub struct CameraTarget {
+    /// The point the cameras aim at, relative to the position of the entity.
+    pub offset: Vector2<f32>,
+}
+
+impl CameraTarget {
+    /// Creates a target aiming the cameras at the given point relative to the entity.
+    pub fn with_offset(x: f32, y: f32) -> Self {
+        CameraTarget {
+            offset: Vector2::new(x, y),
+        }
+    }
+}
+
+impl Component for CameraTarget {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// `Component` attached to a camera's entity to make it follow another entity on the X and Y axes.
+///
+/// The camera only moves once the target leaves the dead zone, a rectangle centered on the view.
+/// The view can lead the target in the direction it moves, and be kept inside world bounds.
+/// You must add the `CameraFollowSystem` to your dispatcher for this to take effect.
+#[derive(Debug, Clone)]
+pub struct CameraFollow {
+    /// The entity followed by the camera, or `None` to follow the center of the entities with a
+    /// `CameraTarget`.
+    pub target: Option<Entity>,
+    /// The width and height of the dead zone, in world units.
+    pub dead_zone: Vector2<f32>,
+    /// The time constant in seconds of the smoothing of the movement. `0.0` disables it.
+    pub smoothing: f32,
+    /// Whether the movement is smoothed by a critically damped spring, easing in and out of the
+    /// movement without overshooting, instead of slowing down exponentially.
+    pub spring: bool,
+    /// How far ahead of the target the view leads, in seconds of the target's velocity.
+    pub look_ahead: f32,
+    /// The world coordinates the view is kept inside of.
+    ///
+    /// The whole view is kept inside with orthographic cameras, and only its center with
+    /// perspective cameras. A view larger than the bounds is centered on them.
+    pub bounds: Option<CameraOrthoWorldCoordinates>,
+    last_target: Option<Vector2<f32>>,
+    velocity: Vector2<f32>,
+}
+
+impl CameraFollow {
+    /// Creates a component following the target without dead zone, smoothing, look ahead or
+    /// bounds.
+    pub fn new(target: Entity) -> Self {
+        CameraFollow {
+            target: Some(target),
+            ..CameraFollow::tagged()
+        }
+    }
+
+    /// Creates a component following the center of the entities with a `CameraTarget`, without
+    /// dead zone, smoothing, look ahead or bounds.
+    pub fn tagged() -> Self {
+        CameraFollow {
+            target: None,
+            dead_zone: Vector2::zeros(),
+            smoothing: 0.0,
+            spring: false,
+            look_ahead: 0.0,
+            bounds: None,
+            last_target: None,
+            velocity: Vector2::zeros(),
+        }
+    }
+
+    /// Sets the size of the dead zone, in world units.
+    pub fn with_dead_zone(mut self, width: f32, height: f32) -> Self {
+        self.dead_zone = Vector2::new(width, height);
+        self
+    }
+
+    /// Sets the time constant in seconds of the smoothing of the movement.
+    pub fn with_smoothing(mut self, time_constant: f32) -> Self {
+        self.smoothing = time_constant;
+        self.spring = false;
+        self
+    }
+
+    /// Smoothes the movement with a critically damped spring, with the given time constant in
+    /// seconds.
+    pub fn with_spring(mut self, time_constant: f32) -> Self {
+        self.smoothing = time_constant;
+        self.spring = true;
+        self
+    }
+
+    /// Sets how far ahead of the target the view leads, in seconds of the target's velocity.
+    pub fn with_look_ahead(mut self, seconds: f32) -> Self {
+        self.look_ahead = seconds;
+        self
+    }
+
+    /// Sets the world coordinates the view is kept inside of.
+    pub fn with_bounds(mut self, bounds: CameraOrthoWorldCoordinates) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    /// Computes the new center of the view from the position of the target.
+    ///
+    /// `view` is the left, right, bottom and top of the view relative to the camera position, if
+    /// the camera is orthographic.
+    fn follow(
+        &mut self,
+        center: Vector2<f32>,
+        target: Vector2<f32>,
+        view: Option<(f32, f32, f32, f32)>,
+        delta_seconds: f32,
+    ) -> Vector2<f32> {
+        let velocity = match self.last_target {
+            Some(last) if delta_seconds > 0.0 => (target - last) / delta_seconds,
+            _ => Vector2::zeros(),
+        };
+        self.last_target = Some(target);
+        let focus = target + velocity * self.look_ahead;
+
+        let half_zone = self.dead_zone / 2.0;
+        let goal = Vector2::new(
+            dead_zone_goal(center.x, focus.x, half_zone.x),
+            dead_zone_goal(center.y, focus.y, half_zone.y),
+        );
+
+        let mut center = if self.smoothing <= 0.0 {
+            goal
+        } else if self.spring {
+            // Closed form step of a critically damped spring, with the approximation of the
+            // exponential of "Game Programming Gems 4", 1.10.
+            let omega = 2.0 / self.smoothing;
+            let x = omega * delta_seconds;
+            let decay = 1.0 / (1.0 + x + 0.48 * x * x + 0.235 * x * x * x);
+            let change = center - goal;
+            let temp = (self.velocity + change * omega) * delta_seconds;
+            self.velocity = (self.velocity - temp * omega) * decay;
+            goal + (change + temp) * decay
+        } else {
+            let factor = 1.0 - (-delta_seconds / self.smoothing).exp();
+            center + (goal - center) * factor
+        };
+
+        if let Some(ref bounds) = self.bounds {
+            let (half_width, half_height) = view
+                .map(|(left, right, bottom, top)| {
+                    ((right - left).abs() / 2.0, (top - bottom).abs() / 2.0)
+                })
+                .unwrap_or((0.0, 0.0));
+            center.x = clamp(
+                center.x,
+                bounds.left.min(bounds.right) + half_width,
+                bounds.left.max(bounds.right) - half_width,
+            );
+            center.y = clamp(
+                center.y,
+                bounds.bottom.min(bounds.top) + half_height,
+                bounds.bottom.max(bounds.top) - half_height,
+            );
+        }
+        center
+    }
+}
+
+impl Component for CameraFollow {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Moves the center just enough for the focus to be back in the dead zone.
+fn dead_zone_goal(center: f32, focus: f32, half_zone: f32) -> f32 {
+    if focus > center + half_zone {
+        focus - half_zone
+    } else if focus < center - half_zone {
+        focus + half_zone
+    } else {
+        center
+    }
+}
+
+/// Clamps the value between `min` and `max`, or returns their middle if `min` is larger.
+fn clamp(value: f32, min: f32, max: f32) -> f32 {
+    if min > max {
+        (min + max) / 2.0
+    } else {
+        value.max(min).min(max)
+    }
+}
+
+/// System moving the cameras with a `CameraFollow` component towards their target.
+///
+/// The camera's `Transform` is changed, so the system should run before the `TransformSystem`.
+/// The positions of the targets and of the parent of the camera are the ones computed by the
+/// `TransformSystem` on the previous frame, so the camera should not be a child of its target.
+#[derive(Default)]
+pub struct CameraFollowSystem;
+
+impl<'a> System<'a> for CameraFollowSystem {
+    type SystemData = (
+        Read<'a, Time>,
+        ReadStorage<'a, GlobalTransform>,
+        ReadStorage<'a, Camera>,
+        ReadStorage<'a, CameraTarget>,
+        ReadStorage<'a, Parent>,
+        WriteStorage<'a, CameraFollow>,
+        WriteStorage<'a, Transform>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            time,
+            global_transforms,
+            cameras,
+            targets,
+            parents,
+            mut follows,
+            mut transforms,
+        ): Self::SystemData,
+    ) {
+        let aim = |global: &GlobalTransform, target: Option<&CameraTarget>| {
+            let offset = target.map_or_else(Vector2::zeros, |target| target.offset);
+            Vector2::new(global.0[(0, 3)], global.0[(1, 3)]) + offset
+        };
+        let tagged = (&global_transforms, &targets)
+            .join()
+            .map(|(global, target)| aim(global, Some(target)))
+            .collect::<Vec<_>>();
+
+        for (camera, parent, follow, transform) in
+            (&cameras, parents.maybe(), &mut follows, &mut transforms).join()
+        {
+            let target = match follow.target {
+                Some(entity) => match global_transforms.get(entity) {
+                    Some(global) => aim(global, targets.get(entity)),
+                    None => continue,
+                },
+                None if !tagged.is_empty() => {
+                    tagged.iter().fold(Vector2::zeros(), |sum, aim| sum + aim) / tagged.len() as f32
+                }
+                None => continue,
+            };
+            // The camera is moved in world coordinates, then back in the coordinates of its parent.
+            let parent = match parent.map(|parent| global_transforms.get(parent.entity)) {
+                Some(Some(global)) => match global.0.try_inverse() {
+                    Some(inverse) => Some((global.0, inverse)),
+                    None => continue,
+                },
+                Some(None) => continue,
+                None => None,
+            };
+
+            // The center of the view can be offset from the camera position by the projection.
+            let view = if camera.proj[(3, 3)] != 0.0 {
+                let ortho = Orthographic3::from_matrix_unchecked(camera.proj);
+                Some((ortho.left(), ortho.right(), ortho.bottom(), ortho.top()))
+            } else {
+                None
+            };
+            let offset = view
+                .map(|(left, right, bottom, top)| {
+                    Vector2::new((left + right) / 2.0, (bottom + top) / 2.0)
+                })
+                .unwrap_or_else(Vector2::zeros);
+
+            let mut position = Point3::from(*transform.translation());
+            if let Some((global, _)) = parent {
+                position = global.transform_point(&position);
+            }
+            let center = follow.follow(
+                Vector2::new(position.x, position.y) + offset,
+                target,
+                view,
+                time.delta_seconds(),
+            );
+            position.x = center.x - offset.x;
+            position.y = center.y - offset.y;
+            if let Some((_, inverse)) = parent {
+                position = inverse.transform_point(&position);
+            }
+            *transform.translation_mut() = position.coords;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amethyst_core::{
+        nalgebra::{Matrix4, Vector3},
+        specs::{Builder, RunNow, World},
+    };
+    use amethyst_renderer::Projection;
+
+    use super::*;
+
+    fn follow() -> CameraFollow {
+        let mut world = World::new();
+        CameraFollow::new(world.create_entity().build())
+    }
+
+    #[test]
+    fn dead_zone_keeps_camera_still() {
+        let mut follow = follow().with_dead_zone(4.0, 2.0);
+        let center = Vector2::new(0.0, 0.0);
+        assert_eq!(
+            follow.follow(center, Vector2::new(1.5, -0.5), None, 0.1),
+            center
+        );
+        assert_eq!(
+            follow.follow(center, Vector2::new(3.0, -0.5), None, 0.1),
+            Vector2::new(1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn look_ahead_leads_target() {
+        let mut follow = follow().with_look_ahead(0.5);
+        let center = Vector2::new(0.0, 0.0);
+        follow.follow(center, Vector2::new(0.0, 0.0), None, 0.1);
+        assert_eq!(
+            follow.follow(center, Vector2::new(1.0, 0.0), None, 0.1),
+            Vector2::new(6.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn bounds_keep_view_inside() {
+        let mut follow = follow().with_bounds(CameraOrthoWorldCoordinates {
+            left: 0.0,
+            right: 10.0,
+            bottom: 0.0,
+            top: 2.0,
+        });
+        let view = Some((-2.0, 2.0, -2.0, 2.0));
+        assert_eq!(
+            follow.follow(Vector2::zeros(), Vector2::new(1.0, 5.0), view, 0.1),
+            Vector2::new(2.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn dead_zone_follows_on_every_side() {
+        let mut follow = follow().with_dead_zone(4.0, 2.0);
+        let center = Vector2::new(0.0, 0.0);
+        assert_eq!(
+            follow.follow(center, Vector2::new(-3.0, -2.0), None, 0.1),
+            Vector2::new(-1.0, -1.0)
+        );
+        assert_eq!(
+            follow.follow(center, Vector2::new(-2.0, 1.5), None, 0.1),
+            Vector2::new(0.0, 0.5)
+        );
+    }
+
+    #[test]
+    fn look_ahead_moves_dead_zone() {
+        let mut follow = follow().with_dead_zone(4.0, 0.0).with_look_ahead(0.5);
+        let center = Vector2::new(0.0, 0.0);
+        follow.follow(center, Vector2::new(0.0, 0.0), None, 0.1);
+        let center = follow.follow(center, Vector2::new(1.0, 0.0), None, 0.1);
+        assert_eq!(center, Vector2::new(4.0, 0.0));
+        // Without elapsed time the velocity of the target is unknown, so the view stops leading.
+        assert_eq!(
+            follow.follow(center, Vector2::new(1.0, 0.0), None, 0.0),
+            Vector2::new(3.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn bounds_keep_center_inside() {
+        let bounds = CameraOrthoWorldCoordinates {
+            left: 0.0,
+            right: 10.0,
+            bottom: 0.0,
+            top: 2.0,
+        };
+        let mut follow = follow().with_bounds(bounds);
+        // The whole orthographic view is kept inside.
+        let view = Some((-2.0, 2.0, -0.5, 0.5));
+        assert_eq!(
+            follow.follow(Vector2::zeros(), Vector2::new(20.0, 5.0), view, 0.1),
+            Vector2::new(8.0, 1.5)
+        );
+        // Only the center of perspective views is.
+        assert_eq!(
+            follow.follow(Vector2::zeros(), Vector2::new(20.0, -5.0), None, 0.1),
+            Vector2::new(10.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn spring_eases_in_without_overshooting() {
+        let mut spring = follow().with_spring(1.0);
+        let mut exponential = follow().with_smoothing(1.0);
+        let goal = Vector2::new(10.0, 0.0);
+        let mut center = spring.follow(Vector2::zeros(), goal, None, 0.1);
+        assert!(center.x > 0.0);
+        assert!(center.x < exponential.follow(Vector2::zeros(), goal, None, 0.1).x);
+        for _ in 0..100 {
+            center = spring.follow(center, goal, None, 0.1);
+            assert!(center.x <= goal.x + 1e-4);
+        }
+        assert!(center.x > 9.99);
+    }
+
+    #[test]
+    fn follows_tagged_targets_from_parent() {
+        let mut world = World::new();
+        world.register::<GlobalTransform>();
+        world.register::<Camera>();
+        world.register::<CameraTarget>();
+        world.register::<Parent>();
+        world.register::<CameraFollow>();
+        world.register::<Transform>();
+        world.add_resource(Time::default());
+
+        let at = |x, y| GlobalTransform(Matrix4::new_translation(&Vector3::new(x, y, 0.0)));
+        world
+            .create_entity()
+            .with(at(0.0, 0.0))
+            .with(CameraTarget::with_offset(0.0, 2.0))
+            .build();
+        world
+            .create_entity()
+            .with(at(4.0, 2.0))
+            .with(CameraTarget::default())
+            .build();
+        let parent = world.create_entity().with(at(1.0, 0.0)).build();
+        let camera = world
+            .create_entity()
+            .with(Camera::from(Projection::orthographic(-1.0, 1.0, -1.0, 1.0)))
+            .with(CameraFollow::tagged())
+            .with(Parent { entity: parent })
+            .with(Transform::default())
+            .build();
+
+        CameraFollowSystem.run_now(&world.res);
+        let transforms = world.read_storage::<Transform>();
+        let translation = transforms.get(camera).unwrap().translation();
+        assert_eq!(*translation, Vector3::new(1.0, 2.0, 0.0));
+    }
+}
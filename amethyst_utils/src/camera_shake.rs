@@ -0,0 +1,229 @@
+//! Provides a camera shake driven by trauma.
+
+use amethyst_core::{
+    nalgebra::{Matrix4, Vector2, Vector3},
+    specs::{Component, DenseVecStorage, Join, Read, System, WriteStorage},
+    GlobalTransform, Time,
+};
+
+/// `Component` attached to a camera's entity to shake it.
+///
+/// Gameplay code adds trauma with `add_trauma`, which decays over time. The camera is moved and
+/// rolled by smooth noise, scaled by the square of the trauma, so small hits barely shake the
+/// camera while big ones add up to a strong shake.
+///
+/// The shake is applied to the `GlobalTransform` of the camera, in its own axes, and the
+/// `Transform` is left untouched so the camera can be moved by other systems such as the
+/// `CameraFollowSystem`. You must add the `CameraShakeSystem` to your dispatcher for this to take
+/// effect.
+#[derive(Debug, Clone)]
+pub struct CameraShake {
+    /// The trauma, between `0.0` and `1.0`.
+    pub trauma: f32,
+    /// The trauma removed per second.
+    pub decay: f32,
+    /// The horizontal and vertical offset of the camera at full trauma, in world units.
+    pub max_offset: Vector2<f32>,
+    /// The roll of the camera at full trauma, in radians.
+    pub max_rotation: f32,
+    /// How many times per second the shake changes direction.
+    pub frequency: f32,
+    /// The seed of the noise, to shake cameras differently.
+    pub seed: u32,
+    time: f32,
+    /// The `GlobalTransform` of the camera before and after the last shake
+    applied: Option<(Matrix4<f32>, Matrix4<f32>)>,
+}
+
+impl CameraShake {
+    /// Creates a shake moving the camera up to the given offsets, and rolling it up to the given
+    /// angle in radians, at full trauma.
+    ///
+    /// The trauma decays in one second, and the shake changes direction 15 times per second.
+    pub fn new(max_x: f32, max_y: f32, max_rotation: f32) -> Self {
+        CameraShake {
+            trauma: 0.0,
+            decay: 1.0,
+            max_offset: Vector2::new(max_x, max_y),
+            max_rotation,
+            frequency: 15.0,
+            seed: 0,
+            time: 0.0,
+            applied: None,
+        }
+    }
+
+    /// Sets the trauma removed per second.
+    pub fn with_decay(mut self, decay: f32) -> Self {
+        self.decay = decay;
+        self
+    }
+
+    /// Sets how many times per second the shake changes direction.
+    pub fn with_frequency(mut self, frequency: f32) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    /// Sets the seed of the noise.
+    pub fn with_seed(mut self, seed: u32) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Adds trauma, keeping the total at most `1.0`.
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).max(0.0).min(1.0);
+    }
+
+    /// Returns the strength of the shake, the square of the trauma.
+    pub fn shake(&self) -> f32 {
+        self.trauma * self.trauma
+    }
+
+    /// Advances the shake, and returns the offset and roll of the camera.
+    fn advance(&mut self, delta_seconds: f32) -> (Vector2<f32>, f32) {
+        let shake = self.shake();
+        self.trauma = (self.trauma - self.decay * delta_seconds).max(0.0);
+        if shake <= 0.0 {
+            self.time = 0.0;
+            return (Vector2::zeros(), 0.0);
+        }
+        self.time += delta_seconds;
+        let t = self.time * self.frequency;
+        let offset = Vector2::new(
+            self.max_offset.x * noise(self.seed, t),
+            self.max_offset.y * noise(self.seed.wrapping_add(1), t),
+        );
+        let rotation = self.max_rotation * noise(self.seed.wrapping_add(2), t);
+        (offset * shake, rotation * shake)
+    }
+}
+
+impl Component for CameraShake {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Smooth noise between `-1.0` and `1.0`, interpolating random values at each integer.
+fn noise(seed: u32, t: f32) -> f32 {
+    let start = t.floor();
+    let f = t - start;
+    let a = lattice(seed, start as i32);
+    let b = lattice(seed, start as i32 + 1);
+    a + (b - a) * f * f * (3.0 - 2.0 * f)
+}
+
+/// Random value between `-1.0` and `1.0` for an integer.
+fn lattice(seed: u32, i: i32) -> f32 {
+    let mut h = (i as u32).wrapping_mul(0x27d4_eb2d) ^ seed.wrapping_mul(0x9e37_79b9);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h as f32 / u32::max_value() as f32 * 2.0 - 1.0
+}
+
+/// System shaking the cameras with a `CameraShake` component.
+///
+/// The shake is applied to the `GlobalTransform`, so the system should run after the
+/// `TransformSystem`.
+#[derive(Default)]
+pub struct CameraShakeSystem;
+
+impl<'a> System<'a> for CameraShakeSystem {
+    type SystemData = (
+        Read<'a, Time>,
+        WriteStorage<'a, CameraShake>,
+        WriteStorage<'a, GlobalTransform>,
+    );
+
+    fn run(&mut self, (time, mut shakes, mut globals): Self::SystemData) {
+        for (shake, global) in (&mut shakes, &mut globals).join() {
+            // The `TransformSystem` only updates the transforms which changed, so the last shake
+            // is still applied if the camera did not move.
+            let base = match shake.applied {
+                Some((base, shaken)) if shaken == global.0 => base,
+                _ => global.0,
+            };
+            let shaking = shake.shake() > 0.0;
+            let (offset, rotation) = shake.advance(time.delta_seconds());
+            if !shaking {
+                if shake.applied.take().is_some() {
+                    global.0 = base;
+                }
+                continue;
+            }
+            global.0 = base
+                * Matrix4::new_translation(&Vector3::new(offset.x, offset.y, 0.0))
+                * Matrix4::from_euler_angles(0.0, 0.0, rotation);
+            shake.applied = Some((base, global.0));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amethyst_core::specs::{Builder, RunNow, World};
+
+    use super::*;
+
+    #[test]
+    fn trauma_is_clamped_and_decays() {
+        let mut shake = CameraShake::new(1.0, 1.0, 0.1).with_decay(2.0);
+        shake.add_trauma(0.4);
+        shake.add_trauma(0.8);
+        assert_eq!(shake.trauma, 1.0);
+        shake.advance(0.25);
+        assert_eq!(shake.trauma, 0.5);
+        assert_eq!(shake.shake(), 0.25);
+        shake.advance(1.0);
+        assert_eq!(shake.advance(0.1), (Vector2::zeros(), 0.0));
+    }
+
+    #[test]
+    fn noise_is_smooth_and_bounded() {
+        for i in 0..1000 {
+            let t = i as f32 * 0.01;
+            let value = noise(3, t);
+            assert!(value.abs() <= 1.0);
+            assert!((noise(3, t + 0.001) - value).abs() < 0.01);
+        }
+        assert_ne!(noise(3, 0.5), noise(4, 0.5));
+    }
+
+    #[test]
+    fn shake_does_not_accumulate() {
+        let mut world = World::new();
+        world.register::<CameraShake>();
+        world.register::<GlobalTransform>();
+        let mut time = Time::default();
+        time.set_delta_seconds(0.1);
+        world.add_resource(time);
+
+        let base = Matrix4::new_translation(&Vector3::new(5.0, 0.0, 0.0));
+        let mut shake = CameraShake::new(1.0, 1.0, 0.0).with_decay(5.0);
+        shake.add_trauma(1.0);
+        let camera = world
+            .create_entity()
+            .with(shake)
+            .with(GlobalTransform(base))
+            .build();
+
+        CameraShakeSystem.run_now(&world.res);
+        let shaken = world
+            .read_storage::<GlobalTransform>()
+            .get(camera)
+            .unwrap()
+            .0;
+        assert_ne!(shaken, base);
+        assert!((shaken[(0, 3)] - 5.0).abs() <= 1.0);
+
+        CameraShakeSystem.run_now(&world.res);
+        CameraShakeSystem.run_now(&world.res);
+        let global = world
+            .read_storage::<GlobalTransform>()
+            .get(camera)
+            .unwrap()
+            .0;
+        assert_eq!(global, base);
+    }
+}
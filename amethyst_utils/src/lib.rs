@@ -6,11 +6,15 @@ pub use self::app_root_dir::*;
 
 pub mod app_root_dir;
 pub mod auto_fov;
+pub mod camera_follow;
+pub mod camera_shake;
 pub mod circular_buffer;
 pub mod fps_counter;
 pub mod ortho_camera;
 pub mod removal;
 pub mod render;
 pub mod scene;
+pub mod sprite_size;
 pub mod tag;
 pub mod time_destroy;
+pub mod tween;
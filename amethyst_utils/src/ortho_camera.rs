@@ -54,6 +54,19 @@ impl CameraOrthoWorldCoordinates {
     }
 }
 
+impl CameraOrthoWorldCoordinates {
+    /// Creates coordinates of `width` by `height` units centered on the origin, such as the size
+    /// of the window in pixels.
+    pub fn centered(width: f32, height: f32) -> CameraOrthoWorldCoordinates {
+        CameraOrthoWorldCoordinates {
+            left: -width / 2.0,
+            right: width / 2.0,
+            bottom: -height / 2.0,
+            top: height / 2.0,
+        }
+    }
+}
+
 impl Default for CameraOrthoWorldCoordinates {
     fn default() -> Self {
         Self::normalized()
@@ -72,7 +85,8 @@ pub struct CameraOrtho {
     pub mode: CameraNormalizeMode,
     /// The world coordinates that this camera will keep visible as the window size changes
     pub world_coordinates: CameraOrthoWorldCoordinates,
-    aspect_ratio_cache: f32,
+    #[serde(skip)]
+    size_cache: (f32, f32),
 }
 
 impl CameraOrtho {
@@ -81,14 +95,53 @@ impl CameraOrtho {
         CameraOrtho {
             mode,
             world_coordinates: Default::default(),
-            aspect_ratio_cache: 0.0,
+            size_cache: (0.0, 0.0),
+        }
+    }
+
+    /// Creates a Camera measuring the world in pixels, keeping `width` by `height` pixels
+    /// centered on the camera visible with `CameraNormalizeMode::Contain`,
+    /// `CameraNormalizeMode::Letterbox` or `CameraNormalizeMode::Stretch`, or drawing the sprites
+    /// at their size in pixels with `CameraNormalizeMode::PixelsPerUnit(1.0)`.
+    pub fn pixels(width: f32, height: f32, mode: CameraNormalizeMode) -> CameraOrtho {
+        CameraOrtho {
+            mode,
+            world_coordinates: CameraOrthoWorldCoordinates::centered(width, height),
+            size_cache: (0.0, 0.0),
         }
     }
 
     /// Get the camera matrix offsets according to the specified options.
+    ///
+    /// `CameraNormalizeMode::PixelsPerUnit` needs the size of the window, use
+    /// `camera_offsets_for_size` with it.
     pub fn camera_offsets(&self, window_aspect_ratio: f32) -> (f32, f32, f32, f32) {
+        self.camera_offsets_for_size(window_aspect_ratio, 1.0)
+    }
+
+    /// Get the camera matrix offsets according to the specified options, for a window of the
+    /// given size in pixels.
+    pub fn camera_offsets_for_size(&self, width: f32, height: f32) -> (f32, f32, f32, f32) {
         self.mode
-            .camera_offsets(window_aspect_ratio, &self.world_coordinates)
+            .camera_offsets(width, height, &self.world_coordinates)
+    }
+
+    /// Returns the left, right, bottom and top of the area of a window of the given size showing
+    /// the world coordinates, in pixels from its bottom left corner.
+    ///
+    /// With `CameraNormalizeMode::Letterbox`, the rest of the window are the bars.
+    pub fn window_bounds(&self, width: f32, height: f32) -> (f32, f32, f32, f32) {
+        let (left, right, bottom, top) = self.camera_offsets_for_size(width, height);
+        let coordinates = &self.world_coordinates;
+        let x = |world_x: f32| (world_x - left) * width / (right - left);
+        let y = |world_y: f32| (world_y - bottom) * height / (top - bottom);
+        let (window_bottom, window_top) = (y(coordinates.bottom), y(coordinates.top));
+        (
+            x(coordinates.left),
+            x(coordinates.right),
+            window_bottom.min(window_top),
+            window_bottom.max(window_top),
+        )
     }
 }
 
@@ -120,6 +173,9 @@ pub enum CameraNormalizeMode {
     ///
     /// If you want the whole world space between (0, 0) and (1, 1) to be shown at ALL times, consider using
     /// `CameraNormalizeMode::Contain` instead.
+    ///
+    /// Lossy with the stretch_direction of Axis::X keeps a fixed world height, and with Axis::Y a
+    /// fixed world width.
     Lossy {
         /// The direction along which the camera will stretch and possibly have a length not equal
         /// to one.
@@ -132,15 +188,37 @@ pub enum CameraNormalizeMode {
     /// If you have a non-default `Transform` on your camera,
     /// it will just translate those coordinates by the translation of the `Transform`.
     Contain,
+
+    /// Keeps a fixed number of pixels per world unit, so the visible area grows with the window
+    /// instead of the scene being scaled. Pixel art stays sharp as long as the sprites are drawn
+    /// at whole pixel positions.
+    ///
+    /// The visible area is centered on the center of the `CameraOrthoWorldCoordinates`.
+    PixelsPerUnit(f32),
+
+    /// Shows exactly the `CameraOrthoWorldCoordinates`, stretching the scene when the aspect
+    /// ratio of the window differs from theirs.
+    Stretch,
+
+    /// Keeps the aspect ratio of the `CameraOrthoWorldCoordinates`, scaling them to fit the
+    /// window and centering them, with bars above and below them (letterbox) or on their sides
+    /// (pillarbox) when the aspect ratio of the window differs from theirs.
+    ///
+    /// The projection is the one of `Contain`, so the scene around the world coordinates is
+    /// visible in the bars unless they are covered, such as by UI images placed outside of the
+    /// area returned by `CameraOrtho::window_bounds`.
+    Letterbox,
 }
 
 impl CameraNormalizeMode {
     /// Get the camera matrix offsets according to the specified options.
     fn camera_offsets(
         &self,
-        window_aspect_ratio: f32,
+        width: f32,
+        height: f32,
         desired_coordinates: &CameraOrthoWorldCoordinates,
     ) -> (f32, f32, f32, f32) {
+        let window_aspect_ratio = width / height;
         match self {
             CameraNormalizeMode::Lossy {
                 ref stretch_direction,
@@ -148,7 +226,7 @@ impl CameraNormalizeMode {
                 Axis2::X => CameraNormalizeMode::lossy_x(window_aspect_ratio, desired_coordinates),
                 Axis2::Y => CameraNormalizeMode::lossy_y(window_aspect_ratio, desired_coordinates),
             },
-            CameraNormalizeMode::Contain => {
+            CameraNormalizeMode::Contain | CameraNormalizeMode::Letterbox => {
                 let desired_aspect_ratio = desired_coordinates.aspect_ratio();
                 // We don't need an == case because lossy handles it just fine
                 if window_aspect_ratio > desired_aspect_ratio {
@@ -158,6 +236,28 @@ impl CameraNormalizeMode {
                     CameraNormalizeMode::lossy_y(window_aspect_ratio, desired_coordinates)
                 }
             }
+            CameraNormalizeMode::Stretch => (
+                desired_coordinates.left,
+                desired_coordinates.right,
+                desired_coordinates.bottom,
+                desired_coordinates.top,
+            ),
+            CameraNormalizeMode::PixelsPerUnit(pixels_per_unit) => {
+                let center_x = (desired_coordinates.left + desired_coordinates.right) / 2.0;
+                let center_y = (desired_coordinates.bottom + desired_coordinates.top) / 2.0;
+                let half_width = width / pixels_per_unit / 2.0;
+                let mut half_height = height / pixels_per_unit / 2.0;
+                // If bottom is higher than top (common in 2D graphics), we flip the offset
+                if desired_coordinates.bottom > desired_coordinates.top {
+                    half_height = -half_height;
+                }
+                (
+                    center_x - half_width,
+                    center_x + half_width,
+                    center_y - half_height,
+                    center_y + half_height,
+                )
+            }
         }
     }
 
@@ -219,12 +319,12 @@ impl<'a> System<'a> for CameraOrthoSystem {
 
     #[cfg_attr(feature = "cargo-clippy", allow(float_cmp))] // cmp just used to recognize change
     fn run(&mut self, (dimensions, mut cameras, mut ortho_cameras): Self::SystemData) {
-        let aspect = dimensions.aspect_ratio();
+        let size = (dimensions.width(), dimensions.height());
 
         for (mut camera, mut ortho_camera) in (&mut cameras, &mut ortho_cameras).join() {
-            if aspect != ortho_camera.aspect_ratio_cache {
-                ortho_camera.aspect_ratio_cache = aspect;
-                let offsets = ortho_camera.camera_offsets(aspect);
+            if size != ortho_camera.size_cache {
+                ortho_camera.size_cache = size;
+                let offsets = ortho_camera.camera_offsets_for_size(size.0, size.1);
 
                 let prev = Orthographic3::from_matrix_unchecked(camera.proj);
                 camera.proj = Orthographic3::new(
@@ -353,7 +453,7 @@ mod test {
                 top: 0.0,
                 bottom: 1.0,
             },
-            aspect_ratio_cache: 0.0,
+            size_cache: (0.0, 0.0),
         };
         assert_eq!((0.0, 1.0, 1.5, -0.5), cam.camera_offsets(aspect));
     }
@@ -369,7 +469,7 @@ mod test {
                 top: 2.0,
                 bottom: 0.0,
             },
-            aspect_ratio_cache: 0.0,
+            size_cache: (0.0, 0.0),
         };
         assert_eq!((0.0, 2.0, 0.0, 2.0), cam.camera_offsets(aspect));
     }
@@ -385,11 +485,46 @@ mod test {
                 top: 2.0,
                 bottom: 0.0,
             },
-            aspect_ratio_cache: 0.0,
+            size_cache: (0.0, 0.0),
         };
         assert_eq!((-1.0, 3.0, 0.0, 2.0), cam.camera_offsets(aspect));
     }
 
+    #[test]
+    fn pixels_per_unit() {
+        let cam = CameraOrtho::normalized(CameraNormalizeMode::PixelsPerUnit(100.0));
+        assert_eq!(
+            (-1.5, 2.5, -0.5, 1.5),
+            cam.camera_offsets_for_size(400.0, 200.0)
+        );
+    }
+
+    #[test]
+    fn pixels_stretch() {
+        let cam = CameraOrtho::pixels(320.0, 180.0, CameraNormalizeMode::Stretch);
+        assert_eq!(
+            (-160.0, 160.0, -90.0, 90.0),
+            cam.camera_offsets_for_size(400.0, 400.0)
+        );
+    }
+
+    #[test]
+    fn pixels_letterbox() {
+        let cam = CameraOrtho::pixels(320.0, 180.0, CameraNormalizeMode::Letterbox);
+        assert_eq!(
+            (-160.0, 160.0, -160.0, 160.0),
+            cam.camera_offsets_for_size(400.0, 400.0)
+        );
+        assert_eq!((0.0, 400.0, 87.5, 312.5), cam.window_bounds(400.0, 400.0));
+
+        // Pillarbox in a wide window.
+        assert_eq!(
+            (-360.0, 360.0, -90.0, 90.0),
+            cam.camera_offsets_for_size(720.0, 180.0)
+        );
+        assert_eq!((200.0, 520.0, 0.0, 180.0), cam.window_bounds(720.0, 180.0));
+    }
+
     #[test]
     fn camera_high_contain() {
         let aspect = 1.0 / 2.0;
@@ -401,7 +536,7 @@ mod test {
                 top: 2.0,
                 bottom: 0.0,
             },
-            aspect_ratio_cache: 0.0,
+            size_cache: (0.0, 0.0),
         };
         assert_eq!((0.0, 2.0, -1.0, 3.0), cam.camera_offsets(aspect));
     }
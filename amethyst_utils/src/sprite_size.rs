@@ -0,0 +1,169 @@
+//! Provides a component sizing sprites in world units or screen pixels.
+//!
+//! Sprites are drawn with one world unit per pixel of the sprite, scaled by their `Transform`. A
+//! 32 pixels sprite looks like a giant quad with a camera showing a few world units, and is
+//! invisible with a camera showing thousands. The `SpriteSize` component sets the scale of the
+//! `Transform` from the size of the sprite, so it renders at a predictable size.
+
+use amethyst_assets::{AssetStorage, PrefabData};
+use amethyst_core::{
+    specs::{
+        Component, DenseVecStorage, Entity, Join, Read, ReadExpect, ReadStorage, System,
+        WriteStorage,
+    },
+    Transform,
+};
+use amethyst_derive::PrefabData;
+use amethyst_error::Error;
+use amethyst_renderer::{
+    ActiveCamera, Camera, ScreenDimensions, Sprite, SpriteRender, SpriteSheet,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// `Component` setting the scale of the `Transform` of a sprite, according to the size of the
+/// sprite in pixels. You must add the `SpriteSizeSystem` to your dispatcher for this to take
+/// effect.
+///
+/// The sign of the scale is kept, so sprites can still be mirrored with a negative scale.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, PrefabData)]
+#[prefab(Component)]
+pub enum SpriteSize {
+    /// Number of pixels of the sprite per world unit. It should match the pixels per unit of the
+    /// camera, see `CameraNormalizeMode::PixelsPerUnit`.
+    PixelsPerUnit(f32),
+    /// Size of the sprite in world units, whatever its size in pixels.
+    World {
+        /// Width in world units
+        width: f32,
+        /// Height in world units
+        height: f32,
+    },
+    /// Number of screen pixels per pixel of the sprite, whatever the projection of the camera.
+    ///
+    /// Only orthographic cameras are supported, and the sprite is resized when the window or the
+    /// camera change.
+    ScreenSpace(f32),
+}
+
+impl SpriteSize {
+    /// Computes the scale of the `Transform` of the sprite.
+    ///
+    /// `world_per_screen_pixel` is the size of a screen pixel in world units, as returned by
+    /// `world_units_per_screen_pixel`, only needed by `SpriteSize::ScreenSpace`.
+    pub fn scale(
+        &self,
+        sprite: &Sprite,
+        world_per_screen_pixel: Option<(f32, f32)>,
+    ) -> Option<(f32, f32)> {
+        match *self {
+            SpriteSize::PixelsPerUnit(pixels_per_unit) => {
+                Some((1.0 / pixels_per_unit, 1.0 / pixels_per_unit))
+            }
+            SpriteSize::World { width, height } => {
+                Some((width / sprite.width, height / sprite.height))
+            }
+            SpriteSize::ScreenSpace(screen_pixels) => {
+                world_per_screen_pixel.map(|(x, y)| (x * screen_pixels, y * screen_pixels))
+            }
+        }
+    }
+}
+
+impl Component for SpriteSize {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Returns the width and height of a screen pixel in world units, or `None` if the camera is not
+/// orthographic.
+pub fn world_units_per_screen_pixel(
+    camera: &Camera,
+    screen: &ScreenDimensions,
+) -> Option<(f32, f32)> {
+    if camera.proj[(3, 3)] == 0.0 {
+        return None;
+    }
+    // The orthographic projection maps the visible width to 2.0 in normalized device coordinates.
+    Some((
+        (2.0 / (camera.proj[(0, 0)] * screen.width())).abs(),
+        (2.0 / (camera.proj[(1, 1)] * screen.height())).abs(),
+    ))
+}
+
+/// System setting the scale of the `Transform` of the sprites with a `SpriteSize` component.
+///
+/// `SpriteSize::ScreenSpace` uses the `ActiveCamera`, or the first camera if there is none.
+#[derive(Default)]
+pub struct SpriteSizeSystem;
+
+impl<'a> System<'a> for SpriteSizeSystem {
+    type SystemData = (
+        Read<'a, AssetStorage<SpriteSheet>>,
+        Option<ReadExpect<'a, ScreenDimensions>>,
+        Read<'a, ActiveCamera>,
+        ReadStorage<'a, Camera>,
+        ReadStorage<'a, SpriteRender>,
+        ReadStorage<'a, SpriteSize>,
+        WriteStorage<'a, Transform>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (sprite_sheets, screen, active_camera, cameras, sprites, sizes, mut transforms) = data;
+        let camera = active_camera
+            .entity
+            .and_then(|entity| cameras.get(entity))
+            .or_else(|| cameras.join().next());
+        let world_per_screen_pixel = match (camera, screen) {
+            (Some(camera), Some(screen)) => world_units_per_screen_pixel(camera, &screen),
+            _ => None,
+        };
+
+        for (sprite_render, size, transform) in (&sprites, &sizes, &mut transforms).join() {
+            let sprite = match sprite_sheets
+                .get(&sprite_render.sprite_sheet)
+                .and_then(|sheet| sheet.sprites.get(sprite_render.sprite_number))
+            {
+                Some(sprite) => sprite,
+                None => continue,
+            };
+            if let Some((x, y)) = size.scale(sprite, world_per_screen_pixel) {
+                let scale = transform.scale_mut();
+                scale.x = x * scale.x.signum();
+                scale.y = y * scale.y.signum();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sprite() -> Sprite {
+        Sprite::from_pixel_values(64, 64, 32, 16, 0, 0, [0.0, 0.0])
+    }
+
+    #[test]
+    fn world_size_ignores_pixels() {
+        let size = SpriteSize::World {
+            width: 2.0,
+            height: 1.0,
+        };
+        assert_eq!(size.scale(&sprite(), None), Some((2.0 / 32.0, 1.0 / 16.0)));
+    }
+
+    #[test]
+    fn screen_space_needs_orthographic_camera() {
+        let screen = ScreenDimensions::new(800, 600, 1.0);
+        let ortho = Camera::standard_2d();
+        let perspective = Camera::standard_3d(800.0, 600.0);
+        assert_eq!(world_units_per_screen_pixel(&perspective, &screen), None);
+
+        let world_per_screen_pixel = world_units_per_screen_pixel(&ortho, &screen);
+        let (x, y) = SpriteSize::ScreenSpace(2.0)
+            .scale(&sprite(), world_per_screen_pixel)
+            .unwrap();
+        assert!((x - 2.0 * 2.0 / 800.0).abs() < 1e-6);
+        assert!((y - 2.0 * 2.0 / 600.0).abs() < 1e-6);
+    }
+}
@@ -1,8 +1,16 @@
 //! Allows you to automatically delete an entity after a set time has elapsed.
+//!
+//! The children of the deleted entities in the `ParentHierarchy` are deleted with them.
+
+use std::marker::PhantomData;
 
 use amethyst_core::{
-    specs::{Component, DenseVecStorage, Entities, Join, Read, ReadStorage, System, WriteStorage},
+    specs::{
+        Component, DenseVecStorage, Entities, Entity, Join, Read, ReadExpect, ReadStorage, System,
+        WriteStorage,
+    },
     timing::Time,
+    ParentHierarchy,
 };
 
 use log::error;
@@ -32,17 +40,79 @@ impl Component for DestroyInTime {
     type Storage = DenseVecStorage<Self>;
 }
 
+/// Destroys the entity to which this is attached after the specified time interval (in seconds).
+///
+/// This is another name of `DestroyInTime`, handled by the `DestroyInTimeSystem`.
+pub type TimedDestroy = DestroyInTime;
+
+/// Destroys the entity to which this is attached at the specified frame.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DestroyAtFrame {
+    /// The frame at which the entity should be destroyed.
+    /// Compared to `Time::frame_number`.
+    pub frame: u64,
+}
+
+impl Component for DestroyAtFrame {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Removes the component `T` from the entity to which this is attached after the specified time
+/// interval (in seconds), then removes itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoveComponentAfter<T> {
+    /// The amount of time before the component should be removed in seconds.
+    pub timer: f64,
+    #[serde(skip)]
+    _marker: PhantomData<T>,
+}
+
+impl<T> RemoveComponentAfter<T> {
+    /// Creates a new `RemoveComponentAfter` removing the component after `timer` seconds.
+    pub fn new(timer: f64) -> Self {
+        RemoveComponentAfter {
+            timer,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Component + Send + Sync> Component for RemoveComponentAfter<T> {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Deletes the entity and its children in the hierarchy, if there is one.
+fn delete_with_children(
+    entities: &Entities<'_>,
+    hierarchy: Option<&ParentHierarchy>,
+    entity: Entity,
+) {
+    if let Some(hierarchy) = hierarchy {
+        for child in hierarchy.all_children_iter(entity) {
+            if let Err(err) = entities.delete(child) {
+                error!("Failed to delete entity: {:?}", err);
+            }
+        }
+    }
+    if let Err(err) = entities.delete(entity) {
+        error!("Failed to delete entity: {:?}", err);
+    }
+}
+
 /// The system in charge of destroying entities with the `DestroyAtTime` component.
 pub struct DestroyAtTimeSystem;
 
 impl<'a> System<'a> for DestroyAtTimeSystem {
-    type SystemData = (Entities<'a>, ReadStorage<'a, DestroyAtTime>, Read<'a, Time>);
-    fn run(&mut self, (entities, dat, time): Self::SystemData) {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, DestroyAtTime>,
+        Read<'a, Time>,
+        Option<ReadExpect<'a, ParentHierarchy>>,
+    );
+    fn run(&mut self, (entities, dat, time, hierarchy): Self::SystemData) {
         for (e, d) in (&entities, &dat).join() {
             if time.absolute_time_seconds() > d.time {
-                if let Err(err) = entities.delete(e) {
-                    error!("Failed to delete entity: {:?}", err);
-                }
+                delete_with_children(&entities, hierarchy.as_ref().map(|h| &**h), e);
             }
         }
     }
@@ -56,15 +126,143 @@ impl<'a> System<'a> for DestroyInTimeSystem {
         Entities<'a>,
         WriteStorage<'a, DestroyInTime>,
         Read<'a, Time>,
+        Option<ReadExpect<'a, ParentHierarchy>>,
     );
-    fn run(&mut self, (entities, mut dit, time): Self::SystemData) {
+    fn run(&mut self, (entities, mut dit, time, hierarchy): Self::SystemData) {
         for (e, d) in (&entities, &mut dit).join() {
             if d.timer <= 0.0 {
-                if let Err(err) = entities.delete(e) {
-                    error!("Failed to delete entity: {:?}", err);
-                }
+                delete_with_children(&entities, hierarchy.as_ref().map(|h| &**h), e);
             }
             d.timer -= f64::from(time.delta_seconds());
         }
     }
 }
+
+/// The system in charge of destroying entities with the `DestroyAtFrame` component.
+pub struct DestroyAtFrameSystem;
+
+impl<'a> System<'a> for DestroyAtFrameSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, DestroyAtFrame>,
+        Read<'a, Time>,
+        Option<ReadExpect<'a, ParentHierarchy>>,
+    );
+    fn run(&mut self, (entities, daf, time, hierarchy): Self::SystemData) {
+        for (e, d) in (&entities, &daf).join() {
+            if time.frame_number() >= d.frame {
+                delete_with_children(&entities, hierarchy.as_ref().map(|h| &**h), e);
+            }
+        }
+    }
+}
+
+/// The system in charge of removing the components of the `RemoveComponentAfter<T>` components.
+pub struct RemoveComponentAfterSystem<T> {
+    _marker: PhantomData<T>,
+}
+
+impl<T> RemoveComponentAfterSystem<T> {
+    /// Creates a new `RemoveComponentAfterSystem`.
+    pub fn new() -> Self {
+        RemoveComponentAfterSystem {
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Default for RemoveComponentAfterSystem<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, T: Component + Send + Sync> System<'a> for RemoveComponentAfterSystem<T> {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, RemoveComponentAfter<T>>,
+        WriteStorage<'a, T>,
+        Read<'a, Time>,
+    );
+    fn run(&mut self, (entities, mut removals, mut components, time): Self::SystemData) {
+        let mut expired = Vec::new();
+        for (e, r) in (&entities, &mut removals).join() {
+            if r.timer <= 0.0 {
+                expired.push(e);
+            }
+            r.timer -= f64::from(time.delta_seconds());
+        }
+        for e in expired {
+            components.remove(e);
+            removals.remove(e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::{
+        specs::{Builder, RunNow, World},
+        Parent,
+    };
+    use specs_hierarchy::HierarchySystem;
+
+    use super::*;
+
+    struct Marker;
+
+    impl Component for Marker {
+        type Storage = DenseVecStorage<Self>;
+    }
+
+    #[test]
+    fn children_are_destroyed_with_parent() {
+        let mut world = World::new();
+        let mut hierarchy = HierarchySystem::<Parent>::new();
+        System::setup(&mut hierarchy, &mut world.res);
+        System::setup(&mut DestroyInTimeSystem, &mut world.res);
+
+        let parent = world
+            .create_entity()
+            .with(DestroyInTime { timer: 0.0 })
+            .build();
+        let child = world
+            .create_entity()
+            .with(Parent { entity: parent })
+            .build();
+        let grandchild = world.create_entity().with(Parent { entity: child }).build();
+        let other = world.create_entity().build();
+
+        hierarchy.run_now(&world.res);
+        DestroyInTimeSystem.run_now(&world.res);
+        world.maintain();
+        assert!(!world.is_alive(parent));
+        assert!(!world.is_alive(child));
+        assert!(!world.is_alive(grandchild));
+        assert!(world.is_alive(other));
+    }
+
+    #[test]
+    fn component_is_removed_after_timer() {
+        let mut world = World::new();
+        let mut system = RemoveComponentAfterSystem::<Marker>::new();
+        System::setup(&mut system, &mut world.res);
+        world.write_resource::<Time>().set_delta_seconds(0.5);
+        let entity = world
+            .create_entity()
+            .with(Marker)
+            .with(RemoveComponentAfter::<Marker>::new(1.0))
+            .build();
+
+        for _ in 0..2 {
+            system.run_now(&world.res);
+            assert!(world.read_storage::<Marker>().contains(entity));
+        }
+        system.run_now(&world.res);
+        assert!(!world.read_storage::<Marker>().contains(entity));
+        assert!(!world
+            .read_storage::<RemoveComponentAfter<Marker>>()
+            .contains(entity));
+        assert!(world.is_alive(entity));
+    }
+}
@@ -0,0 +1,465 @@
+//! Lightweight tweening of arbitrary component fields.
+//!
+//! Tweens are meant for small gameplay effects like camera shakes or pickup pops, where loading a
+//! full animation asset would be overkill. A `Tween<C, V>` component interpolates a value of type
+//! `V` over time and writes it into the component `C` on the same entity. Add a
+//! `TweenSystem<C, V>` for every pair of component and value types you want to tween.
+
+use std::marker::PhantomData;
+
+use amethyst_core::{
+    nalgebra::{UnitQuaternion, Vector2, Vector3, Vector4},
+    shrev::EventChannel,
+    specs::{
+        Component, DenseVecStorage, Entities, Entity, Join, Read, System, Write, WriteStorage,
+    },
+    timing::Time,
+};
+use amethyst_renderer::Rgba;
+
+use log::error;
+use serde::{Deserialize, Serialize};
+
+/// A value that can be interpolated by a `Tween`.
+pub trait Tweenable: Clone + Send + Sync + 'static {
+    /// Interpolates between `self` and `to`, `t` being in the range `0.0..=1.0`.
+    ///
+    /// Easing functions may overshoot, so implementations should not clamp `t`.
+    fn interpolate(&self, to: &Self, t: f32) -> Self;
+}
+
+impl Tweenable for f32 {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Tweenable for f64 {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        self + (to - self) * f64::from(t)
+    }
+}
+
+impl Tweenable for Vector2<f32> {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Tweenable for Vector3<f32> {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Tweenable for Vector4<f32> {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Tweenable for UnitQuaternion<f32> {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        self.try_slerp(to, t, 1.0e-6)
+            .unwrap_or_else(|| self.nlerp(to, t))
+    }
+}
+
+impl Tweenable for Rgba {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        Rgba(
+            self.0.interpolate(&to.0, t),
+            self.1.interpolate(&to.1, t),
+            self.2.interpolate(&to.2, t),
+            self.3.interpolate(&to.3, t),
+        )
+    }
+}
+
+/// Easing function applied to the progress of a `Tween`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Easing {
+    /// Constant speed.
+    Linear,
+    /// Quadratic, accelerating from zero velocity.
+    QuadIn,
+    /// Quadratic, decelerating to zero velocity.
+    QuadOut,
+    /// Quadratic, accelerating until halfway, then decelerating.
+    QuadInOut,
+    /// Cubic, accelerating from zero velocity.
+    CubicIn,
+    /// Cubic, decelerating to zero velocity.
+    CubicOut,
+    /// Cubic, accelerating until halfway, then decelerating.
+    CubicInOut,
+    /// Sinusoidal, accelerating from zero velocity.
+    SineIn,
+    /// Sinusoidal, decelerating to zero velocity.
+    SineOut,
+    /// Sinusoidal, accelerating until halfway, then decelerating.
+    SineInOut,
+    /// Overshoots the target slightly before settling, good for "pops".
+    BackOut,
+    /// Bounces against the target like a dropped ball.
+    BounceOut,
+    /// Springs past the target and oscillates before settling.
+    ElasticOut,
+}
+
+impl Default for Easing {
+    fn default() -> Self {
+        Easing::Linear
+    }
+}
+
+impl Easing {
+    /// Maps the linear progress `t` (in `0.0..=1.0`) to the eased progress.
+    pub fn apply(self, t: f32) -> f32 {
+        use std::f32::consts::{FRAC_PI_2, PI};
+
+        let t = t.max(0.0).min(1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => t * (2.0 - t),
+            Easing::QuadInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    -1.0 + (4.0 - 2.0 * t) * t
+                }
+            }
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => {
+                let f = t - 1.0;
+                f * f * f + 1.0
+            }
+            Easing::CubicInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    let f = 2.0 * t - 2.0;
+                    0.5 * f * f * f + 1.0
+                }
+            }
+            Easing::SineIn => 1.0 - (t * FRAC_PI_2).cos(),
+            Easing::SineOut => (t * FRAC_PI_2).sin(),
+            Easing::SineInOut => 0.5 * (1.0 - (PI * t).cos()),
+            Easing::BackOut => {
+                const S: f32 = 1.70158;
+                let f = t - 1.0;
+                f * f * ((S + 1.0) * f + S) + 1.0
+            }
+            Easing::BounceOut => {
+                const N: f32 = 7.5625;
+                const D: f32 = 2.75;
+                if t < 1.0 / D {
+                    N * t * t
+                } else if t < 2.0 / D {
+                    let f = t - 1.5 / D;
+                    N * f * f + 0.75
+                } else if t < 2.5 / D {
+                    let f = t - 2.25 / D;
+                    N * f * f + 0.9375
+                } else {
+                    let f = t - 2.625 / D;
+                    N * f * f + 0.984_375
+                }
+            }
+            Easing::ElasticOut => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else {
+                    2.0_f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * (2.0 * PI / 3.0)).sin() + 1.0
+                }
+            }
+        }
+    }
+}
+
+/// How many times a `Tween` is played.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TweenRepeat {
+    /// Play the tween once.
+    Once,
+    /// Play the tween the given number of times.
+    Times(u32),
+    /// Play the tween until it is removed.
+    Forever,
+}
+
+impl Default for TweenRepeat {
+    fn default() -> Self {
+        TweenRepeat::Once
+    }
+}
+
+/// Events emitted by the `TweenSystem`s.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TweenEvent {
+    /// The tween on the entity finished a single iteration and is going to repeat.
+    Looped(Entity),
+    /// The tween on the entity finished all of its iterations and was removed.
+    Completed(Entity),
+}
+
+/// Interpolates a value of type `V` and writes it into the component `C` of the same entity.
+///
+/// Once all iterations are done, the final value is applied, the `Tween` is removed from the
+/// entity and a `TweenEvent::Completed` is sent.
+pub struct Tween<C, V> {
+    from: V,
+    to: V,
+    duration: f32,
+    delay: f32,
+    easing: Easing,
+    repeat: TweenRepeat,
+    yoyo: bool,
+    elapsed: f32,
+    iteration: u32,
+    apply: fn(&mut C, V),
+}
+
+impl<C, V> Tween<C, V>
+where
+    V: Tweenable,
+{
+    /// Creates a new linear tween going from `from` to `to` in `duration` seconds.
+    ///
+    /// `apply` writes the interpolated value into the component, e.g.
+    /// `|transform: &mut Transform, pos| { transform.set_position(pos); }`.
+    pub fn new(from: V, to: V, duration: f32, apply: fn(&mut C, V)) -> Self {
+        Tween {
+            from,
+            to,
+            duration,
+            delay: 0.0,
+            easing: Easing::Linear,
+            repeat: TweenRepeat::Once,
+            yoyo: false,
+            elapsed: 0.0,
+            iteration: 0,
+            apply,
+        }
+    }
+
+    /// Sets the easing function.
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Waits `delay` seconds before starting the tween.
+    pub fn with_delay(mut self, delay: f32) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Sets how many times the tween is played.
+    pub fn with_repeat(mut self, repeat: TweenRepeat) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    /// When enabled, every second iteration plays backwards, going from `to` to `from`.
+    pub fn with_yoyo(mut self, yoyo: bool) -> Self {
+        self.yoyo = yoyo;
+        self
+    }
+
+    /// Number of fully completed iterations.
+    pub fn iteration(&self) -> u32 {
+        self.iteration
+    }
+
+    /// Returns the current value of the tween.
+    pub fn value(&self) -> V {
+        let t = if self.duration > 0.0 {
+            (self.elapsed / self.duration).min(1.0)
+        } else {
+            1.0
+        };
+        let t = self.easing.apply(t);
+        if self.yoyo && self.iteration % 2 == 1 {
+            self.to.interpolate(&self.from, t)
+        } else {
+            self.from.interpolate(&self.to, t)
+        }
+    }
+
+    fn last_iteration(&self) -> bool {
+        match self.repeat {
+            TweenRepeat::Once => true,
+            TweenRepeat::Times(n) => self.iteration + 1 >= n,
+            TweenRepeat::Forever => false,
+        }
+    }
+
+    /// Advances the tween by `delta` seconds.
+    fn advance(&mut self, mut delta: f32) -> TweenState {
+        if self.delay > 0.0 {
+            self.delay -= delta;
+            if self.delay > 0.0 {
+                return TweenState::Waiting;
+            }
+            delta = -self.delay;
+            self.delay = 0.0;
+        }
+
+        self.elapsed += delta;
+        if self.elapsed < self.duration {
+            return TweenState::Running;
+        }
+        if self.last_iteration() {
+            self.elapsed = self.duration;
+            return TweenState::Completed;
+        }
+        self.elapsed = if self.duration > 0.0 {
+            (self.elapsed - self.duration) % self.duration
+        } else {
+            0.0
+        };
+        self.iteration += 1;
+        TweenState::Looped
+    }
+}
+
+impl<C, V> Component for Tween<C, V>
+where
+    C: Send + Sync + 'static,
+    V: Tweenable,
+{
+    type Storage = DenseVecStorage<Self>;
+}
+
+#[derive(Debug, PartialEq)]
+enum TweenState {
+    Waiting,
+    Running,
+    Looped,
+    Completed,
+}
+
+/// Advances all `Tween<C, V>` components and applies their values to `C`.
+///
+/// Sends `TweenEvent`s to the `EventChannel<TweenEvent>` resource.
+pub struct TweenSystem<C, V> {
+    _marker: PhantomData<(C, V)>,
+}
+
+impl<C, V> TweenSystem<C, V> {
+    /// Creates a new `TweenSystem`.
+    pub fn new() -> Self {
+        TweenSystem {
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<C, V> Default for TweenSystem<C, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, C, V> System<'a> for TweenSystem<C, V>
+where
+    C: Component + Send + Sync,
+    V: Tweenable,
+{
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, Time>,
+        WriteStorage<'a, Tween<C, V>>,
+        WriteStorage<'a, C>,
+        Write<'a, EventChannel<TweenEvent>>,
+    );
+
+    fn run(&mut self, (entities, time, mut tweens, mut components, mut events): Self::SystemData) {
+        let delta = time.delta_seconds();
+        let mut completed = Vec::new();
+
+        for (entity, tween, component) in (&*entities, &mut tweens, &mut components).join() {
+            let state = tween.advance(delta);
+            if state == TweenState::Waiting {
+                continue;
+            }
+            (tween.apply)(component, tween.value());
+            match state {
+                TweenState::Looped => events.single_write(TweenEvent::Looped(entity)),
+                TweenState::Completed => completed.push(entity),
+                _ => {}
+            }
+        }
+
+        for entity in completed {
+            tweens.remove(entity);
+            events.single_write(TweenEvent::Completed(entity));
+        }
+
+        for (entity, _, _) in (&*entities, &tweens, !&components).join() {
+            error!(
+                "Entity {:?} has a `Tween` but not the component it targets",
+                entity
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Value(f32);
+
+    fn set(value: &mut Value, v: f32) {
+        value.0 = v;
+    }
+
+    #[test]
+    fn easing_starts_and_ends_at_bounds() {
+        let easings = [
+            Easing::Linear,
+            Easing::QuadIn,
+            Easing::QuadOut,
+            Easing::QuadInOut,
+            Easing::CubicIn,
+            Easing::CubicOut,
+            Easing::CubicInOut,
+            Easing::SineIn,
+            Easing::SineOut,
+            Easing::SineInOut,
+            Easing::BackOut,
+            Easing::BounceOut,
+            Easing::ElasticOut,
+        ];
+        for easing in &easings {
+            assert!(easing.apply(0.0).abs() < 1.0e-5, "{:?}", easing);
+            assert!((easing.apply(1.0) - 1.0).abs() < 1.0e-5, "{:?}", easing);
+        }
+    }
+
+    #[test]
+    fn tween_waits_for_delay() {
+        let mut tween = Tween::new(0.0, 10.0, 1.0, set).with_delay(0.5);
+        assert_eq!(tween.advance(0.25), TweenState::Waiting);
+        assert_eq!(tween.advance(0.5), TweenState::Running);
+        assert!((tween.value() - 2.5).abs() < 1.0e-5);
+    }
+
+    #[test]
+    fn tween_repeats_with_yoyo() {
+        let mut tween = Tween::new(0.0, 10.0, 1.0, set)
+            .with_repeat(TweenRepeat::Times(2))
+            .with_yoyo(true);
+        assert_eq!(tween.advance(1.25), TweenState::Looped);
+        assert!((tween.value() - 7.5).abs() < 1.0e-5);
+        assert_eq!(tween.advance(1.0), TweenState::Completed);
+        assert!(tween.value().abs() < 1.0e-5);
+
+        let mut value = Value(5.0);
+        (tween.apply)(&mut value, tween.value());
+        assert!(value.0.abs() < 1.0e-5);
+    }
+}
@@ -2,19 +2,14 @@
 
 use amethyst::{
     assets::{PrefabLoader, PrefabLoaderSystem, RonFormat},
-    controls::{ArcBallControlBundle, ArcBallControlTag},
-    core::{
-        shrev::{EventChannel, ReaderId},
-        transform::{Transform, TransformBundle},
-    },
-    ecs::prelude::{Join, Read, ReadStorage, Resources, System, SystemData, WriteStorage},
-    input::{InputBundle, InputEvent, ScrollDirection},
+    controls::ArcBallControlBundle,
+    core::transform::TransformBundle,
+    input::InputBundle,
     prelude::*,
     renderer::{DisplayConfig, DrawShaded, DrawSkybox, Pipeline, PosNormTex, RenderBundle, Stage},
     utils::{application_root_dir, scene::BasicScenePrefab},
     Error,
 };
-use std::hash::Hash;
 
 type MyPrefabData = BasicScenePrefab<Vec<PosNormTex>>;
 
@@ -29,63 +24,6 @@ impl SimpleState for ExampleState {
     }
 }
 
-struct CameraDistanceSystem<AC>
-where
-    AC: Hash + Eq + 'static,
-{
-    event_reader: Option<ReaderId<InputEvent<AC>>>,
-}
-
-impl<AC> CameraDistanceSystem<AC>
-where
-    AC: Hash + Eq + 'static,
-{
-    pub fn new() -> Self {
-        CameraDistanceSystem { event_reader: None }
-    }
-}
-
-impl<'a, AC> System<'a> for CameraDistanceSystem<AC>
-where
-    AC: Hash + Eq + Clone + Send + Sync + 'static,
-{
-    type SystemData = (
-        Read<'a, EventChannel<InputEvent<AC>>>,
-        ReadStorage<'a, Transform>,
-        WriteStorage<'a, ArcBallControlTag>,
-    );
-
-    fn run(&mut self, (events, transforms, mut tags): Self::SystemData) {
-        for event in events.read(&mut self.event_reader.as_mut().unwrap()) {
-            match *event {
-                InputEvent::MouseWheelMoved(direction) => match direction {
-                    ScrollDirection::ScrollUp => {
-                        for (_, tag) in (&transforms, &mut tags).join() {
-                            tag.distance *= 0.9;
-                        }
-                    }
-                    ScrollDirection::ScrollDown => {
-                        for (_, tag) in (&transforms, &mut tags).join() {
-                            tag.distance *= 1.1;
-                        }
-                    }
-                    _ => (),
-                },
-                _ => (),
-            }
-        }
-    }
-
-    fn setup(&mut self, res: &mut Resources) {
-        Self::SystemData::setup(res);
-
-        self.event_reader = Some(
-            res.fetch_mut::<EventChannel<InputEvent<AC>>>()
-                .register_reader(),
-        );
-    }
-}
-
 fn main() -> Result<(), Error> {
     amethyst::start_logger(Default::default());
 
@@ -115,13 +53,14 @@ fn main() -> Result<(), Error> {
         .with_bundle(
             InputBundle::<String, String>::new().with_bindings_from_file(&key_bindings_path)?,
         )?
-        .with_bundle(ArcBallControlBundle::<String, String>::new())?
-        .with_bundle(render_bundle)?
-        .with(
-            CameraDistanceSystem::<String>::new(),
-            "camera_distance_system",
-            &["input_system"],
-        );
+        .with_bundle(
+            ArcBallControlBundle::<String, String>::new()
+                .with_zoom(1.1, 1.0, 50.0)
+                .with_pan(0.002)
+                .with_pitch_limits(-80.0, 80.0)
+                .with_smoothing(0.05),
+        )?
+        .with_bundle(render_bundle)?;
     let mut game = Application::build(resources_directory, ExampleState)?.build(game_data)?;
     game.run();
     Ok(())
@@ -7,7 +7,7 @@ use amethyst::{
         transform::{Transform, TransformBundle},
         Time,
     },
-    ecs::{Read, System, Write},
+    ecs::{Read, ReadExpect, System, Write},
     input::InputBundle,
     prelude::*,
     renderer::*,
@@ -19,9 +19,10 @@ impl<'s> System<'s> for ExampleLinesSystem {
     type SystemData = (
         Write<'s, DebugLines>, // Request DebugLines resource
         Read<'s, Time>,
+        ReadExpect<'s, ScreenDimensions>,
     );
 
-    fn run(&mut self, (mut debug_lines_resource, time): Self::SystemData) {
+    fn run(&mut self, (mut debug_lines_resource, time, screen): Self::SystemData) {
         // Drawing debug lines, as a resource
         let t = (time.absolute_time_seconds() as f32).cos();
 
@@ -36,6 +37,11 @@ impl<'s> System<'s> for ExampleLinesSystem {
             [0.0, 0.0, 0.2].into(),
             [0.5, 0.05, 0.65, 1.0].into(),
         );
+
+        // Drawing a crosshair in the middle of the screen, in pixels
+        let (x, y) = (screen.width() / 2.0, screen.height() / 2.0);
+        debug_lines_resource.draw_screen_line([x - 10.0, y], [x + 10.0, y], Rgba::WHITE);
+        debug_lines_resource.draw_screen_line([x, y - 10.0], [x, y + 10.0], Rgba::WHITE);
     }
 }
 
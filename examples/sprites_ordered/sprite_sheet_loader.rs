@@ -53,7 +53,13 @@ pub fn load(texture: Handle<Texture>, definition: &sprite::SpriteSheetDefinition
         }
     }
 
-    SpriteSheet { texture, sprites }
+    SpriteSheet {
+        texture,
+        sprites,
+        pages: Vec::new(),
+        normal_maps: Vec::new(),
+        sampler: None,
+    }
 }
 
 /// Returns the pixel offset distances per sprite.
@@ -1,11 +1,6 @@
 //! An example showing how to create a dispatcher inside of a State.
 
-use amethyst::{
-    ecs::{Dispatcher, DispatcherBuilder},
-    prelude::*,
-    shrev::EventChannel,
-    Error,
-};
+use amethyst::{prelude::*, shrev::EventChannel, Error};
 
 use std::marker::PhantomData;
 
@@ -30,23 +25,32 @@ impl SimpleState for StateA {
 
 /// StateB isn't Send + Sync
 struct StateB<'a> {
-    dispatcher: Dispatcher<'static, 'static>,
+    dispatcher: StateDispatcher<'static, 'static>,
     _phantom: &'a PhantomData<()>,
 }
 
 impl<'a> Default for StateB<'a> {
     fn default() -> Self {
         StateB {
-            dispatcher: DispatcherBuilder::new().build(),
+            dispatcher: StateDispatcher::default(),
             _phantom: &PhantomData,
         }
     }
 }
 
 impl<'a> SimpleState for StateB<'a> {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        // The systems of the state are set up here, and run on the thread pool of the game.
+        self.dispatcher = StateDispatcherBuilder::new().build(data.world);
+    }
+
+    fn on_stop(&mut self, _: StateData<'_, GameData<'_, '_>>) {
+        self.dispatcher.dispose();
+    }
+
     fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
         println!("StateB::update()");
-        self.dispatcher.dispatch(&mut data.world.res);
+        self.dispatcher.dispatch(data.world);
         Trans::Quit
     }
 }
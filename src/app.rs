@@ -1,10 +1,16 @@
 //! The core engine framework.
 
-use std::{marker::PhantomData, path::Path, sync::Arc, time::Duration};
+use std::{
+    marker::PhantomData,
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
 
 use crate::shred::Resource;
 use derivative::Derivative;
-use log::{info, log_enabled, trace, Level};
+use log::{error, info, log_enabled, trace, Level};
 use rayon::ThreadPoolBuilder;
 use winit::Event;
 
@@ -26,6 +32,8 @@ use crate::{
     },
     error::Error,
     game_data::DataInit,
+    panic_recovery::{PanicRecovery, PanicReport},
+    shutdown::{ShutdownHooks, ShutdownSignal},
     state::{State, StateData, StateMachine, TransEvent},
     state_event::{StateEvent, StateEventReader},
     ui::UiEvent,
@@ -61,6 +69,8 @@ where
     trans_reader_id: ReaderId<TransEvent<T, E>>,
     states: StateMachine<'a, T, E>,
     ignore_window_close: bool,
+    #[derivative(Debug = "ignore")]
+    panic_recovery: Option<PanicRecovery<T, E>>,
     data: T,
 }
 
@@ -291,7 +301,7 @@ where
         for<'b> R: EventReader<'b, Event = E>,
     {
         trace!("Advancing frame (`Application::advance_frame`)");
-        if self.should_close() {
+        if self.should_close() || self.world.read_resource::<ShutdownSignal>().is_requested() {
             let world = &mut self.world;
             let states = &mut self.states;
             states.stop(StateData::new(world, &mut self.data));
@@ -340,23 +350,29 @@ where
                 }
             }
         }
-        {
-            let do_fixed = {
-                let time = self.world.write_resource::<Time>();
-                time.last_fixed_update().elapsed() >= time.fixed_time()
-            };
-            #[cfg(feature = "profiler")]
-            profile_scope!("fixed_update");
-            if do_fixed {
-                self.states
-                    .fixed_update(StateData::new(&mut self.world, &mut self.data));
-                self.world.write_resource::<Time>().finish_fixed_update();
+        if self.panic_recovery.is_some() {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| self.update_states()));
+            if let Err(payload) = result {
+                let state = self
+                    .panic_recovery
+                    .as_mut()
+                    .expect("Unreachable: Checked above")
+                    .recover(payload);
+                let data = StateData::new(&mut self.world, &mut self.data);
+                match state {
+                    Some(state) => {
+                        error!("Recovering from a panic, the frame is abandoned");
+                        self.states.push_recovery(state, data);
+                    }
+                    None => {
+                        error!("Panicked while recovering from a panic, stopping");
+                        self.states.stop(data);
+                    }
+                }
+                return;
             }
-
-            #[cfg(feature = "profiler")]
-            profile_scope!("update");
-            self.states
-                .update(StateData::new(&mut self.world, &mut self.data));
+        } else {
+            self.update_states();
         }
 
         #[cfg(feature = "profiler")]
@@ -369,11 +385,31 @@ where
         self.world.write_resource::<Errors>().print_and_exit();
     }
 
+    /// Runs the fixed updates and updates of the states, which run the systems.
+    fn update_states(&mut self) {
+        let do_fixed = {
+            let time = self.world.write_resource::<Time>();
+            time.last_fixed_update().elapsed() >= time.fixed_time()
+        };
+        #[cfg(feature = "profiler")]
+        profile_scope!("fixed_update");
+        if do_fixed {
+            self.states
+                .fixed_update(StateData::new(&mut self.world, &mut self.data));
+            self.world.write_resource::<Time>().finish_fixed_update();
+        }
+
+        #[cfg(feature = "profiler")]
+        profile_scope!("update");
+        self.states
+            .update(StateData::new(&mut self.world, &mut self.data));
+    }
+
     /// Cleans up after the quit signal is received.
     fn shutdown(&mut self) {
         info!("Engine is shutting down");
 
-        // Placeholder.
+        ShutdownHooks::run(&mut self.world);
     }
 }
 
@@ -399,6 +435,7 @@ pub struct ApplicationBuilder<S, T, E, R> {
     /// Used by bundles to access the world directly
     pub world: World,
     ignore_window_close: bool,
+    panic_recovery: Option<PanicRecovery<T, E>>,
     phantom: PhantomData<(T, E, R)>,
 }
 
@@ -504,6 +541,8 @@ where
         world.add_resource(Stopwatch::default());
         world.add_resource(Time::default());
         world.add_resource(CallbackQueue::default());
+        world.add_resource(ShutdownSignal::default());
+        world.add_resource(ShutdownHooks::default());
 
         world.register::<Named>();
 
@@ -511,6 +550,7 @@ where
             initial_state,
             world,
             ignore_window_close: false,
+            panic_recovery: None,
             phantom: PhantomData,
         })
     }
@@ -783,6 +823,69 @@ where
         self
     }
 
+    /// Catches the panics of the states and systems, and pushes the state created from the panic
+    /// report instead of exiting, to show a crash screen for instance.
+    ///
+    /// The frame during which the panic happened is abandoned. The recovery state can pop itself
+    /// to resume the game, which may be in an inconsistent state, or quit. The application stops
+    /// if it panics again while the recovery state is on the stack.
+    ///
+    /// A panic hook is installed to record the panics, which calls the previous hook.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the ApplicationBuilder after modifying it.
+    pub fn with_panic_recovery<F>(mut self, create_state: F) -> Self
+    where
+        F: FnMut(PanicReport) -> Box<dyn State<T, E>> + 'static,
+    {
+        self.panic_recovery = Some(PanicRecovery::new(create_state));
+        self
+    }
+
+    /// Adds a function run when the application shuts down, see `ShutdownHooks`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the ApplicationBuilder after modifying it.
+    pub fn with_shutdown_hook<N, F>(mut self, name: N, hook: F) -> Self
+    where
+        N: Into<String>,
+        F: FnOnce(&mut World) + Send + Sync + 'static,
+    {
+        self.world.write_resource::<ShutdownHooks>().add(name, hook);
+        self
+    }
+
+    /// Shuts the application down when the process receives Ctrl-C, or a termination signal on
+    /// Unix, by requesting it through the `ShutdownSignal` resource.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a handler for these signals is already set in the process.
+    pub fn with_shutdown_on_signal(self) -> Result<Self, Error> {
+        self.world
+            .read_resource::<ShutdownSignal>()
+            .on_process_signal()?;
+        Ok(self)
+    }
+
+    /// Configures the application for running without a window, like a dedicated server.
+    ///
+    /// The application runs at most `max_fps` frames per second, sleeping between frames, and
+    /// shuts down on process signals. Window close events are ignored. The ECS, assets, network
+    /// and fixed updates work as usual, as long as no bundle opening a window or reading input
+    /// from one, like the `RenderBundle` or the `InputBundle`, is added to the game data.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a handler for process signals is already set in the process.
+    pub fn headless(self, max_fps: u32) -> Result<Self, Error> {
+        self.with_frame_limit(FrameRateLimitStrategy::Sleep, max_fps)
+            .ignore_window_close(true)
+            .with_shutdown_on_signal()
+    }
+
     /// Tells the resulting application window to ignore close events if ignore is true.
     /// This will make your game window unresponsive to operating system close commands.
     /// Use with caution.
@@ -819,13 +922,37 @@ where
     ///
     /// See the [example show for `ApplicationBuilder::new()`](struct.ApplicationBuilder.html#examples)
     /// for an example on how this method is used.
-    pub fn build<'a, I>(mut self, init: I) -> Result<CoreApplication<'a, T, E, X>, Error>
+    pub fn build<'a, I>(self, init: I) -> Result<CoreApplication<'a, T, E, X>, Error>
     where
         S: State<T, E> + 'a,
         I: DataInit<T>,
         E: Clone + Send + Sync + 'static,
         X: Default,
         for<'b> X: EventReader<'b, Event = E>,
+    {
+        self.build_with_event_reader(init, X::default())
+    }
+
+    /// Build an `Application` object using the `ApplicationBuilder` as configured, with the
+    /// given `EventReader` gathering the events sent to the states.
+    ///
+    /// This allows using a reader which can't be created with `Default`, for instance one
+    /// reading custom events from a channel chosen at runtime.
+    ///
+    /// # Errors
+    ///
+    /// This function currently will not produce an error, returning a result
+    /// type was strictly for future possibilities.
+    pub fn build_with_event_reader<'a, I>(
+        mut self,
+        init: I,
+        mut reader: X,
+    ) -> Result<CoreApplication<'a, T, E, X>, Error>
+    where
+        S: State<T, E> + 'a,
+        I: DataInit<T>,
+        E: Clone + Send + Sync + 'static,
+        for<'b> X: EventReader<'b, Event = E>,
     {
         trace!("Entering `ApplicationBuilder::build`");
 
@@ -834,8 +961,10 @@ where
         #[cfg(feature = "profiler")]
         profile_scope!("new");
 
-        let mut reader = X::default();
         reader.setup(&mut self.world.res);
+        if let Some(ref panic_recovery) = self.panic_recovery {
+            panic_recovery.install_hook();
+        }
         let data = init.build(&mut self.world);
         let event_reader_id = self
             .world
@@ -851,9 +980,118 @@ where
             reader,
             events: Vec::new(),
             ignore_window_close: self.ignore_window_close,
+            panic_recovery: self.panic_recovery,
             data,
             event_reader_id,
             trans_reader_id,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+    use crate::{
+        renderer::{Rgba, ScreenTransition, TransitionEffect},
+        state::{EmptyState, EmptyTrans, Trans},
+    };
+
+    /// Panics in its first update, and quits in the next one.
+    struct PanickingState {
+        panicked: bool,
+    }
+
+    impl EmptyState for PanickingState {
+        fn update(&mut self, _: StateData<'_, ()>) -> EmptyTrans {
+            if self.panicked {
+                return Trans::Quit;
+            }
+            self.panicked = true;
+            panic!("Panicking state");
+        }
+    }
+
+    /// Requests a switch delayed by a `ScreenTransition`, panics while it is pending, and quits in
+    /// the next update.
+    struct TransitioningState {
+        updates: u32,
+    }
+
+    impl EmptyState for TransitioningState {
+        fn update(&mut self, _: StateData<'_, ()>) -> EmptyTrans {
+            self.updates += 1;
+            match self.updates {
+                1 => Trans::Switch(Box::new(PanickingState { panicked: true })),
+                2 => panic!("Panicking during a transition"),
+                _ => Trans::Quit,
+            }
+        }
+    }
+
+    /// Records the panic report and pops itself, or panics too.
+    struct RecoveryState {
+        report: PanicReport,
+        reports: Rc<RefCell<Vec<String>>>,
+        panics: bool,
+    }
+
+    impl EmptyState for RecoveryState {
+        fn update(&mut self, _: StateData<'_, ()>) -> EmptyTrans {
+            if self.panics {
+                panic!("Panicking recovery state");
+            }
+            self.reports.borrow_mut().push(self.report.message.clone());
+            Trans::Pop
+        }
+    }
+
+    fn run(recovery_panics: bool) -> Vec<String> {
+        run_state(PanickingState { panicked: false }, recovery_panics, None)
+    }
+
+    fn run_state<S: EmptyState + 'static>(
+        state: S,
+        recovery_panics: bool,
+        transition: Option<ScreenTransition>,
+    ) -> Vec<String> {
+        let reports = Rc::new(RefCell::new(Vec::new()));
+        let recovery_reports = reports.clone();
+        let mut game = Application::build("assets/", state)
+            .unwrap()
+            .with_panic_recovery(move |report| {
+                Box::new(RecoveryState {
+                    report,
+                    reports: recovery_reports.clone(),
+                    panics: recovery_panics,
+                })
+            })
+            .build(())
+            .unwrap();
+        if let Some(transition) = transition {
+            game.world.add_resource(transition);
+        }
+        game.run();
+        assert!(!game.states.is_running());
+        reports.replace(Vec::new())
+    }
+
+    #[test]
+    fn panics_are_recovered() {
+        assert_eq!(run(false), vec!["Panicking state".to_string()]);
+    }
+
+    #[test]
+    fn panics_while_recovering_stop_the_application() {
+        assert!(run(true).is_empty());
+    }
+
+    #[test]
+    fn panics_during_a_screen_transition_are_recovered() {
+        // The switch waits for a long fade, which the recovery state does not wait for.
+        let transition = ScreenTransition::new(TransitionEffect::FadeToColor(Rgba::BLACK), 100.0);
+        let reports = run_state(TransitioningState { updates: 0 }, false, Some(transition));
+        assert_eq!(reports, vec!["Panicking during a transition".to_string()]);
+    }
+}
@@ -2,8 +2,9 @@ use std::path::Path;
 
 use crate::{
     core::{
-        specs::prelude::{Dispatcher, DispatcherBuilder, System, World},
-        ArcThreadPool, SystemBundle,
+        shred::RunNow,
+        specs::prelude::{Dispatcher, DispatcherBuilder, Resources, System, World},
+        ArcThreadPool, GameplayStatus, SystemBundle,
     },
     error::Error,
     renderer::pipe::pass::Pass,
@@ -223,6 +224,27 @@ impl<'a, 'b> GameDataBuilder<'a, 'b> {
         Ok(self)
     }
 
+    /// Add a given ECS bundle whose systems are part of the gameplay, paused while the
+    /// `GameplayStatus` is `Paused`.
+    ///
+    /// The systems of the bundle run in their own dispatcher, as a thread-local system, so after
+    /// all the other systems. For single systems, use `SystemExt::gameplay` instead.
+    ///
+    /// # Errors
+    ///
+    /// See each individual bundle for a description of the errors it could produce.
+    pub fn with_gameplay_bundle<B>(mut self, bundle: B) -> Result<Self, Error>
+    where
+        B: SystemBundle<'a, 'b>,
+        'a: 'b,
+    {
+        let mut builder = DispatcherBuilder::new();
+        bundle.build(&mut builder)?;
+        self.disp_builder
+            .add_thread_local(GameplayDispatcher::Builder(builder));
+        Ok(self)
+    }
+
     /// Create a basic renderer with a single given `Pass`, and optional support for the `DrawUi` pass.
     ///
     /// Will set the clear color to black.
@@ -276,6 +298,41 @@ impl<'a, 'b> DataInit<GameData<'a, 'b>> for GameDataBuilder<'a, 'b> {
     }
 }
 
+/// Dispatcher of a gameplay bundle, built with the thread pool of the application on setup.
+enum GameplayDispatcher<'a, 'b> {
+    Builder(DispatcherBuilder<'a, 'b>),
+    Built(Dispatcher<'a, 'b>),
+    Empty,
+}
+
+impl<'a, 'b, 'c> RunNow<'a> for GameplayDispatcher<'b, 'c> {
+    fn run_now(&mut self, res: &'a Resources) {
+        if *res.fetch::<GameplayStatus>() != GameplayStatus::Running {
+            return;
+        }
+        if let GameplayDispatcher::Built(ref mut dispatcher) = *self {
+            dispatcher.dispatch(res);
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        res.entry::<GameplayStatus>()
+            .or_insert_with(GameplayStatus::default);
+        if let GameplayDispatcher::Builder(builder) =
+            std::mem::replace(self, GameplayDispatcher::Empty)
+        {
+            #[cfg(not(no_threading))]
+            let mut dispatcher = builder
+                .with_pool(res.fetch::<ArcThreadPool>().clone())
+                .build();
+            #[cfg(no_threading)]
+            let mut dispatcher = builder.build();
+            dispatcher.setup(res);
+            *self = GameplayDispatcher::Built(dispatcher);
+        }
+    }
+}
+
 impl DataInit<()> for () {
     fn build(self, _: &mut World) {}
 }
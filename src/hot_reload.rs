@@ -0,0 +1,261 @@
+//! Game logic loaded from a dynamic library, reloaded when the library is rebuilt.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+    time::{Duration, Instant, SystemTime},
+};
+
+use libloading::Library;
+use log::info;
+
+use crate::{
+    core::specs::World,
+    error::{format_err, Error},
+};
+
+/// Name of the function exported by `export_game_logic!`.
+const CREATE_SYMBOL: &[u8] = b"amethyst_create_game_logic\0";
+
+/// Gameplay code living in a `cdylib`, exported with `export_game_logic!` and run by a
+/// `DynamicLogic`.
+///
+/// The library must be built with the same compiler and the same version of Amethyst as the
+/// game, as the logic is called through the Rust ABI.
+pub trait GameLogic {
+    /// Called once the library is loaded, with the state returned by `unload` from the previous
+    /// version of the library if it is a reload.
+    ///
+    /// This is where the logic registers its resources and components, and builds its
+    /// dispatcher.
+    fn load(&mut self, _world: &mut World, _state: Option<Vec<u8>>) {}
+
+    /// Called every time the `DynamicLogic` is updated.
+    fn update(&mut self, world: &mut World);
+
+    /// Called before the library is unloaded, returns the state handed to the next version.
+    ///
+    /// The code of the library is unloaded with it, so the resources and component storages
+    /// whose types are defined in the library must be serialized into the state and removed from
+    /// the world here. Types shared with the rest of the game are best defined in a crate linked
+    /// by both, which keeps them in the world across reloads.
+    fn unload(&mut self, _world: &mut World) -> Vec<u8> {
+        Vec::new()
+    }
+}
+
+/// Exports the `GameLogic` of a `cdylib`, created by the given expression on every load.
+///
+/// ```rust,ignore
+/// amethyst::export_game_logic!(MyLogic::default());
+/// ```
+#[macro_export]
+macro_rules! export_game_logic {
+    ($create:expr) => {
+        #[no_mangle]
+        pub fn amethyst_create_game_logic() -> Box<dyn $crate::GameLogic> {
+            Box::new($create)
+        }
+    };
+}
+
+/// Loads the `GameLogic` of a dynamic library, and reloads it when the library is rebuilt.
+///
+/// The library is copied before being loaded, so the compiler can overwrite it while the game
+/// runs. It is checked for changes at most once per poll interval, half a second by default,
+/// when the logic is updated. Usually owned by a `State`, which updates it in its `update`.
+pub struct DynamicLogic {
+    path: PathBuf,
+    poll_interval: Duration,
+    last_poll: Option<Instant>,
+    loaded: Option<LoadedLibrary>,
+    /// Modification time of the library the last time it failed to load
+    failed: Option<SystemTime>,
+    loads: u32,
+}
+
+struct LoadedLibrary {
+    logic: Box<dyn GameLogic>,
+    library: Library,
+    copy: PathBuf,
+    modified: SystemTime,
+}
+
+impl LoadedLibrary {
+    /// Drops the logic before unloading the code of the library, then removes the copy.
+    fn close(self) {
+        drop(self.logic);
+        drop(self.library);
+        let _ = fs::remove_file(self.copy);
+    }
+}
+
+impl DynamicLogic {
+    /// Creates a `DynamicLogic` for the library at the given path, loaded on the first update.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        DynamicLogic {
+            path: path.into(),
+            poll_interval: Duration::from_millis(500),
+            last_poll: None,
+            loaded: None,
+            failed: None,
+            loads: 0,
+        }
+    }
+
+    /// Sets how often the library is checked for changes.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// The path of the library.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether a version of the library is loaded.
+    pub fn is_loaded(&self) -> bool {
+        self.loaded.is_some()
+    }
+
+    /// The number of times the library was loaded.
+    pub fn loads(&self) -> u32 {
+        self.loads
+    }
+
+    /// Loads the library if it is not loaded, reloads it if it was rebuilt, and updates the
+    /// logic.
+    ///
+    /// If the new version of the library fails to load, the error is returned and the previous
+    /// version keeps running, until the library changes again.
+    pub fn update(&mut self, world: &mut World) -> Result<(), Error> {
+        let result = if self.loaded.is_none() {
+            self.reload(world)
+        } else if self.should_poll() {
+            let modified = fs::metadata(&self.path).and_then(|metadata| metadata.modified());
+            match modified {
+                Ok(modified) if self.is_new(modified) => self.reload(world),
+                _ => Ok(()),
+            }
+        } else {
+            Ok(())
+        };
+        if let Some(ref mut loaded) = self.loaded {
+            loaded.logic.update(world);
+        }
+        result
+    }
+
+    /// Loads the current version of the library, handing it the state of the loaded version.
+    pub fn reload(&mut self, world: &mut World) -> Result<(), Error> {
+        let modified = fs::metadata(&self.path)?.modified()?;
+        let mut new = match self.open(modified) {
+            Ok(new) => new,
+            Err(e) => {
+                self.failed = Some(modified);
+                return Err(e);
+            }
+        };
+        self.failed = None;
+        let state = self.unload(world);
+        new.logic.load(world, state);
+        self.loaded = Some(new);
+        self.loads += 1;
+        info!("Loaded game logic from {}", self.path.display());
+        Ok(())
+    }
+
+    /// Unloads the library, returns the state returned by its logic if it was loaded.
+    pub fn unload(&mut self, world: &mut World) -> Option<Vec<u8>> {
+        self.loaded.take().map(|mut loaded| {
+            let state = loaded.logic.unload(world);
+            loaded.close();
+            state
+        })
+    }
+
+    fn should_poll(&mut self) -> bool {
+        let now = Instant::now();
+        match self.last_poll {
+            Some(last_poll) if now.duration_since(last_poll) < self.poll_interval => false,
+            _ => {
+                self.last_poll = Some(now);
+                true
+            }
+        }
+    }
+
+    fn is_new(&self, modified: SystemTime) -> bool {
+        let loaded = self.loaded.as_ref().map(|loaded| loaded.modified);
+        loaded != Some(modified) && self.failed != Some(modified)
+    }
+
+    fn open(&self, modified: SystemTime) -> Result<LoadedLibrary, Error> {
+        let file_name = self
+            .path
+            .file_name()
+            .ok_or_else(|| format_err!("Invalid library path: {}", self.path.display()))?;
+        let copy = env::temp_dir().join(format!(
+            "amethyst-{}-{}-{}",
+            process::id(),
+            self.loads,
+            file_name.to_string_lossy()
+        ));
+        fs::copy(&self.path, &copy)?;
+
+        let library = match Library::new(&copy) {
+            Ok(library) => library,
+            Err(e) => {
+                let _ = fs::remove_file(&copy);
+                return Err(e.into());
+            }
+        };
+        // The signature is the one generated by `export_game_logic!`, the library being built
+        // with the same compiler is a documented requirement of `GameLogic`.
+        let logic = unsafe {
+            library
+                .get::<fn() -> Box<dyn GameLogic>>(CREATE_SYMBOL)
+                .map(|create| create())
+        };
+        match logic {
+            Ok(logic) => Ok(LoadedLibrary {
+                logic,
+                library,
+                copy,
+                modified,
+            }),
+            Err(e) => {
+                drop(library);
+                let _ = fs::remove_file(&copy);
+                Err(format_err!(
+                    "The library does not export game logic, use `export_game_logic!`: {}",
+                    e
+                ))
+            }
+        }
+    }
+}
+
+impl Drop for DynamicLogic {
+    fn drop(&mut self) {
+        if let Some(loaded) = self.loaded.take() {
+            loaded.close();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_library() {
+        let mut world = World::new();
+        let mut logic = DynamicLogic::new("does/not/exist.so");
+        assert!(logic.update(&mut world).is_err());
+        assert!(!logic.is_loaded());
+        assert_eq!(logic.loads(), 0);
+    }
+}
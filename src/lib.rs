@@ -64,15 +64,25 @@ pub use amethyst_config as config;
 pub use amethyst_controls as controls;
 pub use amethyst_core as core;
 pub use amethyst_derive as derive;
+#[cfg(feature = "editor_sync")]
+pub use amethyst_editor_sync as editor_sync;
 pub use amethyst_error as error;
 #[cfg(feature = "gltf")]
 pub use amethyst_gltf as gltf;
+#[cfg(feature = "imgui")]
+pub use amethyst_imgui as imgui;
 pub use amethyst_input as input;
 #[cfg(feature = "locale")]
 pub use amethyst_locale as locale;
 #[cfg(feature = "network")]
 pub use amethyst_network as network;
+#[cfg(feature = "physics")]
+pub use amethyst_physics as physics;
 pub use amethyst_renderer as renderer;
+#[cfg(feature = "scripting")]
+pub use amethyst_scripting as scripting;
+#[cfg(feature = "tiled")]
+pub use amethyst_tiled as tiled;
 pub use amethyst_ui as ui;
 pub use amethyst_utils as utils;
 pub use winit;
@@ -86,14 +96,24 @@ pub use self::{
     callback_queue::{Callback, CallbackQueue},
     error::Error,
     game_data::{DataInit, GameData, GameDataBuilder},
-    logger::{start_logger, LevelFilter as LogLevelFilter, Logger, LoggerConfig, StdoutLog},
+    loading_state::{LoadingState, SimpleStateBox, LOADING_PROGRESS_ID},
+    logger::{
+        start_logger, LevelFilter as LogLevelFilter, LogBuffer, LogFileRotation, Logger,
+        LoggerConfig, StdoutLog,
+    },
+    panic_recovery::PanicReport,
+    shutdown::{ShutdownHooks, ShutdownSignal},
     state::{
         EmptyState, EmptyTrans, SimpleState, SimpleTrans, State, StateData, StateMachine, Trans,
         TransEvent,
     },
+    state_dispatcher::{StateDispatcher, StateDispatcherBuilder},
     state_event::{StateEvent, StateEventReader},
 };
 
+#[cfg(feature = "hot_reload")]
+pub use self::hot_reload::{DynamicLogic, GameLogic};
+
 /// Convenience alias for use in main functions that uses Amethyst.
 pub type Result<T> = std::result::Result<T, error::Error>;
 
@@ -102,6 +122,12 @@ pub mod prelude;
 mod app;
 mod callback_queue;
 mod game_data;
+#[cfg(feature = "hot_reload")]
+mod hot_reload;
+mod loading_state;
 mod logger;
+mod panic_recovery;
+mod shutdown;
 mod state;
+mod state_dispatcher;
 mod state_event;
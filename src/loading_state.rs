@@ -0,0 +1,242 @@
+//! A state loading assets before switching to the next state.
+
+use log::error;
+
+use crate::{
+    assets::{AssetErrorMeta, Completion, ProgressCounter},
+    core::transform::ParentHierarchy,
+    ecs::prelude::{Entity, World, WriteStorage},
+    state::{SimpleState, SimpleTrans, State, StateData, Trans},
+    ui::{UiCreator, UiFinder, UiText},
+    GameData, StateEvent,
+};
+
+/// A boxed state, as created by the callbacks of a `LoadingState`.
+pub type SimpleStateBox = Box<dyn State<GameData<'static, 'static>, StateEvent>>;
+
+type LoadFn<T> = Box<dyn FnOnce(&mut World, &mut ProgressCounter) -> T>;
+type NextFn<T> = Box<dyn FnOnce(T) -> SimpleStateBox>;
+type ErrorFn = Box<dyn FnOnce(Vec<AssetErrorMeta>) -> SimpleStateBox>;
+
+/// Id of the `UiText` of the loading UI showing the progress.
+pub const LOADING_PROGRESS_ID: &str = "loading_progress";
+
+/// State starting asset loads when it starts, and switching to the next state when they are
+/// complete.
+///
+/// The load callback starts the loads with the given `ProgressCounter`, and returns what the
+/// next state needs, usually the handles of the assets. The next state is then created from it.
+///
+/// If a load fails, the errors are logged and the state switches to the error state if there is
+/// one, quits otherwise.
+///
+/// ~~~no_run
+/// use amethyst::{
+///     assets::{Handle, Loader},
+///     prelude::*,
+///     renderer::{PngFormat, Texture, TextureMetadata},
+///     LoadingState,
+/// };
+///
+/// struct Game {
+///     texture: Handle<Texture>,
+/// }
+///
+/// impl SimpleState for Game {}
+///
+/// let loading = LoadingState::new(
+///     |world, progress| {
+///         let loader = world.read_resource::<Loader>();
+///         loader.load(
+///             "texture/logo.png",
+///             PngFormat,
+///             TextureMetadata::srgb(),
+///             progress,
+///             &world.read_resource(),
+///         )
+///     },
+///     |texture| Box::new(Game { texture }),
+/// )
+/// .with_ui("ui/loading.ron");
+/// ~~~
+pub struct LoadingState<T> {
+    load: Option<LoadFn<T>>,
+    next: Option<NextFn<T>>,
+    on_error: Option<ErrorFn>,
+    ui: Option<String>,
+    ui_entity: Option<Entity>,
+    progress: ProgressCounter,
+    loaded: Option<T>,
+}
+
+impl<T: 'static> LoadingState<T> {
+    /// Creates a loading state starting the loads with `load`, and creating the next state with
+    /// `next` from what `load` returned once they are complete.
+    pub fn new<L, N>(load: L, next: N) -> Self
+    where
+        L: FnOnce(&mut World, &mut ProgressCounter) -> T + 'static,
+        N: FnOnce(T) -> SimpleStateBox + 'static,
+    {
+        LoadingState {
+            load: Some(Box::new(load)),
+            next: Some(Box::new(next)),
+            on_error: None,
+            ui: None,
+            ui_entity: None,
+            progress: ProgressCounter::new(),
+            loaded: None,
+        }
+    }
+
+    /// Switches to the state created from the load errors if a load fails.
+    pub fn with_error_state<F>(mut self, on_error: F) -> Self
+    where
+        F: FnOnce(Vec<AssetErrorMeta>) -> SimpleStateBox + 'static,
+    {
+        self.on_error = Some(Box::new(on_error));
+        self
+    }
+
+    /// Displays the UI prefab at the given path while loading.
+    ///
+    /// The text of the `UiText` with the id `LOADING_PROGRESS_ID` is set to the percentage of
+    /// the assets loaded.
+    pub fn with_ui<N: Into<String>>(mut self, path: N) -> Self {
+        self.ui = Some(path.into());
+        self
+    }
+
+    /// The ratio of the assets loaded, between 0 and 1.
+    pub fn progress(&self) -> f32 {
+        match self.progress.num_assets() {
+            0 => 1.0,
+            num_assets => self.progress.num_finished() as f32 / num_assets as f32,
+        }
+    }
+
+    fn update_ui(&self, world: &World) {
+        if self.ui_entity.is_none() {
+            return;
+        }
+        let text = format!("{:.0}%", self.progress() * 100.0);
+        world.exec(
+            |(finder, mut texts): (UiFinder<'_>, WriteStorage<'_, UiText>)| {
+                if let Some(ui_text) = finder
+                    .find(LOADING_PROGRESS_ID)
+                    .and_then(|entity| texts.get_mut(entity))
+                {
+                    ui_text.text = text;
+                }
+            },
+        );
+    }
+}
+
+impl<T: 'static> SimpleState for LoadingState<T> {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let StateData { world, .. } = data;
+        if let Some(ref path) = self.ui {
+            let path = path.clone();
+            self.ui_entity =
+                Some(world.exec(|mut creator: UiCreator<'_>| creator.create(path, ())));
+        }
+        if let Some(load) = self.load.take() {
+            self.loaded = Some(load(world, &mut self.progress));
+        }
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        if let Some(entity) = self.ui_entity.take() {
+            let world = data.world;
+            let children = world
+                .read_resource::<ParentHierarchy>()
+                .all_children_iter(entity)
+                .collect::<Vec<_>>();
+            let entities = world.entities();
+            for child in children {
+                let _ = entities.delete(child);
+            }
+            let _ = entities.delete(entity);
+        }
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        self.update_ui(data.world);
+        match self.progress.complete() {
+            Completion::Loading => Trans::None,
+            Completion::Complete => match (self.next.take(), self.loaded.take()) {
+                (Some(next), Some(loaded)) => Trans::Switch(next(loaded)),
+                _ => Trans::None,
+            },
+            Completion::Failed => {
+                let errors = self.progress.errors();
+                for meta in &errors {
+                    error!(
+                        "Failed to load {} {:?}: {}",
+                        meta.asset_type_name, meta.asset_name, meta.error
+                    );
+                }
+                match self.on_error.take() {
+                    Some(on_error) => Trans::Switch(on_error(errors)),
+                    None => Trans::Quit,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+    };
+
+    use super::*;
+
+    use crate::{
+        assets::{Progress, Tracker},
+        ecs::prelude::DispatcherBuilder,
+    };
+
+    struct Next;
+
+    impl SimpleState for Next {}
+
+    #[test]
+    fn switches_to_the_next_state_once_loaded() {
+        let tracker = Rc::new(RefCell::new(None));
+        let switched = Rc::new(Cell::new(false));
+        let (load_tracker, next_switched) = (tracker.clone(), switched.clone());
+        let mut state = LoadingState::new(
+            move |_, mut progress: &mut ProgressCounter| {
+                progress.add_assets(1);
+                let tracker: Box<dyn Tracker> = Box::new(progress.create_tracker());
+                *load_tracker.borrow_mut() = Some(tracker);
+            },
+            move |()| -> SimpleStateBox {
+                next_switched.set(true);
+                Box::new(Next)
+            },
+        );
+
+        let mut world = World::new();
+        let mut data = GameData::new(DispatcherBuilder::new().build());
+        SimpleState::on_start(&mut state, StateData::new(&mut world, &mut data));
+        let trans = SimpleState::update(&mut state, &mut StateData::new(&mut world, &mut data));
+        match trans {
+            Trans::None => {}
+            _ => panic!("The state switched before the assets were loaded"),
+        }
+        assert_eq!(state.progress.num_finished(), 0);
+
+        tracker.borrow_mut().take().unwrap().success();
+        assert_eq!(state.progress.num_finished(), 1);
+        let trans = SimpleState::update(&mut state, &mut StateData::new(&mut world, &mut data));
+        match trans {
+            Trans::Switch(_) => {}
+            _ => panic!("The state did not switch once the assets were loaded"),
+        }
+        assert!(switched.get());
+    }
+}
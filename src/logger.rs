@@ -3,7 +3,14 @@ pub use log::LevelFilter;
 use log::debug;
 use serde::{Deserialize, Serialize};
 
-use std::{env, io, path::PathBuf, str::FromStr};
+use std::{
+    collections::VecDeque,
+    env, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
 
 /// An enum that contains options for logging to the terminal.
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
@@ -16,8 +23,41 @@ pub enum StdoutLog {
     Colored,
 }
 
+/// Options for rotating the log file once it grows too large.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct LogFileRotation {
+    /// Size in bytes above which the log file is rotated.
+    pub max_size: u64,
+    /// Number of rotated files kept, named after the log file with a `.1`, `.2`... suffix, the
+    /// most recent first.
+    pub max_files: usize,
+}
+
+impl Default for LogFileRotation {
+    fn default() -> Self {
+        LogFileRotation {
+            max_size: 10 * 1024 * 1024,
+            max_files: 5,
+        }
+    }
+}
+
 /// Logger configuration object.
+///
+/// It can be loaded from a RON file with `amethyst::config::Config::load`, missing fields taking
+/// their default value:
+///
+/// ```ron
+/// (
+///     level_filter: Info,
+///     module_levels: [("gfx_glyph", Error), ("my_game::ai", Debug)],
+///     log_file: Some("game.log"),
+///     log_file_rotation: Some((max_size: 1048576, max_files: 3)),
+///     log_buffer_size: Some(200),
+/// )
+/// ```
 #[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct LoggerConfig {
     /// Determines whether to log to the terminal or not.
     pub stdout: StdoutLog,
@@ -29,6 +69,12 @@ pub struct LoggerConfig {
     pub allow_env_override: bool,
     /// Sets a different level for gfx_device_gl if Some
     pub log_gfx_device_level: Option<LevelFilter>,
+    /// Sets different levels for the given modules, overriding `level_filter`.
+    pub module_levels: Vec<(String, LevelFilter)>,
+    /// If set, rotates the log file once it grows too large.
+    pub log_file_rotation: Option<LogFileRotation>,
+    /// If set, keeps the given number of the most recent log lines in a `LogBuffer`.
+    pub log_buffer_size: Option<usize>,
 }
 
 impl Default for LoggerConfig {
@@ -39,10 +85,140 @@ impl Default for LoggerConfig {
             log_file: None,
             allow_env_override: true,
             log_gfx_device_level: Some(LevelFilter::Warn),
+            module_levels: Vec::new(),
+            log_file_rotation: None,
+            log_buffer_size: None,
+        }
+    }
+}
+
+/// The most recent log lines, kept by the logger when `LoggerConfig::log_buffer_size` is set.
+///
+/// The buffer is shared with the logger, so a clone of it can be added to the world as a resource
+/// to show the log in game, for instance in the developer console.
+#[derive(Clone, Debug)]
+pub struct LogBuffer {
+    lines: Arc<Mutex<VecDeque<String>>>,
+    capacity: usize,
+}
+
+impl LogBuffer {
+    /// Creates an empty buffer keeping at most `capacity` lines.
+    pub fn new(capacity: usize) -> Self {
+        LogBuffer {
+            lines: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    /// Adds a line, forgetting the oldest one if the buffer is full.
+    pub fn push(&self, line: String) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut lines = self.lines.lock().expect("LogBuffer mutex was poisoned");
+        if lines.len() == self.capacity {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    /// The lines in the buffer, the most recent last.
+    pub fn lines(&self) -> Vec<String> {
+        self.lines
+            .lock()
+            .expect("LogBuffer mutex was poisoned")
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Removes all the lines of the buffer.
+    pub fn clear(&self) {
+        self.lines
+            .lock()
+            .expect("LogBuffer mutex was poisoned")
+            .clear();
+    }
+}
+
+/// A log file renamed with a `.1` suffix and replaced by a new one once it grows too large.
+struct RotatingFile {
+    path: PathBuf,
+    rotation: LogFileRotation,
+    file: fs::File,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf, rotation: LogFileRotation) -> io::Result<Self> {
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(RotatingFile {
+            path,
+            rotation,
+            file,
+            size,
+        })
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        path.into()
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.rotation.max_files == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            let oldest = self.rotated_path(self.rotation.max_files);
+            if oldest.exists() {
+                fs::remove_file(&oldest)?;
+            }
+            for index in (1..self.rotation.max_files).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    fs::rename(&from, self.rotated_path(index + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        *self = RotatingFile::open(self.path.clone(), self.rotation)?;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > self.rotation.max_size {
+            self.rotate()?;
         }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
     }
 }
 
+fn open_log_file(path: &Path, rotation: Option<LogFileRotation>) -> io::Result<fern::Output> {
+    Ok(match rotation {
+        Some(rotation) => {
+            let file: Box<dyn Write + Send> =
+                Box::new(RotatingFile::open(path.to_path_buf(), rotation)?);
+            file.into()
+        }
+        None => fern::log_file(path)?.into(),
+    })
+}
+
 /// Allows the creation of a logger with a set of custom configurations. If no custom configuration
 /// is required [`start_logger`] can be used instead.
 ///
@@ -55,6 +231,7 @@ impl Default for LoggerConfig {
 /// ```
 pub struct Logger {
     dispatch: fern::Dispatch,
+    log_buffer: Option<LogBuffer>,
 }
 
 impl Logger {
@@ -67,7 +244,10 @@ impl Logger {
                 message = message,
             ))
         });
-        Logger { dispatch }
+        Logger {
+            dispatch,
+            log_buffer: None,
+        }
     }
 
     /// Create a new Logger from [`LoggerConfig`]
@@ -95,16 +275,52 @@ impl Logger {
                 .level_for("gfx_device_gl", log_gfx_device_level);
         }
 
+        for (module, level) in config.module_levels {
+            logger.dispatch = logger.dispatch.level_for(module, level);
+        }
+
         if let Some(path) = config.log_file {
-            match fern::log_file(path) {
+            match open_log_file(&path, config.log_file_rotation) {
                 Ok(log_file) => logger.dispatch = logger.dispatch.chain(log_file),
                 Err(_) => eprintln!("Unable to access the log file, as such it will not be used"),
             }
         }
 
+        if let Some(size) = config.log_buffer_size {
+            let log_buffer = LogBuffer::new(size);
+            let sink = log_buffer.clone();
+            logger.dispatch = logger.dispatch.chain(fern::Output::call(move |record| {
+                sink.push(record.args().to_string())
+            }));
+            logger.log_buffer = Some(log_buffer);
+        }
+
         logger
     }
 
+    /// The buffer of the most recent log lines, if `LoggerConfig::log_buffer_size` is set.
+    ///
+    /// ```no_run
+    /// # use amethyst::{prelude::*, Logger, LoggerConfig};
+    /// # struct Example;
+    /// # impl SimpleState for Example {}
+    /// let logger = Logger::from_config(LoggerConfig {
+    ///     log_buffer_size: Some(200),
+    ///     ..Default::default()
+    /// });
+    /// let log_buffer = logger.log_buffer().unwrap();
+    /// logger.start();
+    ///
+    /// let game = Application::build("./", Example)
+    ///     .unwrap()
+    ///     .with_resource(log_buffer)
+    ///     .build(GameDataBuilder::default())
+    ///     .unwrap();
+    /// ```
+    pub fn log_buffer(&self) -> Option<LogBuffer> {
+        self.log_buffer.clone()
+    }
+
     /// Set individual log levels for modules.
     pub fn level_for<T: Into<std::borrow::Cow<'static, str>>>(
         mut self,
@@ -195,4 +411,43 @@ mod tests {
 
         assert_eq!(config.stdout, StdoutLog::Plain);
     }
+
+    #[test]
+    fn log_buffer_keeps_recent_lines() {
+        let buffer = LogBuffer::new(2);
+        for line in &["first", "second", "third"] {
+            buffer.push(line.to_string());
+        }
+        assert_eq!(buffer.lines(), vec!["second", "third"]);
+    }
+
+    #[test]
+    fn log_file_rotates() {
+        let dir = env::temp_dir().join("amethyst_log_file_rotates");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("game.log");
+        let rotation = LogFileRotation {
+            max_size: 8,
+            max_files: 2,
+        };
+
+        let mut file = RotatingFile::open(path.clone(), rotation).unwrap();
+        for line in &["first\n", "second\n", "third\n", "fourth\n"] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+        file.flush().unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fourth\n");
+        assert_eq!(
+            fs::read_to_string(dir.join("game.log.1")).unwrap(),
+            "third\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("game.log.2")).unwrap(),
+            "second\n"
+        );
+        assert!(!dir.join("game.log.3").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -0,0 +1,184 @@
+//! Recovering from panics in states and systems.
+
+use std::{
+    any::Any,
+    cell::Cell,
+    fmt::{Display, Formatter, Result as FmtResult},
+    panic::{self, PanicInfo},
+    rc::Rc,
+    sync::{Arc, Mutex},
+};
+
+use crate::state::{State, StateData, Trans};
+
+/// Description of a panic caught by the application, given to the recovery state.
+#[derive(Clone, Debug)]
+pub struct PanicReport {
+    /// The panic message.
+    pub message: String,
+    /// The file and line of the panic, if known.
+    pub location: Option<String>,
+    /// The name of the thread which panicked, if it has one.
+    pub thread: Option<String>,
+}
+
+impl Display for PanicReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.message)?;
+        if let Some(ref location) = self.location {
+            write!(f, " at {}", location)?;
+        }
+        if let Some(ref thread) = self.thread {
+            write!(f, " in thread {:?}", thread)?;
+        }
+        Ok(())
+    }
+}
+
+impl PanicReport {
+    fn from_info(info: &PanicInfo<'_>) -> Self {
+        PanicReport {
+            message: payload_message(info.payload()),
+            location: info
+                .location()
+                .map(|location| format!("{}:{}", location.file(), location.line())),
+            thread: std::thread::current().name().map(str::to_owned),
+        }
+    }
+}
+
+fn payload_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        (*message).to_owned()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<Any>".to_owned()
+    }
+}
+
+type RecoveryFn<T, E> = Box<dyn FnMut(PanicReport) -> Box<dyn State<T, E>>>;
+
+/// Creates the recovery state of the application from the caught panics.
+pub(crate) struct PanicRecovery<T, E> {
+    create_state: RecoveryFn<T, E>,
+    last_report: Arc<Mutex<Option<PanicReport>>>,
+    active: Rc<Cell<bool>>,
+}
+
+impl<T, E> PanicRecovery<T, E> {
+    pub(crate) fn new<F>(create_state: F) -> Self
+    where
+        F: FnMut(PanicReport) -> Box<dyn State<T, E>> + 'static,
+    {
+        PanicRecovery {
+            create_state: Box::new(create_state),
+            last_report: Arc::new(Mutex::new(None)),
+            active: Rc::new(Cell::new(false)),
+        }
+    }
+
+    /// Installs a panic hook recording the panics, which still calls the previous hook.
+    pub(crate) fn install_hook(&self) {
+        let last_report = self.last_report.clone();
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if let Ok(mut last_report) = last_report.lock() {
+                *last_report = Some(PanicReport::from_info(info));
+            }
+            previous(info);
+        }));
+    }
+
+    /// Creates the recovery state for a caught panic, or returns `None` if the recovery state of a
+    /// previous panic is still on the stack.
+    pub(crate) fn recover(&mut self, payload: Box<dyn Any + Send>) -> Option<Box<dyn State<T, E>>>
+    where
+        T: 'static,
+        E: Send + Sync + 'static,
+    {
+        let report = self
+            .last_report
+            .lock()
+            .ok()
+            .and_then(|mut last_report| last_report.take())
+            .unwrap_or_else(|| PanicReport {
+                message: payload_message(&*payload),
+                location: None,
+                thread: None,
+            });
+        if self.active.get() {
+            return None;
+        }
+        Some(Box::new(RecoveryState {
+            state: (self.create_state)(report),
+            active: self.active.clone(),
+        }))
+    }
+}
+
+/// Wraps the recovery state, to know whether it is on the stack.
+struct RecoveryState<T, E> {
+    state: Box<dyn State<T, E>>,
+    active: Rc<Cell<bool>>,
+}
+
+impl<T, E: Send + Sync + 'static> State<T, E> for RecoveryState<T, E> {
+    fn on_start(&mut self, data: StateData<'_, T>) {
+        self.active.set(true);
+        self.state.on_start(data);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, T>) {
+        self.state.on_stop(data);
+        self.active.set(false);
+    }
+
+    fn on_pause(&mut self, data: StateData<'_, T>) {
+        self.state.on_pause(data);
+    }
+
+    fn on_resume(&mut self, data: StateData<'_, T>) {
+        self.state.on_resume(data);
+    }
+
+    fn on_shutdown(&mut self, data: StateData<'_, T>) {
+        self.state.on_shutdown(data);
+    }
+
+    fn handle_event(&mut self, data: StateData<'_, T>, event: E) -> Trans<T, E> {
+        self.state.handle_event(data, event)
+    }
+
+    fn fixed_update(&mut self, data: StateData<'_, T>) -> Trans<T, E> {
+        self.state.fixed_update(data)
+    }
+
+    fn update(&mut self, data: StateData<'_, T>) -> Trans<T, E> {
+        self.state.update(data)
+    }
+
+    fn shadow_fixed_update(&mut self, data: StateData<'_, T>) {
+        self.state.shadow_fixed_update(data);
+    }
+
+    fn shadow_update(&mut self, data: StateData<'_, T>) {
+        self.state.shadow_update(data);
+    }
+
+    fn pauses_gameplay(&self) -> bool {
+        self.state.pauses_gameplay()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payload_messages() {
+        assert_eq!(payload_message(&"static"), "static");
+        assert_eq!(payload_message(&"owned".to_string()), "owned");
+        assert_eq!(payload_message(&3), "Box<Any>");
+    }
+}
@@ -10,5 +10,6 @@ pub use crate::{
     state::{
         EmptyState, EmptyTrans, SimpleState, SimpleTrans, State, StateData, Trans, TransEvent,
     },
+    state_dispatcher::{StateDispatcher, StateDispatcherBuilder},
     state_event::StateEvent,
 };
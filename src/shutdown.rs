@@ -0,0 +1,131 @@
+//! Requesting the application to shut down.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use log::info;
+
+use crate::{ecs::prelude::World, error::Error};
+
+/// Resource requesting the application to shut down.
+///
+/// The application stops all the states at the start of the next frame once shutdown is
+/// requested, like when the window is closed. It can be cloned to request shutdown from other
+/// threads.
+#[derive(Clone, Debug, Default)]
+pub struct ShutdownSignal {
+    requested: Arc<AtomicBool>,
+}
+
+impl ShutdownSignal {
+    /// Requests the application to shut down.
+    pub fn request(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether shutdown was requested.
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    /// Requests shutdown when the process receives Ctrl-C, or a termination signal on Unix.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a handler for these signals is already set in the process.
+    pub fn on_process_signal(&self) -> Result<(), Error> {
+        let signal = self.clone();
+        ctrlc::set_handler(move || signal.request())?;
+        Ok(())
+    }
+}
+
+type Hook = Box<dyn FnOnce(&mut World) + Send + Sync>;
+
+/// Resource holding the functions run when the application shuts down, to flush saves or close
+/// connections for instance.
+///
+/// When the application exits, be it because the states quit, the window was closed or shutdown
+/// was requested, the teardown happens in this order:
+///
+/// 1. The states on the stack are shut down and stopped, from the top.
+/// 2. The hooks are run, the last one added first.
+/// 3. The world and its resources are dropped.
+///
+/// Process signals are not handled by default: Ctrl-C or a termination signal ends the process
+/// without stopping the states nor running the hooks. To run them on these signals, the
+/// application opts in with `ApplicationBuilder::with_shutdown_on_signal`, which `headless` calls,
+/// or with `ShutdownSignal::on_process_signal`.
+///
+/// Bundles can add hooks from the `setup` of their systems.
+#[derive(Default)]
+pub struct ShutdownHooks {
+    hooks: Vec<(String, Hook)>,
+}
+
+impl ShutdownHooks {
+    /// Adds a hook, the name is logged when it runs.
+    pub fn add<N, F>(&mut self, name: N, hook: F)
+    where
+        N: Into<String>,
+        F: FnOnce(&mut World) + Send + Sync + 'static,
+    {
+        self.hooks.push((name.into(), Box::new(hook)));
+    }
+
+    /// The number of hooks.
+    pub fn len(&self) -> usize {
+        self.hooks.len()
+    }
+
+    /// Whether there are no hooks.
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Takes the hooks out of the world, and runs them in reverse order.
+    pub(crate) fn run(world: &mut World) {
+        let hooks = match world.res.try_fetch_mut::<ShutdownHooks>() {
+            Some(mut hooks) => std::mem::replace(&mut hooks.hooks, Vec::new()),
+            None => return,
+        };
+        for (name, hook) in hooks.into_iter().rev() {
+            info!("Running shutdown hook {:?}", name);
+            hook(world);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_share_request() {
+        let signal = ShutdownSignal::default();
+        let clone = signal.clone();
+        assert!(!signal.is_requested());
+        clone.request();
+        assert!(signal.is_requested());
+    }
+
+    #[test]
+    fn hooks_run_in_reverse_order() {
+        let mut world = World::new();
+        world.add_resource(Vec::<u32>::new());
+        let mut hooks = ShutdownHooks::default();
+        hooks.add("first", |world: &mut World| {
+            world.write_resource::<Vec<u32>>().push(1)
+        });
+        hooks.add("second", |world: &mut World| {
+            world.write_resource::<Vec<u32>>().push(2)
+        });
+        world.add_resource(hooks);
+
+        ShutdownHooks::run(&mut world);
+        assert_eq!(*world.read_resource::<Vec<u32>>(), vec![2, 1]);
+        assert!(world.read_resource::<ShutdownHooks>().is_empty());
+    }
+}
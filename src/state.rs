@@ -4,7 +4,12 @@ use amethyst_input::is_close_requested;
 
 use derivative::Derivative;
 
-use crate::{ecs::prelude::World, GameData, StateEvent};
+use crate::{
+    core::{timing::Time, GameplayStatus},
+    ecs::prelude::World,
+    renderer::ScreenTransition,
+    GameData, StateEvent,
+};
 
 use std::fmt::{Display, Formatter, Result as FmtResult};
 
@@ -90,6 +95,10 @@ pub trait State<T, E: Send + Sync + 'static> {
     /// Executed when the application returns to this game state once again.
     fn on_resume(&mut self, _data: StateData<'_, T>) {}
 
+    /// Executed when the application shuts down, for every state on the stack from the top,
+    /// before its `on_stop`.
+    fn on_shutdown(&mut self, _data: StateData<'_, T>) {}
+
     /// Executed on every frame before updating, for use in reacting to events.
     fn handle_event(&mut self, _data: StateData<'_, T>, _event: E) -> Trans<T, E> {
         Trans::None
@@ -118,6 +127,13 @@ pub trait State<T, E: Send + Sync + 'static> {
     /// even when this is not the active state,
     /// as long as this state is on the [StateMachine](struct.StateMachine.html)'s state-stack.
     fn shadow_update(&mut self, _data: StateData<'_, T>) {}
+
+    /// Whether the gameplay systems are paused while this state is on the state stack.
+    ///
+    /// See `SystemExt::gameplay`.
+    fn pauses_gameplay(&self) -> bool {
+        false
+    }
 }
 
 /// An empty `State` trait. It contains no `StateData` or custom `StateEvent`.
@@ -134,6 +150,10 @@ pub trait EmptyState {
     /// Executed when the application returns to this game state once again.
     fn on_resume(&mut self, _data: StateData<'_, ()>) {}
 
+    /// Executed when the application shuts down, for every state on the stack from the top,
+    /// before its `on_stop`.
+    fn on_shutdown(&mut self, _data: StateData<'_, ()>) {}
+
     /// Executed on every frame before updating, for use in reacting to events.
     fn handle_event(&mut self, _data: StateData<'_, ()>, event: StateEvent) -> EmptyTrans {
         if let StateEvent::Window(event) = &event {
@@ -168,6 +188,13 @@ pub trait EmptyState {
     /// even when this is not the active state,
     /// as long as this state is on the [StateMachine](struct.StateMachine.html)'s state-stack.
     fn shadow_update(&mut self, _data: StateData<'_, ()>) {}
+
+    /// Whether the gameplay systems are paused while this state is on the state stack.
+    ///
+    /// See `SystemExt::gameplay`.
+    fn pauses_gameplay(&self) -> bool {
+        false
+    }
 }
 
 impl<T: EmptyState> State<(), StateEvent> for T {
@@ -191,6 +218,12 @@ impl<T: EmptyState> State<(), StateEvent> for T {
         self.on_resume(data)
     }
 
+    /// Executed when the application shuts down, for every state on the stack from the top,
+    /// before its `on_stop`.
+    fn on_shutdown(&mut self, data: StateData<'_, ()>) {
+        self.on_shutdown(data)
+    }
+
     /// Executed on every frame before updating, for use in reacting to events.
     fn handle_event(&mut self, data: StateData<'_, ()>, event: StateEvent) -> EmptyTrans {
         self.handle_event(data, event)
@@ -221,6 +254,11 @@ impl<T: EmptyState> State<(), StateEvent> for T {
     fn shadow_update(&mut self, data: StateData<'_, ()>) {
         self.shadow_update(data);
     }
+
+    /// Whether the gameplay systems are paused while this state is on the state stack.
+    fn pauses_gameplay(&self) -> bool {
+        self.pauses_gameplay()
+    }
 }
 
 /// A simple `State` trait. It contains `GameData` as its `StateData` and no custom `StateEvent`.
@@ -237,6 +275,10 @@ pub trait SimpleState {
     /// Executed when the application returns to this game state once again.
     fn on_resume(&mut self, _data: StateData<'_, GameData<'_, '_>>) {}
 
+    /// Executed when the application shuts down, for every state on the stack from the top,
+    /// before its `on_stop`.
+    fn on_shutdown(&mut self, _data: StateData<'_, GameData<'_, '_>>) {}
+
     /// Executed on every frame before updating, for use in reacting to events.
     fn handle_event(
         &mut self,
@@ -275,6 +317,13 @@ pub trait SimpleState {
     /// even when this is not the active state,
     /// as long as this state is on the [StateMachine](struct.StateMachine.html)'s state-stack.
     fn shadow_update(&mut self, _data: StateData<'_, GameData<'_, '_>>) {}
+
+    /// Whether the gameplay systems are paused while this state is on the state stack.
+    ///
+    /// See `SystemExt::gameplay`.
+    fn pauses_gameplay(&self) -> bool {
+        false
+    }
 }
 
 impl<T: SimpleState> State<GameData<'static, 'static>, StateEvent> for T {
@@ -300,6 +349,12 @@ impl<T: SimpleState> State<GameData<'static, 'static>, StateEvent> for T {
         self.on_resume(data)
     }
 
+    /// Executed when the application shuts down, for every state on the stack from the top,
+    /// before its `on_stop`.
+    fn on_shutdown(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.on_shutdown(data)
+    }
+
     /// Executed on every frame before updating, for use in reacting to events.
     fn handle_event(
         &mut self,
@@ -336,6 +391,11 @@ impl<T: SimpleState> State<GameData<'static, 'static>, StateEvent> for T {
     fn shadow_update(&mut self, data: StateData<'_, GameData<'_, '_>>) {
         self.shadow_update(data);
     }
+
+    /// Whether the gameplay systems are paused while this state is on the state stack.
+    fn pauses_gameplay(&self) -> bool {
+        self.pauses_gameplay()
+    }
 }
 
 /// A simple stack-based state machine (pushdown automaton).
@@ -345,6 +405,9 @@ pub struct StateMachine<'a, T, E> {
     running: bool,
     #[derivative(Debug = "ignore")]
     state_stack: Vec<Box<dyn State<T, E> + 'a>>,
+    /// Transition waiting for the `ScreenTransition` to cover the screen
+    #[derivative(Debug = "ignore")]
+    pending: Option<Trans<T, E>>,
 }
 
 impl<'a, T, E: Send + Sync + 'static> StateMachine<'a, T, E> {
@@ -353,6 +416,7 @@ impl<'a, T, E: Send + Sync + 'static> StateMachine<'a, T, E> {
         StateMachine {
             running: false,
             state_stack: vec![Box::new(initial_state)],
+            pending: None,
         }
     }
 
@@ -364,12 +428,14 @@ impl<'a, T, E: Send + Sync + 'static> StateMachine<'a, T, E> {
     /// Initializes the state machine.
     pub fn start(&mut self, data: StateData<'_, T>) -> Result<(), StateError> {
         if !self.running {
+            let StateData { world, data } = data;
             let state = self
                 .state_stack
                 .last_mut()
                 .ok_or(StateError::NoStatesPresent)?;
-            state.on_start(data);
+            state.on_start(StateData { world, data });
             self.running = true;
+            self.update_gameplay_status(world);
         }
         Ok(())
     }
@@ -407,6 +473,11 @@ impl<'a, T, E: Send + Sync + 'static> StateMachine<'a, T, E> {
     pub fn update(&mut self, data: StateData<'_, T>) {
         let StateData { world, data } = data;
         if self.running {
+            self.update_screen_transition(StateData { world, data });
+            if !self.running {
+                return;
+            }
+
             let trans = match self.state_stack.last_mut() {
                 Some(state) => state.update(StateData { world, data }),
                 None => Trans::None,
@@ -424,18 +495,95 @@ impl<'a, T, E: Send + Sync + 'static> StateMachine<'a, T, E> {
     /// This method can also be called when there are one or multiple `Trans` stored in the
     /// global `EventChannel<TransEvent<T, E>>`. Such `Trans` will be passed to this method
     /// sequentially in the order of insertion.
+    ///
+    /// If the `ScreenTransition` resource has an effect, pushes, pops and switches are delayed
+    /// until the effect covers the screen, and other requests are ignored until then.
     pub fn transition(&mut self, request: Trans<T, E>, data: StateData<'_, T>) {
         if self.running {
             match request {
                 Trans::None => (),
-                Trans::Pop => self.pop(data),
-                Trans::Push(state) => self.push(state, data),
-                Trans::Switch(state) => self.switch(state, data),
-                Trans::Quit => self.stop(data),
+                Trans::Quit => {
+                    self.pending = None;
+                    self.stop(data);
+                }
+                _ if self.pending.is_some() => (),
+                request => {
+                    let started = data
+                        .world
+                        .res
+                        .try_fetch_mut::<ScreenTransition>()
+                        .map_or(false, |mut transition| transition.start());
+                    if started {
+                        self.pending = Some(request);
+                    } else {
+                        self.apply(request, data);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Pushes the state recovering from a panic right away.
+    ///
+    /// The pending transition, if any, is dropped and the screen covered for it is revealed, so
+    /// the recovery state is shown even when the panic happened during a `ScreenTransition`.
+    pub(crate) fn push_recovery(&mut self, state: Box<dyn State<T, E>>, data: StateData<'_, T>) {
+        let StateData { world, data } = data;
+        if self.pending.take().is_some() {
+            if let Some(mut transition) = world.res.try_fetch_mut::<ScreenTransition>() {
+                transition.reveal();
+            }
+        }
+        self.apply(Trans::Push(state), StateData { world, data });
+    }
+
+    /// Advances the `ScreenTransition`, and performs the pending transition once the screen is
+    /// covered.
+    fn update_screen_transition(&mut self, data: StateData<'_, T>) {
+        let StateData { world, data } = data;
+        let delta = world
+            .res
+            .try_fetch::<Time>()
+            .map_or(0.0, |time| time.delta_real_seconds());
+        let covered = match world.res.try_fetch_mut::<ScreenTransition>() {
+            Some(mut transition) => {
+                transition.advance(delta);
+                transition.is_covered()
+            }
+            None => true,
+        };
+        if covered {
+            if let Some(request) = self.pending.take() {
+                self.apply(request, StateData { world, data });
+                if let Some(mut transition) = world.res.try_fetch_mut::<ScreenTransition>() {
+                    transition.reveal();
+                }
             }
         }
     }
 
+    fn apply(&mut self, request: Trans<T, E>, data: StateData<'_, T>) {
+        let StateData { world, data } = data;
+        match request {
+            Trans::None => (),
+            Trans::Pop => self.pop(StateData { world, data }),
+            Trans::Push(state) => self.push(state, StateData { world, data }),
+            Trans::Switch(state) => self.switch(state, StateData { world, data }),
+            Trans::Quit => self.stop(StateData { world, data }),
+        }
+        self.update_gameplay_status(world);
+    }
+
+    /// Pauses the gameplay systems if a state on the stack pauses them, resumes them otherwise.
+    fn update_gameplay_status(&self, world: &mut World) {
+        let status = if self.state_stack.iter().any(|state| state.pauses_gameplay()) {
+            GameplayStatus::Paused
+        } else {
+            GameplayStatus::Running
+        };
+        world.add_resource(status);
+    }
+
     /// Removes the current state on the stack and inserts a different one.
     fn switch(&mut self, state: Box<dyn State<T, E>>, data: StateData<'_, T>) {
         if self.running {
@@ -490,6 +638,7 @@ impl<'a, T, E: Send + Sync + 'static> StateMachine<'a, T, E> {
         if self.running {
             let StateData { world, data } = data;
             while let Some(mut state) = self.state_stack.pop() {
+                state.on_shutdown(StateData { world, data });
                 state.on_stop(StateData { world, data });
             }
 
@@ -540,4 +689,75 @@ mod tests {
         sm.update(StateData::new(&mut world, &mut ()));
         assert!(!sm.is_running());
     }
+
+    struct Paused;
+
+    impl State<(), ()> for Paused {
+        fn update(&mut self, _: StateData<'_, ()>) -> Trans<(), ()> {
+            Trans::Pop
+        }
+
+        fn pauses_gameplay(&self) -> bool {
+            true
+        }
+    }
+
+    struct Playing;
+
+    impl State<(), ()> for Playing {
+        fn update(&mut self, _: StateData<'_, ()>) -> Trans<(), ()> {
+            Trans::Push(Box::new(Paused))
+        }
+    }
+
+    #[test]
+    fn pausing_state_pauses_gameplay() {
+        let mut world = World::new();
+
+        let mut sm = StateMachine::new(Playing);
+        sm.start(StateData::new(&mut world, &mut ())).unwrap();
+        assert_eq!(
+            *world.read_resource::<GameplayStatus>(),
+            GameplayStatus::Running
+        );
+
+        sm.update(StateData::new(&mut world, &mut ()));
+        assert_eq!(
+            *world.read_resource::<GameplayStatus>(),
+            GameplayStatus::Paused
+        );
+
+        sm.update(StateData::new(&mut world, &mut ()));
+        assert_eq!(
+            *world.read_resource::<GameplayStatus>(),
+            GameplayStatus::Running
+        );
+    }
+
+    #[test]
+    fn screen_transition_delays_switch() {
+        use crate::{
+            ecs::prelude::World,
+            renderer::{Rgba, TransitionEffect},
+        };
+
+        let mut world = World::new();
+        world.add_resource(ScreenTransition::new(
+            TransitionEffect::FadeToColor(Rgba::BLACK),
+            0.0,
+        ));
+
+        let mut sm = StateMachine::new(State1(0));
+        sm.start(StateData::new(&mut world, &mut ())).unwrap();
+
+        // The switch is requested, and waits for the fade.
+        sm.update(StateData::new(&mut world, &mut ()));
+        assert!(sm.pending.is_some());
+        assert!(world.read_resource::<ScreenTransition>().is_active());
+
+        // The screen is covered, `State2` starts and requests a pop.
+        sm.update(StateData::new(&mut world, &mut ()));
+        assert!(sm.is_running());
+        assert!(sm.pending.is_some());
+    }
 }
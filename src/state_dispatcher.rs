@@ -0,0 +1,190 @@
+//! Dispatchers owned by a `State`, running systems only while the state is active.
+
+use crate::{
+    core::{
+        specs::prelude::{Dispatcher, DispatcherBuilder, System, World},
+        ArcThreadPool, SystemBundle,
+    },
+    error::Error,
+};
+
+/// Systems of a `State`, built in its `on_start` by a `StateDispatcherBuilder`.
+///
+/// The state runs the systems in its `update` with `dispatch`, and drops them in its `on_stop`
+/// with `dispose`. An empty `StateDispatcher`, as created by `Default`, runs nothing.
+///
+/// ~~~no_run
+/// use amethyst::{prelude::*, StateDispatcher, StateDispatcherBuilder};
+///
+/// #[derive(Default)]
+/// struct Menu {
+///     dispatcher: StateDispatcher<'static, 'static>,
+/// }
+///
+/// impl SimpleState for Menu {
+///     fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+///         self.dispatcher = StateDispatcherBuilder::new()
+///             // .with(MenuSystem, "menu", &[])
+///             .build(data.world);
+///     }
+///
+///     fn on_stop(&mut self, _: StateData<'_, GameData<'_, '_>>) {
+///         self.dispatcher.dispose();
+///     }
+///
+///     fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+///         self.dispatcher.dispatch(data.world);
+///         Trans::None
+///     }
+/// }
+/// ~~~
+#[derive(Default)]
+pub struct StateDispatcher<'a, 'b> {
+    dispatcher: Option<Dispatcher<'a, 'b>>,
+}
+
+impl<'a, 'b> StateDispatcher<'a, 'b> {
+    /// Whether the dispatcher has systems to run, `false` once disposed.
+    pub fn is_built(&self) -> bool {
+        self.dispatcher.is_some()
+    }
+
+    /// Runs the systems.
+    pub fn dispatch(&mut self, world: &World) {
+        if let Some(ref mut dispatcher) = self.dispatcher {
+            dispatcher.dispatch(&world.res);
+        }
+    }
+
+    /// Drops the systems, the dispatcher runs nothing afterwards.
+    pub fn dispose(&mut self) {
+        self.dispatcher = None;
+    }
+}
+
+/// Builder for a `StateDispatcher`, with the same methods as the `GameDataBuilder`.
+///
+/// The built dispatcher runs its systems on the thread pool of the application.
+pub struct StateDispatcherBuilder<'a, 'b> {
+    disp_builder: DispatcherBuilder<'a, 'b>,
+}
+
+impl<'a, 'b> Default for StateDispatcherBuilder<'a, 'b> {
+    fn default() -> Self {
+        StateDispatcherBuilder::new()
+    }
+}
+
+impl<'a, 'b> StateDispatcherBuilder<'a, 'b> {
+    /// Create new builder
+    pub fn new() -> Self {
+        StateDispatcherBuilder {
+            disp_builder: DispatcherBuilder::new(),
+        }
+    }
+
+    /// Inserts a barrier which assures that all systems added before the
+    /// barrier are executed before the ones after this barrier.
+    ///
+    /// See `GameDataBuilder::with_barrier`.
+    pub fn with_barrier(mut self) -> Self {
+        self.disp_builder.add_barrier();
+        self
+    }
+
+    /// Adds a given system.
+    ///
+    /// See `GameDataBuilder::with`.
+    ///
+    /// # Panics
+    ///
+    /// If two system are added that share an identical name, or if a dependency has not
+    /// previously been added.
+    pub fn with<S>(mut self, system: S, name: &str, dependencies: &[&str]) -> Self
+    where
+        for<'c> S: System<'c> + Send + 'a,
+    {
+        self.disp_builder.add(system, name, dependencies);
+        self
+    }
+
+    /// Add a given thread-local system.
+    ///
+    /// See `GameDataBuilder::with_thread_local`.
+    pub fn with_thread_local<S>(mut self, system: S) -> Self
+    where
+        for<'c> S: System<'c> + 'b,
+    {
+        self.disp_builder.add_thread_local(system);
+        self
+    }
+
+    /// Add a given ECS bundle to the dispatcher.
+    ///
+    /// # Errors
+    ///
+    /// See each individual bundle for a description of the errors it could produce.
+    pub fn with_bundle<B>(mut self, bundle: B) -> Result<Self, Error>
+    where
+        B: SystemBundle<'a, 'b>,
+    {
+        bundle.build(&mut self.disp_builder)?;
+        Ok(self)
+    }
+
+    /// Builds the dispatcher, and sets up the resources and storages used by its systems.
+    pub fn build(self, world: &mut World) -> StateDispatcher<'a, 'b> {
+        #[cfg(not(no_threading))]
+        let pool = world.read_resource::<ArcThreadPool>().clone();
+
+        #[cfg(not(no_threading))]
+        let mut dispatcher = self.disp_builder.with_pool(pool).build();
+        #[cfg(no_threading)]
+        let mut dispatcher = self.disp_builder.build();
+        dispatcher.setup(&mut world.res);
+        StateDispatcher {
+            dispatcher: Some(dispatcher),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rayon::ThreadPoolBuilder;
+
+    use super::*;
+    use crate::ecs::prelude::Write;
+
+    #[derive(Default)]
+    struct Runs(u32);
+
+    struct CountSystem;
+
+    impl<'a> System<'a> for CountSystem {
+        type SystemData = Write<'a, Runs>;
+
+        fn run(&mut self, mut runs: Self::SystemData) {
+            runs.0 += 1;
+        }
+    }
+
+    #[test]
+    fn dispatch_until_disposed() {
+        let mut world = World::new();
+        let pool: ArcThreadPool = Arc::new(ThreadPoolBuilder::new().build().unwrap());
+        world.add_resource(pool);
+
+        let mut dispatcher = StateDispatcherBuilder::new()
+            .with(CountSystem, "count", &[])
+            .build(&mut world);
+        dispatcher.dispatch(&world);
+        assert_eq!(world.read_resource::<Runs>().0, 1);
+
+        dispatcher.dispose();
+        assert!(!dispatcher.is_built());
+        dispatcher.dispatch(&world);
+        assert_eq!(world.read_resource::<Runs>().0, 1);
+    }
+}